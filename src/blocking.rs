@@ -0,0 +1,234 @@
+use std::{fmt::Debug, future::Future, path::Path, sync::Arc};
+
+use chrono::Duration;
+use tokio::runtime::Runtime;
+
+use crate::{
+    Error, FeathrProject, JobBatchSubmission, JobId, JobResult, JobStatus, JobStatusInfo, JobSummary, OutputSchema,
+    SubmitJobRequest,
+};
+
+/**
+ * Blocking wrapper of the async `FeathrClient`, backed by a small tokio runtime owned by the client.
+ * The methods must not be called from within an async context, and the client must not be dropped there.
+ * Operations without a blocking wrapper, e.g. building features in a project, can be run with `block_on`.
+ */
+#[derive(Clone)]
+pub struct FeathrClient {
+    inner: crate::FeathrClient,
+    runtime: Arc<Runtime>,
+}
+
+impl Debug for FeathrClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FeathrClient")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+fn new_runtime() -> Result<Runtime, Error> {
+    Ok(tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("feathr-blocking")
+        .enable_all()
+        .build()?)
+}
+
+impl FeathrClient {
+    pub fn load<T>(conf_file: T) -> Result<Self, Error>
+    where
+        T: AsRef<Path>,
+    {
+        let runtime = new_runtime()?;
+        let inner = runtime.block_on(crate::FeathrClient::load(conf_file))?;
+        Ok(Self::from_parts(inner, runtime))
+    }
+
+    /**
+     * Load config file with the named profile, settings under `profiles.<profile>` override the top level ones
+     */
+    pub fn load_with_profile<T>(conf_file: T, profile: &str) -> Result<Self, Error>
+    where
+        T: AsRef<Path>,
+    {
+        let runtime = new_runtime()?;
+        let inner = runtime.block_on(crate::FeathrClient::load_with_profile(conf_file, profile))?;
+        Ok(Self::from_parts(inner, runtime))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(content: &str) -> Result<Self, Error> {
+        let runtime = new_runtime()?;
+        let inner = runtime.block_on(crate::FeathrClient::from_str(content))?;
+        Ok(Self::from_parts(inner, runtime))
+    }
+
+    fn from_parts(inner: crate::FeathrClient, runtime: Runtime) -> Self {
+        Self {
+            inner,
+            runtime: Arc::new(runtime),
+        }
+    }
+
+    /**
+     * The async client, its futures can be run with `block_on`
+     */
+    pub fn as_async(&self) -> &crate::FeathrClient {
+        &self.inner
+    }
+
+    /**
+     * Run the future to completion on the runtime of this client
+     */
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    pub fn load_project(&self, name: &str) -> Result<FeathrProject, Error> {
+        self.block_on(self.inner.load_project(name))
+    }
+
+    pub fn new_project(&self, name: &str) -> Result<FeathrProject, Error> {
+        self.block_on(self.inner.new_project(name))
+    }
+
+    pub fn submit_job(&self, request: SubmitJobRequest) -> Result<JobId, Error> {
+        self.block_on(self.inner.submit_job(request))
+    }
+
+    pub fn submit_jobs(&self, requests: Vec<SubmitJobRequest>) -> Result<Vec<JobId>, Error> {
+        self.block_on(self.inner.submit_jobs(requests))
+    }
+
+    pub fn submit_job_batch(&self, requests: Vec<SubmitJobRequest>) -> Result<JobBatchSubmission, Error> {
+        self.block_on(self.inner.submit_job_batch(requests))
+    }
+
+    pub fn schedule_job(&self, request: SubmitJobRequest) -> Result<u64, Error> {
+        self.block_on(self.inner.schedule_job(request))
+    }
+
+    pub fn wait_for_job(&self, job_id: JobId, timeout: Option<Duration>) -> Result<String, Error> {
+        self.block_on(self.inner.wait_for_job(job_id, timeout))
+    }
+
+    /**
+     * Wait for jobs and return their outcomes in the same order,
+     * returns `Error::JobsFailed` if `raise_on_failure` is set and any of the jobs didn't succeed
+     */
+    pub fn wait_for_job_results(
+        &self,
+        job_ids: Vec<JobId>,
+        timeout: Option<Duration>,
+        raise_on_failure: bool,
+    ) -> Result<Vec<JobResult>, Error> {
+        self.block_on(
+            self.inner
+                .wait_for_job_results(job_ids, timeout, raise_on_failure),
+        )
+    }
+
+    /**
+     * Same as `wait_for_job_results`, and the output schema of succeeded jobs is read into `JobResult::schema`
+     */
+    pub fn wait_for_job_results_with_schema(
+        &self,
+        job_ids: Vec<JobId>,
+        timeout: Option<Duration>,
+        raise_on_failure: bool,
+    ) -> Result<Vec<JobResult>, Error> {
+        self.block_on(
+            self.inner
+                .wait_for_job_results_with_schema(job_ids, timeout, raise_on_failure),
+        )
+    }
+
+    pub fn get_remote_url(&self, path: &str) -> String {
+        self.inner.get_remote_url(path)
+    }
+
+    pub fn get_job_status(&self, job_id: JobId) -> Result<JobStatus, Error> {
+        self.block_on(self.inner.get_job_status(job_id))
+    }
+
+    pub fn get_job_status_info(&self, job_id: JobId) -> Result<JobStatusInfo, Error> {
+        self.block_on(self.inner.get_job_status_info(job_id))
+    }
+
+    pub fn cancel_job(&self, job_id: JobId) -> Result<(), Error> {
+        self.block_on(self.inner.cancel_job(job_id))
+    }
+
+    pub fn list_jobs(&self, limit: usize) -> Result<Vec<JobSummary>, Error> {
+        self.block_on(self.inner.list_jobs(limit))
+    }
+
+    pub fn get_job_output_url(&self, job_id: JobId) -> Result<Option<String>, Error> {
+        self.block_on(self.inner.get_job_output_url(job_id))
+    }
+
+    pub fn get_job_output_schema(&self, job_id: JobId) -> Result<Option<OutputSchema>, Error> {
+        self.block_on(self.inner.get_job_output_schema(job_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FeatureQuery, FeatureType, ObservationSettings, TypedKey, ValueType};
+
+    use super::FeathrClient;
+
+    const LIVY_CONFIG: &str = r#"
+spark_config:
+  spark_cluster: 'livy'
+  livy:
+    url: 'http://livy:8998'
+    webhdfs_url: 'http://namenode:9870'
+    workspace_dir: 'hdfs://namenode:8020/feathr'
+"#;
+
+    #[test]
+    fn blocking_client() {
+        let client = FeathrClient::from_str(LIVY_CONFIG).unwrap();
+        assert_eq!(
+            client.get_remote_url("job.conf"),
+            "hdfs://namenode:8020/feathr/job.conf"
+        );
+        let proj = client.new_project("p1").unwrap();
+        let request = client.block_on(async {
+            let source = proj
+                .hdfs_source("s", "hdfs://namenode:8020/data/s.csv")
+                .build()
+                .await
+                .unwrap();
+            let key = TypedKey::new("k", ValueType::INT32);
+            let f = proj
+                .anchor_group("g", source)
+                .build()
+                .await
+                .unwrap()
+                .anchor("f", FeatureType::INT32)
+                .unwrap()
+                .transform("x")
+                .keys(&[&key])
+                .build()
+                .await
+                .unwrap();
+            proj.feature_join_job(
+                ObservationSettings::from_path("hdfs://namenode:8020/obs.csv"),
+                &[&FeatureQuery::new(&[&f], &[&key])],
+                "hdfs://namenode:8020/output/p1.avro",
+            )
+            .await
+            .unwrap()
+            .output_path("hdfs://namenode:8020/output/p1.avro")
+            .build()
+        });
+        assert!(request.feature_config.contains("/data/s.csv"));
+        let cloned = client.clone();
+        std::thread::spawn(move || cloned.get_remote_url("a"))
+            .join()
+            .unwrap();
+    }
+}
@@ -1,16 +1,38 @@
-use std::{path::Path, sync::Arc, collections::HashMap};
+use std::{io::Read, path::Path, sync::Arc, collections::{HashMap, HashSet}};
 
-use chrono::Duration;
+use chrono::{DateTime, Duration, Utc};
 use futures::future::join_all;
 use log::{debug, warn};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::{
-    job_client, load_var_source, new_var_source, Error, FeathrApiClient, FeathrProject, JobClient,
-    JobId, JobStatus, SubmitJobRequest, VarSource, FeatureRegistry, registry_client::api_models, project::FeathrProjectImpl,
+    job_client, load_var_source, load_var_source_with_profile, new_var_source, registry_client, Error, FeathrProject, JobClient,
+    JobId, JobStatus, SubmitJobRequest, VarSource, FeatureRegistry, RegistryFeature, FeatureLineage, ProjectSaveSummary, registry_client::api_models, project::FeathrProjectImpl, OnlineClient, OnlineStoreAdmin, ConfigJobOptions, JobResult, JobStatusInfo, JobSummary, DatasetRegistration, JobHandle, JobManifest,
+    job_client::{check_compatibility, check_cron_expr, dedup_gen_requests, detect_incompatibility, is_data_file, parse_statistics, pick_data_file, read_output_schema, profile_job_request, timing_stats, with_manifest, with_workspace_dir}, DateTimeResolution, FeatureStatistics, ProfileOptions,
+    HdfsSink, JobBatchSubmission, SubmissionTimingStats, MaterializationSettingsBuilder, OutputSchema, OutputSink, LocalSample, ObservationSettings, JobCostEstimate, JobCostSummary, PriceTable,
 };
 
+// Materialized offline features are stored in Avro format
+const PROFILE_INPUT_FORMAT: &str = "avro";
+
+// Depth of sub directories searched for a data file in the partitioned output
+const OUTPUT_SCHEMA_MAX_DEPTH: usize = 4;
+
+// Number of lines sampled from the observation data to detect the timestamp column
+const OBSERVATION_SAMPLE_LINES: usize = 1000;
+
+// Only the head of the observation data is read for sampling
+const OBSERVATION_SAMPLE_BYTES: u64 = 1024 * 1024;
+
+// Number of recent jobs checked for generation jobs still running, or for the job of a retried submission
+const RUNNING_JOBS_SCAN_LIMIT: usize = 100;
+
+/**
+ * Feathr client, cheap to clone and safe to share across threads.
+ * Jobs of different projects can be submitted concurrently from multiple tasks, states kept by the client
+ * are keyed by job id, and per-request settings such as `workspace_dir` only apply to the submitting task.
+ */
 #[derive(Clone, Debug)]
 pub struct FeathrClient {
     pub(crate) inner: Arc<FeathrClientImpl>,
@@ -26,6 +48,20 @@ impl FeathrClient {
         })
     }
 
+    /**
+     * Load config file with the named profile, settings under `profiles.<profile>` override the top level ones
+     */
+    pub async fn load_with_profile<T>(conf_file: T, profile: &str) -> Result<Self, Error>
+    where
+        T: AsRef<Path>,
+    {
+        FeathrClientImpl::from_var_source(load_var_source_with_profile(conf_file, Some(profile)))
+            .await
+            .map(|inner| Self {
+                inner: Arc::new(inner),
+            })
+    }
+
     pub async fn from_str(content: &str) -> Result<Self, Error> {
         FeathrClientImpl::from_str(content).await.map(|inner| Self {
             inner: Arc::new(inner),
@@ -46,6 +82,111 @@ impl FeathrClient {
         }
     }
 
+    /**
+     * Register the entities of the project that are missing in the registry or different from the registered ones,
+     * unchanged entities are skipped. Use this to save a detached project, e.g. one built locally, into the registry
+     */
+    pub async fn save_project_delta(&self, project: &FeathrProject) -> Result<ProjectSaveSummary, Error> {
+        if let Some(r) = self.inner.get_registry_client() {
+            let project = project.inner.read().await;
+            registry_client::save_project_delta(r.as_ref(), &project).await
+        } else {
+            Err(Error::DetachedClient)
+        }
+    }
+
+    /**
+     * Names of all projects in the registry
+     */
+    pub async fn list_projects(&self) -> Result<Vec<String>, Error> {
+        if let Some(r) = self.inner.get_registry_client() {
+            r.list_projects().await
+        } else {
+            Err(Error::DetachedClient)
+        }
+    }
+
+    /**
+     * Search features in all projects of the registry by keyword, only features having all the `tags` are returned
+     */
+    pub async fn search_features(
+        &self,
+        query: &str,
+        tags: &HashMap<String, String>,
+    ) -> Result<Vec<RegistryFeature>, Error> {
+        if let Some(r) = self.inner.get_registry_client() {
+            r.search_features(query, tags).await
+        } else {
+            Err(Error::DetachedClient)
+        }
+    }
+
+    /**
+     * Where the feature in the project comes from, and which derived features depend on it
+     */
+    pub async fn get_feature_lineage(&self, project: &str, feature_name: &str) -> Result<FeatureLineage, Error> {
+        if let Some(r) = self.inner.get_registry_client() {
+            r.get_lineage(project, feature_name).await
+        } else {
+            Err(Error::DetachedClient)
+        }
+    }
+
+    /**
+     * Delete the feature from the registry by its qualified name, features used by other derived features are only deleted with `force`
+     */
+    pub async fn delete_feature(&self, qualified_name: &str, force: bool) -> Result<(), Error> {
+        if let Some(r) = self.inner.get_registry_client() {
+            r.delete_feature(qualified_name, force).await
+        } else {
+            Err(Error::DetachedClient)
+        }
+    }
+
+    /**
+     * Delete the project and everything in it from the registry
+     */
+    pub async fn delete_project(&self, name: &str) -> Result<(), Error> {
+        if let Some(r) = self.inner.get_registry_client() {
+            r.delete_project(name).await
+        } else {
+            Err(Error::DetachedClient)
+        }
+    }
+
+    /**
+     * Export the full registry representation of the project, including all entities and lineage edges, into a JSON file
+     */
+    pub async fn export_registry<T>(&self, name: &str, path: T) -> Result<(), Error>
+    where
+        T: AsRef<Path>,
+    {
+        if let Some(r) = self.inner.get_registry_client() {
+            let lineage = r.load_project(name).await?;
+            crate::runtime::write_file(path, serde_json::to_string_pretty(&lineage)?.as_bytes()).await?;
+            Ok(())
+        } else {
+            Err(Error::DetachedClient)
+        }
+    }
+
+    /**
+     * Recreate the project exported by `export_registry` in the registry this client is connected to
+     */
+    pub async fn import_registry<T>(&self, path: T) -> Result<FeathrProject, Error>
+    where
+        T: AsRef<Path>,
+    {
+        let lineage: api_models::EntityLineage =
+            serde_json::from_slice(&crate::runtime::read_file(path).await?)?;
+        let imported: FeathrProjectImpl = lineage.try_into()?;
+        let project = self
+            .new_project_with_tags(&imported.name, imported.registry_tags.clone())
+            .await?;
+        project.inner.write().await.import_from(&imported).await?;
+        Ok(project)
+    }
+
     pub async fn new_project(&self, name: &str) -> Result<FeathrProject, Error> {
         self.new_project_with_tags(name, Default::default()).await
     }
@@ -64,6 +205,12 @@ impl FeathrClient {
         Ok(FeathrProject::new(self.inner.clone(), name, id).await)
     }
 
+    /**
+     * Submit the job, returns the id of the job with the same idempotency key instead if it's still running.
+     * Only jobs submitted by this client are deduplicated, if a previous submission of the key failed,
+     * e.g. timed out after the job was accepted, the 100 most recent jobs in the Spark provider are searched for the key.
+     * Set `request.force` to always submit
+     */
     pub async fn submit_job(&self, request: SubmitJobRequest) -> Result<JobId, Error> {
         self.inner.submit_job(request).await
     }
@@ -72,6 +219,78 @@ impl FeathrClient {
         self.inner.submit_jobs(requests).await
     }
 
+    /**
+     * Create a job running on `request.schedule` instead of a one-shot run, returns the id of the job
+     * in the Spark provider for later management, e.g. the Databricks job id
+     */
+    pub async fn schedule_job(&self, request: SubmitJobRequest) -> Result<u64, Error> {
+        self.inner.schedule_job(request).await
+    }
+
+    /**
+     * Submit the jobs, generation requests identical to an earlier one in the batch, e.g. from overlapping cutoffs
+     * of the same features and sinks, are skipped and reported in `JobBatchSubmission::duplicates`
+     */
+    pub async fn submit_job_batch(&self, requests: Vec<SubmitJobRequest>) -> Result<JobBatchSubmission, Error> {
+        self.inner.submit_job_batch(requests).await
+    }
+
+    /**
+     * Submit the job and return it with the manifest of its remote artifacts and Spark arguments
+     */
+    pub async fn submit_job_with_manifest(&self, request: SubmitJobRequest) -> Result<JobHandle, Error> {
+        let job_id = self.inner.submit_job(request).await?;
+        self.get_job_handle(job_id)
+    }
+
+    /**
+     * Manifest of a job submitted by this client
+     */
+    pub fn get_job_handle(&self, job_id: JobId) -> Result<JobHandle, Error> {
+        self.inner.get_job_handle(job_id)
+    }
+
+    /**
+     * Switch the Redis aliases of the job once it succeeded, returns `false` if it hasn't succeeded yet.
+     * Aliases are switched when the success is observed by the submitting client, this is for jobs whose
+     * handle is saved and checked later, e.g. by another process
+     */
+    pub async fn switch_job_redis_aliases(&self, handle: &JobHandle) -> Result<bool, Error> {
+        self.inner.switch_job_redis_aliases(handle).await
+    }
+
+    /**
+     * Percentiles of the time spent in each submission step of jobs submitted by this client,
+     * queue wait and run time are only counted for jobs waited by this client until they ended
+     */
+    pub fn get_submission_timing_stats(&self) -> Result<SubmissionTimingStats, Error> {
+        self.inner.get_submission_timing_stats()
+    }
+
+    /**
+     * Submit joining job with feature config and feature join config managed outside of this crate
+     */
+    pub async fn submit_join_from_configs(
+        &self,
+        feature_conf: &str,
+        join_conf: &str,
+        opts: &ConfigJobOptions,
+    ) -> Result<JobId, Error> {
+        self.submit_job(opts.join_request(feature_conf, join_conf)).await
+    }
+
+    /**
+     * Submit feature generation job with feature config and feature gen config managed outside of this crate
+     */
+    pub async fn submit_gen_from_configs(
+        &self,
+        feature_conf: &str,
+        gen_conf: &str,
+        opts: &ConfigJobOptions,
+    ) -> Result<JobId, Error> {
+        self.submit_job(opts.gen_request(feature_conf, gen_conf)).await
+    }
+
     pub async fn wait_for_job(
         &self,
         job_id: JobId,
@@ -88,6 +307,57 @@ impl FeathrClient {
         self.inner.wait_for_jobs(job_ids, timeout).await
     }
 
+    /**
+     * Wait for jobs and return their outcomes in the same order,
+     * returns `Error::JobsFailed` if `raise_on_failure` is set and any of the jobs didn't succeed
+     */
+    pub async fn wait_for_job_results(
+        &self,
+        job_ids: Vec<JobId>,
+        timeout: Option<Duration>,
+        raise_on_failure: bool,
+    ) -> Result<Vec<JobResult>, Error> {
+        self.wait_for_job_results_impl(job_ids, timeout, raise_on_failure, false)
+            .await
+    }
+
+    /**
+     * Same as `wait_for_job_results`, and the output schema of succeeded jobs is read into `JobResult::schema`
+     */
+    pub async fn wait_for_job_results_with_schema(
+        &self,
+        job_ids: Vec<JobId>,
+        timeout: Option<Duration>,
+        raise_on_failure: bool,
+    ) -> Result<Vec<JobResult>, Error> {
+        self.wait_for_job_results_impl(job_ids, timeout, raise_on_failure, true)
+            .await
+    }
+
+    async fn wait_for_job_results_impl(
+        &self,
+        job_ids: Vec<JobId>,
+        timeout: Option<Duration>,
+        raise_on_failure: bool,
+        with_schema: bool,
+    ) -> Result<Vec<JobResult>, Error> {
+        let results = join_all(
+            job_ids
+                .into_iter()
+                .map(|job_id| self.inner.wait_for_job_result(job_id, timeout, with_schema)),
+        )
+        .await;
+        let failed: Vec<JobId> = results
+            .iter()
+            .filter(|r| !r.is_success())
+            .map(|r| r.job_id)
+            .collect();
+        if raise_on_failure && !failed.is_empty() {
+            return Err(Error::JobsFailed(failed));
+        }
+        Ok(results)
+    }
+
     pub fn get_remote_url(&self, path: &str) -> String {
         self.inner.get_remote_url(path)
     }
@@ -96,16 +366,223 @@ impl FeathrClient {
         self.inner.get_job_status(job_id).await
     }
 
+    /**
+     * Get current status of the job and the time spent in each status,
+     * `JobStatus::Provisioning` means the job is waiting for the cluster capacity
+     */
+    pub async fn get_job_status_info(&self, job_id: JobId) -> Result<JobStatusInfo, Error> {
+        self.inner.get_job_status_info(job_id).await
+    }
+
+    /**
+     * Stop a running job, a cancelled job ends with `JobStatus::Failed`
+     */
+    pub async fn cancel_job(&self, job_id: JobId) -> Result<(), Error> {
+        self.inner.cancel_job(job_id).await
+    }
+
+    /**
+     * List at most `limit` most recently submitted jobs in the Spark provider, newest first.
+     * The cost of the jobs is estimated if the provider reports the cluster usage in the listing
+     */
+    pub async fn list_jobs(&self, limit: usize) -> Result<Vec<JobSummary>, Error> {
+        self.inner.list_jobs(limit).await
+    }
+
     pub async fn get_job_output_url(&self, job_id: JobId) -> Result<Option<String>, crate::Error> {
         self.inner.get_job_output_url(job_id).await
     }
+
+    /**
+     * Schema of the job output, read from one of the Avro or Parquet files in the output directory,
+     * `None` if the job has no output or the output doesn't have any data file
+     */
+    pub async fn get_job_output_schema(&self, job_id: JobId) -> Result<Option<OutputSchema>, crate::Error> {
+        self.inner.get_job_output_schema(job_id).await
+    }
+
+    /**
+     * Estimate the cost of the job from its cluster size and run time, with prices in `spark_config.cost`.
+     * The cost of a running job is estimated up to now.
+     */
+    pub async fn get_job_cost_estimate(&self, job_id: JobId) -> Result<JobCostEstimate, Error> {
+        self.inner.get_job_cost_estimate(job_id).await
+    }
+
+    /**
+     * Estimate and aggregate the cost of the jobs, jobs whose cost cannot be estimated are listed in `unavailable`.
+     * Use the cost of the jobs returned by `list_jobs` to avoid looking up each job
+     */
+    pub async fn get_jobs_cost_summary(&self, job_ids: &[JobId]) -> Result<JobCostSummary, Error> {
+        let prices = PriceTable::from_var_source(self.inner.var_source.clone()).await?;
+        let estimates = join_all(job_ids.iter().map(|&job_id| {
+            let prices = &prices;
+            async move {
+                self.inner
+                    .estimate_job_cost(prices, job_id)
+                    .await
+                    .map_err(|e| (job_id, e))
+            }
+        }))
+        .await;
+        Ok(JobCostSummary::new(&prices.currency, estimates))
+    }
+
+    /**
+     * Profile the features materialized into the HDFS sink with a Spark job, and wait for the statistics.
+     * The data read is limited by `options`, see `ProfileOptions`.
+     * The job is named after `project`, the project the features belong to.
+     */
+    pub async fn profile_features<T>(
+        &self,
+        project: &str,
+        features: &[T],
+        sink: &HdfsSink,
+        options: &ProfileOptions,
+    ) -> Result<Vec<FeatureStatistics>, Error>
+    where
+        T: ToString,
+    {
+        let features: Vec<String> = features.iter().map(|f| f.to_string()).collect();
+        let mut input_paths: Vec<String> = vec![];
+        for settings in MaterializationSettingsBuilder::new("profile", &features)
+            .sink(sink.to_owned())
+            .build(options.start, options.end, options.step)?
+        {
+            for s in settings.operational.sinks {
+                if let OutputSink::Hdfs(h) = s {
+                    if !input_paths.contains(&h.path) {
+                        input_paths.push(h.path);
+                    }
+                }
+            }
+        }
+        let output = self.get_remote_url(&format!("feathr_profile_{}", Uuid::new_v4().as_simple()));
+        let mut request = profile_job_request(project, &features, &input_paths, PROFILE_INPUT_FORMAT, &output)?;
+        // The profiling script reads the data directly instead of going through Feathr runtime
+        if let Some((host, key_name)) = sink.get_storage_key_name()? {
+            let key = self.inner.var_source.get_environment_variable(&[&key_name]).await?;
+            request
+                .configuration
+                .insert(format!("spark.hadoop.fs.azure.account.key.{}", host), key);
+        }
+        let job_id = self.submit_job(request).await?;
+        let result = self.inner.wait_for_job_result(job_id, options.timeout, false).await;
+        if !result.is_success() {
+            return Err(Error::JobsFailed(vec![job_id]));
+        }
+        let content = self
+            .inner
+            .job_client
+            .read_remote_file(&format!("{}/part-00000", output))
+            .await?;
+        parse_statistics(&String::from_utf8_lossy(&content))
+    }
+
+    /**
+     * Wait until the file, or any file under the directory, is modified after `since`,
+     * returns the latest modification time. Useful to block until the upstream data lands.
+     */
+    pub async fn wait_for_path_update(
+        &self,
+        path: &str,
+        since: DateTime<Utc>,
+        timeout: Option<Duration>,
+    ) -> Result<DateTime<Utc>, Error> {
+        self.inner.wait_for_path_update(path, since, timeout).await
+    }
+
+    /**
+     * Sample the CSV observation data and detect the timestamp column and its format,
+     * the path can be on the workspace storage, a HTTP URL, or a local file
+     */
+    pub async fn detect_observation_settings(&self, observation_path: &str) -> Result<ObservationSettings, Error> {
+        if !observation_path.to_lowercase().ends_with(".csv") {
+            return Err(Error::InvalidUrl(format!(
+                "Only CSV observation data can be sampled, got {}",
+                observation_path
+            )));
+        }
+        let mut content: Vec<u8> = if self.inner.job_client.is_url_on_storage(observation_path) {
+            self.inner
+                .job_client
+                .read_remote_file_range(observation_path, 0, OBSERVATION_SAMPLE_BYTES)
+                .await?
+                .to_vec()
+        } else if observation_path.starts_with("http:") || observation_path.starts_with("https:") {
+            // Servers may ignore the range, stop reading once there is enough content
+            let mut resp = reqwest::Client::new()
+                .get(observation_path)
+                .header(reqwest::header::RANGE, format!("bytes=0-{}", OBSERVATION_SAMPLE_BYTES - 1))
+                .send()
+                .await?
+                .error_for_status()?;
+            let mut content = vec![];
+            while let Some(chunk) = resp.chunk().await? {
+                content.extend_from_slice(&chunk);
+                if content.len() as u64 >= OBSERVATION_SAMPLE_BYTES {
+                    break;
+                }
+            }
+            content
+        } else {
+            let path = observation_path.to_string();
+            crate::runtime::spawn_blocking(move || {
+                let mut content = vec![];
+                std::fs::File::open(path)?
+                    .take(OBSERVATION_SAMPLE_BYTES)
+                    .read_to_end(&mut content)
+                    .map(|_| content)
+            })
+            .await??
+        };
+        // Drop the last line if it's cut in the middle
+        if content.len() as u64 >= OBSERVATION_SAMPLE_BYTES {
+            content.truncate(OBSERVATION_SAMPLE_BYTES as usize);
+            let end = content.iter().rposition(|&b| b == b'\n').unwrap_or_default();
+            content.truncate(end);
+        }
+        let content = String::from_utf8_lossy(&content);
+        let sample: Vec<&str> = content.lines().take(OBSERVATION_SAMPLE_LINES + 1).collect();
+        let sample = LocalSample::from_csv(&sample.join("\n"))?;
+        ObservationSettings::detect(observation_path, &sample)
+    }
+
+    /**
+     * Connect to the Redis online store configured for this client
+     */
+    pub async fn online_client(&self) -> Result<OnlineClient, Error> {
+        OnlineClient::from_var_source(self.inner.var_source.clone()).await
+    }
+
+    /**
+     * Manage the tables in the Redis online store configured for this client, e.g. drop stale tables,
+     * tables are found by `online_store.redis.key_prefix`, which must be set
+     */
+    pub async fn online_store_admin(&self) -> Result<OnlineStoreAdmin, Error> {
+        Ok(OnlineStoreAdmin::new(&self.online_client().await?))
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct FeathrClientImpl {
     job_client: job_client::Client,
-    registry_client: Option<Arc<FeathrApiClient>>,
+    registry_client: Option<Arc<dyn FeatureRegistry>>,
     var_source: Arc<dyn VarSource + Send + Sync>,
+    // Pointer files to be updated when the job succeeded
+    latest_pointers: Arc<std::sync::Mutex<HashMap<JobId, HashMap<String, String>>>>,
+    // Datasets to be registered when the job succeeded
+    datasets: Arc<std::sync::Mutex<HashMap<JobId, Vec<DatasetRegistration>>>>,
+    // Redis aliases to be switched when the job succeeded
+    redis_aliases: Arc<std::sync::Mutex<HashMap<JobId, HashMap<String, String>>>>,
+    // Status transitions observed by this client
+    job_states: Arc<std::sync::Mutex<HashMap<JobId, JobStatusInfo>>>,
+    // Artifacts and arguments of jobs submitted by this client
+    manifests: Arc<std::sync::Mutex<HashMap<JobId, JobManifest>>>,
+    // Latest job submitted by this client with the idempotency key
+    idempotency_keys: Arc<std::sync::Mutex<HashMap<Uuid, JobId>>>,
+    // Idempotency keys this client tried to submit, including failed submissions
+    submitted_keys: Arc<std::sync::Mutex<HashSet<Uuid>>>,
 }
 
 impl FeathrClientImpl {
@@ -113,49 +590,307 @@ impl FeathrClientImpl {
     where
         T: AsRef<Path>,
     {
-        let var_source = load_var_source(conf_file);
-        Ok(Self {
-            job_client: job_client::Client::from_var_source(var_source.clone()).await?,
-            registry_client: FeathrApiClient::from_var_source(var_source.clone())
-                .await
-                .ok()
-                .map(Arc::new),
-            var_source,
-        })
+        Self::from_var_source(load_var_source(conf_file)).await
     }
 
     pub async fn from_str(content: &str) -> Result<Self, Error> {
-        let var_source = new_var_source(content);
+        Self::from_var_source(new_var_source(content)).await
+    }
+
+    pub async fn from_var_source(
+        var_source: Arc<dyn VarSource + Send + Sync>,
+    ) -> Result<Self, Error> {
         Ok(Self {
             job_client: job_client::Client::from_var_source(var_source.clone()).await?,
-            registry_client: FeathrApiClient::from_var_source(var_source.clone())
-                .await
-                .ok()
-                .map(Arc::new),
+            registry_client: registry_client::from_var_source(var_source.clone()).await?,
             var_source,
+            latest_pointers: Default::default(),
+            datasets: Default::default(),
+            redis_aliases: Default::default(),
+            job_states: Default::default(),
+            manifests: Default::default(),
+            idempotency_keys: Default::default(),
+            submitted_keys: Default::default(),
         })
     }
 
-    pub fn get_registry_client(&self) -> Option<Arc<FeathrApiClient>> {
+    pub fn get_registry_client(&self) -> Option<Arc<dyn FeatureRegistry>> {
         self.registry_client.clone()
     }
 
-    pub async fn submit_job(&self, request: SubmitJobRequest) -> Result<JobId, Error> {
-        self.job_client
-            .submit_job(self.var_source.clone(), request)
-            .await
+    pub(crate) fn get_var_source(&self) -> Arc<dyn VarSource + Send + Sync> {
+        self.var_source.clone()
+    }
+
+    pub async fn submit_job(&self, mut request: SubmitJobRequest) -> Result<JobId, Error> {
+        if let Some(cron_expr) = &request.schedule {
+            return Err(Error::InvalidSchedule(
+                cron_expr.to_owned(),
+                "scheduled jobs must be created with `schedule_job`".to_string(),
+            ));
+        }
+        let idempotency_key = request.idempotency_key;
+        if let Some(job_id) = self.find_running_job(&request).await? {
+            debug!(
+                "Job {} with idempotency key {} is still running, re-attached instead of submitting {}",
+                job_id, idempotency_key, request.name
+            );
+            return Ok(job_id);
+        }
+        check_compatibility(self.var_source.clone(), &mut request).await?;
+        if !idempotency_key.is_nil() {
+            self.submitted_keys.lock()?.insert(idempotency_key);
+        }
+        let pointers = std::mem::take(&mut request.latest_pointers);
+        let datasets = std::mem::take(&mut request.datasets);
+        let redis_aliases = std::mem::take(&mut request.redis_aliases);
+        let workspace_dir = request.workspace_dir.clone();
+        if let Some(dir) = &workspace_dir {
+            if !self.job_client.is_url_on_storage(dir) {
+                return Err(Error::InvalidUrl(dir.to_owned()));
+            }
+        }
+        // Existing output is moved aside before submitting, the job may start writing as soon as it's accepted
+        let overwritten = self
+            .move_aside(std::mem::take(&mut request.overwrite_paths))
+            .await?;
+        let start = std::time::Instant::now();
+        let (job_id, mut manifest) = with_manifest(with_workspace_dir(
+            workspace_dir,
+            self.job_client.submit_job(self.var_source.clone(), request),
+        ))
+        .await;
+        let job_id = match job_id {
+            Ok(job_id) => job_id,
+            Err(e) => {
+                self.restore_overwritten(&overwritten).await;
+                return Err(e);
+            }
+        };
+        for (path, backup) in overwritten {
+            debug!("Deleting previous output {} moved from {}", backup, path);
+            if let Err(e) = self.job_client.delete_remote_path(&backup).await {
+                warn!("Failed to delete previous output {}, error: {}", backup, e);
+            }
+        }
+        let timings = &mut manifest.timings;
+        timings.api_call_ms = (start.elapsed().as_millis() as u64)
+            .saturating_sub(timings.config_render_ms + timings.upload_ms);
+        manifest.redis_aliases = redis_aliases.clone();
+        self.manifests.lock()?.insert(job_id, manifest);
+        if !pointers.is_empty() {
+            self.latest_pointers.lock()?.insert(job_id, pointers);
+        }
+        if !datasets.is_empty() {
+            self.datasets.lock()?.insert(job_id, datasets);
+        }
+        if !redis_aliases.is_empty() {
+            self.redis_aliases.lock()?.insert(job_id, redis_aliases);
+        }
+        // The job is waiting for the cluster until another status is observed
+        self.job_states.lock()?.insert(
+            job_id,
+            JobStatusInfo::new(job_id, JobStatus::Provisioning, Utc::now()),
+        );
+        if !idempotency_key.is_nil() {
+            self.idempotency_keys.lock()?.insert(idempotency_key, job_id);
+        }
+        Ok(job_id)
+    }
+
+    /**
+     * Move the existing output folders to hidden siblings, returns the moved paths and where they're moved to.
+     * Folders already moved are restored if one of them cannot be moved
+     */
+    async fn move_aside(&self, paths: Vec<String>) -> Result<Vec<(String, String)>, Error> {
+        let mut moved = vec![];
+        for path in paths {
+            let backup = overwritten_path(&path);
+            debug!("Moving existing output {} to {}", path, backup);
+            if let Err(e) = self.job_client.move_remote_path(&path, &backup).await {
+                self.restore_overwritten(&moved).await;
+                return Err(e);
+            }
+            moved.push((path, backup));
+        }
+        Ok(moved)
+    }
+
+    async fn restore_overwritten(&self, moved: &[(String, String)]) {
+        for (path, backup) in moved {
+            debug!("Restoring previous output {} from {}", path, backup);
+            if let Err(e) = self.job_client.move_remote_path(backup, path).await {
+                warn!("Failed to restore {} from {}, error: {}", path, backup, e);
+            }
+        }
+    }
+
+    /**
+     * Job with the same idempotency key that hasn't ended, unless the request is forced.
+     * None of the Spark providers can filter jobs by tags, so the recent jobs are only scanned for the tagged key
+     * if this client tried to submit the key but has no job for it, i.e. the submission may have been accepted
+     * before it failed. Jobs beyond the `RUNNING_JOBS_SCAN_LIMIT` most recent ones are not found
+     */
+    async fn find_running_job(&self, request: &SubmitJobRequest) -> Result<Option<JobId>, Error> {
+        if request.force || request.idempotency_key.is_nil() {
+            return Ok(None);
+        }
+        let job_id = self.idempotency_keys.lock()?.get(&request.idempotency_key).copied();
+        if let Some(job_id) = job_id {
+            if self.get_job_status(job_id).await?.is_ended() {
+                return Ok(None);
+            }
+            return Ok(Some(job_id));
+        }
+        if !self.submitted_keys.lock()?.contains(&request.idempotency_key) {
+            return Ok(None);
+        }
+        match self.job_client.list_jobs(RUNNING_JOBS_SCAN_LIMIT).await {
+            Ok(jobs) => Ok(running_job_with_key(request.idempotency_key, &jobs)),
+            Err(Error::ListJobsNotSupported(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn schedule_job(&self, mut request: SubmitJobRequest) -> Result<u64, Error> {
+        let cron_expr = request
+            .schedule
+            .clone()
+            .ok_or_else(|| Error::InvalidSchedule(String::default(), "schedule is not set".to_string()))?;
+        check_cron_expr(&cron_expr)?;
+        check_compatibility(self.var_source.clone(), &mut request).await?;
+        let workspace_dir = request.workspace_dir.clone();
+        if let Some(dir) = &workspace_dir {
+            if !self.job_client.is_url_on_storage(dir) {
+                return Err(Error::InvalidUrl(dir.to_owned()));
+            }
+        }
+        with_workspace_dir(
+            workspace_dir,
+            self.job_client.create_scheduled_job(self.var_source.clone(), request),
+        )
+        .await
+    }
+
+    pub fn get_job_handle(&self, job_id: JobId) -> Result<JobHandle, Error> {
+        let manifest = self
+            .manifests
+            .lock()?
+            .get(&job_id)
+            .cloned()
+            .ok_or(Error::ManifestNotFound(job_id))?;
+        Ok(JobHandle { job_id, manifest })
+    }
+
+    /**
+     * Point `_LATEST` files to the output of the succeeded job, unless they already point to a newer one
+     */
+    async fn update_latest_pointers(&self, job_id: JobId) -> Result<(), Error> {
+        let pointers = self.latest_pointers.lock()?.remove(&job_id);
+        for (url, content) in pointers.unwrap_or_default() {
+            let current = self
+                .job_client
+                .read_remote_file(&url)
+                .await
+                .map(|b| String::from_utf8_lossy(&b).trim().to_string())
+                .unwrap_or_default();
+            // Partition folders are zero-padded from year to hour, so they can be compared as strings
+            if current >= content {
+                debug!("{} already points to {}, skipped", url, current);
+                continue;
+            }
+            self.job_client
+                .write_remote_file(&url, content.as_bytes())
+                .await?;
+            debug!("{} updated to {}", url, content);
+        }
+        Ok(())
+    }
+
+    /**
+     * Register output datasets of the succeeded job in the registry
+     */
+    async fn register_datasets(&self, job_id: JobId) -> Result<(), Error> {
+        let datasets = self.datasets.lock()?.remove(&job_id);
+        for dataset in datasets.unwrap_or_default() {
+            match dataset.project.register_dataset(&dataset, job_id).await? {
+                Some(id) => debug!("Dataset {} registered, id: {}", dataset.name, id),
+                None => warn!(
+                    "Dataset {} is not registered as the project is not attached to a registry",
+                    dataset.name
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Switch Redis aliases to the tables written by the succeeded job
+     */
+    async fn switch_redis_aliases(&self, aliases: HashMap<String, String>) -> Result<(), Error> {
+        if aliases.is_empty() {
+            return Ok(());
+        }
+        let online_client = OnlineClient::from_var_source(self.var_source.clone()).await?;
+        for (alias, table) in aliases {
+            online_client.set_alias(&alias, &table).await?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Switch Redis aliases recorded in the handle if the job succeeded, returns `false` if it hasn't
+     */
+    pub async fn switch_job_redis_aliases(&self, handle: &JobHandle) -> Result<bool, Error> {
+        if self.job_client.get_job_status(handle.job_id).await? != JobStatus::Success {
+            return Ok(false);
+        }
+        // Already switched if the success was observed by this client
+        self.redis_aliases.lock()?.remove(&handle.job_id);
+        self.switch_redis_aliases(handle.manifest.redis_aliases.clone()).await?;
+        Ok(true)
+    }
+
+    /**
+     * Runs once when the success of a job submitted by this client is observed, actions are taken from the pending maps
+     */
+    async fn on_job_succeeded(&self, job_id: JobId) {
+        if let Err(e) = self.update_latest_pointers(job_id).await {
+            warn!("Failed to update latest pointers of job {}, error: {}", job_id, e);
+        }
+        if let Err(e) = self.register_datasets(job_id).await {
+            warn!("Failed to register output datasets of job {}, error: {}", job_id, e);
+        }
+        let aliases = self
+            .redis_aliases
+            .lock()
+            .map(|mut a| a.remove(&job_id).unwrap_or_default())
+            .unwrap_or_default();
+        if let Err(e) = self.switch_redis_aliases(aliases).await {
+            warn!("Failed to switch Redis aliases of job {}, error: {}", job_id, e);
+        }
     }
 
     pub async fn submit_jobs(&self, requests: Vec<SubmitJobRequest>) -> Result<Vec<JobId>, Error> {
-        let mut ret = vec![];
+        Ok(self.submit_job_batch(requests).await?.job_ids)
+    }
+
+    pub async fn submit_job_batch(&self, requests: Vec<SubmitJobRequest>) -> Result<JobBatchSubmission, Error> {
+        let (requests, kept_index, duplicates) = dedup_gen_requests(requests);
+        for d in duplicates.iter() {
+            warn!(
+                "Skipped generation job request {} ({}), it's identical to request {}",
+                d.index, d.job_config_file_name, d.duplicate_of
+            );
+        }
+        let mut submitted = vec![];
         for request in requests.into_iter() {
-            ret.push(
-                self.job_client
-                    .submit_job(self.var_source.clone(), request)
-                    .await?,
-            )
+            submitted.push(self.submit_job(request).await?)
         }
-        Ok(ret)
+        Ok(JobBatchSubmission {
+            job_ids: kept_index.into_iter().map(|k| submitted[k]).collect(),
+            duplicates,
+        })
     }
 
     pub async fn wait_for_job(
@@ -163,9 +898,137 @@ impl FeathrClientImpl {
         job_id: JobId,
         timeout: Option<Duration>,
     ) -> Result<String, Error> {
-        let status = self.job_client.wait_for_job(job_id, timeout).await?;
-        debug!("Job {} completed with status {}", job_id, status);
-        self.job_client.get_job_log(job_id).await
+        let status = self.wait_for_status(job_id, timeout).await;
+        let durations = self.take_durations(job_id);
+        let status = status?;
+        debug!(
+            "Job {} completed with status {}, time spent in each status: {:?}",
+            job_id, status, durations
+        );
+        let log = self.job_client.get_job_log(job_id).await?;
+        if status == JobStatus::Failed {
+            if let Some(msg) = detect_incompatibility(&log) {
+                return Err(Error::RuntimeIncompatible(job_id, msg, log));
+            }
+        }
+        Ok(log)
+    }
+
+    /**
+     * Output schema is only read if `with_schema` is set, as it needs extra requests to the storage
+     */
+    pub async fn wait_for_job_result(
+        &self,
+        job_id: JobId,
+        timeout: Option<Duration>,
+        with_schema: bool,
+    ) -> JobResult {
+        let status = self.wait_for_status(job_id, timeout).await;
+        let durations = self.take_durations(job_id);
+        let status = match status {
+            Ok(status) => status,
+            Err(e) => {
+                return JobResult {
+                    job_id,
+                    status: None,
+                    log: None,
+                    output_url: None,
+                    error: Some(e.to_string()),
+                    durations,
+                    schema: None,
+                }
+            }
+        };
+        let log = self.job_client.get_job_log(job_id).await;
+        let output_url = self.get_job_output_url(job_id).await.ok().flatten();
+        let schema = match (status, &output_url) {
+            (JobStatus::Success, Some(url)) if with_schema => self.get_output_schema(url).await.unwrap_or_else(|e| {
+                warn!("Failed to get output schema of job {}: {}", job_id, e);
+                None
+            }),
+            _ => None,
+        };
+        JobResult {
+            job_id,
+            status: Some(status),
+            output_url,
+            error: match (status, &log) {
+                (JobStatus::Success, Ok(_)) => None,
+                (JobStatus::Success, Err(e)) => Some(e.to_string()),
+                (JobStatus::Failed, Ok(log)) => Some(
+                    detect_incompatibility(log)
+                        .map(|msg| Error::RuntimeIncompatible(job_id, msg, log.clone()).to_string())
+                        .unwrap_or_else(|| format!("Job {} ended with status {}", job_id, status)),
+                ),
+                _ => Some(format!("Job {} ended with status {}", job_id, status)),
+            },
+            log: log.ok(),
+            durations,
+            schema,
+        }
+    }
+
+    /**
+     * Same as `JobClient::wait_for_job`, but status transitions are recorded
+     */
+    async fn wait_for_status(
+        &self,
+        job_id: JobId,
+        timeout: Option<Duration>,
+    ) -> Result<JobStatus, Error> {
+        let wait_until = timeout.map(|d| Utc::now() + d);
+        loop {
+            let status = self.get_job_status(job_id).await?;
+            debug!("Job {}, status: {}", job_id, status);
+            if status.is_ended() {
+                return Ok(status);
+            }
+            if let Some(t) = wait_until {
+                if Utc::now() > t {
+                    return Err(Error::Timeout);
+                }
+            }
+            // Check every few seconds
+            crate::runtime::sleep(std::time::Duration::from_secs(10)).await;
+        }
+    }
+
+    fn record_status(&self, job_id: JobId, status: JobStatus) -> Result<JobStatusInfo, Error> {
+        let now = Utc::now();
+        let mut states = self.job_states.lock()?;
+        let info = states
+            .entry(job_id)
+            .or_insert_with(|| JobStatusInfo::new(job_id, status, now));
+        info.update(status, now);
+        Ok(info.clone())
+    }
+
+    fn take_durations(&self, job_id: JobId) -> HashMap<JobStatus, Duration> {
+        let durations = self
+            .job_states
+            .lock()
+            .ok()
+            .and_then(|mut states| states.remove(&job_id))
+            .map(|info| info.durations)
+            .unwrap_or_default();
+        if let Ok(mut manifests) = self.manifests.lock() {
+            if let Some(m) = manifests.get_mut(&job_id) {
+                let ms = |statuses: &[JobStatus]| {
+                    statuses
+                        .iter()
+                        .filter_map(|s| durations.get(s))
+                        .map(|d| d.num_milliseconds().max(0) as u64)
+                        .sum::<u64>()
+                };
+                m.timings.queue_wait_ms = Some(ms(&[JobStatus::Provisioning, JobStatus::Starting]));
+                m.timings.run_ms = Some(ms(&[JobStatus::Running]));
+            }
+        }
+        durations
+    }
+
+    pub fn get_submission_timing_stats(&self) -> Result<SubmissionTimingStats, Error> {
+        Ok(timing_stats(self.manifests.lock()?.values().map(|m| &m.timings)))
     }
 
     pub async fn wait_for_jobs(
@@ -181,16 +1044,196 @@ impl FeathrClientImpl {
     }
 
     pub async fn get_job_status(&self, job_id: JobId) -> Result<JobStatus, Error> {
-        self.job_client.get_job_status(job_id).await
+        Ok(self.get_job_status_info(job_id).await?.status)
+    }
+
+    /**
+     * Latest pointers, datasets and Redis aliases of the job are updated once its success is observed,
+     * whether by waiting for the job or polling its status
+     */
+    pub async fn get_job_status_info(&self, job_id: JobId) -> Result<JobStatusInfo, Error> {
+        let status = self.job_client.get_job_status(job_id).await?;
+        if status == JobStatus::Success {
+            self.on_job_succeeded(job_id).await;
+        }
+        self.record_status(job_id, status)
+    }
+
+    pub async fn cancel_job(&self, job_id: JobId) -> Result<(), Error> {
+        self.job_client.cancel_job(job_id).await
+    }
+
+    pub async fn list_jobs(&self, limit: usize) -> Result<Vec<JobSummary>, Error> {
+        let mut jobs = self.job_client.list_jobs(limit).await?;
+        let prices = match PriceTable::from_var_source(self.var_source.clone()).await {
+            Ok(prices) => prices,
+            Err(e) => {
+                warn!("Cost of the jobs is not estimated, error: {}", e);
+                return Ok(jobs);
+            }
+        };
+        for job in jobs.iter_mut() {
+            job.cost = job.run_info.as_ref().and_then(|r| prices.estimate(r).ok());
+        }
+        Ok(jobs)
     }
 
     pub fn get_remote_url(&self, path: &str) -> String {
         self.job_client.get_remote_url(path)
     }
 
+    pub async fn wait_for_path_update(
+        &self,
+        path: &str,
+        since: DateTime<Utc>,
+        timeout: Option<Duration>,
+    ) -> Result<DateTime<Utc>, Error> {
+        let wait_until = timeout.map(|t| Utc::now() + t);
+        loop {
+            if let Some(modified) = self.job_client.get_remote_last_modified(path).await? {
+                if modified > since {
+                    return Ok(modified);
+                }
+            }
+            if let Some(t) = wait_until {
+                if Utc::now() > t {
+                    return Err(Error::Timeout);
+                }
+            }
+            debug!("Waiting for {} to be updated after {}", path, since);
+            crate::runtime::sleep(std::time::Duration::from_secs(10)).await;
+        }
+    }
+
     pub async fn get_job_output_url(&self, job_id: JobId) -> Result<Option<String>, crate::Error> {
         self.job_client.get_job_output_url(job_id).await
     }
+
+    pub async fn get_job_output_schema(&self, job_id: JobId) -> Result<Option<OutputSchema>, crate::Error> {
+        match self.get_job_output_url(job_id).await? {
+            Some(url) => self.get_output_schema(&url).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn get_output_schema(&self, url: &str) -> Result<Option<OutputSchema>, crate::Error> {
+        let mut file = None;
+        if is_data_file(url) {
+            // The size is needed to read the Parquet footer
+            let url = url.trim_end_matches('/');
+            if let Some((dir, name)) = url.rsplit_once('/') {
+                file = self
+                    .job_client
+                    .list_remote_dir(dir)
+                    .await?
+                    .into_iter()
+                    .find(|f| !f.is_dir && f.url.trim_end_matches('/').rsplit('/').next() == Some(name));
+            }
+        } else {
+            // Partitioned output has data files in sub directories
+            let mut dir = url.to_string();
+            for _ in 0..OUTPUT_SCHEMA_MAX_DEPTH {
+                let files = self.job_client.list_remote_dir(&dir).await?;
+                if let Some(f) = pick_data_file(&files) {
+                    file = Some(f.clone());
+                    break;
+                }
+                let sub_dir = files.into_iter().find(|f| {
+                    let name = f.url.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+                    f.is_dir && !name.starts_with('_') && !name.starts_with('.')
+                });
+                match sub_dir {
+                    Some(sub_dir) => dir = sub_dir.url,
+                    None => break,
+                }
+            }
+        }
+        match file {
+            Some(file) => {
+                debug!("Reading output schema from {}", file.url);
+                Ok(Some(read_output_schema(&self.job_client, &file.url, file.size).await?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /**
+     * Cutoffs between `start` and `end` without successful output in the time partitioned sink, oldest first
+     */
+    pub(crate) async fn get_missing_cutoffs(
+        &self,
+        sink: &HdfsSink,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        step: DateTimeResolution,
+    ) -> Result<Vec<DateTime<Utc>>, Error> {
+        let mut ret = vec![];
+        for settings in MaterializationSettingsBuilder::new("resume", &[])
+            .build(start, end, step)?
+        {
+            let end_time = settings.operational.end_time;
+            let marker = sink.get_success_marker(end_time).ok_or_else(|| {
+                Error::InvalidConfig(format!(
+                    "Sink {} has no time partition pattern, cutoffs cannot be told apart",
+                    sink.path
+                ))
+            })?;
+            if self.job_client.get_remote_last_modified(&marker).await?.is_none() {
+                debug!("Output of cutoff {} is missing or incomplete", end_time);
+                ret.push(end_time);
+            }
+        }
+        // Settings are built newest first, missing cutoffs are re-run in time order
+        ret.sort();
+        Ok(ret)
+    }
+
+    /**
+     * Generation jobs of the project not ended yet, among the recent jobs of the Spark provider.
+     * Only jobs named with the default job name template can be recognized
+     */
+    pub(crate) async fn get_running_generation_jobs(&self, project: &str) -> Result<Vec<JobId>, Error> {
+        let jobs = self.job_client.list_jobs(RUNNING_JOBS_SCAN_LIMIT).await?;
+        Ok(running_generation_jobs(project, &jobs))
+    }
+
+    async fn estimate_job_cost(&self, prices: &PriceTable, job_id: JobId) -> Result<JobCostEstimate, Error> {
+        prices.estimate(&self.job_client.get_job_run_info(job_id).await?)
+    }
+
+    pub async fn get_job_cost_estimate(&self, job_id: JobId) -> Result<JobCostEstimate, Error> {
+        let prices = PriceTable::from_var_source(self.var_source.clone()).await?;
+        self.estimate_job_cost(&prices, job_id).await
+    }
+}
+
+/**
+ * Sibling of the output folder starting with `_`, which Spark skips when reading the parent folder
+ */
+fn overwritten_path(path: &str) -> String {
+    let path = path.trim_end_matches('/');
+    let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+    format!("{}/_{}.overwritten-{}", parent, name, Uuid::new_v4())
+}
+
+fn running_job_with_key(idempotency_key: Uuid, jobs: &[JobSummary]) -> Option<JobId> {
+    jobs.iter()
+        .find(|job| !job.status.is_ended() && job.idempotency_key == Some(idempotency_key))
+        .map(|job| job.job_id)
+}
+
+fn running_generation_jobs(project: &str, jobs: &[JobSummary]) -> Vec<JobId> {
+    jobs.iter()
+        .filter(|job| !job.status.is_ended())
+        .filter(|job| match job_client::parse_job_name(&job.name) {
+            Ok(info) => {
+                info.project.as_deref() == Some(project)
+                    && info.job_type == Some(job_client::JobType::Generation)
+            }
+            Err(_) => false,
+        })
+        .map(|job| job.job_id)
+        .collect()
 }
 
 #[cfg(test)]
@@ -200,6 +1243,289 @@ mod tests {
 
     use crate::*;
 
+    const LIVY_CONFIG: &str = r#"
+spark_config:
+  spark_cluster: 'livy'
+  livy:
+    url: 'http://livy:8998'
+    webhdfs_url: 'http://namenode:9870'
+    workspace_dir: 'hdfs://namenode:8020/feathr'
+"#;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn thread_safety() {
+        assert_send_sync::<FeathrClient>();
+        assert_send_sync::<FeathrProject>();
+        assert_send_sync::<AzureSynapseClient>();
+        assert_send_sync::<DatabricksClient>();
+        assert_send_sync::<LivyJobClient>();
+        assert_send_sync::<EmrClient>();
+        assert_send_sync::<SparkOperatorClient>();
+        assert_send_sync::<OnlineClient>();
+        assert_send_sync::<OnlineStoreAdmin>();
+    }
+
+    #[tokio::test]
+    async fn detect_large_observation() {
+        // Long lines so the sampled head ends in the middle of a line
+        let padding = "x".repeat(3000);
+        let content: String = std::iter::once("id,padding,event_time\n".to_string())
+            .chain((0..1000).map(|i| format!("{},{},2022-07-01 10:00:00\n", i, padding)))
+            .collect();
+        let path = std::env::temp_dir().join(format!("feathr_obs_{}.csv", uuid::Uuid::new_v4().as_simple()));
+        std::fs::write(&path, content).unwrap();
+        let client = FeathrClient::from_str(LIVY_CONFIG).await.unwrap();
+        let settings = client
+            .detect_observation_settings(path.to_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(settings.columns, vec!["id", "padding", "event_time"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn output_schema_from_footer() {
+        use parquet::file::{properties::WriterProperties, writer::SerializedFileWriter};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Metadata larger than the first tail read, so the footer is read in another request
+        let fields: String = (0..5000)
+            .map(|i| format!("optional double feature_with_a_long_name_{};", i))
+            .collect();
+        let message_type = format!("message spark_schema {{ required int64 user_id; {} }}", fields);
+        let schema = std::sync::Arc::new(parquet::schema::parser::parse_message_type(&message_type).unwrap());
+        let mut content = vec![];
+        SerializedFileWriter::new(&mut content, schema, std::sync::Arc::new(WriterProperties::builder().build()))
+            .unwrap()
+            .close()
+            .unwrap();
+        assert!(content.len() > 64 * 1024);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let webhdfs_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let server = {
+            let requests = requests.clone();
+            let content = content.clone();
+            tokio::spawn(async move {
+                loop {
+                    let (mut socket, _) = listener.accept().await.unwrap();
+                    let mut request = vec![];
+                    let mut buf = [0u8; 1024];
+                    while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                        let n = socket.read(&mut buf).await.unwrap();
+                        request.extend_from_slice(&buf[..n]);
+                    }
+                    let request = String::from_utf8_lossy(&request).to_string();
+                    let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+                    let param = |name: &str| -> usize {
+                        path.split(['?', '&'])
+                            .find_map(|p| p.strip_prefix(&format!("{}=", name)))
+                            .and_then(|v| v.parse().ok())
+                            .unwrap_or_default()
+                    };
+                    let body = if path.contains("op=LISTSTATUS") {
+                        format!(
+                            r#"{{"FileStatuses": {{"FileStatus": [{{"pathSuffix": "_SUCCESS", "type": "FILE", "modificationTime": 0, "length": 0}}, {{"pathSuffix": "part-00000.parquet", "type": "FILE", "modificationTime": 0, "length": {}}}]}}}}"#,
+                            content.len()
+                        )
+                        .into_bytes()
+                    } else {
+                        let (offset, length) = (param("offset"), param("length"));
+                        requests.lock().unwrap().push((offset, length));
+                        content[offset.min(content.len())..(offset + length).min(content.len())].to_vec()
+                    };
+                    let header = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+                    socket.write_all(header.as_bytes()).await.unwrap();
+                    socket.write_all(&body).await.unwrap();
+                    socket.shutdown().await.unwrap();
+                }
+            })
+        };
+
+        let config = LIVY_CONFIG.replace("http://namenode:9870", &webhdfs_url);
+        let client = FeathrClient::from_str(&config).await.unwrap();
+        let schema = client
+            .inner
+            .get_output_schema("hdfs://namenode:8020/out")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(schema.columns.len(), 5001);
+        assert_eq!(schema.column("user_id").unwrap().data_type, "long");
+        // Only the tail and the metadata are read, never the whole file
+        let requests = requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0], (content.len() - 64 * 1024, 64 * 1024));
+        assert!(requests.iter().all(|(_, length)| *length < content.len()));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn resume_partially_succeeded_backfill() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // WebHDFS with the output of the first cutoff only
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let webhdfs_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut buf = [0u8; 1024];
+                while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let response = if String::from_utf8_lossy(&request).contains("/features/2022/05/02/_SUCCESS") {
+                    let body = r#"{"FileStatus": {"pathSuffix": "", "type": "FILE", "modificationTime": 1651449600000, "length": 0}}"#;
+                    format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+
+        let config = LIVY_CONFIG.replace("http://namenode:9870", &webhdfs_url);
+        let client = FeathrClient::from_str(&config).await.unwrap();
+        let proj = client.new_project("p1").await.unwrap();
+        let sink = HdfsSink::new("hdfs://namenode:8020/features").time_partitioned(DateTimeResolution::Daily);
+        let start = Utc.with_ymd_and_hms(2022, 5, 1, 0, 0, 0).unwrap();
+
+        // Only the cutoffs without the success marker are missing
+        let missing = client
+            .inner
+            .get_missing_cutoffs(&sink, start, start + Duration::days(3), DateTimeResolution::Daily)
+            .await
+            .unwrap();
+        assert_eq!(missing, vec![start + Duration::days(2), start + Duration::days(3)]);
+
+        // Nothing to resume if all cutoffs are complete
+        let job_ids = proj
+            .resume_materialization(&["f1"], start, start + Duration::days(1), DateTimeResolution::Daily, &sink, false)
+            .await
+            .unwrap();
+        assert!(job_ids.is_empty());
+
+        // Livy cannot list jobs, the missing cutoffs may still be running
+        let r = proj
+            .resume_materialization(&["f1"], start, start + Duration::days(3), DateTimeResolution::Daily, &sink, false)
+            .await;
+        assert!(matches!(r, Err(Error::MaterializationInProgress(_, _))));
+        server.abort();
+    }
+
+    #[test]
+    fn running_generation_jobs() {
+        let job = |id: u64, name: &str, status: JobStatus| JobSummary {
+            job_id: JobId(id),
+            name: name.to_string(),
+            status,
+            started_at: None,
+            ended_at: None,
+            output: None,
+            idempotency_key: None,
+            run_info: None,
+            cost: None,
+        };
+        let jobs = vec![
+            job(1, "p1_gen_20220501_0123abcd", JobStatus::Running),
+            job(2, "p1_gen_20220501_1123abcd", JobStatus::Provisioning),
+            job(3, "p1_gen_20220501_2123abcd", JobStatus::Failed),
+            job(4, "p1_join_20220501_3123abcd", JobStatus::Running),
+            job(5, "p2_gen_20220501_4123abcd", JobStatus::Running),
+            job(6, "my_own_job", JobStatus::Running),
+        ];
+        assert_eq!(super::running_generation_jobs("p1", &jobs), vec![JobId(1), JobId(2)]);
+        assert!(super::running_generation_jobs("p3", &jobs).is_empty());
+
+        // Jobs of failed submissions are found by the tagged key
+        let key = uuid::Uuid::new_v4();
+        let tagged = |id: u64, status: JobStatus| JobSummary {
+            idempotency_key: Some(key),
+            ..job(id, "p1_gen_20220501_5123abcd", status)
+        };
+        let jobs = vec![
+            tagged(7, JobStatus::Failed),
+            job(8, "p1_gen_20220501_6123abcd", JobStatus::Running),
+            tagged(9, JobStatus::Running),
+        ];
+        assert_eq!(super::running_job_with_key(key, &jobs), Some(JobId(9)));
+        assert_eq!(super::running_job_with_key(uuid::Uuid::new_v4(), &jobs), None);
+    }
+
+    #[test]
+    fn overwritten_path() {
+        let backup = super::overwritten_path("abfss://c@a.dfs.core.windows.net/out/2022/05/01/");
+        assert!(backup.starts_with("abfss://c@a.dfs.core.windows.net/out/2022/05/_01.overwritten-"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_projects() {
+        let client = FeathrClient::from_str(LIVY_CONFIG).await.unwrap();
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    let proj = client.new_project(&format!("p{}", i)).await.unwrap();
+                    let source = proj
+                        .hdfs_source("s", &format!("hdfs://namenode:8020/data/{}.csv", i))
+                        .build()
+                        .await
+                        .unwrap();
+                    let key = TypedKey::new("k", ValueType::INT32);
+                    let f = proj
+                        .anchor_group("g", source)
+                        .build()
+                        .await
+                        .unwrap()
+                        .anchor(&format!("f{}", i), FeatureType::INT32)
+                        .unwrap()
+                        .transform("x")
+                        .keys(&[&key])
+                        .build()
+                        .await
+                        .unwrap();
+                    let query = FeatureQuery::new(&[&f], &[&key]);
+                    let mut builder = proj
+                        .feature_join_job(
+                            ObservationSettings::from_path("hdfs://namenode:8020/obs.csv"),
+                            &[&query],
+                            &format!("hdfs://namenode:8020/output/{}.avro", i),
+                        )
+                        .await
+                        .unwrap();
+                    let output = format!("hdfs://namenode:8020/output/{}.avro", i);
+                    let request = builder
+                        .output_path(&output)
+                        .workspace_dir(&format!("hdfs://namenode:8020/ws{}", i))
+                        .build();
+                    assert_send(&client.submit_job(request.clone()));
+                    // Workspace dir override is scoped to the task
+                    let url = crate::job_client::with_workspace_dir(request.workspace_dir.clone(), async {
+                        tokio::task::yield_now().await;
+                        client.get_remote_url("job.conf")
+                    })
+                    .await;
+                    assert_eq!(url, format!("hdfs://namenode:8020/ws{}/job.conf", i));
+                    (proj.get_anchor_features().await, request.feature_config)
+                })
+            })
+            .collect();
+        for (i, h) in handles.into_iter().enumerate() {
+            let (features, feature_config) = h.await.unwrap();
+            assert_eq!(features, vec![format!("f{}", i)]);
+            assert!(feature_config.contains(&format!("/data/{}.csv", i)));
+        }
+        assert_eq!(client.get_remote_url("job.conf"), "hdfs://namenode:8020/feathr/job.conf");
+    }
+
     async fn init() -> FeathrClient {
         crate::tests::init_logger();
         FeathrClient::load("test-script/feathr_config.yaml")
@@ -433,7 +1759,7 @@ mod tests {
 
         println!(
             "features_join.conf:\n{}",
-            proj.get_feature_join_config(&ob, &[&anchor_query, &derived_query], &output)
+            proj.get_feature_join_config(&ob, &[&anchor_query, &derived_query], &output, true)
                 .unwrap()
         );
 
@@ -498,7 +1824,7 @@ mod tests {
 
         println!(
             "features_join.conf:\n{}",
-            proj.get_feature_join_config(&ob, &[&anchor_query, &derived_query], &output)
+            proj.get_feature_join_config(&ob, &[&anchor_query, &derived_query], &output, true)
                 .unwrap()
         );
 
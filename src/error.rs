@@ -3,7 +3,7 @@ use std::sync::PoisonError;
 use chrono::{DateTime, Utc};
 use thiserror::Error;
 
-use crate::registry_client::api_models::EntityType;
+use crate::{registry_client::api_models::EntityType, JobId, ValueType};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -13,9 +13,33 @@ pub enum Error {
     #[error("Project {0} not found")]
     ProjectNotFound(String),
 
-    #[error("Anchor group {0} not found")]
+    #[error("Source {0} not found")]
     SourceGroupNotFound(String),
 
+    #[error("Source {0} already exists")]
+    DuplicateSource(String),
+
+    #[error("Source {0} is still used by anchor groups {1:?}")]
+    SourceInUse(String, Vec<String>),
+
+    #[error("Feature {0} is still used by derived features {1:?}")]
+    FeatureInUse(String, Vec<String>),
+
+    #[error("Online table {0} not found")]
+    OnlineTableNotFound(String),
+
+    #[error("Online table {0} is still pointed to by aliases {1:?}")]
+    OnlineTableInUse(String, Vec<String>),
+
+    #[error("Feature {0} references column {1} which is not declared in the schema of source {2}")]
+    UndeclaredColumn(String, String, String),
+
+    #[error("Feature {0} uses column {1} as a {2:?} key, but the column is declared as {3:?} in the schema of source {4}")]
+    KeyColumnTypeMismatch(String, String, ValueType, ValueType, String),
+
+    #[error("Cannot replace source {0}: {1}")]
+    SourceConflict(String, String),
+
     #[error("Anchor group {0} not found")]
     AnchorGroupNotFound(String),
 
@@ -25,12 +49,21 @@ pub enum Error {
     #[error("Anchor {0} has no transformation")]
     MissingTransformation(String),
 
+    #[error("Template parameter {1} of feature {0} is not set")]
+    MissingTemplateParameter(String, String),
+
+    #[error("Invalid transformation template '{0}'")]
+    InvalidTemplate(String),
+
     #[error("{2} key alias are provided while Anchor {0} has {1} keys")]
     MismatchKeyAlias(String, usize, usize),
 
     #[error("Key alias {1} not found in derived feature {0}, existing keys are: {2}")]
     KeyAliasNotFound(String, String, String),
 
+    #[error("JDBC sources {0} and {1} have different {2}, they cannot be used in the same project")]
+    JdbcConfigConflict(String, String, String),
+
     #[error("Source {0} has neither `dbtable` nor `query` set")]
     SourceNoQuery(String),
 
@@ -43,6 +76,15 @@ pub enum Error {
     #[error("key alias {1} in derived feature {0} must come from its input features key alias list {2}")]
     InvalidDerivedKeyAlias(String, String, String),
 
+    #[error("Invalid identifier '{1}' in {0}: {2}")]
+    InvalidIdentifier(String, String, String),
+
+    #[error("Output column '{0}' of feature {1} conflicts with {2}")]
+    OutputColumnConflict(String, String, String),
+
+    #[error("Input feature {1} of derived feature {0} has time offset but it's not anchored on a source with time window")]
+    InvalidLaggedInput(String, String),
+
     #[error("{0}")]
     SyncError(String),
 
@@ -52,21 +94,42 @@ pub enum Error {
     #[error(transparent)]
     LivyClientError(#[from] crate::livy_client::LivyClientError),
 
-    #[error(transparent)]
-    DbfsError(#[from] dbfs_client::DbfsError),
-
     #[error("Databricks API Error, Code={0}, Message='{1}'")]
     DatabricksApiError(String, String),
 
     #[error("HTTP Error, URL: '{0}', Status: {1}, Response: '{2}' ")]
     DatabricksHttpError(String, String, String),
 
+    #[error("AWS API Error, URL: '{0}', Status: {1}, Response: '{2}'")]
+    AwsHttpError(String, reqwest::StatusCode, String),
+
+    #[error(transparent)]
+    KubeError(#[from] kube::Error),
+
     #[error("Invalid Url {0}")]
     InvalidUrl(String),
 
     #[error("Timeout")]
     Timeout,
 
+    #[error("Runtime error: {0}")]
+    RuntimeError(String),
+
+    #[error("Cost of job {0} is not available, {1}")]
+    CostNotAvailable(JobId, String),
+
+    #[error("Manifest of job {0} not found, the job is not submitted by this client")]
+    ManifestNotFound(JobId),
+
+    #[error("Job {0} is not submitted by this client")]
+    UnknownJob(JobId),
+
+    #[error("Jobs {0:?} failed")]
+    JobsFailed(Vec<JobId>),
+
+    #[error("Cannot read schema from output file '{0}', {1}")]
+    InvalidOutputSchema(String, String),
+
     #[error(transparent)]
     ReqwestError(#[from] reqwest::Error),
 
@@ -76,9 +139,18 @@ pub enum Error {
     #[error("{0}")]
     InvalidConfig(String),
 
+    #[error("Invalid HOCON at line {0}: {1}")]
+    HoconError(usize, String),
+
+    #[error("Invalid feature config for {0}: {1}")]
+    InvalidFeatureConfig(String, String),
+
     #[error(transparent)]
     JsonError(#[from] serde_json::Error),
 
+    #[error(transparent)]
+    DecodeError(#[from] base64::DecodeError),
+
     #[error(transparent)]
     YamlError(#[from] serde_yaml::Error),
 
@@ -94,11 +166,83 @@ pub enum Error {
     #[error("Unsupported Spark provider '{0}'")]
     UnsupportedSparkProvider(String),
 
+    #[error("Unsupported feature registry type '{0}'")]
+    UnsupportedRegistry(String),
+
+    #[error("Invalid streaming job: {0}")]
+    InvalidStreamingJob(String),
+
+    #[error("Invalid schedule '{0}': {1}")]
+    InvalidSchedule(String, String),
+
+    #[error("Scheduled jobs are not supported by {0}")]
+    ScheduleNotSupported(String),
+
+    #[error("Listing jobs is not supported by {0}")]
+    ListJobsNotSupported(String),
+
+    #[error("Cancelling jobs is not supported by {0}")]
+    CancelNotSupported(String),
+
+    #[error("Listing projects is not supported by {0}")]
+    ListProjectsNotSupported(String),
+
+    #[error("Datasets are not supported by {0}")]
+    DatasetNotSupported(String),
+
+    #[error("Updating registered entities is not supported by {0}")]
+    UpdateNotSupported(String),
+
+    #[error("Deleting registered entities is not supported by {0}")]
+    DeleteNotSupported(String),
+
+    #[error("{0} requires Feathr runtime {1}, but configured runtime is {2}")]
+    UnsupportedByRuntime(String, String, String),
+
+    /**
+     * The last field is the log of the failed job
+     */
+    #[error("Job {0} failed due to incompatible Spark runtime. {1}")]
+    RuntimeIncompatible(JobId, String, String),
+
+    #[error("Invalid job name '{0}'")]
+    InvalidJobName(String),
+
+    #[error("No timestamp column detected in columns: {0}")]
+    TimestampColumnNotFound(String),
+
+    #[error("Timestamp column is ambiguous, candidates are {0:?}, specify the timestamp column and format explicitly")]
+    AmbiguousTimestampColumn(Vec<String>),
+
+    #[error("Invalid feature value in online store: {0}")]
+    InvalidFeatureValue(String),
+
+    #[error("Invalid TTL {0}, keys must live for at least 1 second")]
+    InvalidTtl(String),
+
+    #[error("Local evaluation failed: {0}")]
+    LocalEvaluationError(String),
+
     #[error("Entity({0}) has invalid type {1:?}")]
     InvalidEntityType(String, EntityType),
 
+    #[error("{0} has been modified in the registry by others ({1}), reload the project or enable force to overwrite")]
+    RegistryConflict(String, String),
+
+    #[error("Features are registered with different definitions: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    FeatureConflict(Vec<crate::registry_client::FeatureConflict>),
+
     #[error("Feathr client is not connected to the registry")]
     DetachedClient,
+
+    #[error("Project is not created by a Feathr client")]
+    DetachedProject,
+
+    #[error("Materialization of project {0} may still be running, {1}. Wait for the jobs or resume with force")]
+    MaterializationInProgress(String, String),
+
+    #[error(transparent)]
+    RedisError(#[from] redis::RedisError),
 }
 
 impl<Guard> From<PoisonError<Guard>> for Error {
@@ -3,13 +3,16 @@ use std::{
     sync::Arc,
 };
 
+use chrono::Duration;
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::{
     project::{FeathrProjectImpl, FeathrProjectModifier},
-    Error, FeatureType, Transformation, TypedKey, DerivedTransformation,
+    utils::{dur_to_string, str_to_dur},
+    local_eval::{evaluate_feature, local_expression},
+    Error, FeatureType, Transformation, TypedKey, DerivedTransformation, LocalSample, LocalValue,
 };
 
 pub trait Feature
@@ -23,7 +26,89 @@ where
     fn get_key(&self) -> Vec<TypedKey>;
     fn get_transformation(&self) -> Transformation;
     fn get_key_alias(&self) -> Vec<String>;
+    /**
+     * Registry tags of the feature, including the owner, team and description if set
+     */
     fn get_registry_tags(&self) -> HashMap<String, String>;
+
+    fn get_owner(&self) -> Option<String> {
+        None
+    }
+
+    fn get_team(&self) -> Option<String> {
+        None
+    }
+
+    fn get_description(&self) -> Option<String> {
+        None
+    }
+}
+
+/**
+ * Registry tags carrying the feature metadata
+ */
+pub(crate) const OWNER_TAG: &str = "owner";
+pub(crate) const TEAM_TAG: &str = "team";
+pub(crate) const DESCRIPTION_TAG: &str = "description";
+/**
+ * Registry tag keeping the aliases and offsets of the inputs of a derived feature,
+ * the registry only keeps the ids of the input features
+ */
+pub(crate) const INPUTS_TAG: &str = "inputs";
+
+/**
+ * An input of a derived feature as recorded in the `inputs` tag
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct InputSpec {
+    pub(crate) name: String,
+    pub(crate) feature: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) offset: Option<String>,
+}
+
+/**
+ * Value of the `inputs` tag, `None` if all inputs are referred by the feature name without offset
+ */
+pub(crate) fn inputs_tag(inputs: &HashMap<String, InputFeature>) -> Option<String> {
+    if inputs
+        .iter()
+        .all(|(name, i)| name == &i.feature && i.offset.is_none())
+    {
+        return None;
+    }
+    let mut specs: Vec<InputSpec> = inputs
+        .iter()
+        .map(|(name, i)| InputSpec {
+            name: name.to_owned(),
+            feature: i.feature.to_owned(),
+            offset: i.offset.map(dur_to_string),
+        })
+        .collect();
+    specs.sort_by(|a, b| a.name.cmp(&b.name));
+    serde_json::to_string(&specs).ok()
+}
+
+/**
+ * Parse the `inputs` tag into inputs keyed by the name, the keys and ids are resolved from the input features later
+ */
+pub(crate) fn parse_inputs_tag(tag: &str) -> Result<HashMap<String, InputFeature>, Error> {
+    let specs: Vec<InputSpec> = serde_json::from_str(tag)?;
+    specs
+        .into_iter()
+        .map(|s| {
+            Ok((
+                s.name,
+                InputFeature {
+                    key: vec![],
+                    feature: s.feature,
+                    id: Uuid::nil(),
+                    is_anchor_feature: false,
+                    offset: s.offset.as_deref().map(str_to_dur).transpose()?,
+                },
+            ))
+        })
+        .collect()
 }
 
 #[derive(Clone, Debug)]
@@ -42,6 +127,18 @@ impl AnchorFeature {
         self.owner
             .insert_anchor(group, self.inner.as_feature(feature_alias)).await
     }
+
+    /**
+     * Evaluate the transformation on the sample locally, columns of the sample are the source columns
+     */
+    pub fn evaluate_local(&self, sample: &LocalSample) -> Result<Vec<LocalValue>, Error> {
+        evaluate_feature(
+            &self.get_name(),
+            local_expression(&self.inner.transform),
+            &self.inner.base.feature_type,
+            sample,
+        )
+    }
 }
 
 impl Feature for AnchorFeature {
@@ -74,7 +171,19 @@ impl Feature for AnchorFeature {
     }
 
     fn get_registry_tags(&self) -> HashMap<String, String> {
-        self.inner.base.registry_tags.clone()
+        self.inner.base.registry_tags()
+    }
+
+    fn get_owner(&self) -> Option<String> {
+        self.inner.base.owner.clone()
+    }
+
+    fn get_team(&self) -> Option<String> {
+        self.inner.base.team.clone()
+    }
+
+    fn get_description(&self) -> Option<String> {
+        self.inner.base.description.clone()
     }
 }
 
@@ -105,6 +214,60 @@ impl DerivedFeature {
         self.owner
             .insert_derived(self.inner.as_feature(feature_alias)).await
     }
+
+    /**
+     * Evaluate the transformation on the sample locally, columns of the sample are the input feature aliases
+     */
+    pub fn evaluate_local(&self, sample: &LocalSample) -> Result<Vec<LocalValue>, Error> {
+        evaluate_feature(
+            &self.get_name(),
+            local_expression(&self.get_transformation()),
+            &self.inner.base.feature_type,
+            sample,
+        )
+    }
+
+    /**
+     * Input features of the derived feature, ordered by their aliases in the transformation
+     */
+    pub fn get_inputs(&self) -> Vec<DerivedInput> {
+        let mut ret: Vec<DerivedInput> = self
+            .inner
+            .inputs
+            .iter()
+            .map(|(alias, input)| DerivedInput {
+                alias: alias.to_owned(),
+                feature: input.feature.to_owned(),
+                key: input
+                    .key
+                    .iter()
+                    .map(|k| k.key_column_alias.clone().unwrap_or(k.key_column.clone()))
+                    .collect(),
+                is_anchor_feature: input.is_anchor_feature,
+                offset: input.offset,
+            })
+            .collect();
+        ret.sort_by(|a, b| a.alias.cmp(&b.alias));
+        ret
+    }
+}
+
+/**
+ * Input of a derived feature
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DerivedInput {
+    /**
+     * Name of the input in the transformation
+     */
+    pub alias: String,
+    pub feature: String,
+    /**
+     * Key aliases of the input feature
+     */
+    pub key: Vec<String>,
+    pub is_anchor_feature: bool,
+    pub offset: Option<Duration>,
 }
 
 impl Feature for DerivedFeature {
@@ -137,7 +300,19 @@ impl Feature for DerivedFeature {
     }
 
     fn get_registry_tags(&self) -> HashMap<String, String> {
-        self.inner.base.registry_tags.clone()
+        self.inner.base.registry_tags()
+    }
+
+    fn get_owner(&self) -> Option<String> {
+        self.inner.base.owner.clone()
+    }
+
+    fn get_team(&self) -> Option<String> {
+        self.inner.base.team.clone()
+    }
+
+    fn get_description(&self) -> Option<String> {
+        self.inner.base.description.clone()
     }
 }
 
@@ -153,6 +328,110 @@ impl ToString for &DerivedFeature {
     }
 }
 
+/**
+ * Lightweight description of a feature
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureDescriptor {
+    pub id: Uuid,
+    pub name: String,
+    pub feature_type: FeatureType,
+    pub key: Vec<TypedKey>,
+    /**
+     * Anchor group of the anchor feature, `None` for derived features
+     */
+    pub group: Option<String>,
+    pub registry_tags: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl FeatureDescriptor {
+    pub(crate) fn new<T: Feature>(f: &T, group: Option<&str>) -> Self {
+        Self {
+            id: f.get_id(),
+            name: f.get_name(),
+            feature_type: f.get_type(),
+            key: f.get_key(),
+            group: group.map(ToString::to_string),
+            registry_tags: f.get_registry_tags(),
+            owner: f.get_owner(),
+            team: f.get_team(),
+            description: f.get_description(),
+        }
+    }
+
+    pub fn is_anchor_feature(&self) -> bool {
+        self.group.is_some()
+    }
+}
+
+/**
+ * Criteria to filter features, all criteria must be matched
+ */
+#[derive(Clone, Debug, Default)]
+pub struct FeatureFilter {
+    key_columns: Vec<String>,
+    feature_type: Option<FeatureType>,
+    tags: HashMap<String, Option<String>>,
+}
+
+impl FeatureFilter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /**
+     * Feature must have the key column, either the column name or the alias
+     */
+    pub fn key_column(&mut self, key_column: &str) -> &mut Self {
+        self.key_columns.push(key_column.to_string());
+        self
+    }
+
+    pub fn feature_type(&mut self, feature_type: FeatureType) -> &mut Self {
+        self.feature_type = Some(feature_type);
+        self
+    }
+
+    /**
+     * Feature must have the registry tag with the value
+     */
+    pub fn tag(&mut self, key: &str, value: &str) -> &mut Self {
+        self.tags.insert(key.to_string(), Some(value.to_string()));
+        self
+    }
+
+    /**
+     * Feature must have the registry tag, regardless of the value
+     */
+    pub fn has_tag(&mut self, key: &str) -> &mut Self {
+        self.tags.insert(key.to_string(), None);
+        self
+    }
+
+    pub fn matches(&self, f: &FeatureDescriptor) -> bool {
+        self.key_columns.iter().all(|c| {
+            f.key
+                .iter()
+                .any(|k| &k.key_column == c || k.key_column_alias.as_ref() == Some(c))
+        }) && self
+            .feature_type
+            .as_ref()
+            .map(|t| t == &f.feature_type)
+            .unwrap_or(true)
+            && self.tags.iter().all(|(k, v)| match v {
+                Some(v) => f.registry_tags.get(k) == Some(v),
+                None => f.registry_tags.contains_key(k),
+            })
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct FeatureBase {
     #[serde(skip)]
@@ -167,6 +446,42 @@ pub(crate) struct FeatureBase {
     pub(crate) feature_alias: String,
     #[serde(skip)]
     pub(crate) registry_tags: HashMap<String, String>,
+    #[serde(skip)]
+    pub(crate) owner: Option<String>,
+    #[serde(skip)]
+    pub(crate) team: Option<String>,
+    #[serde(skip)]
+    pub(crate) description: Option<String>,
+}
+
+impl FeatureBase {
+    /**
+     * Tags registered with the feature, the metadata overrides user tags with the same names
+     */
+    pub(crate) fn registry_tags(&self) -> HashMap<String, String> {
+        let mut tags = self.registry_tags.clone();
+        for (name, value) in [
+            (OWNER_TAG, &self.owner),
+            (TEAM_TAG, &self.team),
+            (DESCRIPTION_TAG, &self.description),
+        ] {
+            if let Some(v) = value {
+                tags.insert(name.to_string(), v.to_owned());
+            }
+        }
+        tags
+    }
+
+    /**
+     * Set the tags loaded from the registry or a project file, metadata tags are moved to their fields
+     */
+    pub(crate) fn with_registry_tags(mut self, mut tags: HashMap<String, String>) -> Self {
+        self.owner = tags.remove(OWNER_TAG);
+        self.team = tags.remove(TEAM_TAG);
+        self.description = tags.remove(DESCRIPTION_TAG);
+        self.registry_tags = tags;
+        self
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -236,7 +551,19 @@ impl Feature for AnchorFeatureImpl {
     }
 
     fn get_registry_tags(&self) -> HashMap<String, String> {
-        self.base.registry_tags.to_owned()
+        self.base.registry_tags()
+    }
+
+    fn get_owner(&self) -> Option<String> {
+        self.base.owner.to_owned()
+    }
+
+    fn get_team(&self) -> Option<String> {
+        self.base.team.to_owned()
+    }
+
+    fn get_description(&self) -> Option<String> {
+        self.base.description.to_owned()
     }
 }
 
@@ -246,6 +573,8 @@ pub(crate) struct InputFeature {
     pub(crate) feature: String,
     pub(crate) id: Uuid,
     pub(crate) is_anchor_feature: bool,
+    #[serde(default, deserialize_with = "crate::hocon_duration::option::deserialize")]
+    pub(crate) offset: Option<Duration>,
 }
 
 impl Serialize for InputFeature {
@@ -263,6 +592,9 @@ impl Serialize for InputFeature {
                 .collect::<Vec<String>>(),
         )?;
         state.serialize_field("feature", &self.feature)?;
+        if let Some(offset) = self.offset {
+            state.serialize_field("offset", &dur_to_string(offset))?;
+        }
         state.end()
     }
 }
@@ -338,6 +670,18 @@ impl Feature for DerivedFeatureImpl {
     }
 
     fn get_registry_tags(&self) -> HashMap<String, String> {
-        self.base.registry_tags.to_owned()
+        self.base.registry_tags()
+    }
+
+    fn get_owner(&self) -> Option<String> {
+        self.base.owner.to_owned()
+    }
+
+    fn get_team(&self) -> Option<String> {
+        self.base.team.to_owned()
+    }
+
+    fn get_description(&self) -> Option<String> {
+        self.base.description.to_owned()
     }
 }
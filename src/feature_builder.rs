@@ -3,18 +3,82 @@ use std::{
     sync::Arc,
 };
 
+use chrono::Duration;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::{
     feature::{
         AnchorFeature, AnchorFeatureImpl, DerivedFeature, DerivedFeatureImpl, Feature, FeatureBase,
-        InputFeature,
+        InputFeature, DESCRIPTION_TAG, OWNER_TAG, TEAM_TAG,
     },
     project::{FeathrProjectImpl, FeathrProjectModifier},
     Error, FeatureType, Transformation, TypedKey,
 };
 
+/**
+ * Optional metadata shared by the feature builders
+ */
+#[derive(Debug, Default)]
+struct FeatureMetadata {
+    owner: Option<String>,
+    team: Option<String>,
+    description: Option<String>,
+}
+
+impl FeatureMetadata {
+    /**
+     * Metadata overrides tags with the same names, so features are the same no matter how metadata is set
+     */
+    fn merge_tags(&self, tags: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut tags = tags.clone();
+        for (name, value) in [
+            (OWNER_TAG, &self.owner),
+            (TEAM_TAG, &self.team),
+            (DESCRIPTION_TAG, &self.description),
+        ] {
+            if let Some(v) = value {
+                tags.insert(name.to_string(), v.to_owned());
+            }
+        }
+        tags
+    }
+}
+
+/**
+ * Values of the placeholders in the transformation, shared by the feature builders
+ */
+#[derive(Debug, Default)]
+struct TransformParams(HashMap<String, String>);
+
+impl TransformParams {
+    fn insert<T: ToString>(&mut self, name: &str, value: T) {
+        self.0.insert(name.to_string(), value.to_string());
+    }
+
+    fn extend<K, V, I>(&mut self, params: I)
+    where
+        K: AsRef<str>,
+        V: ToString,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (k, v) in params {
+            self.insert(k.as_ref(), v);
+        }
+    }
+
+    /**
+     * Render the placeholders of the transformation of the feature, the transformation is used as is without params
+     */
+    fn render(&self, feature: &str, transform: Transformation) -> Result<Transformation, Error> {
+        if self.0.is_empty() {
+            Ok(transform)
+        } else {
+            transform.render(feature, &self.0)
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AnchorFeatureBuilder {
     pub(crate) owner: Arc<RwLock<FeathrProjectImpl>>,
@@ -22,9 +86,11 @@ pub struct AnchorFeatureBuilder {
     name: String,
     feature_type: FeatureType,
     transform: Option<Transformation>,
+    params: TransformParams,
     keys: Vec<TypedKey>,
     feature_alias: String,
     registry_tags: HashMap<String, String>,
+    metadata: FeatureMetadata,
 }
 
 impl AnchorFeatureBuilder {
@@ -40,9 +106,11 @@ impl AnchorFeatureBuilder {
             name: name.to_string(),
             feature_type: feature_type,
             transform: None,
+            params: Default::default(),
             keys: Default::default(),
             feature_alias: name.to_string(),
             registry_tags: Default::default(),
+            metadata: Default::default(),
         }
     }
 
@@ -54,6 +122,35 @@ impl AnchorFeatureBuilder {
         self
     }
 
+    /**
+     * Set the value of the placeholder `{name}` in the transformation, rendered when the feature is built
+     */
+    pub fn param<T: ToString>(&mut self, name: &str, value: T) -> &mut Self {
+        self.params.insert(name, value);
+        self
+    }
+
+    pub fn params<K, V, I>(&mut self, params: I) -> &mut Self
+    where
+        K: AsRef<str>,
+        V: ToString,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        self.params.extend(params);
+        self
+    }
+
+    /**
+     * The source column with the same name as the feature is used if the transformation is not set
+     */
+    fn render_transform(&self) -> Result<Transformation, Error> {
+        let transform = self
+            .transform
+            .to_owned()
+            .unwrap_or_else(|| Transformation::from(&self.name));
+        self.params.render(&self.name, transform)
+    }
+
     pub fn keys(&mut self, keys: &[&TypedKey]) -> &mut Self {
         self.keys = keys.into_iter().map(|&k| k.to_owned()).collect();
         self
@@ -65,6 +162,30 @@ impl AnchorFeatureBuilder {
         self
     }
 
+    /**
+     * Owner of the feature, registered as the `owner` tag
+     */
+    pub fn owner(&mut self, owner: &str) -> &mut Self {
+        self.metadata.owner = Some(owner.to_string());
+        self
+    }
+
+    /**
+     * Team maintaining the feature, registered as the `team` tag
+     */
+    pub fn team(&mut self, team: &str) -> &mut Self {
+        self.metadata.team = Some(team.to_string());
+        self
+    }
+
+    /**
+     * Human readable description of the feature, registered as the `description` tag
+     */
+    pub fn description(&mut self, description: &str) -> &mut Self {
+        self.metadata.description = Some(description.to_string());
+        self
+    }
+
     pub async fn build(&mut self) -> Result<AnchorFeature, Error> {
         let anchor = AnchorFeatureImpl {
             base: FeatureBase {
@@ -77,8 +198,12 @@ impl AnchorFeatureBuilder {
                     self.keys.clone()
                 },
                 feature_alias: self.feature_alias.clone(),
-                registry_tags: self.registry_tags.clone(),
-            },
+                registry_tags: Default::default(),
+                owner: None,
+                team: None,
+                description: None,
+            }
+            .with_registry_tags(self.metadata.merge_tags(&self.registry_tags)),
             key_alias: self
                 .keys
                 .iter()
@@ -89,13 +214,16 @@ impl AnchorFeatureBuilder {
                         .to_owned()
                 })
                 .collect(),
-            transform: self
-                .transform
-                .as_ref()
-                .ok_or_else(|| Error::MissingTransformation(self.name.clone()))?
-                .to_owned(),
+            transform: self.render_transform()?,
         };
-        self.owner.insert_anchor(&self.group, anchor).await
+        let feature = self.owner.insert_anchor(&self.group, anchor).await?;
+        let mut owner = self.owner.write().await;
+        if self.transform.is_none() {
+            owner.implicit_transforms.insert(self.name.clone());
+        } else {
+            owner.implicit_transforms.remove(&self.name);
+        }
+        Ok(feature)
     }
 }
 #[derive(Debug)]
@@ -104,10 +232,12 @@ pub struct DerivedFeatureBuilder {
     name: String,
     feature_type: FeatureType,
     transform: Option<Transformation>,
+    params: TransformParams,
     keys: Vec<TypedKey>,
     feature_alias: String,
     registry_tags: HashMap<String, String>,
-    input_features: Vec<InputFeature>,
+    metadata: FeatureMetadata,
+    input_features: Vec<(String, InputFeature)>,
 }
 
 impl DerivedFeatureBuilder {
@@ -121,9 +251,11 @@ impl DerivedFeatureBuilder {
             name: name.to_string(),
             feature_type: feature_type,
             transform: None,
+            params: Default::default(),
             keys: Default::default(),
             feature_alias: name.to_string(),
             registry_tags: Default::default(),
+            metadata: Default::default(),
             input_features: Default::default(),
         }
     }
@@ -136,6 +268,32 @@ impl DerivedFeatureBuilder {
         self
     }
 
+    /**
+     * Set the value of the placeholder `{name}` in the transformation, rendered when the feature is built
+     */
+    pub fn param<T: ToString>(&mut self, name: &str, value: T) -> &mut Self {
+        self.params.insert(name, value);
+        self
+    }
+
+    pub fn params<K, V, I>(&mut self, params: I) -> &mut Self
+    where
+        K: AsRef<str>,
+        V: ToString,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        self.params.extend(params);
+        self
+    }
+
+    fn render_transform(&self) -> Result<Transformation, Error> {
+        let transform = self
+            .transform
+            .to_owned()
+            .ok_or_else(|| Error::MissingTransformation(self.name.clone()))?;
+        self.params.render(&self.name, transform)
+    }
+
     pub fn keys(&mut self, keys: &[&TypedKey]) -> &mut Self {
         self.keys = keys.iter().map(|&k| k.to_owned()).collect();
         self
@@ -147,22 +305,93 @@ impl DerivedFeatureBuilder {
         self
     }
 
+    /**
+     * Owner of the feature, registered as the `owner` tag
+     */
+    pub fn owner(&mut self, owner: &str) -> &mut Self {
+        self.metadata.owner = Some(owner.to_string());
+        self
+    }
+
+    /**
+     * Team maintaining the feature, registered as the `team` tag
+     */
+    pub fn team(&mut self, team: &str) -> &mut Self {
+        self.metadata.team = Some(team.to_string());
+        self
+    }
+
+    /**
+     * Human readable description of the feature, registered as the `description` tag
+     */
+    pub fn description(&mut self, description: &str) -> &mut Self {
+        self.metadata.description = Some(description.to_string());
+        self
+    }
+
     pub fn add_input<T: Feature>(&mut self, feature: &T) -> &mut Self {
-        self.input_features.push(InputFeature {
-            id: feature.get_id(),
-            key: feature.get_key(),
-            feature: feature.get_name(),
-            is_anchor_feature: feature.is_anchor_feature(),
-        });
+        self.input_features.push((
+            feature.get_name(),
+            InputFeature {
+                id: feature.get_id(),
+                key: feature.get_key(),
+                feature: feature.get_name(),
+                is_anchor_feature: feature.is_anchor_feature(),
+                offset: None,
+            },
+        ));
+        self
+    }
+
+    /**
+     * Add the value of the feature `offset` ago as input, referred as `alias` in the transformation.
+     * The feature must be anchored on a source with time window.
+     */
+    pub fn add_lagged_input<T: Feature>(
+        &mut self,
+        feature: &T,
+        alias: &str,
+        offset: Duration,
+    ) -> &mut Self {
+        self.input_features.push((
+            alias.to_string(),
+            InputFeature {
+                id: feature.get_id(),
+                key: feature.get_key(),
+                feature: feature.get_name(),
+                is_anchor_feature: feature.is_anchor_feature(),
+                offset: Some(offset),
+            },
+        ));
         self
     }
 
     pub async fn build(&mut self) -> Result<DerivedFeature, Error> {
         // Validation
+        {
+            let owner = self.owner.read().await;
+            for (_, i) in self.input_features.iter().filter(|(_, i)| i.offset.is_some()) {
+                let windowed = i.is_anchor_feature
+                    && owner.anchor_map.iter().any(|(group, features)| {
+                        features.contains(&i.feature)
+                            && owner.anchor_groups[group]
+                                .source
+                                .inner
+                                .time_window_parameters
+                                .is_some()
+                    });
+                if !windowed {
+                    return Err(Error::InvalidLaggedInput(
+                        self.name.to_owned(),
+                        i.feature.to_owned(),
+                    ));
+                }
+            }
+        }
         let key_alias: HashSet<String> = self
             .input_features
             .iter()
-            .flat_map(|i| {
+            .flat_map(|(_, i)| {
                 i.key.iter().map(|k| {
                     k.key_column_alias
                         .to_owned()
@@ -195,8 +424,12 @@ impl DerivedFeatureBuilder {
                     self.keys.clone()
                 },
                 feature_alias: self.feature_alias.clone(),
-                registry_tags: self.registry_tags.clone(),
-            },
+                registry_tags: Default::default(),
+                owner: None,
+                team: None,
+                description: None,
+            }
+            .with_registry_tags(self.metadata.merge_tags(&self.registry_tags)),
             key_alias: {
                 let aliases: Vec<String> = self
                     .keys
@@ -217,14 +450,9 @@ impl DerivedFeatureBuilder {
             inputs: self
                 .input_features
                 .iter()
-                .map(|f| (f.feature.to_owned(), f.to_owned()))
+                .map(|(name, f)| (name.to_owned(), f.to_owned()))
                 .collect(),
-            transform: self
-                .transform
-                .as_ref()
-                .ok_or_else(|| Error::MissingTransformation(self.name.clone()))?
-                .to_owned()
-                .into(),
+            transform: self.render_transform()?.into(),
         };
         self.owner.insert_derived(derived).await
     }
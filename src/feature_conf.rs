@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::{
+    project_file::{
+        AnchorFeatureFile, AnchorGroupFile, DerivedFeatureFile, DerivedInputFile, ProjectFile,
+        SourceFile,
+    },
+    source::SourceImpl,
+    utils::str_to_dur,
+    Aggregation, Error, ExpressionDef, FeatureType, Transformation, TypedKey, ValueType,
+};
+
+/**
+ * Anchors without key use this placeholder in upstream configs
+ */
+const NOT_NEEDED: &str = "NOT_NEEDED";
+
+/**
+ * Convert a parsed upstream `features.conf` into a project definition.
+ *
+ * Key columns are not typed in the feature config, they're imported as `UNSPECIFIED`.
+ * Derived features written as a bare expression take the features referenced in the expression as inputs.
+ */
+pub(crate) fn to_project_file(name: &str, conf: &Value) -> Result<ProjectFile, Error> {
+    let empty = Map::new();
+    let section = |key: &str| -> Result<&Map<String, Value>, Error> {
+        match conf.get(key) {
+            None => Ok(&empty),
+            Some(Value::Object(m)) => Ok(m),
+            Some(_) => Err(Error::InvalidFeatureConfig(
+                key.to_string(),
+                "must be an object".to_string(),
+            )),
+        }
+    };
+
+    let sources = section("sources")?
+        .iter()
+        .map(|(name, s)| {
+            Ok(SourceFile {
+                name: name.to_owned(),
+                source: serde_json::from_value::<SourceImpl>(s.to_owned())
+                    .map_err(|e| Error::InvalidFeatureConfig(name.to_owned(), e.to_string()))?,
+                schema: vec![],
+                preprocessing: None,
+                preprocessing_file: None,
+                tags: Default::default(),
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    // Keys of all features, used for inputs of derived features without explicit inputs
+    let mut feature_keys: HashMap<String, Vec<TypedKey>> = HashMap::new();
+
+    let mut anchor_groups = vec![];
+    for (group, a) in section("anchors")? {
+        let source = a
+            .get("source")
+            .and_then(string)
+            .ok_or_else(|| Error::InvalidFeatureConfig(group.to_owned(), "missing source".to_string()))?;
+        let key = typed_keys(a.get("key"), a.get("keyAlias"));
+        let features = match a.get("features") {
+            Some(Value::Object(features)) => features
+                .iter()
+                .map(|(name, f)| {
+                    feature_keys.insert(name.to_owned(), key.clone());
+                    Ok(AnchorFeatureFile {
+                        name: name.to_owned(),
+                        feature_type: feature_type(name, f.get("type"))?,
+                        key: key.clone(),
+                        transformation: transformation(name, f, "def")?,
+                        tags: Default::default(),
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?,
+            // Features declared as a list of column names
+            Some(Value::Array(columns)) => columns
+                .iter()
+                .filter_map(string)
+                .map(|name| {
+                    feature_keys.insert(name.to_owned(), key.clone());
+                    AnchorFeatureFile {
+                        transformation: Transformation::from(&name),
+                        name,
+                        feature_type: FeatureType::default(),
+                        key: key.clone(),
+                        tags: Default::default(),
+                    }
+                })
+                .collect(),
+            _ => {
+                return Err(Error::InvalidFeatureConfig(
+                    group.to_owned(),
+                    "missing features".to_string(),
+                ))
+            }
+        };
+        anchor_groups.push(AnchorGroupFile {
+            name: group.to_owned(),
+            source,
+            tags: Default::default(),
+            features,
+        });
+    }
+
+    let derivations = section("derivations")?;
+    for (name, d) in derivations {
+        feature_keys.insert(name.to_owned(), typed_keys(d.get("key"), None));
+    }
+    let identifier = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap();
+    let mut derived_features = vec![];
+    for (name, d) in derivations {
+        let transformation = transformation(name, d, "definition")?;
+        let inputs: Vec<DerivedInputFile> = match d.get("inputs") {
+            Some(Value::Object(inputs)) => inputs
+                .iter()
+                .map(|(alias, i)| {
+                    let feature = i.get("feature").and_then(string).unwrap_or_else(|| alias.to_owned());
+                    DerivedInputFile {
+                        name: alias.to_owned(),
+                        key: match i.get("key") {
+                            Some(k) => typed_keys(Some(k), None),
+                            None => feature_keys.get(&feature).cloned().unwrap_or_default(),
+                        },
+                        feature,
+                        offset: None,
+                    }
+                })
+                .collect(),
+            Some(_) => {
+                return Err(Error::InvalidFeatureConfig(
+                    name.to_owned(),
+                    "inputs must be an object".to_string(),
+                ))
+            }
+            None => {
+                let expr = match &transformation {
+                    Transformation::Expression { def } => def.sql_expr.to_owned(),
+                    Transformation::WindowAgg { def_expr, .. } => def_expr.to_owned(),
+                    Transformation::Udf { .. } => String::new(),
+                };
+                let mut names: Vec<&str> = identifier
+                    .find_iter(&expr)
+                    .map(|m| m.as_str())
+                    .filter(|&n| n != name && feature_keys.contains_key(n))
+                    .collect();
+                names.sort();
+                names.dedup();
+                names
+                    .into_iter()
+                    .map(|feature| DerivedInputFile {
+                        name: feature.to_owned(),
+                        feature: feature.to_owned(),
+                        key: feature_keys[feature].clone(),
+                        offset: None,
+                    })
+                    .collect()
+            }
+        };
+        if inputs.is_empty() {
+            return Err(Error::InvalidFeatureConfig(
+                name.to_owned(),
+                "derived feature has no input".to_string(),
+            ));
+        }
+        let key = match d.get("key") {
+            Some(k) => typed_keys(Some(k), None),
+            None => inputs[0].key.clone(),
+        };
+        derived_features.push(DerivedFeatureFile {
+            name: name.to_owned(),
+            feature_type: feature_type(name, d.get("type"))?,
+            key,
+            transformation,
+            inputs,
+            tags: Default::default(),
+        });
+    }
+
+    Ok(ProjectFile {
+        name: name.to_string(),
+        tags: Default::default(),
+        sources,
+        anchor_groups,
+        derived_features,
+    })
+}
+
+fn string(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s.to_owned()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/**
+ * A single expression, a list of expressions, or either form under `sqlExpr`
+ */
+fn strings(v: &Value) -> Vec<String> {
+    match v {
+        Value::Array(a) => a.iter().filter_map(string).collect(),
+        Value::Object(o) => o.get("sqlExpr").map(strings).unwrap_or_default(),
+        v => string(v).into_iter().collect(),
+    }
+}
+
+fn typed_keys(key: Option<&Value>, alias: Option<&Value>) -> Vec<TypedKey> {
+    let columns: Vec<String> = key
+        .map(strings)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|k| k != NOT_NEEDED)
+        .collect();
+    let aliases = alias.map(strings).unwrap_or_default();
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            TypedKey::new(c, ValueType::UNSPECIFIED)
+                .key_column_alias(aliases.get(i).unwrap_or(c))
+        })
+        .collect()
+}
+
+/**
+ * Types are either the full tensor type, or the legacy type names
+ */
+fn feature_type(name: &str, v: Option<&Value>) -> Result<FeatureType, Error> {
+    let invalid = |t: &str| Error::InvalidFeatureConfig(name.to_string(), format!("unsupported type '{}'", t));
+    Ok(match v {
+        None => FeatureType::default(),
+        Some(Value::String(t)) => match t.to_uppercase().as_str() {
+            "BOOLEAN" | "BOOL" => FeatureType::BOOLEAN,
+            "INT" | "INT32" => FeatureType::INT32,
+            "LONG" | "INT64" => FeatureType::INT64,
+            "FLOAT" | "NUMERIC" => FeatureType::FLOAT,
+            "DOUBLE" => FeatureType::DOUBLE,
+            "STRING" | "CATEGORICAL" => FeatureType::STRING,
+            "BYTES" => FeatureType::BYTES,
+            "DENSE_VECTOR" => FeatureType::FLOAT_VECTOR(),
+            _ => return Err(invalid(t)),
+        },
+        Some(t) => serde_json::from_value(t.to_owned()).map_err(|_| invalid(&t.to_string()))?,
+    })
+}
+
+/**
+ * Anchor features use `def` and derived features use `definition` for the expression
+ */
+fn transformation(name: &str, f: &Value, def: &str) -> Result<Transformation, Error> {
+    let invalid = |reason: &str| Error::InvalidFeatureConfig(name.to_string(), reason.to_string());
+    let expr = |v: &Value| strings(v).into_iter().next();
+    let f = match f {
+        Value::Object(f) => f,
+        v => {
+            return expr(v)
+                .map(Transformation::from)
+                .ok_or_else(|| Error::MissingTransformation(name.to_string()))
+        }
+    };
+    if f.contains_key("aggregation") || f.contains_key("window") {
+        let def_expr = f
+            .get(def)
+            .and_then(expr)
+            .ok_or_else(|| Error::MissingTransformation(name.to_string()))?;
+        let agg_func = f
+            .get("aggregation")
+            .and_then(string)
+            .map(|a| {
+                serde_json::from_value::<Aggregation>(Value::String(a.to_uppercase()))
+                    .map_err(|_| invalid(&format!("unsupported aggregation '{}'", a)))
+            })
+            .transpose()?;
+        let window = f.get("window").and_then(string).map(|w| str_to_dur(&w)).transpose()?;
+        return Ok(Transformation::WindowAgg {
+            def_expr,
+            agg_func,
+            window,
+            group_by: f.get("groupBy").and_then(string),
+            filter: f.get("filter").and_then(string),
+            limit: f.get("limit").and_then(Value::as_u64),
+        });
+    }
+    if let Some(sql_expr) = f.get(def).and_then(expr) {
+        return Ok(Transformation::Expression {
+            def: ExpressionDef { sql_expr },
+        });
+    }
+    if f.contains_key("class") {
+        return Err(invalid("UDF transformations are not supported"));
+    }
+    Err(Error::MissingTransformation(name.to_string()))
+}
@@ -1,12 +1,51 @@
-use serde::Serialize;
+use std::collections::HashMap;
 
-use crate::{TypedKey, Feature};
+use serde::{ser::SerializeStruct, Serialize};
 
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
+use crate::{Error, TypedKey, Feature};
+
+#[derive(Clone, Debug)]
 pub struct FeatureQuery {
     pub feature_list: Vec<String>,
     pub key: Vec<String>,
+    /**
+     * Prepended to the output column names of the features not explicitly renamed
+     */
+    pub prefix: Option<String>,
+    /**
+     * Output column names of the features, keyed by feature name
+     */
+    pub aliases: HashMap<String, String>,
+}
+
+impl Serialize for FeatureQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(untagged)]
+        enum JoiningFeature<'a> {
+            Name(&'a str),
+            #[serde(rename_all = "camelCase")]
+            Aliased { name: &'a str, feature_alias: String },
+        }
+        let features: Vec<JoiningFeature> = self
+            .feature_list
+            .iter()
+            .map(|name| match self.output_column(name) {
+                alias if &alias == name => JoiningFeature::Name(name),
+                alias => JoiningFeature::Aliased {
+                    name,
+                    feature_alias: alias,
+                },
+            })
+            .collect();
+        let mut query = serializer.serialize_struct("FeatureQuery", 2)?;
+        query.serialize_field("featureList", &features)?;
+        query.serialize_field("key", &self.key)?;
+        query.end()
+    }
 }
 
 impl FeatureQuery {
@@ -17,6 +56,8 @@ impl FeatureQuery {
         Self {
             feature_list: names.into_iter().map(|name| name.to_string()).collect(),
             key: keys.into_iter().map(|&keys| keys.key_column.to_owned()).collect(),
+            prefix: None,
+            aliases: Default::default(),
         }
     }
 
@@ -34,6 +75,8 @@ impl FeatureQuery {
         Self {
             feature_list: features.into_iter().map(|f| f.get_name()).collect(),
             key: vec![TypedKey::DUMMY_KEY().key_column],
+            prefix: None,
+            aliases: Default::default(),
         }
     }
 
@@ -45,8 +88,94 @@ impl FeatureQuery {
         Self {
             feature_list: features.into_iter().map(|&f| f.get_name()).collect(),
             key: vec![TypedKey::DUMMY_KEY().key_column],
+            prefix: None,
+            aliases: Default::default(),
         }
     }
+
+    /**
+     * Prefix the output columns of the features, e.g. `fs_` to avoid collisions with the observation columns
+     */
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /**
+     * Name the output column of the feature, takes precedence over the prefix
+     */
+    pub fn rename(mut self, feature: &str, column: &str) -> Self {
+        self.aliases.insert(feature.to_string(), column.to_string());
+        self
+    }
+
+    /**
+     * Name the output columns of the features, keyed by feature name
+     */
+    pub fn renames<T>(mut self, aliases: &[(T, T)]) -> Self
+    where
+        T: ToString,
+    {
+        self.aliases
+            .extend(aliases.iter().map(|(f, c)| (f.to_string(), c.to_string())));
+        self
+    }
+
+    /**
+     * Name of the column holding the feature in the joined output
+     */
+    pub fn output_column(&self, feature: &str) -> String {
+        match (self.aliases.get(feature), &self.prefix) {
+            (Some(alias), _) => alias.to_owned(),
+            (None, Some(prefix)) => format!("{}{}", prefix, feature),
+            (None, None) => feature.to_string(),
+        }
+    }
+
+    /**
+     * Output column names of the features in this query
+     */
+    pub fn output_columns(&self) -> Vec<String> {
+        self.feature_list
+            .iter()
+            .map(|f| self.output_column(f))
+            .collect()
+    }
+}
+
+/**
+ * Output columns must be unique and must not collide with the observation columns,
+ * renamed features must be in their queries
+ */
+pub(crate) fn check_output_columns(queries: &[FeatureQuery], observation_columns: &[String]) -> Result<(), Error> {
+    let mut columns: HashMap<String, String> = HashMap::new();
+    for q in queries {
+        if let Some(f) = q.aliases.keys().find(|&f| !q.feature_list.contains(f)) {
+            return Err(Error::FeatureNotFound(f.to_owned()));
+        }
+        for f in q.feature_list.iter() {
+            let column = q.output_column(f);
+            if column.trim().is_empty() {
+                return Err(Error::InvalidIdentifier(
+                    f.to_owned(),
+                    column,
+                    "output column is empty".to_string(),
+                ));
+            }
+            if observation_columns.iter().any(|c| c.eq_ignore_ascii_case(&column)) {
+                return Err(Error::OutputColumnConflict(
+                    column,
+                    f.to_owned(),
+                    "the observation column".to_string(),
+                ));
+            }
+            // Spark column names are case insensitive by default
+            if let Some(other) = columns.insert(column.to_lowercase(), f.to_owned()) {
+                return Err(Error::OutputColumnConflict(column, f.to_owned(), format!("feature {}", other)));
+            }
+        }
+    }
+    Ok(())
 }
 
 impl<T> From<&[T]> for FeatureQuery
@@ -56,4 +185,46 @@ where
     fn from(names: &[T]) -> Self {
         FeatureQuery::by_name(names)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    use super::check_output_columns;
+
+    #[test]
+    fn output_columns() {
+        let key = TypedKey::new("user_id", ValueType::INT32);
+        let q = FeatureQuery::new(&["f_amount", "f_count"], &[&key]).prefix("fs_");
+        assert_eq!(q.output_columns(), vec!["fs_f_amount", "fs_f_count"]);
+        let v = serde_json::to_value(q.clone().rename("f_count", "order_count")).unwrap();
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "featureList": [
+                    {"name": "f_amount", "featureAlias": "fs_f_amount"},
+                    {"name": "f_count", "featureAlias": "order_count"},
+                ],
+                "key": ["user_id"],
+            })
+        );
+        let v = serde_json::to_value(FeatureQuery::new(&["f_amount"], &[&key])).unwrap();
+        assert_eq!(v["featureList"], serde_json::json!(["f_amount"]));
+
+        let observation = vec!["user_id".to_string(), "f_amount".to_string()];
+        let plain = FeatureQuery::new(&["f_amount"], &[&key]);
+        assert!(matches!(
+            check_output_columns(std::slice::from_ref(&plain), &observation),
+            Err(Error::OutputColumnConflict(c, _, _)) if c == "f_amount"
+        ));
+        check_output_columns(std::slice::from_ref(&q), &observation).unwrap();
+        assert!(matches!(
+            check_output_columns(&[q.clone(), plain.clone().rename("f_amount", "FS_F_AMOUNT")], &[]),
+            Err(Error::OutputColumnConflict(_, _, other)) if other == "feature f_amount"
+        ));
+        assert!(matches!(
+            check_output_columns(&[plain.rename("f_other", "x")], &[]),
+            Err(Error::FeatureNotFound(f)) if f == "f_other"
+        ));
+    }
+}
@@ -0,0 +1,371 @@
+use serde_json::{Map, Number, Value};
+
+use crate::Error;
+
+/**
+ * Parse a feature config written by the upstream Feathr Python client into a JSON value.
+ *
+ * This is not a general HOCON parser, it only accepts the subset the Python client emits, plus comments:
+ * unquoted keys and values, dotted key paths, `:` or `{` after keys, newlines or commas between fields,
+ * double quoted strings and merging of objects at the same path.
+ * Substitutions, includes, `=` and `+=`, and triple quoted strings are rejected with `Error::HoconError`,
+ * `${...}` is only accepted inside quoted strings where it's literal text, e.g. the JDBC credentials.
+ * Configs generated by this crate are JSON, which is also accepted.
+ */
+pub(crate) fn parse(content: &str) -> Result<Value, Error> {
+    let mut parser = Parser {
+        chars: content.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    parser.skip_whitespaces();
+    let root = if parser.peek() == Some('{') {
+        parser.next();
+        parser.parse_fields(Some('}'))?
+    } else {
+        parser.parse_fields(None)?
+    };
+    parser.skip_whitespaces();
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected content after the root object"));
+    }
+    Ok(Value::Object(root))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> Error {
+        Error::HoconError(self.line, message.to_string())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_comment(&mut self) -> bool {
+        if self.peek() == Some('#') || self.starts_with("//") {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.next();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /**
+     * Skip spaces and comments on the current line
+     */
+    fn skip_spaces(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() && c != '\n' => {
+                    self.next();
+                }
+                _ => {
+                    if !self.skip_comment() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * Skip spaces, newlines and comments
+     */
+    fn skip_whitespaces(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.next();
+                }
+                _ => {
+                    if !self.skip_comment() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * Skip whitespaces and at most one comma between fields or array elements
+     */
+    fn skip_separator(&mut self) {
+        self.skip_whitespaces();
+        if self.peek() == Some(',') {
+            self.next();
+            self.skip_whitespaces();
+        }
+    }
+
+    fn parse_fields(&mut self, end: Option<char>) -> Result<Map<String, Value>, Error> {
+        let mut map = Map::new();
+        loop {
+            self.skip_separator();
+            match self.peek() {
+                None if end.is_none() => break,
+                None => return Err(self.error("unclosed object")),
+                c if c == end => {
+                    self.next();
+                    break;
+                }
+                _ => {}
+            }
+            let path = self.parse_key()?;
+            self.skip_spaces();
+            if path == ["include"] && self.peek() == Some('"') {
+                return Err(self.error("includes are not supported"));
+            }
+            let value = match self.peek() {
+                Some('{') => self.parse_value()?,
+                Some(':') => {
+                    self.next();
+                    self.parse_value()?
+                }
+                _ => return Err(self.error(&format!("expecting ':' or '{{' after key '{}'", path.join(".")))),
+            };
+            insert(&mut map, &path, value);
+        }
+        Ok(map)
+    }
+
+    fn parse_key(&mut self) -> Result<Vec<String>, Error> {
+        let mut path = vec![];
+        loop {
+            if self.peek() == Some('"') {
+                path.push(self.parse_quoted()?);
+            } else {
+                let mut segment = String::new();
+                while let Some(c) = self.peek() {
+                    if c == '.' || c == ':' || c == '{' || c == '"' || c.is_whitespace() {
+                        break;
+                    }
+                    segment.push(c);
+                    self.next();
+                }
+                if segment.is_empty() {
+                    return Err(self.error("expecting a key"));
+                }
+                path.push(segment);
+            }
+            if self.peek() == Some('.') {
+                self.next();
+            } else {
+                break;
+            }
+        }
+        Ok(path)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, Error> {
+        self.skip_whitespaces();
+        match self.peek() {
+            Some('{') => {
+                self.next();
+                Ok(Value::Object(self.parse_fields(Some('}'))?))
+            }
+            Some('[') => {
+                self.next();
+                let mut values = vec![];
+                loop {
+                    self.skip_separator();
+                    match self.peek() {
+                        None => return Err(self.error("unclosed array")),
+                        Some(']') => {
+                            self.next();
+                            break;
+                        }
+                        _ => values.push(self.parse_value()?),
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            Some('"') if self.starts_with("\"\"\"") => Err(self.error("triple quoted strings are not supported")),
+            Some('"') => Ok(Value::String(self.parse_quoted()?)),
+            _ => self.parse_unquoted(),
+        }
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, Error> {
+        self.next();
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                None | Some('\n') => return Err(self.error("unclosed quoted string")),
+                Some('"') => break,
+                Some('\\') => match self.next() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some(c) => s.push(c),
+                    None => return Err(self.error("unclosed quoted string")),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+        Ok(s)
+    }
+
+    /**
+     * Unquoted values run to the end of the line or the next separator, `//` only starts a comment
+     * at the beginning of the value or after a whitespace so URLs can be left unquoted
+     */
+    fn parse_unquoted(&mut self) -> Result<Value, Error> {
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            let comment = c == '#'
+                || (self.starts_with("//") && s.chars().last().map(char::is_whitespace).unwrap_or(true));
+            if matches!(c, '\n' | ',' | '}' | ']') || comment {
+                break;
+            }
+            s.push(c);
+            self.next();
+        }
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(self.error("expecting a value"));
+        }
+        if s.contains("${") {
+            return Err(self.error(&format!("substitutions are not supported, quote '{}' to use it as text", s)));
+        }
+        Ok(match s {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "null" => Value::Null,
+            _ => s
+                .parse::<i64>()
+                .ok()
+                .map(Number::from)
+                .or_else(|| s.parse::<f64>().ok().and_then(Number::from_f64))
+                .map(Value::Number)
+                .unwrap_or_else(|| Value::String(s.to_string())),
+        })
+    }
+}
+
+/**
+ * Set the value at the dotted path, objects at the same path are merged, other values are replaced
+ */
+fn insert(map: &mut Map<String, Value>, path: &[String], value: Value) {
+    let (key, rest) = match path.split_first() {
+        Some(p) => p,
+        None => return,
+    };
+    if rest.is_empty() {
+        match (map.get_mut(key), value) {
+            (Some(Value::Object(existing)), Value::Object(new)) => {
+                for (k, v) in new {
+                    insert(existing, &[k], v);
+                }
+            }
+            (_, value) => {
+                map.insert(key.to_owned(), value);
+            }
+        }
+    } else {
+        let entry = map
+            .entry(key.to_owned())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !entry.is_object() {
+            *entry = Value::Object(Map::new());
+        }
+        if let Value::Object(child) = entry {
+            insert(child, rest, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse;
+    use crate::Error;
+
+    #[test]
+    fn parse_hocon() {
+        let conf = r#"
+            // Comment
+            anchors {
+                a1: {
+                    source: "s1"
+                    key: [c1, "c2"]
+                    features: {
+                        f1.def.sqlExpr: "a > 1"   # trailing comment
+                        f2: "multi\n\"line\" expr"
+                        f3: { def: count, window: 3d, limit: 10, enabled: true }
+                    }
+                }
+            }
+            sources.s1.location.path: wasbs://a@b.blob.core.windows.net/c.csv
+            sources.s1.location { format: csv }
+        "#;
+        assert_eq!(
+            parse(conf).unwrap(),
+            json!({
+                "anchors": {
+                    "a1": {
+                        "source": "s1",
+                        "key": ["c1", "c2"],
+                        "features": {
+                            "f1": { "def": { "sqlExpr": "a > 1" } },
+                            "f2": "multi\n\"line\" expr",
+                            "f3": { "def": "count", "window": "3d", "limit": 10, "enabled": true },
+                        }
+                    }
+                },
+                "sources": {
+                    "s1": {
+                        "location": {
+                            "path": "wasbs://a@b.blob.core.windows.net/c.csv",
+                            "format": "csv"
+                        }
+                    }
+                }
+            })
+        );
+        assert_eq!(parse("{ a: 1, b: [1, 2,], }").unwrap(), json!({"a": 1, "b": [1, 2]}));
+        assert!(matches!(parse("a {\n b: 1\n"), Err(Error::HoconError(3, _))));
+        assert!(matches!(parse("a: \"x\nb: 1"), Err(Error::HoconError(2, _))));
+        assert_eq!(parse("user: \"${s1_USER}\"").unwrap(), json!({"user": "${s1_USER}"}));
+    }
+
+    #[test]
+    fn unsupported_hocon() {
+        for conf in [
+            "a = 1",
+            "a += 1",
+            "a: ${b}",
+            "include \"other.conf\"",
+            "a: \"\"\"text\"\"\"",
+        ] {
+            assert!(matches!(parse(conf), Err(Error::HoconError(1, _))), "{}", conf);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use serde_json::Value;
+
+use crate::Error;
+
+/**
+ * Spark SQL keywords that can't be used as bare column names
+ */
+const RESERVED_WORDS: &[&str] = &[
+    "ALL", "AND", "ANY", "AS", "AUTHORIZATION", "BOTH", "CASE", "CAST", "CHECK", "COLLATE", "COLUMN",
+    "CONSTRAINT", "CREATE", "CROSS", "CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP", "CURRENT_USER",
+    "DISTINCT", "ELSE", "END", "ESCAPE", "EXCEPT", "FALSE", "FETCH", "FILTER", "FOR", "FOREIGN", "FROM",
+    "FULL", "GRANT", "GROUP", "HAVING", "IN", "INNER", "INTERSECT", "INTO", "IS", "JOIN", "LEADING", "LEFT",
+    "NATURAL", "NOT", "NULL", "OFFSET", "ON", "ONLY", "OR", "ORDER", "OUTER", "OVERLAPS", "PRIMARY",
+    "REFERENCES", "RIGHT", "SELECT", "SESSION_USER", "SOME", "TABLE", "THEN", "TIME", "TO", "TRAILING",
+    "TRUE", "UNION", "UNIQUE", "UNKNOWN", "USER", "USING", "WHEN", "WHERE", "WITH",
+];
+
+fn is_plain_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !RESERVED_WORDS.contains(&s.to_ascii_uppercase().as_str())
+}
+
+fn is_quoted(s: &str) -> bool {
+    s.len() >= 2
+        && s.starts_with('`')
+        && s.ends_with('`')
+        && s[1..s.len() - 1].replace("``", "").find('`').is_none()
+}
+
+/**
+ * Quote the key column or alias with backticks if it is a column name with spaces or a reserved word,
+ * other SQL expressions and quoted names are returned unchanged.
+ * `feature` is only used in the error message.
+ */
+pub(crate) fn quote_identifier(feature: &str, name: &str) -> Result<String, Error> {
+    let invalid = |reason: &str| {
+        Err(Error::InvalidIdentifier(
+            feature.to_string(),
+            name.to_string(),
+            reason.to_string(),
+        ))
+    };
+    if name.trim().is_empty() {
+        return invalid("identifier is empty");
+    }
+    if is_quoted(name) || is_plain_identifier(name) {
+        return Ok(name.to_string());
+    }
+    check_backticks(feature, name)?;
+    if name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ' ') {
+        if name.trim() != name {
+            return invalid("identifier has leading or trailing spaces");
+        }
+        return Ok(format!("`{}`", name));
+    }
+    Ok(name.to_string())
+}
+
+/**
+ * SQL expressions must not have unterminated backtick-quoted names, backticks in string literals are ignored
+ */
+pub(crate) fn check_backticks(feature: &str, expr: &str) -> Result<(), Error> {
+    // The quote character of the string literal or quoted name we're in
+    let mut quote: Option<char> = None;
+    let mut chars = expr.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            // Spark string literals escape with backslashes
+            (Some('\'') | Some('"'), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            _ => {}
+        }
+    }
+    if quote == Some('`') {
+        return Err(Error::InvalidIdentifier(
+            feature.to_string(),
+            expr.to_string(),
+            "unbalanced backtick".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn quote_list(feature: &str, list: Option<&mut Value>) -> Result<(), Error> {
+    for v in list.and_then(Value::as_array_mut).into_iter().flatten() {
+        if let Some(s) = v.as_str() {
+            *v = Value::String(quote_identifier(feature, s)?);
+        }
+    }
+    Ok(())
+}
+
+fn quote_transform(feature: &str, f: &mut Value) -> Result<(), Error> {
+    let exprs = [
+        f.pointer("/def/sqlExpr"),
+        f.get("def"),
+        f.get("definition"),
+        f.get("filter"),
+    ];
+    for expr in exprs.into_iter().flatten().filter_map(Value::as_str) {
+        check_backticks(feature, expr)?;
+    }
+    if let Some(v) = f.get_mut("group_by") {
+        if let Some(s) = v.as_str() {
+            *v = Value::String(quote_identifier(feature, s)?);
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Quote key columns, key aliases and group-by columns in the rendered feature config,
+ * errors point at the anchor group or feature that has the invalid identifier
+ */
+pub(crate) fn quote_feature_config(config: &mut Value) -> Result<(), Error> {
+    let anchors = config.get_mut("anchors").and_then(Value::as_object_mut);
+    for (group, anchor) in anchors.into_iter().flatten() {
+        quote_list(group, anchor.pointer_mut("/key/sqlExpr"))?;
+        let features = anchor.get_mut("features").and_then(Value::as_object_mut);
+        for (name, f) in features.into_iter().flatten() {
+            quote_transform(name, f)?;
+        }
+    }
+    let derivations = config.get_mut("derivations").and_then(Value::as_object_mut);
+    for (name, f) in derivations.into_iter().flatten() {
+        quote_list(name, f.get_mut("key"))?;
+        quote_transform(name, f)?;
+        let inputs = f.get_mut("inputs").and_then(Value::as_object_mut);
+        for (alias, input) in inputs.into_iter().flatten() {
+            if !is_plain_identifier(alias) {
+                return Err(Error::InvalidIdentifier(
+                    name.to_owned(),
+                    alias.to_owned(),
+                    "input alias must be a plain column name".to_string(),
+                ));
+            }
+            quote_list(name, input.get_mut("key"))?;
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Quote observation key columns in the rendered join config
+ */
+pub(crate) fn quote_join_config(config: &mut Value) -> Result<(), Error> {
+    let queries = config.get_mut("featureList").and_then(Value::as_array_mut);
+    for q in queries.into_iter().flatten() {
+        let feature = q
+            .get("featureList")
+            .and_then(Value::as_array)
+            .and_then(|l| l.first())
+            .and_then(|f| f.as_str().or_else(|| f.get("name").and_then(Value::as_str)))
+            .unwrap_or_default()
+            .to_string();
+        quote_list(&feature, q.get_mut("key"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn quote_identifiers() {
+        assert_eq!(quote_identifier("f", "trip_id").unwrap(), "trip_id");
+        assert_eq!(quote_identifier("f", "trip id").unwrap(), "`trip id`");
+        assert_eq!(quote_identifier("f", "order").unwrap(), "`order`");
+        assert_eq!(quote_identifier("f", "1st").unwrap(), "`1st`");
+        assert_eq!(quote_identifier("f", "`trip id`").unwrap(), "`trip id`");
+        assert_eq!(quote_identifier("f", "concat(a, b)").unwrap(), "concat(a, b)");
+        assert_eq!(quote_identifier("f", "a.b").unwrap(), "a.b");
+        assert!(matches!(
+            quote_identifier("f", " "),
+            Err(Error::InvalidIdentifier(f, _, _)) if f == "f"
+        ));
+        assert!(quote_identifier("f", "trip id ").is_err());
+        assert!(quote_identifier("f", "`trip id").is_err());
+
+        let mut config = json!({
+            "anchors": {
+                "g": {
+                    "key": {"sqlExpr": ["trip id"]},
+                    "features": {"f": {"def": "x", "aggregation": "SUM", "group_by": "select"}}
+                }
+            },
+            "derivations": {
+                "d": {"key": ["trip id"], "definition": "f", "inputs": {"f": {"key": ["trip id"], "feature": "f"}}}
+            }
+        });
+        quote_feature_config(&mut config).unwrap();
+        assert_eq!(config["anchors"]["g"]["key"]["sqlExpr"][0], "`trip id`");
+        assert_eq!(config["anchors"]["g"]["features"]["f"]["group_by"], "`select`");
+        assert_eq!(config["derivations"]["d"]["key"][0], "`trip id`");
+        assert_eq!(config["derivations"]["d"]["inputs"]["f"]["key"][0], "`trip id`");
+
+        let mut config = json!({"derivations": {"d": {"key": ["k"], "definition": "`a b", "inputs": {}}}});
+        assert!(matches!(
+            quote_feature_config(&mut config),
+            Err(Error::InvalidIdentifier(f, _, _)) if f == "d"
+        ));
+
+        let mut config = json!({"featureList": [{"featureList": ["f"], "key": ["user id"]}]});
+        quote_join_config(&mut config).unwrap();
+        assert_eq!(config["featureList"][0]["key"][0], "`user id`");
+    }
+
+    #[test]
+    fn backticks_in_literals() {
+        assert!(check_backticks("f", "concat(`a b`, 'x`y')").is_ok());
+        assert!(check_backticks("f", r#"replace(name, "`", '')"#).is_ok());
+        assert!(check_backticks("f", r"concat('it\'s `', `a b`)").is_ok());
+        assert!(check_backticks("f", "`a ``b`` c`").is_ok());
+        assert!(check_backticks("f", "concat('x', `a b)").is_err());
+        assert!(check_backticks("f", "`a 'b` + `c").is_err());
+    }
+}
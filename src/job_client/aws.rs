@@ -0,0 +1,584 @@
+use std::{collections::BTreeMap, time::SystemTime};
+
+use aws_credential_types::Credentials;
+use aws_sigv4::{
+    http_request::{
+        sign, PayloadChecksumKind, PercentEncodingMode, SignableBody, SignableRequest,
+        SigningSettings, UriPathNormalizationMode,
+    },
+    sign::v4,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use log::debug;
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+
+use crate::{Error, RemoteFile};
+
+pub(crate) const S3_SCHEMES: [&str; 3] = ["s3://", "s3a://", "s3n://"];
+
+/**
+ * AWS access key, the session token is needed if the key is temporary
+ */
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish()
+    }
+}
+
+/**
+ * Split `s3://bucket/key` into the bucket and the key, `s3a` and `s3n` are the same storage
+ */
+pub(crate) fn parse_s3_url(url: &str) -> Result<(&str, &str), Error> {
+    let rest = S3_SCHEMES
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))
+        .ok_or_else(|| Error::InvalidUrl(url.to_string()))?;
+    let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+        return Err(Error::InvalidUrl(url.to_string()));
+    }
+    Ok((bucket, key))
+}
+
+/**
+ * Prefix of all objects under the directory
+ */
+fn dir_prefix(key: &str) -> String {
+    let key = key.trim_end_matches('/');
+    if key.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", key)
+    }
+}
+
+/**
+ * Percent-encode everything except the unreserved characters, as required by the signature
+ */
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b'/' if !encode_slash => "/".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/**
+ * AWS REST request, signed with Signature Version 4
+ */
+pub(crate) struct AwsRequest {
+    pub(crate) method: Method,
+    /**
+     * Scheme, host and optional port, e.g. `https://elasticmapreduce.us-west-2.amazonaws.com`
+     */
+    pub(crate) endpoint: String,
+    /**
+     * URI encoded path
+     */
+    pub(crate) path: String,
+    pub(crate) query: Vec<(String, String)>,
+    /**
+     * Lowercase header names
+     */
+    pub(crate) headers: BTreeMap<String, String>,
+    pub(crate) body: Bytes,
+}
+
+impl AwsRequest {
+    pub(crate) fn new(method: Method, endpoint: &str, path: &str) -> Self {
+        Self {
+            method,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            path: path.to_string(),
+            query: vec![],
+            headers: Default::default(),
+            body: Bytes::new(),
+        }
+    }
+
+    fn query_string(&self) -> String {
+        let mut query: Vec<String> = self
+            .query
+            .iter()
+            .map(|(k, v)| format!("{}={}", uri_encode(k, true), uri_encode(v, true)))
+            .collect();
+        query.sort();
+        query.join("&")
+    }
+
+    pub(crate) fn url(&self) -> String {
+        let query = self.query_string();
+        if query.is_empty() {
+            format!("{}{}", self.endpoint, self.path)
+        } else {
+            format!("{}{}?{}", self.endpoint, self.path, query)
+        }
+    }
+
+    /**
+     * Add the date, content hash and authorization headers
+     */
+    fn sign(
+        &mut self,
+        credentials: &AwsCredentials,
+        region: &str,
+        service: &str,
+        time: SystemTime,
+    ) -> Result<(), Error> {
+        let identity = Credentials::new(
+            &credentials.access_key_id,
+            &credentials.secret_access_key,
+            credentials.session_token.clone(),
+            None,
+            "feathr",
+        )
+        .into();
+        let mut settings = SigningSettings::default();
+        if service == "s3" {
+            // S3 paths are encoded once and not normalized, and the payload hash is sent in a header
+            settings.percent_encoding_mode = PercentEncodingMode::Single;
+            settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+            settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+        }
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(region)
+            .name(service)
+            .time(time)
+            .settings(settings)
+            .build()
+            .map_err(|e| Error::RuntimeError(format!("Failed to sign AWS request, {}", e)))?
+            .into();
+        let url = self.url();
+        let request = SignableRequest::new(
+            self.method.as_str(),
+            url,
+            self.headers.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+            SignableBody::Bytes(&self.body),
+        )
+        .and_then(|request| sign(request, &params))
+        .map_err(|e| Error::RuntimeError(format!("Failed to sign AWS request, {}", e)))?;
+        let (instructions, _) = request.into_parts();
+        for (name, value) in instructions.headers() {
+            self.headers.insert(name.to_string(), value.to_string());
+        }
+        Ok(())
+    }
+
+    /**
+     * Sign and send the request, returns `None` if the resource doesn't exist
+     */
+    pub(crate) async fn send(
+        mut self,
+        client: &reqwest::Client,
+        credentials: &AwsCredentials,
+        region: &str,
+        service: &str,
+    ) -> Result<Option<reqwest::Response>, Error> {
+        self.sign(credentials, region, service, SystemTime::now())?;
+        let url = self.url();
+        debug!("AWS request: {} {}", self.method, url);
+        let mut builder = client.request(self.method.clone(), &url);
+        for (name, value) in self.headers.iter() {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let resp = builder.body(self.body).send().await?;
+        let status = resp.status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(Error::AwsHttpError(url, status, resp.text().await?));
+        }
+        Ok(Some(resp))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct S3Object {
+    key: String,
+    last_modified: DateTime<Utc>,
+    size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CommonPrefix {
+    prefix: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListBucketResult {
+    #[serde(default)]
+    contents: Vec<S3Object>,
+    #[serde(default)]
+    common_prefixes: Vec<CommonPrefix>,
+    next_continuation_token: Option<String>,
+}
+
+/**
+ * Files on S3, or on S3 compatible object stores such as MinIO if the endpoint is set
+ */
+#[derive(Debug)]
+pub(crate) struct S3Storage {
+    client: reqwest::Client,
+    credentials: AwsCredentials,
+    region: String,
+    endpoint: Option<String>,
+}
+
+impl S3Storage {
+    pub(crate) fn new(client: reqwest::Client, credentials: AwsCredentials, region: &str) -> Self {
+        Self {
+            client,
+            credentials,
+            region: region.to_string(),
+            endpoint: None,
+        }
+    }
+
+    /**
+     * Path-style requests are sent to the endpoint instead of the virtual-hosted AWS endpoints
+     */
+    pub(crate) fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.trim_end_matches('/').to_string());
+        self
+    }
+
+    pub(crate) fn set_client(&mut self, client: reqwest::Client) {
+        self.client = client;
+    }
+
+    pub(crate) fn set_region(&mut self, region: &str) {
+        self.region = region.to_string();
+    }
+
+    /**
+     * Virtual-hosted style unless the endpoint is set, or the bucket name has dots, which don't
+     * match the wildcard certificate of the virtual hosts
+     */
+    fn request(&self, method: Method, bucket: &str, key: &str) -> AwsRequest {
+        match &self.endpoint {
+            Some(endpoint) => AwsRequest::new(
+                method,
+                endpoint,
+                &format!("/{}/{}", bucket, uri_encode(key, false)),
+            ),
+            None if bucket.contains('.') => AwsRequest::new(
+                method,
+                &format!("https://s3.{}.amazonaws.com", self.region),
+                &format!("/{}/{}", bucket, uri_encode(key, false)),
+            ),
+            None => AwsRequest::new(
+                method,
+                &format!("https://{}.s3.{}.amazonaws.com", bucket, self.region),
+                &format!("/{}", uri_encode(key, false)),
+            ),
+        }
+    }
+
+    async fn send(&self, request: AwsRequest) -> Result<Option<reqwest::Response>, Error> {
+        request
+            .send(&self.client, &self.credentials, &self.region, "s3")
+            .await
+    }
+
+    /**
+     * All objects under the prefix, and the sub directories if `delimiter` is set
+     */
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        delimiter: Option<&str>,
+    ) -> Result<(Vec<S3Object>, Vec<String>), Error> {
+        let mut objects = vec![];
+        let mut prefixes = vec![];
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut request = self.request(Method::GET, bucket, "");
+            request.query = [("list-type", "2"), ("prefix", prefix)]
+                .into_iter()
+                .chain(delimiter.map(|d| ("delimiter", d)))
+                .chain(continuation_token.as_deref().map(|t| ("continuation-token", t)))
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            let resp = match self.send(request).await? {
+                Some(resp) => resp.text().await?,
+                None => return Err(Error::InvalidUrl(format!("s3://{}", bucket))),
+            };
+            let result: ListBucketResult = serde_xml_rs::from_str(&resp)
+                .map_err(|e| Error::RuntimeError(format!("Invalid S3 response, {}", e)))?;
+            objects.extend(result.contents);
+            prefixes.extend(result.common_prefixes.into_iter().map(|p| p.prefix));
+            continuation_token = result.next_continuation_token;
+            if continuation_token.is_none() {
+                return Ok((objects, prefixes));
+            }
+        }
+    }
+
+    pub(crate) async fn write(&self, path: &str, content: &[u8]) -> Result<(), Error> {
+        let (bucket, key) = parse_s3_url(path)?;
+        let mut request = self.request(Method::PUT, bucket, key);
+        request.body = Bytes::copy_from_slice(content);
+        self.send(request)
+            .await?
+            .ok_or_else(|| Error::InvalidUrl(path.to_string()))?;
+        Ok(())
+    }
+
+    /**
+     * Content of the object, `None` if it doesn't exist
+     */
+    pub(crate) async fn get(&self, path: &str) -> Result<Option<Bytes>, Error> {
+        let (bucket, key) = parse_s3_url(path)?;
+        match self.send(self.request(Method::GET, bucket, key)).await? {
+            Some(resp) => Ok(Some(resp.bytes().await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) async fn read(&self, path: &str) -> Result<Bytes, Error> {
+        self.get(path).await?.ok_or_else(|| {
+            Error::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                path.to_string(),
+            ))
+        })
+    }
+
+    /**
+     * Read at most `length` bytes of the object from `offset` with a `Range` request
+     */
+    pub(crate) async fn read_range(&self, path: &str, offset: u64, length: u64) -> Result<Bytes, Error> {
+        if length == 0 {
+            return Ok(Bytes::new());
+        }
+        let (bucket, key) = parse_s3_url(path)?;
+        let mut request = self.request(Method::GET, bucket, key);
+        request.headers.insert(
+            "range".to_string(),
+            format!("bytes={}-{}", offset, offset.saturating_add(length) - 1),
+        );
+        match self.send(request).await? {
+            Some(resp) => Ok(resp.bytes().await?),
+            None => Err(Error::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                path.to_string(),
+            ))),
+        }
+    }
+
+    pub(crate) async fn last_modified(&self, path: &str) -> Result<Option<DateTime<Utc>>, Error> {
+        let (bucket, key) = parse_s3_url(path)?;
+        if !key.is_empty() && !key.ends_with('/') {
+            if let Some(resp) = self.send(self.request(Method::HEAD, bucket, key)).await? {
+                return Ok(resp
+                    .headers()
+                    .get(reqwest::header::LAST_MODIFIED)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+                    .map(|t| t.with_timezone(&Utc)));
+            }
+        }
+        // S3 directories are only prefixes of the objects
+        let (objects, _) = self.list_objects(bucket, &dir_prefix(key), None).await?;
+        Ok(objects.into_iter().map(|o| o.last_modified).max())
+    }
+
+    pub(crate) async fn list_dir(&self, path: &str) -> Result<Vec<RemoteFile>, Error> {
+        let dir = path.trim_end_matches('/');
+        let (bucket, key) = parse_s3_url(dir)?;
+        let prefix = dir_prefix(key);
+        let (objects, prefixes) = self.list_objects(bucket, &prefix, Some("/")).await?;
+        let mut ret: Vec<RemoteFile> = objects
+            .into_iter()
+            // Skip the directory marker created by some tools
+            .filter(|o| o.key.len() > prefix.len())
+            .map(|o| RemoteFile {
+                url: format!("{}/{}", dir, &o.key[prefix.len()..]),
+                size: o.size,
+                is_dir: false,
+            })
+            .chain(prefixes.into_iter().map(|p| RemoteFile {
+                url: format!("{}/{}", dir, p[prefix.len()..].trim_end_matches('/')),
+                size: 0,
+                is_dir: true,
+            }))
+            .collect();
+        ret.sort_by(|a, b| a.url.cmp(&b.url));
+        Ok(ret)
+    }
+
+    /**
+     * Delete the object, or all objects under the directory
+     */
+    pub(crate) async fn delete(&self, path: &str) -> Result<(), Error> {
+        let (bucket, key) = parse_s3_url(path)?;
+        let (objects, _) = self.list_objects(bucket, &dir_prefix(key), None).await?;
+        for key in objects.iter().map(|o| o.key.as_str()).chain([key]) {
+            // Deleting a missing object is not an error on S3
+            self.send(self.request(Method::DELETE, bucket, key)).await?;
+        }
+        Ok(())
+    }
+
+    /**
+     * S3 has no rename, the object, or all objects under the directory, are copied and then deleted
+     */
+    pub(crate) async fn rename(&self, from: &str, to: &str) -> Result<(), Error> {
+        let (bucket, key) = parse_s3_url(from)?;
+        let (to_bucket, to_key) = parse_s3_url(to)?;
+        let key = key.trim_end_matches('/');
+        let to_key = to_key.trim_end_matches('/');
+        let prefix = dir_prefix(key);
+        let (objects, _) = self.list_objects(bucket, key, None).await?;
+        for object in objects.iter().map(|o| o.key.as_str()) {
+            let target = if object == key {
+                to_key.to_string()
+            } else if let Some(name) = object.strip_prefix(&prefix) {
+                format!("{}{}", dir_prefix(to_key), name)
+            } else {
+                // Sibling objects sharing the prefix, e.g. `dir2` of `dir`
+                continue;
+            };
+            let mut request = self.request(Method::PUT, to_bucket, &target);
+            request.headers.insert(
+                "x-amz-copy-source".to_string(),
+                format!("/{}/{}", bucket, uri_encode(object, false)),
+            );
+            self.send(request)
+                .await?
+                .ok_or_else(|| Error::InvalidUrl(from.to_string()))?;
+            self.send(self.request(Method::DELETE, bucket, object)).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    pub(crate) fn credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        }
+    }
+
+    #[test]
+    fn signature_v4() {
+        // `get-vanilla` from the AWS Signature Version 4 test suite
+        let mut request = AwsRequest::new(Method::GET, "https://example.amazonaws.com", "/");
+        request.sign(
+            &credentials(),
+            "us-east-1",
+            "service",
+            Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap().into(),
+        )
+        .unwrap();
+        assert_eq!(
+            request.headers["authorization"],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+            SignedHeaders=host;x-amz-date, \
+            Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+        assert_eq!(format!("{:?}", credentials()), r#"AwsCredentials { access_key_id: "AKIDEXAMPLE" }"#);
+    }
+
+    #[test]
+    fn s3_urls() {
+        let storage = S3Storage::new(reqwest::Client::new(), credentials(), "us-west-2");
+        let (bucket, key) = parse_s3_url("s3://bucket/feathr/job a.conf").unwrap();
+        let mut request = storage.request(Method::GET, bucket, "");
+        request.query = vec![
+            ("prefix".to_string(), dir_prefix(key)),
+            ("list-type".to_string(), "2".to_string()),
+        ];
+        assert_eq!(
+            request.url(),
+            "https://bucket.s3.us-west-2.amazonaws.com/?list-type=2&prefix=feathr%2Fjob%20a.conf%2F"
+        );
+        assert_eq!(
+            storage.request(Method::GET, bucket, key).url(),
+            "https://bucket.s3.us-west-2.amazonaws.com/feathr/job%20a.conf"
+        );
+        let minio = storage.endpoint("http://minio:9000/");
+        let request = minio.request(Method::GET, bucket, key);
+        assert_eq!(request.url(), "http://minio:9000/bucket/feathr/job%20a.conf");
+        let dotted = S3Storage::new(reqwest::Client::new(), credentials(), "us-west-2");
+        assert_eq!(
+            dotted.request(Method::GET, "my.bucket", key).url(),
+            "https://s3.us-west-2.amazonaws.com/my.bucket/feathr/job%20a.conf"
+        );
+        assert!(parse_s3_url("s3:///key").is_err());
+        assert!(parse_s3_url("hdfs:///feathr").is_err());
+    }
+
+    #[test]
+    fn s3_list_objects() {
+        let resp = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+                <Name>bucket</Name>
+                <Prefix>feathr/output/</Prefix>
+                <KeyCount>3</KeyCount>
+                <MaxKeys>1000</MaxKeys>
+                <Delimiter>/</Delimiter>
+                <IsTruncated>true</IsTruncated>
+                <Contents>
+                    <Key>feathr/output/_SUCCESS</Key>
+                    <LastModified>2022-07-01T10:00:00.000Z</LastModified>
+                    <ETag>"d41d8cd98f00b204e9800998ecf8427e"</ETag>
+                    <Size>0</Size>
+                    <StorageClass>STANDARD</StorageClass>
+                </Contents>
+                <Contents>
+                    <Key>feathr/output/part-00000.avro</Key>
+                    <LastModified>2022-07-02T10:00:00.000Z</LastModified>
+                    <ETag>"9b2cf535f27731c974343645a3985328"</ETag>
+                    <Size>24930</Size>
+                    <StorageClass>STANDARD</StorageClass>
+                </Contents>
+                <CommonPrefixes>
+                    <Prefix>feathr/output/2022/</Prefix>
+                </CommonPrefixes>
+                <NextContinuationToken>1ueGcxLPRx1Tr</NextContinuationToken>
+            </ListBucketResult>"#;
+        let result: ListBucketResult = serde_xml_rs::from_str(resp).unwrap();
+        assert_eq!(result.contents.len(), 2);
+        assert_eq!(result.contents[1].key, "feathr/output/part-00000.avro");
+        assert_eq!(result.contents[1].size, 24930);
+        assert_eq!(
+            result.contents[1].last_modified,
+            Utc.with_ymd_and_hms(2022, 7, 2, 10, 0, 0).unwrap()
+        );
+        assert_eq!(result.common_prefixes[0].prefix, "feathr/output/2022/");
+        assert_eq!(result.next_continuation_token.as_deref(), Some("1ueGcxLPRx1Tr"));
+
+        let empty: ListBucketResult =
+            serde_xml_rs::from_str("<ListBucketResult><KeyCount>0</KeyCount></ListBucketResult>").unwrap();
+        assert!(empty.contents.is_empty() && empty.next_continuation_token.is_none());
+    }
+}
@@ -1,19 +1,22 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use async_trait::async_trait;
+use azure_core::error::ErrorKind;
 use azure_identity::DefaultAzureCredential;
 use azure_storage::storage_shared_key_credential::StorageSharedKeyCredential;
 use azure_storage_datalake::clients::{DataLakeClient, PathClient};
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
 use log::debug;
 use reqwest::Url;
 
 use crate::{
     livy_client::{
         AadAuthenticator, AzureSynapseClientBuilder, ClusterSize, LivyClient, LivyStates,
-        SparkRequest,
+        SparkJob, SparkRequest,
     },
-    JobClient, JobId, JobStatus, Logged, VarSource,
+    ExistsCache, JobClient, JobId, JobRunInfo, JobStatus, JobSummary, Logged, RemoteFile, Timeouts, VarSource,
 };
 
 static NOOP_JAR: &'static [u8] = include_bytes!("../../template/noop-1.0.jar");
@@ -26,6 +29,8 @@ pub struct AzureSynapseClient {
     container: String,
     workspace_dir: String,
     maven_artifact: String,
+    timeouts: Timeouts,
+    exists_cache: ExistsCache,
 }
 
 impl AzureSynapseClient {
@@ -54,6 +59,8 @@ impl AzureSynapseClient {
             container: container.to_string(),
             workspace_dir: workspace_dir.to_string(),
             maven_artifact: super::FEATHR_MAVEN_ARTIFACT.to_string(),
+            timeouts: Default::default(),
+            exists_cache: Default::default(),
         })
     }
 
@@ -65,7 +72,7 @@ impl AzureSynapseClient {
                 .get_environment_variable(&["spark_config", "azure_synapse", "workspace_dir"])
                 .await?,
         )?;
-        Ok(Self {
+        Self {
             livy_client: AzureSynapseClientBuilder::default()
                 .url(
                     var_source
@@ -102,7 +109,10 @@ impl AzureSynapseClient {
                     }
                 })
                 .unwrap_or(super::FEATHR_MAVEN_ARTIFACT.to_string()),
-        })
+            timeouts: Default::default(),
+            exists_cache: Default::default(),
+        }
+        .timeouts(&Timeouts::from_var_source(&var_source).await?)
     }
 
     pub fn default() -> Result<Self, crate::Error> {
@@ -124,13 +134,21 @@ impl AzureSynapseClient {
             container,
             workspace_dir: workspace_dir.trim_start_matches("/").to_string(),
             maven_artifact: super::FEATHR_MAVEN_ARTIFACT.to_string(),
+            timeouts: Default::default(),
+            exists_cache: Default::default(),
         })
     }
-}
 
-#[async_trait]
-impl JobClient for AzureSynapseClient {
-    async fn write_remote_file(&self, path: &str, content: &[u8]) -> Result<String, crate::Error> {
+    /**
+     * Livy calls are limited by the API timeout, ADLS operations by the transfer timeout
+     */
+    pub fn timeouts(mut self, timeouts: &Timeouts) -> Result<Self, crate::Error> {
+        self.livy_client.set_http_client(timeouts.api_client()?.build()?);
+        self.timeouts = *timeouts;
+        Ok(self)
+    }
+
+    async fn adls_write(&self, path: &str, content: &[u8]) -> Result<String, crate::Error> {
         let (container, _, path) = parse_abfs(path)?;
         debug!("Container: {}", container);
         debug!("Path: {}", path);
@@ -157,6 +175,140 @@ impl JobClient for AzureSynapseClient {
         http_to_abfs(file_client.url().log()?)
     }
 
+    async fn adls_read(&self, url: &str) -> Result<Bytes, crate::Error> {
+        let (container, _, dir) = parse_abfs(url)?;
+        debug!("Container: {}", container);
+        debug!("Path: {}", dir);
+        let fs_client = self
+            .storage_client
+            .clone()
+            .into_file_system_client(container);
+        let file_client = fs_client.get_file_client(dir);
+        Ok(file_client.read().into_future().await?.data)
+    }
+
+    async fn adls_read_range(&self, url: &str, offset: u64, length: u64) -> Result<Bytes, crate::Error> {
+        let (container, _, dir) = parse_abfs(url)?;
+        let fs_client = self
+            .storage_client
+            .clone()
+            .into_file_system_client(container);
+        let file_client = fs_client.get_file_client(dir);
+        Ok(file_client
+            .read()
+            .range(azure_core::prelude::Range::new(offset, offset.saturating_add(length)))
+            .into_future()
+            .await?
+            .data)
+    }
+
+    async fn adls_last_modified(&self, url: &str) -> Result<Option<DateTime<Utc>>, crate::Error> {
+        let (container, _, dir) = parse_abfs(url)?;
+        let fs_client = self
+            .storage_client
+            .clone()
+            .into_file_system_client(container);
+        let mut latest = match fs_client
+            .get_file_client(dir.clone())
+            .get_properties()
+            .into_future()
+            .await
+        {
+            Ok(props) => props.last_modified,
+            Err(e) if matches!(e.kind(), ErrorKind::HttpResponse { status: 404, .. }) => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        // Listing fails if the path is a file rather than a directory
+        let mut pages = fs_client
+            .list_paths()
+            .directory(dir)
+            .recursive(true)
+            .into_stream();
+        while let Some(page) = pages.next().await {
+            match page {
+                Ok(page) => {
+                    for path in page.paths {
+                        latest = latest.max(path.last_modified);
+                    }
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::HttpResponse { .. }) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(Some(latest))
+    }
+
+    async fn adls_list(&self, url: &str) -> Result<Vec<RemoteFile>, crate::Error> {
+        let (container, account, dir) = parse_abfs(url)?;
+        let fs_client = self
+            .storage_client
+            .clone()
+            .into_file_system_client(container.clone());
+        let mut pages = fs_client
+            .list_paths()
+            .directory(dir.trim_end_matches('/').to_string())
+            .recursive(false)
+            .into_stream();
+        let mut ret = vec![];
+        while let Some(page) = pages.next().await {
+            match page {
+                Ok(page) => ret.extend(page.paths.into_iter().map(|path| RemoteFile {
+                    url: format!("abfss://{}@{}.dfs.core.windows.net/{}", container, account, path.name),
+                    size: path.content_length.max(0) as u64,
+                    is_dir: path.is_directory,
+                })),
+                Err(e) if matches!(e.kind(), ErrorKind::HttpResponse { status: 404, .. }) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        ret.sort_by(|a, b| a.url.cmp(&b.url));
+        Ok(ret)
+    }
+
+    async fn adls_delete(&self, url: &str) -> Result<(), crate::Error> {
+        let (container, _, dir) = parse_abfs(url)?;
+        let fs_client = self
+            .storage_client
+            .clone()
+            .into_file_system_client(container);
+        match fs_client.get_directory_client(dir).delete(true).into_future().await {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.kind(), ErrorKind::HttpResponse { status: 404, .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn adls_rename(&self, from: &str, to: &str) -> Result<(), crate::Error> {
+        let (container, _, dir) = parse_abfs(from)?;
+        let (to_container, _, to_dir) = parse_abfs(to)?;
+        if container != to_container {
+            return Err(crate::Error::InvalidUrl(to.to_string()));
+        }
+        let fs_client = self
+            .storage_client
+            .clone()
+            .into_file_system_client(container);
+        match fs_client.get_directory_client(dir).rename(to_dir).into_future().await {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.kind(), ErrorKind::HttpResponse { status: 404, .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl JobClient for AzureSynapseClient {
+    async fn write_remote_file(&self, path: &str, content: &[u8]) -> Result<String, crate::Error> {
+        let start = Instant::now();
+        self.exists_cache.invalidate(path);
+        let url = crate::runtime::timeout(self.timeouts.transfer, self.adls_write(path, content)).await??;
+        self.exists_cache.invalidate(&url);
+        super::manifest::record_upload(&url, content, start.elapsed());
+        Ok(url)
+    }
+
     async fn submit_job(
         &self,
         var_source: Arc<dyn VarSource + Send + Sync>,
@@ -228,6 +380,7 @@ impl JobClient for AzureSynapseClient {
         debug!("Main executable file: {}", executable);
 
         let mut conf = request.configuration;
+        conf.extend(super::env_var_conf(&request.env_vars));
         if main_jar_path.is_none() {
             let v = match conf.get("spark.jars.packages") {
                 Some(v) => format!("{},{}", v, self.maven_artifact),
@@ -263,6 +416,36 @@ impl JobClient for AzureSynapseClient {
         Ok(self.livy_client.get_batch_job(job_id.0).await?.state.into())
     }
 
+    async fn cancel_job(&self, job_id: JobId) -> Result<(), crate::Error> {
+        Ok(self.livy_client.cancel_batch_job(job_id.0).await?)
+    }
+
+    async fn list_jobs(&self, limit: usize) -> Result<Vec<JobSummary>, crate::Error> {
+        Ok(self
+            .livy_client
+            .get_recent_batch_jobs(limit)
+            .await?
+            .into_iter()
+            .map(|job| {
+                let run_info = run_info(JobId(job.id), &job);
+                JobSummary {
+                    job_id: JobId(job.id),
+                    name: job.name.unwrap_or_default(),
+                    status: job.state.into(),
+                    started_at: run_info.started_at,
+                    ended_at: run_info.ended_at,
+                    output: job.tags.as_ref().and_then(|t| t.get(super::OUTPUT_PATH_TAG).cloned()),
+                    idempotency_key: job
+                        .tags
+                        .as_ref()
+                        .and_then(|t| t.get(super::IDEMPOTENCY_KEY_TAG)?.parse().ok()),
+                    run_info: Some(run_info),
+                    cost: None,
+                }
+            })
+            .collect())
+    }
+
     async fn get_job_log(&self, job_id: JobId) -> Result<String, crate::Error> {
         Ok(self
             .livy_client
@@ -278,19 +461,48 @@ impl JobClient for AzureSynapseClient {
             .flatten())
     }
 
+    async fn get_job_run_info(&self, job_id: JobId) -> Result<JobRunInfo, crate::Error> {
+        Ok(run_info(job_id, &self.livy_client.get_batch_job(job_id.0).await?))
+    }
+
     async fn read_remote_file(&self, url: &str) -> Result<Bytes, crate::Error> {
-        let (container, _, dir) = parse_abfs(url)?;
-        debug!("Container: {}", container);
-        debug!("Path: {}", dir);
-        let fs_client = self
-            .storage_client
-            .clone()
-            .into_file_system_client(container);
-        let file_client = fs_client.get_file_client(dir);
-        Ok(file_client.read().into_future().await?.data)
+        crate::runtime::timeout(self.timeouts.transfer, self.adls_read(url)).await?
+    }
+
+    async fn read_remote_file_range(&self, url: &str, offset: u64, length: u64) -> Result<Bytes, crate::Error> {
+        if length == 0 {
+            return Ok(Bytes::new());
+        }
+        crate::runtime::timeout(self.timeouts.transfer, self.adls_read_range(url, offset, length)).await?
+    }
+
+    async fn get_remote_last_modified(&self, url: &str) -> Result<Option<DateTime<Utc>>, crate::Error> {
+        self.exists_cache
+            .get_or_lookup(url, || async {
+                crate::runtime::timeout(self.timeouts.transfer, self.adls_last_modified(url)).await?
+            })
+            .await
+    }
+
+    async fn list_remote_dir(&self, url: &str) -> Result<Vec<RemoteFile>, crate::Error> {
+        crate::runtime::timeout(self.timeouts.transfer, self.adls_list(url)).await?
+    }
+
+    async fn delete_remote_path(&self, url: &str) -> Result<(), crate::Error> {
+        self.exists_cache.invalidate(url);
+        crate::runtime::timeout(self.timeouts.transfer, self.adls_delete(url)).await?
+    }
+
+    async fn move_remote_path(&self, from: &str, to: &str) -> Result<(), crate::Error> {
+        self.exists_cache.invalidate(from);
+        self.exists_cache.invalidate(to);
+        crate::runtime::timeout(self.timeouts.transfer, self.adls_rename(from, to)).await?
     }
 
     fn get_remote_url(&self, filename: &str) -> String {
+        if let Some(url) = super::overridden_remote_url(filename) {
+            return url;
+        }
         format!(
             "abfss://{}@{}.dfs.core.windows.net/{}",
             self.container,
@@ -314,6 +526,19 @@ impl JobClient for AzureSynapseClient {
  * Convert Storage URL to Spark compatible format:
  * https://storage/container/path -> abfss://container@storage/path
  */
+fn run_info(job_id: JobId, job: &SparkJob) -> JobRunInfo {
+    let scheduler = job.scheduler_info.as_ref();
+    // Jobs are always submitted with the same cluster size, see `submit_job`
+    let size = ClusterSize::MEDIUM();
+    JobRunInfo {
+        job_id,
+        node_type: job.spark_pool_name.to_owned(),
+        num_nodes: size.num_executors as u32 + 1,
+        started_at: scheduler.and_then(|s| s.scheduled_at),
+        ended_at: scheduler.and_then(|s| s.ended_at),
+    }
+}
+
 fn http_to_abfs<T: AsRef<str>>(url: T) -> Result<String, crate::Error> {
     let url =
         Url::parse(url.as_ref()).map_err(|_| crate::Error::InvalidUrl(url.as_ref().to_string()))?;
@@ -372,7 +597,8 @@ impl Into<JobStatus> for LivyStates {
             | LivyStates::Running
             | LivyStates::Recovering
             | LivyStates::ShuttingDown => JobStatus::Running,
-            LivyStates::NotStarted | LivyStates::Starting => JobStatus::Starting,
+            LivyStates::NotStarted => JobStatus::Provisioning,
+            LivyStates::Starting => JobStatus::Starting,
             LivyStates::Success => JobStatus::Success,
         }
     }
@@ -0,0 +1,404 @@
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use log::warn;
+
+use crate::{Error, SubmitJobRequest, VarSource};
+
+use super::FEATHR_MAVEN_ARTIFACT;
+
+pub(crate) const CLIENT_VERSION_TAG: &str = "feathr_client_version";
+pub(crate) const CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/**
+ * Version of the Feathr Spark runtime
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RuntimeVersion(pub u32, pub u32, pub u32);
+
+impl FromStr for RuntimeVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidConfig(format!("Invalid runtime version '{}'", s));
+        // Ignore pre-release and build metadata, e.g. `0.7.2-rc1`
+        let parts: Vec<u32> = s
+            .trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|p| p.parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        match parts.as_slice() {
+            [major] => Ok(Self(*major, 0, 0)),
+            [major, minor] => Ok(Self(*major, *minor, 0)),
+            [major, minor, patch] => Ok(Self(*major, *minor, *patch)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Display for RuntimeVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+type Detector = fn(&SubmitJobRequest) -> bool;
+
+/**
+ * First runtime release whose feature config parser accepts the `offset` of derived feature inputs,
+ * older runtime fails the job with an unknown field in `inputs`
+ */
+const LAGGED_INPUT_MIN_RUNTIME: RuntimeVersion = RuntimeVersion(0, 9, 0);
+
+/**
+ * First runtime release with the `HDFS` sink in `operational.output` of the generation config,
+ * older runtime rejects the unknown sink name
+ */
+const HDFS_SINK_MIN_RUNTIME: RuntimeVersion = RuntimeVersion(0, 5, 0);
+
+/**
+ * First runtime release with the `MONITORING`, `COSMOSDB` and `JDBC` sinks
+ */
+const MONITORING_SINK_MIN_RUNTIME: RuntimeVersion = RuntimeVersion(0, 8, 0);
+const COSMOSDB_SINK_MIN_RUNTIME: RuntimeVersion = RuntimeVersion(0, 8, 0);
+const JDBC_SINK_MIN_RUNTIME: RuntimeVersion = RuntimeVersion(0, 8, 0);
+
+/**
+ * First runtime release with the `AEROSPIKE` and `KAFKA` sinks
+ */
+const AEROSPIKE_SINK_MIN_RUNTIME: RuntimeVersion = RuntimeVersion(0, 9, 0);
+const KAFKA_SINK_MIN_RUNTIME: RuntimeVersion = RuntimeVersion(0, 9, 0);
+
+/**
+ * First runtime release whose Redis sink accepts `encoding`, `keyPrefix`, `ttlSeconds` and `featureTtlSeconds`,
+ * older runtime ignores them and writes protobuf values without prefix and expiration
+ */
+const REDIS_OPTIONS_MIN_RUNTIME: RuntimeVersion = RuntimeVersion(0, 10, 0);
+
+/**
+ * Config constructs generated by this crate that are not understood by older runtime,
+ * each entry is the construct description, the minimal runtime version, and the detector.
+ * Update the versions along with the runtime release notes when a construct is backported.
+ */
+const RUNTIME_REQUIREMENTS: &[(&str, RuntimeVersion, Detector)] = &[
+    (
+        "Derived feature input with time offset",
+        LAGGED_INPUT_MIN_RUNTIME,
+        has_lagged_input,
+    ),
+    ("HDFS output sink", HDFS_SINK_MIN_RUNTIME, has_hdfs_sink),
+    ("Monitoring output sink", MONITORING_SINK_MIN_RUNTIME, has_monitoring_sink),
+    ("CosmosDB output sink", COSMOSDB_SINK_MIN_RUNTIME, has_cosmosdb_sink),
+    ("JDBC output sink", JDBC_SINK_MIN_RUNTIME, has_jdbc_sink),
+    ("Aerospike output sink", AEROSPIKE_SINK_MIN_RUNTIME, has_aerospike_sink),
+    ("Kafka output sink", KAFKA_SINK_MIN_RUNTIME, has_kafka_sink),
+    ("Redis sink encoding", REDIS_OPTIONS_MIN_RUNTIME, has_redis_encoding),
+    ("Redis sink key prefix", REDIS_OPTIONS_MIN_RUNTIME, has_redis_key_prefix),
+    ("Redis sink TTL", REDIS_OPTIONS_MIN_RUNTIME, has_redis_ttl),
+];
+
+fn has_lagged_input(request: &SubmitJobRequest) -> bool {
+    serde_json::from_str::<serde_json::Value>(&request.feature_config)
+        .ok()
+        .and_then(|conf| {
+            conf.get("derivations")?.as_object().map(|derivations| {
+                derivations.values().any(|d| {
+                    d.get("inputs")
+                        .and_then(|i| i.as_object())
+                        .map(|inputs| inputs.values().any(|i| i.get("offset").is_some()))
+                        .unwrap_or_default()
+                })
+            })
+        })
+        .unwrap_or_default()
+}
+
+/**
+ * Sinks in `operational.output` of the generation config
+ */
+fn output_sinks(request: &SubmitJobRequest) -> Vec<serde_json::Value> {
+    serde_json::from_str::<serde_json::Value>(&request.gen_job_config)
+        .ok()
+        .and_then(|mut conf| match conf.get_mut("operational")?.get_mut("output")?.take() {
+            serde_json::Value::Array(sinks) => Some(sinks),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+fn has_sink(request: &SubmitJobRequest, name: &str) -> bool {
+    output_sinks(request)
+        .iter()
+        .any(|s| s.get("name").and_then(|n| n.as_str()) == Some(name))
+}
+
+fn has_redis_params(request: &SubmitJobRequest, params: &[&str]) -> bool {
+    output_sinks(request).iter().any(|s| {
+        s.get("name").and_then(|n| n.as_str()) == Some("REDIS")
+            && params
+                .iter()
+                .any(|p| s.get("params").and_then(|v| v.get(p)).is_some())
+    })
+}
+
+fn has_hdfs_sink(request: &SubmitJobRequest) -> bool {
+    has_sink(request, "HDFS")
+}
+
+fn has_monitoring_sink(request: &SubmitJobRequest) -> bool {
+    has_sink(request, "MONITORING")
+}
+
+fn has_cosmosdb_sink(request: &SubmitJobRequest) -> bool {
+    has_sink(request, "COSMOSDB")
+}
+
+fn has_jdbc_sink(request: &SubmitJobRequest) -> bool {
+    has_sink(request, "JDBC")
+}
+
+fn has_aerospike_sink(request: &SubmitJobRequest) -> bool {
+    has_sink(request, "AEROSPIKE")
+}
+
+fn has_kafka_sink(request: &SubmitJobRequest) -> bool {
+    has_sink(request, "KAFKA")
+}
+
+fn has_redis_encoding(request: &SubmitJobRequest) -> bool {
+    has_redis_params(request, &["encoding"])
+}
+
+fn has_redis_key_prefix(request: &SubmitJobRequest) -> bool {
+    has_redis_params(request, &["keyPrefix"])
+}
+
+fn has_redis_ttl(request: &SubmitJobRequest) -> bool {
+    has_redis_params(request, &["ttlSeconds", "featureTtlSeconds"])
+}
+
+/**
+ * Returns constructs in the request that are not supported by the runtime
+ */
+pub(crate) fn unsupported_constructs(
+    request: &SubmitJobRequest,
+    runtime: RuntimeVersion,
+) -> Vec<(&'static str, RuntimeVersion)> {
+    RUNTIME_REQUIREMENTS
+        .iter()
+        .filter(|(_, required, detect)| runtime < *required && detect(request))
+        .map(|(name, required, _)| (*name, *required))
+        .collect()
+}
+
+/**
+ * Runtime version is either set explicitly by `spark_config.runtime_version`, or taken from the maven artifact
+ */
+pub(crate) async fn get_runtime_version(
+    var_source: Arc<dyn VarSource + Send + Sync>,
+) -> Result<RuntimeVersion, Error> {
+    if let Ok(v) = var_source
+        .get_environment_variable(&["spark_config", "runtime_version"])
+        .await
+    {
+        if !v.trim().is_empty() {
+            return v.parse();
+        }
+    }
+    let artifact = var_source
+        .get_environment_variable(&["spark_config", "maven_artifact"])
+        .await
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| FEATHR_MAVEN_ARTIFACT.to_string());
+    artifact.rsplit(':').next().unwrap_or_default().parse()
+}
+
+/**
+ * Tag the request with the client version, and check if the request can be handled by the runtime.
+ * Unsupported constructs are logged, or rejected if `spark_config.strict_runtime_version` is set to true.
+ */
+pub(crate) async fn check_compatibility(
+    var_source: Arc<dyn VarSource + Send + Sync>,
+    request: &mut SubmitJobRequest,
+) -> Result<(), Error> {
+    request
+        .job_tags
+        .insert(CLIENT_VERSION_TAG.to_string(), CLIENT_VERSION.to_string());
+
+    let runtime = get_runtime_version(var_source.clone()).await?;
+    let strict = var_source
+        .get_environment_variable(&["spark_config", "strict_runtime_version"])
+        .await
+        .map(|s| s.trim().eq_ignore_ascii_case("true"))
+        .unwrap_or_default();
+    for (name, required) in unsupported_constructs(request, runtime) {
+        if strict {
+            return Err(Error::UnsupportedByRuntime(
+                name.to_string(),
+                required.to_string(),
+                runtime.to_string(),
+            ));
+        }
+        warn!(
+            "{} requires Feathr runtime {}, but configured runtime is {}, the job may fail",
+            name, required, runtime
+        );
+    }
+    Ok(())
+}
+
+/**
+ * Only the beginning of the log is scanned, the root cause is usually reported before the stack traces pile up
+ */
+const INCOMPATIBILITY_SCAN_LINES: usize = 200;
+
+/**
+ * Known signatures of a Feathr runtime built for another Spark/Scala version, and the suggested fix
+ */
+const INCOMPATIBILITY_SIGNATURES: &[(&[&str], &str)] = &[
+    (
+        &["NoSuchMethodError: scala.", "NoSuchMethodError: 'scala.", "scala.Product.$init$", "scala/collection/immutable/"],
+        "Scala version mismatch between the Feathr runtime and the Spark cluster, use the runtime jar built for the Scala version of the cluster by setting `spark_config.<provider>.feathr_runtime_jar` or `spark_config.maven_artifact`",
+    ),
+    (
+        &["ClassNotFoundException: com.linkedin.feathr", "NoClassDefFoundError: com/linkedin/feathr"],
+        "Feathr runtime is not loaded by the Spark cluster, check `spark_config.<provider>.feathr_runtime_jar` or `spark_config.maven_artifact`",
+    ),
+    (
+        &["ClassNotFoundException: org.apache.spark", "NoClassDefFoundError: org/apache/spark", "NoSuchMethodError: org.apache.spark", "NoSuchMethodError: 'org.apache.spark"],
+        "Feathr runtime is built for another Spark version, use the runtime jar matching the Spark version of the cluster by setting `spark_config.<provider>.feathr_runtime_jar` or `spark_config.maven_artifact`",
+    ),
+];
+
+/**
+ * Detect Spark runtime incompatibility from the log of a failed job, returns the offending line and the suggested fix
+ */
+pub(crate) fn detect_incompatibility(log: &str) -> Option<String> {
+    log.lines()
+        .take(INCOMPATIBILITY_SCAN_LINES)
+        .find_map(|line| {
+            INCOMPATIBILITY_SIGNATURES
+                .iter()
+                .find(|(patterns, _)| patterns.iter().any(|p| line.contains(p)))
+                .map(|(_, suggestion)| format!("{}: {}", suggestion, line.trim()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runtime_version() {
+        assert_eq!(
+            "0.4.0".parse::<RuntimeVersion>().unwrap(),
+            RuntimeVersion(0, 4, 0)
+        );
+        assert_eq!(
+            "0.7.2-rc1".parse::<RuntimeVersion>().unwrap(),
+            RuntimeVersion(0, 7, 2)
+        );
+        assert!("abc".parse::<RuntimeVersion>().is_err());
+        assert!(RuntimeVersion(0, 10, 0) > RuntimeVersion(0, 9, 1));
+
+        let request = SubmitJobRequest {
+            feature_config: r#"{"derivations":{"d1":{"inputs":{"f1_1d":{"key":["c1"],"feature":"f1","offset":"1d"}}}}}"#.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            unsupported_constructs(&request, RuntimeVersion(0, 4, 0)).len(),
+            1
+        );
+        assert!(unsupported_constructs(&request, RuntimeVersion(0, 9, 0)).is_empty());
+        assert!(unsupported_constructs(&Default::default(), RuntimeVersion(0, 4, 0)).is_empty());
+
+        let request = SubmitJobRequest {
+            gen_job_config: r#"{"operational":{"output":[{"name":"HDFS","params":{"path":"abfss://a@b.dfs.core.windows.net/"}}]}}"#.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            unsupported_constructs(&request, RuntimeVersion(0, 4, 0))[0].0,
+            "HDFS output sink"
+        );
+    }
+
+    #[test]
+    fn output_sinks_covered() {
+        use crate::{
+            AerospikeSink, CosmosDbSink, HdfsSink, JdbcSink, KafkaSink, MonitoringSink, OutputSink,
+            RedisEncoding, RedisSink,
+        };
+        let sinks: Vec<OutputSink> = vec![
+            RedisSink::new("t1").into(),
+            HdfsSink::new("abfss://a@b.dfs.core.windows.net/").into(),
+            KafkaSink::new(&["broker:9092"], "topic1").into(),
+            CosmosDbSink::new("cosmos1", "https://a.documents.azure.com", "db1", "c1").into(),
+            JdbcSink::new("sql1", "jdbc:sqlserver://a", "t1").into(),
+            AerospikeSink::new("as1", "a", "ns1").into(),
+            MonitoringSink::new("t1").into(),
+        ];
+        let request = |sink: &OutputSink| SubmitJobRequest {
+            gen_job_config: serde_json::json!({ "operational": { "output": [sink] } }).to_string(),
+            ..Default::default()
+        };
+        let mut covered = vec![];
+        for sink in sinks.iter() {
+            // Adding a variant fails to compile here, register it in `RUNTIME_REQUIREMENTS` and add it to `sinks`
+            let variant = match sink {
+                // Supported by all runtime releases
+                OutputSink::Redis(_) => continue,
+                OutputSink::Hdfs(_) => "HDFS",
+                OutputSink::Kafka(_) => "Kafka",
+                OutputSink::CosmosDb(_) => "CosmosDB",
+                OutputSink::Jdbc(_) => "JDBC",
+                OutputSink::Aerospike(_) => "Aerospike",
+                OutputSink::Monitoring(_) => "Monitoring",
+            };
+            let unsupported = unsupported_constructs(&request(sink), RuntimeVersion(0, 0, 0));
+            assert_eq!(unsupported.len(), 1, "{} sink is not registered", variant);
+            assert!(unsupported[0].0.starts_with(variant));
+            covered.push(variant);
+        }
+        assert_eq!(covered.len(), 6);
+
+        let redis = RedisSink::new("t1")
+            .encoding(RedisEncoding::Json)
+            .key_prefix("team1:")
+            .ttl(chrono::Duration::days(1))
+            .unwrap();
+        assert_eq!(
+            unsupported_constructs(&request(&redis.into()), RuntimeVersion(0, 9, 0))
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            vec!["Redis sink encoding", "Redis sink key prefix", "Redis sink TTL"]
+        );
+        assert!(unsupported_constructs(&request(&RedisSink::new("t1").into()), RuntimeVersion(0, 0, 0)).is_empty());
+    }
+
+    #[test]
+    fn incompatibility() {
+        let log = "22/10/01 INFO SparkContext: Running Spark version 3.2.1\n\
+            Exception in thread \"main\" java.lang.NoSuchMethodError: scala.Product.$init$(Lscala/Product;)V\n\
+            \tat com.linkedin.feathr.offline.job.FeatureJoinJob$.main(FeatureJoinJob.scala:42)";
+        let msg = detect_incompatibility(log).unwrap();
+        assert!(msg.starts_with("Scala version mismatch"));
+        assert!(msg.ends_with("scala.Product.$init$(Lscala/Product;)V"));
+
+        let log = "java.lang.ClassNotFoundException: com.linkedin.feathr.offline.job.FeatureGenJob";
+        assert!(detect_incompatibility(log)
+            .unwrap()
+            .starts_with("Feathr runtime is not loaded"));
+
+        let log = "java.lang.NoSuchMethodError: org.apache.spark.sql.catalyst.expressions.Cast.<init>";
+        assert!(detect_incompatibility(log)
+            .unwrap()
+            .starts_with("Feathr runtime is built for another Spark version"));
+
+        assert!(detect_incompatibility("java.lang.OutOfMemoryError: Java heap space").is_none());
+    }
+}
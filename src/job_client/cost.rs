@@ -0,0 +1,201 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{Error, JobId, VarSource};
+
+const DEFAULT_CURRENCY: &str = "USD";
+
+/**
+ * Cluster usage of a job run, as reported by the Spark provider
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobRunInfo {
+    pub job_id: JobId,
+    /**
+     * Node type of the cluster, e.g. `Standard_F4s` on Databricks, or the Spark pool name on Azure Synapse
+     */
+    pub node_type: String,
+    /**
+     * Number of nodes including the driver
+     */
+    pub num_nodes: u32,
+    /**
+     * `None` if the job hasn't started yet
+     */
+    pub started_at: Option<DateTime<Utc>>,
+    /**
+     * `None` if the job is still running
+     */
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl JobRunInfo {
+    /**
+     * Run duration so far if the job hasn't ended
+     */
+    pub fn duration(&self) -> Duration {
+        match self.started_at {
+            Some(start) => self.ended_at.unwrap_or_else(Utc::now) - start,
+            None => Duration::zero(),
+        }
+    }
+}
+
+/**
+ * Price per node hour of each node type, configured in `spark_config.cost`
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PriceTable {
+    pub node_hour_prices: HashMap<String, f64>,
+    pub currency: String,
+}
+
+impl PriceTable {
+    pub fn new(node_hour_prices: HashMap<String, f64>, currency: &str) -> Self {
+        Self {
+            node_hour_prices,
+            currency: currency.to_string(),
+        }
+    }
+
+    pub async fn from_var_source(var_source: Arc<dyn VarSource + Send + Sync>) -> Result<Self, Error> {
+        let node_hour_prices = match var_source
+            .get_environment_variable(&["spark_config", "cost", "node_hour_prices"])
+            .await
+        {
+            Ok(s) if !s.trim().is_empty() => serde_yaml::from_str(&s)?,
+            _ => Default::default(),
+        };
+        let currency = var_source
+            .get_environment_variable(&["spark_config", "cost", "currency"])
+            .await
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_CURRENCY.to_string());
+        Ok(Self::new(node_hour_prices, &currency))
+    }
+
+    pub fn estimate(&self, run: &JobRunInfo) -> Result<JobCostEstimate, Error> {
+        let unit_price = *self.node_hour_prices.get(&run.node_type).ok_or_else(|| {
+            Error::InvalidConfig(format!(
+                "Price of node type '{}' is not set in spark_config.cost.node_hour_prices",
+                run.node_type
+            ))
+        })?;
+        let duration = run.duration();
+        let node_hours = run.num_nodes as f64 * duration.num_seconds() as f64 / 3600.0;
+        Ok(JobCostEstimate {
+            job_id: run.job_id,
+            node_type: run.node_type.to_owned(),
+            num_nodes: run.num_nodes,
+            duration,
+            node_hours,
+            unit_price,
+            cost: node_hours * unit_price,
+            currency: self.currency.to_owned(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobCostEstimate {
+    pub job_id: JobId,
+    pub node_type: String,
+    pub num_nodes: u32,
+    pub duration: Duration,
+    pub node_hours: f64,
+    /**
+     * Price per node hour
+     */
+    pub unit_price: f64,
+    pub cost: f64,
+    pub currency: String,
+}
+
+/**
+ * Aggregated cost of multiple jobs, e.g. for chargeback
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobCostSummary {
+    pub jobs: Vec<JobCostEstimate>,
+    /**
+     * Jobs whose cost cannot be estimated, with the reason
+     */
+    pub unavailable: Vec<(JobId, String)>,
+    pub total_node_hours: f64,
+    pub total_cost: f64,
+    pub cost_by_node_type: HashMap<String, f64>,
+    pub currency: String,
+}
+
+impl JobCostSummary {
+    pub(crate) fn new(currency: &str, estimates: Vec<Result<JobCostEstimate, (JobId, Error)>>) -> Self {
+        let mut ret = Self {
+            currency: currency.to_string(),
+            ..Default::default()
+        };
+        for estimate in estimates {
+            match estimate {
+                Ok(e) => {
+                    ret.total_node_hours += e.node_hours;
+                    ret.total_cost += e.cost;
+                    *ret.cost_by_node_type.entry(e.node_type.clone()).or_default() += e.cost;
+                    ret.jobs.push(e);
+                }
+                Err((job_id, e)) => ret.unavailable.push((job_id, e.to_string())),
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn estimate_cost() {
+        let prices = PriceTable::from_var_source(crate::new_var_source(
+            r#"
+            spark_config:
+                cost:
+                    node_hour_prices: {'Standard_F4s': 0.5, 'Standard_D4_v2': 1.2}
+            "#,
+        ))
+        .await
+        .unwrap();
+        assert_eq!(prices.currency, "USD");
+        let start = Utc.with_ymd_and_hms(2022, 5, 20, 0, 0, 0).unwrap();
+        let run = |id: u64, node_type: &str, minutes: i64| JobRunInfo {
+            job_id: JobId(id),
+            node_type: node_type.to_string(),
+            num_nodes: 3,
+            started_at: Some(start),
+            ended_at: Some(start + Duration::minutes(minutes)),
+        };
+        let e = prices.estimate(&run(1, "Standard_F4s", 40)).unwrap();
+        assert!((e.node_hours - 2.0).abs() < 1e-9);
+        assert!((e.cost - 1.0).abs() < 1e-9);
+
+        let summary = JobCostSummary::new(
+            &prices.currency,
+            [
+                run(1, "Standard_F4s", 40),
+                run(2, "Standard_D4_v2", 20),
+                run(3, "Standard_F4s", 20),
+                run(4, "Standard_F8s", 20),
+            ]
+            .iter()
+            .map(|r| prices.estimate(r).map_err(|e| (r.job_id, e)))
+            .collect(),
+        );
+        assert_eq!(summary.jobs.len(), 3);
+        assert_eq!(summary.unavailable[0].0, JobId(4));
+        assert!((summary.total_node_hours - 4.0).abs() < 1e-9);
+        assert!((summary.total_cost - 2.7).abs() < 1e-9);
+        assert!((summary.cost_by_node_type["Standard_F4s"] - 1.5).abs() < 1e-9);
+    }
+}
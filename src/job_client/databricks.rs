@@ -1,45 +1,54 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use async_trait::async_trait;
 use bytes::Bytes;
-use dbfs_client::DbfsClient;
-use log::{debug, warn};
+use chrono::{DateTime, TimeZone, Utc};
+use handlebars::Handlebars;
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use tokio::io::AsyncReadExt;
+use serde_json::Value;
 
-use crate::{Error, JobClient, JobId, JobStatus, SubmitJobRequest, VarSource};
+use crate::{
+    storage::dbfs::{DbfsClient, LoggedResponse},
+    Error, ExistsCache, JobClient, JobId, JobRunInfo, JobStatus, JobSummary, RemoteFile, SubmitJobRequest, TimePartition,
+    Timeouts, VarSource,
+};
 
-#[async_trait]
-trait LoggedResponse {
-    async fn detailed_error_for_status(self) -> Result<Self, Error>
-    where
-        Self: Sized;
-}
+/**
+ * Cluster termination codes caused by transient cloud provider failures, the run can be resubmitted
+ */
+const RETRYABLE_TERMINATION_CODES: &[&str] = &[
+    "CLOUD_PROVIDER_LAUNCH_FAILURE",
+    "CLOUD_PROVIDER_SHUTDOWN",
+    "AZURE_RESOURCE_PROVIDER_THROTTLING",
+    "AZURE_QUOTA_EXCEEDED_EXCEPTION",
+    "INSTANCE_UNREACHABLE",
+    "SPARK_STARTUP_FAILURE",
+];
 
-#[async_trait]
-impl LoggedResponse for reqwest::Response {
-    async fn detailed_error_for_status(self) -> Result<Self, Error> {
-        if self.status().is_client_error() || self.status().is_server_error() {
-            let url = self.url().to_string();
-            let status = self.status().to_string();
-            let text = self.text().await?;
-            Err(
-                match serde_json::from_str::<DatabricksErrorResponse>(&text) {
-                    Ok(resp) => Error::DatabricksApiError(resp.error_code, resp.message),
-                    Err(_) => Error::DatabricksHttpError(url, status, text),
-                },
-            )
-        } else {
-            Ok(self)
-        }
-    }
-}
+const DEFAULT_LAUNCH_RETRIES: u32 = 2;
 
-#[derive(Clone, Debug, Deserialize)]
-struct DatabricksErrorResponse {
-    pub error_code: String,
-    pub message: String,
-}
+const DEFAULT_NUM_WORKERS: u32 = 2;
+const DEFAULT_SPARK_VERSION: &str = "9.1.x-scala2.12";
+const DEFAULT_NODE_TYPE: &str = "Standard_D4_v2";
+
+const DRIVER_LOG_FILES: &[&str] = &["stdout", "stderr", "log4j-active.log"];
+
+const SCHEDULED_DRIVER_TEMPLATE: &str = include_str!("../../template/feathr_scheduled_driver_template.py.hbr");
+
+/**
+ * Dynamic value reference resolved by Databricks in each run of a scheduled job
+ */
+const TRIGGER_TIME_PARAM: &str = "{{job.trigger.time.timestamp_ms}}";
+
+/**
+ * Max number of runs returned by one `runs/list` call
+ */
+const LIST_RUNS_PAGE_SIZE: usize = 25;
 
 #[derive(Debug)]
 pub struct DatabricksClient {
@@ -49,6 +58,22 @@ pub struct DatabricksClient {
     workspace_dir: String,
     cluster: Cluster,
     maven_artifact: String,
+    max_launch_retries: u32,
+    fallback_node_types: Vec<String>,
+    access_control_list: Vec<AccessControlRequest>,
+    secret_scope: Option<String>,
+    // Original run id -> resubmitting state
+    launches: Mutex<HashMap<u64, LaunchState>>,
+    // Serializes relaunches so concurrent pollers of the same run don't resubmit it twice
+    relaunch_lock: tokio::sync::Mutex<()>,
+    exists_cache: ExistsCache,
+}
+
+#[derive(Clone, Debug)]
+struct LaunchState {
+    request: SubmitRunRequest,
+    current_run_id: u64,
+    retries: u32,
 }
 
 impl DatabricksClient {
@@ -59,6 +84,28 @@ impl DatabricksClient {
         cluster: Option<Cluster>,
         maven_artifact: &str,
     ) -> Self {
+        Self::with_timeouts(
+            url_base,
+            token,
+            workspace_dir,
+            cluster,
+            maven_artifact,
+            &Default::default(),
+        )
+        .unwrap()
+    }
+
+    /**
+     * API calls are limited by the API timeout, DBFS operations by the transfer timeout, fails if the timeouts are negative
+     */
+    pub fn with_timeouts(
+        url_base: &str,
+        token: &str,
+        workspace_dir: &str,
+        cluster: Option<Cluster>,
+        maven_artifact: &str,
+        timeouts: &Timeouts,
+    ) -> Result<Self, Error> {
         let mut headers = reqwest::header::HeaderMap::new();
         if !token.is_empty() {
             headers.insert(
@@ -66,30 +113,371 @@ impl DatabricksClient {
                 reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
             );
         }
-        Self {
+        Ok(Self {
             url_base: format!("{}/api/2.0", url_base.trim_end_matches("/")),
-            dbfs: DbfsClient::new(url_base, token),
-            client: reqwest::ClientBuilder::new()
+            dbfs: DbfsClient::with_timeouts(url_base, token, timeouts)?,
+            client: timeouts
+                .api_client()?
                 .default_headers(headers)
-                .build()
-                .unwrap(),
+                .build()?,
             workspace_dir: workspace_dir.to_string(),
             cluster: cluster.unwrap_or(Cluster::NewCluster(NewCluster {
-                num_workers: 2,
-                spark_version: "9.1.x-scala2.12".to_string(),
-                node_type_id: "Standard_D4_v2".to_string(),
+                num_workers: DEFAULT_NUM_WORKERS,
+                spark_version: DEFAULT_SPARK_VERSION.to_string(),
+                node_type_id: DEFAULT_NODE_TYPE.to_string(),
                 spark_conf: Default::default(),
                 custom_tags: Default::default(),
+                cluster_log_conf: None,
+                spark_env_vars: None,
             })),
             maven_artifact: maven_artifact.to_string(),
+            max_launch_retries: DEFAULT_LAUNCH_RETRIES,
+            fallback_node_types: Default::default(),
+            access_control_list: Default::default(),
+            secret_scope: None,
+            launches: Default::default(),
+            relaunch_lock: Default::default(),
+            exists_cache: Default::default(),
+        })
+    }
+
+    /**
+     * Set max number of resubmissions when the job cluster failed to launch,
+     * resubmitted runs use fallback node types in order if provided
+     */
+    pub fn launch_retries(mut self, max_retries: u32, fallback_node_types: &[&str]) -> Self {
+        self.max_launch_retries = max_retries;
+        self.fallback_node_types = fallback_node_types.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /**
+     * Set permissions of submitted runs, e.g. grant a group `CAN_VIEW` so the runs are visible to the team
+     */
+    pub fn access_control_list(mut self, access_control_list: Vec<AccessControlRequest>) -> Self {
+        self.access_control_list = access_control_list;
+        self
+    }
+
+    /**
+     * Secret scope holding the credentials of scheduled jobs, the token must be able to write into it
+     */
+    pub fn secret_scope(mut self, scope: &str) -> Self {
+        self.secret_scope = Some(scope.to_string());
+        self
+    }
+
+    async fn submit_run(&self, job: &SubmitRunRequest) -> Result<u64, Error> {
+        let url = format!("{}/jobs/runs/submit", self.url_base);
+        debug!("URL: {}", url);
+        let text = self
+            .client
+            .post(url)
+            .json(job)
+            .send()
+            .await?
+            .detailed_error_for_status()
+            .await?
+            .text()
+            .await?;
+        debug!("Response: {}", text);
+        let resp: SubmitRunResponse = serde_json::from_str(&text)?;
+        Ok(resp.run_id)
+    }
+
+    /**
+     * Upload the artifacts of the job and create the run settings with the arguments
+     */
+    async fn build_run_request(
+        &self,
+        var_source: Arc<dyn VarSource + Send + Sync>,
+        request: SubmitJobRequest,
+        args: Vec<String>,
+    ) -> Result<SubmitRunRequest, Error> {
+        let main_jar_path = if request.main_jar_path.is_none() {
+            var_source
+                .get_environment_variable(&[
+                    "spark_config",
+                    "databricks",
+                    "feathr_runtime_location",
+                ])
+                .await
+                .ok()
+        } else {
+            request.main_jar_path
+        };
+
+        let mut orig_files: Vec<String> = vec![];
+        let mut orig_jars: Vec<String> = match main_jar_path.clone() {
+            Some(p) => vec![p],
+            None => vec![],
+        };
+
+        for f in request.reference_files.into_iter() {
+            if f.ends_with(".jar") {
+                orig_jars.push(f)
+            } else {
+                orig_files.push(f)
+            }
+        }
+
+        debug!("Uploading JARs: {:#?}", orig_jars);
+        let jars = self.multi_upload_or_get_url(&orig_jars).await?;
+        debug!("JARs uploaded, URLs: {:#?}", jars);
+
+        debug!("Uploading files: {:#?}", orig_files);
+        let files = self.multi_upload_or_get_url(&orig_files).await?;
+        debug!("Files uploaded, URLs: {:#?}", files);
+
+        debug!("Uploading Python files: {:#?}", request.python_files);
+        let py_files = self.multi_upload_or_get_url(&request.python_files).await?;
+        debug!("Python files uploaded, URLs: {:#?}", py_files);
+
+        let task = if let Some(code) = request.main_python_script {
+            let py_url = self
+                .write_remote_file(
+                    &self.get_remote_url(&format!(
+                        "feathr_pyspark_driver_{}_{}.py",
+                        request.name,
+                        request.job_key.as_simple()
+                    )),
+                    code.as_bytes(),
+                )
+                .await?;
+            debug!("Main executable file: {}", py_url);
+            SparkTask::SparkPythonTask {
+                python_file: py_url,
+                parameters: args,
+            }
+        } else {
+            debug!("Main class name: {}", request.main_class_name);
+            SparkTask::SparkJarTask {
+                main_class_name: request.main_class_name,
+                parameters: args,
+            }
+        };
+
+        let mut libraries: Vec<Library> = jars.into_iter().map(Library::Jar).collect();
+
+        if main_jar_path.is_none() {
+            // Add maven artifact as the dependency
+            libraries.push(Library::Maven {
+                coordinates: self.maven_artifact.clone(),
+                repo: None,
+                exclusions: vec![],
+            });
         }
+
+        let cluster = match self.cluster.clone() {
+            Cluster::NewCluster(mut cluster) => {
+                // Job tags are kept as cluster tags, e.g. the idempotency key to find the run later
+                let mut tags = request.job_tags;
+                if !request.output.is_empty() {
+                    tags.insert("output".to_string(), request.output);
+                }
+                cluster.custom_tags = if tags.is_empty() { None } else { Some(tags) };
+                if !request.env_vars.is_empty() {
+                    cluster
+                        .spark_env_vars
+                        .get_or_insert_with(Default::default)
+                        .extend(request.env_vars);
+                }
+                Cluster::NewCluster(cluster)
+            }
+            Cluster::ExistingClusterId(cluster_id) => {
+                if !request.env_vars.is_empty() {
+                    warn!(
+                        "Environment variables {:?} are ignored, they cannot be set on existing cluster {}",
+                        request.env_vars.keys().collect::<Vec<_>>(),
+                        cluster_id
+                    );
+                }
+                Cluster::ExistingClusterId(cluster_id)
+            }
+        };
+
+        Ok(SubmitRunRequest {
+            tasks: vec![SubmitRunSettings {
+                task_key: request.job_key.as_simple().to_string(),
+                cluster,
+                task,
+                libraries,
+            }],
+            run_name: request.name,
+            access_control_list: self.access_control_list.clone(),
+        })
+    }
+
+    async fn put_secret(&self, scope: &str, key: &str, value: &str) -> Result<(), Error> {
+        let url = format!("{}/secrets/put", self.url_base);
+        debug!("URL: {}, key: {}", url, key);
+        self.client
+            .post(url)
+            .json(&serde_json::json!({
+                "scope": scope,
+                "key": key,
+                "string_value": value,
+            }))
+            .send()
+            .await?
+            .detailed_error_for_status()
+            .await?;
+        Ok(())
+    }
+
+    async fn create_job(&self, job: &CreateJobRequest) -> Result<u64, Error> {
+        let url = format!("{}/jobs/create", self.url_base);
+        debug!("URL: {}", url);
+        let text = self
+            .client
+            .post(url)
+            .json(job)
+            .send()
+            .await?
+            .detailed_error_for_status()
+            .await?
+            .text()
+            .await?;
+        debug!("Response: {}", text);
+        let resp: CreateJobResponse = serde_json::from_str(&text)?;
+        Ok(resp.job_id)
+    }
+
+    async fn get_termination_reason(&self, cluster_id: &str) -> Result<Option<TerminationReason>, Error> {
+        let url = format!("{}/clusters/get?cluster_id={}", self.url_base, cluster_id);
+        let resp: GetClusterResponse = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .detailed_error_for_status()
+            .await?
+            .json()
+            .await?;
+        Ok(resp.termination_reason)
     }
 
-    async fn get_run_status(
+    /**
+     * Resubmit the run if its cluster failed to launch, returns the new run id
+     */
+    async fn relaunch(
         &self,
         id: u64,
-    ) -> Result<(JobStatus, String, Option<HashMap<String, String>>), Error> {
-        let url = format!("{}/jobs/runs/get-output?run_id={}", self.url_base, id);
+        failed_run_id: u64,
+        resp: &GetRunOutputResponse,
+    ) -> Result<Option<u64>, Error> {
+        let _guard = self.relaunch_lock.lock().await;
+        let state = match self.launches.lock()?.get(&id) {
+            // Already resubmitted by another caller
+            Some(state) if state.current_run_id != failed_run_id => {
+                return Ok(Some(state.current_run_id))
+            }
+            Some(state) if state.retries < self.max_launch_retries => state.clone(),
+            _ => return Ok(None),
+        };
+        let cluster_id = match &resp.metadata.cluster_instance {
+            Some(c) => c.cluster_id.to_owned(),
+            None => return Ok(None),
+        };
+        let reason = match self.get_termination_reason(&cluster_id).await? {
+            Some(r) if r.is_retryable() => r,
+            _ => return Ok(None),
+        };
+        let retries = state.retries + 1;
+        let job = state.request.with_fallback_node_type(
+            self.fallback_node_types
+                .get(retries as usize - 1)
+                .or_else(|| self.fallback_node_types.last()),
+        );
+        info!(
+            "Cluster of run {} failed to launch with {}, resubmitting ({}/{})",
+            state.current_run_id, reason.code, retries, self.max_launch_retries
+        );
+        let run_id = self.submit_run(&job).await?;
+        self.launches.lock()?.insert(
+            id,
+            LaunchState {
+                request: job,
+                current_run_id: run_id,
+                retries,
+            },
+        );
+        Ok(Some(run_id))
+    }
+
+    /**
+     * Cancel the current run of the job, and stop resubmitting it if its cluster failed to launch.
+     * Relaunches are only tracked by this client, the job id of a job submitted by another client is
+     * taken as the run id, so only its first run is cancelled if it has been relaunched.
+     */
+    async fn cancel_run(&self, id: u64) -> Result<(), Error> {
+        let _guard = self.relaunch_lock.lock().await;
+        let run_id = match self.launches.lock()?.get_mut(&id) {
+            Some(state) => {
+                state.retries = self.max_launch_retries;
+                state.current_run_id
+            }
+            None => id,
+        };
+        let url = format!("{}/jobs/runs/cancel", self.url_base);
+        debug!("URL: {}", url);
+        self.client
+            .post(url)
+            .json(&CancelRunRequest { run_id })
+            .send()
+            .await?
+            .detailed_error_for_status()
+            .await?;
+        Ok(())
+    }
+
+    /**
+     * List runs submitted by `runs/submit`, newest first, resubmitted runs are listed with the id of the first run
+     */
+    async fn list_runs(&self, limit: usize) -> Result<Vec<JobSummary>, Error> {
+        let first_runs: HashMap<u64, u64> = self
+            .launches
+            .lock()?
+            .iter()
+            .map(|(id, state)| (state.current_run_id, *id))
+            .collect();
+        let mut ret = vec![];
+        while ret.len() < limit {
+            let url = format!(
+                "{}/jobs/runs/list?run_type=SUBMIT_RUN&offset={}&limit={}",
+                self.url_base,
+                ret.len(),
+                (limit - ret.len()).min(LIST_RUNS_PAGE_SIZE)
+            );
+            debug!("URL: {}", url);
+            let resp: ListRunsResponse = self
+                .client
+                .get(url)
+                .send()
+                .await?
+                .detailed_error_for_status()
+                .await?
+                .json()
+                .await?;
+            ret.extend(resp.runs.iter().map(|run| {
+                run.summary(JobId(first_runs.get(&run.run_id).copied().unwrap_or(run.run_id)))
+            }));
+            if !resp.has_more || resp.runs.is_empty() {
+                break;
+            }
+        }
+        ret.truncate(limit);
+        Ok(ret)
+    }
+
+    async fn get_run_status(&self, id: u64) -> Result<RunStatus, Error> {
+        let run_id = self
+            .launches
+            .lock()?
+            .get(&id)
+            .map(|s| s.current_run_id)
+            .unwrap_or(id);
+        let url = format!("{}/jobs/runs/get-output?run_id={}", self.url_base, run_id);
         let resp: GetRunOutputResponse = self
             .client
             .get(url)
@@ -100,35 +488,67 @@ impl DatabricksClient {
             .json()
             .await?;
         debug!("Status response: {:#?}", resp);
-        let status = match resp.metadata.state.life_cycle_state {
-            RunLifeCycleState::Pending => JobStatus::Starting,
-            RunLifeCycleState::Running | RunLifeCycleState::Terminating => JobStatus::Running,
-            RunLifeCycleState::Terminated => match resp.metadata.state.result_state {
-                Some(RunResultState::Success) => JobStatus::Success,
-                _ => JobStatus::Failed,
-            },
-            RunLifeCycleState::Skipped | RunLifeCycleState::InternalError => JobStatus::Failed,
+        let status = resp.metadata.state.job_status();
+        let status = if status == JobStatus::Failed && self.relaunch(id, run_id, &resp).await?.is_some() {
+            JobStatus::Provisioning
+        } else {
+            status
         };
 
-        Ok((
+        Ok(RunStatus {
             status,
-            vec![
-                resp.error.map(|s| format!("{}\n", s)).unwrap_or_default(),
-                resp.logs.map(|s| format!("{}\n", s)).unwrap_or_default(),
-                resp.error_trace
-                    .map(|s| format!("{}\n", s))
-                    .unwrap_or_default(),
-            ]
-            .join(""),
-            match resp.metadata.cluster_spec.cluster {
+            delivered_log_dir: resp.delivered_log_dir(),
+            started_at: epoch_millis(resp.metadata.start_time),
+            ended_at: epoch_millis(resp.metadata.end_time),
+            error: resp.error,
+            logs: resp.logs,
+            error_trace: resp.error_trace,
+            tags: match &resp.metadata.cluster_spec.cluster {
                 Cluster::ExistingClusterId(_) => {
                     warn!("Cannot get output directory from existing cluster");
                     Default::default()
                 }
-                Cluster::NewCluster(nc) => nc.custom_tags,
+                Cluster::NewCluster(nc) => nc.custom_tags.clone(),
+            },
+            cluster: resp.metadata.cluster_spec.cluster,
+        })
+    }
+
+    /**
+     * Read driver logs and executor stderr from the cluster log delivery dir
+     */
+    async fn read_delivered_logs(&self, dir: &str) -> Result<String, Error> {
+        if !dir.starts_with("dbfs:") {
+            return Ok(format!(
+                "Logs are truncated, full logs are delivered to {}\n",
+                dir
+            ));
+        }
+        let mut ret = String::new();
+        for name in DRIVER_LOG_FILES {
+            let path = format!("{}/driver/{}", dir, name);
+            match self.dbfs.read_file(&path).await {
+                Ok(content) => {
+                    ret.push_str(&format!("===== {} =====\n", path));
+                    ret.push_str(&String::from_utf8_lossy(&content));
+                    ret.push('\n');
+                }
+                Err(e) => debug!("Failed to read {}, error: {}", path, e),
             }
-            .to_owned(),
-        ))
+        }
+        // Executor logs are under `executor/<app-id>/<executor-id>/`
+        let executor_dir = format!("{}/executor", dir);
+        for app in self.dbfs.list(&executor_dir).await.unwrap_or_default() {
+            for executor in self.dbfs.list(&app.path).await.unwrap_or_default() {
+                let path = format!("{}/stderr", executor.path);
+                if let Ok(content) = self.dbfs.read_file(&path).await {
+                    ret.push_str(&format!("===== {} =====\n", path));
+                    ret.push_str(&String::from_utf8_lossy(&content));
+                    ret.push('\n');
+                }
+            }
+        }
+        Ok(ret)
     }
 
     pub(crate) async fn from_var_source(
@@ -152,22 +572,15 @@ impl DatabricksClient {
             .get_environment_variable(&["DATABRICKS_WORKSPACE_TOKEN_VALUE"])
             .await?;
 
-        #[derive(Debug, Deserialize)]
-        struct ConfigTemplate {
-            #[serde(flatten)]
-            cluster: Cluster,
-        }
-
-        let value: serde_yaml::Value = serde_yaml::from_str(
-            &var_source
-                .get_environment_variable(&["spark_config", "databricks", "config_template"])
-                .await?,
-        )?;
-
-        debug!("{:#?}", value);
-
-        let config_template = serde_yaml::from_value::<ConfigTemplate>(value.to_owned())?;
-        let nc = config_template.cluster;
+        // Default cluster is used if the template is not set
+        let nc = match var_source
+            .get_environment_variable(&["spark_config", "databricks", "config_template"])
+            .await
+        {
+            Ok(s) if !s.trim().is_empty() => Some(ClusterTemplate::parse(&s)?),
+            _ => None,
+        };
+        debug!("Cluster: {:?}", nc);
 
         let maven_artifact = var_source
             .get_environment_variable(&["spark_config", "maven_artifact"])
@@ -181,14 +594,127 @@ impl DatabricksClient {
             .unwrap_or(super::FEATHR_MAVEN_ARTIFACT.to_string());
         debug!("Maven artifact: {}", maven_artifact);
 
-        Ok(Self::new(
+        let max_launch_retries = var_source
+            .get_environment_variable(&["spark_config", "databricks", "max_launch_retries"])
+            .await
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(DEFAULT_LAUNCH_RETRIES);
+        let fallback_node_types = var_source
+            .get_environment_variable(&["spark_config", "databricks", "fallback_node_type_ids"])
+            .await
+            .unwrap_or_default();
+        let fallback_node_types: Vec<&str> = fallback_node_types
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+        let access_control_list: Vec<AccessControlRequest> = match var_source
+            .get_environment_variable(&["spark_config", "databricks", "access_control_list"])
+            .await
+        {
+            Ok(s) if !s.trim().is_empty() => serde_yaml::from_str(&s)?,
+            _ => vec![],
+        };
+        let secret_scope = var_source
+            .get_environment_variable(&["spark_config", "databricks", "secret_scope"])
+            .await
+            .unwrap_or_default();
+
+        let client = Self::with_timeouts(
             &url_base,
             &token,
             &workspace_dir,
-            Some(nc),
+            nc,
             &maven_artifact,
-        ))
+            &Timeouts::from_var_source(&var_source).await?,
+        )?
+        .launch_retries(max_launch_retries, &fallback_node_types)
+        .access_control_list(access_control_list);
+        Ok(if secret_scope.trim().is_empty() {
+            client
+        } else {
+            client.secret_scope(secret_scope.trim())
+        })
+    }
+}
+
+/**
+ * Name of the secret holding the value at `path` of the JSON arguments, e.g. `feathr-<job key>.redis-config.REDIS_PASSWORD`,
+ * the driver script of scheduled jobs derives the same name
+ */
+fn secret_name(prefix: &str, path: &[&str]) -> String {
+    std::iter::once(prefix)
+        .chain(path.iter().copied())
+        .collect::<Vec<_>>()
+        .join(".")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "_.-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/**
+ * Credentials in the JSON arguments, keyed by the secret name, found by comparing with the redacted arguments
+ */
+fn collect_secrets(prefix: &str, args: &[String], redacted: &[String]) -> HashMap<String, String> {
+    fn walk(prefix: &str, path: &mut Vec<String>, value: &Value, redacted: &Value, ret: &mut HashMap<String, String>) {
+        match (value, redacted) {
+            (Value::Object(value), Value::Object(redacted)) => {
+                for (k, v) in value.iter() {
+                    if let Some(r) = redacted.get(k) {
+                        path.push(k.to_owned());
+                        walk(prefix, path, v, r, ret);
+                        path.pop();
+                    }
+                }
+            }
+            (Value::String(value), Value::String(redacted)) if value != redacted => {
+                let path: Vec<&str> = path.iter().map(String::as_str).collect();
+                ret.insert(secret_name(prefix, &path), value.to_owned());
+            }
+            _ => {}
+        }
     }
+    let mut ret = HashMap::new();
+    for i in 1..args.len().min(redacted.len()) {
+        if args[i] == redacted[i] {
+            continue;
+        }
+        if let (Ok(value), Ok(r)) = (
+            serde_json::from_str::<Value>(&args[i]),
+            serde_json::from_str::<Value>(&redacted[i]),
+        ) {
+            let mut path = vec![args[i - 1].trim_start_matches('-').to_string()];
+            walk(prefix, &mut path, &value, &r, &mut ret);
+        }
+    }
+    ret
+}
+
+/**
+ * PySpark driver of scheduled jobs, resolves the window and the credentials of each run before running `FeatureGenJob`
+ */
+fn scheduled_driver_script(
+    secret_scope: &str,
+    secret_prefix: &str,
+    time_partitions: &[TimePartition],
+) -> Result<String, Error> {
+    let params = serde_json::to_vec(&serde_json::json!({
+        "secret_scope": secret_scope,
+        "secret_prefix": secret_prefix,
+        "time_partitions": time_partitions,
+    }))?;
+    let mut hbs = Handlebars::new();
+    hbs.register_template_string("py", SCHEDULED_DRIVER_TEMPLATE)
+        .map_err(|e| Error::InvalidConfig(e.to_string()))?;
+    hbs.render("py", &HashMap::from([("params", super::encode_buf(&params))]))
+        .map_err(|e| Error::InvalidConfig(e.to_string()))
 }
 
 #[derive(Clone, Copy, Debug, Deserialize)]
@@ -218,10 +744,66 @@ struct RunState {
     // Other fields omitted
 }
 
+impl RunState {
+    fn job_status(&self) -> JobStatus {
+        match self.life_cycle_state {
+            RunLifeCycleState::Pending => JobStatus::Provisioning,
+            RunLifeCycleState::Running | RunLifeCycleState::Terminating => JobStatus::Running,
+            RunLifeCycleState::Terminated => match self.result_state {
+                Some(RunResultState::Success) => JobStatus::Success,
+                _ => JobStatus::Failed,
+            },
+            RunLifeCycleState::Skipped | RunLifeCycleState::InternalError => JobStatus::Failed,
+        }
+    }
+}
+
+/**
+ * Time in epoch milliseconds, 0 means not started or ended yet
+ */
+fn epoch_millis(ms: i64) -> Option<DateTime<Utc>> {
+    (ms > 0).then(|| Utc.timestamp_millis_opt(ms).single()).flatten()
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ClusterInstance {
+    cluster_id: String,
+    // Other fields omitted
+}
+
 #[derive(Clone, Debug, Deserialize)]
 struct RunMetadata {
     state: RunState,
     cluster_spec: ClusterSpec,
+    cluster_instance: Option<ClusterInstance>,
+    // Epoch milliseconds, 0 if not started or ended yet
+    #[serde(default)]
+    start_time: i64,
+    #[serde(default)]
+    end_time: i64,
+    // Other fields omitted
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct TerminationReason {
+    code: String,
+    #[serde(rename = "type")]
+    reason_type: Option<String>,
+    #[serde(default)]
+    parameters: HashMap<String, String>,
+}
+
+impl TerminationReason {
+    fn is_retryable(&self) -> bool {
+        RETRYABLE_TERMINATION_CODES.contains(&self.code.as_str())
+            || self.reason_type.as_deref() == Some("CLOUD_FAILURE")
+            || self.parameters.contains_key("azure_error_code")
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct GetClusterResponse {
+    termination_reason: Option<TerminationReason>,
     // Other fields omitted
 }
 
@@ -236,13 +818,145 @@ struct GetRunOutputResponse {
     metadata: RunMetadata,
     error: Option<String>,
     logs: Option<String>,
+    #[serde(default)]
+    logs_truncated: bool,
     error_trace: Option<String>,
 }
 
+impl GetRunOutputResponse {
+    /**
+     * Dir of the delivered cluster logs, only if the logs returned by the API are truncated
+     */
+    fn delivered_log_dir(&self) -> Option<String> {
+        if !self.logs_truncated {
+            return None;
+        }
+        let cluster_id = &self.metadata.cluster_instance.as_ref()?.cluster_id;
+        match &self.metadata.cluster_spec.cluster {
+            Cluster::NewCluster(NewCluster {
+                cluster_log_conf: Some(conf),
+                ..
+            }) => {
+                let destination = match conf {
+                    ClusterLogConf::Dbfs { destination } => destination,
+                    ClusterLogConf::S3 { destination, .. } => destination,
+                };
+                Some(format!("{}/{}", destination.trim_end_matches('/'), cluster_id))
+            }
+            _ => None,
+        }
+    }
+}
+
+struct RunStatus {
+    status: JobStatus,
+    error: Option<String>,
+    logs: Option<String>,
+    error_trace: Option<String>,
+    tags: Option<HashMap<String, String>>,
+    delivered_log_dir: Option<String>,
+    cluster: Cluster,
+    started_at: Option<DateTime<Utc>>,
+    ended_at: Option<DateTime<Utc>>,
+}
+
+impl RunStatus {
+    fn log(&self, logs: Option<&str>) -> String {
+        [self.error.as_deref(), logs, self.error_trace.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(|s| format!("{}\n", s))
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 struct SubmitRunRequest {
     tasks: Vec<SubmitRunSettings>,
     run_name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    access_control_list: Vec<AccessControlRequest>,
+}
+
+/**
+ * Persistent job with the same tasks as a run, triggered by the schedule
+ */
+#[derive(Clone, Debug, Serialize)]
+struct CreateJobRequest {
+    name: String,
+    tasks: Vec<SubmitRunSettings>,
+    schedule: JobSchedule,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    access_control_list: Vec<AccessControlRequest>,
+}
+
+impl CreateJobRequest {
+    fn new(run: SubmitRunRequest, quartz_cron_expression: &str) -> Self {
+        Self {
+            name: run.run_name,
+            tasks: run.tasks,
+            schedule: JobSchedule {
+                quartz_cron_expression: quartz_cron_expression.to_string(),
+                timezone_id: "UTC".to_string(),
+            },
+            access_control_list: run.access_control_list,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct JobSchedule {
+    quartz_cron_expression: String,
+    timezone_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct CreateJobResponse {
+    job_id: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PermissionLevel {
+    CanView,
+    CanManageRun,
+    IsOwner,
+    CanManage,
+}
+
+/**
+ * User, group or service principal the permission is granted to
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessControlPrincipal {
+    UserName(String),
+    GroupName(String),
+    ServicePrincipalName(String),
+}
+
+/**
+ * Permission of the submitted run, e.g. `{'group_name': 'data-team', 'permission_level': 'CAN_VIEW'}`
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AccessControlRequest {
+    #[serde(flatten)]
+    pub principal: AccessControlPrincipal,
+    pub permission_level: PermissionLevel,
+}
+
+impl SubmitRunRequest {
+    fn with_fallback_node_type(&self, node_type: Option<&String>) -> Self {
+        let mut ret = self.clone();
+        if let Some(node_type) = node_type {
+            for task in ret.tasks.iter_mut() {
+                if let Cluster::NewCluster(c) = &mut task.cluster {
+                    c.node_type_id = node_type.to_owned();
+                }
+            }
+        }
+        ret
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -267,13 +981,164 @@ pub struct NewCluster {
     pub spark_conf: Option<HashMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub custom_tags: Option<HashMap<String, String>>,
+    /**
+     * Location the cluster logs are delivered to, full job logs are read from here if the logs returned by the API are truncated
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_log_conf: Option<ClusterLogConf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spark_env_vars: Option<HashMap<String, String>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterLogConf {
+    Dbfs { destination: String },
+    S3 {
+        destination: String,
+        #[serde(flatten)]
+        options: HashMap<String, serde_json::Value>,
+    },
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cluster {
+    ExistingClusterId(String),
+    NewCluster(NewCluster),
+}
+
+/**
+ * Cluster part of `spark_config.databricks.config_template`, other parts of the template are ignored.
+ * Missing fields of the new cluster take the defaults, except `spark_version` which decides the runtime
+ */
+#[derive(Debug, Deserialize)]
+struct ClusterTemplate {
+    #[serde(default)]
+    existing_cluster_id: Option<String>,
+    #[serde(default)]
+    new_cluster: Option<NewClusterTemplate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NewClusterTemplate {
+    #[serde(default)]
+    num_workers: Option<u32>,
+    #[serde(default)]
+    spark_version: Option<String>,
+    #[serde(default)]
+    node_type_id: Option<String>,
+    /**
+     * Scalar values are accepted, e.g. `spark.executor.cores: 4`
+     */
+    #[serde(default)]
+    spark_conf: Option<HashMap<String, serde_yaml::Value>>,
+    #[serde(default)]
+    custom_tags: Option<HashMap<String, String>>,
+    #[serde(default)]
+    cluster_log_conf: Option<ClusterLogConf>,
+    #[serde(default)]
+    spark_env_vars: Option<HashMap<String, String>>,
+}
+
+impl ClusterTemplate {
+    fn parse(s: &str) -> Result<Cluster, Error> {
+        let invalid = |msg: String| {
+            Error::InvalidConfig(format!("spark_config.databricks.config_template: {}", msg))
+        };
+        let template: ClusterTemplate =
+            serde_yaml::from_str(s).map_err(|e| invalid(e.to_string()))?;
+        match template {
+            ClusterTemplate {
+                existing_cluster_id: Some(_),
+                new_cluster: Some(_),
+            } => Err(invalid(
+                "only one of `existing_cluster_id` and `new_cluster` can be set".to_string(),
+            )),
+            ClusterTemplate {
+                existing_cluster_id: Some(id),
+                ..
+            } => {
+                if id.trim().is_empty() {
+                    return Err(invalid("`existing_cluster_id` is empty".to_string()));
+                }
+                Ok(Cluster::ExistingClusterId(id.trim().to_string()))
+            }
+            ClusterTemplate {
+                new_cluster: Some(nc),
+                ..
+            } => nc.into_cluster().map_err(invalid),
+            _ => Err(invalid(
+                "either `existing_cluster_id` or `new_cluster` must be set".to_string(),
+            )),
+        }
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(rename_all = "snake_case")]
-pub enum Cluster {
-    ExistingClusterId(String),
-    NewCluster(NewCluster),
+impl NewClusterTemplate {
+    fn into_cluster(self) -> Result<Cluster, String> {
+        let spark_version = self
+            .spark_version
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| {
+                format!(
+                    "`new_cluster.spark_version` is missing, e.g. `{}`",
+                    DEFAULT_SPARK_VERSION
+                )
+            })?;
+        let version_pattern = regex::Regex::new(r"^\d+\.\d+\.x(-[a-z0-9-]+)?-scala\d+\.\d+$").unwrap();
+        if !version_pattern.is_match(&spark_version) {
+            return Err(format!(
+                "invalid `new_cluster.spark_version` '{}', expecting Databricks runtime version like `{}`",
+                spark_version, DEFAULT_SPARK_VERSION
+            ));
+        }
+        let node_type_id = self
+            .node_type_id
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| DEFAULT_NODE_TYPE.to_string());
+        // e.g. `Standard_D4_v2` on Azure, `i3.xlarge` on AWS, or `n1-standard-4` on GCP
+        let node_type_pattern = regex::Regex::new(r"^[A-Za-z0-9][A-Za-z0-9_.-]*$").unwrap();
+        if !node_type_pattern.is_match(&node_type_id) {
+            return Err(format!(
+                "unknown format of `new_cluster.node_type_id` '{}', expecting node type like `{}`",
+                node_type_id, DEFAULT_NODE_TYPE
+            ));
+        }
+        let spark_conf = self
+            .spark_conf
+            .map(|conf| {
+                conf.into_iter()
+                    .map(|(k, v)| {
+                        let v = match v {
+                            serde_yaml::Value::String(s) => s,
+                            serde_yaml::Value::Bool(b) => b.to_string(),
+                            serde_yaml::Value::Number(n) => n.to_string(),
+                            _ => {
+                                return Err(format!(
+                                    "value of `new_cluster.spark_conf.{}` must be a scalar",
+                                    k
+                                ))
+                            }
+                        };
+                        Ok((k, v))
+                    })
+                    .collect::<Result<_, _>>()
+            })
+            .transpose()?;
+        Ok(Cluster::NewCluster(NewCluster {
+            num_workers: self.num_workers.unwrap_or(DEFAULT_NUM_WORKERS),
+            spark_version,
+            node_type_id,
+            spark_conf,
+            custom_tags: self.custom_tags,
+            cluster_log_conf: self.cluster_log_conf,
+            spark_env_vars: self.spark_env_vars,
+        }))
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -313,10 +1178,108 @@ struct SubmitRunResponse {
     run_id: u64,
 }
 
+#[derive(Clone, Debug, Serialize)]
+struct CancelRunRequest {
+    run_id: u64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct ListedCluster {
+    custom_tags: Option<HashMap<String, String>>,
+    node_type_id: Option<String>,
+    // Not set for autoscaling clusters
+    num_workers: Option<u32>,
+    // Other fields omitted
+}
+
+/**
+ * Cluster spec of a listed run, unlike `ClusterSpec` it may have neither a new nor an existing cluster
+ */
+#[derive(Clone, Debug, Default, Deserialize)]
+struct ListedClusterSpec {
+    new_cluster: Option<ListedCluster>,
+    // Other fields omitted
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ListedTask {
+    #[serde(default)]
+    cluster_spec: ListedClusterSpec,
+    // Other fields omitted
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ListedRun {
+    run_id: u64,
+    #[serde(default)]
+    run_name: String,
+    state: RunState,
+    #[serde(default)]
+    start_time: i64,
+    #[serde(default)]
+    end_time: i64,
+    #[serde(default)]
+    cluster_spec: ListedClusterSpec,
+    #[serde(default)]
+    tasks: Vec<ListedTask>,
+    // Other fields omitted
+}
+
+impl ListedRun {
+    fn summary(&self, job_id: JobId) -> JobSummary {
+        // Runs submitted with tasks have the cluster spec on the task
+        let new_clusters = || {
+            std::iter::once(&self.cluster_spec)
+                .chain(self.tasks.iter().map(|t| &t.cluster_spec))
+                .filter_map(|spec| spec.new_cluster.as_ref())
+        };
+        let tag = |name: &str| {
+            new_clusters()
+                .filter_map(|c| c.custom_tags.as_ref()?.get(name))
+                .next()
+                .cloned()
+        };
+        let started_at = epoch_millis(self.start_time);
+        let ended_at = epoch_millis(self.end_time);
+        let run_info = new_clusters()
+            .find_map(|c| Some((c.node_type_id.clone()?, c.num_workers?)))
+            .map(|(node_type, num_workers)| JobRunInfo {
+                job_id,
+                node_type,
+                // Workers and the driver
+                num_nodes: num_workers + 1,
+                started_at,
+                ended_at,
+            });
+        JobSummary {
+            job_id,
+            name: self.run_name.clone(),
+            status: self.state.job_status(),
+            started_at,
+            ended_at,
+            output: tag("output"),
+            idempotency_key: tag(super::IDEMPOTENCY_KEY_TAG).and_then(|k| k.parse().ok()),
+            run_info,
+            cost: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ListRunsResponse {
+    #[serde(default)]
+    runs: Vec<ListedRun>,
+    #[serde(default)]
+    has_more: bool,
+}
+
 #[async_trait]
 impl JobClient for DatabricksClient {
     async fn write_remote_file(&self, path: &str, content: &[u8]) -> Result<String, Error> {
+        let start = Instant::now();
+        self.exists_cache.invalidate(path);
         self.dbfs.write_file(path, content).await?;
+        super::manifest::record_upload(path, content, start.elapsed());
         Ok(path.to_string())
     }
 
@@ -324,151 +1287,216 @@ impl JobClient for DatabricksClient {
         Ok(self.dbfs.read_file(path).await?.into())
     }
 
-    async fn submit_job(
-        &self,
-        var_source: Arc<dyn VarSource + Send + Sync>,
-        request: SubmitJobRequest,
-    ) -> Result<JobId, Error> {
-        let args = self.get_arguments(var_source.clone(), &request).await?;
+    async fn read_remote_file_range(&self, path: &str, offset: u64, length: u64) -> Result<Bytes, Error> {
+        Ok(self
+            .dbfs
+            .read_range(path, offset as usize, length as usize)
+            .await?
+            .into())
+    }
 
-        let main_jar_path = if request.main_jar_path.is_none() {
-            var_source
-                .get_environment_variable(&[
-                    "spark_config",
-                    "databricks",
-                    "feathr_runtime_location",
-                ])
-                .await
-                .ok()
-        } else {
-            request.main_jar_path
-        };
+    async fn get_remote_last_modified(&self, path: &str) -> Result<Option<DateTime<Utc>>, Error> {
+        self.exists_cache
+            .get_or_lookup(path, || async {
+                let status = match self.dbfs.get_file_status(path).await {
+                    Ok(status) => status,
+                    Err(Error::DatabricksApiError(code, _)) if code == "RESOURCE_DOES_NOT_EXIST" => {
+                        return Ok(None)
+                    }
+                    Err(e) => return Err(e),
+                };
+                let mut latest = status.modification_time;
+                let mut dirs = vec![];
+                if status.is_dir {
+                    dirs.push(status.path);
+                }
+                while let Some(dir) = dirs.pop() {
+                    for status in self.dbfs.list(&dir).await? {
+                        latest = latest.max(status.modification_time);
+                        if status.is_dir {
+                            dirs.push(status.path);
+                        }
+                    }
+                }
+                Ok(Utc.timestamp_millis_opt(latest as i64).single())
+            })
+            .await
+    }
 
-        let mut orig_files: Vec<String> = vec![];
-        let mut orig_jars: Vec<String> = match main_jar_path.clone() {
-            Some(p) => vec![p],
-            None => vec![],
+    async fn list_remote_dir(&self, path: &str) -> Result<Vec<RemoteFile>, Error> {
+        let mut ret: Vec<RemoteFile> = match self.dbfs.list(path).await {
+            Ok(list) => list
+                .into_iter()
+                .map(|status| RemoteFile {
+                    url: format!("dbfs:{}", status.path),
+                    size: status.file_size as u64,
+                    is_dir: status.is_dir,
+                })
+                .collect(),
+            Err(Error::DatabricksApiError(code, _)) if code == "RESOURCE_DOES_NOT_EXIST" => vec![],
+            Err(e) => return Err(e),
         };
+        ret.sort_by(|a, b| a.url.cmp(&b.url));
+        Ok(ret)
+    }
 
-        for f in request.reference_files.into_iter() {
-            if f.ends_with(".jar") {
-                orig_jars.push(f)
-            } else {
-                orig_files.push(f)
-            }
+    async fn delete_remote_path(&self, path: &str) -> Result<(), Error> {
+        self.exists_cache.invalidate(path);
+        match self.dbfs.delete_dir(path).await {
+            Err(Error::DatabricksApiError(code, _)) if code == "RESOURCE_DOES_NOT_EXIST" => Ok(()),
+            r => r,
         }
+    }
 
-        debug!("Uploading JARs: {:#?}", orig_jars);
-        let jars = self.multi_upload_or_get_url(&orig_jars).await?;
-        debug!("JARs uploaded, URLs: {:#?}", jars);
+    async fn move_remote_path(&self, from: &str, to: &str) -> Result<(), Error> {
+        self.exists_cache.invalidate(from);
+        self.exists_cache.invalidate(to);
+        match self.dbfs.move_file(from, to).await {
+            Err(Error::DatabricksApiError(code, _)) if code == "RESOURCE_DOES_NOT_EXIST" => Ok(()),
+            r => r,
+        }
+    }
 
-        debug!("Uploading files: {:#?}", orig_files);
-        let files = self.multi_upload_or_get_url(&orig_files).await?;
-        debug!("Files uploaded, URLs: {:#?}", files);
+    async fn submit_job(
+        &self,
+        var_source: Arc<dyn VarSource + Send + Sync>,
+        request: SubmitJobRequest,
+    ) -> Result<JobId, Error> {
+        let args = self.get_arguments(var_source.clone(), &request).await?;
+        let job = self.build_run_request(var_source, request, args).await?;
+        debug!(
+            "Job request: {}",
+            serde_json::to_string_pretty(&job).unwrap()
+        );
 
-        debug!("Uploading Python files: {:#?}", request.python_files);
-        let py_files = self.multi_upload_or_get_url(&request.python_files).await?;
-        debug!("Python files uploaded, URLs: {:#?}", py_files);
+        let run_id = self.submit_run(&job).await?;
+        debug!("Job submitted, id is {}", run_id);
+        if matches!(self.cluster, Cluster::NewCluster(_)) && self.max_launch_retries > 0 {
+            self.launches.lock()?.insert(
+                run_id,
+                LaunchState {
+                    request: job,
+                    current_run_id: run_id,
+                    retries: 0,
+                },
+            );
+        }
+        Ok(JobId(run_id))
+    }
 
-        let task = if let Some(code) = request.main_python_script {
-            let py_url = self
-                .write_remote_file(
-                    &self.get_remote_url(&format!(
-                        "feathr_pyspark_driver_{}_{}.py",
-                        request.name,
-                        request.job_key.as_simple()
-                    )),
-                    code.as_bytes(),
-                )
-                .await?;
-            debug!("Main executable file: {}", py_url);
-            SparkTask::SparkPythonTask {
-                python_file: py_url,
-                parameters: args,
+    async fn create_scheduled_job(
+        &self,
+        var_source: Arc<dyn VarSource + Send + Sync>,
+        request: SubmitJobRequest,
+    ) -> Result<u64, Error> {
+        let cron_expr = request
+            .schedule
+            .clone()
+            .ok_or_else(|| Error::InvalidSchedule(String::default(), "schedule is not set".to_string()))?;
+        if request.main_python_script.is_some() {
+            return Err(Error::InvalidSchedule(
+                cron_expr,
+                "jobs with Python UDFs can't be scheduled".to_string(),
+            ));
+        }
+        if request.gen_job_config.is_empty() {
+            return Err(Error::InvalidSchedule(
+                cron_expr,
+                "only feature generation jobs can be scheduled".to_string(),
+            ));
+        }
+        // The job definition only has redacted arguments, credentials are put into the secret scope
+        // and read by the driver script at run time
+        let args = self.get_spark_arguments(var_source.clone(), &request).await?;
+        let redacted = args.redacted().to_vec();
+        let secret_prefix = format!("feathr-{}", request.job_key.as_simple());
+        let secrets = collect_secrets(&secret_prefix, &args.to_vec(), &redacted);
+        let secret_scope = self.secret_scope.clone().unwrap_or_default();
+        if !secrets.is_empty() {
+            if secret_scope.is_empty() {
+                return Err(Error::InvalidSchedule(
+                    cron_expr,
+                    "`spark_config.databricks.secret_scope` must be set to schedule jobs with credentials".to_string(),
+                ));
             }
-        } else {
-            debug!("Main class name: {}", request.main_class_name);
-            SparkTask::SparkJarTask {
-                main_class_name: request.main_class_name,
-                parameters: args,
+            for (key, value) in secrets.iter() {
+                self.put_secret(&secret_scope, key, value).await?;
             }
-        };
-
-        let mut libraries: Vec<Library> = jars.into_iter().map(|jar| Library::Jar(jar)).collect();
-
-        if main_jar_path.is_none() {
-            // Add maven artifact as the dependency
-            libraries.push(Library::Maven {
-                coordinates: self.maven_artifact.clone(),
-                repo: None,
-                exclusions: vec![],
-            });
+            debug!("Secrets put into scope {}: {:?}", secret_scope, secrets.keys().collect::<Vec<_>>());
         }
-
-        let cluster = match self.cluster.clone() {
-            Cluster::NewCluster(mut cluster) => {
-                cluster.custom_tags = if request.output.is_empty() {
-                    None
-                } else {
-                    let tags: HashMap<String, String> = [("output".to_string(), request.output)]
-                        .into_iter()
-                        .collect();
-                    Some(tags)
-                };
-                Cluster::NewCluster(cluster)
-            }
-            Cluster::ExistingClusterId(cluster_id) => Cluster::ExistingClusterId(cluster_id),
-        };
-
-        let job = SubmitRunRequest {
-            tasks: vec![SubmitRunSettings {
-                task_key: request.job_key.as_simple().to_string(),
-                cluster,
-                task,
-                libraries,
-            }],
-            run_name: request.name,
-        };
+        let mut request = request;
+        request.main_python_script = Some(scheduled_driver_script(
+            &secret_scope,
+            &secret_prefix,
+            &request.time_partitions,
+        )?);
+        let parameters = std::iter::once(TRIGGER_TIME_PARAM.to_string())
+            .chain(redacted)
+            .collect();
+        let job = CreateJobRequest::new(
+            self.build_run_request(var_source, request, parameters).await?,
+            &cron_expr,
+        );
         debug!(
             "Job request: {}",
             serde_json::to_string_pretty(&job).unwrap()
         );
-
-        let url = format!("{}/jobs/runs/submit", self.url_base);
-        debug!("URL: {}", url);
-        let text = self
-            .client
-            .post(url)
-            .json(&job)
-            .send()
-            .await?
-            .detailed_error_for_status()
-            .await?
-            .text()
-            .await?;
-        debug!("Response: {}", text);
-        let resp: SubmitRunResponse = serde_json::from_str(&text)?;
-        debug!("Job submitted, id is {}", resp.run_id);
-        Ok(JobId(resp.run_id))
+        let job_id = self.create_job(&job).await?;
+        info!("Scheduled job created, id is {}", job_id);
+        Ok(job_id)
     }
 
     async fn get_job_status(&self, job_id: JobId) -> Result<JobStatus, Error> {
-        Ok(self.get_run_status(job_id.0).await?.0)
+        Ok(self.get_run_status(job_id.0).await?.status)
+    }
+
+    async fn cancel_job(&self, job_id: JobId) -> Result<(), Error> {
+        self.cancel_run(job_id.0).await
+    }
+
+    async fn list_jobs(&self, limit: usize) -> Result<Vec<JobSummary>, Error> {
+        self.list_runs(limit).await
     }
 
     async fn get_job_log(&self, job_id: JobId) -> Result<String, Error> {
-        Ok(self.get_run_status(job_id.0).await?.1)
+        let run = self.get_run_status(job_id.0).await?;
+        if let Some(dir) = &run.delivered_log_dir {
+            match self.read_delivered_logs(dir).await {
+                Ok(logs) if !logs.is_empty() => return Ok(run.log(Some(&logs))),
+                Ok(_) => warn!("No delivered logs found in {}", dir),
+                Err(e) => warn!("Failed to read delivered logs from {}, error: {}", dir, e),
+            }
+        }
+        Ok(run.log(run.logs.as_deref()))
     }
 
     async fn get_job_output_url(&self, job_id: JobId) -> Result<Option<String>, Error> {
         Ok(self
             .get_run_status(job_id.0)
             .await?
-            .2
+            .tags
             .map(|tags| tags.get("output").map(|v| v.to_owned()))
             .flatten())
     }
 
+    async fn get_job_run_info(&self, job_id: JobId) -> Result<JobRunInfo, Error> {
+        let run = self.get_run_status(job_id.0).await?;
+        match run.cluster {
+            Cluster::NewCluster(nc) => Ok(JobRunInfo {
+                job_id,
+                node_type: nc.node_type_id,
+                // Workers and the driver
+                num_nodes: nc.num_workers + 1,
+                started_at: run.started_at,
+                ended_at: run.ended_at,
+            }),
+            Cluster::ExistingClusterId(id) => Err(Error::CostNotAvailable(
+                job_id,
+                format!("the job runs on existing cluster {}", id),
+            )),
+        }
+    }
+
     async fn upload_or_get_url(&self, path: &str) -> Result<String, Error> {
         let bytes = if path.starts_with("http:") || path.starts_with("https:") {
             // It's a Internet file
@@ -480,21 +1508,20 @@ impl JobClient for DatabricksClient {
                 .await?
         } else if path.starts_with("dbfs:/") {
             // It's a file on the storage
+            super::manifest::record_reference(path);
             return Ok(path.to_string());
         } else {
             // Local file
-            let mut v: Vec<u8> = vec![];
-            tokio::fs::File::open(path)
-                .await?
-                .read_to_end(&mut v)
-                .await?;
-            Bytes::from(v)
+            Bytes::from(crate::runtime::read_file(path).await?)
         };
         let url = self.get_remote_url(&self.get_file_name(path)?);
         self.write_remote_file(&url, &bytes).await
     }
 
     fn get_remote_url(&self, filename: &str) -> String {
+        if let Some(url) = super::overridden_remote_url(filename) {
+            return url;
+        }
         format!(
             "dbfs:/{}",
             [self.workspace_dir.as_str().trim_end_matches("/"), filename]
@@ -530,6 +1557,8 @@ mod tests {
                 node_type_id: "Standard_D3_v2".to_string(),
                 spark_conf: Default::default(),
                 custom_tags: None,
+                cluster_log_conf: None,
+                spark_env_vars: None,
             }),
             task: SparkTask::SparkJarTask {
                 main_class_name: "mainClassName".to_string(),
@@ -538,6 +1567,17 @@ mod tests {
             libraries: lib,
         };
         println!("{}", serde_json::to_string_pretty(&x).unwrap());
+
+        let run = SubmitRunRequest {
+            tasks: vec![x],
+            run_name: "gen".to_string(),
+            access_control_list: vec![],
+        };
+        let job = serde_json::to_value(CreateJobRequest::new(run, "0 0 1 * * ?")).unwrap();
+        assert_eq!(job["name"], "gen");
+        assert_eq!(job["schedule"]["quartz_cron_expression"], "0 0 1 * * ?");
+        assert_eq!(job["schedule"]["timezone_id"], "UTC");
+        assert!(job.get("access_control_list").is_none());
     }
 
     #[test]
@@ -568,4 +1608,294 @@ mod tests {
             _ => assert!(false),
         }
     }
+
+    #[test]
+    fn launch_failure_retry() {
+        let reason: TerminationReason = serde_json::from_str(
+            r#"{"code":"CLOUD_PROVIDER_LAUNCH_FAILURE","type":"CLOUD_FAILURE","parameters":{"azure_error_code":"OperationNotAllowed"}}"#,
+        )
+        .unwrap();
+        assert!(reason.is_retryable());
+        let reason: TerminationReason =
+            serde_json::from_str(r#"{"code":"USER_REQUEST","type":"SUCCESS"}"#).unwrap();
+        assert!(!reason.is_retryable());
+
+        let req = SubmitRunRequest {
+            tasks: vec![SubmitRunSettings {
+                task_key: "t1".to_string(),
+                cluster: Cluster::NewCluster(NewCluster {
+                    num_workers: 2,
+                    spark_version: "9.1.x-scala2.12".to_string(),
+                    node_type_id: "Standard_D3_v2".to_string(),
+                    spark_conf: Default::default(),
+                    custom_tags: None,
+                    cluster_log_conf: None,
+                    spark_env_vars: None,
+                }),
+                task: SparkTask::SparkJarTask {
+                    main_class_name: "mainClassName".to_string(),
+                    parameters: vec![],
+                },
+                libraries: vec![],
+            }],
+            run_name: "r1".to_string(),
+            access_control_list: vec![],
+        };
+        let fallback = req.with_fallback_node_type(Some(&"Standard_F4s".to_string()));
+        match &fallback.tasks[0].cluster {
+            Cluster::NewCluster(c) => assert_eq!(c.node_type_id, "Standard_F4s"),
+            _ => panic!("unexpected cluster type"),
+        }
+        match &req.with_fallback_node_type(None).tasks[0].cluster {
+            Cluster::NewCluster(c) => assert_eq!(c.node_type_id, "Standard_D3_v2"),
+            _ => panic!("unexpected cluster type"),
+        }
+    }
+
+    #[tokio::test]
+    async fn cancel_relaunched_run() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url_base = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let mut buf = [0u8; 1024];
+            while !String::from_utf8_lossy(&request).contains("run_id") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}")
+                .await
+                .unwrap();
+            socket.shutdown().await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let c = DatabricksClient::new(&url_base, "", "dbfs:/feathr", None, "");
+        c.launches.lock().unwrap().insert(
+            1,
+            LaunchState {
+                request: SubmitRunRequest {
+                    tasks: vec![],
+                    run_name: "r1".to_string(),
+                    access_control_list: vec![],
+                },
+                current_run_id: 2,
+                retries: 0,
+            },
+        );
+        c.cancel_job(JobId(1)).await.unwrap();
+        let request = server.await.unwrap();
+        // The resubmitted run is cancelled instead of the original one
+        assert!(request.starts_with("POST /api/2.0/jobs/runs/cancel "));
+        assert!(request.ends_with(r#"{"run_id":2}"#));
+        assert_eq!(c.launches.lock().unwrap()[&1].retries, c.max_launch_retries);
+    }
+
+    #[test]
+    fn listed_runs() {
+        let resp: ListRunsResponse = serde_json::from_str(
+            r#"{
+                "runs": [
+                    {
+                        "run_id": 12,
+                        "run_name": "join_1",
+                        "state": {"life_cycle_state": "TERMINATED", "result_state": "SUCCESS"},
+                        "start_time": 1665000000000,
+                        "end_time": 1665000600000,
+                        "tasks": [{"cluster_spec": {"new_cluster": {"custom_tags": {"output": "dbfs:/out/join_1"}, "node_type_id": "Standard_F4s", "num_workers": 2}}}]
+                    },
+                    {
+                        "run_id": 11,
+                        "state": {"life_cycle_state": "PENDING"},
+                        "start_time": 0,
+                        "cluster_spec": {"job_cluster_key": "c1"}
+                    }
+                ],
+                "has_more": false
+            }"#,
+        )
+        .unwrap();
+        let s = resp.runs[0].summary(JobId(10));
+        assert_eq!(s.job_id, JobId(10));
+        assert_eq!(s.name, "join_1");
+        assert_eq!(s.status, JobStatus::Success);
+        assert_eq!(s.started_at.unwrap().timestamp(), 1665000000);
+        assert_eq!(s.ended_at.unwrap().timestamp(), 1665000600);
+        assert_eq!(s.output.as_deref(), Some("dbfs:/out/join_1"));
+        let run = s.run_info.unwrap();
+        assert_eq!((run.node_type.as_str(), run.num_nodes), ("Standard_F4s", 3));
+        assert_eq!(run.duration(), chrono::Duration::minutes(10));
+
+        let s = resp.runs[1].summary(JobId(11));
+        assert_eq!(s.status, JobStatus::Provisioning);
+        assert_eq!(s.started_at, None);
+        assert_eq!(s.output, None);
+        assert_eq!(s.run_info, None);
+    }
+
+    #[test]
+    fn cluster_template() {
+        let c = ClusterTemplate::parse(
+            "{'run_name':'','new_cluster':{'spark_version':'9.1.x-scala2.12','node_type_id':'Standard_F4s','num_workers':2,'spark_conf':{}},'libraries':[{'jar':''}]}",
+        )
+        .unwrap();
+        assert!(matches!(c, Cluster::NewCluster(nc) if nc.node_type_id == "Standard_F4s"));
+
+        // Partial template
+        let c = ClusterTemplate::parse(
+            "new_cluster:\n  spark_version: 11.3.x-cpu-ml-scala2.12\n  spark_conf:\n    spark.executor.cores: 4\n",
+        )
+        .unwrap();
+        match c {
+            Cluster::NewCluster(nc) => {
+                assert_eq!(nc.num_workers, DEFAULT_NUM_WORKERS);
+                assert_eq!(nc.node_type_id, DEFAULT_NODE_TYPE);
+                assert_eq!(nc.spark_conf.unwrap()["spark.executor.cores"], "4");
+            }
+            _ => panic!("unexpected cluster type"),
+        }
+        assert!(matches!(
+            ClusterTemplate::parse("existing_cluster_id: 0123-456789-abcdefgh").unwrap(),
+            Cluster::ExistingClusterId(id) if id == "0123-456789-abcdefgh"
+        ));
+
+        for (template, msg) in [
+            ("new_cluster: {node_type_id: Standard_F4s}", "spark_version` is missing"),
+            ("new_cluster: {spark_version: '3.2.1'}", "invalid `new_cluster.spark_version`"),
+            ("new_cluster: {spark_version: 9.1.x-scala2.12, node_type_id: 'Standard F4s'}", "unknown format"),
+            ("run_name: r1", "must be set"),
+            ("new_cluster: [", "config_template"),
+        ] {
+            match ClusterTemplate::parse(template) {
+                Err(Error::InvalidConfig(e)) => assert!(e.contains(msg), "{}", e),
+                r => panic!("unexpected result {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn access_control_list() {
+        let acl: Vec<AccessControlRequest> = serde_yaml::from_str(
+            "[{'group_name':'data-team','permission_level':'CAN_VIEW'},{'user_name':'someone@example.com','permission_level':'CAN_MANAGE_RUN'}]",
+        )
+        .unwrap();
+        assert_eq!(
+            acl[0],
+            AccessControlRequest {
+                principal: AccessControlPrincipal::GroupName("data-team".to_string()),
+                permission_level: PermissionLevel::CanView,
+            }
+        );
+        assert_eq!(acl[1].permission_level, PermissionLevel::CanManageRun);
+        let req = SubmitRunRequest {
+            tasks: vec![],
+            run_name: "r1".to_string(),
+            access_control_list: acl,
+        };
+        let v = serde_json::to_value(&req).unwrap();
+        assert_eq!(
+            v["access_control_list"][0],
+            serde_json::json!({"group_name": "data-team", "permission_level": "CAN_VIEW"})
+        );
+        let req = SubmitRunRequest {
+            access_control_list: vec![],
+            ..req
+        };
+        assert!(serde_json::to_value(&req).unwrap().get("access_control_list").is_none());
+    }
+
+    #[test]
+    fn delivered_log_dir() {
+        let s = r#"{
+            "metadata": {
+                "state": {"life_cycle_state": "TERMINATED", "result_state": "FAILED"},
+                "cluster_spec": {"new_cluster": {
+                    "spark_version": "9.1.x-scala2.12",
+                    "cluster_log_conf": {"dbfs": {"destination": "dbfs:/cluster-logs/"}}
+                }},
+                "cluster_instance": {"cluster_id": "0701-abc"}
+            },
+            "logs": "...",
+            "logs_truncated": true
+        }"#;
+        let resp: GetRunOutputResponse = serde_json::from_str(s).unwrap();
+        assert_eq!(
+            resp.delivered_log_dir(),
+            Some("dbfs:/cluster-logs/0701-abc".to_string())
+        );
+
+        let resp: GetRunOutputResponse =
+            serde_json::from_str(&s.replace(r#""logs_truncated": true"#, r#""logs_truncated": false"#))
+                .unwrap();
+        assert_eq!(resp.delivered_log_dir(), None);
+    }
+
+    #[test]
+    fn scheduled_job_secrets() {
+        use crate::{BlobConfig, RedisConfig, SparkArguments, SparkJobArguments};
+        let args = SparkArguments {
+            s3_config: Default::default(),
+            adls_config: Default::default(),
+            blob_config: BlobConfig {
+                blob_account: "account1".to_string(),
+                blob_key: "blob-k3y".to_string(),
+                blob_account_keys: [("account2.blob.core.windows.net".to_string(), "blob2-k3y".to_string())]
+                    .into_iter()
+                    .collect(),
+            },
+            sql_config: Default::default(),
+            snowflake_config: Default::default(),
+            feature_config_url: "dbfs:/features.conf".to_string(),
+            job: SparkJobArguments::Generation {
+                redis_config: RedisConfig {
+                    redis_host: "host1".to_string(),
+                    redis_password: "redis-pa55".to_string(),
+                    ..Default::default()
+                },
+                kafka_config: Default::default(),
+                cosmos_config: Default::default(),
+                monitoring_config: None,
+                generation_config_url: "dbfs:/gen.conf".to_string(),
+            },
+        };
+        let secrets = collect_secrets("feathr-abc", &args.to_vec(), &args.redacted().to_vec());
+        let expected: HashMap<String, String> = [
+            ("feathr-abc.blob-config.BLOB_KEY", "blob-k3y"),
+            ("feathr-abc.blob-config.BLOB_ACCOUNT_KEYS.account2.blob.core.windows.net", "blob2-k3y"),
+            ("feathr-abc.redis-config.REDIS_PASSWORD", "redis-pa55"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        assert_eq!(secrets, expected);
+        assert_eq!(
+            secret_name("feathr-abc", &["sql-config", "JDBC_PROPERTIES", "ssl password"]),
+            "feathr-abc.sql-config.JDBC_PROPERTIES.ssl_password"
+        );
+
+        let partitions = vec![TimePartition {
+            sink: 1,
+            path: "abfss://c@a.dfs.core.windows.net/out".to_string(),
+            pattern: "%Y/%m/%d".to_string(),
+            update_latest: true,
+            overwrite_existing: false,
+        }];
+        let script = scheduled_driver_script("feathr", "feathr-abc", &partitions).unwrap();
+        let encoded: String = script
+            .split("\"\"\"")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .collect();
+        let params: Value = serde_json::from_slice(&base64::decode(encoded).unwrap()).unwrap();
+        assert_eq!(params["secret_scope"], "feathr");
+        assert_eq!(params["secret_prefix"], "feathr-abc");
+        assert_eq!(params["time_partitions"][0]["sink"], 1);
+        assert_eq!(params["time_partitions"][0]["pattern"], "%Y/%m/%d");
+        assert!(!script.contains("redis-pa55"));
+    }
 }
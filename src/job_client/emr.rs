@@ -0,0 +1,759 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, TimeZone, Utc};
+use flate2::read::MultiGzDecoder;
+use log::debug;
+use reqwest::{Method, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use super::aws::{parse_s3_url, AwsCredentials, AwsRequest, S3Storage, S3_SCHEMES};
+use crate::{
+    livy_client::SparkRequest, Error, ExistsCache, JobClient, JobId, JobRunInfo, JobStatus,
+    RemoteFile, Timeouts, VarSource,
+};
+
+/**
+ * Spark job submitted as a step of an existing EMR cluster, with artifacts stored on S3
+ */
+#[derive(Debug)]
+pub struct EmrClient {
+    api_client: reqwest::Client,
+    credentials: AwsCredentials,
+    region: String,
+    storage: S3Storage,
+    cluster_id: String,
+    workspace_dir: String,
+    log_uri: Option<String>,
+    maven_artifact: String,
+    steps: Mutex<HashMap<u64, EmrStep>>,
+    exists_cache: ExistsCache,
+}
+
+#[derive(Clone, Debug)]
+struct EmrStep {
+    step_id: String,
+    output: Option<String>,
+}
+
+impl EmrClient {
+    /**
+     * Create client with the region and id of the cluster, and the workspace dir (e.g. `s3://bucket/feathr`)
+     */
+    pub fn new(
+        region: &str,
+        cluster_id: &str,
+        credentials: AwsCredentials,
+        workspace_dir: &str,
+    ) -> Result<Self, Error> {
+        parse_s3_url(workspace_dir)?;
+        let timeouts = Timeouts::default();
+        Ok(Self {
+            api_client: timeouts.api_client()?.build()?,
+            storage: S3Storage::new(
+                timeouts.transfer_client()?.build()?,
+                credentials.clone(),
+                region,
+            ),
+            credentials,
+            region: region.to_string(),
+            cluster_id: cluster_id.to_string(),
+            workspace_dir: workspace_dir.trim_end_matches('/').to_string(),
+            log_uri: None,
+            maven_artifact: super::FEATHR_MAVEN_ARTIFACT.to_string(),
+            steps: Default::default(),
+            exists_cache: Default::default(),
+        })
+    }
+
+    /**
+     * S3 location of the cluster logs, taken from the cluster if not set
+     */
+    pub fn log_uri(mut self, log_uri: &str) -> Result<Self, Error> {
+        parse_s3_url(log_uri)?;
+        self.log_uri = Some(log_uri.trim_end_matches('/').to_string());
+        Ok(self)
+    }
+
+    /**
+     * EMR calls are limited by the API timeout, S3 operations by the transfer timeout
+     */
+    pub fn timeouts(mut self, timeouts: &Timeouts) -> Result<Self, Error> {
+        self.api_client = timeouts.api_client()?.build()?;
+        self.storage.set_client(timeouts.transfer_client()?.build()?);
+        Ok(self)
+    }
+
+    pub(crate) async fn from_var_source(
+        var_source: Arc<dyn VarSource + Send + Sync>,
+    ) -> Result<Self, Error> {
+        let get = |key: &'static str| {
+            let var_source = var_source.clone();
+            async move {
+                var_source
+                    .get_environment_variable(&["spark_config", "emr", key])
+                    .await
+                    .ok()
+                    .filter(|s| !s.trim().is_empty())
+            }
+        };
+        let region = match get("region").await {
+            Some(region) => region,
+            None => var_source
+                .get_environment_variable(&["AWS_REGION"])
+                .await
+                .map_err(|_| Error::InvalidConfig("spark_config.emr.region is not set".to_string()))?,
+        };
+        let cluster_id = get("cluster_id").await.ok_or_else(|| {
+            Error::InvalidConfig("spark_config.emr.cluster_id is not set".to_string())
+        })?;
+        let workspace_dir = get("workspace_dir").await.ok_or_else(|| {
+            Error::InvalidConfig("spark_config.emr.workspace_dir is not set".to_string())
+        })?;
+        let credentials = AwsCredentials {
+            access_key_id: var_source
+                .get_environment_variable(&["AWS_ACCESS_KEY_ID"])
+                .await?,
+            secret_access_key: var_source
+                .get_environment_variable(&["AWS_SECRET_ACCESS_KEY"])
+                .await?,
+            session_token: var_source
+                .get_environment_variable(&["AWS_SESSION_TOKEN"])
+                .await
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+        };
+        let mut client = Self::new(&region, &cluster_id, credentials, &workspace_dir)?
+            .timeouts(&Timeouts::from_var_source(&var_source).await?)?;
+        if let Some(log_uri) = get("log_uri").await {
+            client = client.log_uri(&log_uri)?;
+        }
+        if let Ok(artifact) = var_source
+            .get_environment_variable(&["spark_config", "maven_artifact"])
+            .await
+        {
+            if !artifact.trim().is_empty() {
+                client.maven_artifact = artifact;
+            }
+        }
+        Ok(client)
+    }
+
+    async fn emr_request<T: DeserializeOwned>(&self, action: &str, body: Value) -> Result<T, Error> {
+        let mut request = AwsRequest::new(
+            Method::POST,
+            &format!("https://elasticmapreduce.{}.amazonaws.com", self.region),
+            "/",
+        );
+        request.headers.insert(
+            "content-type".to_string(),
+            "application/x-amz-json-1.1".to_string(),
+        );
+        request.headers.insert(
+            "x-amz-target".to_string(),
+            format!("ElasticMapReduce.{}", action),
+        );
+        request.body = Bytes::from(serde_json::to_vec(&body)?);
+        let url = request.url();
+        match request
+            .send(&self.api_client, &self.credentials, &self.region, "elasticmapreduce")
+            .await?
+        {
+            Some(resp) => Ok(serde_json::from_slice(&resp.bytes().await?)?),
+            None => Err(Error::AwsHttpError(url, StatusCode::NOT_FOUND, action.to_string())),
+        }
+    }
+
+    /**
+     * Steps submitted by other clients are looked up in the steps of the cluster
+     */
+    async fn get_step(&self, job_id: JobId) -> Result<EmrStep, Error> {
+        if let Some(step) = self
+            .steps
+            .lock()
+            .map_err(|e| Error::RuntimeError(e.to_string()))?
+            .get(&job_id.0)
+            .cloned()
+        {
+            return Ok(step);
+        }
+        let mut marker: Option<String> = None;
+        loop {
+            let mut body = json!({ "ClusterId": self.cluster_id });
+            if let Some(marker) = marker {
+                body["Marker"] = Value::String(marker);
+            }
+            let resp: ListStepsResponse = self.emr_request("ListSteps", body).await?;
+            if let Some(summary) = resp.steps.into_iter().find(|s| step_job_id(&s.id) == job_id) {
+                let step = EmrStep {
+                    output: step_output(&summary.config.args),
+                    step_id: summary.id,
+                };
+                self.steps
+                    .lock()
+                    .map_err(|e| Error::RuntimeError(e.to_string()))?
+                    .insert(job_id.0, step.clone());
+                return Ok(step);
+            }
+            marker = resp.marker;
+            if marker.is_none() {
+                return Err(Error::UnknownJob(job_id));
+            }
+        }
+    }
+
+    async fn describe_step(&self, job_id: JobId) -> Result<EmrStepInfo, Error> {
+        let step = self.get_step(job_id).await?;
+        let resp: DescribeStepResponse = self
+            .emr_request(
+                "DescribeStep",
+                json!({ "ClusterId": self.cluster_id, "StepId": step.step_id }),
+            )
+            .await?;
+        Ok(resp.step)
+    }
+}
+
+/**
+ * EMR step ids are strings like `s-1A2B3C4D5E6F7`, the job id is the FNV-1a hash of the step id
+ */
+fn step_job_id(step_id: &str) -> JobId {
+    JobId(step_id.bytes().fold(0xcbf29ce484222325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    }))
+}
+
+fn step_status(state: &str) -> JobStatus {
+    match state {
+        "PENDING" => JobStatus::Starting,
+        "RUNNING" | "CANCEL_PENDING" => JobStatus::Running,
+        "COMPLETED" => JobStatus::Success,
+        // CANCELLED, FAILED, INTERRUPTED
+        _ => JobStatus::Failed,
+    }
+}
+
+/**
+ * The step reads the `spark-submit` arguments from the file in `$0`, one per line
+ */
+const STEP_SCRIPT: &str = r#"set -e; f=$(mktemp); aws s3 cp "$0" "$f" --quiet; readarray -t args < "$f"; rm -f "$f"; exec spark-submit "${args[@]}""#;
+
+/**
+ * Arguments of the step, the `spark-submit` arguments carry credentials so they're kept in the
+ * file on S3 instead of the step config, which `DescribeStep` shows to anyone with read access to
+ * the cluster. The output path is appended so jobs can be re-attached by other clients.
+ */
+fn step_args(args_url: &str, output: Option<&str>) -> Vec<String> {
+    ["bash", "-c", STEP_SCRIPT, args_url]
+        .into_iter()
+        .chain(output)
+        .map(str::to_string)
+        .collect()
+}
+
+/**
+ * Output path of the step submitted by `step_args`
+ */
+fn step_output(args: &[String]) -> Option<String> {
+    match args {
+        [bash, _, script, _, output] if bash == "bash" && script == STEP_SCRIPT => Some(output.to_owned()),
+        _ => None,
+    }
+}
+
+/**
+ * Arguments of `spark-submit` running the job in cluster mode
+ */
+fn spark_submit_args(job: &SparkRequest) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "spark-submit".to_string(),
+        "--deploy-mode".to_string(),
+        "cluster".to_string(),
+        "--name".to_string(),
+        job.name.clone(),
+    ];
+    if !job.class_name.is_empty() {
+        args.extend(["--class".to_string(), job.class_name.clone()]);
+    }
+    let conf: BTreeMap<_, _> = job.conf.iter().collect();
+    for (k, v) in conf {
+        args.extend(["--conf".to_string(), format!("{}={}", k, v)]);
+    }
+    for (option, files) in [
+        ("--jars", &job.jars),
+        ("--files", &job.files),
+        ("--py-files", &job.py_files),
+    ] {
+        if !files.is_empty() {
+            args.extend([option.to_string(), files.join(",")]);
+        }
+    }
+    args.push(job.file.clone());
+    args.extend(job.args.iter().cloned());
+    args
+}
+
+fn epoch_seconds(t: Option<f64>) -> Option<DateTime<Utc>> {
+    t.and_then(|t| Utc.timestamp_millis_opt((t * 1000.0) as i64).single())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AddJobFlowStepsResponse {
+    step_ids: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmrStepTimeline {
+    start_date_time: Option<f64>,
+    end_date_time: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmrStepStatus {
+    state: String,
+    #[serde(default)]
+    timeline: EmrStepTimeline,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmrStepInfo {
+    status: EmrStepStatus,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribeStepResponse {
+    step: EmrStepInfo,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmrStepConfig {
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmrStepSummary {
+    id: String,
+    #[serde(default)]
+    config: EmrStepConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListStepsResponse {
+    #[serde(default)]
+    steps: Vec<EmrStepSummary>,
+    marker: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CancelStepsInfo {
+    status: String,
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CancelStepsResponse {
+    #[serde(default)]
+    cancel_steps_info_list: Vec<CancelStepsInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmrCluster {
+    log_uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribeClusterResponse {
+    cluster: EmrCluster,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InstanceGroup {
+    instance_group_type: String,
+    instance_type: String,
+    running_instance_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListInstanceGroupsResponse {
+    instance_groups: Vec<InstanceGroup>,
+}
+
+#[async_trait]
+impl JobClient for EmrClient {
+    async fn write_remote_file(&self, path: &str, content: &[u8]) -> Result<String, Error> {
+        let start = Instant::now();
+        self.exists_cache.invalidate(path);
+        self.storage.write(path, content).await?;
+        super::manifest::record_upload(path, content, start.elapsed());
+        Ok(path.to_string())
+    }
+
+    async fn read_remote_file(&self, path: &str) -> Result<Bytes, Error> {
+        self.storage.read(path).await
+    }
+
+    async fn read_remote_file_range(&self, path: &str, offset: u64, length: u64) -> Result<Bytes, Error> {
+        self.storage.read_range(path, offset, length).await
+    }
+
+    async fn get_remote_last_modified(&self, path: &str) -> Result<Option<DateTime<Utc>>, Error> {
+        self.exists_cache
+            .get_or_lookup(path, || self.storage.last_modified(path))
+            .await
+    }
+
+    async fn list_remote_dir(&self, path: &str) -> Result<Vec<RemoteFile>, Error> {
+        self.storage.list_dir(path).await
+    }
+
+    async fn delete_remote_path(&self, path: &str) -> Result<(), Error> {
+        self.exists_cache.invalidate(path);
+        self.storage.delete(path).await
+    }
+
+    async fn move_remote_path(&self, from: &str, to: &str) -> Result<(), Error> {
+        self.exists_cache.invalidate(from);
+        self.exists_cache.invalidate(to);
+        self.storage.rename(from, to).await
+    }
+
+    async fn submit_job(
+        &self,
+        var_source: Arc<dyn VarSource + Send + Sync>,
+        request: super::SubmitJobRequest,
+    ) -> Result<JobId, Error> {
+        let args = self.get_arguments(var_source.clone(), &request).await?;
+
+        let main_jar_path = match request.main_jar_path {
+            Some(p) => Some(p),
+            None => var_source
+                .get_environment_variable(&["spark_config", "emr", "feathr_runtime_location"])
+                .await
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+        };
+
+        let mut jars: Vec<String> = main_jar_path.clone().into_iter().collect();
+        let mut files: Vec<String> = vec![];
+        for f in request.reference_files.into_iter() {
+            if f.ends_with(".jar") {
+                jars.push(f)
+            } else {
+                files.push(f)
+            }
+        }
+        let jars = self.multi_upload_or_get_url(&jars).await?;
+        let files = self.multi_upload_or_get_url(&files).await?;
+        let py_files = self.multi_upload_or_get_url(&request.python_files).await?;
+
+        let executable = match request.main_python_script.as_ref() {
+            Some(code) => {
+                self.write_remote_file(
+                    &self.get_remote_url(&format!(
+                        "feathr_pyspark_driver_{}_{}.py",
+                        request.name, request.job_key
+                    )),
+                    code.as_bytes(),
+                )
+                .await?
+            }
+            None => jars.first().cloned().ok_or_else(|| {
+                Error::InvalidConfig(
+                    "spark_config.emr.feathr_runtime_location is not set".to_string(),
+                )
+            })?,
+        };
+        debug!("Main executable file: {}", executable);
+
+        let mut conf = request.configuration;
+        conf.extend(super::env_var_conf(&request.env_vars));
+        if main_jar_path.is_none() {
+            let v = match conf.get("spark.jars.packages") {
+                Some(v) => format!("{},{}", v, self.maven_artifact),
+                None => self.maven_artifact.clone(),
+            };
+            conf.insert("spark.jars.packages".to_string(), v);
+        }
+
+        let job = SparkRequest {
+            args,
+            class_name: if request.main_python_script.is_none() {
+                request.main_class_name
+            } else {
+                Default::default()
+            },
+            conf,
+            file: executable,
+            files,
+            // The main jar is the executable
+            jars: jars.into_iter().skip(1).collect(),
+            name: format!("{}_{}", request.name, request.job_key),
+            py_files,
+            ..Default::default()
+        };
+        let submit_args = spark_submit_args(&job);
+        if submit_args.iter().any(|arg| arg.contains('\n')) {
+            return Err(Error::InvalidConfig(
+                "Arguments of EMR steps must not contain line breaks".to_string(),
+            ));
+        }
+        let args_url = self.get_remote_url(&format!("feathr_spark_submit_{}.args", job.name));
+        self.storage
+            .write(&args_url, submit_args.join("\n").as_bytes())
+            .await?;
+        // The AWS CLI on the cluster only takes the `s3` scheme
+        let (bucket, key) = parse_s3_url(&args_url)?;
+        let output = request.job_tags.get(super::OUTPUT_PATH_TAG).cloned();
+        let step = json!({
+            "JobFlowId": self.cluster_id,
+            "Steps": [{
+                "Name": job.name,
+                "ActionOnFailure": "CONTINUE",
+                "HadoopJarStep": {
+                    "Jar": "command-runner.jar",
+                    "Args": step_args(&format!("s3://{}/{}", bucket, key), output.as_deref()),
+                },
+            }],
+        });
+        debug!("Job request: {:#?}", step);
+        let resp: AddJobFlowStepsResponse = self.emr_request("AddJobFlowSteps", step).await?;
+        let step_id = resp
+            .step_ids
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::RuntimeError("EMR didn't return the step id".to_string()))?;
+        let job_id = step_job_id(&step_id);
+        debug!("Job submitted as step {}, id is {}", step_id, job_id);
+        self.steps
+            .lock()
+            .map_err(|e| Error::RuntimeError(e.to_string()))?
+            .insert(job_id.0, EmrStep { step_id, output });
+        Ok(job_id)
+    }
+
+    async fn get_job_status(&self, job_id: JobId) -> Result<JobStatus, Error> {
+        Ok(step_status(&self.describe_step(job_id).await?.status.state))
+    }
+
+    async fn cancel_job(&self, job_id: JobId) -> Result<(), Error> {
+        let step = self.get_step(job_id).await?;
+        let resp: CancelStepsResponse = self
+            .emr_request(
+                "CancelSteps",
+                json!({
+                    "ClusterId": self.cluster_id,
+                    "StepIds": [step.step_id],
+                    "StepCancellationOption": "SEND_INTERRUPT",
+                }),
+            )
+            .await?;
+        match resp.cancel_steps_info_list.into_iter().find(|i| i.status == "FAILED") {
+            Some(info) => Err(Error::RuntimeError(format!(
+                "Failed to cancel step {}: {}",
+                step.step_id,
+                info.reason.unwrap_or_default()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    async fn get_job_log(&self, job_id: JobId) -> Result<String, Error> {
+        let step = self.get_step(job_id).await?;
+        let log_uri = match &self.log_uri {
+            Some(uri) => uri.to_owned(),
+            None => {
+                let resp: DescribeClusterResponse = self
+                    .emr_request("DescribeCluster", json!({ "ClusterId": self.cluster_id }))
+                    .await?;
+                resp.cluster.log_uri.ok_or_else(|| {
+                    Error::InvalidConfig(format!(
+                        "spark_config.emr.log_uri is not set and logging is disabled on cluster {}",
+                        self.cluster_id
+                    ))
+                })?
+            }
+        };
+        // EMR uploads step logs every few minutes, and after the step finishes
+        let url = format!(
+            "{}/{}/steps/{}/stderr.gz",
+            log_uri.trim_end_matches('/'),
+            self.cluster_id,
+            step.step_id
+        );
+        match self.storage.get(&url).await? {
+            Some(content) => {
+                let mut log = vec![];
+                MultiGzDecoder::new(content.as_ref()).read_to_end(&mut log)?;
+                Ok(String::from_utf8_lossy(&log).into_owned())
+            }
+            None => {
+                debug!("Log {} is not uploaded yet", url);
+                Ok(String::new())
+            }
+        }
+    }
+
+    async fn get_job_output_url(&self, job_id: JobId) -> Result<Option<String>, Error> {
+        Ok(self.get_step(job_id).await?.output)
+    }
+
+    async fn get_job_run_info(&self, job_id: JobId) -> Result<JobRunInfo, Error> {
+        let timeline = self.describe_step(job_id).await?.status.timeline;
+        let resp: ListInstanceGroupsResponse = self
+            .emr_request("ListInstanceGroups", json!({ "ClusterId": self.cluster_id }))
+            .await
+            .map_err(|e| Error::CostNotAvailable(job_id, e.to_string()))?;
+        // Workers are in the core group, task groups usually have the same instance type
+        let node_type = resp
+            .instance_groups
+            .iter()
+            .find(|g| g.instance_group_type == "CORE")
+            .or_else(|| resp.instance_groups.first())
+            .map(|g| g.instance_type.clone())
+            .unwrap_or_default();
+        Ok(JobRunInfo {
+            job_id,
+            node_type,
+            num_nodes: resp
+                .instance_groups
+                .iter()
+                .map(|g| g.running_instance_count)
+                .sum(),
+            started_at: epoch_seconds(timeline.start_date_time),
+            ended_at: epoch_seconds(timeline.end_date_time),
+        })
+    }
+
+    fn get_remote_url(&self, filename: &str) -> String {
+        if let Some(url) = super::overridden_remote_url(filename) {
+            return url;
+        }
+        format!("{}/{}", self.workspace_dir, filename.trim_start_matches('/'))
+    }
+
+    fn is_url_on_storage(&self, url: &str) -> bool {
+        S3_SCHEMES.iter().any(|scheme| url.starts_with(scheme))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emr_client() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".to_string(),
+            session_token: None,
+        };
+        let client =
+            EmrClient::new("us-west-2", "j-1ABCDEFGHIJKL", credentials.clone(), "s3://bucket/feathr/").unwrap();
+        let path = client.get_remote_url("job.conf");
+        assert_eq!(path, "s3://bucket/feathr/job.conf");
+        assert!(client.is_url_on_storage(&path));
+        assert!(client.is_url_on_storage("s3a://other/data.csv"));
+        assert!(!client.is_url_on_storage("abfss://c@a.dfs.core.windows.net/f"));
+        assert!(EmrClient::new("us-west-2", "j-1ABCDEFGHIJKL", credentials, "hdfs:///feathr").is_err());
+    }
+
+    #[test]
+    fn emr_step() {
+        let job = SparkRequest {
+            args: vec!["--join-config".to_string(), "s3://bucket/feathr/join.conf".to_string()],
+            class_name: "com.linkedin.feathr.offline.job.FeatureJoinJob".to_string(),
+            conf: [
+                ("spark.jars.packages".to_string(), "com.linkedin.feathr:feathr_2.12:0.4.0".to_string()),
+                ("spark.executor.memory".to_string(), "4g".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            file: "s3://bucket/feathr/feathr.jar".to_string(),
+            py_files: vec!["s3://bucket/feathr/udf.py".to_string()],
+            name: "join_1".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            spark_submit_args(&job),
+            [
+                "spark-submit",
+                "--deploy-mode",
+                "cluster",
+                "--name",
+                "join_1",
+                "--class",
+                "com.linkedin.feathr.offline.job.FeatureJoinJob",
+                "--conf",
+                "spark.executor.memory=4g",
+                "--conf",
+                "spark.jars.packages=com.linkedin.feathr:feathr_2.12:0.4.0",
+                "--py-files",
+                "s3://bucket/feathr/udf.py",
+                "s3://bucket/feathr/feathr.jar",
+                "--join-config",
+                "s3://bucket/feathr/join.conf",
+            ]
+        );
+        let args = step_args("s3://bucket/feathr/feathr_spark_submit_join_1.args", Some("s3://bucket/output"));
+        assert_eq!(args[3], "s3://bucket/feathr/feathr_spark_submit_join_1.args");
+        assert_eq!(step_output(&args).as_deref(), Some("s3://bucket/output"));
+        assert_eq!(step_output(&step_args("s3://bucket/feathr/a.args", None)), None);
+
+        let resp: ListStepsResponse = serde_json::from_value(json!({
+            "Steps": [
+                {"Id": "s-1ABCDEFGHIJKL", "Name": "join_1", "Config": {"Jar": "command-runner.jar", "Args": args}},
+                {"Id": "s-1ABCDEFGHIJKM", "Name": "setup", "Config": {"Jar": "s3://bucket/setup.jar"}},
+            ],
+            "Marker": "m1",
+        }))
+        .unwrap();
+        let summary = resp
+            .steps
+            .iter()
+            .find(|s| step_job_id(&s.id) == step_job_id("s-1ABCDEFGHIJKL"))
+            .unwrap();
+        assert_eq!(step_output(&summary.config.args).as_deref(), Some("s3://bucket/output"));
+        assert_eq!(step_output(&resp.steps[1].config.args), None);
+        assert_eq!(resp.marker.as_deref(), Some("m1"));
+        assert_eq!(step_job_id("s-1ABCDEFGHIJKL"), step_job_id("s-1ABCDEFGHIJKL"));
+        assert_ne!(step_job_id("s-1ABCDEFGHIJKL"), step_job_id("s-1ABCDEFGHIJKM"));
+        assert_eq!(step_status("PENDING"), JobStatus::Starting);
+        assert_eq!(step_status("CANCEL_PENDING"), JobStatus::Running);
+        assert_eq!(step_status("COMPLETED"), JobStatus::Success);
+        assert_eq!(step_status("INTERRUPTED"), JobStatus::Failed);
+
+        let resp: DescribeStepResponse = serde_json::from_str(
+            r#"{"Step": {"Id": "s-1ABCDEFGHIJKL", "Name": "join_1", "Status": {
+                "State": "COMPLETED",
+                "Timeline": {"CreationDateTime": 1656669600.0, "StartDateTime": 1656669660.5, "EndDateTime": 1656670200.0}
+            }}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            epoch_seconds(resp.step.status.timeline.start_date_time),
+            Some(Utc.timestamp_millis_opt(1656669660500).unwrap())
+        );
+    }
+}
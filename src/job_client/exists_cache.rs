@@ -0,0 +1,147 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::Error;
+
+/**
+ * Remote files are assumed to be unchanged by others in this period,
+ * it's not longer than the polling interval of `FeathrClient::wait_for_path_update`
+ */
+const DEFAULT_EXISTS_CACHE_TTL: Duration = Duration::from_secs(10);
+
+// Last modification time of the path, and when it was looked up
+type CacheEntry = (Option<DateTime<Utc>>, Instant);
+
+/**
+ * Short-lived cache of remote path lookups, keeps the last modification time or `None` if the path doesn't exist.
+ * Entries are invalidated by writes and deletes through the same client, changes made by others show up after the TTL
+ */
+#[derive(Debug)]
+pub(crate) struct ExistsCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl Default for ExistsCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_EXISTS_CACHE_TTL)
+    }
+}
+
+impl ExistsCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Default::default(),
+        }
+    }
+
+    pub(crate) fn get(&self, path: &str) -> Option<Option<DateTime<Utc>>> {
+        let mut entries = self.entries.lock().ok()?;
+        match entries.get(normalize(path)) {
+            Some((modified, since)) if since.elapsed() < self.ttl => Some(*modified),
+            Some(_) => {
+                entries.remove(normalize(path));
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&self, path: &str, modified: Option<DateTime<Utc>>) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(normalize(path).to_string(), (modified, Instant::now()));
+        }
+    }
+
+    /**
+     * Return the cached lookup of the path, or look it up with `f` and cache the result
+     */
+    pub(crate) async fn get_or_lookup<F, Fut>(&self, path: &str, f: F) -> Result<Option<DateTime<Utc>>, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<DateTime<Utc>>, Error>>,
+    {
+        if let Some(modified) = self.get(path) {
+            return Ok(modified);
+        }
+        let modified = f().await?;
+        self.insert(path, modified);
+        Ok(modified)
+    }
+
+    /**
+     * Remove the path and everything under it, as well as its parent directories
+     */
+    pub(crate) fn invalidate(&self, path: &str) {
+        let path = normalize(path);
+        let prefix = format!("{}/", path);
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|k, _| {
+                k != path && !k.starts_with(&prefix) && !path.starts_with(&format!("{}/", k))
+            });
+        }
+    }
+}
+
+fn normalize(path: &str) -> &str {
+    path.trim_end_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exists_cache() {
+        let t = Some(Utc::now());
+        let cache = ExistsCache::default();
+        assert_eq!(cache.get("dbfs:/feathr/a.conf"), None);
+        cache.insert("dbfs:/feathr/a.conf", t);
+        cache.insert("dbfs:/feathr/dir/", t);
+        cache.insert("dbfs:/feathr/dir/b.conf", None);
+        cache.insert("dbfs:/other", t);
+        assert_eq!(cache.get("dbfs:/feathr/a.conf"), Some(t));
+        assert_eq!(cache.get("dbfs:/feathr/dir/b.conf"), Some(None));
+
+        // Writing a file invalidates the file and its parent directories
+        cache.invalidate("dbfs:/feathr/dir/b.conf");
+        assert_eq!(cache.get("dbfs:/feathr/dir/b.conf"), None);
+        assert_eq!(cache.get("dbfs:/feathr/dir"), None);
+        assert_eq!(cache.get("dbfs:/feathr/a.conf"), Some(t));
+
+        // Deleting a directory invalidates everything under it
+        cache.insert("dbfs:/feathr/dir/b.conf", t);
+        cache.invalidate("dbfs:/feathr");
+        assert_eq!(cache.get("dbfs:/feathr/a.conf"), None);
+        assert_eq!(cache.get("dbfs:/feathr/dir/b.conf"), None);
+        assert_eq!(cache.get("dbfs:/other"), Some(t));
+
+        let cache = ExistsCache::new(Duration::ZERO);
+        cache.insert("dbfs:/feathr/a.conf", t);
+        assert_eq!(cache.get("dbfs:/feathr/a.conf"), None);
+    }
+
+    #[tokio::test]
+    async fn lookup_once() {
+        let cache = ExistsCache::default();
+        let lookups = std::sync::atomic::AtomicUsize::new(0);
+        for _ in 0..3 {
+            let modified = cache
+                .get_or_lookup("dbfs:/feathr/_SUCCESS", || async {
+                    lookups.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    Ok(None)
+                })
+                .await
+                .unwrap();
+            assert_eq!(modified, None);
+        }
+        assert_eq!(lookups.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,151 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use uuid::Uuid;
+
+use crate::Error;
+
+/**
+ * Default job name template, placeholders are `{project}`, `{jobtype}`, `{date}` and `{shortkey}`
+ */
+pub const DEFAULT_JOB_NAME_TEMPLATE: &str = "{project}_{jobtype}_{date}_{shortkey}";
+
+const DATE_FORMAT: &str = "%Y%m%d";
+const SHORT_KEY_LEN: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JobType {
+    Join,
+    Generation,
+    Profile,
+}
+
+impl Display for JobType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobType::Join => write!(f, "join"),
+            JobType::Generation => write!(f, "gen"),
+            JobType::Profile => write!(f, "profile"),
+        }
+    }
+}
+
+impl FromStr for JobType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "join" => Ok(JobType::Join),
+            "gen" => Ok(JobType::Generation),
+            "profile" => Ok(JobType::Profile),
+            _ => Err(Error::InvalidJobName(s.to_string())),
+        }
+    }
+}
+
+/**
+ * Parts recovered from a generated job name, parts not in the template are `None`
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobNameInfo {
+    pub project: Option<String>,
+    pub job_type: Option<JobType>,
+    pub date: Option<NaiveDate>,
+    pub short_key: Option<String>,
+}
+
+/**
+ * Generate job name with the template
+ */
+pub fn format_job_name(
+    template: &str,
+    project: &str,
+    job_type: JobType,
+    date: DateTime<Utc>,
+    job_key: Uuid,
+) -> String {
+    template
+        .replace("{project}", project)
+        .replace("{jobtype}", &job_type.to_string())
+        .replace("{date}", &date.format(DATE_FORMAT).to_string())
+        .replace(
+            "{shortkey}",
+            &job_key.as_simple().to_string()[..SHORT_KEY_LEN],
+        )
+}
+
+/**
+ * Parse job name generated with the default template
+ */
+pub fn parse_job_name(name: &str) -> Result<JobNameInfo, Error> {
+    parse_job_name_with_template(DEFAULT_JOB_NAME_TEMPLATE, name)
+}
+
+/**
+ * Parse job name generated with the template
+ */
+pub fn parse_job_name_with_template(template: &str, name: &str) -> Result<JobNameInfo, Error> {
+    let pattern = regex::escape(template)
+        .replace(r"\{project\}", "(?P<project>.+?)")
+        .replace(r"\{jobtype\}", "(?P<jobtype>join|gen|profile)")
+        .replace(r"\{date\}", r"(?P<date>\d{8})")
+        .replace(r"\{shortkey\}", "(?P<shortkey>[0-9a-f]{8})");
+    let re = Regex::new(&format!("^{}$", pattern))
+        .map_err(|_| Error::InvalidJobName(template.to_string()))?;
+    let caps = re
+        .captures(name)
+        .ok_or_else(|| Error::InvalidJobName(name.to_string()))?;
+    Ok(JobNameInfo {
+        project: caps.name("project").map(|m| m.as_str().to_string()),
+        job_type: caps
+            .name("jobtype")
+            .map(|m| m.as_str().parse())
+            .transpose()?,
+        date: caps
+            .name("date")
+            .map(|m| NaiveDate::parse_from_str(m.as_str(), DATE_FORMAT))
+            .transpose()
+            .map_err(|_| Error::InvalidJobName(name.to_string()))?,
+        short_key: caps.name("shortkey").map(|m| m.as_str().to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn job_name() {
+        let key = Uuid::parse_str("936da01f-9abd-4d9d-80c7-02af85c822a8").unwrap();
+        let date = Utc.with_ymd_and_hms(2022, 7, 1, 10, 0, 0).unwrap();
+        let name = format_job_name(
+            DEFAULT_JOB_NAME_TEMPLATE,
+            "my_project",
+            JobType::Join,
+            date,
+            key,
+        );
+        assert_eq!(name, "my_project_join_20220701_936da01f");
+        assert_eq!(
+            parse_job_name(&name).unwrap(),
+            JobNameInfo {
+                project: Some("my_project".to_string()),
+                job_type: Some(JobType::Join),
+                date: NaiveDate::from_ymd_opt(2022, 7, 1),
+                short_key: Some("936da01f".to_string()),
+            }
+        );
+
+        let template = "feathr-{jobtype}-{project}";
+        let name = format_job_name(template, "p1", JobType::Generation, date, key);
+        assert_eq!(name, "feathr-gen-p1");
+        let info = parse_job_name_with_template(template, &name).unwrap();
+        assert_eq!(info.job_type, Some(JobType::Generation));
+        assert_eq!(info.date, None);
+
+        assert!(parse_job_name("p1_feathr_feature_join_job").is_err());
+    }
+}
@@ -0,0 +1,559 @@
+use std::{collections::HashMap, sync::Arc, sync::Mutex, time::Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, TimeZone, Utc};
+use log::debug;
+use reqwest::{redirect::Policy, Method, StatusCode, Url};
+use serde::Deserialize;
+
+use crate::{
+    livy_client::{
+        Authenticator, ClusterSize, LivyAuthenticator, LivyClient, LivyClientError, SparkRequest,
+    },
+    Error, ExistsCache, JobClient, JobId, JobRunInfo, JobStatus, RemoteFile, Timeouts, VarSource,
+};
+
+const LOG_LINES: usize = 1000;
+
+/**
+ * Spark cluster with generic Livy endpoint, e.g. on-prem Hadoop, with artifacts stored on HDFS via WebHDFS
+ */
+#[derive(Debug)]
+pub struct LivyJobClient {
+    livy_client: LivyClient<LivyAuthenticator>,
+    http_client: reqwest::Client,
+    webhdfs_url: String,
+    webhdfs_auth: LivyAuthenticator,
+    webhdfs_user: Option<String>,
+    workspace_dir: String,
+    proxy_user: Option<String>,
+    maven_artifact: String,
+    outputs: Mutex<HashMap<u64, String>>,
+    exists_cache: ExistsCache,
+}
+
+impl LivyJobClient {
+    /**
+     * Create client with Livy URL, WebHDFS URL (e.g. `http://namenode:9870`) and the workspace dir (e.g. `hdfs://namenode:8020/feathr`)
+     */
+    pub fn new(
+        livy_url: &str,
+        livy_auth: LivyAuthenticator,
+        webhdfs_url: &str,
+        webhdfs_auth: LivyAuthenticator,
+        workspace_dir: &str,
+    ) -> Result<Self, Error> {
+        if !workspace_dir.contains("://") {
+            return Err(Error::InvalidUrl(workspace_dir.to_string()));
+        }
+        let timeouts = Timeouts::default();
+        Ok(Self {
+            livy_client: LivyClient::<LivyAuthenticator>::with_authenticator(
+                timeouts.api_client()?.build()?,
+                livy_url,
+                "",
+                livy_auth,
+            ),
+            http_client: Self::webhdfs_client(&timeouts)?,
+            webhdfs_url: webhdfs_url.trim_end_matches('/').to_string(),
+            webhdfs_auth,
+            webhdfs_user: None,
+            workspace_dir: workspace_dir.trim_end_matches('/').to_string(),
+            proxy_user: None,
+            maven_artifact: super::FEATHR_MAVEN_ARTIFACT.to_string(),
+            outputs: Default::default(),
+            exists_cache: Default::default(),
+        })
+    }
+
+    /**
+     * User name for WebHDFS simple authentication
+     */
+    pub fn webhdfs_user(mut self, user: &str) -> Self {
+        self.webhdfs_user = Some(user.to_string());
+        self
+    }
+
+    /**
+     * Run jobs as the user, requires impersonation enabled on the Livy server
+     */
+    pub fn proxy_user(mut self, user: &str) -> Self {
+        self.proxy_user = Some(user.to_string());
+        self
+    }
+
+    /**
+     * Livy calls are limited by the API timeout, WebHDFS operations by the transfer timeout
+     */
+    pub fn timeouts(mut self, timeouts: &Timeouts) -> Result<Self, Error> {
+        self.livy_client.set_http_client(timeouts.api_client()?.build()?);
+        self.http_client = Self::webhdfs_client(timeouts)?;
+        Ok(self)
+    }
+
+    fn webhdfs_client(timeouts: &Timeouts) -> Result<reqwest::Client, Error> {
+        // WebHDFS redirects data requests to datanodes, redirects are handled manually to resend the content
+        Ok(timeouts.transfer_client()?.redirect(Policy::none()).build()?)
+    }
+
+    pub(crate) async fn from_var_source(
+        var_source: Arc<dyn VarSource + Send + Sync>,
+    ) -> Result<Self, Error> {
+        let get = |key: &'static str| {
+            let var_source = var_source.clone();
+            async move {
+                var_source
+                    .get_environment_variable(&["spark_config", "livy", key])
+                    .await
+                    .ok()
+                    .filter(|s| !s.trim().is_empty())
+            }
+        };
+        let livy_url = get("url")
+            .await
+            .ok_or_else(|| Error::InvalidConfig("spark_config.livy.url is not set".to_string()))?;
+        let webhdfs_url = get("webhdfs_url").await.ok_or_else(|| {
+            Error::InvalidConfig("spark_config.livy.webhdfs_url is not set".to_string())
+        })?;
+        let workspace_dir = get("workspace_dir").await.ok_or_else(|| {
+            Error::InvalidConfig("spark_config.livy.workspace_dir is not set".to_string())
+        })?;
+        let auth = get("auth").await.unwrap_or_default().to_lowercase();
+        let (livy_auth, webhdfs_auth) = match auth.as_str() {
+            "" | "none" => (LivyAuthenticator::None, LivyAuthenticator::None),
+            "kerberos" => (
+                LivyAuthenticator::kerberos(&livy_url)?,
+                LivyAuthenticator::kerberos(&webhdfs_url)?,
+            ),
+            "basic" => {
+                let basic = LivyAuthenticator::Basic {
+                    username: get("username").await.ok_or_else(|| {
+                        Error::InvalidConfig("spark_config.livy.username is not set".to_string())
+                    })?,
+                    password: var_source
+                        .get_environment_variable(&["LIVY_PASSWORD"])
+                        .await
+                        .ok(),
+                };
+                (basic.clone(), basic)
+            }
+            "token" => {
+                let token =
+                    LivyAuthenticator::Token(var_source.get_environment_variable(&["LIVY_TOKEN"]).await?);
+                (token.clone(), token)
+            }
+            _ => {
+                return Err(Error::InvalidConfig(format!(
+                    "Unsupported Livy authentication '{}'",
+                    auth
+                )))
+            }
+        };
+        let mut client = Self::new(
+            &livy_url,
+            livy_auth,
+            &webhdfs_url,
+            webhdfs_auth,
+            &workspace_dir,
+        )?
+        .timeouts(&Timeouts::from_var_source(&var_source).await?)?;
+        if let Some(user) = get("webhdfs_user").await {
+            client = client.webhdfs_user(&user);
+        }
+        if let Some(user) = get("proxy_user").await {
+            client = client.proxy_user(&user);
+        }
+        if let Ok(artifact) = var_source
+            .get_environment_variable(&["spark_config", "maven_artifact"])
+            .await
+        {
+            if !artifact.trim().is_empty() {
+                client.maven_artifact = artifact;
+            }
+        }
+        Ok(client)
+    }
+
+    /**
+     * WebHDFS REST URL of the operation on the file
+     */
+    fn get_webhdfs_url(&self, path: &str, op: &str) -> Result<String, Error> {
+        let url = Url::parse(path).map_err(|_| Error::InvalidUrl(path.to_string()))?;
+        Ok(format!(
+            "{}/webhdfs/v1{}?op={}{}",
+            self.webhdfs_url,
+            url.path(),
+            op,
+            self.webhdfs_user
+                .as_ref()
+                .map(|u| format!("&user.name={}", u))
+                .unwrap_or_default()
+        ))
+    }
+
+    async fn webhdfs_request(
+        &self,
+        method: Method,
+        url: &str,
+        content: Option<Bytes>,
+    ) -> Result<Bytes, Error> {
+        debug!("WebHDFS URL: {}", url);
+        let mut url = url.to_string();
+        // NameNode redirects to DataNode, which doesn't need authentication as the delegation token is in the URL
+        for authenticate in [true, false] {
+            let mut builder = self.http_client.request(method.clone(), &url);
+            if authenticate {
+                builder = self.webhdfs_auth.authenticate(builder).await?;
+            }
+            if let Some(content) = content.clone() {
+                builder = builder.body(content);
+            }
+            let resp = builder.send().await?;
+            let status = resp.status();
+            if status == StatusCode::TEMPORARY_REDIRECT {
+                if let Some(location) = resp.headers().get(reqwest::header::LOCATION) {
+                    url = location
+                        .to_str()
+                        .map_err(|_| Error::InvalidUrl(format!("{:?}", location)))?
+                        .to_string();
+                    debug!("Redirected to {}", url);
+                    continue;
+                }
+            }
+            if status.is_client_error() || status.is_server_error() {
+                return Err(Error::LivyClientError(
+                    crate::livy_client::LivyClientError::HttpError(url, status, resp.text().await?),
+                ));
+            }
+            return Ok(resp.bytes().await?);
+        }
+        Err(Error::InvalidUrl(url))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebHdfsFileStatus {
+    path_suffix: String,
+    #[serde(rename = "type")]
+    file_type: String,
+    modification_time: i64,
+    #[serde(default)]
+    length: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebHdfsFileStatusResponse {
+    #[serde(rename = "FileStatus")]
+    file_status: WebHdfsFileStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebHdfsFileStatuses {
+    #[serde(rename = "FileStatus")]
+    file_status: Vec<WebHdfsFileStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebHdfsListStatusResponse {
+    #[serde(rename = "FileStatuses")]
+    file_statuses: WebHdfsFileStatuses,
+}
+
+#[async_trait]
+impl JobClient for LivyJobClient {
+    async fn write_remote_file(&self, path: &str, content: &[u8]) -> Result<String, Error> {
+        let start = Instant::now();
+        self.exists_cache.invalidate(path);
+        let url = format!("{}&overwrite=true", self.get_webhdfs_url(path, "CREATE")?);
+        self.webhdfs_request(Method::PUT, &url, Some(Bytes::copy_from_slice(content)))
+            .await?;
+        super::manifest::record_upload(path, content, start.elapsed());
+        Ok(path.to_string())
+    }
+
+    async fn read_remote_file(&self, path: &str) -> Result<Bytes, Error> {
+        let url = self.get_webhdfs_url(path, "OPEN")?;
+        self.webhdfs_request(Method::GET, &url, None).await
+    }
+
+    async fn read_remote_file_range(&self, path: &str, offset: u64, length: u64) -> Result<Bytes, Error> {
+        let url = format!(
+            "{}&offset={}&length={}",
+            self.get_webhdfs_url(path, "OPEN")?,
+            offset,
+            length
+        );
+        self.webhdfs_request(Method::GET, &url, None).await
+    }
+
+    async fn get_remote_last_modified(&self, path: &str) -> Result<Option<DateTime<Utc>>, Error> {
+        self.exists_cache
+            .get_or_lookup(path, || async {
+                let url = self.get_webhdfs_url(path, "GETFILESTATUS")?;
+                let status: WebHdfsFileStatus = match self.webhdfs_request(Method::GET, &url, None).await {
+                    Ok(resp) => serde_json::from_slice::<WebHdfsFileStatusResponse>(&resp)?.file_status,
+                    Err(Error::LivyClientError(LivyClientError::HttpError(_, StatusCode::NOT_FOUND, _))) => {
+                        return Ok(None)
+                    }
+                    Err(e) => return Err(e),
+                };
+                let mut latest = status.modification_time;
+                let mut dirs = vec![];
+                if status.file_type == "DIRECTORY" {
+                    dirs.push(path.trim_end_matches('/').to_string());
+                }
+                while let Some(dir) = dirs.pop() {
+                    let url = self.get_webhdfs_url(&dir, "LISTSTATUS")?;
+                    let resp = self.webhdfs_request(Method::GET, &url, None).await?;
+                    let list: WebHdfsListStatusResponse = serde_json::from_slice(&resp)?;
+                    for status in list.file_statuses.file_status {
+                        latest = latest.max(status.modification_time);
+                        if status.file_type == "DIRECTORY" {
+                            dirs.push(format!("{}/{}", dir, status.path_suffix));
+                        }
+                    }
+                }
+                Ok(Utc.timestamp_millis_opt(latest).single())
+            })
+            .await
+    }
+
+    async fn list_remote_dir(&self, path: &str) -> Result<Vec<RemoteFile>, Error> {
+        let dir = path.trim_end_matches('/');
+        let url = self.get_webhdfs_url(dir, "LISTSTATUS")?;
+        let resp = match self.webhdfs_request(Method::GET, &url, None).await {
+            Ok(resp) => resp,
+            Err(Error::LivyClientError(LivyClientError::HttpError(_, StatusCode::NOT_FOUND, _))) => {
+                return Ok(vec![])
+            }
+            Err(e) => return Err(e),
+        };
+        let list: WebHdfsListStatusResponse = serde_json::from_slice(&resp)?;
+        let mut ret: Vec<RemoteFile> = list
+            .file_statuses
+            .file_status
+            .into_iter()
+            .map(|status| RemoteFile {
+                url: format!("{}/{}", dir, status.path_suffix),
+                size: status.length,
+                is_dir: status.file_type == "DIRECTORY",
+            })
+            .collect();
+        ret.sort_by(|a, b| a.url.cmp(&b.url));
+        Ok(ret)
+    }
+
+    async fn delete_remote_path(&self, path: &str) -> Result<(), Error> {
+        self.exists_cache.invalidate(path);
+        // WebHDFS returns `{"boolean": false}` if the path doesn't exist
+        let url = format!("{}&recursive=true", self.get_webhdfs_url(path, "DELETE")?);
+        self.webhdfs_request(Method::DELETE, &url, None).await?;
+        Ok(())
+    }
+
+    async fn move_remote_path(&self, from: &str, to: &str) -> Result<(), Error> {
+        self.exists_cache.invalidate(from);
+        self.exists_cache.invalidate(to);
+        let to = Url::parse(to).map_err(|_| Error::InvalidUrl(to.to_string()))?;
+        // WebHDFS returns `{"boolean": false}` if the source doesn't exist
+        let url = format!("{}&destination={}", self.get_webhdfs_url(from, "RENAME")?, to.path());
+        self.webhdfs_request(Method::PUT, &url, None).await?;
+        Ok(())
+    }
+
+    async fn submit_job(
+        &self,
+        var_source: Arc<dyn VarSource + Send + Sync>,
+        request: super::SubmitJobRequest,
+    ) -> Result<JobId, Error> {
+        let args = self.get_arguments(var_source.clone(), &request).await?;
+
+        let main_jar_path = match request.main_jar_path {
+            Some(p) => Some(p),
+            None => var_source
+                .get_environment_variable(&["spark_config", "livy", "feathr_runtime_location"])
+                .await
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+        };
+
+        let mut jars: Vec<String> = main_jar_path.clone().into_iter().collect();
+        let mut files: Vec<String> = vec![];
+        for f in request.reference_files.into_iter() {
+            if f.ends_with(".jar") {
+                jars.push(f)
+            } else {
+                files.push(f)
+            }
+        }
+        let jars = self.multi_upload_or_get_url(&jars).await?;
+        let files = self.multi_upload_or_get_url(&files).await?;
+        let py_files = self.multi_upload_or_get_url(&request.python_files).await?;
+
+        let executable = match request.main_python_script.as_ref() {
+            Some(code) => {
+                self.write_remote_file(
+                    &self.get_remote_url(&format!(
+                        "feathr_pyspark_driver_{}_{}.py",
+                        request.name, request.job_key
+                    )),
+                    code.as_bytes(),
+                )
+                .await?
+            }
+            None => jars
+                .first()
+                .cloned()
+                .ok_or_else(|| {
+                    Error::InvalidConfig(
+                        "spark_config.livy.feathr_runtime_location is not set".to_string(),
+                    )
+                })?,
+        };
+        debug!("Main executable file: {}", executable);
+
+        let mut conf = request.configuration;
+        conf.extend(super::env_var_conf(&request.env_vars));
+        if main_jar_path.is_none() {
+            let v = match conf.get("spark.jars.packages") {
+                Some(v) => format!("{},{}", v, self.maven_artifact),
+                None => self.maven_artifact.clone(),
+            };
+            conf.insert("spark.jars.packages".to_string(), v);
+        }
+
+        let job = SparkRequest {
+            args,
+            class_name: if request.main_python_script.is_none() {
+                request.main_class_name
+            } else {
+                Default::default()
+            },
+            conf,
+            cluster_size: ClusterSize::MEDIUM(),
+            file: executable,
+            files,
+            // The main jar is the executable
+            jars: jars.into_iter().skip(1).collect(),
+            name: format!("{}_{}", request.name, request.job_key),
+            proxy_user: self.proxy_user.clone().unwrap_or_default(),
+            py_files,
+            tags: request.job_tags.clone(),
+            ..Default::default()
+        };
+        debug!("Job request: {:#?}", job);
+        let jid = self.livy_client.create_batch_job(job).await?.id;
+        debug!("Job submitted, id is {}", jid);
+        // Generic Livy doesn't keep tags
+        if let Some(output) = request.job_tags.get(super::OUTPUT_PATH_TAG) {
+            self.outputs
+                .lock()
+                .map_err(|e| Error::LivyClientError(e.into()))?
+                .insert(jid, output.to_owned());
+        }
+        Ok(JobId(jid))
+    }
+
+    async fn get_job_status(&self, job_id: JobId) -> Result<JobStatus, Error> {
+        Ok(self.livy_client.get_batch_job(job_id.0).await?.state.into())
+    }
+
+    async fn cancel_job(&self, job_id: JobId) -> Result<(), Error> {
+        Ok(self.livy_client.cancel_batch_job(job_id.0).await?)
+    }
+
+    async fn get_job_log(&self, job_id: JobId) -> Result<String, Error> {
+        Ok(self
+            .livy_client
+            .get_batch_job_log(job_id.0, 0, LOG_LINES)
+            .await?
+            .join("\n"))
+    }
+
+    async fn get_job_output_url(&self, job_id: JobId) -> Result<Option<String>, Error> {
+        Ok(self
+            .outputs
+            .lock()
+            .map_err(|e| Error::LivyClientError(e.into()))?
+            .get(&job_id.0)
+            .cloned())
+    }
+
+    async fn get_job_run_info(&self, job_id: JobId) -> Result<JobRunInfo, Error> {
+        // Generic Livy doesn't report run times, and on-prem clusters are not billed per node
+        Err(Error::CostNotAvailable(
+            job_id,
+            "Livy doesn't report cluster usage".to_string(),
+        ))
+    }
+
+    fn get_remote_url(&self, filename: &str) -> String {
+        if let Some(url) = super::overridden_remote_url(filename) {
+            return url;
+        }
+        format!("{}/{}", self.workspace_dir, filename.trim_start_matches('/'))
+    }
+
+    fn is_url_on_storage(&self, url: &str) -> bool {
+        url.starts_with("hdfs://")
+            || url.starts_with("webhdfs://")
+            || url.starts_with("swebhdfs://")
+            || url.starts_with(&self.workspace_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhdfs_url() {
+        let client = LivyJobClient::new(
+            "http://livy:8998/",
+            LivyAuthenticator::None,
+            "http://namenode:9870/",
+            LivyAuthenticator::None,
+            "hdfs://namenode:8020/feathr/",
+        )
+        .unwrap()
+        .webhdfs_user("feathr");
+        let path = client.get_remote_url("job.conf");
+        assert_eq!(path, "hdfs://namenode:8020/feathr/job.conf");
+        assert!(client.is_url_on_storage(&path));
+        assert!(!client.is_url_on_storage("abfss://c@a.dfs.core.windows.net/f"));
+        assert_eq!(
+            client.get_webhdfs_url(&path, "OPEN").unwrap(),
+            "http://namenode:9870/webhdfs/v1/feathr/job.conf?op=OPEN&user.name=feathr"
+        );
+        assert!(LivyJobClient::new(
+            "http://livy:8998",
+            LivyAuthenticator::None,
+            "http://namenode:9870",
+            LivyAuthenticator::None,
+            "/feathr"
+        )
+        .is_err());
+        assert_eq!(
+            format!("{:?}", LivyAuthenticator::Token("secret_token".to_string())),
+            "Token"
+        );
+    }
+
+    #[test]
+    fn webhdfs_list_status() {
+        let resp = r#"{"FileStatuses":{"FileStatus":[
+            {"accessTime":0,"blockSize":0,"group":"supergroup","length":0,"modificationTime":1656669600000,
+             "owner":"feathr","pathSuffix":"2022","permission":"755","replication":0,"type":"DIRECTORY"},
+            {"accessTime":1656669600000,"blockSize":134217728,"group":"supergroup","length":24930,
+             "modificationTime":1656756000000,"owner":"feathr","pathSuffix":"_SUCCESS","permission":"644",
+             "replication":3,"type":"FILE"}
+        ]}}"#;
+        let list: WebHdfsListStatusResponse = serde_json::from_str(resp).unwrap();
+        let statuses = list.file_statuses.file_status;
+        assert_eq!(statuses[0].path_suffix, "2022");
+        assert_eq!(statuses[0].file_type, "DIRECTORY");
+        assert_eq!(statuses[1].modification_time, 1656756000000);
+    }
+}
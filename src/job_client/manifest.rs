@@ -0,0 +1,309 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, JobId};
+
+tokio::task_local! {
+    // Manifest of the job being submitted
+    static MANIFEST: Arc<Mutex<JobManifest>>;
+}
+
+/**
+ * A remote artifact used by a job
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactRecord {
+    pub url: String,
+    /**
+     * `None` if the artifact was already on the storage and not uploaded by the client
+     */
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub size: Option<u64>,
+    /**
+     * Hex encoded SHA-256 of the content, `None` if the artifact was not uploaded by the client
+     */
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sha256: Option<String>,
+}
+
+/**
+ * Everything needed to reproduce or audit a job run
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobManifest {
+    pub artifacts: Vec<ArtifactRecord>,
+    /**
+     * Arguments passed to the Spark job, with credentials masked
+     */
+    pub spark_arguments: Vec<String>,
+    #[serde(default)]
+    pub timings: SubmissionTimings,
+    /**
+     * Redis aliases to be switched to the tables once the job succeeded, keyed by alias
+     */
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub redis_aliases: HashMap<String, String>,
+}
+
+/**
+ * Time spent in each step of the submission and the job run, in milliseconds
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmissionTimings {
+    /**
+     * Rendering job configs and Spark arguments, excluding uploads
+     */
+    pub config_render_ms: u64,
+    /**
+     * Uploading job configs and local files to the workspace storage
+     */
+    pub upload_ms: u64,
+    /**
+     * Calling the Spark provider API, everything else in the submission
+     */
+    pub api_call_ms: u64,
+    /**
+     * Waiting for the cluster and starting the job, `None` until the job ended, as observed by the client
+     */
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub queue_wait_ms: Option<u64>,
+    /**
+     * Running the job, `None` until the job ended, as observed by the client
+     */
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub run_ms: Option<u64>,
+}
+
+/**
+ * Percentiles of the submission timings of jobs submitted by the client
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubmissionTimingStats {
+    pub jobs: usize,
+    pub p50: SubmissionTimings,
+    pub p90: SubmissionTimings,
+    pub p99: SubmissionTimings,
+}
+
+/**
+ * Nearest-rank percentile
+ */
+fn percentile(mut values: Vec<u64>, p: usize) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let rank = (values.len() * p).div_ceil(100).max(1);
+    Some(values[rank - 1])
+}
+
+pub(crate) fn timing_stats<'a, I>(timings: I) -> SubmissionTimingStats
+where
+    I: IntoIterator<Item = &'a SubmissionTimings>,
+{
+    let timings: Vec<&SubmissionTimings> = timings.into_iter().collect();
+    let at = |p: usize| {
+        let field = |f: fn(&SubmissionTimings) -> Option<u64>| {
+            percentile(timings.iter().filter_map(|t| f(t)).collect(), p)
+        };
+        SubmissionTimings {
+            config_render_ms: field(|t| Some(t.config_render_ms)).unwrap_or_default(),
+            upload_ms: field(|t| Some(t.upload_ms)).unwrap_or_default(),
+            api_call_ms: field(|t| Some(t.api_call_ms)).unwrap_or_default(),
+            queue_wait_ms: field(|t| t.queue_wait_ms),
+            run_ms: field(|t| t.run_ms),
+        }
+    };
+    SubmissionTimingStats {
+        jobs: timings.len(),
+        p50: at(50),
+        p90: at(90),
+        p99: at(99),
+    }
+}
+
+impl JobManifest {
+    fn add(&mut self, record: ArtifactRecord) {
+        // The same url may be written more than once, the last write wins
+        self.artifacts.retain(|a| a.url != record.url);
+        self.artifacts.push(record);
+    }
+}
+
+/**
+ * Submitted job with its manifest
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobHandle {
+    pub job_id: JobId,
+    pub manifest: JobManifest,
+}
+
+impl JobHandle {
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+/**
+ * Run `f` and collect the artifacts and arguments it records
+ */
+pub(crate) async fn with_manifest<F>(f: F) -> (F::Output, JobManifest)
+where
+    F: std::future::Future,
+{
+    let manifest = Arc::new(Mutex::new(JobManifest::default()));
+    let ret = MANIFEST.scope(manifest.clone(), f).await;
+    let manifest = manifest
+        .lock()
+        .map(|m| m.clone())
+        .unwrap_or_default();
+    (ret, manifest)
+}
+
+fn record<F: FnOnce(&mut JobManifest)>(f: F) {
+    // Not submitting a job or the lock is poisoned, nothing to record
+    let _ = MANIFEST.try_with(|m| {
+        if let Ok(mut m) = m.lock() {
+            f(&mut m)
+        }
+    });
+}
+
+/**
+ * Record an artifact uploaded to `url`
+ */
+pub(crate) fn record_upload(url: &str, content: &[u8], elapsed: Duration) {
+    let hash: String = openssl::sha::sha256(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    record(|m| {
+        m.timings.upload_ms += elapsed.as_millis() as u64;
+        m.add(ArtifactRecord {
+            url: url.to_string(),
+            size: Some(content.len() as u64),
+            sha256: Some(hash),
+        })
+    });
+}
+
+/**
+ * Record an artifact already on the storage
+ */
+pub(crate) fn record_reference(url: &str) {
+    record(|m| {
+        m.add(ArtifactRecord {
+            url: url.to_string(),
+            ..Default::default()
+        })
+    });
+}
+
+pub(crate) fn record_spark_arguments(args: &[String]) {
+    record(|m| m.spark_arguments = args.to_vec());
+}
+
+/**
+ * Time spent in uploads so far
+ */
+pub(crate) fn recorded_upload_ms() -> u64 {
+    MANIFEST
+        .try_with(|m| m.lock().map(|m| m.timings.upload_ms).unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/**
+ * Record time spent in rendering, uploads since `upload_ms_before` are excluded
+ */
+pub(crate) fn record_config_render(elapsed: Duration, upload_ms_before: u64) {
+    record(|m| {
+        let uploads = m.timings.upload_ms.saturating_sub(upload_ms_before);
+        m.timings.config_render_ms += (elapsed.as_millis() as u64).saturating_sub(uploads);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn collect_manifest() {
+        let (_, manifest) = with_manifest(async {
+            record_upload("abfss://c@a.dfs.core.windows.net/w/feature.conf", b"hello", Duration::from_millis(30));
+            record_reference("abfss://c@a.dfs.core.windows.net/jars/feathr.jar");
+            let before = recorded_upload_ms();
+            record_upload(
+                "abfss://c@a.dfs.core.windows.net/w/feature.conf",
+                b"hello, world",
+                Duration::from_millis(20),
+            );
+            record_config_render(Duration::from_millis(25), before);
+            record_spark_arguments(&["--input".to_string(), "in".to_string()]);
+        })
+        .await;
+        assert_eq!(manifest.timings.upload_ms, 50);
+        assert_eq!(manifest.timings.config_render_ms, 5);
+        assert_eq!(manifest.artifacts.len(), 2);
+        assert_eq!(manifest.artifacts[0].size, None);
+        assert_eq!(manifest.artifacts[1].size, Some(12));
+        assert_eq!(
+            manifest.artifacts[1].sha256.as_deref(),
+            Some("09ca7e4eaa6e8ae9c7d261167129184883644d07dfba7cbfbc4c8a2e08360d5b")
+        );
+        assert_eq!(manifest.spark_arguments, vec!["--input", "in"]);
+
+        // Nothing is recorded outside of the scope
+        record_reference("dbfs:/jars/feathr.jar");
+
+        let mut handle = JobHandle {
+            job_id: JobId(42),
+            manifest,
+        };
+        assert_eq!(JobHandle::from_json(&handle.to_json().unwrap()).unwrap(), handle);
+        assert!(!handle.to_json().unwrap().contains("redisAliases"));
+        handle
+            .manifest
+            .redis_aliases
+            .insert("user_features".to_string(), "user_features_v2".to_string());
+        assert_eq!(JobHandle::from_json(&handle.to_json().unwrap()).unwrap(), handle);
+        // Manifests saved before timings were recorded are still readable
+        let old = r#"{"jobId": 1, "manifest": {"artifacts": [], "sparkArguments": []}}"#;
+        assert_eq!(JobHandle::from_json(old).unwrap().manifest.timings, SubmissionTimings::default());
+    }
+
+    #[test]
+    fn submission_timing_stats() {
+        let timings: Vec<SubmissionTimings> = (1..=10)
+            .map(|i| SubmissionTimings {
+                config_render_ms: i,
+                upload_ms: i * 100,
+                api_call_ms: 1000,
+                queue_wait_ms: (i <= 4).then_some(i * 1000),
+                run_ms: None,
+            })
+            .collect();
+        let stats = timing_stats(&timings);
+        assert_eq!(stats.jobs, 10);
+        assert_eq!(stats.p50.config_render_ms, 5);
+        assert_eq!(stats.p90.upload_ms, 900);
+        assert_eq!(stats.p99.upload_ms, 1000);
+        assert_eq!(stats.p50.api_call_ms, 1000);
+        assert_eq!(stats.p50.queue_wait_ms, Some(2000));
+        assert_eq!(stats.p50.run_ms, None);
+        assert_eq!(timing_stats(&[]), SubmissionTimingStats::default());
+    }
+}
@@ -1,27 +1,72 @@
+mod aws;
 mod azure_synapse;
+mod compat;
+mod cost;
 mod databricks;
-
-use std::{collections::HashMap, fs::File, io::Read, path::Path, sync::Arc, time::Instant};
+mod emr;
+mod exists_cache;
+mod job_name;
+mod livy;
+mod manifest;
+mod output_schema;
+mod profile;
+mod spark_args;
+mod spark_operator;
+mod timeouts;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::Read,
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+    time::Instant,
+};
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::{DateTime, Duration, Utc};
 use handlebars::Handlebars;
-use log::debug;
+use log::{debug, warn};
 use reqwest::Url;
 use serde::Serialize;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use uuid::Uuid;
 
 use crate::{
-    load_var_source, DateTimeResolution, Error, MaterializationSettingsBuilder, OutputSink,
-    VarSource,
+    load_var_source, DateTimeResolution, Error, FeathrProject, MaterializationSettings, MaterializationSettingsBuilder,
+    MonitoringSink, OutputSink, ScheduleManifest, ScheduledWindow, SourceFormat, TimePartition, VarSource,
 };
 
 pub use azure_synapse::AzureSynapseClient;
-pub use databricks::DatabricksClient;
+pub use databricks::{AccessControlPrincipal, AccessControlRequest, DatabricksClient, PermissionLevel};
+pub use aws::AwsCredentials;
+pub use emr::EmrClient;
+pub use livy::LivyJobClient;
+pub use job_name::*;
+pub use profile::{FeatureStatistics, ProfileOptions, PROFILE_QUANTILES};
+pub(crate) use profile::{parse_statistics, profile_job_request};
+pub use compat::RuntimeVersion;
+pub use cost::{JobCostEstimate, JobCostSummary, JobRunInfo, PriceTable};
+pub use manifest::{ArtifactRecord, JobHandle, JobManifest, SubmissionTimingStats, SubmissionTimings};
+pub(crate) use manifest::{timing_stats, with_manifest};
+pub use output_schema::{OutputColumn, OutputSchema};
+pub(crate) use output_schema::{is_data_file, pick_data_file, read_output_schema};
+pub(crate) use compat::{check_compatibility, detect_incompatibility};
+pub use spark_args::*;
+pub use spark_operator::SparkOperatorClient;
+pub use timeouts::Timeouts;
+pub(crate) use exists_cache::ExistsCache;
 
 pub(crate) const OUTPUT_PATH_TAG: &str = "output_path";
+/**
+ * Tag of the idempotency key, so the job of a submission that failed after being accepted can be found by `list_jobs`
+ */
+pub(crate) const IDEMPOTENCY_KEY_TAG: &str = "feathr_idempotency_key";
+/**
+ * Spark conf read by the runtime to pick the format of the joined output
+ */
+pub(crate) const OUTPUT_FORMAT_CONF: &str = "spark.feathr.outputFormat";
 pub(crate) const JOIN_JOB_MAIN_CLASS_NAME: &str = "com.linkedin.feathr.offline.job.FeatureJoinJob";
 pub(crate) const GEN_JOB_MAIN_CLASS_NAME: &str = "com.linkedin.feathr.offline.job.FeatureGenJob";
 const PYTHON_TEMPLATE: &str = include_str!("../../template/feathr_pyspark_driver_template.py.hbr");
@@ -47,12 +92,309 @@ pub struct SubmitJobRequest {
     // TODO:
     pub secret_key: Vec<String>,
     pub configuration: HashMap<String, String>,
+    /**
+     * Secrets holding the access keys of storage accounts used by sinks, keyed by storage host,
+     * e.g. `account.dfs.core.windows.net`, resolved when the job is submitted
+     */
+    pub storage_key_names: HashMap<String, String>,
+    /**
+     * Pointer files to be updated after the job succeeded, maps the pointer file url to the content
+     */
+    pub latest_pointers: HashMap<String, String>,
+    /**
+     * Stage the artifacts of this job in this dir instead of the workspace dir in the config
+     */
+    pub workspace_dir: Option<String>,
+    /**
+     * Datasets to be registered in the registry after the job succeeded
+     */
+    pub datasets: Vec<DatasetRegistration>,
+    /**
+     * Output folders to be cleared before the job is submitted, they're restored if the submission fails
+     */
+    pub overwrite_paths: Vec<String>,
+    /**
+     * Redis aliases to be switched after the job succeeded, maps the alias to the table
+     */
+    pub redis_aliases: HashMap<String, String>,
+    /**
+     * JDBC driver class of JDBC sources, overrides `JDBC_DRIVER` in `--sql-config`
+     */
+    pub jdbc_driver: Option<String>,
+    /**
+     * JDBC connection properties of JDBC sources, passed in `--sql-config`
+     */
+    pub jdbc_properties: HashMap<String, String>,
+    /**
+     * Environment variables of the Spark driver and executors, for non-secret runtime switches only
+     */
+    pub env_vars: HashMap<String, String>,
+    /**
+     * Secrets holding the keys of CosmosDB sinks, passed in `--cosmos-config`
+     */
+    pub cosmos_key_names: Vec<String>,
+    /**
+     * Time partitioned HDFS sinks, scheduled jobs write each run into the folder of its own window
+     */
+    pub time_partitions: Vec<TimePartition>,
+    /**
+     * Quartz cron expression in UTC, the job is created with `FeathrClient::schedule_job` to run on the schedule
+     */
+    pub schedule: Option<String>,
+    /**
+     * Generation job consuming a streaming source, it keeps running until the timeout of its streaming sinks
+     */
+    pub streaming: bool,
+    /**
+     * Same for requests of the same project writing the same output with the same configs, regardless of when they're built
+     */
+    pub idempotency_key: Uuid,
+    /**
+     * Submit even if a job with the same idempotency key is still running
+     */
+    pub force: bool,
+}
+
+/**
+ * All fields of `SubmitJobRequest` except the job identity and the actions after the job succeeded
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+struct JobConfigKey {
+    name: String,
+    input: String,
+    output: String,
+    main_jar_path: Option<String>,
+    main_class_name: String,
+    main_python_script: Option<String>,
+    feature_config: String,
+    join_job_config: String,
+    gen_job_config: String,
+    python_files: Vec<String>,
+    reference_files: Vec<String>,
+    job_tags: BTreeMap<String, String>,
+    secret_key: Vec<String>,
+    configuration: BTreeMap<String, String>,
+    storage_key_names: BTreeMap<String, String>,
+    workspace_dir: Option<String>,
+    overwrite_paths: Vec<String>,
+    jdbc_driver: Option<String>,
+    jdbc_properties: BTreeMap<String, String>,
+    env_vars: BTreeMap<String, String>,
+    cosmos_key_names: Vec<String>,
+    time_partitions: Vec<TimePartition>,
+    schedule: Option<String>,
+    streaming: bool,
+    force: bool,
+}
+
+/**
+ * Spark conf setting the environment variables on YARN based clusters, e.g. Synapse and Livy
+ */
+pub(crate) fn env_var_conf(env_vars: &HashMap<String, String>) -> Vec<(String, String)> {
+    env_vars
+        .iter()
+        .flat_map(|(k, v)| {
+            [
+                (format!("spark.yarn.appMasterEnv.{}", k), v.to_owned()),
+                (format!("spark.executorEnv.{}", k), v.to_owned()),
+            ]
+        })
+        .collect()
+}
+
+impl SubmitJobRequest {
+    /**
+     * Everything deciding what the job runs and writes, requests with equal keys submit the same job
+     */
+    fn job_config_key(&self) -> JobConfigKey {
+        let sorted = |m: &HashMap<String, String>| m.clone().into_iter().collect::<BTreeMap<_, _>>();
+        JobConfigKey {
+            name: self.name.to_owned(),
+            input: self.input.to_owned(),
+            output: self.output.to_owned(),
+            main_jar_path: self.main_jar_path.to_owned(),
+            main_class_name: self.main_class_name.to_owned(),
+            main_python_script: self.main_python_script.to_owned(),
+            feature_config: self.feature_config.to_owned(),
+            join_job_config: self.join_job_config.to_owned(),
+            gen_job_config: self.gen_job_config.to_owned(),
+            python_files: self.python_files.to_owned(),
+            reference_files: self.reference_files.to_owned(),
+            job_tags: sorted(&self.job_tags),
+            secret_key: self.secret_key.to_owned(),
+            configuration: sorted(&self.configuration),
+            storage_key_names: sorted(&self.storage_key_names),
+            workspace_dir: self.workspace_dir.to_owned(),
+            overwrite_paths: self.overwrite_paths.to_owned(),
+            jdbc_driver: self.jdbc_driver.to_owned(),
+            jdbc_properties: sorted(&self.jdbc_properties),
+            env_vars: sorted(&self.env_vars),
+            cosmos_key_names: self.cosmos_key_names.to_owned(),
+            time_partitions: self.time_partitions.to_owned(),
+            schedule: self.schedule.to_owned(),
+            streaming: self.streaming,
+            force: self.force,
+        }
+    }
+
+    /**
+     * Key of the whole request, also tagged on the job, the name has the build time in it
+     */
+    pub(crate) fn with_idempotency_key(mut self, project: &str) -> Self {
+        let mut key = JobConfigKey {
+            name: Default::default(),
+            force: false,
+            ..self.job_config_key()
+        };
+        key.job_tags.remove(IDEMPOTENCY_KEY_TAG);
+        let content = serde_json::to_string(&key).unwrap();
+        self.idempotency_key = Uuid::new_v5(&Uuid::NAMESPACE_OID, format!("{}\0{}", project, content).as_bytes());
+        self.job_tags
+            .insert(IDEMPOTENCY_KEY_TAG.to_string(), self.idempotency_key.to_string());
+        self
+    }
+}
+
+/**
+ * Generation job request skipped because an identical one is in the same batch
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateJobRequest {
+    /**
+     * Index of the skipped request in the batch
+     */
+    pub index: usize,
+    /**
+     * Index of the request submitted in place of the skipped one
+     */
+    pub duplicate_of: usize,
+    pub job_config_file_name: String,
+}
+
+/**
+ * Outcome of submitting a batch of job requests
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobBatchSubmission {
+    /**
+     * One job id per request in the batch, skipped duplicates have the job id of the submitted request
+     */
+    pub job_ids: Vec<JobId>,
+    pub duplicates: Vec<DuplicateJobRequest>,
+}
+
+/**
+ * Remove generation requests identical to an earlier one in the batch, e.g. from overlapping cutoffs,
+ * actions after the job succeeded are merged into the kept request.
+ * Returns the kept requests, the index of the kept request for each request in the batch, and the skipped duplicates.
+ */
+pub(crate) fn dedup_gen_requests(
+    requests: Vec<SubmitJobRequest>,
+) -> (Vec<SubmitJobRequest>, Vec<usize>, Vec<DuplicateJobRequest>) {
+    let mut kept: Vec<SubmitJobRequest> = vec![];
+    let mut kept_index: Vec<usize> = vec![];
+    let mut first_seen: HashMap<JobConfigKey, (usize, usize)> = HashMap::new();
+    let mut duplicates = vec![];
+    for (index, request) in requests.into_iter().enumerate() {
+        if request.gen_job_config.is_empty() {
+            kept_index.push(kept.len());
+            kept.push(request);
+            continue;
+        }
+        let key = request.job_config_key();
+        match first_seen.get(&key) {
+            Some(&(first, k)) => {
+                let target = &mut kept[k];
+                target.latest_pointers.extend(request.latest_pointers);
+                target.redis_aliases.extend(request.redis_aliases);
+                target.datasets.extend(request.datasets);
+                duplicates.push(DuplicateJobRequest {
+                    index,
+                    duplicate_of: first,
+                    job_config_file_name: request.job_config_file_name,
+                });
+                kept_index.push(k);
+            }
+            None => {
+                first_seen.insert(key, (index, kept.len()));
+                kept_index.push(kept.len());
+                kept.push(request);
+            }
+        }
+    }
+    (kept, kept_index, duplicates)
+}
+
+/**
+ * Quartz cron expressions have 6 or 7 fields, seconds first, values of each field must be in its range
+ */
+pub(crate) fn check_cron_expr(cron_expr: &str) -> Result<(), Error> {
+    let fields: Vec<&str> = cron_expr.split_whitespace().collect();
+    if !(6..=7).contains(&fields.len()) {
+        return Err(Error::InvalidSchedule(
+            cron_expr.to_string(),
+            format!("Quartz cron expression must have 6 or 7 fields, got {}", fields.len()),
+        ));
+    }
+    // `L`, `W` and `#` of the day fields are Quartz only, such expressions are left to the Spark provider to validate
+    if fields[3].contains(['L', 'W']) || fields[5].contains('#') || fields[5].ends_with('L') {
+        return Ok(());
+    }
+    cron::Schedule::from_str(cron_expr)
+        .map(|_| ())
+        .map_err(|e| Error::InvalidSchedule(cron_expr.to_string(), e.to_string()))
+}
+
+/**
+ * Output dataset of a job to be registered in the registry of the project
+ */
+#[derive(Clone, Debug)]
+pub struct DatasetRegistration {
+    pub(crate) project: FeathrProject,
+    pub name: String,
+    pub path: String,
+    pub schema: Option<String>,
+    pub job_name: String,
+    pub features: Vec<String>,
+}
+
+tokio::task_local! {
+    // Workspace dir overridden by the job request being submitted
+    static WORKSPACE_DIR_OVERRIDE: String;
+}
+
+/**
+ * Run `f` with remote URLs created under `workspace_dir`, if it's set
+ */
+pub(crate) async fn with_workspace_dir<F>(workspace_dir: Option<String>, f: F) -> F::Output
+where
+    F: std::future::Future,
+{
+    match workspace_dir {
+        Some(dir) => WORKSPACE_DIR_OVERRIDE.scope(dir, f).await,
+        None => f.await,
+    }
+}
+
+/**
+ * Remote URL under the overridden workspace dir, `None` if not overridden
+ */
+pub(crate) fn overridden_remote_url(filename: &str) -> Option<String> {
+    WORKSPACE_DIR_OVERRIDE
+        .try_with(|dir| {
+            format!(
+                "{}/{}",
+                dir.trim_end_matches('/'),
+                filename.trim_start_matches('/')
+            )
+        })
+        .ok()
 }
 
 /**
  * Spark Job Id
  */
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, serde::Deserialize)]
 pub struct JobId(pub u64);
 
 impl std::fmt::Display for JobId {
@@ -61,8 +403,12 @@ impl std::fmt::Display for JobId {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum JobStatus {
+    /**
+     * Waiting for the cluster to be provisioned
+     */
+    Provisioning,
     Starting,
     Running,
     Success,
@@ -81,6 +427,7 @@ impl std::fmt::Display for JobStatus {
             f,
             "{}",
             match &self {
+                JobStatus::Provisioning => "Provisioning",
                 JobStatus::Starting => "Starting",
                 JobStatus::Running => "Running",
                 JobStatus::Success => "Success",
@@ -90,6 +437,140 @@ impl std::fmt::Display for JobStatus {
     }
 }
 
+/**
+ * File or directory on the remote storage
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteFile {
+    /**
+     * Spark compatible URL
+     */
+    pub url: String,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+/**
+ * Outcome of a finished, failed or timed out job
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobResult {
+    pub job_id: JobId,
+    /**
+     * `None` if the status cannot be retrieved, e.g. timed out
+     */
+    pub status: Option<JobStatus>,
+    pub log: Option<String>,
+    pub output_url: Option<String>,
+    pub error: Option<String>,
+    /**
+     * Time spent in each status before the job ended, as observed by the client
+     */
+    pub durations: HashMap<JobStatus, Duration>,
+    /**
+     * Schema of the output dataset, only read by `FeathrClient::wait_for_job_results_with_schema`
+     * for succeeded jobs with Avro or Parquet output
+     */
+    pub schema: Option<OutputSchema>,
+}
+
+impl JobResult {
+    pub fn is_success(&self) -> bool {
+        self.status == Some(JobStatus::Success)
+    }
+}
+
+/**
+ * Status of the job with the time spent in each status, as observed by the client
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobStatusInfo {
+    pub job_id: JobId,
+    pub status: JobStatus,
+    /**
+     * Time the job entered current status, the first status is counted from the submission
+     */
+    pub status_since: DateTime<Utc>,
+    /**
+     * Time spent in previous statuses
+     */
+    pub durations: HashMap<JobStatus, Duration>,
+}
+
+impl JobStatusInfo {
+    pub(crate) fn new(job_id: JobId, status: JobStatus, since: DateTime<Utc>) -> Self {
+        Self {
+            job_id,
+            status,
+            status_since: since,
+            durations: Default::default(),
+        }
+    }
+
+    pub(crate) fn update(&mut self, status: JobStatus, now: DateTime<Utc>) {
+        if status != self.status {
+            *self.durations.entry(self.status).or_insert_with(Duration::zero) +=
+                now - self.status_since;
+            self.status = status;
+            self.status_since = now;
+        }
+    }
+
+    pub fn elapsed_in_status(&self) -> Duration {
+        Utc::now() - self.status_since
+    }
+
+    /**
+     * Time spent waiting for the cluster so far, tells if delays are caused by cluster capacity
+     */
+    pub fn provisioning_duration(&self) -> Duration {
+        let past = self
+            .durations
+            .get(&JobStatus::Provisioning)
+            .cloned()
+            .unwrap_or_else(Duration::zero);
+        if self.status == JobStatus::Provisioning {
+            past + self.elapsed_in_status()
+        } else {
+            past
+        }
+    }
+}
+
+/**
+ * A job submitted to the Spark provider, returned by `list_jobs`
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobSummary {
+    pub job_id: JobId,
+    pub name: String,
+    pub status: JobStatus,
+    /**
+     * `None` if the job hasn't started yet
+     */
+    pub started_at: Option<DateTime<Utc>>,
+    /**
+     * `None` if the job is still running
+     */
+    pub ended_at: Option<DateTime<Utc>>,
+    /**
+     * Output path tagged on the job, `None` if the job has no output
+     */
+    pub output: Option<String>,
+    /**
+     * Idempotency key tagged on the job, `None` if the job isn't tagged
+     */
+    pub idempotency_key: Option<Uuid>,
+    /**
+     * Cluster usage of the job if the Spark provider reports it in the listing
+     */
+    pub run_info: Option<JobRunInfo>,
+    /**
+     * Estimated with the prices in `spark_config.cost`, `None` if the usage or the price of the node type is unknown
+     */
+    pub cost: Option<JobCostEstimate>,
+}
+
 /**
  * Spark client trait
  */
@@ -108,6 +589,40 @@ where
      */
     async fn read_remote_file(&self, path: &str) -> Result<Bytes, crate::Error>;
 
+    /**
+     * Read at most `length` bytes of the file from `offset`, less if the file ends earlier.
+     * The default implementation reads the whole file, clients override it with ranged reads of their storage
+     */
+    async fn read_remote_file_range(&self, path: &str, offset: u64, length: u64) -> Result<Bytes, crate::Error> {
+        let content = self.read_remote_file(path).await?;
+        let start = (offset as usize).min(content.len());
+        let end = (offset.saturating_add(length) as usize).min(content.len());
+        Ok(content.slice(start..end))
+    }
+
+    /**
+     * Get the latest modification time of the file, or of any file under the directory,
+     * returns `None` if the path doesn't exist
+     */
+    async fn get_remote_last_modified(&self, path: &str) -> Result<Option<DateTime<Utc>>, crate::Error>;
+
+    /**
+     * List files and sub directories directly under the directory, ordered by URL,
+     * returns empty list if the directory doesn't exist
+     */
+    async fn list_remote_dir(&self, path: &str) -> Result<Vec<RemoteFile>, crate::Error>;
+
+    /**
+     * Delete the file, or the directory and all its content, do nothing if the path doesn't exist
+     */
+    async fn delete_remote_path(&self, path: &str) -> Result<(), crate::Error>;
+
+    /**
+     * Move the file, or the directory and all its content, to `to` on the same storage,
+     * do nothing if the path doesn't exist
+     */
+    async fn move_remote_path(&self, from: &str, to: &str) -> Result<(), crate::Error>;
+
     /**
      * Submit Spark job, upload files if necessary
      */
@@ -117,11 +632,41 @@ where
         request: SubmitJobRequest,
     ) -> Result<JobId, crate::Error>;
 
+    /**
+     * Create a job running on `request.schedule` instead of submitting a one-shot run,
+     * returns the id of the created job in the Spark provider
+     */
+    async fn create_scheduled_job(
+        &self,
+        _var_source: Arc<dyn VarSource + Send + Sync>,
+        _request: SubmitJobRequest,
+    ) -> Result<u64, crate::Error> {
+        let name = std::any::type_name::<Self>().rsplit("::").next().unwrap_or_default();
+        Err(crate::Error::ScheduleNotSupported(name.to_string()))
+    }
+
     /**
      * Get job status
      */
     async fn get_job_status(&self, job_id: JobId) -> Result<JobStatus, crate::Error>;
 
+    /**
+     * Stop the job, a cancelled job ends with `JobStatus::Failed`, except on Livy and Azure Synapse
+     * where the batch is deleted, so the job is unknown to the server afterwards
+     */
+    async fn cancel_job(&self, _job_id: JobId) -> Result<(), crate::Error> {
+        let name = std::any::type_name::<Self>().rsplit("::").next().unwrap_or_default();
+        Err(crate::Error::CancelNotSupported(name.to_string()))
+    }
+
+    /**
+     * List at most `limit` most recently submitted jobs, newest first
+     */
+    async fn list_jobs(&self, _limit: usize) -> Result<Vec<JobSummary>, crate::Error> {
+        let name = std::any::type_name::<Self>().rsplit("::").next().unwrap_or_default();
+        Err(crate::Error::ListJobsNotSupported(name.to_string()))
+    }
+
     /**
      * Get job driver log
      */
@@ -132,6 +677,11 @@ where
      */
     async fn get_job_output_url(&self, job_id: JobId) -> Result<Option<String>, crate::Error>;
 
+    /**
+     * Get node type, number of nodes and run time of the job cluster
+     */
+    async fn get_job_run_info(&self, job_id: JobId) -> Result<JobRunInfo, crate::Error>;
+
     /**
      * Construct remote URL for the filename
      */
@@ -161,7 +711,9 @@ where
         job_id: JobId,
         timeout: Option<Duration>,
     ) -> Result<JobStatus, crate::Error> {
-        let wait_until = timeout.map(|d| Instant::now() + d.to_std().unwrap());
+        let wait_until = timeout
+            .map(|d| crate::utils::to_std_duration(d).map(|d| Instant::now() + d))
+            .transpose()?;
         loop {
             let status = self.get_job_status(job_id).await?;
             debug!("Job {}, status: {}", job_id, status);
@@ -175,7 +727,7 @@ where
                 }
             }
             // Check every few seconds
-            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            crate::runtime::sleep(std::time::Duration::from_secs(10)).await;
         }
         Err(crate::Error::Timeout)
     }
@@ -184,12 +736,10 @@ where
      * Download a file from remote side to local cache dir
      */
     async fn download_file(&self, url: &str, local_cache_dir: &str) -> Result<(), crate::Error> {
-        let mut bytes = self.read_remote_file(url).await?;
+        let bytes = self.read_remote_file(url).await?;
         let dir = std::path::Path::new(local_cache_dir);
         let file_path = dir.join(self.get_file_name(url)?);
-        let mut file = tokio::fs::File::create(file_path).await?;
-        file.write_all_buf(&mut bytes).await?;
-        Ok(())
+        crate::runtime::write_file(file_path, &bytes).await
     }
 
     /**
@@ -206,15 +756,11 @@ where
                 .await?
         } else if self.is_url_on_storage(path) {
             // It's a file on the storage
+            manifest::record_reference(path);
             return Ok(path.to_string());
         } else {
             // Local file
-            let mut v: Vec<u8> = vec![];
-            tokio::fs::File::open(path)
-                .await?
-                .read_to_end(&mut v)
-                .await?;
-            Bytes::from(v)
+            Bytes::from(crate::runtime::read_file(path).await?)
         };
         let url = self.get_remote_url(&self.get_file_name(path)?);
         self.write_remote_file(&url, &bytes).await
@@ -251,64 +797,81 @@ where
         var_source: Arc<dyn VarSource + Send + Sync>,
         request: &SubmitJobRequest,
     ) -> Result<Vec<String>, crate::Error> {
-        let mut ret: Vec<String> = vec![
-            "--s3-config".to_string(),
-            self.get_s3_config(var_source.clone()).await?,
-            "--adls-config".to_string(),
-            self.get_adls_config(var_source.clone()).await?,
-            "--blob-config".to_string(),
-            self.get_blob_config(var_source.clone()).await?,
-            "--sql-config".to_string(),
-            self.get_sql_config(var_source.clone()).await?,
-            "--snowflake-config".to_string(),
-            self.get_snowflake_config(var_source.clone()).await?,
-        ];
+        let start = Instant::now();
+        let upload_ms = manifest::recorded_upload_ms();
+        let args = self.get_spark_arguments(var_source, request).await?;
+        manifest::record_config_render(start.elapsed(), upload_ms);
+        // Manifests are meant to be archived, credentials must not be recorded
+        manifest::record_spark_arguments(&args.redacted().to_vec());
+        Ok(args.to_vec())
+    }
 
+    /**
+     * Generate typed arguments for the Spark job, job configs are uploaded to the remote storage
+     */
+    async fn get_spark_arguments(
+        &self,
+        var_source: Arc<dyn VarSource + Send + Sync>,
+        request: &SubmitJobRequest,
+    ) -> Result<SparkArguments, crate::Error> {
         let feature_config_url = self.get_remote_url(&format!(
             "features_{}_{}.conf",
             request.name, request.job_key.as_simple()
         ));
         let feature_config_url = self
-            .write_remote_file(&feature_config_url, &request.feature_config.as_bytes())
+            .write_remote_file(&feature_config_url, request.feature_config.as_bytes())
             .await?;
-        ret.extend(vec!["--feature-config".to_string(), feature_config_url].into_iter());
 
         let job_config_url = self.get_remote_url(&request.job_config_file_name);
-        if request.gen_job_config.is_empty() {
+        let job = if request.gen_job_config.is_empty() {
             // This is a feature joining job request
-            let job_config_url = self
-                .write_remote_file(&job_config_url, &request.join_job_config.as_bytes())
-                .await?;
-            ret.extend(
-                vec![
-                    "--num-parts".to_string(),
-                    self.get_output_num_parts(var_source.clone()).await?,
-                    "--input".to_string(),
-                    request.input.clone(),
-                    "--output".to_string(),
-                    request.output.clone(),
-                    "--join-config".to_string(),
-                    job_config_url,
-                ]
-                .into_iter(),
-            );
+            SparkJobArguments::Join {
+                num_parts: self.get_output_num_parts(var_source.clone()).await?,
+                input: request.input.clone(),
+                output: request.output.clone(),
+                join_config_url: self
+                    .write_remote_file(&job_config_url, request.join_job_config.as_bytes())
+                    .await?,
+            }
         } else {
             // This is a feature generation job request
-            let job_config_url = self
-                .write_remote_file(&job_config_url, &request.gen_job_config.as_bytes())
-                .await?;
-            ret.extend(
-                vec![
-                    "--redis-config".to_string(),
-                    self.get_redis_config(var_source.clone()).await?,
-                    "--generation-config".to_string(),
-                    job_config_url,
-                ]
-                .into_iter(),
-            );
-        }
-        debug!("Arguments: {}", serde_json::to_string_pretty(&ret).unwrap());
-        Ok(ret)
+            SparkJobArguments::Generation {
+                redis_config: RedisConfig::from_var_source(&var_source).await,
+                kafka_config: KafkaConfig::from_var_source(&var_source).await,
+                cosmos_config: CosmosConfig::from_var_source(&var_source, &request.cosmos_key_names).await,
+                monitoring_config: MonitoringConfig::from_var_source(&var_source, &request.secret_key).await,
+                generation_config_url: self
+                    .write_remote_file(&job_config_url, request.gen_job_config.as_bytes())
+                    .await?,
+            }
+        };
+
+        let mut adls_config = AdlsConfig::from_var_source(&var_source).await;
+        let mut blob_config = BlobConfig::from_var_source(&var_source).await;
+        for (host, key_name) in request.storage_key_names.iter() {
+            let key = var_source.get_environment_variable(&[key_name]).await?;
+            if let Some(account) = host.strip_suffix(ADLS_HOST_SUFFIX) {
+                adls_config.adls_account_keys.insert(account.to_string(), key);
+            } else if let Some(account) = host.strip_suffix(BLOB_HOST_SUFFIX) {
+                blob_config.blob_account_keys.insert(account.to_string(), key);
+            } else {
+                warn!("Storage account {} is neither ADLS nor Azure Blob, access key ignored", host);
+            }
+        }
+
+        let args = SparkArguments {
+            s3_config: S3Config::from_var_source(&var_source).await?,
+            adls_config,
+            blob_config,
+            sql_config: SqlConfig::from_var_source(&var_source)
+                .await
+                .with_jdbc_config(&request.jdbc_driver, &request.jdbc_properties),
+            snowflake_config: SnowflakeConfig::from_var_source(&var_source).await,
+            feature_config_url,
+            job,
+        };
+        debug!("Spark arguments: {:#?}", args);
+        Ok(args)
     }
 
     async fn get_output_num_parts(
@@ -324,225 +887,49 @@ where
         &self,
         var_source: Arc<dyn VarSource + Send + Sync>,
     ) -> Result<String, crate::Error> {
-        #[derive(Debug, Serialize)]
-        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-        struct Config {
-            s3_endpoint: String,
-            s3_access_key: String,
-            s3_secret_key: String,
-        }
-        Ok(serde_json::to_string_pretty(&Config {
-            s3_endpoint: var_source
-                .get_environment_variable(&["offline_store", "s3", "s3_endpoint"])
-                .await?,
-            s3_access_key: var_source
-                .get_environment_variable(&["S3_ACCESS_KEY"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            s3_secret_key: var_source
-                .get_environment_variable(&["S3_SECRET_KEY"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-        })
-        .unwrap())
+        Ok(to_json(&S3Config::from_var_source(&var_source).await?))
     }
 
     async fn get_adls_config(
         &self,
         var_source: Arc<dyn VarSource + Send + Sync>,
     ) -> Result<String, crate::Error> {
-        #[derive(Debug, Serialize)]
-        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-        struct Config {
-            adls_account: String,
-            adls_key: String,
-        }
-        Ok(serde_json::to_string_pretty(&Config {
-            adls_account: var_source
-                .get_environment_variable(&["ADLS_ACCOUNT"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            adls_key: var_source
-                .get_environment_variable(&["ADLS_KEY"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-        })
-        .unwrap())
+        Ok(to_json(&AdlsConfig::from_var_source(&var_source).await))
     }
 
     async fn get_blob_config(
         &self,
         var_source: Arc<dyn VarSource + Send + Sync>,
     ) -> Result<String, crate::Error> {
-        #[derive(Debug, Serialize)]
-        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-        struct Config {
-            blob_account: String,
-            blob_key: String,
-        }
-        Ok(serde_json::to_string_pretty(&Config {
-            blob_account: var_source
-                .get_environment_variable(&["BLOB_ACCOUNT"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            blob_key: var_source
-                .get_environment_variable(&["BLOB_KEY"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-        })
-        .unwrap())
+        Ok(to_json(&BlobConfig::from_var_source(&var_source).await))
     }
 
     async fn get_sql_config(
         &self,
         var_source: Arc<dyn VarSource + Send + Sync>,
     ) -> Result<String, crate::Error> {
-        #[derive(Debug, Serialize)]
-        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-        struct Config {
-            jdbc_table: String,
-            jdbc_user: String,
-            jdbc_password: String,
-            jdbc_driver: String,
-            jdbc_auth_flag: String,
-            jdbc_token: String,
-        }
-        Ok(serde_json::to_string_pretty(&Config {
-            jdbc_table: var_source
-                .get_environment_variable(&["JDBC_TABLE"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            jdbc_user: var_source
-                .get_environment_variable(&["JDBC_USER"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            jdbc_password: var_source
-                .get_environment_variable(&["JDBC_PASSWORD"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            jdbc_driver: var_source
-                .get_environment_variable(&["JDBC_DRIVER"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            jdbc_auth_flag: var_source
-                .get_environment_variable(&["JDBC_AUTH_FLAG"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            jdbc_token: var_source
-                .get_environment_variable(&["JDBC_TOKEN"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-        })
-        .unwrap())
+        Ok(to_json(&SqlConfig::from_var_source(&var_source).await))
     }
 
     async fn get_snowflake_config(
         &self,
         var_source: Arc<dyn VarSource + Send + Sync>,
     ) -> Result<String, crate::Error> {
-        #[derive(Debug, Serialize)]
-        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-        struct Config {
-            jdbc_sf_url: String,
-            jdbc_sf_user: String,
-            jdbc_sf_role: String,
-            jdbc_sf_password: String,
-        }
-        Ok(serde_json::to_string_pretty(&Config {
-            jdbc_sf_url: var_source
-                .get_environment_variable(&["JDBC_SF_URL"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            jdbc_sf_user: var_source
-                .get_environment_variable(&["JDBC_SF_USER"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            jdbc_sf_role: var_source
-                .get_environment_variable(&["JDBC_SF_ROLE"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            jdbc_sf_password: var_source
-                .get_environment_variable(&["JDBC_SF_PASSWORD"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-        })
-        .unwrap())
+        Ok(to_json(&SnowflakeConfig::from_var_source(&var_source).await))
     }
 
     async fn get_redis_config(
         &self,
         var_source: Arc<dyn VarSource + Send + Sync>,
     ) -> Result<String, crate::Error> {
-        #[derive(Debug, Serialize)]
-        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-        struct Config {
-            redis_password: String,
-            redis_host: String,
-            redis_port: u16,
-            redis_ssl_enabled: bool,
-        }
-        Ok(serde_json::to_string_pretty(&Config {
-            redis_password: var_source
-                .get_environment_variable(&["REDIS_PASSWORD"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            redis_host: var_source
-                .get_environment_variable(&["REDIS_HOST"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-            redis_port: var_source
-                .get_environment_variable(&["REDIS_PORT"])
-                .await
-                .ok()
-                .unwrap_or_default()
-                .parse()
-                .unwrap_or(6380),
-            redis_ssl_enabled: var_source
-                .get_environment_variable(&["REDIS_SSL_ENABLED"])
-                .await
-                .ok()
-                .unwrap_or_default()
-                .parse()
-                .unwrap_or(true),
-        })
-        .unwrap())
+        Ok(to_json(&RedisConfig::from_var_source(&var_source).await))
     }
 
     async fn get_kafka_config(
         &self,
         var_source: Arc<dyn VarSource + Send + Sync>,
     ) -> Result<String, crate::Error> {
-        #[derive(Debug, Serialize)]
-        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-        struct Config {
-            kafka_sasl_jaas_config: String,
-        }
-        Ok(serde_json::to_string_pretty(&Config {
-            kafka_sasl_jaas_config: var_source
-                .get_environment_variable(&["KAFKA_SASL_JAAS_CONFIG"])
-                .await
-                .ok()
-                .unwrap_or_default(),
-        })
-        .unwrap())
+        Ok(to_json(&KafkaConfig::from_var_source(&var_source).await))
     }
 }
 
@@ -550,11 +937,14 @@ where
  * Builder to build a Spark Job submitting request
  */
 pub struct SubmitJoiningJobRequestBuilder {
-    job_name: String,
+    project: FeathrProject,
+    project_name: String,
+    job_name_template: String,
     input_path: String,
     main_jar_path: Option<String>,
     main_class_name: Option<String>,
     output_path: Option<String>,
+    output_format: Option<SourceFormat>,
     python_files: Vec<String>,
     reference_files: Vec<String>,
     configuration: HashMap<String, String>,
@@ -562,11 +952,20 @@ pub struct SubmitJoiningJobRequestBuilder {
     feature_join_config: String,
     secret_keys: Vec<String>,
     user_functions: HashMap<String, String>,
+    workspace_dir: Option<String>,
+    feature_names: Vec<String>,
+    dataset: Option<(String, Option<String>)>,
+    jdbc_driver: Option<String>,
+    jdbc_properties: HashMap<String, String>,
+    env_vars: HashMap<String, String>,
 }
 
 impl SubmitJoiningJobRequestBuilder {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new_join(
-        job_name: String,
+        project: FeathrProject,
+        project_name: String,
+        feature_names: Vec<String>,
         input_path: String,
         feature_config: String,
         job_config: String, // feature_join_config or feature_gen_config
@@ -574,11 +973,14 @@ impl SubmitJoiningJobRequestBuilder {
         user_functions: HashMap<String, String>,
     ) -> Self {
         Self {
-            job_name,
+            project,
+            project_name,
+            job_name_template: DEFAULT_JOB_NAME_TEMPLATE.to_string(),
             input_path,
             main_jar_path: None,
             main_class_name: None,
             output_path: None,
+            output_format: None,
             python_files: Default::default(),
             reference_files: Default::default(),
             configuration: Default::default(),
@@ -586,9 +988,30 @@ impl SubmitJoiningJobRequestBuilder {
             feature_join_config: job_config,
             secret_keys: secret_keys,
             user_functions: user_functions,
+            workspace_dir: None,
+            feature_names,
+            dataset: None,
+            jdbc_driver: None,
+            jdbc_properties: Default::default(),
+            env_vars: Default::default(),
         }
     }
 
+    pub(crate) fn jdbc_config(&mut self, driver: Option<String>, properties: HashMap<String, String>) -> &mut Self {
+        self.jdbc_driver = driver;
+        self.jdbc_properties = properties;
+        self
+    }
+
+    /**
+     * Set an environment variable of the Spark driver and executors, e.g. a switch read by preprocessing UDFs.
+     * Values are visible in the cluster config, use secrets for credentials
+     */
+    pub fn env_var(&mut self, key: &str, value: &str) -> &mut Self {
+        self.env_vars.insert(key.to_string(), value.to_string());
+        self
+    }
+
     /**
      * Set main Python script content for this job
      */
@@ -601,10 +1024,84 @@ impl SubmitJoiningJobRequestBuilder {
     }
 
     /**
-     * Set output path for the Spark job
+     * Set the template of the job name, see `DEFAULT_JOB_NAME_TEMPLATE`
+     */
+    pub fn job_name_template(&mut self, template: &str) -> &mut Self {
+        self.job_name_template = template.to_string();
+        self
+    }
+
+    /**
+     * Stage the artifacts of this job in the dir instead of the workspace dir in the config,
+     * the dir must be on the same storage, e.g. `abfss://container@account.dfs.core.windows.net/scratch`
+     */
+    pub fn workspace_dir(&mut self, dir: &str) -> &mut Self {
+        self.workspace_dir = Some(dir.to_string());
+        self
+    }
+
+    /**
+     * Set output path for the Spark job
+     */
+    pub fn output_path(&mut self, output_path: &str) -> &mut Self {
+        self.output_path = Some(output_path.to_string());
+        self
+    }
+
+    /**
+     * Write the joined output in the format instead of the default Avro, e.g. `SourceFormat::Parquet`
+     */
+    pub fn output_format(&mut self, format: SourceFormat) -> &mut Self {
+        self.output_format = Some(format);
+        self
+    }
+
+    /**
+     * Join config with the output format, HOCON configs get the field appended to the root object,
+     * where it overrides an earlier `outputFormat`
+     */
+    fn join_config(&self) -> String {
+        let format = match self.output_format {
+            Some(format) => format,
+            None => return self.feature_join_config.to_owned(),
+        };
+        if let Ok(serde_json::Value::Object(mut config)) = serde_json::from_str(&self.feature_join_config) {
+            config.insert("outputFormat".to_string(), format.as_str().into());
+            return serde_json::to_string_pretty(&config).unwrap();
+        }
+        let field = format!("outputFormat: \"{}\"", format.as_str());
+        let config = &self.feature_join_config;
+        // Comments are skipped to find the braces of the root object
+        let is_content = |l: &&str| {
+            let l = l.trim();
+            !l.is_empty() && !l.starts_with('#') && !l.starts_with("//")
+        };
+        let braced = config
+            .lines()
+            .find(is_content)
+            .map(|l| l.trim_start().starts_with('{'))
+            .unwrap_or_default();
+        // Position of the closing brace on the last line with content
+        let mut closing = None;
+        let mut offset = 0;
+        for l in config.split_inclusive('\n') {
+            if is_content(&l) {
+                closing = l.rfind('}').map(|i| offset + i);
+            }
+            offset += l.len();
+        }
+        match closing {
+            Some(i) if braced => format!("{}\n{}\n{}", &config[..i], field, &config[i..]),
+            _ => format!("{}\n{}\n", config.trim_end(), field),
+        }
+    }
+
+    /**
+     * Register the output in the registry as a dataset with lineage to the joined features after the job succeeded
+     * Only `LocalRegistry` supports datasets, with other registries a warning is logged and nothing is registered
      */
-    pub fn output_path(&mut self, output_path: &str) -> &mut Self {
-        self.output_path = Some(output_path.to_string());
+    pub fn register_dataset(&mut self, name: &str, schema: Option<&str>) -> &mut Self {
+        self.dataset = Some((name.to_string(), schema.map(ToString::to_string)));
         self
     }
 
@@ -617,10 +1114,29 @@ impl SubmitJoiningJobRequestBuilder {
             .into_iter()
             .collect();
         let job_key = Uuid::new_v4();
+        let job_name = format_job_name(
+            &self.job_name_template,
+            &self.project_name,
+            JobType::Join,
+            Utc::now(),
+            job_key,
+        );
+        let datasets = self
+            .dataset
+            .iter()
+            .map(|(name, schema)| DatasetRegistration {
+                project: self.project.clone(),
+                name: name.to_owned(),
+                path: output.to_owned(),
+                schema: schema.to_owned(),
+                job_name: job_name.to_owned(),
+                features: self.feature_names.to_owned(),
+            })
+            .collect();
         SubmitJobRequest {
             job_key,
-            name: self.job_name.to_owned(),
-            job_config_file_name: format!("feathr_join_config_{}_{}.conf", self.job_name, job_key.as_simple()),
+            job_config_file_name: format!("feathr_join_config_{}_{}.conf", job_name, job_key.as_simple()),
+            name: job_name,
             input: self.input_path.to_owned(),
             output,
             main_jar_path: self.main_jar_path.clone(),
@@ -630,19 +1146,45 @@ impl SubmitJoiningJobRequestBuilder {
                 .unwrap_or_else(|| JOIN_JOB_MAIN_CLASS_NAME.to_string()),
             main_python_script: gen_main_python(&self.user_functions, &self.python_files),
             feature_config: self.feature_config.to_owned(),
-            join_job_config: self.feature_join_config.to_owned(),
+            join_job_config: self.join_config(),
             gen_job_config: Default::default(),
             python_files: self.python_files.to_owned(),
             reference_files: self.reference_files.to_owned(),
             job_tags,
-            configuration: self.configuration.to_owned(),
+            configuration: self
+                .configuration
+                .iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .chain(
+                    self.output_format
+                        .map(|f| (OUTPUT_FORMAT_CONF.to_string(), f.as_str().to_string())),
+                )
+                .collect(),
             secret_key: self.secret_keys.to_owned(),
+            storage_key_names: Default::default(),
+            latest_pointers: Default::default(),
+            workspace_dir: self.workspace_dir.to_owned(),
+            datasets,
+            overwrite_paths: Default::default(),
+            redis_aliases: Default::default(),
+            jdbc_driver: self.jdbc_driver.to_owned(),
+            jdbc_properties: self.jdbc_properties.to_owned(),
+            env_vars: self.env_vars.to_owned(),
+            cosmos_key_names: Default::default(),
+            time_partitions: Default::default(),
+            schedule: None,
+            streaming: false,
+            idempotency_key: Uuid::nil(),
+            force: false,
         }
+        .with_idempotency_key(&self.project_name)
     }
 }
 
 pub struct SubmitGenerationJobRequestBuilder {
-    job_name: String,
+    project: FeathrProject,
+    project_name: String,
+    job_name_template: String,
     input_path: String,
     main_jar_path: Option<String>,
     main_class_name: Option<String>,
@@ -655,14 +1197,23 @@ pub struct SubmitGenerationJobRequestBuilder {
     start: DateTime<Utc>,
     end: DateTime<Utc>,
     step: DateTimeResolution,
+    backfill: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    schedule: Option<String>,
+    streaming: bool,
     materialization_builder: MaterializationSettingsBuilder,
 
     user_functions: HashMap<String, String>,
+    workspace_dir: Option<String>,
+    dataset: Option<(String, Option<String>)>,
+    jdbc_driver: Option<String>,
+    jdbc_properties: HashMap<String, String>,
+    env_vars: HashMap<String, String>,
 }
 
 impl SubmitGenerationJobRequestBuilder {
     pub(crate) fn new_gen(
-        job_name: String,
+        project: FeathrProject,
+        project_name: String,
         feature_names: &[String],
         input_path: String,
         feature_config: String,
@@ -672,8 +1223,11 @@ impl SubmitGenerationJobRequestBuilder {
         step: DateTimeResolution,
         user_functions: HashMap<String, String>,
     ) -> Self {
+        let materialization_name = format!("{}_feathr_feature_materialization_job", project_name);
         Self {
-            job_name: job_name.clone(),
+            project,
+            project_name,
+            job_name_template: DEFAULT_JOB_NAME_TEMPLATE.to_string(),
             input_path,
             main_jar_path: None,
             main_class_name: None,
@@ -685,11 +1239,42 @@ impl SubmitGenerationJobRequestBuilder {
             start,
             end,
             step,
-            materialization_builder: MaterializationSettingsBuilder::new(&job_name, feature_names),
+            backfill: None,
+            schedule: None,
+            streaming: false,
+            materialization_builder: MaterializationSettingsBuilder::new(&materialization_name, feature_names),
             user_functions,
+            workspace_dir: None,
+            dataset: None,
+            jdbc_driver: None,
+            jdbc_properties: Default::default(),
+            env_vars: Default::default(),
         }
     }
 
+    pub(crate) fn jdbc_config(&mut self, driver: Option<String>, properties: HashMap<String, String>) -> &mut Self {
+        self.jdbc_driver = driver;
+        self.jdbc_properties = properties;
+        self
+    }
+
+    /**
+     * Set an environment variable of the Spark driver and executors, e.g. a switch read by preprocessing UDFs.
+     * Values are visible in the cluster config, use secrets for credentials
+     */
+    pub fn env_var(&mut self, key: &str, value: &str) -> &mut Self {
+        self.env_vars.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /**
+     * Input of the job, defaults to the path of the source if all features are anchored on the same HDFS source
+     */
+    pub fn input_path(&mut self, input_path: &str) -> &mut Self {
+        self.input_path = input_path.to_string();
+        self
+    }
+
     pub fn sink<T>(&mut self, sink: T) -> &mut Self
     where
         T: Into<OutputSink>,
@@ -708,6 +1293,14 @@ impl SubmitGenerationJobRequestBuilder {
         self
     }
 
+    /**
+     * Also compute statistics of the features and write them into the monitoring table
+     */
+    pub fn monitoring(&mut self, sink: MonitoringSink) -> &mut Self {
+        self.materialization_builder.monitoring(sink);
+        self
+    }
+
     pub fn feature<T>(&mut self, feature: T) -> &mut Self
     where
         T: ToString,
@@ -736,24 +1329,263 @@ impl SubmitGenerationJobRequestBuilder {
         self
     }
 
+    /**
+     * Re-materialize the historical range instead of the cutoff window of the job, one job per day or hour,
+     * the range is widened to whole days or hours
+     */
+    pub fn backfill_time(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> &mut Self {
+        self.backfill = Some((start, end));
+        self
+    }
+
+    /**
+     * Aggregate window features incrementally from the output of the previous cutoff,
+     * the job must have a single cutoff because cutoffs are not run in order
+     */
+    pub fn incremental(&mut self, incremental: bool) -> &mut Self {
+        self.materialization_builder.incremental(incremental);
+        self
+    }
+
+    /**
+     * Create a job running on the Quartz cron schedule in UTC, e.g. `0 0 2 * * ?`, instead of a one-shot run,
+     * the job must have a single cutoff and is created with `FeathrClient::schedule_job`.
+     * Each run generates the window ending at its trigger time floored to the resolution, credentials are put into
+     * the secret scope in `spark_config.databricks.secret_scope` instead of the job definition.
+     * Only supported on Databricks, jobs with Python UDFs can't be scheduled
+     */
+    pub fn schedule(&mut self, cron_expr: &str) -> &mut Self {
+        self.schedule = Some(cron_expr.trim().to_string());
+        self
+    }
+
+    pub(crate) fn streaming(&mut self) -> &mut Self {
+        self.streaming = true;
+        self
+    }
+
+    /**
+     * Streaming jobs have a single cutoff and must write into streaming sinks only
+     */
+    fn check_streaming(&self) -> Result<(), Error> {
+        let sinks = &self.materialization_builder.sinks;
+        if sinks.is_empty() || sinks.iter().any(|s| !s.is_streaming()) {
+            return Err(Error::InvalidStreamingJob(
+                "all sinks must be streaming, e.g. `RedisSink` with `streaming` set".to_string(),
+            ));
+        }
+        if self.backfill.is_some() || self.schedule.is_some() {
+            return Err(Error::InvalidStreamingJob(
+                "streaming job can't be backfilled or scheduled".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn materialization_settings(&self) -> Result<Vec<MaterializationSettings>, Error> {
+        if self.streaming {
+            // Streaming jobs consume the source until the sinks time out, there is no window to generate
+            return Ok(vec![self.materialization_builder.cutoff(self.end, self.step)]);
+        }
+        let (start, end) = match self.backfill {
+            Some((start, end)) => (self.step.floor(start), self.step.ceil(end)),
+            None => (self.start, self.end),
+        };
+        let settings = self.materialization_builder.build(start, end, self.step)?;
+        // Jobs of the cutoffs run concurrently, an incremental cutoff would read the unfinished output of the previous one
+        if self.materialization_builder.incremental && settings.len() > 1 {
+            return Err(Error::InvalidConfig(format!(
+                "incremental aggregation needs a single cutoff, got {}, backfill without incremental first",
+                settings.len()
+            )));
+        }
+        Ok(settings)
+    }
+
+    /**
+     * Register the HDFS sinks in the registry as datasets with lineage to the materialized features,
+     * after the job of the last cutoff succeeded
+     * Only `LocalRegistry` supports datasets, with other registries a warning is logged and nothing is registered
+     */
+    pub fn register_dataset(&mut self, name: &str, schema: Option<&str>) -> &mut Self {
+        self.dataset = Some((name.to_string(), schema.map(ToString::to_string)));
+        self
+    }
+
+    /**
+     * Set the template of the job name, see `DEFAULT_JOB_NAME_TEMPLATE`
+     */
+    pub fn job_name_template(&mut self, template: &str) -> &mut Self {
+        self.job_name_template = template.to_string();
+        self
+    }
+
+    /**
+     * Stage the artifacts of this job in the dir instead of the workspace dir in the config,
+     * the dir must be on the same storage, e.g. `abfss://container@account.dfs.core.windows.net/scratch`
+     */
+    pub fn workspace_dir(&mut self, dir: &str) -> &mut Self {
+        self.workspace_dir = Some(dir.to_string());
+        self
+    }
+
+    /**
+     * Secrets of the sources and the sinks, each one once
+     */
+    fn get_secret_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .secret_keys
+            .iter()
+            .cloned()
+            .chain(
+                self.materialization_builder
+                    .sinks
+                    .iter()
+                    .flat_map(|sink| sink.get_secret_keys()),
+            )
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /**
+     * Write the planned cutoffs with their gen configs into a JSON file instead of submitting them,
+     * the manifest can be read back with `ScheduleManifest::load`
+     */
+    pub fn export_schedule_manifest<T>(&self, path: T) -> Result<ScheduleManifest, Error>
+    where
+        T: AsRef<Path>,
+    {
+        let windows = self
+            .materialization_settings()?
+            .into_iter()
+            .map(|s| ScheduledWindow {
+                start: s.operational.end_time - s.operational.resolution.duration(),
+                end: s.operational.end_time,
+                overwrite_paths: s
+                    .operational
+                    .sinks
+                    .iter()
+                    .filter_map(|sink| sink.get_overwrite_path())
+                    .collect(),
+                latest_pointers: self
+                    .materialization_builder
+                    .sinks
+                    .iter()
+                    .filter_map(|sink| sink.get_latest_pointer(s.operational.end_time))
+                    .collect(),
+                gen_config: s,
+            })
+            .collect();
+        let manifest = ScheduleManifest {
+            job_name: format_job_name(
+                &self.job_name_template,
+                &self.project_name,
+                JobType::Generation,
+                Utc::now(),
+                Uuid::new_v4(),
+            ),
+            input_path: self.input_path.to_owned(),
+            feature_config: self.feature_config.to_owned(),
+            secret_keys: self.get_secret_keys(),
+            python_files: self.python_files.to_owned(),
+            main_jar_path: self.main_jar_path.to_owned(),
+            reference_files: self.reference_files.to_owned(),
+            configuration: self.configuration.to_owned(),
+            workspace_dir: self.workspace_dir.to_owned(),
+            env_vars: self.env_vars.to_owned(),
+            storage_key_names: self
+                .materialization_builder
+                .sinks
+                .iter()
+                .filter_map(|s| s.get_storage_key_name().transpose())
+                .collect::<Result<HashMap<_, _>, _>>()?,
+            jdbc_driver: self.jdbc_driver.to_owned(),
+            jdbc_properties: self.jdbc_properties.to_owned(),
+            cosmos_key_names: self
+                .materialization_builder
+                .sinks
+                .iter()
+                .filter_map(|sink| sink.get_cosmos_key_name())
+                .collect(),
+            windows,
+        };
+        std::fs::write(path, serde_json::to_string_pretty(&manifest)?)?;
+        Ok(manifest)
+    }
+
     /**
      * Create Spark job request
      */
     pub fn build(&self) -> Result<Vec<SubmitJobRequest>, Error> {
-        let mat_settings = self
+        if self.streaming {
+            self.check_streaming()?;
+        }
+        let mat_settings = self.materialization_settings()?;
+        if let Some(cron_expr) = &self.schedule {
+            check_cron_expr(cron_expr)?;
+            if mat_settings.len() > 1 {
+                return Err(Error::InvalidSchedule(
+                    cron_expr.to_owned(),
+                    format!("scheduled job must have a single cutoff, got {}", mat_settings.len()),
+                ));
+            }
+        }
+        let storage_key_names = self
             .materialization_builder
-            .build(self.start, self.end, self.step)?;
+            .sinks
+            .iter()
+            .filter_map(|s| s.get_storage_key_name().transpose())
+            .collect::<Result<HashMap<_, _>, _>>()?;
         let job_key = Uuid::new_v4();
+        let job_name = format_job_name(
+            &self.job_name_template,
+            &self.project_name,
+            JobType::Generation,
+            Utc::now(),
+            job_key,
+        );
+        let hdfs_paths: Vec<&str> = self
+            .materialization_builder
+            .sinks
+            .iter()
+            .filter_map(|s| match s {
+                OutputSink::Hdfs(sink) => Some(sink.path.as_str()),
+                _ => None,
+            })
+            .collect();
+        let datasets: Vec<DatasetRegistration> = match &self.dataset {
+            Some((name, schema)) => hdfs_paths
+                .iter()
+                .enumerate()
+                .map(|(i, path)| DatasetRegistration {
+                    project: self.project.clone(),
+                    name: if hdfs_paths.len() > 1 {
+                        format!("{}_{}", name, i)
+                    } else {
+                        name.to_owned()
+                    },
+                    path: path.to_string(),
+                    schema: schema.to_owned(),
+                    job_name: job_name.to_owned(),
+                    features: self.materialization_builder.features.to_owned(),
+                })
+                .collect(),
+            None => Default::default(),
+        };
+        let last = mat_settings.len().saturating_sub(1);
         Ok(mat_settings
             .into_iter()
-            .map(|s| {
+            .enumerate()
+            .map(|(i, s)| {
                 let conf = serde_json::to_string_pretty(&s).unwrap();
                 SubmitJobRequest {
                     job_key,
-                    name: self.job_name.to_owned(),
+                    name: job_name.to_owned(),
                     job_config_file_name: format!(
                         "feathr_gen_conf_{}_{}_{}.conf",
-                        self.job_name,
+                        job_name,
                         job_key.as_simple(),
                         s.operational.end_time.timestamp_millis()
                     ),
@@ -772,13 +1604,208 @@ impl SubmitGenerationJobRequestBuilder {
                     reference_files: self.reference_files.to_owned(),
                     job_tags: Default::default(),
                     configuration: self.configuration.to_owned(),
-                    secret_key: self.secret_keys.to_owned(),
+                    secret_key: self.get_secret_keys(),
+                    storage_key_names: storage_key_names.clone(),
+                    latest_pointers: self
+                        .materialization_builder
+                        .sinks
+                        .iter()
+                        .filter_map(|sink| sink.get_latest_pointer(s.operational.end_time))
+                        .collect(),
+                    workspace_dir: self.workspace_dir.to_owned(),
+                    datasets: if i == last {
+                        datasets.clone()
+                    } else {
+                        Default::default()
+                    },
+                    redis_aliases: if i == last {
+                        self.materialization_builder
+                            .sinks
+                            .iter()
+                            .filter_map(|sink| sink.get_redis_alias())
+                            .collect()
+                    } else {
+                        Default::default()
+                    },
+                    overwrite_paths: s
+                        .operational
+                        .sinks
+                        .iter()
+                        .filter_map(|sink| sink.get_overwrite_path())
+                        .collect(),
+                    jdbc_driver: self.jdbc_driver.to_owned(),
+                    jdbc_properties: self.jdbc_properties.to_owned(),
+                    env_vars: self.env_vars.to_owned(),
+                    cosmos_key_names: self
+                        .materialization_builder
+                        .sinks
+                        .iter()
+                        .filter_map(|sink| sink.get_cosmos_key_name())
+                        .collect(),
+                    time_partitions: self
+                        .materialization_builder
+                        .sinks
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, sink)| sink.get_time_partition(i))
+                        .collect(),
+                    schedule: self.schedule.to_owned(),
+                    streaming: self.streaming,
+                    idempotency_key: Uuid::nil(),
+                    force: false,
                 }
+                .with_idempotency_key(&self.project_name)
             })
             .collect())
     }
 }
 
+/**
+ * Options to submit jobs with feature and job configs managed outside of this crate
+ */
+#[derive(Clone, Debug, Default)]
+pub struct ConfigJobOptions {
+    pub job_name: String,
+    /**
+     * Observation data path, only used by joining job
+     */
+    pub input_path: String,
+    /**
+     * Output path, only used by joining job
+     */
+    pub output_path: String,
+    pub main_jar_path: Option<String>,
+    pub python_files: Vec<String>,
+    pub reference_files: Vec<String>,
+    pub secret_keys: Vec<String>,
+    pub configuration: HashMap<String, String>,
+    /**
+     * Overrides the workspace dir in the config
+     */
+    pub workspace_dir: Option<String>,
+    /**
+     * Environment variables of the Spark driver and executors
+     */
+    pub env_vars: HashMap<String, String>,
+    /**
+     * Secrets holding the access keys of storage accounts used by sinks, keyed by storage host
+     */
+    pub storage_key_names: HashMap<String, String>,
+    /**
+     * JDBC driver class of JDBC sources, overrides `JDBC_DRIVER` in `--sql-config`
+     */
+    pub jdbc_driver: Option<String>,
+    /**
+     * JDBC connection properties of JDBC sources
+     */
+    pub jdbc_properties: HashMap<String, String>,
+    /**
+     * Secrets holding the keys of CosmosDB sinks
+     */
+    pub cosmos_key_names: Vec<String>,
+}
+
+impl ConfigJobOptions {
+    pub fn new(job_name: &str) -> Self {
+        Self {
+            job_name: job_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /**
+     * Create joining job request from feature config and feature join config
+     */
+    pub fn join_request(&self, feature_config: &str, join_config: &str) -> SubmitJobRequest {
+        let job_key = Uuid::new_v4();
+        SubmitJobRequest {
+            job_key,
+            name: self.job_name.to_owned(),
+            job_config_file_name: format!(
+                "feathr_join_config_{}_{}.conf",
+                self.job_name,
+                job_key.as_simple()
+            ),
+            input: self.input_path.to_owned(),
+            output: self.output_path.to_owned(),
+            main_jar_path: self.main_jar_path.clone(),
+            main_class_name: JOIN_JOB_MAIN_CLASS_NAME.to_string(),
+            main_python_script: None,
+            feature_config: feature_config.to_owned(),
+            join_job_config: join_config.to_owned(),
+            gen_job_config: Default::default(),
+            python_files: self.python_files.to_owned(),
+            reference_files: self.reference_files.to_owned(),
+            job_tags: [(OUTPUT_PATH_TAG.to_string(), self.output_path.clone())]
+                .into_iter()
+                .collect(),
+            configuration: self.configuration.to_owned(),
+            secret_key: self.secret_keys.to_owned(),
+            storage_key_names: self.storage_key_names.to_owned(),
+            latest_pointers: Default::default(),
+            workspace_dir: self.workspace_dir.to_owned(),
+            datasets: Default::default(),
+            overwrite_paths: Default::default(),
+            redis_aliases: Default::default(),
+            jdbc_driver: self.jdbc_driver.to_owned(),
+            jdbc_properties: self.jdbc_properties.to_owned(),
+            env_vars: self.env_vars.to_owned(),
+            cosmos_key_names: self.cosmos_key_names.to_owned(),
+            time_partitions: Default::default(),
+            schedule: None,
+            streaming: false,
+            idempotency_key: Uuid::nil(),
+            force: false,
+        }
+        .with_idempotency_key(&self.job_name)
+    }
+
+    /**
+     * Create feature generation job request from feature config and feature gen config
+     */
+    pub fn gen_request(&self, feature_config: &str, gen_config: &str) -> SubmitJobRequest {
+        let job_key = Uuid::new_v4();
+        SubmitJobRequest {
+            job_key,
+            name: self.job_name.to_owned(),
+            job_config_file_name: format!(
+                "feathr_gen_conf_{}_{}.conf",
+                self.job_name,
+                job_key.as_simple()
+            ),
+            input: self.input_path.to_owned(),
+            output: Default::default(),
+            main_jar_path: self.main_jar_path.clone(),
+            main_class_name: GEN_JOB_MAIN_CLASS_NAME.to_string(),
+            main_python_script: None,
+            feature_config: feature_config.to_owned(),
+            join_job_config: Default::default(),
+            gen_job_config: gen_config.to_owned(),
+            python_files: self.python_files.to_owned(),
+            reference_files: self.reference_files.to_owned(),
+            job_tags: Default::default(),
+            configuration: self.configuration.to_owned(),
+            secret_key: self.secret_keys.to_owned(),
+            storage_key_names: self.storage_key_names.to_owned(),
+            latest_pointers: Default::default(),
+            workspace_dir: self.workspace_dir.to_owned(),
+            datasets: Default::default(),
+            overwrite_paths: Default::default(),
+            redis_aliases: Default::default(),
+            jdbc_driver: self.jdbc_driver.to_owned(),
+            jdbc_properties: self.jdbc_properties.to_owned(),
+            env_vars: self.env_vars.to_owned(),
+            cosmos_key_names: self.cosmos_key_names.to_owned(),
+            time_partitions: Default::default(),
+            schedule: None,
+            streaming: false,
+            idempotency_key: Uuid::nil(),
+            force: false,
+        }
+        .with_idempotency_key(&self.job_name)
+    }
+}
+
 fn encode_buf(buf: &[u8]) -> String {
     let v: Vec<String> = base64::encode_config(buf, base64::STANDARD)
         .as_bytes()
@@ -853,6 +1880,9 @@ fn gen_main_python(
 pub enum Client {
     AzureSynapse(Arc<AzureSynapseClient>),
     Databricks(Arc<DatabricksClient>),
+    Livy(Arc<LivyJobClient>),
+    Emr(Arc<EmrClient>),
+    SparkOperator(Arc<SparkOperatorClient>),
 }
 
 #[async_trait]
@@ -864,6 +1894,9 @@ impl JobClient for Client {
         match self {
             Client::AzureSynapse(c) => c.write_remote_file(path, content),
             Client::Databricks(c) => c.write_remote_file(path, content),
+            Client::Livy(c) => c.write_remote_file(path, content),
+            Client::Emr(c) => c.write_remote_file(path, content),
+            Client::SparkOperator(c) => c.write_remote_file(path, content),
         }
         .await
     }
@@ -875,6 +1908,79 @@ impl JobClient for Client {
         match self {
             Client::AzureSynapse(c) => c.read_remote_file(path),
             Client::Databricks(c) => c.read_remote_file(path),
+            Client::Livy(c) => c.read_remote_file(path),
+            Client::Emr(c) => c.read_remote_file(path),
+            Client::SparkOperator(c) => c.read_remote_file(path),
+        }
+        .await
+    }
+
+    /**
+     * Read at most `length` bytes of the file from `offset`
+     */
+    async fn read_remote_file_range(&self, path: &str, offset: u64, length: u64) -> Result<Bytes, crate::Error> {
+        match self {
+            Client::AzureSynapse(c) => c.read_remote_file_range(path, offset, length),
+            Client::Databricks(c) => c.read_remote_file_range(path, offset, length),
+            Client::Livy(c) => c.read_remote_file_range(path, offset, length),
+            Client::Emr(c) => c.read_remote_file_range(path, offset, length),
+            Client::SparkOperator(c) => c.read_remote_file_range(path, offset, length),
+        }
+        .await
+    }
+
+    /**
+     * Get the latest modification time of the file, or of any file under the directory
+     */
+    async fn get_remote_last_modified(&self, path: &str) -> Result<Option<DateTime<Utc>>, crate::Error> {
+        match self {
+            Client::AzureSynapse(c) => c.get_remote_last_modified(path),
+            Client::Databricks(c) => c.get_remote_last_modified(path),
+            Client::Livy(c) => c.get_remote_last_modified(path),
+            Client::Emr(c) => c.get_remote_last_modified(path),
+            Client::SparkOperator(c) => c.get_remote_last_modified(path),
+        }
+        .await
+    }
+
+    /**
+     * List files and sub directories directly under the directory
+     */
+    async fn list_remote_dir(&self, path: &str) -> Result<Vec<RemoteFile>, crate::Error> {
+        match self {
+            Client::AzureSynapse(c) => c.list_remote_dir(path),
+            Client::Databricks(c) => c.list_remote_dir(path),
+            Client::Livy(c) => c.list_remote_dir(path),
+            Client::Emr(c) => c.list_remote_dir(path),
+            Client::SparkOperator(c) => c.list_remote_dir(path),
+        }
+        .await
+    }
+
+    /**
+     * Delete the file, or the directory and all its content
+     */
+    async fn delete_remote_path(&self, path: &str) -> Result<(), crate::Error> {
+        match self {
+            Client::AzureSynapse(c) => c.delete_remote_path(path),
+            Client::Databricks(c) => c.delete_remote_path(path),
+            Client::Livy(c) => c.delete_remote_path(path),
+            Client::Emr(c) => c.delete_remote_path(path),
+            Client::SparkOperator(c) => c.delete_remote_path(path),
+        }
+        .await
+    }
+
+    /**
+     * Move the file, or the directory and all its content, on the same storage
+     */
+    async fn move_remote_path(&self, from: &str, to: &str) -> Result<(), crate::Error> {
+        match self {
+            Client::AzureSynapse(c) => c.move_remote_path(from, to),
+            Client::Databricks(c) => c.move_remote_path(from, to),
+            Client::Livy(c) => c.move_remote_path(from, to),
+            Client::Emr(c) => c.move_remote_path(from, to),
+            Client::SparkOperator(c) => c.move_remote_path(from, to),
         }
         .await
     }
@@ -890,6 +1996,24 @@ impl JobClient for Client {
         match self {
             Client::AzureSynapse(c) => c.submit_job(var_source, request),
             Client::Databricks(c) => c.submit_job(var_source, request),
+            Client::Livy(c) => c.submit_job(var_source, request),
+            Client::Emr(c) => c.submit_job(var_source, request),
+            Client::SparkOperator(c) => c.submit_job(var_source, request),
+        }
+        .await
+    }
+
+    async fn create_scheduled_job(
+        &self,
+        var_source: Arc<dyn VarSource + Send + Sync>,
+        request: SubmitJobRequest,
+    ) -> Result<u64, crate::Error> {
+        match self {
+            Client::AzureSynapse(c) => c.create_scheduled_job(var_source, request),
+            Client::Databricks(c) => c.create_scheduled_job(var_source, request),
+            Client::Livy(c) => c.create_scheduled_job(var_source, request),
+            Client::Emr(c) => c.create_scheduled_job(var_source, request),
+            Client::SparkOperator(c) => c.create_scheduled_job(var_source, request),
         }
         .await
     }
@@ -901,6 +2025,38 @@ impl JobClient for Client {
         match self {
             Client::AzureSynapse(c) => c.get_job_status(job_id),
             Client::Databricks(c) => c.get_job_status(job_id),
+            Client::Livy(c) => c.get_job_status(job_id),
+            Client::Emr(c) => c.get_job_status(job_id),
+            Client::SparkOperator(c) => c.get_job_status(job_id),
+        }
+        .await
+    }
+
+    /**
+     * Stop the job, a cancelled job ends with `JobStatus::Failed`, except on Livy and Azure Synapse
+     * where the batch is deleted, so the job is unknown to the server afterwards
+     */
+    async fn cancel_job(&self, job_id: JobId) -> Result<(), crate::Error> {
+        match self {
+            Client::AzureSynapse(c) => c.cancel_job(job_id),
+            Client::Databricks(c) => c.cancel_job(job_id),
+            Client::Livy(c) => c.cancel_job(job_id),
+            Client::Emr(c) => c.cancel_job(job_id),
+            Client::SparkOperator(c) => c.cancel_job(job_id),
+        }
+        .await
+    }
+
+    /**
+     * List at most `limit` most recently submitted jobs, newest first
+     */
+    async fn list_jobs(&self, limit: usize) -> Result<Vec<JobSummary>, crate::Error> {
+        match self {
+            Client::AzureSynapse(c) => c.list_jobs(limit),
+            Client::Databricks(c) => c.list_jobs(limit),
+            Client::Livy(c) => c.list_jobs(limit),
+            Client::Emr(c) => c.list_jobs(limit),
+            Client::SparkOperator(c) => c.list_jobs(limit),
         }
         .await
     }
@@ -912,6 +2068,9 @@ impl JobClient for Client {
         match self {
             Client::AzureSynapse(c) => c.get_job_log(job_id),
             Client::Databricks(c) => c.get_job_log(job_id),
+            Client::Livy(c) => c.get_job_log(job_id),
+            Client::Emr(c) => c.get_job_log(job_id),
+            Client::SparkOperator(c) => c.get_job_log(job_id),
         }
         .await
     }
@@ -923,6 +2082,23 @@ impl JobClient for Client {
         match self {
             Client::AzureSynapse(c) => c.get_job_output_url(job_id),
             Client::Databricks(c) => c.get_job_output_url(job_id),
+            Client::Livy(c) => c.get_job_output_url(job_id),
+            Client::Emr(c) => c.get_job_output_url(job_id),
+            Client::SparkOperator(c) => c.get_job_output_url(job_id),
+        }
+        .await
+    }
+
+    /**
+     * Get node type, number of nodes and run time of the job cluster
+     */
+    async fn get_job_run_info(&self, job_id: JobId) -> Result<JobRunInfo, crate::Error> {
+        match self {
+            Client::AzureSynapse(c) => c.get_job_run_info(job_id),
+            Client::Databricks(c) => c.get_job_run_info(job_id),
+            Client::Livy(c) => c.get_job_run_info(job_id),
+            Client::Emr(c) => c.get_job_run_info(job_id),
+            Client::SparkOperator(c) => c.get_job_run_info(job_id),
         }
         .await
     }
@@ -934,6 +2110,9 @@ impl JobClient for Client {
         match self {
             Client::AzureSynapse(c) => c.get_remote_url(filename),
             Client::Databricks(c) => c.get_remote_url(filename),
+            Client::Livy(c) => c.get_remote_url(filename),
+            Client::Emr(c) => c.get_remote_url(filename),
+            Client::SparkOperator(c) => c.get_remote_url(filename),
         }
     }
 
@@ -944,6 +2123,9 @@ impl JobClient for Client {
         match self {
             Client::AzureSynapse(c) => c.is_url_on_storage(url),
             Client::Databricks(c) => c.is_url_on_storage(url),
+            Client::Livy(c) => c.is_url_on_storage(url),
+            Client::Emr(c) => c.is_url_on_storage(url),
+            Client::SparkOperator(c) => c.is_url_on_storage(url),
         }
     }
 }
@@ -971,6 +2153,11 @@ impl Client {
             "databricks" => Client::Databricks(Arc::new(
                 DatabricksClient::from_var_source(var_source).await?,
             )),
+            "livy" => Client::Livy(Arc::new(LivyJobClient::from_var_source(var_source).await?)),
+            "emr" => Client::Emr(Arc::new(EmrClient::from_var_source(var_source).await?)),
+            "kubernetes" => Client::SparkOperator(Arc::new(
+                SparkOperatorClient::from_var_source(var_source).await?,
+            )),
             _ => {
                 return Err(Error::UnsupportedSparkProvider(provider));
             }
@@ -983,7 +2170,168 @@ impl Client {
 mod tests {
     use std::collections::HashMap;
 
-    use super::gen_main_python;
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::{
+        dedup_gen_requests, env_var_conf, gen_main_python, with_workspace_dir, ConfigJobOptions, JobClient, JobId, JobStatus,
+        JobStatusInfo, LivyJobClient, SourceFormat, SubmitJoiningJobRequestBuilder, JOIN_JOB_MAIN_CLASS_NAME,
+        OUTPUT_FORMAT_CONF, OUTPUT_PATH_TAG,
+    };
+    use crate::LivyAuthenticator;
+
+    #[tokio::test]
+    async fn workspace_dir_override() {
+        let client = LivyJobClient::new(
+            "http://livy:8998",
+            LivyAuthenticator::None,
+            "http://namenode:9870",
+            LivyAuthenticator::None,
+            "hdfs://namenode:8020/feathr",
+        )
+        .unwrap();
+        assert_eq!(client.get_remote_url("a.conf"), "hdfs://namenode:8020/feathr/a.conf");
+        let url = with_workspace_dir(Some("hdfs://namenode:8020/scratch/".to_string()), async {
+            client.get_remote_url("a.conf")
+        })
+        .await;
+        assert_eq!(url, "hdfs://namenode:8020/scratch/a.conf");
+        assert_eq!(client.get_remote_url("a.conf"), "hdfs://namenode:8020/feathr/a.conf");
+    }
+
+    #[tokio::test]
+    async fn dataset_registration() {
+        let project = crate::FeathrProject::new_detached("p1").await;
+        let mut builder = SubmitJoiningJobRequestBuilder::new_join(
+            project,
+            "p1".to_string(),
+            vec!["f1".to_string(), "f2".to_string()],
+            "abfss://c@a.dfs.core.windows.net/obs.csv".to_string(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        builder.output_path("abfss://c@a.dfs.core.windows.net/output.avro");
+        assert!(builder.build().datasets.is_empty());
+
+        let req = builder.register_dataset("training_set", None).build();
+        assert_eq!(req.datasets.len(), 1);
+        let dataset = &req.datasets[0];
+        assert_eq!(dataset.name, "training_set");
+        assert_eq!(dataset.path, "abfss://c@a.dfs.core.windows.net/output.avro");
+        assert_eq!(dataset.job_name, req.name);
+        assert_eq!(dataset.features, vec!["f1", "f2"]);
+    }
+
+    #[tokio::test]
+    async fn env_vars() {
+        let project = crate::FeathrProject::new_detached("p1").await;
+        let req = SubmitJoiningJobRequestBuilder::new_join(
+            project,
+            "p1".to_string(),
+            vec!["f1".to_string()],
+            "abfss://c@a.dfs.core.windows.net/obs.csv".to_string(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .output_path("abfss://c@a.dfs.core.windows.net/output.avro")
+        .env_var("PREPROCESS_MODE", "strict")
+        .build();
+        assert_eq!(req.env_vars["PREPROCESS_MODE"], "strict");
+        let conf: HashMap<String, String> = env_var_conf(&req.env_vars).into_iter().collect();
+        assert_eq!(conf["spark.yarn.appMasterEnv.PREPROCESS_MODE"], "strict");
+        assert_eq!(conf["spark.executorEnv.PREPROCESS_MODE"], "strict");
+    }
+
+    #[tokio::test]
+    async fn output_format() {
+        let project = crate::FeathrProject::new_detached("p1").await;
+        let mut builder = SubmitJoiningJobRequestBuilder::new_join(
+            project,
+            "p1".to_string(),
+            vec!["f1".to_string()],
+            "abfss://c@a.dfs.core.windows.net/obs.csv".to_string(),
+            Default::default(),
+            r#"{"observationPath": "abfss://c@a.dfs.core.windows.net/obs.csv", "featureList": []}"#.to_string(),
+            Default::default(),
+            Default::default(),
+        );
+        builder.output_path("abfss://c@a.dfs.core.windows.net/output");
+        let req = builder.build();
+        assert!(!req.join_job_config.contains("outputFormat"));
+        assert!(!req.configuration.contains_key(OUTPUT_FORMAT_CONF));
+
+        let req = builder.output_format(SourceFormat::Parquet).build();
+        let config: serde_json::Value = serde_json::from_str(&req.join_job_config).unwrap();
+        assert_eq!(config["outputFormat"], "parquet");
+        assert_eq!(config["featureList"], serde_json::json!([]));
+        assert_eq!(req.configuration[OUTPUT_FORMAT_CONF], "parquet");
+
+        // HOCON configs, with and without the braces of the root object
+        for join_config in [
+            "// Join config\n{\n  featureList: []\n  outputFormat: avro\n}\n# End\n",
+            "featureList: []\noutputFormat: avro",
+        ] {
+            builder.feature_join_config = join_config.to_string();
+            let req = builder.build();
+            let config = crate::hocon::parse(&req.join_job_config).unwrap();
+            assert_eq!(config["outputFormat"], "parquet");
+            assert_eq!(config["featureList"], serde_json::json!([]));
+        }
+    }
+
+    #[test]
+    fn dedup_gen() {
+        let opts = ConfigJobOptions::default();
+        let mut requests = vec![
+            opts.gen_request("features", r#"{"operational": {"endTime": "2022-05-02"}}"#),
+            opts.gen_request("features", r#"{"operational": {"endTime": "2022-05-03"}}"#),
+            opts.join_request("features", "join"),
+            opts.gen_request("features", r#"{"operational": {"endTime": "2022-05-03"}}"#),
+            opts.join_request("features", "join"),
+        ];
+        requests[3]
+            .latest_pointers
+            .insert("abfss://c@a.dfs.core.windows.net/_LATEST".to_string(), "2022/05/03".to_string());
+        let file_name = requests[3].job_config_file_name.clone();
+        let (kept, kept_index, duplicates) = dedup_gen_requests(requests);
+        assert_eq!(kept.len(), 4);
+        assert_eq!(kept_index, vec![0, 1, 2, 1, 3]);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].index, 3);
+        assert_eq!(duplicates[0].duplicate_of, 1);
+        assert_eq!(duplicates[0].job_config_file_name, file_name);
+        assert_eq!(kept[1].latest_pointers.len(), 1);
+
+        // Same generation config with different Python files or Spark configs is another job
+        let mut requests = vec![
+            opts.gen_request("features", r#"{"operational": {"endTime": "2022-05-03"}}"#),
+            opts.gen_request("features", r#"{"operational": {"endTime": "2022-05-03"}}"#),
+            opts.gen_request("features", r#"{"operational": {"endTime": "2022-05-03"}}"#),
+        ];
+        requests[1].python_files.push("udf.py".to_string());
+        requests[2]
+            .configuration
+            .insert("spark.sql.shuffle.partitions".to_string(), "10".to_string());
+        let (kept, _, duplicates) = dedup_gen_requests(requests);
+        assert_eq!(kept.len(), 3);
+        assert!(duplicates.is_empty());
+    }
+
+    #[test]
+    fn status_durations() {
+        let t = Utc.with_ymd_and_hms(2022, 7, 1, 10, 0, 0).unwrap();
+        let mut info = JobStatusInfo::new(JobId(1), JobStatus::Provisioning, t);
+        info.update(JobStatus::Provisioning, t + Duration::minutes(3));
+        info.update(JobStatus::Running, t + Duration::minutes(5));
+        info.update(JobStatus::Success, t + Duration::minutes(6));
+        assert_eq!(info.status, JobStatus::Success);
+        assert_eq!(info.durations[&JobStatus::Provisioning], Duration::minutes(5));
+        assert_eq!(info.durations[&JobStatus::Running], Duration::minutes(1));
+        assert_eq!(info.provisioning_duration(), Duration::minutes(5));
+    }
 
     #[test]
     fn test_template() {
@@ -1000,4 +2348,46 @@ mod tests {
         let s = gen_main_python(&user_functions, &files);
         println!("{}", s.unwrap());
     }
+
+    #[test]
+    fn config_job_request() {
+        let opts = ConfigJobOptions {
+            input_path: "wasbs://a/input.csv".to_string(),
+            output_path: "wasbs://a/output.avro".to_string(),
+            ..ConfigJobOptions::new("j1")
+        };
+        let req = opts.join_request("anchors: {}", "featureList: []");
+        assert_eq!(req.main_class_name, JOIN_JOB_MAIN_CLASS_NAME);
+        assert_eq!(req.feature_config, "anchors: {}");
+        assert_eq!(req.join_job_config, "featureList: []");
+        assert_eq!(req.job_tags[OUTPUT_PATH_TAG], "wasbs://a/output.avro");
+        assert!(req.job_config_file_name.starts_with("feathr_join_config_j1_"));
+        let key = req.idempotency_key;
+        assert!(!key.is_nil());
+        assert_ne!(opts.join_request("anchors: {}", "featureList: []").job_key, req.job_key);
+        assert_eq!(opts.join_request("anchors: {}", "featureList: []").idempotency_key, key);
+        assert_ne!(opts.join_request("anchors: {}", "featureList: [f1]").idempotency_key, key);
+        assert_eq!(req.job_tags[super::IDEMPOTENCY_KEY_TAG], key.to_string());
+        let other_conf = ConfigJobOptions {
+            configuration: [("spark.executor.memory".to_string(), "8g".to_string())]
+                .into_iter()
+                .collect(),
+            ..opts.clone()
+        };
+        assert_ne!(other_conf.join_request("anchors: {}", "featureList: []").idempotency_key, key);
+        let other_files = ConfigJobOptions {
+            python_files: vec!["udf.py".to_string()],
+            ..opts.clone()
+        };
+        assert_ne!(other_files.join_request("anchors: {}", "featureList: []").idempotency_key, key);
+        let other_output = ConfigJobOptions {
+            output_path: "wasbs://a/output2.avro".to_string(),
+            ..opts.clone()
+        };
+        assert_ne!(other_output.join_request("anchors: {}", "featureList: []").idempotency_key, key);
+        let req = opts.gen_request("anchors: {}", "operational: {}");
+        assert_eq!(req.gen_job_config, "operational: {}");
+        assert!(req.join_job_config.is_empty());
+        assert_ne!(req.idempotency_key, key);
+    }
 }
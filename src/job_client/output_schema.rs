@@ -0,0 +1,558 @@
+use parquet::{
+    basic::{ConvertedType, LogicalType, Repetition, Type as PhysicalType},
+    file::{metadata::ParquetMetaDataReader, FOOTER_SIZE},
+    schema::types::Type as ParquetType,
+};
+use serde_json::Value;
+
+use crate::Error;
+
+use super::{JobClient, RemoteFile};
+
+const AVRO_MAGIC: &[u8] = b"Obj\x01";
+const AVRO_SCHEMA_KEY: &str = "avro.schema";
+const PARQUET_MAGIC: &[u8] = b"PAR1";
+// First read of the Avro header, grows up to the max if the schema doesn't fit
+const AVRO_HEADER_BYTES: u64 = 64 * 1024;
+const AVRO_HEADER_MAX_BYTES: u64 = 16 * 1024 * 1024;
+// Tail of the Parquet file read first, the metadata is usually within it
+const PARQUET_TAIL_BYTES: u64 = 64 * 1024;
+
+/**
+ * Column of the job output dataset
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputColumn {
+    pub name: String,
+    /**
+     * Spark style type name, e.g. `long`, `string`, `array<float>`
+     */
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+/**
+ * Schema of the job output dataset, only top level columns are listed
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutputSchema {
+    pub columns: Vec<OutputColumn>,
+}
+
+impl OutputSchema {
+    pub fn column(&self, name: &str) -> Option<&OutputColumn> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+
+    pub fn column_names(&self) -> Vec<&str> {
+        self.columns.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    /**
+     * Expected columns that don't exist in the output
+     */
+    pub fn missing_columns<T: AsRef<str>>(&self, expected: &[T]) -> Vec<String> {
+        expected
+            .iter()
+            .map(|c| c.as_ref())
+            .filter(|c| self.column(c).is_none())
+            .map(|c| c.to_string())
+            .collect()
+    }
+}
+
+pub(crate) fn is_data_file(url: &str) -> bool {
+    let name = url.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+    !name.starts_with('_')
+        && !name.starts_with('.')
+        && (name.ends_with(".avro") || name.ends_with(".parquet"))
+}
+
+/**
+ * Pick the smallest non-empty data file, Spark writes metadata files like `_SUCCESS` along with the data
+ */
+pub(crate) fn pick_data_file(files: &[RemoteFile]) -> Option<&RemoteFile> {
+    files
+        .iter()
+        .filter(|f| !f.is_dir && is_data_file(&f.url))
+        .min_by_key(|f| (f.size == 0, f.size))
+}
+
+/**
+ * Read the schema of the Avro or Parquet data file of `size` bytes, only the Avro header or the Parquet footer is read
+ */
+pub(crate) async fn read_output_schema<C: JobClient + Sync>(
+    client: &C,
+    url: &str,
+    size: u64,
+) -> Result<OutputSchema, Error> {
+    let invalid = |msg: &str| Error::InvalidOutputSchema(url.to_string(), msg.to_string());
+    if url.trim_end_matches('/').ends_with(".parquet") {
+        let tail_len = size.min(PARQUET_TAIL_BYTES);
+        let tail = client
+            .read_remote_file_range(url, size - tail_len, tail_len)
+            .await?;
+        if !tail.ends_with(PARQUET_MAGIC) {
+            return Err(invalid("not a Parquet file"));
+        }
+        let footer_len = parquet_footer_len(url, &tail)? as u64;
+        let metadata_end = tail.len() as u64 - FOOTER_SIZE as u64;
+        let metadata = if footer_len <= metadata_end {
+            tail.slice((metadata_end - footer_len) as usize..metadata_end as usize)
+        } else if footer_len + (FOOTER_SIZE + PARQUET_MAGIC.len()) as u64 <= size {
+            // Metadata larger than the tail, read it in one more request
+            client
+                .read_remote_file_range(url, size - FOOTER_SIZE as u64 - footer_len, footer_len)
+                .await?
+        } else {
+            return Err(invalid("invalid footer length"));
+        };
+        return parse_parquet_metadata(url, &metadata);
+    }
+
+    // The Avro header is usually small, read more only if the schema doesn't fit
+    let mut len = AVRO_HEADER_BYTES;
+    loop {
+        let head = client.read_remote_file_range(url, 0, len).await?;
+        if !head.starts_with(AVRO_MAGIC) {
+            return Err(invalid("not an Avro file"));
+        }
+        match parse_avro_header(&head) {
+            Ok(schema) => return Ok(schema),
+            Err(AvroError::Truncated) if (head.len() as u64) == len && len < AVRO_HEADER_MAX_BYTES => {
+                len = (len * 4).min(AVRO_HEADER_MAX_BYTES);
+            }
+            Err(AvroError::Truncated) => return Err(invalid("file metadata is truncated or too large")),
+            Err(AvroError::Invalid(msg)) => return Err(invalid(&msg)),
+        }
+    }
+}
+
+enum AvroError {
+    /**
+     * Data ends before the schema, more data needs to be read
+     */
+    Truncated,
+    Invalid(String),
+}
+
+impl From<&str> for AvroError {
+    fn from(msg: &str) -> Self {
+        AvroError::Invalid(msg.to_string())
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8, AvroError> {
+        let b = *self.buf.get(self.pos).ok_or(AvroError::Truncated)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], AvroError> {
+        let end = self.pos.checked_add(len).ok_or("invalid length")?;
+        let ret = self.buf.get(self.pos..end).ok_or(AvroError::Truncated)?;
+        self.pos = end;
+        Ok(ret)
+    }
+
+    fn varint(&mut self) -> Result<u64, AvroError> {
+        let mut ret = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            // The 10th byte can only carry the highest bit
+            if shift == 63 && b > 1 {
+                return Err("varint overflows".into());
+            }
+            ret |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(ret);
+            }
+        }
+        Err("varint is too long".into())
+    }
+
+    fn zigzag(&mut self) -> Result<i64, AvroError> {
+        let n = self.varint()?;
+        Ok((n >> 1) as i64 ^ -((n & 1) as i64))
+    }
+
+    fn len(&mut self) -> Result<usize, AvroError> {
+        let n = self.zigzag()?;
+        usize::try_from(n).map_err(|_| AvroError::Invalid(format!("invalid length {}", n)))
+    }
+}
+
+fn parse_avro_header(content: &[u8]) -> Result<OutputSchema, AvroError> {
+    let mut r = Reader::new(&content[AVRO_MAGIC.len()..]);
+    // File metadata is an Avro map, a block with negative count is followed by its size in bytes
+    loop {
+        let count = r.zigzag()?;
+        if count == 0 {
+            break;
+        }
+        if count < 0 {
+            r.zigzag()?;
+        }
+        for _ in 0..count.unsigned_abs() {
+            let key_len = r.len()?;
+            let key = r.bytes(key_len)?;
+            let value_len = r.len()?;
+            let value = r.bytes(value_len)?;
+            if key == AVRO_SCHEMA_KEY.as_bytes() {
+                let schema: Value = serde_json::from_slice(value).map_err(|e| AvroError::Invalid(e.to_string()))?;
+                return avro_columns(&schema).map_err(AvroError::Invalid);
+            }
+        }
+    }
+    Err(AvroError::Invalid(format!("'{}' not found in file metadata", AVRO_SCHEMA_KEY)))
+}
+
+fn avro_columns(schema: &Value) -> Result<OutputSchema, String> {
+    let fields = schema
+        .get("fields")
+        .and_then(Value::as_array)
+        .ok_or("top level schema is not a record")?;
+    let columns = fields
+        .iter()
+        .map(|f| {
+            let name = f
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or("field without name")?;
+            let (data_type, nullable) = avro_type(f.get("type").unwrap_or(&Value::Null));
+            Ok(OutputColumn {
+                name: name.to_string(),
+                data_type,
+                nullable,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(OutputSchema { columns })
+}
+
+/**
+ * Returns the type name and whether the type is a union with `null`
+ */
+fn avro_type(t: &Value) -> (String, bool) {
+    match t {
+        Value::String(s) => (avro_primitive(s), s == "null"),
+        Value::Array(union) => {
+            let nullable = union.iter().any(|v| v.as_str() == Some("null"));
+            let types: Vec<String> = union
+                .iter()
+                .filter(|v| v.as_str() != Some("null"))
+                .map(|v| avro_type(v).0)
+                .collect();
+            match types.as_slice() {
+                [t] => (t.clone(), nullable),
+                _ => (format!("union<{}>", types.join(",")), nullable),
+            }
+        }
+        Value::Object(o) => {
+            let logical = o.get("logicalType").and_then(Value::as_str).unwrap_or_default();
+            let data_type = match (o.get("type").and_then(Value::as_str), logical) {
+                (_, "timestamp-millis" | "timestamp-micros") => "timestamp".to_string(),
+                (_, "date") => "date".to_string(),
+                (_, "decimal") => "decimal".to_string(),
+                (Some("array"), _) => format!(
+                    "array<{}>",
+                    avro_type(o.get("items").unwrap_or(&Value::Null)).0
+                ),
+                (Some("map"), _) => format!(
+                    "map<string,{}>",
+                    avro_type(o.get("values").unwrap_or(&Value::Null)).0
+                ),
+                (Some("record"), _) => "struct".to_string(),
+                (Some(s), _) => avro_primitive(s),
+                (None, _) => return avro_type(o.get("type").unwrap_or(&Value::Null)),
+            };
+            (data_type, false)
+        }
+        _ => ("unknown".to_string(), false),
+    }
+}
+
+fn avro_primitive(t: &str) -> String {
+    match t {
+        "fixed" => "bytes",
+        "enum" => "string",
+        s => s,
+    }
+    .to_string()
+}
+
+/**
+ * Length of the Parquet file metadata from the last 8 bytes of the file
+ */
+pub(crate) fn parquet_footer_len(url: &str, tail: &[u8]) -> Result<usize, Error> {
+    let footer: &[u8; FOOTER_SIZE] = tail
+        .get(tail.len().saturating_sub(FOOTER_SIZE)..)
+        .and_then(|t| t.try_into().ok())
+        .ok_or_else(|| Error::InvalidOutputSchema(url.to_string(), "file is too short".to_string()))?;
+    ParquetMetaDataReader::decode_footer(footer)
+        .map_err(|e| Error::InvalidOutputSchema(url.to_string(), e.to_string()))
+}
+
+/**
+ * Parse the schema from the Parquet file metadata, i.e. the `footer_len` bytes before the last 8 bytes of the file
+ */
+pub(crate) fn parse_parquet_metadata(url: &str, metadata: &[u8]) -> Result<OutputSchema, Error> {
+    let metadata = ParquetMetaDataReader::decode_metadata(metadata)
+        .map_err(|e| Error::InvalidOutputSchema(url.to_string(), e.to_string()))?;
+    let columns = metadata
+        .file_metadata()
+        .schema()
+        .get_fields()
+        .iter()
+        .map(|field| OutputColumn {
+            name: field.name().to_string(),
+            data_type: parquet_type(field),
+            nullable: is_optional(field),
+        })
+        .collect();
+    Ok(OutputSchema { columns })
+}
+
+fn is_optional(t: &ParquetType) -> bool {
+    let info = t.get_basic_info();
+    info.has_repetition() && info.repetition() == Repetition::OPTIONAL
+}
+
+fn parquet_type(t: &ParquetType) -> String {
+    let info = t.get_basic_info();
+    if info.has_repetition() && info.repetition() == Repetition::REPEATED {
+        format!("array<{}>", parquet_base_type(t))
+    } else {
+        parquet_base_type(t)
+    }
+}
+
+/**
+ * Type of the field without its repetition
+ */
+fn parquet_base_type(t: &ParquetType) -> String {
+    let info = t.get_basic_info();
+    let fields = match t {
+        ParquetType::PrimitiveType { physical_type, .. } => {
+            return parquet_leaf_type(*physical_type, info.logical_type(), info.converted_type()).to_string()
+        }
+        ParquetType::GroupType { fields, .. } => fields,
+    };
+    match (info.logical_type(), info.converted_type(), fields.as_slice()) {
+        (Some(LogicalType::List), _, [repeated]) | (_, ConvertedType::LIST, [repeated]) => {
+            match repeated.get_fields() {
+                // 3-level list, the repeated group wraps the element
+                [element] if repeated.is_group() => format!("array<{}>", parquet_type(element)),
+                // Legacy 2-level list, the repeated field is the element
+                _ => format!("array<{}>", parquet_base_type(repeated)),
+            }
+        }
+        (Some(LogicalType::Map), _, [key_value])
+        | (_, ConvertedType::MAP | ConvertedType::MAP_KEY_VALUE, [key_value]) => {
+            match key_value.get_fields() {
+                [key, value] => format!("map<{},{}>", parquet_type(key), parquet_type(value)),
+                _ => "map".to_string(),
+            }
+        }
+        _ => "struct".to_string(),
+    }
+}
+
+fn parquet_leaf_type(
+    physical_type: PhysicalType,
+    logical_type: Option<LogicalType>,
+    converted_type: ConvertedType,
+) -> &'static str {
+    match (logical_type, converted_type) {
+        (Some(LogicalType::Decimal { .. }), _) | (_, ConvertedType::DECIMAL) => return "decimal",
+        (Some(LogicalType::Date), _) | (_, ConvertedType::DATE) => return "date",
+        (Some(LogicalType::Timestamp { .. }), _)
+        | (_, ConvertedType::TIMESTAMP_MILLIS | ConvertedType::TIMESTAMP_MICROS) => return "timestamp",
+        (Some(LogicalType::String | LogicalType::Enum | LogicalType::Json), _)
+        | (_, ConvertedType::UTF8 | ConvertedType::ENUM | ConvertedType::JSON) => return "string",
+        _ => {}
+    }
+    match physical_type {
+        PhysicalType::BOOLEAN => "boolean",
+        PhysicalType::INT32 => "int",
+        PhysicalType::INT64 => "long",
+        // Spark writes legacy timestamps as INT96
+        PhysicalType::INT96 => "timestamp",
+        PhysicalType::FLOAT => "float",
+        PhysicalType::DOUBLE => "double",
+        PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY => "bytes",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut n: u64, out: &mut Vec<u8>) {
+        loop {
+            let b = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                out.push(b);
+                return;
+            }
+            out.push(b | 0x80);
+        }
+    }
+
+    fn zigzag(n: i64, out: &mut Vec<u8>) {
+        varint(((n << 1) ^ (n >> 63)) as u64, out)
+    }
+
+    fn avro_bytes(b: &[u8], out: &mut Vec<u8>) {
+        zigzag(b.len() as i64, out);
+        out.extend_from_slice(b);
+    }
+
+    #[test]
+    fn avro_schema() {
+        let schema = r#"{
+            "type": "record",
+            "name": "topLevelRecord",
+            "fields": [
+                {"name": "user_id", "type": "long"},
+                {"name": "f_total", "type": ["double", "null"]},
+                {"name": "f_embedding", "type": ["null", {"type": "array", "items": "float"}]},
+                {"name": "ts", "type": {"type": "long", "logicalType": "timestamp-micros"}}
+            ]
+        }"#;
+        let mut content = AVRO_MAGIC.to_vec();
+        // One block with negative count and byte size
+        zigzag(-2, &mut content);
+        zigzag(0, &mut content);
+        avro_bytes(b"avro.codec", &mut content);
+        avro_bytes(b"snappy", &mut content);
+        avro_bytes(AVRO_SCHEMA_KEY.as_bytes(), &mut content);
+        avro_bytes(schema.as_bytes(), &mut content);
+        zigzag(0, &mut content);
+
+        let schema = match parse_avro_header(&content) {
+            Ok(schema) => schema,
+            _ => panic!("invalid Avro header"),
+        };
+        assert_eq!(schema.column_names(), vec!["user_id", "f_total", "f_embedding", "ts"]);
+        assert_eq!(schema.column("user_id").unwrap().data_type, "long");
+        assert!(!schema.column("user_id").unwrap().nullable);
+        assert_eq!(schema.column("f_total").unwrap().data_type, "double");
+        assert!(schema.column("f_total").unwrap().nullable);
+        assert_eq!(schema.column("f_embedding").unwrap().data_type, "array<float>");
+        assert_eq!(schema.column("ts").unwrap().data_type, "timestamp");
+        assert_eq!(
+            schema.missing_columns(&["user_id", "f_count"]),
+            vec!["f_count".to_string()]
+        );
+
+        // More data is needed if the header is cut short
+        for len in [AVRO_MAGIC.len() + 1, content.len() / 2, content.len() - 2] {
+            assert!(matches!(parse_avro_header(&content[..len]), Err(AvroError::Truncated)));
+        }
+        let mut invalid = AVRO_MAGIC.to_vec();
+        zigzag(1, &mut invalid);
+        zigzag(-1, &mut invalid);
+        assert!(matches!(parse_avro_header(&invalid), Err(AvroError::Invalid(_))));
+        // Varint overflowing 64 bits
+        let mut overflow = AVRO_MAGIC.to_vec();
+        overflow.extend_from_slice(&[0xff; 9]);
+        overflow.push(0x02);
+        assert!(matches!(parse_avro_header(&overflow), Err(AvroError::Invalid(_))));
+    }
+
+    fn parquet_file(message_type: &str) -> Vec<u8> {
+        use parquet::file::{properties::WriterProperties, writer::SerializedFileWriter};
+        use std::sync::Arc;
+
+        let schema = Arc::new(parquet::schema::parser::parse_message_type(message_type).unwrap());
+        let mut content = vec![];
+        SerializedFileWriter::new(&mut content, schema, Arc::new(WriterProperties::builder().build()))
+            .unwrap()
+            .close()
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn parquet_schema() {
+        let content = parquet_file(
+            "message spark_schema {
+                required int64 user_id;
+                optional binary name (UTF8);
+                optional group f_embedding (LIST) {
+                    repeated group list {
+                        optional float element;
+                    }
+                }
+                optional group f_tags (MAP) {
+                    repeated group key_value {
+                        required binary key (UTF8);
+                        optional int32 value;
+                    }
+                }
+                repeated int32 legacy_list;
+                optional int96 ts;
+                optional int32 d (DATE);
+            }",
+        );
+        let footer_len = parquet_footer_len("part-00000.snappy.parquet", &content).unwrap();
+        let end = content.len() - FOOTER_SIZE;
+        let schema =
+            parse_parquet_metadata("part-00000.snappy.parquet", &content[end - footer_len..end]).unwrap();
+        let column = |name: &str, data_type: &str, nullable: bool| OutputColumn {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable,
+        };
+        assert_eq!(
+            schema.columns,
+            vec![
+                column("user_id", "long", false),
+                column("name", "string", true),
+                column("f_embedding", "array<float>", true),
+                column("f_tags", "map<string,int>", true),
+                column("legacy_list", "array<int>", false),
+                column("ts", "timestamp", true),
+                column("d", "date", true),
+            ]
+        );
+
+        assert!(parquet_footer_len("x.parquet", b"PAR1").is_err());
+        assert!(parquet_footer_len("x.parquet", b"a,b,c,d,e,f").is_err());
+        assert!(parse_parquet_metadata("x.parquet", &content[end - footer_len + 1..end]).is_err());
+    }
+
+    #[test]
+    fn data_file() {
+        let file = |url: &str, size: u64, is_dir: bool| RemoteFile {
+            url: url.to_string(),
+            size,
+            is_dir,
+        };
+        let files = vec![
+            file("dbfs:/out/_SUCCESS", 0, false),
+            file("dbfs:/out/_committed_123", 10, false),
+            file("dbfs:/out/part-00000.avro", 0, false),
+            file("dbfs:/out/part-00001.avro", 300, false),
+            file("dbfs:/out/part-00002.avro", 200, false),
+            file("dbfs:/out/date=2022-01-01", 0, true),
+        ];
+        assert_eq!(
+            pick_data_file(&files).unwrap().url,
+            "dbfs:/out/part-00002.avro"
+        );
+        assert!(pick_data_file(&files[..2]).is_none());
+    }
+}
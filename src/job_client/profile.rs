@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use handlebars::Handlebars;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{DateTimeResolution, Error};
+
+use super::{
+    encode_buf, format_job_name, JobType, SubmitJobRequest, DEFAULT_JOB_NAME_TEMPLATE,
+    OUTPUT_PATH_TAG,
+};
+
+const PROFILE_TEMPLATE: &str = include_str!("../../template/feathr_profile_template.py.hbr");
+const PROFILE_RELATIVE_ERROR: f64 = 0.01;
+const PROFILE_TAG_PREFIX: &str = "profile";
+
+/**
+ * Probabilities of the quantiles computed by the profiling job
+ */
+pub const PROFILE_QUANTILES: &[f64] = &[0.05, 0.25, 0.5, 0.75, 0.95];
+
+/**
+ * Statistics of a materialized feature, `min`, `max` and `quantiles` are only available for numeric features
+ */
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeatureStatistics {
+    pub feature: String,
+    pub count: u64,
+    pub null_count: u64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /**
+     * Pairs of probability and approximate quantile value
+     */
+    #[serde(default)]
+    pub quantiles: Vec<(f64, f64)>,
+}
+
+impl FeatureStatistics {
+    pub fn null_rate(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.null_count as f64 / self.count as f64
+        }
+    }
+
+    /**
+     * Statistics as registry tags, e.g. `profile.null_rate`, `profile.p50`
+     */
+    pub fn to_registry_tags(&self) -> HashMap<String, String> {
+        let mut tags: HashMap<String, String> = [
+            ("count", Some(self.count as f64)),
+            ("null_rate", Some(self.null_rate())),
+            ("min", self.min),
+            ("max", self.max),
+        ]
+        .into_iter()
+        .filter_map(|(name, v)| v.map(|v| (format!("{}.{}", PROFILE_TAG_PREFIX, name), v.to_string())))
+        .collect();
+        tags.extend(self.quantiles.iter().map(|(p, v)| {
+            (
+                format!("{}.p{}", PROFILE_TAG_PREFIX, (p * 100.0).round()),
+                v.to_string(),
+            )
+        }));
+        tags
+    }
+}
+
+/**
+ * Range of the materialized data to be profiled, with time partitioned sink only partitions of cutoffs
+ * between `start` and `end` are read
+ */
+#[derive(Clone, Debug)]
+pub struct ProfileOptions {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub step: DateTimeResolution,
+    /**
+     * Stop waiting for the profiling job after `timeout`, wait until the job ends if not set
+     */
+    pub timeout: Option<Duration>,
+}
+
+impl ProfileOptions {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>, step: DateTimeResolution) -> Self {
+        Self {
+            start,
+            end,
+            step,
+            timeout: None,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/**
+ * Parse the output of the profiling job, one JSON object per line
+ */
+pub(crate) fn parse_statistics(content: &str) -> Result<Vec<FeatureStatistics>, Error> {
+    Ok(content
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?)
+}
+
+/**
+ * Create the request of the PySpark job profiling features in the data, results are written into `output`
+ */
+pub(crate) fn profile_job_request(
+    project: &str,
+    features: &[String],
+    input_paths: &[String],
+    format: &str,
+    output: &str,
+) -> Result<SubmitJobRequest, Error> {
+    #[derive(Serialize)]
+    struct Params<'a> {
+        features: &'a [String],
+        input_paths: &'a [String],
+        format: &'a str,
+        output: &'a str,
+        quantiles: &'a [f64],
+        relative_error: f64,
+    }
+    let params = serde_json::to_vec(&Params {
+        features,
+        input_paths,
+        format,
+        output,
+        quantiles: PROFILE_QUANTILES,
+        relative_error: PROFILE_RELATIVE_ERROR,
+    })?;
+    let mut hbs = Handlebars::new();
+    hbs.register_template_string("py", PROFILE_TEMPLATE)
+        .map_err(|e| Error::InvalidConfig(e.to_string()))?;
+    let script = hbs
+        .render("py", &HashMap::from([("params", encode_buf(&params))]))
+        .map_err(|e| Error::InvalidConfig(e.to_string()))?;
+
+    let job_key = Uuid::new_v4();
+    let name = format_job_name(
+        DEFAULT_JOB_NAME_TEMPLATE,
+        project,
+        JobType::Profile,
+        Utc::now(),
+        job_key,
+    );
+    Ok(SubmitJobRequest {
+        job_key,
+        job_config_file_name: format!("feathr_profile_conf_{}.conf", name),
+        name,
+        output: output.to_string(),
+        main_python_script: Some(script),
+        job_tags: [(OUTPUT_PATH_TAG.to_string(), output.to_string())]
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_request() {
+        let request = profile_job_request(
+            "p1",
+            &["f1".to_string()],
+            &["abfss://c@a.dfs.core.windows.net/features/2022/07/01".to_string()],
+            "avro",
+            "abfss://c@a.dfs.core.windows.net/profile",
+        )
+        .unwrap();
+        assert!(request.name.starts_with("p1_profile_"));
+        let script = request.main_python_script.unwrap();
+        assert!(!script.contains("{{"));
+        let encoded: String = script
+            .split("\"\"\"")
+            .nth(1)
+            .unwrap()
+            .split_whitespace()
+            .collect();
+        let params: serde_json::Value =
+            serde_json::from_slice(&base64::decode(encoded).unwrap()).unwrap();
+        assert_eq!(params["features"][0], "f1");
+        assert_eq!(params["format"], "avro");
+
+        let stats = parse_statistics(
+            r#"{"feature": "f1", "count": 10, "null_count": 2, "min": 0.0, "max": 5.5, "quantiles": [[0.5, 2.0]]}
+{"feature": "f2", "count": 10, "null_count": 0, "min": null, "max": null, "quantiles": []}
+"#,
+        )
+        .unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].null_rate(), 0.2);
+        let tags = stats[0].to_registry_tags();
+        assert_eq!(tags["profile.p50"], "2");
+        assert_eq!(tags["profile.max"], "5.5");
+        assert!(!stats[1].to_registry_tags().contains_key("profile.min"));
+    }
+}
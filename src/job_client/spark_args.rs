@@ -0,0 +1,597 @@
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+use serde::Serialize;
+
+use crate::{materialization::MONITORING_SECRET_KEYS, Error, VarSource};
+
+const REDACTED: &str = "******";
+pub(crate) const ADLS_HOST_SUFFIX: &str = ".dfs.core.windows.net";
+pub(crate) const BLOB_HOST_SUFFIX: &str = ".blob.core.windows.net";
+
+async fn get_or_default(var_source: &Arc<dyn VarSource + Send + Sync>, name: &str) -> String {
+    var_source
+        .get_environment_variable(&[name])
+        .await
+        .ok()
+        .unwrap_or_default()
+}
+
+trait Redact {
+    type Output: Debug;
+    fn redacted(&self) -> Self::Output;
+}
+
+impl Redact for String {
+    type Output = &'static str;
+
+    fn redacted(&self) -> Self::Output {
+        if self.is_empty() {
+            ""
+        } else {
+            REDACTED
+        }
+    }
+}
+
+impl Redact for HashMap<String, String> {
+    type Output = HashMap<String, &'static str>;
+
+    fn redacted(&self) -> Self::Output {
+        self.iter().map(|(k, v)| (k.to_owned(), v.redacted())).collect()
+    }
+}
+
+/**
+ * Replaces secret values in place, empty values are kept to show they're not set
+ */
+trait Mask {
+    fn mask(&mut self);
+}
+
+impl Mask for String {
+    fn mask(&mut self) {
+        if !self.is_empty() {
+            *self = REDACTED.to_string();
+        }
+    }
+}
+
+impl Mask for HashMap<String, String> {
+    fn mask(&mut self) {
+        self.values_mut().for_each(Mask::mask);
+    }
+}
+
+/**
+ * Implements `Debug` with secret fields redacted, and `redacted()` returning a copy with secret fields masked
+ */
+macro_rules! redacted_debug {
+    ($t:ident, [$($field:ident),*], [$($secret:ident),*]) => {
+        impl Debug for $t {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(stringify!($t))
+                    $(.field(stringify!($field), &self.$field))*
+                    $(.field(stringify!($secret), &self.$secret.redacted()))*
+                    .finish()
+            }
+        }
+
+        impl $t {
+            /**
+             * Copy with secret fields masked, safe to be logged or archived
+             */
+            pub fn redacted(&self) -> Self {
+                let mut ret = self.clone();
+                $(ret.$secret.mask();)*
+                ret
+            }
+        }
+    };
+}
+
+#[derive(Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct S3Config {
+    pub s3_endpoint: String,
+    pub s3_access_key: String,
+    pub s3_secret_key: String,
+}
+redacted_debug!(S3Config, [s3_endpoint], [s3_access_key, s3_secret_key]);
+
+impl S3Config {
+    pub async fn from_var_source(
+        var_source: &Arc<dyn VarSource + Send + Sync>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            s3_endpoint: var_source
+                .get_environment_variable(&["offline_store", "s3", "s3_endpoint"])
+                .await?,
+            s3_access_key: get_or_default(var_source, "S3_ACCESS_KEY").await,
+            s3_secret_key: get_or_default(var_source, "S3_SECRET_KEY").await,
+        })
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct AdlsConfig {
+    pub adls_account: String,
+    pub adls_key: String,
+    /**
+     * Keys of additional storage accounts, e.g. used by output sinks
+     */
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub adls_account_keys: HashMap<String, String>,
+}
+redacted_debug!(AdlsConfig, [adls_account], [adls_key, adls_account_keys]);
+
+impl AdlsConfig {
+    pub async fn from_var_source(var_source: &Arc<dyn VarSource + Send + Sync>) -> Self {
+        Self {
+            adls_account: get_or_default(var_source, "ADLS_ACCOUNT").await,
+            adls_key: get_or_default(var_source, "ADLS_KEY").await,
+            adls_account_keys: Default::default(),
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct BlobConfig {
+    pub blob_account: String,
+    pub blob_key: String,
+    /**
+     * Keys of additional storage accounts, e.g. used by output sinks
+     */
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub blob_account_keys: HashMap<String, String>,
+}
+redacted_debug!(BlobConfig, [blob_account], [blob_key, blob_account_keys]);
+
+impl BlobConfig {
+    pub async fn from_var_source(var_source: &Arc<dyn VarSource + Send + Sync>) -> Self {
+        Self {
+            blob_account: get_or_default(var_source, "BLOB_ACCOUNT").await,
+            blob_key: get_or_default(var_source, "BLOB_KEY").await,
+            blob_account_keys: Default::default(),
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct SqlConfig {
+    pub jdbc_table: String,
+    pub jdbc_user: String,
+    pub jdbc_password: String,
+    pub jdbc_driver: String,
+    pub jdbc_auth_flag: String,
+    pub jdbc_token: String,
+    /**
+     * JDBC connection properties, may contain credentials
+     */
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub jdbc_properties: HashMap<String, String>,
+}
+redacted_debug!(
+    SqlConfig,
+    [jdbc_table, jdbc_user, jdbc_driver, jdbc_auth_flag],
+    [jdbc_password, jdbc_token, jdbc_properties]
+);
+
+impl SqlConfig {
+    pub async fn from_var_source(var_source: &Arc<dyn VarSource + Send + Sync>) -> Self {
+        Self {
+            jdbc_table: get_or_default(var_source, "JDBC_TABLE").await,
+            jdbc_user: get_or_default(var_source, "JDBC_USER").await,
+            jdbc_password: get_or_default(var_source, "JDBC_PASSWORD").await,
+            jdbc_driver: get_or_default(var_source, "JDBC_DRIVER").await,
+            jdbc_auth_flag: get_or_default(var_source, "JDBC_AUTH_FLAG").await,
+            jdbc_token: get_or_default(var_source, "JDBC_TOKEN").await,
+            jdbc_properties: Default::default(),
+        }
+    }
+
+    /**
+     * Driver and connection properties set on JDBC sources take precedence over the environment
+     */
+    pub(crate) fn with_jdbc_config(mut self, driver: &Option<String>, properties: &HashMap<String, String>) -> Self {
+        if let Some(driver) = driver {
+            self.jdbc_driver = driver.to_owned();
+        }
+        self.jdbc_properties.extend(properties.to_owned());
+        self
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct SnowflakeConfig {
+    pub jdbc_sf_url: String,
+    pub jdbc_sf_user: String,
+    pub jdbc_sf_role: String,
+    pub jdbc_sf_password: String,
+}
+redacted_debug!(
+    SnowflakeConfig,
+    [jdbc_sf_url, jdbc_sf_user, jdbc_sf_role],
+    [jdbc_sf_password]
+);
+
+impl SnowflakeConfig {
+    pub async fn from_var_source(var_source: &Arc<dyn VarSource + Send + Sync>) -> Self {
+        Self {
+            jdbc_sf_url: get_or_default(var_source, "JDBC_SF_URL").await,
+            jdbc_sf_user: get_or_default(var_source, "JDBC_SF_USER").await,
+            jdbc_sf_role: get_or_default(var_source, "JDBC_SF_ROLE").await,
+            jdbc_sf_password: get_or_default(var_source, "JDBC_SF_PASSWORD").await,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct RedisConfig {
+    pub redis_password: String,
+    pub redis_host: String,
+    pub redis_port: u16,
+    pub redis_ssl_enabled: bool,
+}
+redacted_debug!(
+    RedisConfig,
+    [redis_host, redis_port, redis_ssl_enabled],
+    [redis_password]
+);
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        Self {
+            redis_password: Default::default(),
+            redis_host: Default::default(),
+            redis_port: 6380,
+            redis_ssl_enabled: true,
+        }
+    }
+}
+
+impl RedisConfig {
+    pub async fn from_var_source(var_source: &Arc<dyn VarSource + Send + Sync>) -> Self {
+        Self {
+            redis_password: get_or_default(var_source, "REDIS_PASSWORD").await,
+            redis_host: get_or_default(var_source, "REDIS_HOST").await,
+            redis_port: get_or_default(var_source, "REDIS_PORT")
+                .await
+                .parse()
+                .unwrap_or(6380),
+            redis_ssl_enabled: get_or_default(var_source, "REDIS_SSL_ENABLED")
+                .await
+                .parse()
+                .unwrap_or(true),
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct KafkaConfig {
+    pub kafka_sasl_jaas_config: String,
+}
+redacted_debug!(KafkaConfig, [], [kafka_sasl_jaas_config]);
+
+impl KafkaConfig {
+    pub async fn from_var_source(var_source: &Arc<dyn VarSource + Send + Sync>) -> Self {
+        Self {
+            kafka_sasl_jaas_config: get_or_default(var_source, "KAFKA_SASL_JAAS_CONFIG").await,
+        }
+    }
+}
+
+/**
+ * Account keys of CosmosDB sinks, keyed by the secret name, e.g. `cosmos1_KEY`
+ */
+#[derive(Clone, Default, PartialEq, Serialize)]
+pub struct CosmosConfig {
+    #[serde(flatten)]
+    pub cosmos_keys: HashMap<String, String>,
+}
+redacted_debug!(CosmosConfig, [], [cosmos_keys]);
+
+impl CosmosConfig {
+    pub async fn from_var_source(
+        var_source: &Arc<dyn VarSource + Send + Sync>,
+        key_names: &[String],
+    ) -> Self {
+        let mut cosmos_keys = HashMap::new();
+        for name in key_names {
+            cosmos_keys.insert(name.to_owned(), get_or_default(var_source, name).await);
+        }
+        Self { cosmos_keys }
+    }
+}
+
+/**
+ * Connection of the monitoring database, only set when the job has monitoring sinks
+ */
+#[derive(Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct MonitoringConfig {
+    pub monitoring_database_sql_url: String,
+    pub monitoring_database_sql_user: String,
+    pub monitoring_database_sql_password: String,
+}
+redacted_debug!(
+    MonitoringConfig,
+    [monitoring_database_sql_url, monitoring_database_sql_user],
+    [monitoring_database_sql_password]
+);
+
+impl MonitoringConfig {
+    /**
+     * Monitoring sinks add the monitoring secrets into the secret keys of the job
+     */
+    pub async fn from_var_source(
+        var_source: &Arc<dyn VarSource + Send + Sync>,
+        secret_keys: &[String],
+    ) -> Option<Self> {
+        if !secret_keys.iter().any(|k| MONITORING_SECRET_KEYS.contains(&k.as_str())) {
+            return None;
+        }
+        let [url, user, password] = MONITORING_SECRET_KEYS;
+        Some(Self {
+            monitoring_database_sql_url: get_or_default(var_source, url).await,
+            monitoring_database_sql_user: get_or_default(var_source, user).await,
+            monitoring_database_sql_password: get_or_default(var_source, password).await,
+        })
+    }
+}
+
+/**
+ * Job specific arguments
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum SparkJobArguments {
+    Join {
+        num_parts: String,
+        input: String,
+        output: String,
+        join_config_url: String,
+    },
+    Generation {
+        redis_config: RedisConfig,
+        kafka_config: KafkaConfig,
+        cosmos_config: CosmosConfig,
+        monitoring_config: Option<MonitoringConfig>,
+        generation_config_url: String,
+    },
+}
+
+/**
+ * Arguments passed to the Feathr Spark job
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparkArguments {
+    pub s3_config: S3Config,
+    pub adls_config: AdlsConfig,
+    pub blob_config: BlobConfig,
+    pub sql_config: SqlConfig,
+    pub snowflake_config: SnowflakeConfig,
+    pub feature_config_url: String,
+    pub job: SparkJobArguments,
+}
+
+pub(crate) fn to_json<T: Serialize>(config: &T) -> String {
+    serde_json::to_string_pretty(config).unwrap()
+}
+
+impl SparkArguments {
+    /**
+     * Copy with all credentials masked, safe to be logged or archived
+     */
+    pub fn redacted(&self) -> Self {
+        Self {
+            s3_config: self.s3_config.redacted(),
+            adls_config: self.adls_config.redacted(),
+            blob_config: self.blob_config.redacted(),
+            sql_config: self.sql_config.redacted(),
+            snowflake_config: self.snowflake_config.redacted(),
+            feature_config_url: self.feature_config_url.clone(),
+            job: match &self.job {
+                SparkJobArguments::Join { .. } => self.job.clone(),
+                SparkJobArguments::Generation {
+                    redis_config,
+                    kafka_config,
+                    cosmos_config,
+                    monitoring_config,
+                    generation_config_url,
+                } => SparkJobArguments::Generation {
+                    redis_config: redis_config.redacted(),
+                    kafka_config: kafka_config.redacted(),
+                    cosmos_config: cosmos_config.redacted(),
+                    monitoring_config: monitoring_config.as_ref().map(MonitoringConfig::redacted),
+                    generation_config_url: generation_config_url.clone(),
+                },
+            },
+        }
+    }
+
+    /**
+     * Command line arguments of the Spark job
+     */
+    pub fn to_vec(&self) -> Vec<String> {
+        let mut ret = vec![
+            "--s3-config".to_string(),
+            to_json(&self.s3_config),
+            "--adls-config".to_string(),
+            to_json(&self.adls_config),
+            "--blob-config".to_string(),
+            to_json(&self.blob_config),
+            "--sql-config".to_string(),
+            to_json(&self.sql_config),
+            "--snowflake-config".to_string(),
+            to_json(&self.snowflake_config),
+            "--feature-config".to_string(),
+            self.feature_config_url.to_owned(),
+        ];
+        match &self.job {
+            SparkJobArguments::Join {
+                num_parts,
+                input,
+                output,
+                join_config_url,
+            } => ret.extend([
+                "--num-parts".to_string(),
+                num_parts.to_owned(),
+                "--input".to_string(),
+                input.to_owned(),
+                "--output".to_string(),
+                output.to_owned(),
+                "--join-config".to_string(),
+                join_config_url.to_owned(),
+            ]),
+            SparkJobArguments::Generation {
+                redis_config,
+                kafka_config,
+                cosmos_config,
+                monitoring_config,
+                generation_config_url,
+            } => {
+                ret.extend([
+                    "--redis-config".to_string(),
+                    to_json(redis_config),
+                    "--generation-config".to_string(),
+                    generation_config_url.to_owned(),
+                    "--kafka-config".to_string(),
+                    to_json(kafka_config),
+                ]);
+                // Only passed with CosmosDB sinks, older runtime doesn't accept the argument
+                if !cosmos_config.cosmos_keys.is_empty() {
+                    ret.extend(["--cosmos-config".to_string(), to_json(cosmos_config)]);
+                }
+                if let Some(monitoring_config) = monitoring_config {
+                    ret.extend(["--monitoring-config".to_string(), to_json(monitoring_config)]);
+                }
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spark_arguments() {
+        let args = SparkArguments {
+            s3_config: Default::default(),
+            adls_config: Default::default(),
+            blob_config: BlobConfig {
+                blob_account: "account1".to_string(),
+                blob_key: "blob-k3y".to_string(),
+                blob_account_keys: [("account2".to_string(), "blob2-k3y".to_string())]
+                    .into_iter()
+                    .collect(),
+            },
+            sql_config: SqlConfig {
+                jdbc_driver: "com.microsoft.sqlserver.jdbc.SQLServerDriver".to_string(),
+                ..Default::default()
+            }
+            .with_jdbc_config(
+                &Some("org.postgresql.Driver".to_string()),
+                &[("sslpassword".to_string(), "ssl-pa55".to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            snowflake_config: Default::default(),
+            feature_config_url: "dbfs:/features.conf".to_string(),
+            job: SparkJobArguments::Generation {
+                redis_config: RedisConfig {
+                    redis_host: "host1".to_string(),
+                    redis_password: "redis-pa55".to_string(),
+                    ..Default::default()
+                },
+                kafka_config: KafkaConfig {
+                    kafka_sasl_jaas_config: "kafka-s3cret".to_string(),
+                },
+                cosmos_config: Default::default(),
+                monitoring_config: None,
+                generation_config_url: "dbfs:/gen.conf".to_string(),
+            },
+        };
+
+        let debug = format!("{:?}", args);
+        assert!(debug.contains("account1"));
+        assert!(!debug.contains("blob-k3y"));
+        assert!(debug.contains("account2"));
+        assert!(!debug.contains("blob2-k3y"));
+        assert!(!debug.contains("redis-pa55"));
+        assert!(!debug.contains("kafka-s3cret"));
+        assert!(!debug.contains("ssl-pa55"));
+
+        let v = args.to_vec();
+        assert_eq!(v.len(), 18);
+        let blob: serde_json::Value = serde_json::from_str(&v[5]).unwrap();
+        assert_eq!(blob["BLOB_KEY"], "blob-k3y");
+        assert_eq!(blob["BLOB_ACCOUNT_KEYS"]["account2"], "blob2-k3y");
+        let adls: serde_json::Value = serde_json::from_str(&v[3]).unwrap();
+        assert!(adls.get("ADLS_ACCOUNT_KEYS").is_none());
+        let sql: serde_json::Value = serde_json::from_str(&v[7]).unwrap();
+        assert_eq!(sql["JDBC_DRIVER"], "org.postgresql.Driver");
+        assert_eq!(sql["JDBC_PROPERTIES"]["sslpassword"], "ssl-pa55");
+        assert_eq!(&v[10..12], &["--feature-config", "dbfs:/features.conf"]);
+        let redis: serde_json::Value = serde_json::from_str(&v[13]).unwrap();
+        assert_eq!(redis["REDIS_PORT"], 6380);
+        assert_eq!(v[15], "dbfs:/gen.conf");
+        let kafka: serde_json::Value = serde_json::from_str(&v[17]).unwrap();
+        assert_eq!(kafka["KAFKA_SASL_JAAS_CONFIG"], "kafka-s3cret");
+
+        let mut args = args;
+        if let SparkJobArguments::Generation { cosmos_config, .. } = &mut args.job {
+            cosmos_config
+                .cosmos_keys
+                .insert("cosmos1_KEY".to_string(), "cosmos-k3y".to_string());
+        }
+        assert!(!format!("{:?}", args).contains("cosmos-k3y"));
+        let v = args.to_vec();
+        assert_eq!(v[18], "--cosmos-config");
+        let cosmos: serde_json::Value = serde_json::from_str(&v[19]).unwrap();
+        assert_eq!(cosmos["cosmos1_KEY"], "cosmos-k3y");
+
+        if let SparkJobArguments::Generation { monitoring_config, .. } = &mut args.job {
+            *monitoring_config = Some(MonitoringConfig {
+                monitoring_database_sql_url: "jdbc:postgresql://db1:5432/monitoring".to_string(),
+                monitoring_database_sql_user: "user1".to_string(),
+                monitoring_database_sql_password: "monitoring-pa55".to_string(),
+            });
+        }
+        assert!(!format!("{:?}", args).contains("monitoring-pa55"));
+        let v = args.to_vec();
+        assert_eq!(v[20], "--monitoring-config");
+        let monitoring: serde_json::Value = serde_json::from_str(&v[21]).unwrap();
+        assert_eq!(monitoring["MONITORING_DATABASE_SQL_PASSWORD"], "monitoring-pa55");
+
+        // Redacted arguments keep everything but the credentials
+        let redacted = args.redacted().to_vec();
+        assert_eq!(redacted.len(), args.to_vec().len());
+        let joined = redacted.join(" ");
+        for secret in [
+            "blob-k3y",
+            "blob2-k3y",
+            "ssl-pa55",
+            "redis-pa55",
+            "kafka-s3cret",
+            "cosmos-k3y",
+            "monitoring-pa55",
+        ] {
+            assert!(!joined.contains(secret), "{} is not redacted", secret);
+        }
+        let blob: serde_json::Value = serde_json::from_str(&redacted[5]).unwrap();
+        assert_eq!(blob["BLOB_ACCOUNT"], "account1");
+        assert_eq!(blob["BLOB_ACCOUNT_KEYS"]["account2"], REDACTED);
+        // Unset secrets stay empty
+        let s3: serde_json::Value = serde_json::from_str(&redacted[1]).unwrap();
+        assert_eq!(s3["S3_SECRET_KEY"], "");
+        let monitoring: serde_json::Value = serde_json::from_str(&redacted[21]).unwrap();
+        assert_eq!(monitoring["MONITORING_DATABASE_SQL_USER"], "user1");
+    }
+}
@@ -0,0 +1,892 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::{Pod, Secret};
+use kube::{
+    api::{
+        Api, ApiResource, DeleteParams, DynamicObject, GroupVersionKind, LogParams, Patch,
+        PatchParams, PostParams,
+    },
+    config::KubeConfigOptions,
+};
+use log::{debug, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::aws::{parse_s3_url, AwsCredentials, S3Storage, S3_SCHEMES};
+use crate::{
+    livy_client::{ClusterSize, SparkRequest},
+    utils::to_std_duration,
+    Error, ExistsCache, JobClient, JobId, JobRunInfo, JobStatus, RemoteFile, Timeouts, VarSource,
+};
+
+const LOG_LINES: i64 = 1000;
+const DEFAULT_SPARK_VERSION: &str = "3.2.1";
+const SPARK_OPERATOR_GROUP: &str = "sparkoperator.k8s.io";
+const SPARK_OPERATOR_VERSION: &str = "v1beta2";
+/**
+ * Prefix of the annotations keeping the job tags
+ */
+const TAG_ANNOTATION_PREFIX: &str = "feathr/";
+
+/**
+ * Spark jobs submitted as `SparkApplication` resources to the Kubernetes Spark Operator, with artifacts
+ * stored on S3 or an S3 compatible object store
+ */
+pub struct SparkOperatorClient {
+    kube_config: kube::Config,
+    kube_client: kube::Client,
+    timeouts: Timeouts,
+    namespace: String,
+    image: String,
+    spark_version: String,
+    service_account: Option<String>,
+    cluster_size: ClusterSize,
+    workspace_dir: String,
+    s3_endpoint: Option<String>,
+    storage: S3Storage,
+    maven_artifact: String,
+    exists_cache: ExistsCache,
+}
+
+impl std::fmt::Debug for SparkOperatorClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SparkOperatorClient")
+            .field("cluster_url", &self.kube_config.cluster_url)
+            .field("namespace", &self.namespace)
+            .field("image", &self.image)
+            .field("spark_version", &self.spark_version)
+            .field("service_account", &self.service_account)
+            .field("cluster_size", &self.cluster_size)
+            .field("workspace_dir", &self.workspace_dir)
+            .field("s3_endpoint", &self.s3_endpoint)
+            .finish()
+    }
+}
+
+/**
+ * Kubernetes client with the connect and API timeouts, must be created in a Tokio runtime
+ */
+fn kube_client(config: &kube::Config, timeouts: &Timeouts) -> Result<kube::Client, Error> {
+    let mut config = config.clone();
+    config.connect_timeout = Some(to_std_duration(timeouts.connect)?);
+    config.read_timeout = Some(to_std_duration(timeouts.api)?);
+    config.write_timeout = Some(to_std_duration(timeouts.api)?);
+    Ok(kube::Client::try_from(config)?)
+}
+
+fn is_not_found(e: &kube::Error) -> bool {
+    matches!(e, kube::Error::Api(resp) if resp.code == 404)
+}
+
+impl SparkOperatorClient {
+    /**
+     * Create client with the Kubernetes config (e.g. from `kube::Config::infer`), the Spark image with
+     * S3A support, and the workspace dir on S3 (e.g. `s3a://bucket/feathr`).
+     * Jobs are submitted to the default namespace of the config, the client must be created in a Tokio runtime.
+     */
+    pub fn new(
+        config: kube::Config,
+        image: &str,
+        workspace_dir: &str,
+        credentials: AwsCredentials,
+    ) -> Result<Self, Error> {
+        parse_s3_url(workspace_dir)?;
+        let timeouts = Timeouts::default();
+        Ok(Self {
+            kube_client: kube_client(&config, &timeouts)?,
+            namespace: config.default_namespace.clone(),
+            kube_config: config,
+            timeouts,
+            image: image.to_string(),
+            spark_version: DEFAULT_SPARK_VERSION.to_string(),
+            service_account: None,
+            cluster_size: ClusterSize {
+                driver_cores: 1,
+                driver_memory: "2g".to_string(),
+                executor_cores: 1,
+                executor_memory: "2g".to_string(),
+                num_executors: 2,
+            },
+            workspace_dir: workspace_dir.trim_end_matches('/').to_string(),
+            s3_endpoint: None,
+            storage: S3Storage::new(timeouts.transfer_client()?.build()?, credentials, "us-east-1"),
+            maven_artifact: super::FEATHR_MAVEN_ARTIFACT.to_string(),
+            exists_cache: Default::default(),
+        })
+    }
+
+    /**
+     * Namespace of the jobs
+     */
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.to_string();
+        self
+    }
+
+    /**
+     * Kubernetes calls are limited by the API timeout, S3 operations by the transfer timeout
+     */
+    pub fn timeouts(mut self, timeouts: &Timeouts) -> Result<Self, Error> {
+        self.kube_client = kube_client(&self.kube_config, timeouts)?;
+        self.storage.set_client(timeouts.transfer_client()?.build()?);
+        self.timeouts = *timeouts;
+        Ok(self)
+    }
+
+    /**
+     * Region of the S3 bucket
+     */
+    pub fn region(mut self, region: &str) -> Self {
+        self.storage.set_region(region);
+        self
+    }
+
+    /**
+     * S3 compatible object store, e.g. `http://minio.minio:9000`, also passed to the jobs as the S3A endpoint
+     */
+    pub fn s3_endpoint(mut self, endpoint: &str) -> Self {
+        self.storage = self.storage.endpoint(endpoint);
+        self.s3_endpoint = Some(endpoint.to_string());
+        self
+    }
+
+    /**
+     * Version of Spark in the image
+     */
+    pub fn spark_version(mut self, version: &str) -> Self {
+        self.spark_version = version.to_string();
+        self
+    }
+
+    /**
+     * Service account of the driver pod, needs permission to create executor pods
+     */
+    pub fn service_account(mut self, service_account: &str) -> Self {
+        self.service_account = Some(service_account.to_string());
+        self
+    }
+
+    /**
+     * Cores and memory of the driver and the executors, and the number of executors
+     */
+    pub fn cluster_size(mut self, cluster_size: ClusterSize) -> Self {
+        self.cluster_size = cluster_size;
+        self
+    }
+
+    pub(crate) async fn from_var_source(
+        var_source: Arc<dyn VarSource + Send + Sync>,
+    ) -> Result<Self, Error> {
+        let get = |key: &'static str| {
+            let var_source = var_source.clone();
+            async move {
+                var_source
+                    .get_environment_variable(&["spark_config", "kubernetes", key])
+                    .await
+                    .ok()
+                    .filter(|s| !s.trim().is_empty())
+            }
+        };
+        // The kubeconfig, or the service account of the pod when running in the cluster
+        let config = match get("context").await {
+            Some(context) => kube::Config::from_kubeconfig(&KubeConfigOptions {
+                context: Some(context),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| Error::InvalidConfig(format!("Cannot load the Kubernetes config, {}", e)))?,
+            None => kube::Config::infer()
+                .await
+                .map_err(|e| Error::InvalidConfig(format!("Cannot load the Kubernetes config, {}", e)))?,
+        };
+        let image = get("image").await.ok_or_else(|| {
+            Error::InvalidConfig("spark_config.kubernetes.image is not set".to_string())
+        })?;
+        let workspace_dir = get("workspace_dir").await.ok_or_else(|| {
+            Error::InvalidConfig("spark_config.kubernetes.workspace_dir is not set".to_string())
+        })?;
+        let credentials = AwsCredentials {
+            access_key_id: var_source
+                .get_environment_variable(&["AWS_ACCESS_KEY_ID"])
+                .await?,
+            secret_access_key: var_source
+                .get_environment_variable(&["AWS_SECRET_ACCESS_KEY"])
+                .await?,
+            session_token: var_source
+                .get_environment_variable(&["AWS_SESSION_TOKEN"])
+                .await
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+        };
+        let mut client = Self::new(config, &image, &workspace_dir, credentials)?
+            .timeouts(&Timeouts::from_var_source(&var_source).await?)?;
+        if let Some(namespace) = get("namespace").await {
+            client = client.namespace(&namespace);
+        }
+        let region = match get("region").await {
+            Some(region) => Some(region),
+            None => var_source.get_environment_variable(&["AWS_REGION"]).await.ok(),
+        };
+        if let Some(region) = region.filter(|s| !s.trim().is_empty()) {
+            client = client.region(&region);
+        }
+        if let Some(endpoint) = get("s3_endpoint").await {
+            client = client.s3_endpoint(&endpoint);
+        }
+        if let Some(version) = get("spark_version").await {
+            client = client.spark_version(&version);
+        }
+        if let Some(service_account) = get("service_account").await {
+            client = client.service_account(&service_account);
+        }
+        let mut size = client.cluster_size.clone();
+        for (key, value) in [
+            ("driver_cores", &mut size.driver_cores),
+            ("executor_cores", &mut size.executor_cores),
+            ("executor_instances", &mut size.num_executors),
+        ] {
+            if let Some(v) = get(key).await {
+                *value = v.trim().parse().map_err(|_| {
+                    Error::InvalidConfig(format!(
+                        "spark_config.kubernetes.{} '{}' is not a number",
+                        key, v
+                    ))
+                })?;
+            }
+        }
+        if let Some(memory) = get("driver_memory").await {
+            size.driver_memory = memory;
+        }
+        if let Some(memory) = get("executor_memory").await {
+            size.executor_memory = memory;
+        }
+        client = client.cluster_size(size);
+        if let Ok(artifact) = var_source
+            .get_environment_variable(&["spark_config", "maven_artifact"])
+            .await
+        {
+            if !artifact.trim().is_empty() {
+                client.maven_artifact = artifact;
+            }
+        }
+        Ok(client)
+    }
+
+    fn applications(&self) -> Api<DynamicObject> {
+        let gvk = GroupVersionKind::gvk(SPARK_OPERATOR_GROUP, SPARK_OPERATOR_VERSION, "SparkApplication");
+        Api::namespaced_with(
+            self.kube_client.clone(),
+            &self.namespace,
+            &ApiResource::from_gvk(&gvk),
+        )
+    }
+
+    fn pods(&self) -> Api<Pod> {
+        Api::namespaced(self.kube_client.clone(), &self.namespace)
+    }
+
+    fn secrets(&self) -> Api<Secret> {
+        Api::namespaced(self.kube_client.clone(), &self.namespace)
+    }
+
+    async fn get_application(&self, job_id: JobId) -> Result<SparkApplication, Error> {
+        match self.applications().get_opt(&application_name(job_id)).await? {
+            Some(app) => Ok(serde_json::from_value(serde_json::to_value(app)?)?),
+            None => Err(Error::UnknownJob(job_id)),
+        }
+    }
+
+    /**
+     * Phase of the driver pod, `None` if the pod is not created yet or already deleted
+     */
+    async fn get_driver_phase(&self, app: &SparkApplication) -> Result<Option<String>, Error> {
+        let pod = match &app.status.driver_info.pod_name {
+            Some(pod) => pod,
+            None => return Ok(None),
+        };
+        Ok(self
+            .pods()
+            .get_opt(pod)
+            .await?
+            .and_then(|pod| pod.status)
+            .and_then(|status| status.phase))
+    }
+
+    /**
+     * Store the secret arguments in a `Secret` named after the application, it's owned by the
+     * application once created so they're deleted together
+     */
+    async fn create_secret(&self, job_id: JobId, secrets: BTreeMap<String, String>) -> Result<(), Error> {
+        let secret: Secret = serde_json::from_value(json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": {
+                "name": application_name(job_id),
+                "namespace": self.namespace,
+                "labels": { "app.kubernetes.io/managed-by": "feathr" },
+            },
+            "type": "Opaque",
+            "stringData": secrets,
+        }))?;
+        self.secrets().create(&PostParams::default(), &secret).await?;
+        Ok(())
+    }
+
+    /**
+     * `SparkApplication` resource of the job, the arguments in `secret_vars` are read from the
+     * env of the driver, which is taken from the secret of the job
+     */
+    fn spark_application<'a>(
+        &self,
+        job_id: JobId,
+        job: &SparkRequest,
+        tags: &HashMap<String, String>,
+        secret_vars: impl Iterator<Item = &'a String>,
+    ) -> Value {
+        let python = job.file.ends_with(".py");
+        let mut conf: BTreeMap<String, String> = job.conf.clone().into_iter().collect();
+        if let Some(endpoint) = &self.s3_endpoint {
+            conf.entry("spark.hadoop.fs.s3a.endpoint".to_string())
+                .or_insert_with(|| endpoint.to_owned());
+            conf.entry("spark.hadoop.fs.s3a.path.style.access".to_string())
+                .or_insert_with(|| "true".to_string());
+        }
+        let mut annotations: BTreeMap<String, String> = tags
+            .iter()
+            .filter(|(k, _)| {
+                k.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            })
+            .map(|(k, v)| (format!("{}{}", TAG_ANNOTATION_PREFIX, k), v.to_owned()))
+            .collect();
+        annotations.insert(format!("{}job_name", TAG_ANNOTATION_PREFIX), job.name.clone());
+        let size = &job.cluster_size;
+        let env: Vec<Value> = secret_vars
+            .map(|var| {
+                json!({
+                    "name": var,
+                    "valueFrom": { "secretKeyRef": { "name": application_name(job_id), "key": var } },
+                })
+            })
+            .collect();
+        let mut driver = json!({ "cores": size.driver_cores, "memory": size.driver_memory, "env": env });
+        if let Some(service_account) = &self.service_account {
+            driver["serviceAccount"] = json!(service_account);
+        }
+        let mut spec = json!({
+            "type": if python { "Python" } else { "Scala" },
+            "mode": "cluster",
+            "image": self.image,
+            "sparkVersion": self.spark_version,
+            "mainApplicationFile": job.file,
+            "arguments": job.args,
+            "sparkConf": conf,
+            "deps": {
+                "jars": job.jars,
+                "files": job.files,
+                "pyFiles": job.py_files,
+            },
+            "restartPolicy": { "type": "Never" },
+            "driver": driver,
+            "executor": {
+                "cores": size.executor_cores,
+                "instances": size.num_executors,
+                "memory": size.executor_memory,
+            },
+        });
+        if python {
+            spec["pythonVersion"] = json!("3");
+        } else {
+            spec["mainClass"] = json!(job.class_name);
+        }
+        json!({
+            "apiVersion": format!("{}/{}", SPARK_OPERATOR_GROUP, SPARK_OPERATOR_VERSION),
+            "kind": "SparkApplication",
+            "metadata": {
+                "name": application_name(job_id),
+                "namespace": self.namespace,
+                "labels": { "app.kubernetes.io/managed-by": "feathr" },
+                "annotations": annotations,
+            },
+            "spec": spec,
+        })
+    }
+}
+
+/**
+ * Credentials are in the `--*-config` JSON arguments, they're replaced by `$(VAR)` references which
+ * Kubernetes expands from the env of the driver, so they're not stored in the application resource.
+ * Returns the arguments and the secret values by the env var names.
+ */
+fn secret_arguments(args: &[String]) -> (Vec<String>, BTreeMap<String, String>) {
+    let mut secrets = BTreeMap::new();
+    let args = args
+        .iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            let flag = if i > 0 { args[i - 1].as_str() } else { "" };
+            if flag.starts_with("--") && flag.ends_with("-config") && arg.starts_with('{') {
+                let var = format!("FEATHR_ARG_{}", i);
+                secrets.insert(var.clone(), arg.to_owned());
+                format!("$({})", var)
+            } else {
+                // Escaped as Kubernetes would expand it too
+                arg.replace("$(", "$$(")
+            }
+        })
+        .collect();
+    (args, secrets)
+}
+
+/**
+ * The job id is encoded in the resource name, so jobs can be found by other clients
+ */
+fn application_name(job_id: JobId) -> String {
+    format!("feathr-{}", job_id.0)
+}
+
+fn pod_phase_status(phase: &str) -> JobStatus {
+    match phase {
+        "Pending" => JobStatus::Starting,
+        "Succeeded" => JobStatus::Success,
+        "Failed" => JobStatus::Failed,
+        // Running, or Unknown when the node is unreachable
+        _ => JobStatus::Running,
+    }
+}
+
+/**
+ * Status of the application before the driver pod is created, or after it's deleted
+ */
+fn application_state_status(state: &str) -> JobStatus {
+    match state {
+        "COMPLETED" => JobStatus::Success,
+        "FAILED" | "SUBMISSION_FAILED" => JobStatus::Failed,
+        "RUNNING" | "SUCCEEDING" | "FAILING" | "UNKNOWN" => JobStatus::Running,
+        // Not submitted yet, or waiting for resubmission
+        _ => JobStatus::Provisioning,
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApplicationState {
+    #[serde(default)]
+    state: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriverInfo {
+    pod_name: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SparkApplicationStatus {
+    #[serde(default)]
+    application_state: ApplicationState,
+    #[serde(default)]
+    driver_info: DriverInfo,
+    last_submission_attempt_time: Option<DateTime<Utc>>,
+    termination_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Metadata {
+    #[serde(default)]
+    annotations: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ExecutorSpec {
+    instances: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SparkApplicationSpec {
+    #[serde(default)]
+    executor: ExecutorSpec,
+}
+
+#[derive(Debug, Deserialize)]
+struct SparkApplication {
+    #[serde(default)]
+    metadata: Metadata,
+    #[serde(default)]
+    spec: SparkApplicationSpec,
+    #[serde(default)]
+    status: SparkApplicationStatus,
+}
+
+#[async_trait]
+impl JobClient for SparkOperatorClient {
+    async fn write_remote_file(&self, path: &str, content: &[u8]) -> Result<String, Error> {
+        let start = Instant::now();
+        self.exists_cache.invalidate(path);
+        self.storage.write(path, content).await?;
+        super::manifest::record_upload(path, content, start.elapsed());
+        Ok(path.to_string())
+    }
+
+    async fn read_remote_file(&self, path: &str) -> Result<Bytes, Error> {
+        self.storage.read(path).await
+    }
+
+    async fn read_remote_file_range(&self, path: &str, offset: u64, length: u64) -> Result<Bytes, Error> {
+        self.storage.read_range(path, offset, length).await
+    }
+
+    async fn get_remote_last_modified(&self, path: &str) -> Result<Option<DateTime<Utc>>, Error> {
+        self.exists_cache
+            .get_or_lookup(path, || self.storage.last_modified(path))
+            .await
+    }
+
+    async fn list_remote_dir(&self, path: &str) -> Result<Vec<RemoteFile>, Error> {
+        self.storage.list_dir(path).await
+    }
+
+    async fn delete_remote_path(&self, path: &str) -> Result<(), Error> {
+        self.exists_cache.invalidate(path);
+        self.storage.delete(path).await
+    }
+
+    async fn move_remote_path(&self, from: &str, to: &str) -> Result<(), Error> {
+        self.exists_cache.invalidate(from);
+        self.exists_cache.invalidate(to);
+        self.storage.rename(from, to).await
+    }
+
+    async fn submit_job(
+        &self,
+        var_source: Arc<dyn VarSource + Send + Sync>,
+        request: super::SubmitJobRequest,
+    ) -> Result<JobId, Error> {
+        let args = self.get_arguments(var_source.clone(), &request).await?;
+
+        let main_jar_path = match request.main_jar_path {
+            Some(p) => Some(p),
+            None => var_source
+                .get_environment_variable(&["spark_config", "kubernetes", "feathr_runtime_location"])
+                .await
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+        };
+
+        let mut jars: Vec<String> = main_jar_path.clone().into_iter().collect();
+        let mut files: Vec<String> = vec![];
+        for f in request.reference_files.into_iter() {
+            if f.ends_with(".jar") {
+                jars.push(f)
+            } else {
+                files.push(f)
+            }
+        }
+        let jars = self.multi_upload_or_get_url(&jars).await?;
+        let files = self.multi_upload_or_get_url(&files).await?;
+        let py_files = self.multi_upload_or_get_url(&request.python_files).await?;
+
+        let executable = match request.main_python_script.as_ref() {
+            Some(code) => {
+                self.write_remote_file(
+                    &self.get_remote_url(&format!(
+                        "feathr_pyspark_driver_{}_{}.py",
+                        request.name, request.job_key
+                    )),
+                    code.as_bytes(),
+                )
+                .await?
+            }
+            None => jars.first().cloned().ok_or_else(|| {
+                Error::InvalidConfig(
+                    "spark_config.kubernetes.feathr_runtime_location is not set".to_string(),
+                )
+            })?,
+        };
+        debug!("Main executable file: {}", executable);
+
+        let mut conf = request.configuration;
+        conf.extend(super::env_var_conf(&request.env_vars));
+        if main_jar_path.is_none() {
+            let v = match conf.get("spark.jars.packages") {
+                Some(v) => format!("{},{}", v, self.maven_artifact),
+                None => self.maven_artifact.clone(),
+            };
+            conf.insert("spark.jars.packages".to_string(), v);
+        }
+
+        let (args, secrets) = secret_arguments(&args);
+        let job = SparkRequest {
+            args,
+            class_name: if request.main_python_script.is_none() {
+                request.main_class_name
+            } else {
+                Default::default()
+            },
+            cluster_size: self.cluster_size.clone(),
+            conf,
+            file: executable,
+            files,
+            // The main jar is the executable
+            jars: jars.into_iter().skip(1).collect(),
+            name: format!("{}_{}", request.name, request.job_key),
+            py_files,
+            ..Default::default()
+        };
+        // Fits in a JSON number
+        let job_id = JobId(Uuid::new_v4().as_u128() as u64 & ((1 << 53) - 1));
+        let name = application_name(job_id);
+        let app = self.spark_application(job_id, &job, &request.job_tags, secrets.keys());
+        debug!("Job request: {:#?}", app);
+        self.create_secret(job_id, secrets).await?;
+        let app: DynamicObject = serde_json::from_value(app)?;
+        let created = match self.applications().create(&PostParams::default(), &app).await {
+            Ok(created) => created,
+            Err(e) => {
+                if let Err(e) = self.secrets().delete(&name, &DeleteParams::default()).await {
+                    warn!("Failed to delete secret {}, {}", name, e);
+                }
+                return Err(if is_not_found(&e) {
+                    Error::InvalidConfig(format!(
+                        "SparkApplication resource is not found in namespace {}, is the Spark Operator installed?",
+                        self.namespace
+                    ))
+                } else {
+                    e.into()
+                });
+            }
+        };
+        let owner = json!({
+            "metadata": {
+                "ownerReferences": [{
+                    "apiVersion": format!("{}/{}", SPARK_OPERATOR_GROUP, SPARK_OPERATOR_VERSION),
+                    "kind": "SparkApplication",
+                    "name": name,
+                    "uid": created.metadata.uid,
+                }],
+            },
+        });
+        // The job is already submitted, the secret is only left behind after the application is deleted
+        if let Err(e) = self
+            .secrets()
+            .patch(&name, &PatchParams::default(), &Patch::Merge(&owner))
+            .await
+        {
+            warn!("Failed to set the owner of secret {}, {}", name, e);
+        }
+        debug!("Job submitted as {}", name);
+        Ok(job_id)
+    }
+
+    async fn get_job_status(&self, job_id: JobId) -> Result<JobStatus, Error> {
+        let app = self.get_application(job_id).await?;
+        Ok(match self.get_driver_phase(&app).await? {
+            Some(phase) => pod_phase_status(&phase),
+            None => application_state_status(&app.status.application_state.state),
+        })
+    }
+
+    async fn cancel_job(&self, job_id: JobId) -> Result<(), Error> {
+        let app = self.get_application(job_id).await?;
+        // Deleting the driver pod makes the operator mark the application as failed, the
+        // application is deleted instead if the driver has not been created yet
+        let deleted = match app.status.driver_info.pod_name {
+            Some(pod) => self.pods().delete(&pod, &DeleteParams::default()).await.map(|_| ()),
+            None => self
+                .applications()
+                .delete(&application_name(job_id), &DeleteParams::default())
+                .await
+                .map(|_| ()),
+        };
+        match deleted {
+            Err(e) if !is_not_found(&e) => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn get_job_log(&self, job_id: JobId) -> Result<String, Error> {
+        let app = self.get_application(job_id).await?;
+        let pod = match app.status.driver_info.pod_name {
+            Some(pod) => pod,
+            None => return Ok(String::new()),
+        };
+        let params = LogParams {
+            tail_lines: Some(LOG_LINES),
+            ..Default::default()
+        };
+        match self.pods().logs(&pod, &params).await {
+            Ok(log) => Ok(log),
+            Err(e) if is_not_found(&e) => Ok(String::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn get_job_output_url(&self, job_id: JobId) -> Result<Option<String>, Error> {
+        let key = format!("{}{}", TAG_ANNOTATION_PREFIX, super::OUTPUT_PATH_TAG);
+        Ok(self
+            .get_application(job_id)
+            .await?
+            .metadata
+            .annotations
+            .get(&key)
+            .cloned())
+    }
+
+    async fn get_job_run_info(&self, job_id: JobId) -> Result<JobRunInfo, Error> {
+        let app = self.get_application(job_id).await?;
+        // Node pools are not visible to the job, pods are priced by the namespace
+        Ok(JobRunInfo {
+            job_id,
+            node_type: self.namespace.clone(),
+            num_nodes: 1 + app.spec.executor.instances.unwrap_or(self.cluster_size.num_executors as u32),
+            started_at: app.status.last_submission_attempt_time,
+            ended_at: app.status.termination_time,
+        })
+    }
+
+    fn get_remote_url(&self, filename: &str) -> String {
+        if let Some(url) = super::overridden_remote_url(filename) {
+            return url;
+        }
+        format!("{}/{}", self.workspace_dir, filename.trim_start_matches('/'))
+    }
+
+    fn is_url_on_storage(&self, url: &str) -> bool {
+        S3_SCHEMES.iter().any(|scheme| url.starts_with(scheme))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "minio".to_string(),
+            secret_access_key: "minio123".to_string(),
+            session_token: None,
+        }
+    }
+
+    fn client() -> SparkOperatorClient {
+        SparkOperatorClient::new(
+            kube::Config::new("https://k8s.example.com:6443".parse().unwrap()),
+            "apache/spark:v3.2.1",
+            "s3a://bucket/feathr/",
+            credentials(),
+        )
+        .unwrap()
+        .namespace("spark")
+        .s3_endpoint("http://minio.minio:9000")
+        .service_account("spark")
+    }
+
+    #[tokio::test]
+    async fn spark_application() {
+        let client = client();
+        assert_eq!(client.get_remote_url("job.conf"), "s3a://bucket/feathr/job.conf");
+        assert!(client.is_url_on_storage("s3a://bucket/feathr/job.conf"));
+        assert!(!client.is_url_on_storage("hdfs://namenode/feathr/job.conf"));
+        assert_eq!(client.applications().resource_url(), "/apis/sparkoperator.k8s.io/v1beta2/namespaces/spark/sparkapplications");
+
+        let (args, secrets) = secret_arguments(&[
+            "--s3-config".to_string(),
+            r#"{"s3_secret_key":"secret"}"#.to_string(),
+            "--input".to_string(),
+            "s3a://bucket/$(input)".to_string(),
+            "--join-config".to_string(),
+            "s3a://bucket/feathr/join.conf".to_string(),
+        ]);
+        assert_eq!(
+            args,
+            ["--s3-config", "$(FEATHR_ARG_1)", "--input", "s3a://bucket/$$(input)", "--join-config", "s3a://bucket/feathr/join.conf"]
+        );
+        assert_eq!(secrets["FEATHR_ARG_1"], r#"{"s3_secret_key":"secret"}"#);
+        assert_eq!(secrets.len(), 1);
+
+        let job = SparkRequest {
+            args,
+            class_name: "com.linkedin.feathr.offline.job.FeatureJoinJob".to_string(),
+            cluster_size: ClusterSize {
+                executor_memory: "8g".to_string(),
+                ..client.cluster_size.clone()
+            },
+            file: "s3a://bucket/feathr/feathr.jar".to_string(),
+            name: "join_1".to_string(),
+            ..Default::default()
+        };
+        let tags: HashMap<String, String> = [
+            (super::super::OUTPUT_PATH_TAG.to_string(), "s3a://bucket/output".to_string()),
+            ("invalid key".to_string(), "v".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let app = client.spark_application(JobId(42), &job, &tags, secrets.keys());
+        assert_eq!(app["metadata"]["name"], "feathr-42");
+        assert_eq!(
+            app["metadata"]["annotations"],
+            json!({ "feathr/output_path": "s3a://bucket/output", "feathr/job_name": "join_1" })
+        );
+        assert_eq!(app["spec"]["type"], "Scala");
+        assert_eq!(app["spec"]["mainClass"], "com.linkedin.feathr.offline.job.FeatureJoinJob");
+        assert_eq!(app["spec"]["arguments"][1], "$(FEATHR_ARG_1)");
+        assert_eq!(
+            app["spec"]["driver"]["env"],
+            json!([{
+                "name": "FEATHR_ARG_1",
+                "valueFrom": { "secretKeyRef": { "name": "feathr-42", "key": "FEATHR_ARG_1" } },
+            }])
+        );
+        assert_eq!(app["spec"]["driver"]["serviceAccount"], "spark");
+        assert_eq!(app["spec"]["driver"]["memory"], "2g");
+        assert_eq!(app["spec"]["executor"], json!({ "cores": 1, "instances": 2, "memory": "8g" }));
+        assert_eq!(app["spec"]["sparkConf"]["spark.hadoop.fs.s3a.endpoint"], "http://minio.minio:9000");
+        assert!(serde_json::from_value::<DynamicObject>(app).is_ok());
+        assert!(SparkOperatorClient::new(
+            kube::Config::new("https://k8s.example.com".parse().unwrap()),
+            "apache/spark:v3.2.1",
+            "/feathr",
+            credentials(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn application_status() {
+        let app: SparkApplication = serde_json::from_str(
+            r#"{
+                "apiVersion": "sparkoperator.k8s.io/v1beta2",
+                "kind": "SparkApplication",
+                "metadata": {"name": "feathr-42", "annotations": {"feathr/output_path": "s3a://bucket/output"}},
+                "spec": {"executor": {"instances": 4}},
+                "status": {
+                    "applicationState": {"state": "RUNNING"},
+                    "driverInfo": {"podName": "feathr-42-driver"},
+                    "lastSubmissionAttemptTime": "2022-07-01T10:00:00Z",
+                    "terminationTime": null
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(app.status.driver_info.pod_name.as_deref(), Some("feathr-42-driver"));
+        assert_eq!(app.spec.executor.instances, Some(4));
+        assert!(app.status.termination_time.is_none());
+        assert_eq!(application_state_status(&app.status.application_state.state), JobStatus::Running);
+
+        let new: SparkApplication = serde_json::from_str(r#"{"metadata": {"name": "feathr-43"}}"#).unwrap();
+        assert_eq!(application_state_status(&new.status.application_state.state), JobStatus::Provisioning);
+        assert_eq!(application_state_status("SUBMISSION_FAILED"), JobStatus::Failed);
+        assert_eq!(pod_phase_status("Pending"), JobStatus::Starting);
+        assert_eq!(pod_phase_status("Succeeded"), JobStatus::Success);
+        assert_eq!(pod_phase_status("Unknown"), JobStatus::Running);
+    }
+}
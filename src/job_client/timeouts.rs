@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+use reqwest::ClientBuilder;
+
+use crate::{
+    utils::{str_to_dur, to_std_duration},
+    Error, VarSource,
+};
+
+/**
+ * HTTP timeouts of the Spark provider and its storage, configured in `spark_config.timeouts`
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeouts {
+    /**
+     * Time to establish the connection
+     */
+    pub connect: Duration,
+    /**
+     * Time to complete an API call, e.g. submitting a job or polling its status
+     */
+    pub api: Duration,
+    /**
+     * Time to complete a storage operation, e.g. uploading a JAR or reading a file
+     */
+    pub transfer: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::seconds(30),
+            api: Duration::seconds(60),
+            transfer: Duration::minutes(30),
+        }
+    }
+}
+
+impl Timeouts {
+    /**
+     * Durations are in HOCON format, e.g. `30s` or `10m`, missing settings use default values
+     */
+    pub async fn from_var_source(var_source: &Arc<dyn VarSource + Send + Sync>) -> Result<Self, Error> {
+        let default = Self::default();
+        let get = |key: &'static str, default: Duration| async move {
+            match var_source
+                .get_environment_variable(&["spark_config", "timeouts", key])
+                .await
+            {
+                Ok(s) if !s.trim().is_empty() => str_to_dur(&s),
+                _ => Ok(default),
+            }
+        };
+        let ret = Self {
+            connect: get("connect", default.connect).await?,
+            api: get("api", default.api).await?,
+            transfer: get("transfer", default.transfer).await?,
+        };
+        ret.validate()?;
+        Ok(ret)
+    }
+
+    /**
+     * Fails if any of the timeouts is negative
+     */
+    pub fn validate(&self) -> Result<(), Error> {
+        for d in [self.connect, self.api, self.transfer] {
+            to_std_duration(d)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Client builder for API calls
+     */
+    pub(crate) fn api_client(&self) -> Result<ClientBuilder, Error> {
+        Ok(ClientBuilder::new()
+            .connect_timeout(to_std_duration(self.connect)?)
+            .timeout(to_std_duration(self.api)?))
+    }
+
+    /**
+     * Client builder for storage operations
+     */
+    pub(crate) fn transfer_client(&self) -> Result<ClientBuilder, Error> {
+        Ok(ClientBuilder::new()
+            .connect_timeout(to_std_duration(self.connect)?)
+            .timeout(to_std_duration(self.transfer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn timeouts_from_config() {
+        let var_source = crate::new_var_source(
+            r#"
+            spark_config:
+                timeouts:
+                    connect: '5s'
+                    transfer: '2h'
+            "#,
+        );
+        let timeouts = Timeouts::from_var_source(&var_source).await.unwrap();
+        assert_eq!(timeouts.connect, Duration::seconds(5));
+        assert_eq!(timeouts.api, Timeouts::default().api);
+        assert_eq!(timeouts.transfer, Duration::hours(2));
+
+        let var_source = crate::new_var_source("spark_config: {timeouts: {api: 'soon'}}");
+        assert!(matches!(
+            Timeouts::from_var_source(&var_source).await,
+            Err(Error::DurationError(_))
+        ));
+
+        let negative = Timeouts {
+            api: Duration::seconds(-1),
+            ..Default::default()
+        };
+        assert!(matches!(negative.validate(), Err(Error::DurationError(_))));
+        assert!(matches!(negative.api_client(), Err(Error::DurationError(_))));
+        assert!(negative.transfer_client().is_ok());
+    }
+}
@@ -1,4 +1,7 @@
 mod project;
+mod project_file;
+mod feature_conf;
+mod hocon;
 mod error;
 mod var_source;
 mod feature;
@@ -12,15 +15,31 @@ mod job_config;
 mod utils;
 mod job_client;
 mod registry_client;
-mod livy_client;
+/**
+ * Low level client of the Livy REST API, including the Azure Synapse flavor,
+ * for driving Spark batches and sessions directly with the same authenticators the Feathr client uses
+ */
+pub mod livy_client;
 mod client;
+mod online_client;
+mod online_admin;
+mod local_eval;
+mod lint;
+mod identifier;
+mod runtime;
+pub mod storage;
+/**
+ * Blocking API for applications that don't run an async runtime, enabled with the `blocking` feature
+ */
+#[cfg(feature = "blocking")]
+pub mod blocking;
 
 use log::trace;
 pub use livy_client::*;
 pub use project::{AnchorGroup, AnchorGroupBuilder, FeathrProject};
 pub use error::Error;
-pub use var_source::{VarSource, new_var_source, load_var_source, default_var_source};
-pub use feature::{AnchorFeature, DerivedFeature, Feature};
+pub use var_source::{VarSource, new_var_source, load_var_source, load_var_source_with_profile, default_var_source};
+pub use feature::{AnchorFeature, DerivedFeature, DerivedInput, Feature, FeatureDescriptor, FeatureFilter};
 pub use feature_builder::{AnchorFeatureBuilder, DerivedFeatureBuilder};
 pub use model::*;
 pub use source::*;
@@ -28,10 +47,16 @@ pub use observation::*;
 pub use feature_query::*;
 pub use materialization::*;
 pub use job_config::*;
-pub use utils::ExtDuration;
+pub use utils::{hocon_duration, ExtDuration, IntoDuration};
+pub use chrono::Duration;
 pub use job_client::*;
-pub use registry_client::{FeatureRegistry, FeathrApiClient};
+pub use registry_client::{FeatureRegistry, FeathrApiClient, LocalRegistry, LocalRegistryFormat, RegistryFeature, FeatureLineage, LineageEdge, LineageGraph, LineageNode, LineageNodeType, ProjectSaveSummary, FeatureConflict, FeatureDifference};
 pub use client::FeathrClient;
+pub use online_client::{OnlineClient, FeatureRow, FeatureValue, RedisEncoding};
+pub use online_admin::{OnlineStoreAdmin, OnlineTableInfo};
+pub use local_eval::{LocalSample, LocalValue};
+pub use lint::{LintResult, LintRule, LintRules};
+pub use runtime::{Runtime, TokioRuntime, set_runtime};
 
 /// Log if `Result` is an error
 pub(crate) trait Logged {
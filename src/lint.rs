@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::{Error, VarSource};
+
+// Spark SQL keywords that break feature expressions when used as names
+const DEFAULT_RESERVED_WORDS: &[&str] = &[
+    "all", "and", "as", "by", "case", "else", "end", "from", "group", "having", "in", "is",
+    "join", "not", "null", "or", "order", "select", "table", "then", "when", "where",
+];
+
+/**
+ * Naming conventions of feature names, configured in `project_config.feature_lint`
+ */
+#[derive(Clone, Debug, Serialize)]
+pub struct LintRules {
+    /**
+     * Feature names must start with this prefix, e.g. `f_`
+     */
+    pub prefix: Option<String>,
+    /**
+     * Feature names must be lowercase snake case
+     */
+    pub snake_case: bool,
+    pub max_length: Option<usize>,
+    /**
+     * Feature names must not be any of these words, case insensitive
+     */
+    pub reserved_words: Vec<String>,
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        Self {
+            prefix: None,
+            snake_case: true,
+            max_length: None,
+            reserved_words: DEFAULT_RESERVED_WORDS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl LintRules {
+    /**
+     * Load rules from `project_config.feature_lint`, missing settings use default values
+     */
+    pub async fn from_var_source(var_source: Arc<dyn VarSource + Send + Sync>) -> Result<Self, Error> {
+        let get = |key: &'static str| {
+            let var_source = var_source.clone();
+            async move {
+                var_source
+                    .get_environment_variable(&["project_config", "feature_lint", key])
+                    .await
+                    .ok()
+                    // Non-string values are serialized as YAML documents
+                    .map(|s| s.trim().trim_start_matches("---").trim().to_string())
+                    .filter(|s| !s.is_empty())
+            }
+        };
+        let default = Self::default();
+        Ok(Self {
+            prefix: get("prefix").await,
+            snake_case: match get("snake_case").await {
+                Some(s) => s
+                    .parse()
+                    .map_err(|_| Error::InvalidConfig(format!("Invalid feature_lint.snake_case '{}'", s)))?,
+                None => default.snake_case,
+            },
+            max_length: match get("max_length").await {
+                Some(s) => Some(
+                    s.parse()
+                        .map_err(|_| Error::InvalidConfig(format!("Invalid feature_lint.max_length '{}'", s)))?,
+                ),
+                None => default.max_length,
+            },
+            reserved_words: match get("reserved_words").await {
+                Some(s) => s
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_lowercase)
+                    .collect(),
+                None => default.reserved_words,
+            },
+        })
+    }
+
+    /**
+     * Check the feature name against the rules
+     */
+    pub fn lint(&self, name: &str) -> Vec<LintResult> {
+        let mut ret = vec![];
+        let mut violate = |rule: LintRule, message: String| {
+            ret.push(LintResult {
+                feature: name.to_string(),
+                rule,
+                message,
+            })
+        };
+        if let Some(prefix) = &self.prefix {
+            if !name.starts_with(prefix.as_str()) {
+                violate(LintRule::Prefix, format!("Feature name must start with '{}'", prefix));
+            }
+        }
+        if self.snake_case {
+            let re = Regex::new(r"^[a-z][a-z0-9]*(_[a-z0-9]+)*$").unwrap();
+            if !re.is_match(name) {
+                violate(LintRule::SnakeCase, "Feature name must be lowercase snake case".to_string());
+            }
+        }
+        if let Some(max_length) = self.max_length {
+            if name.len() > max_length {
+                violate(
+                    LintRule::MaxLength,
+                    format!("Feature name is longer than {} characters", max_length),
+                );
+            }
+        }
+        if self.reserved_words.contains(&name.to_lowercase()) {
+            violate(LintRule::ReservedWord, format!("'{}' is a reserved word", name));
+        }
+        ret
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    Prefix,
+    SnakeCase,
+    MaxLength,
+    ReservedWord,
+    /**
+     * Anchor feature without transformation, the source column with the same name is used
+     */
+    ImplicitTransformation,
+}
+
+/**
+ * A violation of the naming conventions, serializable so CI can consume the results
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LintResult {
+    pub feature: String,
+    pub rule: LintRule,
+    pub message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn lint_feature_names() {
+        let rules = LintRules::from_var_source(crate::new_var_source(
+            r#"
+            project_config:
+                feature_lint:
+                    prefix: 'f_'
+                    max_length: 16
+                    reserved_words: 'select, total'
+            "#,
+        ))
+        .await
+        .unwrap();
+        assert!(rules.snake_case);
+        assert!(rules.lint("f_trip_distance").is_empty());
+        let rules_of = |name: &str| -> Vec<LintRule> { rules.lint(name).into_iter().map(|r| r.rule).collect() };
+        assert_eq!(rules_of("tripDistance"), vec![LintRule::Prefix, LintRule::SnakeCase]);
+        assert_eq!(rules_of("f_trip_distance_in_miles"), vec![LintRule::MaxLength]);
+        assert_eq!(rules_of("f_trip_"), vec![LintRule::SnakeCase]);
+        assert_eq!(rules_of("TOTAL"), vec![LintRule::Prefix, LintRule::SnakeCase, LintRule::ReservedWord]);
+        assert_eq!(
+            serde_json::to_value(&rules.lint("f_A")[0]).unwrap(),
+            serde_json::json!({"feature": "f_A", "rule": "snake_case", "message": "Feature name must be lowercase snake case"})
+        );
+    }
+}
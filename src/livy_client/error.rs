@@ -21,7 +21,10 @@ pub enum LivyClientError {
     AzureSynapseError(#[from] super::azure_synapse::AzureSynapseError),
 
     #[error("Job {0} is not in valid state")]
-    InvalidJobState(u64)
+    InvalidJobState(u64),
+
+    #[error("Authentication failed: {0}")]
+    AuthenticationError(String),
 }
 
 impl<Guard> From<PoisonError<Guard>> for LivyClientError {
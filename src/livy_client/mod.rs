@@ -1,12 +1,13 @@
 mod error;
 mod models;
+mod spnego;
 
 mod azure_synapse;
 
 use async_trait::async_trait;
 use log::{debug, trace};
 use reqwest::{RequestBuilder, Response};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::fmt::Debug;
 
 pub use error::{LivyClientError, Result};
@@ -118,7 +119,79 @@ impl Authenticator for BasicAuthenticator {
 }
 
 /**
- * Livy API client
+ * Authentication methods supported by generic Livy endpoints and WebHDFS
+ */
+#[derive(Clone)]
+pub enum LivyAuthenticator {
+    None,
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    Token(String),
+    /**
+     * Kerberos/SPNEGO, requires GSSAPI library and a valid ticket cache, `host` is the host part of the service principal
+     */
+    Kerberos {
+        service: String,
+        host: String,
+    },
+}
+
+impl LivyAuthenticator {
+    /**
+     * Kerberos authentication with the `HTTP` service principal of the host in the URL
+     */
+    pub fn kerberos(url: &str) -> Result<Self> {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .ok_or_else(|| LivyClientError::AuthenticationError(format!("Invalid URL {}", url)))?;
+        Ok(Self::Kerberos {
+            service: "HTTP".to_string(),
+            host,
+        })
+    }
+}
+
+impl std::fmt::Debug for LivyAuthenticator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Self::Token(_) => write!(f, "Token"),
+            Self::Kerberos { service, host } => f
+                .debug_struct("Kerberos")
+                .field("service", service)
+                .field("host", host)
+                .finish(),
+        }
+    }
+}
+
+#[async_trait]
+impl Authenticator for LivyAuthenticator {
+    async fn authenticate(
+        &self,
+        builder: RequestBuilder,
+    ) -> std::result::Result<RequestBuilder, LivyClientError> {
+        Ok(match self {
+            Self::None => builder,
+            Self::Basic { username, password } => builder.basic_auth(username, password.as_ref()),
+            Self::Token(token) => builder.bearer_auth(token),
+            Self::Kerberos { service, host } => builder.header(
+                reqwest::header::AUTHORIZATION,
+                format!("Negotiate {}", spnego::negotiate_token(service, host).await?),
+            ),
+        })
+    }
+}
+
+/**
+ * Livy API client, use `AzureSynapseClientBuilder` to create one for Azure Synapse
  */
 #[derive(Debug)]
 pub struct LivyClient<T: Authenticator> {
@@ -128,35 +201,28 @@ pub struct LivyClient<T: Authenticator> {
     authenticator: T,
 }
 
-impl<T: Authenticator> LivyClient<T> {
+impl LivyClient<DummyAuthenticator> {
     /**
-     * Create a new Livy API Client
+     * Create a new Livy API Client without authentication
      * User should provide a customized reqwest::Client, or just use default one.
      * Without `log_base` set correctly, the client cannot fetch driver logs from the server,
      * but other functions are not affected.
      */
-    pub fn new(
-        client: reqwest::Client,
-        url_base: &str,
-        log_base: &str,
-    ) -> LivyClient<DummyAuthenticator> {
-        LivyClient {
-            client,
-            url_base: Self::remove_trailing_slash(url_base),
-            log_base: Self::remove_trailing_slash(log_base),
-            authenticator: DummyAuthenticator,
-        }
+    pub fn new(client: reqwest::Client, url_base: &str, log_base: &str) -> Self {
+        Self::with_authenticator(client, url_base, log_base, DummyAuthenticator)
     }
+}
 
+impl<T: Authenticator> LivyClient<T> {
     /**
-     * Create Livy API client with customized authenticator
+     * Create Livy API client with customized authenticator, e.g. `LivyAuthenticator`
      */
-    pub fn with_authenticator<A: Authenticator>(
+    pub fn with_authenticator(
         client: reqwest::Client,
         url_base: &str,
         log_base: &str,
-        authenticator: A,
-    ) -> LivyClient<A> {
+        authenticator: T,
+    ) -> Self {
         LivyClient {
             client,
             url_base: Self::remove_trailing_slash(url_base),
@@ -165,6 +231,23 @@ impl<T: Authenticator> LivyClient<T> {
         }
     }
 
+    /**
+     * Base URL of the Livy API, e.g. `http://livy:8998`
+     */
+    pub fn url_base(&self) -> &str {
+        &self.url_base
+    }
+
+    /**
+     * Replace the HTTP client, e.g. to change timeouts
+     */
+    pub(crate) fn set_http_client(&mut self, client: reqwest::Client) {
+        self.client = client;
+    }
+
+    /**
+     * All interactive sessions on the server
+     */
     pub async fn get_sessions(&self) -> Result<Vec<SparkJob>> {
         let mut ret: Vec<SparkJob> = vec![];
         let mut from = 0usize;
@@ -203,6 +286,9 @@ impl<T: Authenticator> LivyClient<T> {
         .await
     }
 
+    /**
+     * Kill the session and delete it from the server
+     */
     pub async fn cancel_session(&self, id: u64) -> Result<()> {
         self.delete(&format!("{}/sessions/{}?detailed=true", self.url_base, id))
             .await
@@ -289,6 +375,9 @@ impl<T: Authenticator> LivyClient<T> {
         self.get_driver_log(id, &app_id, "stderr").await
     }
 
+    /**
+     * All batches on the server
+     */
     pub async fn get_batch_jobs(&self) -> Result<Vec<SparkJob>> {
         let mut ret: Vec<SparkJob> = vec![];
         let mut from = 0usize;
@@ -311,6 +400,32 @@ impl<T: Authenticator> LivyClient<T> {
         Ok(ret)
     }
 
+    /**
+     * At most `limit` batches with the largest ids, newest first
+     */
+    pub async fn get_recent_batch_jobs(&self, limit: usize) -> Result<Vec<SparkJob>> {
+        let url = |from: usize| {
+            format!(
+                "{}/batches?from={}&size={}&detailed=true",
+                self.url_base, from, limit
+            )
+        };
+        let mut resp = self.get::<(), SparkJobCollection>(&url(0), None).await?;
+        // Batches are listed in submission order, fetch the last page if there are more
+        if resp.total > limit {
+            resp = self
+                .get::<(), SparkJobCollection>(&url(resp.total - limit), None)
+                .await?;
+        }
+        let mut ret = resp.sessions;
+        ret.sort_by_key(|job| std::cmp::Reverse(job.id));
+        ret.truncate(limit);
+        Ok(ret)
+    }
+
+    /**
+     * Submit a batch, `file` of the request is the main jar or Python script
+     */
     pub async fn create_batch_job(&self, job: SparkRequest) -> Result<SparkJob> {
         self.post(
             &format!("{}/batches?detailed=true", self.url_base),
@@ -327,11 +442,44 @@ impl<T: Authenticator> LivyClient<T> {
         .await
     }
 
+    /**
+     * State of the batch, lighter than `get_batch_job`
+     */
+    pub async fn get_batch_job_state(&self, id: u64) -> Result<LivyStates> {
+        #[derive(Debug, Deserialize)]
+        struct Response {
+            state: LivyStates,
+        }
+        Ok(self
+            .get::<(), Response>(&format!("{}/batches/{}/state", self.url_base, id), None)
+            .await?
+            .state)
+    }
+
+    /**
+     * Kill the batch and delete it from the server
+     */
     pub async fn cancel_batch_job(&self, id: u64) -> Result<()> {
         self.delete(&format!("{}/batches/{}?detailed=true", self.url_base, id))
             .await
     }
 
+    /**
+     * Get the log lines kept by the Livy server, works with generic Livy endpoints
+     */
+    pub async fn get_batch_job_log(&self, id: u64, from: usize, size: usize) -> Result<Vec<String>> {
+        Ok(self
+            .get::<(), SparkJobLog>(
+                &format!(
+                    "{}/batches/{}/log?from={}&size={}",
+                    self.url_base, id, from, size
+                ),
+                None,
+            )
+            .await?
+            .log)
+    }
+
     /**
      * NOTE: Livy outputs PySpark program error to StdOut instead of StdErr,
      * and the StdErr is flooded by lots of Spark logs, basically useless.
@@ -386,9 +534,11 @@ impl<T: Authenticator> LivyClient<T> {
     }
 
     async fn delete(&self, url: &str) -> Result<()> {
+        debug!("URL: {}", url);
+        debug!("Method: DELETE");
         let builder = self
             .authenticator
-            .authenticate(self.client.delete(format!("{}{}", self.url_base, url)))
+            .authenticate(self.client.delete(url))
             .await?;
         get_response(url, builder.send().await?).await?;
         Ok(())
@@ -414,6 +564,20 @@ mod tests {
             .unwrap()
     }
 
+    #[test]
+    fn client_urls() {
+        let client = LivyClient::new(reqwest::Client::new(), "http://livy:8998/", "");
+        assert_eq!(client.url_base(), "http://livy:8998");
+        let client = LivyClient::with_authenticator(
+            reqwest::Client::new(),
+            "http://livy:8998",
+            "",
+            LivyAuthenticator::Token("t0k3n".to_string()),
+        );
+        assert_eq!(client.url_base(), "http://livy:8998");
+        assert!(!format!("{:?}", client).contains("t0k3n"));
+    }
+
     #[ignore]
     #[tokio::test]
     async fn get_sessions() {
@@ -134,6 +134,8 @@ pub struct SparkRequest {
     pub jars: Vec<String>,
     pub name: String,
     #[serde(skip_serializing_if = "is_default")]
+    pub proxy_user: String,
+    #[serde(skip_serializing_if = "is_default")]
     pub py_files: Vec<String>,
     #[serde(skip_serializing_if = "is_default")]
     pub tags: HashMap<String, String>,
@@ -323,6 +325,15 @@ pub struct SparkJobDefinitionResource {
     pub resource_type: String,
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SparkJobLog {
+    pub id: u64,
+    pub from: usize,
+    pub total: usize,
+    pub log: Vec<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SparkJobCollection {
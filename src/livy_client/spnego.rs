@@ -0,0 +1,269 @@
+// Minimal SPNEGO token generation with the system GSSAPI library.
+// The library is loaded at runtime so the crate builds and runs without Kerberos installed,
+// a valid ticket cache (e.g. from `kinit`) is required when the token is requested.
+use super::{LivyClientError, Result};
+
+#[cfg(unix)]
+mod ffi {
+    use std::{
+        ffi::{c_void, CString},
+        ptr::null_mut,
+        sync::OnceLock,
+    };
+
+    use super::{LivyClientError, Result};
+
+    #[repr(C)]
+    struct GssBuffer {
+        length: usize,
+        value: *mut c_void,
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[repr(C)]
+    struct GssOid {
+        length: u32,
+        elements: *const c_void,
+    }
+
+    // Apple's GSS.framework declares `gss_OID_desc` with `#pragma pack(2)`
+    #[cfg(target_os = "macos")]
+    #[repr(C, packed(2))]
+    struct GssOid {
+        length: u32,
+        elements: *const c_void,
+    }
+
+    type ImportName =
+        unsafe extern "C" fn(*mut u32, *const GssBuffer, *const GssOid, *mut *mut c_void) -> u32;
+    #[allow(clippy::type_complexity)]
+    type InitSecContext = unsafe extern "C" fn(
+        *mut u32,
+        *mut c_void,
+        *mut *mut c_void,
+        *mut c_void,
+        *const GssOid,
+        u32,
+        u32,
+        *mut c_void,
+        *const GssBuffer,
+        *mut *mut GssOid,
+        *mut GssBuffer,
+        *mut u32,
+        *mut u32,
+    ) -> u32;
+    type ReleaseBuffer = unsafe extern "C" fn(*mut u32, *mut GssBuffer) -> u32;
+    type ReleaseName = unsafe extern "C" fn(*mut u32, *mut *mut c_void) -> u32;
+    type DeleteSecContext = unsafe extern "C" fn(*mut u32, *mut *mut c_void, *mut GssBuffer) -> u32;
+
+    /// Candidate libraries with the symbol version their exports are bound to, the function
+    /// signatures below are the RFC 2744 C bindings that all of them implement.
+    /// Only MIT Kerberos version its symbols, for the others the soname is the only ABI check.
+    #[cfg(not(target_os = "macos"))]
+    const LIBRARIES: &[(&str, Option<&str>)] = &[
+        ("libgssapi_krb5.so.2", Some("gssapi_krb5_2_MIT")),
+        ("libgssapi.so.3", None),
+    ];
+    #[cfg(target_os = "macos")]
+    const LIBRARIES: &[(&str, Option<&str>)] =
+        &[("/System/Library/Frameworks/GSS.framework/GSS", None)];
+    // 1.2.840.113554.1.2.1.4
+    const NT_HOSTBASED_SERVICE: &[u8] = b"\x2a\x86\x48\x86\xf7\x12\x01\x02\x01\x04";
+    // 1.3.6.1.5.5.2
+    const SPNEGO_MECHANISM: &[u8] = b"\x2b\x06\x01\x05\x05\x02";
+    const GSS_C_MUTUAL_FLAG: u32 = 2;
+    const GSS_C_SEQUENCE_FLAG: u32 = 8;
+
+    fn error(msg: String) -> LivyClientError {
+        LivyClientError::AuthenticationError(msg)
+    }
+
+    fn is_error(major: u32) -> bool {
+        // Calling and routine errors are in the high 16 bits
+        major >> 16 != 0
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    unsafe fn lookup(lib: *mut c_void, name: &CString, version: Option<&str>) -> *mut c_void {
+        match version {
+            Some(version) => {
+                let cversion = CString::new(version).unwrap();
+                libc::dlvsym(lib, name.as_ptr(), cversion.as_ptr())
+            }
+            None => libc::dlsym(lib, name.as_ptr()),
+        }
+    }
+
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    unsafe fn lookup(lib: *mut c_void, name: &CString, _version: Option<&str>) -> *mut c_void {
+        libc::dlsym(lib, name.as_ptr())
+    }
+
+    /**
+     * Resolves `name` from `lib` as a function pointer of type `T`
+     *
+     * # Safety
+     * `lib` must be a live handle returned by `dlopen` and `T` must be the `extern "C" fn`
+     * type matching the C prototype of `name`.
+     */
+    unsafe fn symbol<T>(lib: *mut c_void, name: &str, version: Option<&str>) -> Result<T> {
+        let cname = CString::new(name).unwrap();
+        let sym = lookup(lib, &cname, version);
+        if sym.is_null() {
+            return Err(error(match version {
+                Some(version) => format!("GSSAPI function {}@{} not found", name, version),
+                None => format!("GSSAPI function {} not found", name),
+            }));
+        }
+        // SAFETY: `sym` is a non-null function address and `T` is a pointer-sized fn type
+        // matching its prototype per the caller's contract.
+        Ok(std::mem::transmute_copy(&sym))
+    }
+
+    /**
+     * Functions of the GSSAPI library, the library is loaded once and never `dlclose`d as
+     * unloading Kerberos libraries is not supported by all implementations
+     */
+    struct Gss {
+        import_name: ImportName,
+        init_sec_context: InitSecContext,
+        release_buffer: ReleaseBuffer,
+        release_name: ReleaseName,
+        delete_sec_context: DeleteSecContext,
+    }
+
+    static GSS: OnceLock<std::result::Result<Gss, String>> = OnceLock::new();
+
+    fn load() -> Result<Gss> {
+        // SAFETY: the library names are NUL-terminated strings that outlive the `dlopen` calls,
+        // and each symbol is resolved as the fn type of its RFC 2744 prototype.
+        unsafe {
+            let (lib, version) = LIBRARIES
+                .iter()
+                .map(|(l, version)| {
+                    let name = CString::new(*l).unwrap();
+                    (
+                        libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL),
+                        *version,
+                    )
+                })
+                .find(|(l, _)| !l.is_null())
+                .ok_or_else(|| error("GSSAPI library not found".to_string()))?;
+            // Resolve all functions up front so a partial or mismatched library fails cleanly
+            // before any GSSAPI object is allocated.
+            Ok(Gss {
+                import_name: symbol(lib, "gss_import_name", version)?,
+                init_sec_context: symbol(lib, "gss_init_sec_context", version)?,
+                release_buffer: symbol(lib, "gss_release_buffer", version)?,
+                release_name: symbol(lib, "gss_release_name", version)?,
+                delete_sec_context: symbol(lib, "gss_delete_sec_context", version)?,
+            })
+        }
+    }
+
+    fn gss() -> Result<&'static Gss> {
+        GSS.get_or_init(|| {
+            load().map_err(|e| match e {
+                LivyClientError::AuthenticationError(msg) => msg,
+                e => e.to_string(),
+            })
+        })
+            .as_ref()
+            .map_err(|e| error(e.to_owned()))
+    }
+
+    pub(super) fn init_token(service: &str, host: &str) -> Result<Vec<u8>> {
+        let Gss {
+            import_name,
+            init_sec_context,
+            release_buffer,
+            release_name,
+            delete_sec_context,
+        } = gss()?;
+        // SAFETY: every pointer handed to the GSSAPI functions below either points to a live
+        // local (`minor`, `name_buf`, `name_type`, `mechanism`, `output`, `target`, `context`)
+        // or is NULL where RFC 2744 allows the default (credentials, channel bindings, actual
+        // mechanism, flags and time out-params). `output` is only read when the call succeeded
+        // and reported a non-null buffer of `output.length` bytes, and all GSSAPI-owned objects
+        // are released exactly once before returning.
+        unsafe {
+            let mut minor = 0u32;
+            let principal = format!("{}@{}", service, host);
+            let name_buf = GssBuffer {
+                length: principal.len(),
+                value: principal.as_ptr() as *mut c_void,
+            };
+            let name_type = GssOid {
+                length: NT_HOSTBASED_SERVICE.len() as u32,
+                elements: NT_HOSTBASED_SERVICE.as_ptr() as *const c_void,
+            };
+            let mut target = null_mut();
+            let major = import_name(&mut minor, &name_buf, &name_type, &mut target);
+            if is_error(major) {
+                return Err(error(format!(
+                    "Failed to import service name {}, status {}/{}",
+                    principal, major, minor
+                )));
+            }
+
+            let mechanism = GssOid {
+                length: SPNEGO_MECHANISM.len() as u32,
+                elements: SPNEGO_MECHANISM.as_ptr() as *const c_void,
+            };
+            let mut context = null_mut();
+            let mut output = GssBuffer {
+                length: 0,
+                value: null_mut(),
+            };
+            let major = init_sec_context(
+                &mut minor,
+                null_mut(),
+                &mut context,
+                target,
+                &mechanism,
+                GSS_C_MUTUAL_FLAG | GSS_C_SEQUENCE_FLAG,
+                0,
+                null_mut(),
+                std::ptr::null(),
+                null_mut(),
+                &mut output,
+                null_mut(),
+                null_mut(),
+            );
+            let token = if is_error(major) || output.value.is_null() {
+                Err(error(format!(
+                    "Failed to initialize security context for {}, status {}/{}, check if there is a valid Kerberos ticket",
+                    principal, major, minor
+                )))
+            } else {
+                Ok(std::slice::from_raw_parts(output.value as *const u8, output.length).to_vec())
+            };
+            release_buffer(&mut minor, &mut output);
+            delete_sec_context(&mut minor, &mut context, null_mut());
+            release_name(&mut minor, &mut target);
+            token
+        }
+    }
+}
+
+/**
+ * Returns the base64 encoded SPNEGO token for `Authorization: Negotiate` header
+ */
+pub(crate) async fn negotiate_token(service: &str, host: &str) -> Result<String> {
+    #[cfg(unix)]
+    {
+        let service = service.to_string();
+        let host = host.to_string();
+        let token = crate::runtime::spawn_blocking(move || ffi::init_token(&service, &host))
+            .await
+            .map_err(|e| LivyClientError::AuthenticationError(e.to_string()))??;
+        Ok(base64::encode(token))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (service, host);
+        Err(LivyClientError::AuthenticationError(
+            "Kerberos authentication is only supported on Unix".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,1021 @@
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use parquet::{
+    basic::{ConvertedType, LogicalType, Repetition, Type as PhysicalType},
+    file::reader::{FileReader, SerializedFileReader},
+    record::Field,
+    schema::types::Type as ParquetType,
+};
+
+use crate::{Aggregation, Error, FeatureType, Transformation, ValueType};
+
+/**
+ * Value produced by local evaluation
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum LocalValue {
+    Null,
+    Bool(bool),
+    Long(i64),
+    Double(f64),
+    String(String),
+}
+
+impl LocalValue {
+    fn parse(s: &str) -> Self {
+        let s = s.trim();
+        if s.is_empty() {
+            LocalValue::Null
+        } else if let Ok(v) = s.parse::<i64>() {
+            LocalValue::Long(v)
+        } else if let Ok(v) = s.parse::<f64>() {
+            LocalValue::Double(v)
+        } else if s.eq_ignore_ascii_case("true") {
+            LocalValue::Bool(true)
+        } else if s.eq_ignore_ascii_case("false") {
+            LocalValue::Bool(false)
+        } else {
+            LocalValue::String(s.to_string())
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            LocalValue::Null => "NULL",
+            LocalValue::Bool(_) => "BOOLEAN",
+            LocalValue::Long(_) => "LONG",
+            LocalValue::Double(_) => "DOUBLE",
+            LocalValue::String(_) => "STRING",
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            LocalValue::Long(v) => Some(*v as f64),
+            LocalValue::Double(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /**
+     * Check if the value can be stored as the feature value type
+     */
+    fn is_compatible(&self, value_type: ValueType) -> bool {
+        match (self, value_type) {
+            (LocalValue::Null, _) | (_, ValueType::UNSPECIFIED) => true,
+            (LocalValue::Bool(_), ValueType::BOOL) => true,
+            (LocalValue::Long(v), ValueType::INT32) => i32::try_from(*v).is_ok(),
+            (LocalValue::Long(_), ValueType::INT64) => true,
+            (LocalValue::Long(_) | LocalValue::Double(_), ValueType::FLOAT | ValueType::DOUBLE) => true,
+            (LocalValue::String(_), ValueType::STRING | ValueType::BYTES) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Display for LocalValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LocalValue::Null => write!(f, "NULL"),
+            LocalValue::Bool(v) => write!(f, "{}", v),
+            LocalValue::Long(v) => write!(f, "{}", v),
+            LocalValue::Double(v) => write!(f, "{}", v),
+            LocalValue::String(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/**
+ * Small data sample to evaluate feature transformations locally, a quick check before submitting a Spark job.
+ *
+ * Only a subset of Spark SQL is supported:
+ * - Literals, column references, arithmetic, comparison, `AND`/`OR`/`NOT`, `IS [NOT] NULL`, `CASE WHEN` and `CAST`
+ * - Functions `coalesce`, `if`, `abs`, `round`, `floor`, `ceil`, `lower`, `upper`, `trim`, `length` and `concat`
+ * - Values are NULL, BOOLEAN, LONG, DOUBLE or STRING, CSV column types are inferred from the values,
+ *   Parquet dates and timestamps are read as strings, nested and binary Parquet columns are skipped
+ *
+ * Other constructs, e.g. aggregations, UDFs, arrays, maps, dates and implicit string to number coercion,
+ * are rejected or may differ from Spark, LONG overflow is reported as an error instead of wrapping around.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LocalSample {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<LocalValue>>,
+}
+
+impl LocalSample {
+    /**
+     * Parse CSV content with header, column types are inferred from the values
+     */
+    pub fn from_csv(content: &str) -> Result<Self, Error> {
+        let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+        let columns: Vec<String> = split_csv_line(lines.next().unwrap_or_default())?
+            .into_iter()
+            .map(|c| c.trim().to_string())
+            .collect();
+        let rows = lines
+            .enumerate()
+            .map(|(n, line)| {
+                let fields = split_csv_line(line)?;
+                if fields.len() != columns.len() {
+                    return Err(Error::LocalEvaluationError(format!(
+                        "Row {} has {} fields, expected {}",
+                        n + 1,
+                        fields.len(),
+                        columns.len()
+                    )));
+                }
+                Ok(fields.iter().map(|f| LocalValue::parse(f)).collect())
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns, rows })
+    }
+
+    pub async fn load_csv<T: AsRef<Path>>(path: T) -> Result<Self, Error> {
+        Self::from_csv(&crate::runtime::read_to_string(path).await?)
+    }
+
+    /**
+     * Read all rows of the Parquet file content, column types are taken from the Parquet schema
+     */
+    pub fn from_parquet(content: Bytes) -> Result<Self, Error> {
+        let parquet_error = |e: parquet::errors::ParquetError| {
+            Error::LocalEvaluationError(format!("Invalid Parquet sample, {}", e))
+        };
+        let reader = SerializedFileReader::new(content).map_err(parquet_error)?;
+        let schema = reader.metadata().file_metadata().schema_descr_ptr();
+        let columns: Vec<String> = schema
+            .root_schema()
+            .get_fields()
+            .iter()
+            .filter(|f| is_scalar_column(f))
+            .map(|f| f.name().to_string())
+            .collect();
+        let rows = reader
+            .get_row_iter(None)
+            .map_err(parquet_error)?
+            .map(|row| {
+                let row = row.map_err(parquet_error)?;
+                let values: HashMap<&str, &Field> =
+                    row.get_column_iter().map(|(name, field)| (name.as_str(), field)).collect();
+                Ok(columns
+                    .iter()
+                    .map(|c| values.get(c.as_str()).map(|f| parquet_value(f)).unwrap_or(LocalValue::Null))
+                    .collect())
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self { columns, rows })
+    }
+
+    pub async fn load_parquet<T: AsRef<Path>>(path: T) -> Result<Self, Error> {
+        Self::from_parquet(crate::runtime::read_file(path).await?.into())
+    }
+
+    /**
+     * Load the sample file, `.parquet` files are read as Parquet, other files as CSV
+     */
+    pub async fn load<T: AsRef<Path>>(path: T) -> Result<Self, Error> {
+        let is_parquet = path
+            .as_ref()
+            .extension()
+            .map(|e| e.eq_ignore_ascii_case("parquet"))
+            .unwrap_or_default();
+        if is_parquet {
+            Self::load_parquet(path).await
+        } else {
+            Self::load_csv(path).await
+        }
+    }
+
+    /**
+     * Evaluate the SQL expression on every row
+     */
+    pub fn evaluate(&self, expr: &str) -> Result<Vec<LocalValue>, Error> {
+        let expr = Parser::new(expr)?.parse()?;
+        self.rows
+            .iter()
+            .map(|row| {
+                let row: HashMap<&str, &LocalValue> = self
+                    .columns
+                    .iter()
+                    .map(String::as_str)
+                    .zip(row.iter())
+                    .collect();
+                expr.eval(&row)
+            })
+            .collect()
+    }
+}
+
+/**
+ * Nested and binary columns can't be referenced by the supported expressions
+ */
+fn is_scalar_column(t: &ParquetType) -> bool {
+    let info = t.get_basic_info();
+    t.is_primitive()
+        && info.repetition() != Repetition::REPEATED
+        && (t.get_physical_type() != PhysicalType::BYTE_ARRAY
+            || info.converted_type() == ConvertedType::UTF8
+            || matches!(info.logical_type(), Some(LogicalType::String | LogicalType::Json)))
+}
+
+/**
+ * Dates and timestamps are converted to strings in the format of Spark `cast(... as string)`
+ */
+fn parquet_value(field: &Field) -> LocalValue {
+    let timestamp = |t: Option<DateTime<Utc>>| {
+        t.map(|t| LocalValue::String(t.format("%Y-%m-%d %H:%M:%S%.f").to_string()))
+            .unwrap_or(LocalValue::Null)
+    };
+    match field {
+        Field::Bool(v) => LocalValue::Bool(*v),
+        Field::Byte(v) => LocalValue::Long(*v as i64),
+        Field::Short(v) => LocalValue::Long(*v as i64),
+        Field::Int(v) => LocalValue::Long(*v as i64),
+        Field::Long(v) => LocalValue::Long(*v),
+        Field::UByte(v) => LocalValue::Long(*v as i64),
+        Field::UShort(v) => LocalValue::Long(*v as i64),
+        Field::UInt(v) => LocalValue::Long(*v as i64),
+        Field::ULong(v) => i64::try_from(*v)
+            .map(LocalValue::Long)
+            .unwrap_or(LocalValue::Double(*v as f64)),
+        Field::Float16(v) => LocalValue::Double(v.to_f64()),
+        Field::Float(v) => LocalValue::Double(*v as f64),
+        Field::Double(v) => LocalValue::Double(*v),
+        Field::Decimal(_) => field
+            .to_string()
+            .parse()
+            .map(LocalValue::Double)
+            .unwrap_or(LocalValue::Null),
+        Field::Str(s) => LocalValue::String(s.to_owned()),
+        Field::Date(days) => DateTime::from_timestamp(*days as i64 * 86400, 0)
+            .map(|d| LocalValue::String(d.format("%Y-%m-%d").to_string()))
+            .unwrap_or(LocalValue::Null),
+        Field::TimestampMillis(v) => timestamp(DateTime::from_timestamp_millis(*v)),
+        Field::TimestampMicros(v) => timestamp(DateTime::from_timestamp_micros(*v)),
+        _ => LocalValue::Null,
+    }
+}
+
+fn split_csv_line(line: &str) -> Result<Vec<String>, Error> {
+    let mut fields = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                current.push('"');
+            }
+            ('"', _) => in_quotes = !in_quotes,
+            (',', false) => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(Error::LocalEvaluationError(format!(
+            "Unterminated quote in line '{}'",
+            line
+        )));
+    }
+    fields.push(current);
+    Ok(fields)
+}
+
+/**
+ * Row level expression of the transformation, aggregations and UDFs need the Spark runtime
+ */
+pub(crate) fn local_expression(transform: &Transformation) -> Option<&str> {
+    match transform {
+        Transformation::Expression { def } => Some(&def.sql_expr),
+        Transformation::WindowAgg {
+            def_expr,
+            agg_func: None | Some(Aggregation::NOP),
+            ..
+        } => Some(def_expr),
+        _ => None,
+    }
+}
+
+/**
+ * Columns referenced by the row level expression of the transformation,
+ * `None` if the transformation has no such expression or it cannot be parsed locally
+ */
+pub(crate) fn referenced_columns(transform: &Transformation) -> Option<Vec<String>> {
+    let expr = match transform {
+        Transformation::Expression { def } => &def.sql_expr,
+        Transformation::WindowAgg { def_expr, .. } => def_expr,
+        _ => return None,
+    };
+    let mut columns = vec![];
+    Parser::new(expr).ok()?.parse().ok()?.collect_columns(&mut columns);
+    columns.sort();
+    columns.dedup();
+    Some(columns)
+}
+
+/**
+ * Evaluate the expression of the feature transformation locally and check the results against the feature type
+ */
+pub(crate) fn evaluate_feature(
+    name: &str,
+    expr: Option<&str>,
+    feature_type: &FeatureType,
+    sample: &LocalSample,
+) -> Result<Vec<LocalValue>, Error> {
+    let expr = expr.ok_or_else(|| {
+        Error::LocalEvaluationError(format!(
+            "Transformation of feature {} cannot be evaluated locally",
+            name
+        ))
+    })?;
+    let values = sample.evaluate(expr)?;
+    if let Some((n, v)) = values
+        .iter()
+        .enumerate()
+        .find(|(_, v)| !v.is_compatible(feature_type.val_type))
+    {
+        return Err(Error::LocalEvaluationError(format!(
+            "Feature {} has type {:?}, but row {} evaluated to {} value '{}'",
+            name,
+            feature_type.val_type,
+            n + 1,
+            v.type_name(),
+            v
+        )));
+    }
+    Ok(values)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(LocalValue),
+    Symbol(&'static str),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, Error> {
+    const SYMBOLS: &[&str] = &[
+        "<=", ">=", "!=", "<>", "==", "(", ")", ",", "+", "-", "*", "/", "%", "=", "<", ">",
+    ];
+    let mut tokens = vec![];
+    let mut rest = s;
+    loop {
+        rest = rest.trim_start();
+        let c = match rest.chars().next() {
+            Some(c) => c,
+            None => break,
+        };
+        if c.is_ascii_digit() || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Literal(LocalValue::parse(&rest[..len])));
+            rest = &rest[len..];
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            rest = &rest[len..];
+        } else if c == '`' {
+            let len = rest[1..].find('`').ok_or_else(|| {
+                Error::LocalEvaluationError(format!("Unterminated identifier in '{}'", s))
+            })?;
+            tokens.push(Token::Ident(rest[1..len + 1].to_string()));
+            rest = &rest[len + 2..];
+        } else if c == '\'' || c == '"' {
+            let mut value = String::new();
+            let mut chars = rest.char_indices().skip(1).peekable();
+            let mut end = None;
+            while let Some((i, ch)) = chars.next() {
+                if ch == c {
+                    if matches!(chars.peek(), Some((_, next)) if *next == c) {
+                        chars.next();
+                        value.push(c);
+                    } else {
+                        end = Some(i);
+                        break;
+                    }
+                } else {
+                    value.push(ch);
+                }
+            }
+            let end = end.ok_or_else(|| {
+                Error::LocalEvaluationError(format!("Unterminated string in '{}'", s))
+            })?;
+            tokens.push(Token::Literal(LocalValue::String(value)));
+            rest = &rest[end + 1..];
+        } else if let Some(sym) = SYMBOLS.iter().find(|sym| rest.starts_with(*sym)) {
+            tokens.push(Token::Symbol(sym));
+            rest = &rest[sym.len()..];
+        } else {
+            return Err(Error::LocalEvaluationError(format!(
+                "Unexpected character '{}' in '{}'",
+                c, s
+            )));
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Literal(LocalValue),
+    Column(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    IsNull(Box<Expr>, bool),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Func(String, Vec<Expr>),
+    Cast(Box<Expr>, String),
+    Case(Vec<(Expr, Expr)>, Option<Box<Expr>>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(s: &str) -> Result<Self, Error> {
+        Ok(Self {
+            tokens: tokenize(s)?,
+            pos: 0,
+        })
+    }
+
+    fn parse(mut self) -> Result<Expr, Error> {
+        let expr = self.parse_expr(0)?;
+        match self.peek() {
+            Some(t) => Err(self.unexpected(t)),
+            None => Ok(expr),
+        }
+    }
+
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        self.pos += 1;
+        self.tokens.get(self.pos - 1).cloned()
+    }
+
+    fn unexpected(&self, t: Token) -> Error {
+        Error::LocalEvaluationError(format!("Unexpected token {:?}", t))
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(s)) if s.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), Error> {
+        if self.is_keyword(keyword) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(Error::LocalEvaluationError(format!(
+                "Expecting {}, found {:?}",
+                keyword,
+                self.peek()
+            )))
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), Error> {
+        match self.next() {
+            Some(Token::Symbol(s)) if s == symbol => Ok(()),
+            t => Err(Error::LocalEvaluationError(format!(
+                "Expecting '{}', found {:?}",
+                symbol, t
+            ))),
+        }
+    }
+
+    /**
+     * Binary operator and its precedence
+     */
+    fn peek_binary(&self) -> Option<(&'static str, u8)> {
+        match self.peek()? {
+            Token::Ident(s) if s.eq_ignore_ascii_case("or") => Some(("OR", 1)),
+            Token::Ident(s) if s.eq_ignore_ascii_case("and") => Some(("AND", 2)),
+            Token::Ident(s) if s.eq_ignore_ascii_case("is") => Some(("IS", 4)),
+            Token::Symbol(s @ ("=" | "==" | "!=" | "<>" | "<" | "<=" | ">" | ">=")) => Some((s, 4)),
+            Token::Symbol(s @ ("+" | "-")) => Some((s, 5)),
+            Token::Symbol(s @ ("*" | "/" | "%")) => Some((s, 6)),
+            _ => None,
+        }
+    }
+
+    fn parse_expr(&mut self, min_prec: u8) -> Result<Expr, Error> {
+        let mut lhs = self.parse_unary()?;
+        while let Some((op, prec)) = self.peek_binary() {
+            if prec <= min_prec {
+                break;
+            }
+            self.pos += 1;
+            lhs = if op == "IS" {
+                let negated = self.is_keyword("not");
+                if negated {
+                    self.pos += 1;
+                }
+                self.expect_keyword("null")?;
+                Expr::IsNull(Box::new(lhs), negated)
+            } else {
+                Expr::Binary(op, Box::new(lhs), Box::new(self.parse_expr(prec)?))
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, Error> {
+        match self.next() {
+            Some(Token::Symbol("-")) => Ok(Expr::Neg(Box::new(self.parse_expr(6)?))),
+            Some(Token::Symbol("(")) => {
+                let e = self.parse_expr(0)?;
+                self.expect_symbol(")")?;
+                Ok(e)
+            }
+            Some(Token::Literal(v)) => Ok(Expr::Literal(v)),
+            Some(Token::Ident(s)) => match s.to_lowercase().as_str() {
+                "not" => Ok(Expr::Not(Box::new(self.parse_expr(3)?))),
+                "null" => Ok(Expr::Literal(LocalValue::Null)),
+                "true" => Ok(Expr::Literal(LocalValue::Bool(true))),
+                "false" => Ok(Expr::Literal(LocalValue::Bool(false))),
+                "case" => self.parse_case(),
+                "cast" if self.peek() == Some(Token::Symbol("(")) => {
+                    self.pos += 1;
+                    let e = self.parse_expr(0)?;
+                    self.expect_keyword("as")?;
+                    let t = match self.next() {
+                        Some(Token::Ident(t)) => t.to_lowercase(),
+                        t => return Err(self.unexpected(t.unwrap_or(Token::Symbol("")))),
+                    };
+                    self.expect_symbol(")")?;
+                    Ok(Expr::Cast(Box::new(e), t))
+                }
+                name if self.peek() == Some(Token::Symbol("(")) => {
+                    self.pos += 1;
+                    let mut args = vec![];
+                    if self.peek() != Some(Token::Symbol(")")) {
+                        loop {
+                            args.push(self.parse_expr(0)?);
+                            if self.peek() == Some(Token::Symbol(",")) {
+                                self.pos += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                    self.expect_symbol(")")?;
+                    Ok(Expr::Func(name.to_string(), args))
+                }
+                _ => Ok(Expr::Column(s)),
+            },
+            Some(t) => Err(self.unexpected(t)),
+            None => Err(Error::LocalEvaluationError(
+                "Unexpected end of expression".to_string(),
+            )),
+        }
+    }
+
+    fn parse_case(&mut self) -> Result<Expr, Error> {
+        let mut branches = vec![];
+        while self.is_keyword("when") {
+            self.pos += 1;
+            let cond = self.parse_expr(0)?;
+            self.expect_keyword("then")?;
+            branches.push((cond, self.parse_expr(0)?));
+        }
+        let otherwise = if self.is_keyword("else") {
+            self.pos += 1;
+            Some(Box::new(self.parse_expr(0)?))
+        } else {
+            None
+        };
+        self.expect_keyword("end")?;
+        Ok(Expr::Case(branches, otherwise))
+    }
+}
+
+fn eval_error<T>(msg: String) -> Result<T, Error> {
+    Err(Error::LocalEvaluationError(msg))
+}
+
+/**
+ * Spark wraps around on LONG overflow unless ANSI mode is on, overflow is reported instead of guessing the mode
+ */
+fn overflow(op: &str, v: &i64) -> Error {
+    Error::LocalEvaluationError(format!("LONG overflow when applying '{}' to {}", op, v))
+}
+
+impl Expr {
+    fn collect_columns(&self, columns: &mut Vec<String>) {
+        match self {
+            Expr::Literal(_) => {}
+            Expr::Column(c) => columns.push(c.to_owned()),
+            Expr::Neg(e) | Expr::Not(e) | Expr::IsNull(e, _) | Expr::Cast(e, _) => e.collect_columns(columns),
+            Expr::Binary(_, l, r) => {
+                l.collect_columns(columns);
+                r.collect_columns(columns);
+            }
+            Expr::Func(_, args) => args.iter().for_each(|a| a.collect_columns(columns)),
+            Expr::Case(branches, otherwise) => {
+                for (cond, value) in branches {
+                    cond.collect_columns(columns);
+                    value.collect_columns(columns);
+                }
+                if let Some(e) = otherwise {
+                    e.collect_columns(columns);
+                }
+            }
+        }
+    }
+
+    fn eval(&self, row: &HashMap<&str, &LocalValue>) -> Result<LocalValue, Error> {
+        Ok(match self {
+            Expr::Literal(v) => v.to_owned(),
+            Expr::Column(c) => match row.get(c.as_str()) {
+                Some(v) => (*v).to_owned(),
+                None => return eval_error(format!("Column {} not found", c)),
+            },
+            Expr::Neg(e) => match e.eval(row)? {
+                LocalValue::Null => LocalValue::Null,
+                LocalValue::Long(v) => LocalValue::Long(v.checked_neg().ok_or_else(|| overflow("-", &v))?),
+                LocalValue::Double(v) => LocalValue::Double(-v),
+                v => return eval_error(format!("Cannot negate {} value", v.type_name())),
+            },
+            Expr::Not(e) => match e.eval(row)? {
+                LocalValue::Null => LocalValue::Null,
+                LocalValue::Bool(v) => LocalValue::Bool(!v),
+                v => return eval_error(format!("Cannot apply NOT to {} value", v.type_name())),
+            },
+            Expr::IsNull(e, negated) => LocalValue::Bool((e.eval(row)? == LocalValue::Null) != *negated),
+            Expr::Binary(op, l, r) => eval_binary(op, l.eval(row)?, r.eval(row)?)?,
+            Expr::Func(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|a| a.eval(row))
+                    .collect::<Result<Vec<_>, _>>()?;
+                eval_func(name, args)?
+            }
+            Expr::Cast(e, t) => eval_cast(e.eval(row)?, t)?,
+            Expr::Case(branches, otherwise) => {
+                for (cond, value) in branches {
+                    if cond.eval(row)? == LocalValue::Bool(true) {
+                        return value.eval(row);
+                    }
+                }
+                match otherwise {
+                    Some(e) => e.eval(row)?,
+                    None => LocalValue::Null,
+                }
+            }
+        })
+    }
+}
+
+fn eval_binary(op: &str, l: LocalValue, r: LocalValue) -> Result<LocalValue, Error> {
+    use LocalValue::*;
+    match (op, &l, &r) {
+        ("AND", Bool(false), _) | ("AND", _, Bool(false)) => return Ok(Bool(false)),
+        ("OR", Bool(true), _) | ("OR", _, Bool(true)) => return Ok(Bool(true)),
+        (_, Null, _) | (_, _, Null) => return Ok(Null),
+        ("AND", Bool(_), Bool(_)) => return Ok(Bool(true)),
+        ("OR", Bool(_), Bool(_)) => return Ok(Bool(false)),
+        _ => {}
+    }
+    let ordering = match (&l, &r) {
+        (String(a), String(b)) => Some(a.cmp(b)),
+        (Bool(a), Bool(b)) => Some(a.cmp(b)),
+        _ => match (l.as_f64(), r.as_f64()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        },
+    };
+    Ok(match (op, &l, &r) {
+        ("=" | "==", _, _) => Bool(ordering == Some(std::cmp::Ordering::Equal)),
+        ("!=" | "<>", _, _) => Bool(ordering != Some(std::cmp::Ordering::Equal)),
+        ("<" | "<=" | ">" | ">=", _, _) => match ordering {
+            Some(o) => Bool(match op {
+                "<" => o.is_lt(),
+                "<=" => o.is_le(),
+                ">" => o.is_gt(),
+                _ => o.is_ge(),
+            }),
+            None => {
+                return eval_error(format!(
+                    "Cannot compare {} with {}",
+                    l.type_name(),
+                    r.type_name()
+                ))
+            }
+        },
+        ("+", Long(a), Long(b)) => Long(a.checked_add(*b).ok_or_else(|| overflow(op, a))?),
+        ("-", Long(a), Long(b)) => Long(a.checked_sub(*b).ok_or_else(|| overflow(op, a))?),
+        ("*", Long(a), Long(b)) => Long(a.checked_mul(*b).ok_or_else(|| overflow(op, a))?),
+        ("%", Long(_), Long(0)) => Null,
+        ("%", Long(a), Long(b)) => Long(a.checked_rem(*b).ok_or_else(|| overflow(op, a))?),
+        ("+" | "-" | "*" | "/" | "%", _, _) => match (l.as_f64(), r.as_f64()) {
+            // Same as Spark SQL, division always returns double and division by zero returns null
+            (Some(_), Some(b)) if (op == "/" || op == "%") && b == 0.0 => Null,
+            (Some(a), Some(b)) => Double(match op {
+                "+" => a + b,
+                "-" => a - b,
+                "*" => a * b,
+                "/" => a / b,
+                _ => a % b,
+            }),
+            _ => {
+                return eval_error(format!(
+                    "Cannot apply '{}' to {} and {}",
+                    op,
+                    l.type_name(),
+                    r.type_name()
+                ))
+            }
+        },
+        _ => {
+            return eval_error(format!(
+                "Cannot apply '{}' to {} and {}",
+                op,
+                l.type_name(),
+                r.type_name()
+            ))
+        }
+    })
+}
+
+fn eval_func(name: &str, args: Vec<LocalValue>) -> Result<LocalValue, Error> {
+    use LocalValue::*;
+    let name = name.to_lowercase();
+    Ok(match (name.as_str(), args.as_slice()) {
+        ("coalesce", args) => args.iter().find(|v| **v != Null).cloned().unwrap_or(Null),
+        ("if", [cond, a, b]) => {
+            if *cond == Bool(true) {
+                a.to_owned()
+            } else {
+                b.to_owned()
+            }
+        }
+        (_, [Null, ..]) => Null,
+        ("abs", [Long(v)]) => Long(v.checked_abs().ok_or_else(|| overflow("abs", v))?),
+        ("abs", [Double(v)]) => Double(v.abs()),
+        // Spark keeps the type of the value, and rounds half away from zero
+        ("round", [Long(v)]) => Long(*v),
+        ("round", [Long(v), Long(d)]) => Long(round_long(*v, *d)?),
+        ("round", [Double(v)]) => Double(v.round()),
+        ("round", [Double(v), Long(d)]) => {
+            let m = 10f64.powi(*d as i32);
+            Double((v * m).round() / m)
+        }
+        ("floor" | "ceil", [Long(v)]) => Long(*v),
+        ("floor", [v]) if v.as_f64().is_some() => Long(v.as_f64().unwrap().floor() as i64),
+        ("ceil", [v]) if v.as_f64().is_some() => Long(v.as_f64().unwrap().ceil() as i64),
+        ("lower", [String(s)]) => String(s.to_lowercase()),
+        ("upper", [String(s)]) => String(s.to_uppercase()),
+        ("trim", [String(s)]) => String(s.trim().to_string()),
+        ("length", [String(s)]) => Long(s.chars().count() as i64),
+        ("concat", args) => {
+            if args.contains(&Null) {
+                Null
+            } else {
+                String(args.iter().map(ToString::to_string).collect())
+            }
+        }
+        _ => {
+            return eval_error(format!(
+                "Unsupported function {}({})",
+                name,
+                args.iter().map(|a| a.type_name()).collect::<Vec<_>>().join(", ")
+            ))
+        }
+    })
+}
+
+/**
+ * Round to `d` decimal places, tens, hundreds etc. if `d` is negative
+ */
+fn round_long(v: i64, d: i64) -> Result<i64, Error> {
+    if d >= 0 {
+        return Ok(v);
+    }
+    let m = match 10i64.checked_pow(d.unsigned_abs().min(u32::MAX as u64) as u32) {
+        Some(m) => m,
+        None => return Ok(0),
+    };
+    let r = v % m;
+    if r.unsigned_abs() * 2 < m.unsigned_abs() {
+        Ok(v - r)
+    } else {
+        (v - r)
+            .checked_add(v.signum() * m)
+            .ok_or_else(|| overflow("round", &v))
+    }
+}
+
+/**
+ * Spark truncates the fraction when casting strings to integers, e.g. `'1.5'` to 1,
+ * other strings that are not numbers, including exponents, are NULL
+ */
+fn parse_integral(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (int, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    match int {
+        "" | "-" | "+" if !fraction.is_empty() => Some(0),
+        _ => int.parse().ok(),
+    }
+}
+
+fn eval_cast(v: LocalValue, t: &str) -> Result<LocalValue, Error> {
+    use LocalValue::*;
+    Ok(match (t, &v) {
+        (_, Null) => Null,
+        ("int" | "integer" | "bigint" | "long", Long(_)) => v,
+        ("int" | "integer" | "bigint" | "long", Double(d)) => Long(*d as i64),
+        ("int" | "integer", String(s)) => parse_integral(s)
+            .filter(|n| i32::try_from(*n).is_ok())
+            .map(Long)
+            .unwrap_or(Null),
+        ("bigint" | "long", String(s)) => parse_integral(s).map(Long).unwrap_or(Null),
+        ("int" | "integer" | "bigint" | "long", Bool(b)) => Long(*b as i64),
+        ("double" | "float", _) if v.as_f64().is_some() => Double(v.as_f64().unwrap()),
+        ("double" | "float", String(s)) => s.trim().parse().map(Double).unwrap_or(Null),
+        ("string", _) => String(v.to_string()),
+        ("boolean", Bool(_)) => v,
+        ("boolean", String(s)) => match s.trim().to_lowercase().as_str() {
+            "true" => Bool(true),
+            "false" => Bool(false),
+            _ => Null,
+        },
+        ("boolean", Long(n)) => Bool(*n != 0),
+        _ => return eval_error(format!("Cannot cast {} value to {}", v.type_name(), t)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"id,fare,name,flag
+1,10.5,"Alice, A",true
+2,3,bob,false
+3,,carol,
+"#;
+
+    #[test]
+    fn evaluate_expressions() {
+        let sample = LocalSample::from_csv(SAMPLE).unwrap();
+        assert_eq!(sample.columns, vec!["id", "fare", "name", "flag"]);
+        assert_eq!(sample.rows[0][2], LocalValue::String("Alice, A".to_string()));
+
+        assert_eq!(
+            sample.evaluate("fare * 2 + id").unwrap(),
+            vec![
+                LocalValue::Double(22.0),
+                LocalValue::Long(8),
+                LocalValue::Null
+            ]
+        );
+        assert_eq!(
+            sample.evaluate("id / 2").unwrap()[0],
+            LocalValue::Double(0.5)
+        );
+        assert_eq!(
+            sample
+                .evaluate("CASE WHEN fare > 5 THEN 'high' WHEN fare IS NULL THEN 'none' ELSE 'low' END")
+                .unwrap(),
+            vec![
+                LocalValue::String("high".to_string()),
+                LocalValue::String("low".to_string()),
+                LocalValue::String("none".to_string())
+            ]
+        );
+        assert_eq!(
+            sample.evaluate("upper(name) = 'BOB' and not flag").unwrap()[1],
+            LocalValue::Bool(true)
+        );
+        assert_eq!(
+            sample.evaluate("cast(coalesce(fare, -1) as int)").unwrap()[2],
+            LocalValue::Long(-1)
+        );
+        assert!(sample.evaluate("unknown_column + 1").is_err());
+        assert_eq!(sample.evaluate("id % 0").unwrap()[0], LocalValue::Null);
+        assert!(sample.evaluate("-(-9223372036854775807 - 1)").is_err());
+        assert!(sample.evaluate("abs(-9223372036854775807 - id)").is_err());
+        assert!(sample.evaluate("(-9223372036854775807 - 1) % -1").is_err());
+        assert!(sample.evaluate("9223372036854775807 * 2").is_err());
+        assert_eq!(
+            sample.evaluate("(-9223372036854775807 - 1) / -1").unwrap()[0],
+            LocalValue::Double(9223372036854775808.0)
+        );
+        assert!(sample.evaluate("fare +").is_err());
+    }
+
+    #[test]
+    fn match_spark_semantics() {
+        let sample = LocalSample::from_csv(SAMPLE).unwrap();
+        assert_eq!(sample.evaluate("round(id)").unwrap()[0], LocalValue::Long(1));
+        assert_eq!(sample.evaluate("round(fare)").unwrap()[0], LocalValue::Double(11.0));
+        assert_eq!(sample.evaluate("round(125, -1)").unwrap()[0], LocalValue::Long(130));
+        assert_eq!(sample.evaluate("round(-125, -1)").unwrap()[0], LocalValue::Long(-130));
+        assert_eq!(sample.evaluate("round(124, -1)").unwrap()[0], LocalValue::Long(120));
+        assert_eq!(sample.evaluate("round(2.345, 2)").unwrap()[0], LocalValue::Double(2.35));
+        assert_eq!(sample.evaluate("floor(id)").unwrap()[0], LocalValue::Long(1));
+
+        assert_eq!(sample.evaluate("cast('1.5' as int)").unwrap()[0], LocalValue::Long(1));
+        assert_eq!(sample.evaluate("cast(' -7.9 ' as bigint)").unwrap()[0], LocalValue::Long(-7));
+        assert_eq!(sample.evaluate("cast('.5' as int)").unwrap()[0], LocalValue::Long(0));
+        assert_eq!(sample.evaluate("cast('1e3' as int)").unwrap()[0], LocalValue::Null);
+        assert_eq!(sample.evaluate("cast('3000000000' as int)").unwrap()[0], LocalValue::Null);
+        assert_eq!(
+            sample.evaluate("cast('3000000000' as bigint)").unwrap()[0],
+            LocalValue::Long(3000000000)
+        );
+    }
+
+    #[test]
+    fn parquet_sample() {
+        use parquet::{
+            data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type},
+            file::{properties::WriterProperties, writer::SerializedFileWriter},
+        };
+        use std::sync::Arc;
+
+        let schema = Arc::new(
+            parquet::schema::parser::parse_message_type(
+                "message spark_schema {
+                    required int64 id;
+                    optional double fare;
+                    optional binary name (UTF8);
+                    optional binary raw;
+                }",
+            )
+            .unwrap(),
+        );
+        let mut content = vec![];
+        let mut writer =
+            SerializedFileWriter::new(&mut content, schema, Arc::new(WriterProperties::builder().build())).unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column.typed::<Int64Type>().write_batch(&[1, 2], None, None).unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column.typed::<DoubleType>().write_batch(&[10.5], Some(&[1, 0]), None).unwrap();
+        column.close().unwrap();
+        for _ in 0..2 {
+            let mut column = row_group.next_column().unwrap().unwrap();
+            column
+                .typed::<ByteArrayType>()
+                .write_batch(&[ByteArray::from("bob")], Some(&[0, 1]), None)
+                .unwrap();
+            column.close().unwrap();
+        }
+        row_group.close().unwrap();
+        writer.close().unwrap();
+
+        let sample = LocalSample::from_parquet(content.into()).unwrap();
+        assert_eq!(sample.columns, vec!["id", "fare", "name"]);
+        assert_eq!(
+            sample.rows,
+            vec![
+                vec![LocalValue::Long(1), LocalValue::Double(10.5), LocalValue::Null],
+                vec![LocalValue::Long(2), LocalValue::Null, LocalValue::String("bob".to_string())],
+            ]
+        );
+        assert_eq!(
+            sample.evaluate("id * 2 + coalesce(fare, 0.0)").unwrap(),
+            vec![LocalValue::Double(12.5), LocalValue::Double(4.0)]
+        );
+        assert!(LocalSample::from_parquet(Bytes::from_static(b"id,fare")).is_err());
+    }
+
+    #[test]
+    fn collect_columns() {
+        assert_eq!(
+            referenced_columns(&Transformation::from("coalesce(a, `b c`) + length(d) * 2")),
+            Some(vec!["a".to_string(), "b c".to_string(), "d".to_string()])
+        );
+        assert_eq!(
+            referenced_columns(&Transformation::from("case when a is null then 'x' else e end")),
+            Some(vec!["a".to_string(), "e".to_string()])
+        );
+        assert_eq!(referenced_columns(&Transformation::from("a[0]")), None);
+    }
+
+    #[test]
+    fn check_feature_type() {
+        let sample = LocalSample::from_csv(SAMPLE).unwrap();
+        assert!(evaluate_feature("f1", Some("fare"), &FeatureType::DOUBLE, &sample).is_ok());
+        assert!(evaluate_feature("f1", Some("fare"), &FeatureType::INT64, &sample).is_err());
+        assert!(evaluate_feature("f1", Some("name"), &FeatureType::STRING, &sample).is_ok());
+        assert!(evaluate_feature("f1", None, &FeatureType::STRING, &sample).is_err());
+    }
+}
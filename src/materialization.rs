@@ -1,9 +1,14 @@
-use chrono::{DateTime, Duration, Utc};
-use serde::Serialize;
+use std::collections::HashMap;
 
-use crate::Error;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{source::JdbcAuth, ConfigJobOptions, Error, JdbcSourceAuth, RedisEncoding, SourceFormat, SubmitJobRequest};
 
 const END_TIME_FORMAT: &str = "yyyy-MM-dd HH:mm:ss";
+pub(crate) const LATEST_POINTER_FILE: &str = "_LATEST";
+// Written by Spark into the output folder when the job succeeded
+const SUCCESS_MARKER_FILE: &str = "_SUCCESS";
 
 mod job_date_format {
     pub fn serialize<S>(
@@ -15,25 +20,102 @@ mod job_date_format {
     {
         serializer.serialize_str(&format!("{}", date.format("%Y-%m-%d %H:%M:%S")))
     }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<chrono::DateTime<chrono::Utc>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        chrono::NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
+            .map(|t| t.and_utc())
+            .map_err(serde::de::Error::custom)
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum DateTimeResolution {
     Daily,
     Hourly,
 }
 
-#[derive(Clone, Debug, Serialize)]
+impl DateTimeResolution {
+    pub fn duration(&self) -> Duration {
+        match self {
+            DateTimeResolution::Daily => Duration::days(1),
+            DateTimeResolution::Hourly => Duration::hours(1),
+        }
+    }
+
+    /**
+     * Start of the day or hour the time is in
+     */
+    pub fn floor(&self, t: DateTime<Utc>) -> DateTime<Utc> {
+        t.duration_trunc(self.duration()).unwrap_or(t)
+    }
+
+    /**
+     * End of the day or hour the time is in, the time itself if it's already on the boundary
+     */
+    pub fn ceil(&self, t: DateTime<Utc>) -> DateTime<Utc> {
+        let floor = self.floor(t);
+        if floor == t {
+            t
+        } else {
+            floor + self.duration()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RedisSink {
     pub table_name: String,
     pub streaming: bool,
     #[serde(
         rename = "timeoutMs",
+        default,
         skip_serializing_if = "Option::is_none",
-        serialize_with = "ser_timeout"
+        serialize_with = "ser_timeout",
+        deserialize_with = "de_timeout"
     )]
     pub streaming_timeout: Option<Duration>,
+    /**
+     * Format of feature values, the runtime default is used if not set
+     */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<RedisEncoding>,
+    /**
+     * Alias switched to this table after the job succeeded
+     */
+    #[serde(skip)]
+    pub alias: Option<String>,
+    /**
+     * Prepended to the keys written by this sink, e.g. `team1:`, so several tenants can share a Redis instance
+     */
+    #[serde(rename = "keyPrefix", default, skip_serializing_if = "Option::is_none")]
+    pub key_prefix: Option<String>,
+    /**
+     * Expiration of the keys, keys never expire if not set
+     */
+    #[serde(
+        rename = "ttlSeconds",
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "ser_seconds",
+        deserialize_with = "de_seconds"
+    )]
+    pub ttl: Option<Duration>,
+    /**
+     * Expiration of the keys of individual features, overrides `ttl`
+     */
+    #[serde(
+        rename = "featureTtlSeconds",
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "ser_seconds_map",
+        deserialize_with = "de_seconds_map"
+    )]
+    pub feature_ttl: HashMap<String, Duration>,
 }
 
 impl RedisSink {
@@ -42,22 +124,589 @@ impl RedisSink {
             table_name: table_name.to_string(),
             streaming: false,
             streaming_timeout: None,
+            encoding: None,
+            alias: None,
+            key_prefix: None,
+            ttl: None,
+            feature_ttl: Default::default(),
         }
     }
 
     pub fn with_timeout(table_name: &str, timeout: Duration) -> Self {
         Self {
-            table_name: table_name.to_string(),
-            streaming: false,
             streaming_timeout: Some(timeout),
+            ..Self::new(table_name)
+        }
+    }
+
+    /**
+     * Keep writing rows of the streaming source, stop after `timeout` if set
+     */
+    pub fn streaming(mut self, timeout: Option<Duration>) -> Self {
+        self.streaming = true;
+        self.streaming_timeout = timeout;
+        self
+    }
+
+    /**
+     * Write feature values in the format, e.g. `RedisEncoding::Json` for debugging
+     */
+    pub fn encoding(mut self, encoding: RedisEncoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /**
+     * Switch the alias to this table after the job of the last cutoff succeeded,
+     * so readers of the alias move from the previous table to this one at once
+     */
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_string());
+        self
+    }
+
+    pub fn key_prefix(mut self, prefix: &str) -> Self {
+        self.key_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /**
+     * Expire the keys after `ttl`, fails if `ttl` is shorter than 1 second
+     */
+    pub fn ttl(mut self, ttl: Duration) -> Result<Self, Error> {
+        crate::utils::ttl_seconds(ttl)?;
+        self.ttl = Some(ttl);
+        Ok(self)
+    }
+
+    /**
+     * Expire keys of the feature after `ttl`, other features use the sink-wide `ttl`.
+     * Fails if `ttl` is shorter than 1 second
+     */
+    pub fn feature_ttl(mut self, feature: &str, ttl: Duration) -> Result<Self, Error> {
+        crate::utils::ttl_seconds(ttl)?;
+        self.feature_ttl.insert(feature.to_string(), ttl);
+        Ok(self)
+    }
+}
+
+/**
+ * Publishes feature rows to a Kafka topic or an Azure Event Hub,
+ * credentials are taken from `KAFKA_SASL_JAAS_CONFIG` and passed to the job via `--kafka-config`
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KafkaSink {
+    pub brokers: Vec<String>,
+    pub topic: String,
+    pub streaming: bool,
+    #[serde(
+        rename = "timeoutMs",
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "ser_timeout",
+        deserialize_with = "de_timeout"
+    )]
+    pub streaming_timeout: Option<Duration>,
+}
+
+impl KafkaSink {
+    pub fn new<T: ToString>(brokers: &[T], topic: &str) -> Self {
+        Self {
+            brokers: brokers.iter().map(|b| b.to_string()).collect(),
+            topic: topic.to_string(),
+            streaming: false,
+            streaming_timeout: None,
+        }
+    }
+
+    /**
+     * Event Hub `event_hub` in namespace `namespace`, via its Kafka endpoint
+     */
+    pub fn event_hub(namespace: &str, event_hub: &str) -> Self {
+        Self::new(&[format!("{}.servicebus.windows.net:9093", namespace)], event_hub)
+    }
+
+    /**
+     * Keep publishing rows of the streaming source, stop after `timeout` if set
+     */
+    pub fn streaming(mut self, timeout: Option<Duration>) -> Self {
+        self.streaming = true;
+        self.streaming_timeout = timeout;
+        self
+    }
+}
+
+/**
+ * Writes feature rows into a CosmosDB container, the account key is taken from the secret `<name>_KEY`
+ * and passed to the job via `--cosmos-config`
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CosmosDbSink {
+    #[serde(skip)]
+    pub name: String,
+    pub endpoint: String,
+    pub database: String,
+    #[serde(rename = "collection")]
+    pub container: String,
+}
+
+impl CosmosDbSink {
+    pub fn new(name: &str, endpoint: &str, database: &str, container: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            endpoint: endpoint.to_string(),
+            database: database.to_string(),
+            container: container.to_string(),
+        }
+    }
+
+    /**
+     * Name of the secret holding the account key
+     */
+    pub fn get_key_name(&self) -> String {
+        format!("{}_KEY", self.name)
+    }
+}
+
+// Default service port of Aerospike
+const AEROSPIKE_DEFAULT_PORT: u16 = 3000;
+
+/**
+ * Writes feature rows into an Aerospike set, credentials are referred as secrets `${name}_USER` and `${name}_PASSWORD`
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AerospikeSink {
+    #[serde(skip)]
+    pub name: String,
+    pub seedhost: String,
+    pub port: u16,
+    pub namespace: String,
+    #[serde(rename = "setname", skip_serializing_if = "Option::is_none")]
+    pub set: Option<String>,
+    user: String,
+    password: String,
+}
+
+impl AerospikeSink {
+    pub fn new(name: &str, seedhost: &str, namespace: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            seedhost: seedhost.to_string(),
+            port: AEROSPIKE_DEFAULT_PORT,
+            namespace: namespace.to_string(),
+            set: None,
+            user: format!("${{{}_USER}}", name),
+            password: format!("${{{}_PASSWORD}}", name),
+        }
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /**
+     * Write into the set, the null set of the namespace is used if not set
+     */
+    pub fn set(mut self, set: &str) -> Self {
+        self.set = Some(set.to_string());
+        self
+    }
+
+    pub fn get_secret_keys(&self) -> Vec<String> {
+        vec![format!("{}_USER", self.name), format!("{}_PASSWORD", self.name)]
+    }
+}
+
+/**
+ * Secrets of the monitoring database, passed to the job via `--monitoring-config`
+ */
+pub(crate) const MONITORING_SECRET_KEYS: [&str; 3] = [
+    "MONITORING_DATABASE_SQL_URL",
+    "MONITORING_DATABASE_SQL_USER",
+    "MONITORING_DATABASE_SQL_PASSWORD",
+];
+
+/**
+ * Writes feature statistics for drift and quality monitoring into a table of the monitoring database,
+ * the connection is referred as secrets `MONITORING_DATABASE_SQL_URL`, `_USER` and `_PASSWORD`
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MonitoringSink {
+    pub table_name: String,
+}
+
+impl MonitoringSink {
+    pub fn new(table_name: &str) -> Self {
+        Self {
+            table_name: table_name.to_string(),
+        }
+    }
+
+    pub fn get_secret_keys(&self) -> Vec<String> {
+        MONITORING_SECRET_KEYS.iter().map(|k| k.to_string()).collect()
+    }
+}
+
+/**
+ * Writes feature rows into a relational table, credentials are referred as secrets named after the sink,
+ * e.g. `${name}_USER` and `${name}_PASSWORD`
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JdbcSink {
+    #[serde(skip)]
+    pub name: String,
+    pub url: String,
+    pub dbtable: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+    #[serde(flatten)]
+    pub(crate) auth: JdbcAuth,
+}
+
+impl JdbcSink {
+    pub fn new(name: &str, url: &str, dbtable: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            dbtable: dbtable.to_string(),
+            driver: None,
+            auth: JdbcAuth::Anonymous,
+        }
+    }
+
+    pub fn auth(mut self, auth: JdbcSourceAuth) -> Self {
+        self.auth = match auth {
+            JdbcSourceAuth::Anonymous => JdbcAuth::Anonymous,
+            JdbcSourceAuth::Userpass => JdbcAuth::Userpass {
+                user: format!("${{{}_USER}}", self.name),
+                password: format!("${{{}_PASSWORD}}", self.name),
+            },
+            JdbcSourceAuth::Token => JdbcAuth::Token {
+                token: format!("${{{}_TOKEN}}", self.name),
+            },
+        };
+        self
+    }
+
+    /**
+     * JDBC driver class, e.g. `org.postgresql.Driver`
+     */
+    pub fn driver(mut self, class_name: &str) -> Self {
+        self.driver = Some(class_name.to_string());
+        self
+    }
+
+    pub fn get_secret_keys(&self) -> Vec<String> {
+        match self.auth {
+            JdbcAuth::Userpass { .. } => {
+                vec![format!("{}_USER", self.name), format!("{}_PASSWORD", self.name)]
+            }
+            JdbcAuth::Token { .. } => vec![format!("{}_TOKEN", self.name)],
+            JdbcAuth::Anonymous => vec![],
+        }
+    }
+}
+
+/**
+ * Offline sink writes to HDFS compatible storage, e.g. ADLS or Azure Blob
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HdfsSink {
+    pub path: String,
+    /**
+     * Format of the output files, the runtime writes Avro if not set
+     */
+    #[serde(rename = "outputFormat", skip_serializing_if = "Option::is_none")]
+    pub format: Option<SourceFormat>,
+    /**
+     * Secret holding the access key of the storage account in `path`, needed if it's different from the workspace storage
+     */
+    #[serde(skip)]
+    pub account_key_name: Option<String>,
+    /**
+     * Each cutoff is written into its own folder under `path` with this pattern, e.g. `yyyy/MM/dd`
+     */
+    #[serde(skip)]
+    pub time_partition_pattern: Option<String>,
+    /**
+     * Write the folder of the newest cutoff into `path/_LATEST` after the job succeeded
+     */
+    #[serde(skip)]
+    pub update_latest: bool,
+    /**
+     * Clear the output folder of the cutoff before submitting the job, see `overwrite_existing`
+     */
+    #[serde(skip)]
+    pub overwrite_existing: bool,
+}
+
+impl HdfsSink {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            format: None,
+            account_key_name: None,
+            time_partition_pattern: None,
+            update_latest: false,
+            overwrite_existing: false,
+        }
+    }
+
+    /**
+     * The access key of the storage account in `path` is taken from the secret `<name>_STORAGE_KEY`
+     */
+    pub fn with_account_key(path: &str, name: &str) -> Self {
+        Self {
+            account_key_name: Some(format!("{}_STORAGE_KEY", name)),
+            ..Self::new(path)
+        }
+    }
+
+    /**
+     * Write the output in the format, e.g. `SourceFormat::Parquet` for consumers that don't read Avro
+     */
+    pub fn format(mut self, format: SourceFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /**
+     * Write each cutoff into a sub folder with the pattern, e.g. `yyyy/MM/dd` or `yyyy/MM/dd/HH`
+     */
+    pub fn time_partition_pattern(mut self, pattern: &str) -> Self {
+        self.time_partition_pattern = Some(pattern.to_string());
+        self
+    }
+
+    /**
+     * Default time partition pattern of the resolution
+     */
+    pub fn time_partitioned(self, resolution: DateTimeResolution) -> Self {
+        self.time_partition_pattern(match resolution {
+            DateTimeResolution::Daily => "yyyy/MM/dd",
+            DateTimeResolution::Hourly => "yyyy/MM/dd/HH",
+        })
+    }
+
+    /**
+     * Update the `_LATEST` pointer file under `path` to the newest cutoff folder, needs time partition pattern
+     */
+    pub fn update_latest(mut self) -> Self {
+        self.update_latest = true;
+        self
+    }
+
+    /**
+     * Clear the existing output folder of the cutoff before submitting the job,
+     * so a re-run doesn't leave files of different runs in the same folder.
+     * The folder is moved aside first and restored if the submission fails, it's deleted once the job is accepted
+     */
+    pub fn overwrite_existing(mut self) -> Self {
+        self.overwrite_existing = true;
+        self
+    }
+
+    /**
+     * Output folder of the cutoff
+     */
+    pub(crate) fn get_partition_path(&self, end_time: DateTime<Utc>) -> String {
+        match &self.time_partition_pattern {
+            Some(pattern) => format!(
+                "{}/{}",
+                self.path.trim_end_matches('/'),
+                end_time.format(&to_chrono_format(pattern))
+            ),
+            None => self.path.to_owned(),
+        }
+    }
+
+    /**
+     * Pointer file and its content of the cutoff
+     */
+    pub(crate) fn get_latest_pointer(&self, end_time: DateTime<Utc>) -> Option<(String, String)> {
+        if self.update_latest && self.time_partition_pattern.is_some() {
+            Some((
+                format!("{}/{}", self.path.trim_end_matches('/'), LATEST_POINTER_FILE),
+                self.get_partition_path(end_time),
+            ))
+        } else {
+            None
         }
     }
+
+    /**
+     * Success marker in the output folder of the cutoff, needs time partition pattern
+     */
+    pub(crate) fn get_success_marker(&self, end_time: DateTime<Utc>) -> Option<String> {
+        self.time_partition_pattern.as_ref().map(|_| {
+            format!("{}/{}", self.get_partition_path(end_time), SUCCESS_MARKER_FILE)
+        })
+    }
+
+    /**
+     * Storage host, e.g. `account.dfs.core.windows.net`, and the secret holding its access key
+     */
+    pub(crate) fn get_storage_key_name(&self) -> Result<Option<(String, String)>, Error> {
+        match &self.account_key_name {
+            Some(key) => {
+                let url = reqwest::Url::parse(&self.path)
+                    .map_err(|_| Error::InvalidUrl(self.path.to_owned()))?;
+                let host = url
+                    .host_str()
+                    .ok_or_else(|| Error::InvalidUrl(self.path.to_owned()))?;
+                Ok(Some((host.to_string(), key.to_owned())))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/**
+ * Time partitioned HDFS sink of a gen config, scheduled jobs resolve the output folder of each run from it
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct TimePartition {
+    /**
+     * Index of the sink in `operational.output` of the gen config
+     */
+    pub sink: usize,
+    pub path: String,
+    /**
+     * Pattern of the sub folder in `strftime` format, e.g. `%Y/%m/%d`
+     */
+    pub pattern: String,
+    pub update_latest: bool,
+    pub overwrite_existing: bool,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "name", content = "params", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OutputSink {
     Redis(RedisSink),
+    Hdfs(HdfsSink),
+    Kafka(KafkaSink),
+    #[serde(rename = "COSMOSDB")]
+    CosmosDb(CosmosDbSink),
+    Jdbc(JdbcSink),
+    Aerospike(AerospikeSink),
+    Monitoring(MonitoringSink),
+}
+
+/**
+ * Convert Java `SimpleDateFormat` pattern used by Feathr into chrono format
+ */
+fn to_chrono_format(pattern: &str) -> String {
+    [("yyyy", "%Y"), ("MM", "%m"), ("dd", "%d"), ("HH", "%H"), ("mm", "%M")]
+        .into_iter()
+        .fold(pattern.to_string(), |p, (from, to)| p.replace(from, to))
+}
+
+impl OutputSink {
+    /**
+     * Sink of the cutoff
+     */
+    pub(crate) fn for_cutoff(&self, end_time: DateTime<Utc>) -> Self {
+        match self {
+            OutputSink::Hdfs(s) => OutputSink::Hdfs(HdfsSink {
+                path: s.get_partition_path(end_time),
+                ..s.to_owned()
+            }),
+            _ => self.to_owned(),
+        }
+    }
+
+    pub(crate) fn get_latest_pointer(&self, end_time: DateTime<Utc>) -> Option<(String, String)> {
+        match self {
+            OutputSink::Hdfs(s) => s.get_latest_pointer(end_time),
+            _ => None,
+        }
+    }
+
+    /**
+     * Time partition of the sink at `index` of the sinks
+     */
+    pub(crate) fn get_time_partition(&self, index: usize) -> Option<TimePartition> {
+        match self {
+            OutputSink::Hdfs(s) => s.time_partition_pattern.as_ref().map(|pattern| TimePartition {
+                sink: index,
+                path: s.path.trim_end_matches('/').to_string(),
+                pattern: to_chrono_format(pattern),
+                update_latest: s.update_latest,
+                overwrite_existing: s.overwrite_existing,
+            }),
+            _ => None,
+        }
+    }
+
+    /**
+     * Folder to be deleted before submitting the job, call on the sink of the cutoff
+     */
+    pub(crate) fn get_overwrite_path(&self) -> Option<String> {
+        match self {
+            OutputSink::Hdfs(s) if s.overwrite_existing => Some(s.path.to_owned()),
+            _ => None,
+        }
+    }
+
+    /**
+     * Alias and the table it points to after the job succeeded
+     */
+    pub(crate) fn get_redis_alias(&self) -> Option<(String, String)> {
+        match self {
+            OutputSink::Redis(RedisSink {
+                table_name,
+                alias: Some(alias),
+                ..
+            }) => Some((alias.to_owned(), table_name.to_owned())),
+            _ => None,
+        }
+    }
+
+    /**
+     * Secrets referred by the sink, the CosmosDB account key is passed separately in `cosmos_key_names`
+     */
+    pub(crate) fn get_secret_keys(&self) -> Vec<String> {
+        match self {
+            OutputSink::Jdbc(s) => s.get_secret_keys(),
+            OutputSink::Aerospike(s) => s.get_secret_keys(),
+            OutputSink::Monitoring(s) => s.get_secret_keys(),
+            _ => vec![],
+        }
+    }
+
+    /**
+     * Sink keeps consuming rows of the streaming source
+     */
+    pub fn is_streaming(&self) -> bool {
+        match self {
+            OutputSink::Redis(s) => s.streaming,
+            OutputSink::Kafka(s) => s.streaming,
+            _ => false,
+        }
+    }
+
+    /**
+     * Secret holding the CosmosDB account key
+     */
+    pub(crate) fn get_cosmos_key_name(&self) -> Option<String> {
+        match self {
+            OutputSink::CosmosDb(s) => Some(s.get_key_name()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_storage_key_name(&self) -> Result<Option<(String, String)>, Error> {
+        match self {
+            OutputSink::Redis(_)
+            | OutputSink::Kafka(_)
+            | OutputSink::CosmosDb(_)
+            | OutputSink::Jdbc(_)
+            | OutputSink::Aerospike(_)
+            | OutputSink::Monitoring(_) => Ok(None),
+            OutputSink::Hdfs(s) => s.get_storage_key_name(),
+        }
+    }
 }
 
 impl From<&OutputSink> for OutputSink {
@@ -78,6 +727,78 @@ impl From<&RedisSink> for OutputSink {
     }
 }
 
+impl From<KafkaSink> for OutputSink {
+    fn from(s: KafkaSink) -> Self {
+        Self::Kafka(s)
+    }
+}
+
+impl From<&KafkaSink> for OutputSink {
+    fn from(s: &KafkaSink) -> Self {
+        Self::Kafka(s.to_owned())
+    }
+}
+
+impl From<CosmosDbSink> for OutputSink {
+    fn from(s: CosmosDbSink) -> Self {
+        Self::CosmosDb(s)
+    }
+}
+
+impl From<&CosmosDbSink> for OutputSink {
+    fn from(s: &CosmosDbSink) -> Self {
+        Self::CosmosDb(s.to_owned())
+    }
+}
+
+impl From<AerospikeSink> for OutputSink {
+    fn from(s: AerospikeSink) -> Self {
+        Self::Aerospike(s)
+    }
+}
+
+impl From<&AerospikeSink> for OutputSink {
+    fn from(s: &AerospikeSink) -> Self {
+        Self::Aerospike(s.to_owned())
+    }
+}
+
+impl From<JdbcSink> for OutputSink {
+    fn from(s: JdbcSink) -> Self {
+        Self::Jdbc(s)
+    }
+}
+
+impl From<&JdbcSink> for OutputSink {
+    fn from(s: &JdbcSink) -> Self {
+        Self::Jdbc(s.to_owned())
+    }
+}
+
+impl From<HdfsSink> for OutputSink {
+    fn from(s: HdfsSink) -> Self {
+        Self::Hdfs(s)
+    }
+}
+
+impl From<&HdfsSink> for OutputSink {
+    fn from(s: &HdfsSink) -> Self {
+        Self::Hdfs(s.to_owned())
+    }
+}
+
+impl From<MonitoringSink> for OutputSink {
+    fn from(s: MonitoringSink) -> Self {
+        Self::Monitoring(s)
+    }
+}
+
+impl From<&MonitoringSink> for OutputSink {
+    fn from(s: &MonitoringSink) -> Self {
+        Self::Monitoring(s.to_owned())
+    }
+}
+
 fn ser_timeout<S>(v: &Option<Duration>, ser: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
@@ -88,29 +809,210 @@ where
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+fn ser_seconds<S>(v: &Option<Duration>, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match v {
+        Some(dur) => ser.serialize_i64(dur.num_seconds()),
+        None => ser.serialize_none(),
+    }
+}
+
+fn de_seconds<'de, D>(de: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<i64>::deserialize(de)?
+        .map(|s| crate::utils::ttl_seconds(Duration::seconds(s)).map(Duration::seconds))
+        .transpose()
+        .map_err(serde::de::Error::custom)
+}
+
+fn ser_seconds_map<S>(v: &HashMap<String, Duration>, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    ser.collect_map(v.iter().map(|(k, d)| (k, d.num_seconds())))
+}
+
+fn de_seconds_map<'de, D>(de: D) -> Result<HashMap<String, Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    HashMap::<String, i64>::deserialize(de)?
+        .into_iter()
+        .map(|(k, s)| crate::utils::ttl_seconds(Duration::seconds(s)).map(|s| (k, Duration::seconds(s))))
+        .collect::<Result<_, Error>>()
+        .map_err(serde::de::Error::custom)
+}
+
+fn de_timeout<'de, D>(de: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<i64>::deserialize(de)?.map(Duration::milliseconds))
+}
+
+fn de_end_time_format<'de, D>(de: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let format = String::deserialize(de)?;
+    if format != END_TIME_FORMAT {
+        return Err(serde::de::Error::custom(format!(
+            "unsupported end time format '{}', expected '{}'",
+            format, END_TIME_FORMAT
+        )));
+    }
+    Ok(format)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MaterializationOperation {
     pub name: String,
     #[serde(with = "job_date_format")]
     pub end_time: DateTime<Utc>,
-    pub end_time_format: &'static str,
+    #[serde(deserialize_with = "de_end_time_format")]
+    pub end_time_format: String,
     pub resolution: DateTimeResolution,
     #[serde(rename = "output")]
     pub sinks: Vec<OutputSink>,
+    /**
+     * Aggregate window features incrementally from the output of the previous cutoff
+     */
+    #[serde(rename = "enableIncremental", default, skip_serializing_if = "std::ops::Not::not")]
+    pub enable_incremental: bool,
 }
 
-#[derive(Clone, Debug, Serialize)]
+/**
+ * Gen job config of one cutoff, can be read back from the config to submit it with `submit_gen_from_configs`
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MaterializationSettings {
     pub operational: MaterializationOperation,
     #[serde(rename = "features")]
     pub feature_names: Vec<String>,
 }
 
+/**
+ * Planned cutoffs of a feature generation job, for external orchestrators submitting each cutoff
+ * with `submit_gen_from_configs`
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleManifest {
+    pub job_name: String,
+    pub input_path: String,
+    pub feature_config: String,
+    pub secret_keys: Vec<String>,
+    pub python_files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_jar_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reference_files: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub configuration: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env_vars: HashMap<String, String>,
+    /**
+     * Secrets holding the access keys of the sink storage accounts, keyed by storage host,
+     * the keys are resolved when the requests of the manifest are submitted
+     */
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub storage_key_names: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jdbc_driver: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub jdbc_properties: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cosmos_key_names: Vec<String>,
+    /**
+     * Ordered from the newest cutoff to the oldest, same as the requests built by the job builder
+     */
+    pub windows: Vec<ScheduledWindow>,
+}
+
+impl ScheduleManifest {
+    pub fn load<T>(path: T) -> Result<Self, Error>
+    where
+        T: AsRef<std::path::Path>,
+    {
+        Ok(serde_json::from_reader(std::fs::File::open(path)?)?)
+    }
+
+    /**
+     * Options to submit the gen config of each window
+     */
+    pub fn job_options(&self) -> ConfigJobOptions {
+        ConfigJobOptions {
+            input_path: self.input_path.to_owned(),
+            main_jar_path: self.main_jar_path.to_owned(),
+            python_files: self.python_files.to_owned(),
+            reference_files: self.reference_files.to_owned(),
+            secret_keys: self.secret_keys.to_owned(),
+            configuration: self.configuration.to_owned(),
+            workspace_dir: self.workspace_dir.to_owned(),
+            env_vars: self.env_vars.to_owned(),
+            storage_key_names: self.storage_key_names.to_owned(),
+            jdbc_driver: self.jdbc_driver.to_owned(),
+            jdbc_properties: self.jdbc_properties.to_owned(),
+            cosmos_key_names: self.cosmos_key_names.to_owned(),
+            ..ConfigJobOptions::new(&self.job_name)
+        }
+    }
+
+    /**
+     * Request of the window, same as the one built by the job builder except the job key
+     */
+    pub fn gen_request(&self, window: &ScheduledWindow) -> SubmitJobRequest {
+        let mut request = self
+            .job_options()
+            .gen_request(&self.feature_config, &window.gen_config_str());
+        request.overwrite_paths = window.overwrite_paths.to_owned();
+        request.latest_pointers = window.latest_pointers.to_owned();
+        request
+    }
+}
+
+/**
+ * A cutoff of the generation job, covers `(start, end]`
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub gen_config: MaterializationSettings,
+    /**
+     * Output folders to be cleared before the job of the window is submitted
+     */
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overwrite_paths: Vec<String>,
+    /**
+     * Pointer files to be updated after the job of the window succeeded
+     */
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub latest_pointers: HashMap<String, String>,
+}
+
+impl ScheduledWindow {
+    /**
+     * Gen config content to be passed to `submit_gen_from_configs`
+     */
+    pub fn gen_config_str(&self) -> String {
+        serde_json::to_string_pretty(&self.gen_config).unwrap()
+    }
+}
+
 pub struct MaterializationSettingsBuilder {
     pub(crate) name: String,
     pub(crate) sinks: Vec<OutputSink>,
     pub(crate) features: Vec<String>,
+    pub(crate) incremental: bool,
 }
 
 impl MaterializationSettingsBuilder {
@@ -119,9 +1021,18 @@ impl MaterializationSettingsBuilder {
             name: name.to_string(),
             sinks: Default::default(),
             features: features.to_owned(),
+            incremental: false,
         }
     }
 
+    /**
+     * Aggregate window features incrementally from the output of the previous cutoff
+     */
+    pub fn incremental(&mut self, incremental: bool) -> &mut Self {
+        self.incremental = incremental;
+        self
+    }
+
     pub fn sink<T>(&mut self, sink: T) -> &mut Self
     where
         T: Into<OutputSink>,
@@ -139,6 +1050,14 @@ impl MaterializationSettingsBuilder {
         self
     }
 
+    /**
+     * Also compute statistics of the features and write them into the monitoring table
+     */
+    pub fn monitoring(&mut self, sink: MonitoringSink) -> &mut Self {
+        self.sinks.push(sink.into());
+        self
+    }
+
     pub fn build(
         &self,
         start: DateTime<Utc>,
@@ -149,33 +1068,35 @@ impl MaterializationSettingsBuilder {
             return Err(Error::InvalidTimeRange(start, end));
         }
         let seconds = (end - start).num_seconds();
-        let step_sec = match step {
-            DateTimeResolution::Daily => 86400,
-            DateTimeResolution::Hourly => 3600,
-        };
+        let step_sec = step.duration().num_seconds();
         let ret: Vec<MaterializationSettings> = (0..seconds)
             .step_by(step_sec as usize)
-            .map(|delta| {
-                let end_time = end - Duration::seconds(delta);
-                MaterializationSettings {
-                    operational: MaterializationOperation {
-                        name: self.name.clone(),
-                        end_time,
-                        end_time_format: END_TIME_FORMAT,
-                        resolution: step,
-                        sinks: self.sinks.clone(),
-                    },
-                    feature_names: self.features.clone(),
-                }
-            })
+            .map(|delta| self.cutoff(end - Duration::seconds(delta), step))
             .collect();
         Ok(ret)
     }
+
+    /**
+     * Settings of the single cutoff ending at `end_time`
+     */
+    pub(crate) fn cutoff(&self, end_time: DateTime<Utc>, step: DateTimeResolution) -> MaterializationSettings {
+        MaterializationSettings {
+            operational: MaterializationOperation {
+                name: self.name.clone(),
+                end_time,
+                end_time_format: END_TIME_FORMAT.to_string(),
+                resolution: step,
+                sinks: self.sinks.iter().map(|s| s.for_cutoff(end_time)).collect(),
+                enable_incremental: self.incremental,
+            },
+            feature_names: self.features.clone(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::{Duration, Utc};
+    use chrono::{Duration, TimeZone, Utc};
 
     use crate::*;
 
@@ -185,6 +1106,11 @@ mod tests {
             table_name: "table1".to_string(),
             streaming: true,
             streaming_timeout: Some(Duration::seconds(10)),
+            encoding: None,
+            alias: None,
+            key_prefix: None,
+            ttl: None,
+            feature_ttl: Default::default(),
         };
 
         println!("{}", serde_json::to_string_pretty(&rs).unwrap());
@@ -193,9 +1119,185 @@ mod tests {
             table_name: "table1".to_string(),
             streaming: true,
             streaming_timeout: None,
+            encoding: None,
+            alias: None,
+            key_prefix: None,
+            ttl: None,
+            feature_ttl: Default::default(),
         });
 
         println!("{}", serde_json::to_string_pretty(&rs).unwrap());
+
+        let v = serde_json::to_value(OutputSink::from(RedisSink::new("table1"))).unwrap();
+        assert!(v["params"].get("encoding").is_none());
+        let v = serde_json::to_value(OutputSink::from(RedisSink::new("table1").encoding(RedisEncoding::Json))).unwrap();
+        assert_eq!(v["params"]["encoding"], "json");
+        assert!(v["params"].get("ttlSeconds").is_none());
+
+        let s = RedisSink::new("table1")
+            .key_prefix("team1:")
+            .ttl(Duration::days(1))
+            .unwrap()
+            .feature_ttl("f_trip_count", Duration::hours(1))
+            .unwrap();
+        let v = serde_json::to_value(OutputSink::from(&s)).unwrap();
+        assert_eq!(v["params"]["keyPrefix"], "team1:");
+        assert_eq!(v["params"]["ttlSeconds"], 86400);
+        assert_eq!(v["params"]["featureTtlSeconds"], serde_json::json!({"f_trip_count": 3600}));
+        let parsed: OutputSink = serde_json::from_value(v).unwrap();
+        assert!(matches!(parsed, OutputSink::Redis(r) if r.feature_ttl["f_trip_count"] == Duration::hours(1)));
+        assert!(matches!(RedisSink::new("table1").ttl(Duration::zero()), Err(Error::InvalidTtl(_))));
+        assert!(matches!(
+            RedisSink::new("table1").feature_ttl("f_trip_count", Duration::milliseconds(500)),
+            Err(Error::InvalidTtl(_))
+        ));
+        assert!(serde_json::from_value::<RedisSink>(serde_json::json!({"table_name": "table1", "streaming": false, "ttlSeconds": -1})).is_err());
+
+        let s = OutputSink::from(MonitoringSink::new("feature_stats"));
+        let v = serde_json::to_value(&s).unwrap();
+        assert_eq!(
+            v,
+            serde_json::json!({"name": "MONITORING", "params": {"table_name": "feature_stats"}})
+        );
+        assert_eq!(s.get_secret_keys().len(), 3);
+    }
+
+    #[test]
+    fn ser_kafka_sink() {
+        let v = serde_json::to_value(OutputSink::from(KafkaSink::event_hub("ns1", "features"))).unwrap();
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "name": "KAFKA",
+                "params": {"brokers": ["ns1.servicebus.windows.net:9093"], "topic": "features", "streaming": false}
+            })
+        );
+        let s = KafkaSink::new(&["broker1:9092", "broker2:9092"], "t1").streaming(Some(Duration::seconds(30)));
+        let v = serde_json::to_value(OutputSink::from(&s)).unwrap();
+        assert_eq!(v["params"]["brokers"][1], "broker2:9092");
+        assert_eq!(v["params"]["streaming"], true);
+        assert_eq!(v["params"]["timeoutMs"], 30000);
+    }
+
+    #[test]
+    fn ser_cosmos_sink() {
+        let s = CosmosDbSink::new("cosmos1", "https://cosmos1.documents.azure.com:443/", "feathr", "features");
+        let v = serde_json::to_value(OutputSink::from(&s)).unwrap();
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "name": "COSMOSDB",
+                "params": {
+                    "endpoint": "https://cosmos1.documents.azure.com:443/",
+                    "database": "feathr",
+                    "collection": "features"
+                }
+            })
+        );
+        assert_eq!(OutputSink::from(s).get_cosmos_key_name(), Some("cosmos1_KEY".to_string()));
+    }
+
+    #[test]
+    fn ser_aerospike_sink() {
+        let s = AerospikeSink::new("as1", "aerospike.internal", "feathr").set("user_features");
+        let v = serde_json::to_value(OutputSink::from(&s)).unwrap();
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "name": "AEROSPIKE",
+                "params": {
+                    "seedhost": "aerospike.internal",
+                    "port": 3000,
+                    "namespace": "feathr",
+                    "setname": "user_features",
+                    "user": "${as1_USER}",
+                    "password": "${as1_PASSWORD}"
+                }
+            })
+        );
+        assert_eq!(OutputSink::from(s).get_secret_keys(), vec!["as1_USER", "as1_PASSWORD"]);
+        let v = serde_json::to_value(OutputSink::from(AerospikeSink::new("as2", "h", "ns").port(3100))).unwrap();
+        assert_eq!(v["params"]["port"], 3100);
+        assert!(v["params"].get("setname").is_none());
+    }
+
+    #[test]
+    fn ser_jdbc_sink() {
+        let s = JdbcSink::new("sql1", "jdbc:postgresql://db:5432/features", "public.user_features")
+            .driver("org.postgresql.Driver")
+            .auth(JdbcSourceAuth::Userpass);
+        let v = serde_json::to_value(OutputSink::from(&s)).unwrap();
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "name": "JDBC",
+                "params": {
+                    "url": "jdbc:postgresql://db:5432/features",
+                    "dbtable": "public.user_features",
+                    "driver": "org.postgresql.Driver",
+                    "type": "jdbc",
+                    "user": "${sql1_USER}",
+                    "password": "${sql1_PASSWORD}"
+                }
+            })
+        );
+        assert_eq!(OutputSink::from(s).get_secret_keys(), vec!["sql1_USER", "sql1_PASSWORD"]);
+        let s = JdbcSink::new("sql2", "jdbc:sqlserver://db", "t").auth(JdbcSourceAuth::Token);
+        assert_eq!(s.get_secret_keys(), vec!["sql2_TOKEN"]);
+        let v = serde_json::to_value(OutputSink::from(JdbcSink::new("sql3", "jdbc:h2:mem:", "t"))).unwrap();
+        assert_eq!(v["params"]["anonymous"], true);
+        let s: OutputSink = serde_json::from_value(v).unwrap();
+        assert!(s.get_secret_keys().is_empty());
+
+        // Partial credentials are rejected instead of falling back to anonymous
+        let v = serde_json::json!({
+            "name": "JDBC",
+            "params": {"url": "jdbc:h2:mem:", "dbtable": "t", "type": "jdbc", "user": "${sql4_USER}"}
+        });
+        assert!(serde_json::from_value::<OutputSink>(v).is_err());
+    }
+
+    #[test]
+    fn ser_hdfs_sink() {
+        let s: OutputSink = HdfsSink::with_account_key(
+            "abfss://output@account2.dfs.core.windows.net/features",
+            "account2",
+        )
+        .into();
+        let v = serde_json::to_value(&s).unwrap();
+        assert_eq!(v["name"], "HDFS");
+        assert_eq!(
+            v["params"]["path"],
+            "abfss://output@account2.dfs.core.windows.net/features"
+        );
+        assert!(v["params"].get("account_key_name").is_none());
+        assert!(v["params"].get("outputFormat").is_none());
+        assert_eq!(
+            s.get_storage_key_name().unwrap(),
+            Some(("account2.dfs.core.windows.net".to_string(), "account2_STORAGE_KEY".to_string()))
+        );
+        assert_eq!(
+            OutputSink::from(HdfsSink::new("abfss://output@account2.dfs.core.windows.net/"))
+                .get_storage_key_name()
+                .unwrap(),
+            None
+        );
+
+        let sink = HdfsSink::new("abfss://output@account2.dfs.core.windows.net/features")
+            .format(SourceFormat::Parquet)
+            .time_partitioned(DateTimeResolution::Daily);
+        let end = Utc.with_ymd_and_hms(2022, 5, 3, 0, 0, 0).unwrap();
+        let v = serde_json::to_value(OutputSink::from(&sink).for_cutoff(end)).unwrap();
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "name": "HDFS",
+                "params": {
+                    "path": "abfss://output@account2.dfs.core.windows.net/features/2022/05/03",
+                    "outputFormat": "parquet"
+                }
+            })
+        );
     }
 
     #[test]
@@ -213,5 +1315,119 @@ mod tests {
         println!("{}", serde_json::to_string_pretty(&b).unwrap());
         assert_eq!(b.len(), 3);
         assert_eq!(b[1].operational.name, b[0].operational.name);
+
+        let b = MaterializationSettingsBuilder::new("some_name", &["abc".to_string()])
+            .incremental(true)
+            .build(now - Duration::hours(1), now, DateTimeResolution::Hourly)
+            .unwrap();
+        let v = serde_json::to_value(&b[0]).unwrap();
+        assert_eq!(v["operational"]["enableIncremental"], true);
+
+        let t = Utc.with_ymd_and_hms(2022, 5, 3, 10, 30, 0).unwrap();
+        assert_eq!(DateTimeResolution::Daily.floor(t), Utc.with_ymd_and_hms(2022, 5, 3, 0, 0, 0).unwrap());
+        assert_eq!(DateTimeResolution::Hourly.ceil(t), Utc.with_ymd_and_hms(2022, 5, 3, 11, 0, 0).unwrap());
+        assert_eq!(DateTimeResolution::Daily.ceil(DateTimeResolution::Daily.floor(t)), DateTimeResolution::Daily.floor(t));
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let end = Utc.with_ymd_and_hms(2022, 5, 3, 0, 0, 0).unwrap();
+        let b = MaterializationSettingsBuilder::new("some_name", &["abc".to_string()])
+            .sink(RedisSink::with_timeout("table1", Duration::seconds(10)).encoding(RedisEncoding::Json))
+            .sink(HdfsSink::new("abfss://output@account2.dfs.core.windows.net/features").format(SourceFormat::Parquet))
+            .sink(KafkaSink::new(&["broker1:9092"], "t1"))
+            .sink(CosmosDbSink::new("cosmos1", "https://cosmos1.documents.azure.com:443/", "feathr", "features"))
+            .sink(JdbcSink::new("sql1", "jdbc:postgresql://db:5432/features", "t").auth(JdbcSourceAuth::Userpass))
+            .sink(AerospikeSink::new("as1", "aerospike.internal", "feathr"))
+            .sink(JdbcSink::new("sql2", "jdbc:h2:mem:", "t"))
+            .build(end - Duration::days(1), end, DateTimeResolution::Daily)
+            .unwrap();
+        let conf = serde_json::to_string_pretty(&b[0]).unwrap();
+        let parsed: MaterializationSettings = serde_json::from_str(&conf).unwrap();
+        assert_eq!(parsed.operational.end_time, end);
+        assert_eq!(parsed.operational.resolution, DateTimeResolution::Daily);
+        assert_eq!(parsed.feature_names, vec!["abc"]);
+        assert_eq!(serde_json::to_string_pretty(&parsed).unwrap(), conf);
+        assert!(!conf.contains("enableIncremental"));
+
+        let conf = conf.replace("yyyy-MM-dd HH:mm:ss", "yyyyMMdd");
+        assert!(serde_json::from_str::<MaterializationSettings>(&conf).is_err());
+    }
+
+    #[test]
+    fn redis_alias() {
+        let sink = OutputSink::from(RedisSink::new("trips_v2").alias("trips"));
+        assert_eq!(
+            serde_json::to_value(&sink).unwrap(),
+            serde_json::json!({"name": "REDIS", "params": {"table_name": "trips_v2", "streaming": false}})
+        );
+        assert_eq!(
+            sink.get_redis_alias(),
+            Some(("trips".to_string(), "trips_v2".to_string()))
+        );
+        assert_eq!(OutputSink::from(RedisSink::new("trips")).get_redis_alias(), None);
+    }
+
+    #[test]
+    fn time_partitioned_sink() {
+        let end = Utc.with_ymd_and_hms(2022, 5, 3, 0, 0, 0).unwrap();
+        let sink = HdfsSink::new("abfss://output@account2.dfs.core.windows.net/features/")
+            .time_partitioned(DateTimeResolution::Hourly)
+            .update_latest();
+        let b = MaterializationSettingsBuilder::new("some_name", &["abc".to_string()])
+            .sink(&sink)
+            .build(end - Duration::hours(2), end, DateTimeResolution::Hourly)
+            .unwrap();
+        let paths: Vec<String> = b
+            .iter()
+            .map(|s| match &s.operational.sinks[0] {
+                OutputSink::Hdfs(h) => h.path.to_owned(),
+                _ => panic!("unexpected sink"),
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "abfss://output@account2.dfs.core.windows.net/features/2022/05/03/00",
+                "abfss://output@account2.dfs.core.windows.net/features/2022/05/02/23",
+            ]
+        );
+        assert_eq!(
+            OutputSink::from(&sink).get_latest_pointer(end),
+            Some((
+                "abfss://output@account2.dfs.core.windows.net/features/_LATEST".to_string(),
+                paths[0].to_owned()
+            ))
+        );
+        assert_eq!(
+            OutputSink::from(HdfsSink::new("abfss://a@b.dfs.core.windows.net/").update_latest())
+                .get_latest_pointer(end),
+            None
+        );
+        assert_eq!(
+            sink.get_success_marker(end).unwrap(),
+            "abfss://output@account2.dfs.core.windows.net/features/2022/05/03/00/_SUCCESS"
+        );
+        assert_eq!(HdfsSink::new("abfss://a@b.dfs.core.windows.net/").get_success_marker(end), None);
+    }
+
+    #[test]
+    fn overwrite_existing_sink() {
+        let end = Utc.with_ymd_and_hms(2022, 5, 3, 0, 0, 0).unwrap();
+        let sink = HdfsSink::new("abfss://output@account2.dfs.core.windows.net/features/")
+            .time_partitioned(DateTimeResolution::Daily)
+            .overwrite_existing();
+        let b = MaterializationSettingsBuilder::new("some_name", &["abc".to_string()])
+            .sink(&sink)
+            .build(end - Duration::days(1), end, DateTimeResolution::Daily)
+            .unwrap();
+        assert_eq!(
+            b[0].operational.sinks[0].get_overwrite_path(),
+            Some("abfss://output@account2.dfs.core.windows.net/features/2022/05/03".to_string())
+        );
+        assert_eq!(
+            OutputSink::from(HdfsSink::new("abfss://a@b.dfs.core.windows.net/")).get_overwrite_path(),
+            None
+        );
     }
 }
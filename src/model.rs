@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use chrono::Duration;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Serialize};
 
-use crate::utils::{dur_to_string, str_to_dur};
+use crate::utils::IntoDuration;
 
 #[allow(non_camel_case_types)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -217,30 +219,6 @@ pub struct ExpressionDef {
     pub sql_expr: String,
 }
 
-fn ser_opt_dur<S>(d: &Option<Duration>, s: S) -> std::result::Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    match d.to_owned() {
-        Some(d) => s.serialize_str(&dur_to_string(d)),
-        None => s.serialize_none(),
-    }
-}
-
-fn des_opt_dur<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = Option::<String>::deserialize(deserializer)?;
-    match s {
-        Some(s) => match str_to_dur(&s) {
-            Ok(d) => Ok(Some(d)),
-            Err(e) => Err(serde::de::Error::custom(e.to_string())),
-        },
-        None => Ok(None),
-    }
-}
-
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Transformation {
@@ -255,8 +233,7 @@ pub enum Transformation {
         agg_func: Option<Aggregation>,
         #[serde(
             skip_serializing_if = "Option::is_none",
-            serialize_with = "ser_opt_dur",
-            deserialize_with = "des_opt_dur",
+            with = "crate::hocon_duration::option",
             default
         )]
         window: Option<Duration>,
@@ -273,20 +250,94 @@ pub enum Transformation {
 }
 
 impl Transformation {
-    pub fn window_agg(
+    /**
+     * `window` is a `chrono::Duration`, a `std::time::Duration` or a HOCON duration string, e.g. `"7d"`
+     */
+    pub fn window_agg<D: IntoDuration>(
         def_expr: &str,
         agg_func: Aggregation,
-        window: Duration,
+        window: D,
     ) -> Result<Self, crate::Error> {
         Ok(Self::WindowAgg {
             def_expr: def_expr.to_string(),
             agg_func: Some(agg_func),
-            window: Some(window),
+            window: Some(window.into_duration()?),
             group_by: None,
             filter: None,
             limit: None,
         })
     }
+
+    /**
+     * Replace `{name}` placeholders in the expressions with values in `params`, `{{` and `}}` are literal braces.
+     * `feature` is only used in the error message.
+     */
+    pub fn render(&self, feature: &str, params: &HashMap<String, String>) -> Result<Self, crate::Error> {
+        let render = |s: &str| render_template(feature, s, params);
+        Ok(match self {
+            Transformation::Expression { def } => Transformation::Expression {
+                def: ExpressionDef {
+                    sql_expr: render(&def.sql_expr)?,
+                },
+            },
+            Transformation::WindowAgg {
+                def_expr,
+                agg_func,
+                window,
+                group_by,
+                filter,
+                limit,
+            } => Transformation::WindowAgg {
+                def_expr: render(def_expr)?,
+                agg_func: agg_func.to_owned(),
+                window: window.to_owned(),
+                group_by: group_by.as_deref().map(render).transpose()?,
+                filter: filter.as_deref().map(render).transpose()?,
+                limit: limit.to_owned(),
+            },
+            Transformation::Udf { name } => Transformation::Udf {
+                name: render(name)?,
+            },
+        })
+    }
+}
+
+fn render_template(
+    feature: &str,
+    template: &str,
+    params: &HashMap<String, String>,
+) -> Result<String, crate::Error> {
+    let mut ret = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                ret.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                ret.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) if c.is_alphanumeric() || c == '_' => name.push(c),
+                        _ => return Err(crate::Error::InvalidTemplate(template.to_string())),
+                    }
+                }
+                let value = params.get(&name).ok_or_else(|| {
+                    crate::Error::MissingTemplateParameter(feature.to_string(), name)
+                })?;
+                ret.push_str(value);
+            }
+            '}' => return Err(crate::Error::InvalidTemplate(template.to_string())),
+            c => ret.push(c),
+        }
+    }
+    Ok(ret)
 }
 
 impl<T> From<T> for Transformation
@@ -325,8 +376,7 @@ pub(crate) enum DerivedTransformation {
         agg_func: Option<Aggregation>,
         #[serde(
             skip_serializing_if = "Option::is_none",
-            serialize_with = "ser_opt_dur",
-            deserialize_with = "des_opt_dur",
+            with = "crate::hocon_duration::option",
             default
         )]
         window: Option<Duration>,
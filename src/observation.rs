@@ -1,11 +1,37 @@
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::Serialize;
 
+use crate::{Error, LocalSample, LocalValue};
+
+// Feathr pattern, chrono format, and whether the pattern has time part
+const TIMESTAMP_PATTERNS: &[(&str, &str, bool)] = &[
+    ("yyyy-MM-dd HH:mm:ss", "%Y-%m-%d %H:%M:%S", true),
+    ("yyyy-MM-dd'T'HH:mm:ss", "%Y-%m-%dT%H:%M:%S", true),
+    ("yyyy-MM-dd'T'HH:mm:ss'Z'", "%Y-%m-%dT%H:%M:%SZ", true),
+    ("yyyy/MM/dd HH:mm:ss", "%Y/%m/%d %H:%M:%S", true),
+    ("MM/dd/yyyy HH:mm:ss", "%m/%d/%Y %H:%M:%S", true),
+    ("dd/MM/yyyy HH:mm:ss", "%d/%m/%Y %H:%M:%S", true),
+    ("yyyy-MM-dd", "%Y-%m-%d", false),
+    ("yyyy/MM/dd", "%Y/%m/%d", false),
+    ("MM/dd/yyyy", "%m/%d/%Y", false),
+    ("dd/MM/yyyy", "%d/%m/%Y", false),
+    ("yyyyMMdd", "%Y%m%d", false),
+];
+
+// Epoch values between 2000-01-01 and 2100-01-01
+const EPOCH_RANGE: std::ops::Range<i64> = 946684800..4102444800;
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ObservationSettings {
     pub observation_path: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub settings: Option<ObservationInnerSettings>,
+    /**
+     * Columns of the observation data if known, joined feature columns must not collide with them
+     */
+    #[serde(skip)]
+    pub columns: Vec<String>,
 }
 
 impl ObservationSettings {
@@ -20,6 +46,7 @@ impl ObservationSettings {
                     },
                 },
             }),
+            columns: vec![],
         }
     }
 
@@ -27,8 +54,112 @@ impl ObservationSettings {
         Self {
             observation_path: observation_path.to_string(),
             settings: None,
+            columns: vec![],
         }
     }
+
+    pub fn columns<T>(mut self, columns: &[T]) -> Self
+    where
+        T: ToString,
+    {
+        self.columns = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    /**
+     * Detect the timestamp column and its format from the sample of the observation data,
+     * returns `Error::AmbiguousTimestampColumn` if more than one column or format matches
+     */
+    pub fn detect(observation_path: &str, sample: &LocalSample) -> Result<Self, Error> {
+        let (column, format) = detect_timestamp_column(sample)?;
+        Ok(Self::new(observation_path, &column, &format).columns(&sample.columns))
+    }
+}
+
+fn is_timestamp_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("time") || name.contains("date") || name == "ts" || name.ends_with("_ts")
+}
+
+/**
+ * Formats matching all non-null values of the column
+ */
+fn matching_formats(values: &[&LocalValue]) -> Vec<&'static str> {
+    if values.is_empty() {
+        return vec![];
+    }
+    let longs: Option<Vec<i64>> = values
+        .iter()
+        .map(|v| match v {
+            LocalValue::Long(v) => Some(*v),
+            _ => None,
+        })
+        .collect();
+    let mut ret = vec![];
+    if let Some(longs) = &longs {
+        if longs.iter().all(|v| EPOCH_RANGE.contains(v)) {
+            ret.push("epoch");
+        }
+        if longs.iter().all(|v| EPOCH_RANGE.contains(&(v / 1000))) {
+            ret.push("epoch_millis");
+        }
+    }
+    ret.extend(
+        TIMESTAMP_PATTERNS
+            .iter()
+            .filter(|(_, format, has_time)| {
+                values.iter().all(|v| {
+                    let s = match v {
+                        LocalValue::String(s) => s.to_owned(),
+                        LocalValue::Long(v) => v.to_string(),
+                        _ => return false,
+                    };
+                    if *has_time {
+                        NaiveDateTime::parse_from_str(&s, format).is_ok()
+                    } else {
+                        NaiveDate::parse_from_str(&s, format).is_ok()
+                    }
+                })
+            })
+            .map(|(pattern, _, _)| *pattern),
+    );
+    ret
+}
+
+/**
+ * Detect the timestamp column and its format, columns with timestamp-like names are preferred
+ */
+pub(crate) fn detect_timestamp_column(sample: &LocalSample) -> Result<(String, String), Error> {
+    let mut candidates: Vec<(&str, Vec<&str>)> = sample
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let values: Vec<&LocalValue> = sample
+                .rows
+                .iter()
+                .filter_map(|r| r.get(i))
+                .filter(|v| **v != LocalValue::Null)
+                .collect();
+            (column.as_str(), matching_formats(&values))
+        })
+        .filter(|(_, formats)| !formats.is_empty())
+        .collect();
+    if candidates.len() > 1 && candidates.iter().any(|(c, _)| is_timestamp_name(c)) {
+        candidates.retain(|(c, _)| is_timestamp_name(c));
+    }
+    match candidates.as_slice() {
+        [] => Err(Error::TimestampColumnNotFound(sample.columns.join(", "))),
+        [(column, formats)] if formats.len() == 1 => {
+            Ok((column.to_string(), formats[0].to_string()))
+        }
+        _ => Err(Error::AmbiguousTimestampColumn(
+            candidates
+                .iter()
+                .flat_map(|(c, formats)| formats.iter().map(move |f| format!("{} ({})", c, f)))
+                .collect(),
+        )),
+    }
 }
 
 impl<T> From<T> for ObservationSettings
@@ -97,3 +228,40 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_timestamp() {
+        let sample = LocalSample::from_csv(
+            "id,amount,event_time,created\n1,10,2022-07-01 10:00:00,1656669600\n2,20,2022-07-02 11:30:00,1656756000\n",
+        )
+        .unwrap();
+        assert_eq!(
+            detect_timestamp_column(&sample).unwrap(),
+            ("event_time".to_string(), "yyyy-MM-dd HH:mm:ss".to_string())
+        );
+
+        let sample = LocalSample::from_csv("id,ts\n1,1656669600000\n2,1656756000000\n").unwrap();
+        let settings = ObservationSettings::detect("obs.csv", &sample).unwrap();
+        let ts = settings.settings.unwrap().join_time_settings.timestamp_column;
+        assert_eq!(ts.def, "ts");
+        assert!(matches!(ts.format, TimestampColumnFormat::EpochMillis));
+        assert_eq!(settings.columns, vec!["id", "ts"]);
+
+        // Both MM/dd/yyyy and dd/MM/yyyy match
+        let sample = LocalSample::from_csv("id,date\n1,01/02/2022\n2,03/04/2022\n").unwrap();
+        assert!(matches!(
+            detect_timestamp_column(&sample),
+            Err(Error::AmbiguousTimestampColumn(c)) if c.len() == 2
+        ));
+
+        let sample = LocalSample::from_csv("id,name\n1,a\n").unwrap();
+        assert!(matches!(
+            detect_timestamp_column(&sample),
+            Err(Error::TimestampColumnNotFound(_))
+        ));
+    }
+}
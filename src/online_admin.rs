@@ -0,0 +1,322 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use log::debug;
+use once_cell::sync::Lazy;
+use redis::{aio::ConnectionManager, Script};
+
+use crate::{
+    online_client::{ALIAS_KEY_PREFIX, KEY_DELIMITER},
+    Error, OnlineClient,
+};
+
+const DEFAULT_SCAN_COUNT: usize = 1000;
+// Keys passed to one script call, stays below the limit of Lua `unpack`
+const UNLINK_BATCH_SIZE: usize = 1000;
+
+/**
+ * Summary of a table in the online store
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OnlineTableInfo {
+    pub name: String,
+    /**
+     * Number of keys, i.e. entities, in the table
+     */
+    pub key_count: usize,
+    /**
+     * Features stored in a sampled key of the table
+     */
+    pub features: Vec<String>,
+    /**
+     * Aliases pointing to the table
+     */
+    pub aliases: Vec<String>,
+}
+
+/**
+ * Deletes the keys in `KEYS` unless an alias matching `ARGV[1]` points to the table `ARGV[2]`, returns -1 in that case,
+ * so an alias created during the drop can't be left pointing to a half deleted table
+ */
+static DROP_KEYS_SCRIPT: Lazy<Script> = Lazy::new(|| {
+    Script::new(
+        r"local cursor = '0'
+repeat
+  local page = redis.call('SCAN', cursor, 'MATCH', ARGV[1], 'COUNT', 1000)
+  cursor = page[1]
+  for _, alias in ipairs(page[2]) do
+    if redis.call('GET', alias) == ARGV[2] then
+      return -1
+    end
+  end
+until cursor == '0'
+if #KEYS == 0 then
+  return 0
+end
+return redis.call('UNLINK', unpack(KEYS))",
+    )
+});
+
+/**
+ * Lists, inspects and drops the tables written into the Redis online store by materialization jobs.
+ * Tables are key prefixes, only tables of sinks with a `key_prefix` can be told from other keys and managed.
+ * Listing and dropping scan the whole keyspace page by page and can be slow on large instances
+ */
+#[derive(Clone)]
+pub struct OnlineStoreAdmin {
+    conn: ConnectionManager,
+    key_prefix: String,
+    scan_count: usize,
+}
+
+impl std::fmt::Debug for OnlineStoreAdmin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnlineStoreAdmin")
+            .field("key_prefix", &self.key_prefix)
+            .field("scan_count", &self.scan_count)
+            .finish()
+    }
+}
+
+impl OnlineStoreAdmin {
+    /**
+     * Admin sharing the connection and the key prefix of the online client
+     */
+    pub fn new(client: &OnlineClient) -> Self {
+        Self {
+            conn: client.conn.clone(),
+            key_prefix: client.key_prefix.clone(),
+            scan_count: DEFAULT_SCAN_COUNT,
+        }
+    }
+
+    /**
+     * Only manage tables written by sinks with the `key_prefix`, e.g. `team1:`
+     */
+    pub fn key_prefix(mut self, prefix: &str) -> Self {
+        self.key_prefix = prefix.to_string();
+        self
+    }
+
+    /**
+     * Set the number of keys examined by each `SCAN` call
+     */
+    pub fn scan_count(mut self, count: usize) -> Self {
+        self.scan_count = count.max(1);
+        self
+    }
+
+    /**
+     * Names of the tables in the store, ordered by name
+     */
+    pub async fn list_tables(&self) -> Result<Vec<String>, Error> {
+        self.check_key_prefix()?;
+        let pattern = format!("{}*", escape_pattern(&self.key_prefix));
+        let mut conn = self.conn.clone();
+        let mut tables: BTreeSet<String> = Default::default();
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = self.scan_page(&mut conn, cursor, &pattern).await?;
+            tables.extend(
+                keys.iter()
+                    .filter_map(|key| key.strip_prefix(&self.key_prefix))
+                    .filter_map(table_of_key)
+                    .filter(|table| *table != ALIAS_KEY_PREFIX)
+                    .map(ToString::to_string),
+            );
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        Ok(tables.into_iter().collect())
+    }
+
+    /**
+     * Tables the aliases point to, keyed by alias
+     */
+    pub async fn list_aliases(&self) -> Result<BTreeMap<String, String>, Error> {
+        let alias_prefix = format!("{}{}", ALIAS_KEY_PREFIX, KEY_DELIMITER);
+        let pattern = format!("{}*", escape_pattern(&alias_prefix));
+        let mut conn = self.conn.clone();
+        let mut aliases = BTreeMap::new();
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = self.scan_page(&mut conn, cursor, &pattern).await?;
+            if !keys.is_empty() {
+                let tables: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
+                aliases.extend(
+                    keys.iter()
+                        .zip(tables)
+                        .filter_map(|(key, table)| Some((key.strip_prefix(&alias_prefix)?.to_string(), table?))),
+                );
+            }
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        Ok(aliases)
+    }
+
+    /**
+     * Number of keys, features and aliases of the table, `Error::OnlineTableNotFound` if the table has no key.
+     * The count is approximate if keys are written or expire during the scan
+     */
+    pub async fn table_info(&self, table: &str) -> Result<OnlineTableInfo, Error> {
+        self.check_key_prefix()?;
+        let pattern = self.table_pattern(table);
+        let mut conn = self.conn.clone();
+        let mut sample: Option<String> = None;
+        let mut key_count = 0;
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = self.scan_page(&mut conn, cursor, &pattern).await?;
+            key_count += keys.len();
+            if sample.is_none() {
+                sample = keys.into_iter().next();
+            }
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        let sample = sample.ok_or_else(|| Error::OnlineTableNotFound(table.to_string()))?;
+        let mut features: Vec<String> = redis::cmd("HKEYS").arg(&sample).query_async(&mut conn).await?;
+        features.sort();
+        Ok(OnlineTableInfo {
+            name: table.to_string(),
+            key_count,
+            features,
+            aliases: self.aliases_of(table).await?,
+        })
+    }
+
+    /**
+     * Delete all keys of the table, returns the number of keys deleted.
+     * Fails with `Error::OnlineTableInUse` if any alias points to the table, remove or switch the aliases first.
+     * Aliases are checked with each deleted page, keys deleted before an alias is created are not restored
+     */
+    pub async fn drop_table(&self, table: &str) -> Result<usize, Error> {
+        self.check_key_prefix()?;
+        let alias_pattern = format!("{}{}*", escape_pattern(ALIAS_KEY_PREFIX), KEY_DELIMITER);
+        let pattern = self.table_pattern(table);
+        let mut conn = self.conn.clone();
+        let mut deleted = 0;
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = self.scan_page(&mut conn, cursor, &pattern).await?;
+            for batch in keys.chunks(UNLINK_BATCH_SIZE) {
+                deleted += self.drop_keys(&mut conn, table, &alias_pattern, batch).await?;
+            }
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        if deleted == 0 {
+            // Dropping a table in use fails even if it has no key
+            self.drop_keys(&mut conn, table, &alias_pattern, &[]).await?;
+        }
+        debug!("{} keys of table {} deleted", deleted, table);
+        Ok(deleted)
+    }
+
+    async fn drop_keys(
+        &self,
+        conn: &mut ConnectionManager,
+        table: &str,
+        alias_pattern: &str,
+        keys: &[String],
+    ) -> Result<usize, Error> {
+        let n: i64 = DROP_KEYS_SCRIPT
+            .key(keys)
+            .arg(alias_pattern)
+            .arg(table)
+            .invoke_async(conn)
+            .await?;
+        if n < 0 {
+            return Err(Error::OnlineTableInUse(table.to_string(), self.aliases_of(table).await?));
+        }
+        Ok(n as usize)
+    }
+
+    async fn aliases_of(&self, table: &str) -> Result<Vec<String>, Error> {
+        Ok(self
+            .list_aliases()
+            .await?
+            .into_iter()
+            .filter(|(_, t)| t == table)
+            .map(|(alias, _)| alias)
+            .collect())
+    }
+
+    /**
+     * Without a prefix any `a:b` key looks like a key of table `a`, which must not be listed or dropped
+     */
+    fn check_key_prefix(&self) -> Result<(), Error> {
+        if self.key_prefix.is_empty() {
+            return Err(Error::InvalidConfig(
+                "online store admin needs the `key_prefix` of the sinks to tell Feathr tables from other keys"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn table_pattern(&self, table: &str) -> String {
+        format!(
+            "{}{}*",
+            escape_pattern(&format!("{}{}", self.key_prefix, table)),
+            KEY_DELIMITER
+        )
+    }
+
+    /**
+     * One `SCAN` call, returns the next cursor and the keys of the page, a key may be returned in several pages
+     */
+    async fn scan_page(
+        &self,
+        conn: &mut ConnectionManager,
+        cursor: u64,
+        pattern: &str,
+    ) -> Result<(u64, Vec<String>), Error> {
+        Ok(redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(pattern)
+            .arg("COUNT")
+            .arg(self.scan_count)
+            .query_async(conn)
+            .await?)
+    }
+}
+
+/**
+ * Escape glob characters of `SCAN MATCH` patterns
+ */
+fn escape_pattern(s: &str) -> String {
+    s.chars()
+        .flat_map(|c| match c {
+            '*' | '?' | '[' | ']' | '\\' => vec!['\\', c],
+            _ => vec![c],
+        })
+        .collect()
+}
+
+fn table_of_key(key: &str) -> Option<&str> {
+    key.split_once(KEY_DELIMITER).map(|(table, _)| table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_patterns() {
+        assert_eq!(escape_pattern("trips"), "trips");
+        assert_eq!(escape_pattern("t*[1]?"), "t\\*\\[1\\]\\?");
+        assert_eq!(table_of_key("trips:1:2"), Some("trips"));
+        assert_eq!(table_of_key("__feathr_alias__:trips"), Some(ALIAS_KEY_PREFIX));
+        assert_eq!(table_of_key("orphan"), None);
+    }
+}
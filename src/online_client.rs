@@ -0,0 +1,684 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use log::debug;
+use redis::{aio::ConnectionManager, ConnectionAddr, ConnectionInfo, RedisConnectionInfo};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, RedisSink, VarSource};
+
+pub(crate) const KEY_DELIMITER: &str = ":";
+// Aliases are stored as `__feathr_alias__:<alias>` pointing to the table name
+pub(crate) const ALIAS_KEY_PREFIX: &str = "__feathr_alias__";
+const DEFAULT_BATCH_SIZE: usize = 1000;
+const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+
+/**
+ * Format of feature values in Redis, Feathr runtime uses protobuf by default,
+ * JSON is easier to inspect when debugging
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedisEncoding {
+    #[default]
+    Protobuf,
+    Json,
+}
+
+impl std::str::FromStr for RedisEncoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "protobuf" => Ok(RedisEncoding::Protobuf),
+            "json" => Ok(RedisEncoding::Json),
+            _ => Err(Error::InvalidConfig(format!("Invalid Redis encoding '{}'", s))),
+        }
+    }
+}
+
+/**
+ * Feature value to be written into the online store
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeatureValue {
+    Bool(bool),
+    Int32(i32),
+    Int64(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+impl FeatureValue {
+    // Field numbers of the `FeatureValue` message used by Feathr runtime
+    const BOOLEAN_VALUE: u32 = 1;
+    const STRING_VALUE: u32 = 2;
+    const FLOAT_VALUE: u32 = 3;
+    const DOUBLE_VALUE: u32 = 4;
+    const INT_VALUE: u32 = 5;
+    const LONG_VALUE: u32 = 6;
+    const BYTES_VALUE: u32 = 30;
+
+    /**
+     * Encode the value as protobuf `FeatureValue` message, which is the format Feathr runtime writes into Redis
+     */
+    pub(crate) fn encode_proto(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        match self {
+            FeatureValue::Bool(v) => {
+                put_varint(&mut buf, (Self::BOOLEAN_VALUE << 3) as u64);
+                put_varint(&mut buf, *v as u64);
+            }
+            FeatureValue::Int32(v) => {
+                put_varint(&mut buf, (Self::INT_VALUE << 3) as u64);
+                // Negative int32 is sign-extended to 64 bits
+                put_varint(&mut buf, *v as i64 as u64);
+            }
+            FeatureValue::Int64(v) => {
+                put_varint(&mut buf, (Self::LONG_VALUE << 3) as u64);
+                put_varint(&mut buf, *v as u64);
+            }
+            FeatureValue::Float(v) => {
+                put_varint(&mut buf, ((Self::FLOAT_VALUE << 3) | 5) as u64);
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            FeatureValue::Double(v) => {
+                put_varint(&mut buf, ((Self::DOUBLE_VALUE << 3) | 1) as u64);
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            FeatureValue::String(v) => {
+                put_varint(&mut buf, ((Self::STRING_VALUE << 3) | 2) as u64);
+                put_varint(&mut buf, v.len() as u64);
+                buf.extend_from_slice(v.as_bytes());
+            }
+            FeatureValue::Bytes(v) => {
+                put_varint(&mut buf, ((Self::BYTES_VALUE << 3) | 2) as u64);
+                put_varint(&mut buf, v.len() as u64);
+                buf.extend_from_slice(v);
+            }
+        }
+        buf
+    }
+
+    /**
+     * Decode protobuf `FeatureValue` message, only the scalar fields written by `encode_proto` are supported
+     */
+    pub(crate) fn decode_proto(mut buf: &[u8]) -> Result<Self, Error> {
+        let invalid = || Error::InvalidFeatureValue("malformed protobuf message".to_string());
+        let tag = get_varint(&mut buf).ok_or_else(invalid)?;
+        let (field, wire_type) = ((tag >> 3) as u32, tag & 0x07);
+        let value = match (field, wire_type) {
+            (Self::BOOLEAN_VALUE, 0) => FeatureValue::Bool(get_varint(&mut buf).ok_or_else(invalid)? != 0),
+            (Self::INT_VALUE, 0) => FeatureValue::Int32(get_varint(&mut buf).ok_or_else(invalid)? as i32),
+            (Self::LONG_VALUE, 0) => FeatureValue::Int64(get_varint(&mut buf).ok_or_else(invalid)? as i64),
+            (Self::FLOAT_VALUE, 5) => FeatureValue::Float(f32::from_le_bytes(
+                buf.get(..4).ok_or_else(invalid)?.try_into().map_err(|_| invalid())?,
+            )),
+            (Self::DOUBLE_VALUE, 1) => FeatureValue::Double(f64::from_le_bytes(
+                buf.get(..8).ok_or_else(invalid)?.try_into().map_err(|_| invalid())?,
+            )),
+            (Self::STRING_VALUE, 2) | (Self::BYTES_VALUE, 2) => {
+                let len = get_varint(&mut buf).ok_or_else(invalid)? as usize;
+                let data = buf.get(..len).ok_or_else(invalid)?.to_vec();
+                if field == Self::STRING_VALUE {
+                    FeatureValue::String(String::from_utf8(data).map_err(|_| invalid())?)
+                } else {
+                    FeatureValue::Bytes(data)
+                }
+            }
+            _ => {
+                return Err(Error::InvalidFeatureValue(format!(
+                    "unsupported field {} with wire type {}",
+                    field, wire_type
+                )))
+            }
+        };
+        Ok(value)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            FeatureValue::Bool(v) => (*v).into(),
+            FeatureValue::Int32(v) => (*v).into(),
+            FeatureValue::Int64(v) => (*v).into(),
+            FeatureValue::Float(v) => (*v).into(),
+            FeatureValue::Double(v) => (*v).into(),
+            FeatureValue::String(v) => v.to_owned().into(),
+            FeatureValue::Bytes(v) => v.to_owned().into(),
+        }
+    }
+
+    /**
+     * Decode JSON value, integers are decoded as `Int64` and other numbers as `Double` as JSON doesn't keep the width
+     */
+    fn from_json(value: serde_json::Value) -> Result<Self, Error> {
+        let invalid = |v: &serde_json::Value| Error::InvalidFeatureValue(format!("unsupported JSON value {}", v));
+        Ok(match value {
+            serde_json::Value::Bool(v) => FeatureValue::Bool(v),
+            serde_json::Value::Number(v) => match v.as_i64() {
+                Some(i) => FeatureValue::Int64(i),
+                None => FeatureValue::Double(v.as_f64().ok_or_else(|| invalid(&v.clone().into()))?),
+            },
+            serde_json::Value::String(v) => FeatureValue::String(v),
+            serde_json::Value::Array(v) => FeatureValue::Bytes(
+                v.iter()
+                    .map(|b| b.as_u64().filter(|b| *b <= u8::MAX as u64).map(|b| b as u8))
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or_else(|| invalid(&serde_json::Value::Array(v.clone())))?,
+            ),
+            v => return Err(invalid(&v)),
+        })
+    }
+
+    /**
+     * Encode the value as a Redis hash field value
+     */
+    pub(crate) fn encode(&self, encoding: RedisEncoding) -> String {
+        match encoding {
+            RedisEncoding::Protobuf => base64::encode_config(self.encode_proto(), base64::STANDARD),
+            RedisEncoding::Json => self.to_json().to_string(),
+        }
+    }
+
+    /**
+     * Decode the Redis hash field value
+     */
+    pub fn decode(value: &str, encoding: RedisEncoding) -> Result<Self, Error> {
+        match encoding {
+            RedisEncoding::Protobuf => Self::decode_proto(&base64::decode_config(value, base64::STANDARD)?),
+            RedisEncoding::Json => Self::from_json(serde_json::from_str(value)?),
+        }
+    }
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn get_varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut ret = 0u64;
+    for (i, b) in buf.iter().enumerate().take(10) {
+        ret |= ((b & 0x7f) as u64) << (7 * i);
+        if b & 0x80 == 0 {
+            *buf = &buf[i + 1..];
+            return Some(ret);
+        }
+    }
+    None
+}
+
+/**
+ * A row of feature values sharing the same key
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeatureRow {
+    pub key: Vec<String>,
+    pub features: HashMap<String, FeatureValue>,
+}
+
+impl FeatureRow {
+    pub fn new<T>(key: &[T]) -> Self
+    where
+        T: ToString,
+    {
+        Self {
+            key: key.iter().map(|k| k.to_string()).collect(),
+            features: Default::default(),
+        }
+    }
+
+    pub fn feature<T>(mut self, name: &str, value: T) -> Self
+    where
+        T: Into<FeatureValue>,
+    {
+        self.features.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+macro_rules! impl_from_value {
+    ($t:ty, $variant:ident) => {
+        impl From<$t> for FeatureValue {
+            fn from(v: $t) -> Self {
+                FeatureValue::$variant(v.into())
+            }
+        }
+    };
+}
+
+impl_from_value!(bool, Bool);
+impl_from_value!(i32, Int32);
+impl_from_value!(i64, Int64);
+impl_from_value!(f32, Float);
+impl_from_value!(f64, Double);
+impl_from_value!(String, String);
+impl_from_value!(&str, String);
+impl_from_value!(Vec<u8>, Bytes);
+
+/**
+ * Client of the Redis online store
+ */
+#[derive(Clone)]
+pub struct OnlineClient {
+    pub(crate) conn: ConnectionManager,
+    batch_size: usize,
+    max_in_flight: usize,
+    encoding: RedisEncoding,
+    pub(crate) key_prefix: String,
+    ttl: Option<chrono::Duration>,
+}
+
+impl std::fmt::Debug for OnlineClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnlineClient")
+            .field("batch_size", &self.batch_size)
+            .field("max_in_flight", &self.max_in_flight)
+            .field("encoding", &self.encoding)
+            .field("key_prefix", &self.key_prefix)
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+impl OnlineClient {
+    pub async fn new(
+        host: &str,
+        port: u16,
+        password: Option<&str>,
+        ssl_enabled: bool,
+    ) -> Result<Self, Error> {
+        Self::with_tls_verification(host, port, password, ssl_enabled, true).await
+    }
+
+    /**
+     * Same as `new`, `verify_certificate = false` skips the verification of the server certificate,
+     * only for test servers with self-signed certificates
+     */
+    pub async fn with_tls_verification(
+        host: &str,
+        port: u16,
+        password: Option<&str>,
+        ssl_enabled: bool,
+        verify_certificate: bool,
+    ) -> Result<Self, Error> {
+        let client = redis::Client::open(connection_info(
+            host,
+            port,
+            password,
+            ssl_enabled,
+            verify_certificate,
+        ))?;
+        Ok(Self {
+            conn: ConnectionManager::new(client).await?,
+            batch_size: DEFAULT_BATCH_SIZE,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            encoding: Default::default(),
+            key_prefix: Default::default(),
+            ttl: None,
+        })
+    }
+
+    /**
+     * Create online client with the same Redis settings used by the materialization jobs
+     */
+    pub async fn from_var_source(
+        var_source: Arc<dyn VarSource + Send + Sync>,
+    ) -> Result<Self, Error> {
+        let host = match var_source.get_environment_variable(&["REDIS_HOST"]).await {
+            Ok(host) => host,
+            Err(_) => {
+                var_source
+                    .get_environment_variable(&["online_store", "redis", "host"])
+                    .await?
+            }
+        };
+        let port = match var_source.get_environment_variable(&["REDIS_PORT"]).await {
+            Ok(port) => port,
+            Err(_) => var_source
+                .get_environment_variable(&["online_store", "redis", "port"])
+                .await
+                .unwrap_or_default(),
+        }
+        .trim()
+        .parse()
+        .unwrap_or(6380);
+        let ssl_enabled = match var_source
+            .get_environment_variable(&["REDIS_SSL_ENABLED"])
+            .await
+        {
+            Ok(ssl) => ssl,
+            Err(_) => var_source
+                .get_environment_variable(&["online_store", "redis", "ssl_enabled"])
+                .await
+                .unwrap_or_default(),
+        }
+        .trim()
+        .to_lowercase()
+        .parse()
+        .unwrap_or(true);
+        let password = var_source
+            .get_environment_variable(&["REDIS_PASSWORD"])
+            .await
+            .ok();
+        let verify_certificate = !var_source
+            .get_environment_variable(&["online_store", "redis", "insecure_skip_verify"])
+            .await
+            .unwrap_or_default()
+            .trim()
+            .eq_ignore_ascii_case("true");
+        let encoding = match var_source
+            .get_environment_variable(&["online_store", "redis", "encoding"])
+            .await
+        {
+            Ok(s) if !s.trim().is_empty() => s.parse()?,
+            _ => Default::default(),
+        };
+        let key_prefix = var_source
+            .get_environment_variable(&["online_store", "redis", "key_prefix"])
+            .await
+            .unwrap_or_default();
+        Ok(Self::with_tls_verification(&host, port, password.as_deref(), ssl_enabled, verify_certificate)
+            .await?
+            .encoding(encoding)
+            .key_prefix(key_prefix.trim()))
+    }
+
+    /**
+     * Set the format of feature values, must match the `encoding` of the `RedisSink` the table is written by
+     */
+    pub fn encoding(mut self, encoding: RedisEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /**
+     * Set the prefix of the keys, must match the `key_prefix` of the `RedisSink` the table is written by
+     */
+    pub fn key_prefix(mut self, prefix: &str) -> Self {
+        self.key_prefix = prefix.to_string();
+        self
+    }
+
+    /**
+     * Expire the keys written by `write_features` after `ttl`, keys never expire if not set.
+     * Fails if `ttl` is shorter than 1 second
+     */
+    pub fn ttl(mut self, ttl: chrono::Duration) -> Result<Self, Error> {
+        crate::utils::ttl_seconds(ttl)?;
+        self.ttl = Some(ttl);
+        Ok(self)
+    }
+
+    /**
+     * Read and write tables in the layout of the sink, i.e. with its encoding, key prefix and TTL.
+     * Per-feature TTLs are not applied by `write_features`, Redis expires whole keys
+     */
+    pub fn sink_layout(mut self, sink: &RedisSink) -> Self {
+        if let Some(encoding) = sink.encoding {
+            self.encoding = encoding;
+        }
+        self.key_prefix = sink.key_prefix.to_owned().unwrap_or_default();
+        self.ttl = sink.ttl;
+        self
+    }
+
+    /**
+     * Set the number of rows written in one pipelined request
+     */
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /**
+     * Set the max number of pipelined requests waiting for responses, further batches are held until one completes
+     */
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /**
+     * Write feature rows into the table, in the same layout as the materialization job does.
+     * Returns number of rows written.
+     */
+    pub async fn write_features(&self, table: &str, rows: &[FeatureRow]) -> Result<usize, Error> {
+        let ttl_seconds = self
+            .ttl
+            .map(crate::utils::ttl_seconds)
+            .transpose()?
+            .map(|s| s as usize);
+        let written = stream::iter(rows.chunks(self.batch_size))
+            .map(|batch| {
+                let mut conn = self.conn.clone();
+                let mut pipe = redis::pipe();
+                // Rows without features are skipped, they're not counted as written
+                let mut count = 0;
+                for row in batch.iter().filter(|r| !r.features.is_empty()) {
+                    count += 1;
+                    let fields: Vec<(&str, String)> = row
+                        .features
+                        .iter()
+                        .map(|(name, v)| (name.as_str(), v.encode(self.encoding)))
+                        .collect();
+                    let key = format!("{}{}", self.key_prefix, redis_key(table, &row.key));
+                    pipe.hset_multiple(&key, &fields).ignore();
+                    if let Some(seconds) = ttl_seconds {
+                        pipe.expire(&key, seconds).ignore();
+                    }
+                }
+                async move {
+                    if count > 0 {
+                        pipe.query_async::<_, ()>(&mut conn).await?;
+                    }
+                    debug!("{} rows written into table {}", count, table);
+                    Ok::<_, Error>(count)
+                }
+            })
+            .buffer_unordered(self.max_in_flight)
+            .try_fold(0, |acc, n| async move { Ok(acc + n) })
+            .await?;
+        Ok(written)
+    }
+
+    /**
+     * Point the alias to the table atomically, returns the table it pointed to before
+     */
+    pub async fn set_alias(&self, alias: &str, table: &str) -> Result<Option<String>, Error> {
+        let mut conn = self.conn.clone();
+        // `SET ... GET` replaces the deprecated `GETSET`, needs Redis 6.2
+        let previous: Option<String> = redis::cmd("SET")
+            .arg(alias_key(alias))
+            .arg(table)
+            .arg("GET")
+            .query_async(&mut conn)
+            .await?;
+        debug!("Alias {} switched from {:?} to {}", alias, previous, table);
+        Ok(previous)
+    }
+
+    /**
+     * Table the alias points to, `None` if the alias doesn't exist
+     */
+    pub async fn get_alias(&self, alias: &str) -> Result<Option<String>, Error> {
+        let mut conn = self.conn.clone();
+        Ok(redis::cmd("GET")
+            .arg(alias_key(alias))
+            .query_async(&mut conn)
+            .await?)
+    }
+
+    /**
+     * Remove the alias, returns `false` if it doesn't exist
+     */
+    pub async fn remove_alias(&self, alias: &str) -> Result<bool, Error> {
+        let mut conn = self.conn.clone();
+        let removed: u64 = redis::cmd("DEL")
+            .arg(alias_key(alias))
+            .query_async(&mut conn)
+            .await?;
+        Ok(removed > 0)
+    }
+
+    /**
+     * The table the alias points to, or `table` itself if it's not an alias
+     */
+    pub async fn resolve_table(&self, table: &str) -> Result<String, Error> {
+        Ok(self
+            .get_alias(table)
+            .await?
+            .unwrap_or_else(|| table.to_string()))
+    }
+
+    /**
+     * Read feature values of the key from the table or the table the alias points to,
+     * `None` if the feature is not found
+     */
+    pub async fn get_features<T>(
+        &self,
+        table: &str,
+        key: &[T],
+        features: &[&str],
+    ) -> Result<Vec<Option<FeatureValue>>, Error>
+    where
+        T: ToString,
+    {
+        if features.is_empty() {
+            return Ok(vec![]);
+        }
+        let key: Vec<String> = key.iter().map(|k| k.to_string()).collect();
+        // The alias is resolved with a separate `GET` so the hash key is known to the client,
+        // Redis Cluster routes commands by the keys they declare
+        let table = self.resolve_table(table).await?;
+        let mut conn = self.conn.clone();
+        let values: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(format!("{}{}", self.key_prefix, redis_key(&table, &key)))
+            .arg(features)
+            .query_async(&mut conn)
+            .await?;
+        values
+            .into_iter()
+            .map(|v| v.map(|v| FeatureValue::decode(&v, self.encoding)).transpose())
+            .collect()
+    }
+}
+
+/**
+ * Connection settings are passed as fields instead of a URL, so the password doesn't need to be escaped
+ */
+fn connection_info(
+    host: &str,
+    port: u16,
+    password: Option<&str>,
+    ssl_enabled: bool,
+    verify_certificate: bool,
+) -> ConnectionInfo {
+    ConnectionInfo {
+        addr: if ssl_enabled {
+            ConnectionAddr::TcpTls {
+                host: host.to_string(),
+                port,
+                insecure: !verify_certificate,
+                tls_params: None,
+            }
+        } else {
+            ConnectionAddr::Tcp(host.to_string(), port)
+        },
+        redis: RedisConnectionInfo {
+            password: password.map(|p| p.to_string()),
+            ..Default::default()
+        },
+    }
+}
+
+pub(crate) fn alias_key(alias: &str) -> String {
+    [ALIAS_KEY_PREFIX, alias].join(KEY_DELIMITER)
+}
+
+pub(crate) fn redis_key(table: &str, key: &[String]) -> String {
+    [table.to_string()]
+        .into_iter()
+        .chain(key.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(KEY_DELIMITER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_feature_value() {
+        assert_eq!(FeatureValue::Int32(150).encode_proto(), vec![0x28, 0x96, 0x01]);
+        assert_eq!(FeatureValue::Bool(true).encode_proto(), vec![0x08, 0x01]);
+        assert_eq!(
+            FeatureValue::String("ab".to_string()).encode_proto(),
+            vec![0x12, 0x02, b'a', b'b']
+        );
+        assert_eq!(
+            FeatureValue::Float(1.0).encode_proto(),
+            vec![0x1d, 0x00, 0x00, 0x80, 0x3f]
+        );
+        assert_eq!(FeatureValue::Int32(-1).encode_proto().len(), 11);
+        assert_eq!(
+            redis_key("table1", &["a".to_string(), "b".to_string()]),
+            "table1:a:b"
+        );
+    }
+
+    #[test]
+    fn decode_feature_value() {
+        let values = [
+            FeatureValue::Bool(true),
+            FeatureValue::Int32(-1),
+            FeatureValue::Int64(1 << 40),
+            FeatureValue::Float(1.5),
+            FeatureValue::Double(-2.25),
+            FeatureValue::String("ab".to_string()),
+            FeatureValue::Bytes(vec![0, 255]),
+        ];
+        for v in values.iter() {
+            assert_eq!(&FeatureValue::decode(&v.encode(RedisEncoding::Protobuf), RedisEncoding::Protobuf).unwrap(), v);
+        }
+        assert_eq!(FeatureValue::Int32(150).encode(RedisEncoding::Json), "150");
+        assert_eq!(FeatureValue::String("ab".to_string()).encode(RedisEncoding::Json), r#""ab""#);
+        assert_eq!(
+            FeatureValue::decode("150", RedisEncoding::Json).unwrap(),
+            FeatureValue::Int64(150)
+        );
+        assert_eq!(
+            FeatureValue::decode(&FeatureValue::Bytes(vec![0, 255]).encode(RedisEncoding::Json), RedisEncoding::Json)
+                .unwrap(),
+            FeatureValue::Bytes(vec![0, 255])
+        );
+        assert!(FeatureValue::decode("KgA=", RedisEncoding::Protobuf).is_err());
+        assert_eq!("JSON".parse::<RedisEncoding>().unwrap(), RedisEncoding::Json);
+    }
+
+    #[test]
+    fn redis_connection_info() {
+        let info = connection_info("host1", 6380, Some("p@ss/w:rd#1"), true, true);
+        assert!(matches!(
+            info.addr,
+            ConnectionAddr::TcpTls { ref host, port: 6380, insecure: false, .. } if host == "host1"
+        ));
+        assert_eq!(info.redis.password.as_deref(), Some("p@ss/w:rd#1"));
+        let info = connection_info("host1", 6380, None, true, false);
+        assert!(matches!(info.addr, ConnectionAddr::TcpTls { insecure: true, .. }));
+        let info = connection_info("host1", 6379, None, false, true);
+        assert!(matches!(info.addr, ConnectionAddr::Tcp(_, 6379)));
+        assert_eq!(info.redis.password, None);
+    }
+
+    #[test]
+    fn alias_keys() {
+        assert_eq!(alias_key("trips"), "__feathr_alias__:trips");
+        assert_eq!(redis_key("trips", &["1".to_string()]), "trips:1");
+    }
+}
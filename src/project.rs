@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::{HashMap, HashSet}, path::Path, sync::Arc};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -9,19 +9,26 @@ use uuid::Uuid;
 
 use crate::client::FeathrClientImpl;
 use crate::feature::{
-    AnchorFeature, AnchorFeatureImpl, DerivedFeature, DerivedFeatureImpl, InputFeature,
+    AnchorFeature, AnchorFeatureImpl, DerivedFeature, DerivedFeatureImpl, FeatureDescriptor,
+    FeatureFilter, InputFeature,
 };
 use crate::feature_builder::{AnchorFeatureBuilder, DerivedFeatureBuilder};
-use crate::registry_client::api_models::{EdgeType, EntityLineage, EntityType};
+use crate::local_eval::referenced_columns;
+use crate::project_file::{ProjectFile, ProjectFileFormat};
+use crate::identifier::{quote_feature_config, quote_join_config};
+use crate::feature_query::check_output_columns;
+use crate::registry_client::api_models::{AnchorDef, DatasetDef, EdgeType, EntityDef, EntityLineage, EntityType};
+use crate::registry_client::{feature_conflict, FeatureDef};
 use crate::{
-    DateTimeResolution, Error, Feature, FeatureQuery, FeatureRegistry, FeatureType,
-    HdfsSourceBuilder, JdbcSourceBuilder, KafkaSourceBuilder, ObservationSettings, Source,
-    SourceImpl, SourceLocation, SubmitGenerationJobRequestBuilder, SubmitJoiningJobRequestBuilder,
-    TypedKey,
+    DatasetRegistration, DateTimeResolution, Error, HdfsSink, Feature, LineageEdge, LineageGraph, LineageNode, LineageNodeType, FeatureQuery, FeatureRegistry, FeatureType,
+    ElasticSourceBuilder, EventHubSourceBuilder, GenericSourceBuilder, HdfsSourceBuilder, JdbcSourceBuilder, KafkaSourceBuilder, SnowflakeSourceBuilder, ObservationSettings, Source,
+    JobId, LintResult, LintRule, LintRules, SourceImpl, SourceLocation, SubmitGenerationJobRequestBuilder,
+    SubmitJoiningJobRequestBuilder, Transformation, TypedKey, ValueType,
 };
 
 /**
  * A Feathr Project is the container of all anchor features, anchor groups, derived features, and data sources.
+ * Clones share the same project, it can be modified from multiple tasks as changes are serialized by a lock.
  */
 #[derive(Clone, Debug)]
 pub struct FeathrProject {
@@ -44,6 +51,11 @@ impl FeathrProject {
             anchor_map: Default::default(),
             sources: Default::default(),
             registry_tags: Default::default(),
+            version: None,
+            force: false,
+            overwrite: false,
+            quote_identifiers: true,
+            implicit_transforms: Default::default(),
         }));
         inner
             .insert_source(SourceImpl::INPUT_CONTEXT())
@@ -67,6 +79,11 @@ impl FeathrProject {
             anchor_map: Default::default(),
             sources: Default::default(),
             registry_tags: Default::default(),
+            version: None,
+            force: false,
+            overwrite: false,
+            quote_identifiers: true,
+            implicit_transforms: Default::default(),
         }));
         inner
             .insert_source(SourceImpl::INPUT_CONTEXT())
@@ -87,6 +104,37 @@ impl FeathrProject {
         self.inner.read().await.registry_tags.to_owned()
     }
 
+    /**
+     * Concurrency token of the project in the registry
+     */
+    pub async fn get_version(&self) -> Option<String> {
+        self.inner.read().await.version.to_owned()
+    }
+
+    /**
+     * By default changes are rejected with `Error::RegistryConflict` if the project has been modified in the registry since it was loaded,
+     * set `force` to write anyway
+     */
+    pub async fn set_force(&self, force: bool) {
+        self.inner.write().await.force = force;
+    }
+
+    /**
+     * By default saving a feature that is registered with a different kind, type, transformation or keys fails with
+     * `Error::FeatureConflict`, set `overwrite` to replace the registered definition. Independent from `set_force`
+     */
+    pub async fn set_overwrite(&self, overwrite: bool) {
+        self.inner.write().await.overwrite = overwrite;
+    }
+
+    /**
+     * Key columns with spaces or reserved words are quoted with backticks in the generated configs by default,
+     * unset to write identifiers as is
+     */
+    pub async fn set_quote_identifiers(&self, quote_identifiers: bool) {
+        self.inner.write().await.quote_identifiers = quote_identifiers;
+    }
+
     pub async fn get_sources(&self) -> Vec<String> {
         self.inner.read().await.sources.keys().map(ToOwned::to_owned).collect()
     }
@@ -103,6 +151,279 @@ impl FeathrProject {
         self.inner.read().await.derivations.keys().map(ToOwned::to_owned).collect()
     }
 
+    /**
+     * Data sources in the project with their names, ordered by name
+     */
+    pub async fn sources(&self) -> Vec<(String, Source)> {
+        let r = self.inner.read().await;
+        let mut ret: Vec<(String, Source)> = r
+            .sources
+            .iter()
+            .map(|(name, s)| (name.to_owned(), Source { inner: s.clone() }))
+            .collect();
+        ret.sort_by(|a, b| a.0.cmp(&b.0));
+        ret
+    }
+
+    /**
+     * Anchor groups in the project with their names, ordered by name
+     */
+    pub async fn anchor_groups(&self) -> Vec<(String, AnchorGroup)> {
+        let r = self.inner.read().await;
+        let mut ret: Vec<(String, AnchorGroup)> = r
+            .anchor_groups
+            .iter()
+            .map(|(name, g)| {
+                (
+                    name.to_owned(),
+                    AnchorGroup {
+                        owner: self.inner.clone(),
+                        inner: g.clone(),
+                    },
+                )
+            })
+            .collect();
+        ret.sort_by(|a, b| a.0.cmp(&b.0));
+        ret
+    }
+
+    /**
+     * Anchor features in the anchor group `group` with their names, in the order they were added
+     */
+    pub async fn anchors(&self, group: &str) -> Result<Vec<(String, AnchorFeature)>, Error> {
+        let r = self.inner.read().await;
+        r.anchor_map
+            .get(group)
+            .ok_or_else(|| Error::AnchorGroupNotFound(group.to_string()))?
+            .iter()
+            .map(|name| {
+                Ok((
+                    name.to_owned(),
+                    AnchorFeature {
+                        owner: self.inner.clone(),
+                        inner: r.get_anchor_feature(group, name)?,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /**
+     * Derived features in the project with their names, ordered by name
+     */
+    pub async fn derived_features(&self) -> Vec<(String, DerivedFeature)> {
+        let r = self.inner.read().await;
+        let mut ret: Vec<(String, DerivedFeature)> = r
+            .derivations
+            .iter()
+            .map(|(name, f)| {
+                (
+                    name.to_owned(),
+                    DerivedFeature {
+                        owner: self.inner.clone(),
+                        inner: f.clone(),
+                    },
+                )
+            })
+            .collect();
+        ret.sort_by(|a, b| a.0.cmp(&b.0));
+        ret
+    }
+
+    /**
+     * Dependency graph of the derived features, maps each derived feature to the sorted names of its inputs,
+     * anchor features have no dependencies and are not included
+     */
+    pub async fn dependency_graph(&self) -> HashMap<String, Vec<String>> {
+        let r = self.inner.read().await;
+        r.derivations
+            .iter()
+            .map(|(name, f)| {
+                let mut inputs: Vec<String> = f.inputs.values().map(|i| i.feature.to_owned()).collect();
+                inputs.sort();
+                inputs.dedup();
+                (name.to_owned(), inputs)
+            })
+            .collect()
+    }
+
+    /**
+     * Graph of the sources, anchor groups, anchor features and derived features of the project,
+     * nodes and edges are ordered by name so the output is stable, use `LineageGraph::to_dot` to visualize it
+     */
+    pub async fn lineage_graph(&self) -> LineageGraph {
+        let r = self.inner.read().await;
+        let node = |id: Uuid, name: &str, qualified_name: String, node_type: LineageNodeType| LineageNode {
+            id,
+            name: name.to_string(),
+            qualified_name,
+            node_type,
+        };
+        let mut nodes = vec![];
+        let mut edges = vec![];
+
+        let mut groups: Vec<&Arc<AnchorGroupImpl>> = r.anchor_groups.values().collect();
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        // `PASSTHROUGH` is only shown when some anchor group uses it
+        let mut sources: Vec<&Arc<SourceImpl>> = r
+            .sources
+            .values()
+            .filter(|s| !s.is_input_context() || groups.iter().any(|g| g.source.inner.id == s.id))
+            .collect();
+        sources.sort_by(|a, b| a.name.cmp(&b.name));
+        for s in sources {
+            nodes.push(node(s.id, &s.name, format!("{}__{}", r.name, s.name), LineageNodeType::Source));
+        }
+        for g in groups {
+            nodes.push(node(g.id, &g.name, format!("{}__{}", r.name, g.name), LineageNodeType::Anchor));
+            edges.push(LineageEdge { from: g.source.inner.id, to: g.id });
+            let mut features: Vec<&Arc<AnchorFeatureImpl>> = r.anchor_map[&g.name]
+                .iter()
+                .filter_map(|name| r.anchor_features.get(name))
+                .collect();
+            features.sort_by_key(|f| f.get_name());
+            for f in features {
+                nodes.push(node(
+                    f.get_id(),
+                    &f.get_name(),
+                    format!("{}__{}__{}", r.name, g.name, f.get_name()),
+                    LineageNodeType::AnchorFeature,
+                ));
+                edges.push(LineageEdge { from: g.id, to: f.get_id() });
+            }
+        }
+        let mut derivations: Vec<&Arc<DerivedFeatureImpl>> = r.derivations.values().collect();
+        derivations.sort_by_key(|f| f.get_name());
+        for f in derivations {
+            nodes.push(node(
+                f.get_id(),
+                &f.get_name(),
+                format!("{}__{}", r.name, f.get_name()),
+                LineageNodeType::DerivedFeature,
+            ));
+            let mut inputs: Vec<&InputFeature> = f.inputs.values().collect();
+            inputs.sort_by(|a, b| a.feature.cmp(&b.feature));
+            inputs.dedup_by(|a, b| a.id == b.id);
+            for i in inputs {
+                edges.push(LineageEdge { from: i.id, to: f.get_id() });
+            }
+        }
+        LineageGraph {
+            name: r.name.clone(),
+            nodes,
+            edges,
+        }
+    }
+
+    /**
+     * Save the definition of the project, including sources, anchor groups, features and tags, to a file
+     * that can be versioned along with the code, `.yaml` and `.yml` files are written as YAML, others as JSON
+     */
+    pub async fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let format = ProjectFileFormat::from_path(&path);
+        let content = ProjectFile::from(&*self.inner.read().await).to_string(format)?;
+        crate::runtime::write_file(path, content.as_bytes()).await
+    }
+
+    /**
+     * Load a detached project saved by `to_file`
+     */
+    pub async fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let format = ProjectFileFormat::from_path(&path);
+        let content = crate::runtime::read_to_string(path).await?;
+        Self::from_definition(ProjectFile::parse(&content, format)?).await
+    }
+
+    /**
+     * Import an upstream Feathr feature config, usually `features.conf` in HOCON, as a detached project,
+     * so projects defined with the Python client can be migrated. Only the HOCON syntax the Python client emits is supported,
+     * hand-written configs using substitutions or includes need to be resolved first
+     */
+    pub async fn from_feature_conf<P: AsRef<Path>>(name: &str, path: P) -> Result<Self, Error> {
+        let content = crate::runtime::read_to_string(path).await?;
+        Self::from_feature_conf_str(name, &content).await
+    }
+
+    /**
+     * Same as `from_feature_conf` but with the content of the feature config
+     */
+    pub async fn from_feature_conf_str(name: &str, conf: &str) -> Result<Self, Error> {
+        let conf = crate::hocon::parse(conf)?;
+        Self::from_definition(crate::feature_conf::to_project_file(name, &conf)?).await
+    }
+
+    async fn from_definition(file: ProjectFile) -> Result<Self, Error> {
+        let definition: FeathrProjectImpl = file.try_into()?;
+        let project = Self::new_detached(&definition.name).await;
+        {
+            let mut inner = project.inner.write().await;
+            inner.import_from(&definition).await?;
+            inner.registry_tags = definition.registry_tags.clone();
+        }
+        Ok(project)
+    }
+
+    /**
+     * Validate the project, feature names are checked against the lint rules in `project_config.feature_lint`
+     * of the client config, detached projects use the default rules
+     */
+    pub async fn validate(&self) -> Result<Vec<LintResult>, Error> {
+        let owner = self.inner.read().await.owner.clone();
+        let rules = match owner {
+            Some(owner) => LintRules::from_var_source(owner.get_var_source()).await?,
+            None => LintRules::default(),
+        };
+        Ok(self.lint(&rules).await)
+    }
+
+    /**
+     * Check names of anchor and derived features against the rules, ordered by feature name
+     */
+    pub async fn lint(&self, rules: &LintRules) -> Vec<LintResult> {
+        let r = self.inner.read().await;
+        let mut names: Vec<&String> = r.anchor_features.keys().chain(r.derivations.keys()).collect();
+        names.sort();
+        names
+            .into_iter()
+            .flat_map(|name| {
+                let mut ret = rules.lint(name);
+                if r.implicit_transforms.contains(name) {
+                    ret.push(LintResult {
+                        feature: name.to_owned(),
+                        rule: LintRule::ImplicitTransformation,
+                        message: format!("Transformation is not set, the source column '{}' is used as is", name),
+                    });
+                }
+                ret
+            })
+            .collect()
+    }
+
+    /**
+     * List anchor and derived features matching the filter, ordered by name
+     */
+    pub async fn list_features(&self, filter: &FeatureFilter) -> Vec<FeatureDescriptor> {
+        let r = self.inner.read().await;
+        let mut ret: Vec<FeatureDescriptor> = r
+            .anchor_map
+            .iter()
+            .flat_map(|(group, features)| {
+                features
+                    .iter()
+                    .filter_map(|name| r.anchor_features.get(name))
+                    .map(move |f| FeatureDescriptor::new(f.as_ref(), Some(group)))
+            })
+            .chain(
+                r.derivations
+                    .values()
+                    .map(|f| FeatureDescriptor::new(f.as_ref(), None)),
+            )
+            .filter(|f| filter.matches(f))
+            .collect();
+        ret.sort_by(|a, b| a.name.cmp(&b.name));
+        ret
+    }
+
     /**
      * Retrieve anchor feature with `name` from specified group
      */
@@ -130,7 +451,7 @@ impl FeathrProject {
     }
 
     /**
-     * Retrieve anchor group with `name`
+     * Retrieve data source with `name`
      */
     pub async fn get_source(&self, name: &str) -> Result<Source, Error> {
         let g = self
@@ -146,6 +467,46 @@ impl FeathrProject {
         })
     }
 
+    /**
+     * Retrieve all data sources in the project
+     */
+    pub async fn list_sources(&self) -> Vec<Source> {
+        self.inner
+            .read()
+            .await
+            .sources
+            .values()
+            .map(|s| Source { inner: s.clone() })
+            .collect()
+    }
+
+    /**
+     * Remove data source with `name` from the project, fails if any anchor group still uses it.
+     * NOTE: The source is not deleted from the registry.
+     */
+    pub async fn remove_source(&self, name: &str) -> Result<(), Error> {
+        self.inner.write().await.remove_source(name)
+    }
+
+    /**
+     * Replace data source `name` with `source`, anchor groups using the old source are switched to the new one.
+     * `source` can be created in this project under another name, in which case the old source is removed,
+     * or in another (e.g. detached) project, in which case it's added into this project.
+     * If the project is attached to a registry, the anchor groups are updated in the registry, which fails with
+     * `Error::UpdateNotSupported` if the registry doesn't support updates, the project is unchanged in that case.
+     * NOTE: The old source is not deleted from the registry.
+     */
+    pub async fn replace_source(&self, name: &str, source: Source) -> Result<Source, Error> {
+        Ok(Source {
+            inner: self
+                .inner
+                .write()
+                .await
+                .replace_source(name, source.inner)
+                .await?,
+        })
+    }
+
     /**
      * Retrieve anchor group with `name`
      */
@@ -192,6 +553,45 @@ impl FeathrProject {
         JdbcSourceBuilder::new(self.inner.clone(), name, url)
     }
 
+    /**
+     * Start creating an Azure SQL data source with given name, reading `database` on `server`,
+     * the server is either the server name or the full host name.
+     * `hostNameInCertificate` is only set for `*.database.windows.net` hosts, use `connection_property` to set it for others.
+     * User and password are read from secrets `<name>_USER` and `<name>_PASSWORD`
+     */
+    pub fn mssql_source(&self, name: &str, server: &str, database: &str) -> JdbcSourceBuilder {
+        JdbcSourceBuilder::new_mssql(self.inner.clone(), name, server, database)
+    }
+
+    /**
+     * Start creating a Snowflake data source with given name, either `dbtable` or `query` must be set
+     */
+    pub fn snowflake_source(&self, name: &str, url: &str) -> SnowflakeSourceBuilder {
+        SnowflakeSourceBuilder::new(self.inner.clone(), name, url)
+    }
+
+    /**
+     * Start creating a data source with given name, read by the Spark data source `format` with options
+     */
+    pub fn generic_source(&self, name: &str, format: &str) -> GenericSourceBuilder {
+        GenericSourceBuilder::new(self.inner.clone(), name, format)
+    }
+
+    /**
+     * Start creating an Elasticsearch data source with given name, reading `index` from `hosts`
+     */
+    pub fn elastic_source<T: ToString>(&self, name: &str, hosts: &[T], index: &str) -> ElasticSourceBuilder {
+        ElasticSourceBuilder::new(self.inner.clone(), name, hosts, index)
+    }
+
+    /**
+     * Start creating an Azure Event Hubs streaming source with given name,
+     * the connection string of the hub is read from the secret `<SOURCE_NAME>_CONNECTION_STRING`
+     */
+    pub fn eventhub_source(&self, name: &str) -> EventHubSourceBuilder {
+        EventHubSourceBuilder::new(self.inner.clone(), name)
+    }
+
     /**
      * Start creating a JDBC data source with given name
      */
@@ -229,14 +629,27 @@ impl FeathrProject {
             .collect();
 
         let ob = observation_settings.into();
-        Ok(SubmitJoiningJobRequestBuilder::new_join(
-            format!("{}_feathr_feature_join_job", self.inner.read().await.name),
+        let (jdbc_driver, jdbc_properties) = self.get_jdbc_config().await?;
+        let mut builder = SubmitJoiningJobRequestBuilder::new_join(
+            self.clone(),
+            self.inner.read().await.name.clone(),
+            feature_names.clone(),
             ob.observation_path.to_string(),
             self.get_feature_config().await?,
-            self.get_feature_join_config(ob, feature_query, output)?,
+            self.get_feature_join_config(
+                ob,
+                feature_query,
+                output,
+                self.inner.read().await.quote_identifiers,
+            )?,
             self.get_secret_keys().await?,
             self.get_user_functions(&feature_names).await?,
-        ))
+        );
+        builder.jdbc_config(jdbc_driver, jdbc_properties);
+        for f in self.get_preprocessing_files(&feature_names).await {
+            builder.python_file(&f);
+        }
+        Ok(builder)
     }
 
     /**
@@ -253,20 +666,103 @@ impl FeathrProject {
         T: ToString,
     {
         let feature_names: Vec<String> = feature_names.into_iter().map(|f| f.to_string()).collect();
-        Ok(SubmitGenerationJobRequestBuilder::new_gen(
-            format!(
-                "{}_feathr_feature_materialization_job",
-                self.inner.read().await.name
-            ),
+        let (jdbc_driver, jdbc_properties) = self.get_jdbc_config().await?;
+        let mut builder = SubmitGenerationJobRequestBuilder::new_gen(
+            self.clone(),
+            self.inner.read().await.name.clone(),
             &feature_names,
-            Default::default(), // TODO:
+            self.inner.read().await.get_default_input_path(&feature_names),
             self.get_feature_config().await?,
             self.get_secret_keys().await?,
             start,
             end,
             step,
             self.get_user_functions(&feature_names).await?,
-        ))
+        );
+        builder.jdbc_config(jdbc_driver, jdbc_properties);
+        for f in self.get_preprocessing_files(&feature_names).await {
+            builder.python_file(&f);
+        }
+        Ok(builder)
+    }
+
+    /**
+     * Creates the Spark job request to materialize features of streaming sources, e.g. Kafka or Event Hub,
+     * into streaming sinks, the job keeps running until the timeout of the sinks
+     */
+    pub async fn feature_streaming_job<T>(&self, feature_names: &[T]) -> Result<SubmitGenerationJobRequestBuilder, Error>
+    where
+        T: ToString,
+    {
+        let names: Vec<String> = feature_names.iter().map(|f| f.to_string()).collect();
+        self.inner.read().await.check_streaming_sources(&names)?;
+        // Streaming jobs don't have a time range, the single cutoff only fills the required `endTime`
+        let now = Utc::now();
+        let mut builder = self
+            .feature_gen_job(&names, now, now, DateTimeResolution::Daily)
+            .await?;
+        builder.streaming();
+        Ok(builder)
+    }
+
+    /**
+     * Re-materialize the cutoffs between `start` and `end` whose output is missing in the time partitioned sink,
+     * e.g. after some jobs of a backfill failed. Partial output of these cutoffs is deleted before submitting.
+     * Returns the ids of the submitted jobs, empty if all cutoffs are complete.
+     *
+     * Output of a cutoff still being materialized is missing as well, so nothing is submitted while generation
+     * jobs of the project are running, or when the Spark provider cannot list jobs to tell.
+     * Set `force` to re-run the missing cutoffs anyway.
+     */
+    pub async fn resume_materialization<T>(
+        &self,
+        features: &[T],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        step: DateTimeResolution,
+        sink: &HdfsSink,
+        force: bool,
+    ) -> Result<Vec<JobId>, Error>
+    where
+        T: ToString,
+    {
+        let (owner, project) = {
+            let inner = self.inner.read().await;
+            (inner.owner.clone().ok_or(Error::DetachedProject)?, inner.name.clone())
+        };
+        let missing = owner.get_missing_cutoffs(sink, start, end, step).await?;
+        if !missing.is_empty() && !force {
+            match owner.get_running_generation_jobs(&project).await {
+                Ok(running) if running.is_empty() => {}
+                Ok(running) => {
+                    return Err(Error::MaterializationInProgress(project, format!("jobs {:?} are running", running)))
+                }
+                Err(e) => return Err(Error::MaterializationInProgress(project, e.to_string())),
+            }
+        }
+        let mut job_ids = vec![];
+        for end_time in missing {
+            let requests = self
+                .feature_gen_job(features, end_time - step.duration(), end_time, step)
+                .await?
+                .sink(sink.to_owned().overwrite_existing())
+                .build()?;
+            for request in requests {
+                job_ids.push(owner.submit_job(request).await?);
+            }
+        }
+        Ok(job_ids)
+    }
+
+    /**
+     * Register the output dataset of the job, returns `None` if the project is not attached to a registry
+     */
+    pub(crate) async fn register_dataset(
+        &self,
+        dataset: &DatasetRegistration,
+        job_id: JobId,
+    ) -> Result<Option<Uuid>, Error> {
+        self.inner.write().await.register_dataset(dataset, job_id).await
     }
 
     pub(crate) async fn get_user_functions(
@@ -276,14 +772,31 @@ impl FeathrProject {
         Ok(self.inner.read().await.get_user_functions(feature_names))
     }
 
+    pub(crate) async fn get_preprocessing_files(&self, feature_names: &[String]) -> Vec<String> {
+        self.inner.read().await.get_preprocessing_files(feature_names)
+    }
+
     pub(crate) async fn get_secret_keys(&self) -> Result<Vec<String>, Error> {
         Ok(self.inner.read().await.get_secret_keys())
     }
 
+    pub(crate) async fn get_jdbc_config(&self) -> Result<(Option<String>, HashMap<String, String>), Error> {
+        self.inner.read().await.get_jdbc_config()
+    }
+
+    /**
+     * Feature config of the project, fails if any anchor transformation references a column
+     * not declared in the schema of its source
+     */
     pub(crate) async fn get_feature_config(&self) -> Result<String, Error> {
         let r = self.inner.read().await;
-        let s = serde_json::to_string_pretty(&*r).unwrap();
-        Ok(s)
+        r.check_source_columns()?;
+        if !r.quote_identifiers {
+            return Ok(serde_json::to_string_pretty(&*r).unwrap());
+        }
+        let mut config = serde_json::to_value(&*r)?;
+        quote_feature_config(&mut config)?;
+        Ok(serde_json::to_string_pretty(&config)?)
     }
 
     pub(crate) fn get_feature_join_config<O, Q>(
@@ -291,6 +804,7 @@ impl FeathrProject {
         observation_settings: O,
         feature_query: &[&Q],
         output: &str,
+        quote_identifiers: bool,
     ) -> Result<String, Error>
     where
         O: Into<ObservationSettings>,
@@ -314,7 +828,13 @@ impl FeathrProject {
                 .collect(),
             output_path: output.to_string(),
         };
-        Ok(serde_json::to_string_pretty(&cfg)?)
+        check_output_columns(&cfg.feature_list, &cfg.observation_settings.columns)?;
+        if !quote_identifiers {
+            return Ok(serde_json::to_string_pretty(&cfg)?);
+        }
+        let mut config = serde_json::to_value(&cfg)?;
+        quote_join_config(&mut config)?;
+        Ok(serde_json::to_string_pretty(&config)?)
     }
 }
 
@@ -329,6 +849,26 @@ pub(crate) struct FeathrProjectImpl {
     pub(crate) anchor_map: HashMap<String, Vec<String>>,
     pub(crate) sources: HashMap<String, Arc<SourceImpl>>,
     pub(crate) registry_tags: HashMap<String, String>,
+    /**
+     * Concurrency token of the project in the registry, `None` until the project is loaded or first modified
+     */
+    pub(crate) version: Option<String>,
+    /**
+     * Write into the registry even if the project has been modified by others
+     */
+    pub(crate) force: bool,
+    /**
+     * Replace registered features with a different kind, type, transformation or keys instead of failing
+     */
+    pub(crate) overwrite: bool,
+    /**
+     * Quote key columns and aliases in the generated configs
+     */
+    pub(crate) quote_identifiers: bool,
+    /**
+     * Anchor features built without transformation in this session, they're the source column with the same name
+     */
+    pub(crate) implicit_transforms: HashSet<String>,
 }
 
 impl Serialize for FeathrProjectImpl {
@@ -430,6 +970,90 @@ impl FeathrProjectImpl {
             .map(|r| r.to_owned())
     }
 
+    /**
+     * Create the entity in the registry, the write is rejected if the project has been modified in the registry
+     * since the last time this project read or wrote it, unless `force` is set
+     */
+    async fn create_entity(&mut self, c: &dyn FeatureRegistry, definition: EntityDef) -> Result<Uuid, Error> {
+        let version = self.version.clone().filter(|_| !self.force);
+        let (id, version) = c
+            .new_entity_if_version(&self.name, self.id, version.as_deref(), definition)
+            .await?;
+        self.version = Some(version);
+        Ok(id)
+    }
+
+    /**
+     * Update the entity in the registry, with the same version check as `create_entity`
+     */
+    async fn update_entity(
+        &mut self,
+        c: &dyn FeatureRegistry,
+        id: Uuid,
+        definition: EntityDef,
+    ) -> Result<(), Error> {
+        let version = self.version.clone().filter(|_| !self.force);
+        let version = c
+            .update_entity_if_version(&self.name, self.id, version.as_deref(), id, definition)
+            .await?;
+        self.version = Some(version);
+        Ok(())
+    }
+
+    /**
+     * Id of the feature with the same name already in the project, if it can be updated in place by the new definition.
+     * Fails with `Error::FeatureConflict` if the definitions differ, unless `overwrite` is set
+     */
+    fn replaced_feature(&self, name: &str, group: Option<&str>, local: &FeatureDef) -> Result<Option<Uuid>, Error> {
+        let (qualified_name, existing, id, same_place) = if let Some(f) = self.anchor_features.get(name) {
+            let g = self
+                .anchor_map
+                .iter()
+                .find(|(_, names)| names.iter().any(|n| n == name))
+                .map(|(g, _)| g.as_str())
+                .unwrap_or_default();
+            let qualified_name = format!("{}__{}__{}", self.name, g, name);
+            (qualified_name, FeatureDef::from(f.as_ref()), f.get_id(), group == Some(g))
+        } else if let Some(f) = self.derivations.get(name) {
+            let qualified_name = format!("{}__{}", self.name, name);
+            (qualified_name, FeatureDef::from(f.as_ref()), f.get_id(), group.is_none())
+        } else {
+            return Ok(None);
+        };
+        match feature_conflict(&qualified_name, &existing, local) {
+            Some(conflict) if !self.overwrite => Err(Error::FeatureConflict(vec![conflict])),
+            Some(_) => Ok(same_place.then_some(id)),
+            None => Ok(None),
+        }
+    }
+
+    fn remove_feature(&mut self, name: &str) {
+        self.anchor_features.remove(name);
+        self.derivations.remove(name);
+        for names in self.anchor_map.values_mut() {
+            names.retain(|n| n != name);
+        }
+    }
+
+    /**
+     * Update the replaced feature in place if the registry supports it, otherwise create a new one
+     */
+    async fn save_feature(
+        &mut self,
+        c: &dyn FeatureRegistry,
+        replaced: Option<Uuid>,
+        definition: EntityDef,
+    ) -> Result<Uuid, Error> {
+        if let Some(id) = replaced {
+            match self.update_entity(c, id, definition.clone()).await {
+                Ok(()) => return Ok(id),
+                Err(Error::UpdateNotSupported(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.create_entity(c, definition).await
+    }
+
     async fn insert_anchor_group(
         &mut self,
         mut group: AnchorGroupImpl,
@@ -440,7 +1064,9 @@ impl FeathrProjectImpl {
             .map(|o| o.get_registry_client())
             .flatten()
         {
-            group.id = c.new_anchor(self.id, group.clone().into()).await?;
+            group.id = self
+                .create_entity(c.as_ref(), EntityDef::Anchor(group.clone().into()))
+                .await?;
         }
 
         let name = group.name.clone();
@@ -462,7 +1088,8 @@ impl FeathrProjectImpl {
 
         let g = self
             .anchor_groups
-            .get_mut(group)
+            .get(group)
+            .cloned()
             .ok_or_else(|| Error::AnchorGroupNotFound(group.to_string()))?;
 
         if let Some(c) = self
@@ -471,7 +1098,9 @@ impl FeathrProjectImpl {
             .map(|o| o.get_registry_client())
             .flatten()
         {
-            f.base.id =  c.new_anchor_feature(self.id, g.id, f.clone().into())
+            let replaced = self.replaced_feature(&f.get_name(), Some(group), &FeatureDef::from(&f))?;
+            f.base.id = self
+                .save_feature(c.as_ref(), replaced, EntityDef::AnchorFeature(g.id, f.clone().into()))
                 .await?;
         }
 
@@ -481,6 +1110,8 @@ impl FeathrProjectImpl {
             return Err(Error::DummyKeyUsedWithoutInputContext(f.get_name()));
         }
         let name = f.get_name();
+        // The feature replaces the one with the same name
+        self.remove_feature(&name);
         self.anchor_map.get_mut(group).map(|g| g.push(name.clone()));
         let ret = Arc::new(f);
         self.anchor_features.insert(name, ret.clone());
@@ -498,23 +1129,68 @@ impl FeathrProjectImpl {
             .map(|o| o.get_registry_client())
             .flatten()
         {
-            f.base.id = c.new_derived_feature(self.id, f.clone().into()).await?;
+            let replaced = self.replaced_feature(&f.get_name(), None, &FeatureDef::from(&f))?;
+            f.base.id = self
+                .save_feature(c.as_ref(), replaced, EntityDef::DerivedFeature(f.clone().into()))
+                .await?;
         }
 
         let name = f.base.name.clone();
+        self.remove_feature(&name);
         let ret = Arc::new(f);
         self.derivations.insert(name, ret.clone());
         Ok(ret)
     }
 
+    async fn register_dataset(
+        &mut self,
+        dataset: &DatasetRegistration,
+        job_id: JobId,
+    ) -> Result<Option<Uuid>, Error> {
+        let c = match self.owner.as_ref().and_then(|o| o.get_registry_client()) {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        let features = dataset
+            .features
+            .iter()
+            .map(|name| {
+                self.anchor_features
+                    .get(name)
+                    .map(|f| f.get_id())
+                    .or_else(|| self.derivations.get(name).map(|f| f.get_id()))
+                    .ok_or_else(|| Error::FeatureNotFound(name.to_owned()))
+            })
+            .collect::<Result<_, _>>()?;
+        let definition = DatasetDef {
+            name: dataset.name.to_owned(),
+            path: dataset.path.to_owned(),
+            schema: dataset.schema.to_owned(),
+            job_name: dataset.job_name.to_owned(),
+            job_id: Some(job_id.to_string()),
+            features,
+            tags: Default::default(),
+        };
+        let id = self
+            .create_entity(c.as_ref(), EntityDef::Dataset(definition))
+            .await?;
+        Ok(Some(id))
+    }
+
     async fn insert_source(&mut self, mut s: SourceImpl) -> Result<Arc<SourceImpl>, Error> {
+        if self.sources.contains_key(&s.name) {
+            return Err(Error::DuplicateSource(s.name));
+        }
+
         if let Some(c) = self
             .owner
             .clone()
             .map(|o| o.get_registry_client())
             .flatten()
         {
-            s.id = c.new_source(self.id, s.clone().into()).await?;
+            s.id = self
+                .create_entity(c.as_ref(), EntityDef::Source(s.clone().into()))
+                .await?;
         }
 
         let name = s.name.clone();
@@ -523,6 +1199,228 @@ impl FeathrProjectImpl {
         Ok(ret)
     }
 
+    fn remove_source(&mut self, name: &str) -> Result<(), Error> {
+        if !self.sources.contains_key(name) {
+            return Err(Error::SourceGroupNotFound(name.to_string()));
+        }
+        let mut groups: Vec<String> = self
+            .anchor_groups
+            .values()
+            .filter(|g| g.source.inner.name == name)
+            .map(|g| g.name.to_owned())
+            .collect();
+        if !groups.is_empty() {
+            groups.sort();
+            return Err(Error::SourceInUse(name.to_string(), groups));
+        }
+        self.sources.remove(name);
+        Ok(())
+    }
+
+    async fn replace_source(
+        &mut self,
+        name: &str,
+        source: Arc<SourceImpl>,
+    ) -> Result<Arc<SourceImpl>, Error> {
+        let old = self
+            .sources
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::SourceGroupNotFound(name.to_string()))?;
+        if old.is_input_context() || source.is_input_context() {
+            return Err(Error::SourceConflict(
+                name.to_string(),
+                "INPUT_CONTEXT cannot be replaced".to_string(),
+            ));
+        }
+        let groups: Vec<Arc<AnchorGroupImpl>> = self
+            .anchor_groups
+            .values()
+            .filter(|g| g.source.inner.name == name)
+            .cloned()
+            .collect();
+        if source.time_window_parameters.is_none() {
+            // Window aggregation needs the timestamp column of the source
+            let window_agg = groups
+                .iter()
+                .flat_map(|g| self.anchor_map.get(&g.name).into_iter().flatten())
+                .filter_map(|f| self.anchor_features.get(f))
+                .find(|f| matches!(f.get_transformation(), Transformation::WindowAgg { .. }));
+            if let Some(f) = window_agg {
+                return Err(Error::SourceConflict(
+                    name.to_string(),
+                    format!("window aggregation feature {} requires time window parameters", f.get_name()),
+                ));
+            }
+        }
+
+        let (new, inserted) = match self.sources.get(&source.name) {
+            Some(s) if s.id == source.id => (s.clone(), false),
+            Some(_) if source.name != name => return Err(Error::DuplicateSource(source.name.clone())),
+            _ => {
+                // Source from another project, or another source with the same name
+                self.sources.remove(name);
+                match self.insert_source(source.as_ref().to_owned()).await {
+                    Ok(s) => (s, true),
+                    Err(e) => {
+                        self.sources.insert(name.to_string(), old);
+                        return Err(e);
+                    }
+                }
+            }
+        };
+
+        if let Some(c) = self.owner.as_ref().and_then(|o| o.get_registry_client()) {
+            for g in groups.iter() {
+                let mut def: AnchorDef = g.as_ref().to_owned().into();
+                def.source_id = new.id.to_string();
+                if let Err(e) = self.update_entity(c.as_ref(), g.id, EntityDef::Anchor(def)).await {
+                    if inserted {
+                        self.sources.remove(&new.name);
+                        self.sources.insert(name.to_string(), old);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        for g in groups {
+            let mut group = g.as_ref().to_owned();
+            group.source = Source { inner: new.clone() };
+            self.anchor_groups.insert(group.name.clone(), Arc::new(group));
+        }
+        if new.name != name {
+            self.sources.remove(name);
+        }
+        Ok(new)
+    }
+
+    /**
+     * Recreate all sources, anchor groups and features of `other` in this project,
+     * entities are registered and get new ids if this project is attached to a registry.
+     */
+    pub(crate) async fn import_from(&mut self, other: &FeathrProjectImpl) -> Result<(), Error> {
+        for s in other.sources.values().filter(|s| !s.is_input_context()) {
+            self.insert_source(s.as_ref().to_owned()).await?;
+        }
+
+        let mut id_map: HashMap<String, Uuid> = HashMap::new();
+        for g in other.anchor_groups.values() {
+            let mut group = g.as_ref().to_owned();
+            group.source = Source {
+                inner: self
+                    .sources
+                    .get(&g.source.get_name())
+                    .ok_or_else(|| Error::SourceGroupNotFound(g.source.get_name()))?
+                    .to_owned(),
+            };
+            self.insert_anchor_group(group).await?;
+            for name in other.anchor_map.get(&g.name).into_iter().flatten() {
+                let f = other
+                    .anchor_features
+                    .get(name)
+                    .ok_or_else(|| Error::FeatureNotFound(name.to_owned()))?;
+                let f = self
+                    .insert_anchor_feature(&g.name, f.as_ref().to_owned())
+                    .await?;
+                id_map.insert(f.get_name(), f.get_id());
+            }
+        }
+
+        // Derived features may depend on other derived features, insert them after all their inputs
+        let mut pending: Vec<&DerivedFeatureImpl> =
+            other.derivations.values().map(AsRef::as_ref).collect();
+        while !pending.is_empty() {
+            let (ready, rest): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .partition(|f| f.inputs.values().all(|i| id_map.contains_key(&i.feature)));
+            if ready.is_empty() {
+                let missing = rest
+                    .iter()
+                    .flat_map(|f| f.inputs.values().map(|i| &i.feature))
+                    .find(|i| !id_map.contains_key(*i))
+                    .cloned()
+                    .unwrap_or_default();
+                return Err(Error::FeatureNotFound(missing));
+            }
+            for f in ready {
+                let mut f = f.to_owned();
+                for input in f.inputs.values_mut() {
+                    input.id = id_map[&input.feature];
+                }
+                let f = self.insert_derived_feature(f).await?;
+                id_map.insert(f.get_name(), f.get_id());
+            }
+            pending = rest;
+        }
+        Ok(())
+    }
+
+    /**
+     * Check anchor transformations and keys against the declared schema of their sources,
+     * column names are case-insensitive as in Spark. Keys using a declared column directly must have the declared type.
+     * Expressions that cannot be parsed locally are skipped, so are sources with preprocessing as the
+     * preprocessing function may add or rename columns
+     */
+    fn check_source_columns(&self) -> Result<(), Error> {
+        let mut groups: Vec<&Arc<AnchorGroupImpl>> = self
+            .anchor_groups
+            .values()
+            .filter(|g| !g.source.inner.schema.is_empty() && g.source.inner.preprocessing.is_none())
+            .collect();
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        for g in groups {
+            let source = &g.source.inner;
+            let features = self
+                .anchor_map
+                .get(&g.name)
+                .into_iter()
+                .flatten()
+                .filter_map(|name| self.anchor_features.get(name));
+            let declared = |column: &str| {
+                source
+                    .schema
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(column))
+                    .map(|(_, t)| *t)
+            };
+            for f in features {
+                let keys = f.get_key();
+                let key_columns = keys
+                    .iter()
+                    .filter(|k| **k != TypedKey::DUMMY_KEY())
+                    .filter_map(|k| {
+                        if let Some(t) = declared(&k.key_column) {
+                            if t != k.key_column_type
+                                && t != ValueType::UNSPECIFIED
+                                && k.key_column_type != ValueType::UNSPECIFIED
+                            {
+                                return Some(Err(Error::KeyColumnTypeMismatch(
+                                    f.get_name(),
+                                    k.key_column.clone(),
+                                    k.key_column_type,
+                                    t,
+                                    source.name.clone(),
+                                )));
+                            }
+                            return None;
+                        }
+                        referenced_columns(&Transformation::from(k.key_column.as_str())).map(Ok)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let columns = referenced_columns(&f.get_transformation()).unwrap_or_default();
+                if let Some(c) = columns
+                    .into_iter()
+                    .chain(key_columns.into_iter().flatten())
+                    .find(|c| declared(c).is_none())
+                {
+                    return Err(Error::UndeclaredColumn(f.get_name(), c, source.name.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn get_user_functions(&self, feature_names: &[String]) -> HashMap<String, String> {
         let mut ret = HashMap::new();
         for (_, g) in &self.anchor_groups {
@@ -544,12 +1442,160 @@ impl FeathrProjectImpl {
         ret
     }
 
+    /**
+     * Preprocessing files of the sources used by the features, they're uploaded with the job
+     */
+    fn get_preprocessing_files(&self, feature_names: &[String]) -> Vec<String> {
+        let mut ret: Vec<String> = self
+            .anchor_groups
+            .values()
+            .filter(|g| {
+                self.anchor_map
+                    .get(&g.name)
+                    .into_iter()
+                    .flatten()
+                    .any(|name| feature_names.contains(name))
+            })
+            .filter_map(|g| g.source.inner.preprocessing_file.clone())
+            .collect();
+        ret.sort();
+        ret.dedup();
+        ret
+    }
+
+    /**
+     * Anchor features and the sources of their anchor groups, including inputs of derived features,
+     * `None` for features not found in the project
+     */
+    fn get_anchor_sources<'a>(&'a self, feature_names: &'a [String]) -> Vec<(&'a str, Option<&'a SourceImpl>)> {
+        let mut sources: Vec<(&str, Option<&SourceImpl>)> = vec![];
+        let mut visited: Vec<&str> = vec![];
+        let mut pending: Vec<&str> = feature_names.iter().map(|f| f.as_str()).collect();
+        while let Some(name) = pending.pop() {
+            if visited.contains(&name) {
+                continue;
+            }
+            visited.push(name);
+            if let Some(d) = self.derivations.get(name) {
+                pending.extend(d.inputs.values().map(|i| i.feature.as_str()));
+                continue;
+            }
+            let group = self
+                .anchor_map
+                .iter()
+                .find(|(_, features)| features.iter().any(|f| f == name))
+                .and_then(|(g, _)| self.anchor_groups.get(g));
+            sources.push((name, group.map(|g| g.source.inner.as_ref())));
+        }
+        sources
+    }
+
+    /**
+     * Path of the HDFS source if all the features, including inputs of derived features, are anchored on it,
+     * empty otherwise
+     */
+    fn get_default_input_path(&self, feature_names: &[String]) -> String {
+        let mut paths: Vec<&str> = vec![];
+        for (_, source) in self.get_anchor_sources(feature_names) {
+            match source.map(|s| &s.location) {
+                Some(SourceLocation::Hdfs { path, .. }) => paths.push(path),
+                _ => return String::default(),
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        match paths.as_slice() {
+            [path] => path.to_string(),
+            _ => String::default(),
+        }
+    }
+
+    /**
+     * Streaming jobs can only materialize features anchored on Kafka or Event Hub sources
+     */
+    fn check_streaming_sources(&self, feature_names: &[String]) -> Result<(), Error> {
+        let sources = self.get_anchor_sources(feature_names);
+        if sources.is_empty() {
+            return Err(Error::InvalidStreamingJob("no feature to materialize".to_string()));
+        }
+        for (name, source) in sources {
+            match source {
+                Some(s) if matches!(s.location, SourceLocation::Kafka { .. }) => {}
+                Some(s) => {
+                    return Err(Error::InvalidStreamingJob(format!(
+                        "source {} is not a streaming source",
+                        s.name
+                    )))
+                }
+                None => return Err(Error::FeatureNotFound(name.to_string())),
+            }
+        }
+        Ok(())
+    }
+
     fn get_secret_keys(&self) -> Vec<String> {
-        self.sources
-            .iter()
-            .map(|(_, s)| s.get_secret_keys().into_iter())
-            .flatten()
-            .collect()
+        let mut keys: Vec<String> = self
+            .sources
+            .values()
+            .flat_map(|s| s.get_secret_keys())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /**
+     * JDBC driver and connection properties of JDBC sources, they're passed to the job as a single SQL config,
+     * so sources setting different drivers or different values of the same property cannot be used together
+     */
+    fn get_jdbc_config(&self) -> Result<(Option<String>, HashMap<String, String>), Error> {
+        let mut sources: Vec<&Arc<SourceImpl>> = self.sources.values().collect();
+        sources.sort_by(|a, b| a.name.cmp(&b.name));
+        // Values with the name of the source setting them
+        let mut driver: Option<(&str, &String)> = None;
+        let mut properties: HashMap<&String, (&str, &String)> = HashMap::new();
+        for s in sources {
+            if let SourceLocation::Jdbc {
+                driver: d,
+                connection_properties,
+                ..
+            } = &s.location
+            {
+                if let Some(d) = d {
+                    match driver {
+                        Some((other, value)) if value != d => {
+                            return Err(Error::JdbcConfigConflict(
+                                other.to_string(),
+                                s.name.clone(),
+                                "drivers".to_string(),
+                            ))
+                        }
+                        _ => driver = Some((&s.name, d)),
+                    }
+                }
+                for (k, v) in connection_properties {
+                    match properties.get(k) {
+                        Some((other, value)) if *value != v => {
+                            return Err(Error::JdbcConfigConflict(
+                                other.to_string(),
+                                s.name.clone(),
+                                format!("connection property '{}'", k),
+                            ))
+                        }
+                        _ => {
+                            properties.insert(k, (&s.name, v));
+                        }
+                    }
+                }
+            }
+        }
+        Ok((
+            driver.map(|(_, d)| d.to_owned()),
+            properties
+                .into_iter()
+                .map(|(k, (_, v))| (k.to_owned(), v.to_owned()))
+                .collect(),
+        ))
     }
 }
 
@@ -594,6 +1640,32 @@ impl AnchorGroup {
         ))
     }
 
+    /**
+     * Create one anchor feature for each `(name, transform, feature_type)` tuple, all with the same `keys`.
+     * The transform can be a column name, an expression or a window aggregation.
+     * Features are added in order, the ones before the failed one are kept.
+     */
+    pub async fn anchors_from_columns<T>(
+        &self,
+        keys: &[&TypedKey],
+        columns: &[(&str, T, FeatureType)],
+    ) -> Result<Vec<AnchorFeature>, Error>
+    where
+        T: Into<Transformation> + Clone,
+    {
+        let mut ret = Vec::with_capacity(columns.len());
+        for (name, transform, feature_type) in columns {
+            ret.push(
+                self.anchor(name, feature_type.to_owned())?
+                    .transform(transform.to_owned())
+                    .keys(keys)
+                    .build()
+                    .await?,
+            );
+        }
+        Ok(ret)
+    }
+
     pub async fn get_anchor(&self, name: &str) -> Result<AnchorFeature, Error> {
         Ok(AnchorFeature {
             owner: self.owner.clone(),
@@ -722,6 +1794,7 @@ impl TryFrom<EntityLineage> for FeathrProjectImpl {
             .find(|(_, entity)| entity.get_entity_type() == EntityType::Project)
             .ok_or_else(|| Error::ProjectNotFound(Default::default()))?;
         let mut project: FeathrProjectImpl = entity.to_owned().try_into()?;
+        project.version = Some(value.version());
         // Add sources into project
         project.sources = value
             .guid_entity_map
@@ -778,7 +1851,7 @@ impl TryFrom<EntityLineage> for FeathrProjectImpl {
                     .try_into()
                     .ok()
                     .map(|mut i: DerivedFeatureImpl| {
-                        i.inputs = value
+                        let inputs: HashMap<String, InputFeature> = value
                             .relations
                             .iter()
                             .filter(|&r| r.edge_type == EdgeType::Consumes && &r.from == id)
@@ -790,11 +1863,31 @@ impl TryFrom<EntityLineage> for FeathrProjectImpl {
                                         feature: value.guid_entity_map[&r.to].name.to_owned(),
                                         is_anchor_feature: value.guid_entity_map[&r.to].get_entity_type()
                                             == EntityType::AnchorFeature,
+                                        offset: None,
                                     }
                                 })
                             })
                             .map(|f| (f.feature.clone(), f))
                             .collect();
+                        // Aliased and lagged inputs are recorded in the `inputs` tag, others are referred by the name
+                        let mut aliased = std::mem::take(&mut i.inputs);
+                        for (name, input) in aliased.iter_mut() {
+                            if let Some(f) = inputs.get(&input.feature) {
+                                *input = InputFeature {
+                                    offset: input.offset,
+                                    ..f.to_owned()
+                                };
+                            } else {
+                                log::warn!("Input {} of feature {} is not found", name, i.base.name);
+                            }
+                        }
+                        aliased.retain(|_, input| !input.id.is_nil());
+                        for (name, input) in inputs {
+                            if !aliased.values().any(|i| i.feature == name) {
+                                aliased.insert(name, input);
+                            }
+                        }
+                        i.inputs = aliased;
                         (i.base.name.clone(), Arc::new(i))
                     })
             })
@@ -842,4 +1935,1679 @@ mod tests {
         let s = proj.get_feature_config().await.unwrap();
         println!("{}", s);
     }
+
+    #[tokio::test]
+    async fn export_schedule_manifest() {
+        use chrono::{TimeZone, Utc};
+
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj.hdfs_source("h1", "wasbs://a@b.blob.core.windows.net/c.csv").build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        proj.anchor_group("g1", s)
+            .build()
+            .await
+            .unwrap()
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let end = Utc.with_ymd_and_hms(2022, 5, 3, 0, 0, 0).unwrap();
+        let path = std::env::temp_dir().join(format!("feathr_schedule_{}.json", uuid::Uuid::new_v4().as_simple()));
+        let manifest = proj
+            .feature_gen_job(&["f1"], end - Duration::days(2), end, DateTimeResolution::Daily)
+            .await
+            .unwrap()
+            .sink(RedisSink::new("table1"))
+            .sink(
+                HdfsSink::with_account_key("abfss://output@account2.dfs.core.windows.net/features", "account2")
+                    .time_partitioned(DateTimeResolution::Daily)
+                    .update_latest()
+                    .overwrite_existing(),
+            )
+            .env_var("FEATHR_MODE", "batch")
+            .export_schedule_manifest(&path)
+            .unwrap();
+        let loaded = ScheduleManifest::load(&path).unwrap();
+        let content: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            content["storageKeyNames"],
+            serde_json::json!({ "account2.dfs.core.windows.net": "account2_STORAGE_KEY" })
+        );
+        assert_eq!(loaded.windows.len(), 2);
+        assert_eq!(loaded.windows[0].end, end);
+        assert_eq!(loaded.windows[1].start, end - Duration::days(2));
+        assert_eq!(loaded.windows[0].gen_config_str(), manifest.windows[0].gen_config_str());
+        assert!(loaded.feature_config.contains("f1"));
+        assert_eq!(loaded.input_path, "wasbs://a@b.blob.core.windows.net/c.csv");
+        let request = loaded
+            .job_options()
+            .gen_request(&loaded.feature_config, &loaded.windows[1].gen_config_str());
+        assert_eq!(request.name, manifest.job_name);
+        assert!(request.gen_job_config.contains("2022-05-02 00:00:00"));
+
+        // The replayed request has the same settings as the one built by the job builder
+        let built = proj
+            .feature_gen_job(&["f1"], end - Duration::days(2), end, DateTimeResolution::Daily)
+            .await
+            .unwrap()
+            .sink(RedisSink::new("table1"))
+            .sink(
+                HdfsSink::with_account_key("abfss://output@account2.dfs.core.windows.net/features", "account2")
+                    .time_partitioned(DateTimeResolution::Daily)
+                    .update_latest()
+                    .overwrite_existing(),
+            )
+            .env_var("FEATHR_MODE", "batch")
+            .build()
+            .unwrap();
+        let request = loaded.gen_request(&loaded.windows[1]);
+        assert_eq!(request.storage_key_names, built[1].storage_key_names);
+        assert_eq!(request.storage_key_names["account2.dfs.core.windows.net"], "account2_STORAGE_KEY");
+        assert_eq!(request.env_vars, built[1].env_vars);
+        assert_eq!(request.overwrite_paths, built[1].overwrite_paths);
+        assert_eq!(request.overwrite_paths.len(), 1);
+        assert_eq!(request.latest_pointers, built[1].latest_pointers);
+        assert_eq!(request.latest_pointers.len(), 1);
+        assert_eq!(request.secret_key, built[1].secret_key);
+
+        let requests = proj
+            .feature_gen_job(&["f1"], end - Duration::days(1), end, DateTimeResolution::Daily)
+            .await
+            .unwrap()
+            .sink(RedisSink::new("table1"))
+            .backfill_time(
+                Utc.with_ymd_and_hms(2022, 4, 1, 6, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2022, 4, 3, 12, 0, 0).unwrap(),
+            )
+            .build()
+            .unwrap();
+        let cutoffs: Vec<MaterializationSettings> =
+            requests.iter().map(|r| serde_json::from_str(&r.gen_job_config).unwrap()).collect();
+        assert_eq!(
+            cutoffs.iter().map(|s| s.operational.end_time).collect::<Vec<_>>(),
+            vec![
+                Utc.with_ymd_and_hms(2022, 4, 4, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2022, 4, 3, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2022, 4, 2, 0, 0, 0).unwrap(),
+            ]
+        );
+
+        // Incremental cutoffs depend on each other, they can't be backfilled at once
+        let mut builder = proj
+            .feature_gen_job(&["f1"], end - Duration::days(1), end, DateTimeResolution::Daily)
+            .await
+            .unwrap();
+        builder.sink(RedisSink::new("table1")).incremental(true);
+        let requests = builder.build().unwrap();
+        let cutoff: MaterializationSettings = serde_json::from_str(&requests[0].gen_job_config).unwrap();
+        assert!(cutoff.operational.enable_incremental);
+        builder.backfill_time(
+            Utc.with_ymd_and_hms(2022, 4, 1, 6, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2022, 4, 3, 12, 0, 0).unwrap(),
+        );
+        assert!(matches!(builder.build(), Err(Error::InvalidConfig(_))));
+
+        let requests = proj
+            .feature_gen_job(&["f1"], end - Duration::days(1), end, DateTimeResolution::Daily)
+            .await
+            .unwrap()
+            .sink(RedisSink::new("table1"))
+            .sink(
+                HdfsSink::new("abfss://a@b.dfs.core.windows.net/features/")
+                    .time_partitioned(DateTimeResolution::Daily)
+                    .update_latest(),
+            )
+            .schedule("0 0 1 * * ?")
+            .input_path("wasbs://a@b.blob.core.windows.net/d.csv")
+            .build()
+            .unwrap();
+        assert_eq!(requests[0].schedule.as_deref(), Some("0 0 1 * * ?"));
+        // Each scheduled run writes into the folder of its own window
+        assert_eq!(
+            requests[0].time_partitions,
+            vec![TimePartition {
+                sink: 1,
+                path: "abfss://a@b.dfs.core.windows.net/features".to_string(),
+                pattern: "%Y/%m/%d".to_string(),
+                update_latest: true,
+                overwrite_existing: false,
+            }]
+        );
+        assert_eq!(requests[0].input, "wasbs://a@b.blob.core.windows.net/d.csv");
+        assert!(matches!(
+            proj.feature_gen_job(&["f1"], end - Duration::days(1), end, DateTimeResolution::Daily)
+                .await
+                .unwrap()
+                .sink(RedisSink::new("table1"))
+                .schedule("0 1 * * *")
+                .build(),
+            Err(Error::InvalidSchedule(_, _))
+        ));
+        for cron_expr in ["0 0 25 * * ?", "0 61 1 * * ?", "0 0 1 * * 8"] {
+            assert!(matches!(
+                proj.feature_gen_job(&["f1"], end - Duration::days(1), end, DateTimeResolution::Daily)
+                    .await
+                    .unwrap()
+                    .sink(RedisSink::new("table1"))
+                    .schedule(cron_expr)
+                    .build(),
+                Err(Error::InvalidSchedule(_, _))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn streaming_job() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let kafka = proj
+            .kafka_source("k1")
+            .broker("ns1.servicebus.windows.net:9093")
+            .topic("trips")
+            .build()
+            .await
+            .unwrap();
+        let hdfs = proj.hdfs_source("h1", "wasbs://a@b.blob.core.windows.net/c.csv").build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        for (group, source, name) in [("g1", kafka, "f1"), ("g2", hdfs, "f2")] {
+            proj.anchor_group(group, source)
+                .build()
+                .await
+                .unwrap()
+                .anchor(name, FeatureType::INT32)
+                .unwrap()
+                .transform("x")
+                .keys(&[&k1])
+                .build()
+                .await
+                .unwrap();
+        }
+
+        let requests = proj
+            .feature_streaming_job(&["f1"])
+            .await
+            .unwrap()
+            .sink(RedisSink::new("table1").streaming(Some(Duration::minutes(10))))
+            .build()
+            .unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].streaming);
+        // A single cutoff without a batch window
+        let cutoff: MaterializationSettings = serde_json::from_str(&requests[0].gen_job_config).unwrap();
+        assert!(chrono::Utc::now() - cutoff.operational.end_time < Duration::minutes(1));
+        assert!(matches!(
+            proj.feature_streaming_job(&["f1"]).await.unwrap().sink(RedisSink::new("table1")).build(),
+            Err(Error::InvalidStreamingJob(_))
+        ));
+        assert!(matches!(
+            proj.feature_streaming_job(&["f1", "f2"]).await,
+            Err(Error::InvalidStreamingJob(_))
+        ));
+        assert!(matches!(
+            proj.feature_streaming_job(&["f1", "f3"]).await,
+            Err(Error::FeatureNotFound(name)) if name == "f3"
+        ));
+    }
+
+    #[tokio::test]
+    async fn introspect_project() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/a.csv")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        for name in ["f2", "f1"] {
+            g1.anchor(name, FeatureType::INT32)
+                .unwrap()
+                .transform("x")
+                .keys(&[&k1])
+                .build()
+                .await
+                .unwrap();
+        }
+        let f = proj.get_anchor_feature("g1", "f1").await.unwrap();
+        proj.derived_feature("d1", FeatureType::INT32)
+            .add_input(&f)
+            .transform("f1")
+            .build()
+            .await
+            .unwrap();
+
+        let sources: Vec<String> = proj.sources().await.into_iter().map(|(n, _)| n).collect();
+        assert_eq!(sources, vec!["PASSTHROUGH", "h1"]);
+        let groups = proj.anchor_groups().await;
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].1.get_id(), g1.get_id());
+        let anchors = proj.anchors("g1").await.unwrap();
+        assert_eq!(anchors.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(), vec!["f2", "f1"]);
+        assert_eq!(anchors[1].1.get_id(), f.get_id());
+        assert!(matches!(proj.anchors("g2").await, Err(Error::AnchorGroupNotFound(_))));
+        let derived = proj.derived_features().await;
+        assert_eq!(derived[0].0, "d1");
+        assert_eq!(derived[0].1.get_name(), "d1");
+        let inputs = derived[0].1.get_inputs();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].feature, "f1");
+        assert!(inputs[0].is_anchor_feature);
+        let graph = proj.dependency_graph().await;
+        assert_eq!(graph.len(), 1);
+        assert_eq!(graph["d1"], vec!["f1"]);
+    }
+
+    #[tokio::test]
+    async fn validate_feature_names() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj.hdfs_source("h1", "wasbs://a@b.blob.core.windows.net/c.csv").build().await.unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let f = g1
+            .anchor("tripDistance", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&TypedKey::new("c1", ValueType::INT32)])
+            .build()
+            .await
+            .unwrap();
+        proj.derived_feature("select", FeatureType::INT32)
+            .add_input(&f)
+            .transform("1")
+            .build()
+            .await
+            .unwrap();
+        let lints = proj.validate().await.unwrap();
+        let rules: Vec<(&str, LintRule)> = lints.iter().map(|l| (l.feature.as_str(), l.rule)).collect();
+        assert_eq!(
+            rules,
+            vec![("select", LintRule::ReservedWord), ("tripDistance", LintRule::SnakeCase)]
+        );
+
+        // The source column with the feature name is used if the transformation is omitted
+        let f = g1
+            .anchor("fare_amount", FeatureType::FLOAT)
+            .unwrap()
+            .keys(&[&TypedKey::new("c1", ValueType::INT32)])
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(f.get_transformation(), Transformation::from("fare_amount"));
+        // Explicitly set to the source column, not linted
+        g1.anchor("tip_amount", FeatureType::FLOAT)
+            .unwrap()
+            .transform("tip_amount")
+            .keys(&[&TypedKey::new("c1", ValueType::INT32)])
+            .build()
+            .await
+            .unwrap();
+        let lints = proj.validate().await.unwrap();
+        let implicit: Vec<&str> = lints
+            .iter()
+            .filter(|l| l.rule == LintRule::ImplicitTransformation)
+            .map(|l| l.feature.as_str())
+            .collect();
+        assert_eq!(implicit, vec!["fare_amount"]);
+        assert!(matches!(
+            proj.derived_feature("d1", FeatureType::INT32).add_input(&f).build().await,
+            Err(Error::MissingTransformation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn import_project() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/a.csv")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let f1 = g1
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let d1 = proj
+            .derived_feature("d1", FeatureType::INT32)
+            .add_input(&f1)
+            .transform("f1 + 1")
+            .build()
+            .await
+            .unwrap();
+        proj.derived_feature("d2", FeatureType::INT32)
+            .add_input(&d1)
+            .transform("d1 * 2")
+            .build()
+            .await
+            .unwrap();
+
+        let imported = FeathrProject::new_detached("p2").await;
+        imported
+            .inner
+            .write()
+            .await
+            .import_from(&*proj.inner.read().await)
+            .await
+            .unwrap();
+
+        let expected: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        let actual: serde_json::Value =
+            serde_json::from_str(&imported.get_feature_config().await.unwrap()).unwrap();
+        assert_eq!(expected, actual);
+        assert_eq!(
+            imported.get_derived_feature("d2").await.unwrap().inner.inputs["d1"].id,
+            imported.get_derived_feature("d1").await.unwrap().get_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn lagged_input() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/a.csv")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let f1 = g1
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        proj.derived_feature("d1", FeatureType::INT32)
+            .add_input(&f1)
+            .add_lagged_input(&f1, "f1_7d_ago", chrono::Duration::days(7))
+            .transform("f1 - f1_7d_ago")
+            .build()
+            .await
+            .unwrap();
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        let inputs = &conf["derivations"]["d1"]["inputs"];
+        assert_eq!(inputs["f1_7d_ago"]["feature"], "f1");
+        assert_eq!(inputs["f1_7d_ago"]["offset"], "7d");
+        assert!(inputs["f1"].get("offset").is_none());
+
+        let g2 = proj
+            .anchor_group("g2", Source::INPUT_CONTEXT())
+            .build()
+            .await
+            .unwrap();
+        let f2 = g2
+            .anchor("f2", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(
+            proj.derived_feature("d2", FeatureType::INT32)
+                .add_lagged_input(&f2, "f2_1d_ago", chrono::Duration::days(1))
+                .transform("f2_1d_ago")
+                .build()
+                .await,
+            Err(Error::InvalidLaggedInput(_, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn transform_template() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/a.csv")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        for days in [1, 7, 30] {
+            g1.anchor(&format!("f_sum_{}d", days), FeatureType::INT32)
+                .unwrap()
+                .transform(Transformation::window_agg("{col}", Aggregation::SUM, chrono::Duration::days(days)).unwrap())
+                .param("col", "fare_amount")
+                .keys(&[&k1])
+                .build()
+                .await
+                .unwrap();
+        }
+        let f = g1
+            .anchor("f_fare", FeatureType::DOUBLE)
+            .unwrap()
+            .transform("cast({col} as double) * {factor}")
+            .params([("col", "fare_amount"), ("factor", "1.5")])
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        proj.derived_feature("d1", FeatureType::DOUBLE)
+            .add_input(&f)
+            .transform("{{{input}}} + {offset}")
+            .param("input", "f_fare")
+            .param("offset", 1)
+            .build()
+            .await
+            .unwrap();
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        let features = &conf["anchors"]["g1"]["features"];
+        assert_eq!(features["f_sum_7d"]["def"], "fare_amount");
+        assert_eq!(features["f_fare"]["def"]["sqlExpr"], "cast(fare_amount as double) * 1.5");
+        assert_eq!(conf["derivations"]["d1"]["definition"], "{f_fare} + 1");
+
+        assert!(matches!(
+            g1.anchor("f2", FeatureType::INT32)
+                .unwrap()
+                .transform("{col} * {factor}")
+                .param("col", "x")
+                .keys(&[&k1])
+                .build()
+                .await,
+            Err(Error::MissingTemplateParameter(f, p)) if f == "f2" && p == "factor"
+        ));
+        // Transformations without parameters are not rendered
+        g1.anchor("f3", FeatureType::INT32)
+            .unwrap()
+            .transform("map_keys(m)['{x}']")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn source_registry() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/a.csv")
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(
+            proj.hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/b.csv")
+                .build()
+                .await,
+            Err(Error::DuplicateSource(_))
+        ));
+        assert_eq!(proj.get_source("h1").await.unwrap().get_id(), s.get_id());
+        assert_eq!(proj.list_sources().await.len(), 2);
+
+        proj.anchor_group("g1", s).build().await.unwrap();
+        assert!(matches!(
+            proj.remove_source("h1").await,
+            Err(Error::SourceInUse(_, groups)) if groups == vec!["g1".to_string()]
+        ));
+
+        proj.hdfs_source("h2", "wasbs://public@azurefeathrstorage.blob.core.windows.net/c.csv")
+            .build()
+            .await
+            .unwrap();
+        proj.remove_source("h2").await.unwrap();
+        assert!(proj.get_source("h2").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn replace_source() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/a.csv")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        g1.anchor("f1", FeatureType::DOUBLE)
+            .unwrap()
+            .keys(&[&TypedKey::new("c1", ValueType::INT32)])
+            .transform(Transformation::window_agg("fare", Aggregation::SUM, chrono::Duration::days(1)).unwrap())
+            .build()
+            .await
+            .unwrap();
+
+        // Replaced by a source in the same project, under another name
+        let s2 = proj
+            .hdfs_source("h2", "wasbs://public@azurefeathrstorage.blob.core.windows.net/b.csv")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let no_ts = proj
+            .hdfs_source("h3", "wasbs://public@azurefeathrstorage.blob.core.windows.net/c.csv")
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(
+            proj.replace_source("h1", no_ts).await,
+            Err(Error::SourceConflict(..))
+        ));
+        assert!(matches!(
+            proj.replace_source("h1", proj.INPUT_CONTEXT().await).await,
+            Err(Error::SourceConflict(..))
+        ));
+        proj.replace_source("h1", s2.clone()).await.unwrap();
+        assert!(proj.get_source("h1").await.is_err());
+        assert_eq!(proj.get_anchor_group("g1").await.unwrap().inner.source.get_id(), s2.get_id());
+
+        // Replaced by a source with the same name from a detached project
+        let other = FeathrProject::new_detached("p2").await;
+        let s3 = other
+            .hdfs_source("h2", "wasbs://public@azurefeathrstorage.blob.core.windows.net/d.csv")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        proj.replace_source("h2", s3.clone()).await.unwrap();
+        assert_eq!(proj.get_source("h2").await.unwrap().get_id(), s3.get_id());
+        assert_eq!(proj.get_anchor_group("g1").await.unwrap().inner.source.get_id(), s3.get_id());
+
+        // Name taken by another source
+        let s4 = other
+            .hdfs_source("h3", "wasbs://public@azurefeathrstorage.blob.core.windows.net/e.csv")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(
+            proj.replace_source("h2", s4).await,
+            Err(Error::DuplicateSource(_))
+        ));
+        assert!(matches!(
+            proj.replace_source("h4", s3).await,
+            Err(Error::SourceGroupNotFound(s)) if s == "h4"
+        ));
+    }
+
+    #[tokio::test]
+    async fn replace_source_in_registry() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", uuid::Uuid::new_v4().as_simple()));
+        let conf = format!(
+            "spark_config:\n  spark_cluster: 'livy'\n  livy:\n    url: 'http://livy:8998'\n    webhdfs_url: 'http://namenode:9870'\n    workspace_dir: 'hdfs://namenode:8020/feathr'\nfeature_registry:\n  type: local\n  local:\n    path: '{}'\n",
+            root.to_string_lossy()
+        );
+        let client = FeathrClient::from_str(&conf).await.unwrap();
+        let proj = client.new_project("p1").await.unwrap();
+        let s1 = proj
+            .hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/a.csv")
+            .build()
+            .await
+            .unwrap();
+        proj.anchor_group("g1", s1).build().await.unwrap();
+        let s2 = proj
+            .hdfs_source("h2", "wasbs://public@azurefeathrstorage.blob.core.windows.net/b.csv")
+            .build()
+            .await
+            .unwrap();
+        proj.replace_source("h1", s2.clone()).await.unwrap();
+
+        // The anchor group uses the new source after reloading the project from the registry
+        let reloaded = client.load_project("p1").await.unwrap();
+        let g1 = reloaded.get_anchor_group("g1").await.unwrap();
+        assert_eq!(g1.inner.source.get_id(), s2.get_id());
+        assert_eq!(g1.inner.source.get_name(), "h2");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn feature_conflict_in_registry() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", uuid::Uuid::new_v4().as_simple()));
+        let conf = format!(
+            "spark_config:\n  spark_cluster: 'livy'\n  livy:\n    url: 'http://livy:8998'\n    webhdfs_url: 'http://namenode:9870'\n    workspace_dir: 'hdfs://namenode:8020/feathr'\nfeature_registry:\n  type: local\n  local:\n    path: '{}'\n",
+            root.to_string_lossy()
+        );
+        let client = FeathrClient::from_str(&conf).await.unwrap();
+        let proj = client.new_project("p1").await.unwrap();
+        let s1 = proj.hdfs_source("h1", "a.csv").build().await.unwrap();
+        proj.anchor_group("g1", s1).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let anchor = |transform: &'static str| {
+            let client = client.clone();
+            let k1 = k1.clone();
+            async move {
+                let proj = client.load_project("p1").await.unwrap();
+                let g1 = proj.get_anchor_group("g1").await.unwrap();
+                let ret = g1
+                    .anchor("f1", FeatureType::INT32)
+                    .unwrap()
+                    .transform(transform)
+                    .keys(&[&k1])
+                    .build()
+                    .await;
+                (proj, ret)
+            }
+        };
+        let (_, f1) = anchor("x").await;
+        let id = f1.unwrap().get_id();
+
+        // A different definition of a registered feature is rejected, even if forced
+        let (proj, ret) = anchor("y").await;
+        assert!(matches!(ret, Err(Error::FeatureConflict(c)) if c[0].qualified_name == "p1__g1__f1"));
+        proj.set_force(true).await;
+        let g1 = proj.get_anchor_group("g1").await.unwrap();
+        let ret = g1.anchor("f1", FeatureType::INT32).unwrap().transform("y").keys(&[&k1]).build().await;
+        assert!(matches!(ret, Err(Error::FeatureConflict(_))));
+
+        // Overwriting updates the registered feature in place
+        proj.set_overwrite(true).await;
+        let f1 = g1.anchor("f1", FeatureType::INT32).unwrap().transform("y").keys(&[&k1]).build().await.unwrap();
+        assert_eq!(f1.get_id(), id);
+        assert_eq!(g1.get_anchor_features().await, vec!["f1"]);
+        let reloaded = client.load_project("p1").await.unwrap();
+        let f1 = reloaded.get_anchor_group("g1").await.unwrap().get_anchor("f1").await.unwrap();
+        assert_eq!(f1.get_id(), id);
+        assert_eq!(f1.get_transformation(), Transformation::from("y"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn anchors_from_columns() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/a.csv")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let features = g1
+            .anchors_from_columns(
+                &[&k1],
+                &[
+                    ("f_fare", "fare_amount", FeatureType::DOUBLE),
+                    ("f_distance", "trip_distance", FeatureType::DOUBLE),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(features.len(), 2);
+        let windows: Vec<_> = [1, 7]
+            .into_iter()
+            .map(|days| {
+                (
+                    format!("f_fare_sum_{}d", days),
+                    Transformation::window_agg("fare_amount", Aggregation::SUM, chrono::Duration::days(days)).unwrap(),
+                )
+            })
+            .collect();
+        let windows: Vec<_> = windows
+            .iter()
+            .map(|(name, t)| (name.as_str(), t, FeatureType::DOUBLE))
+            .collect();
+        g1.anchors_from_columns(&[&k1], &windows).await.unwrap();
+        assert_eq!(
+            g1.get_anchor_features().await,
+            vec!["f_fare", "f_distance", "f_fare_sum_1d", "f_fare_sum_7d"]
+        );
+        assert_eq!(g1.get_anchor("f_fare").await.unwrap().get_key(), vec![k1]);
+    }
+
+    #[tokio::test]
+    async fn source_format() {
+        let proj = FeathrProject::new_detached("p1").await;
+        assert!(matches!(
+            proj.hdfs_source("h0", "abfss://a@b.dfs.core.windows.net/c")
+                .csv_delimiter("|")
+                .build()
+                .await,
+            Err(Error::InvalidConfig(_))
+        ));
+        let s1 = proj
+            .hdfs_source("h1", "abfss://a@b.dfs.core.windows.net/delta_table")
+            .format("delta".parse().unwrap())
+            .build()
+            .await
+            .unwrap();
+        let s2 = proj
+            .hdfs_source("h2", "abfss://a@b.dfs.core.windows.net/c.txt")
+            .format(SourceFormat::Csv)
+            .csv_delimiter("|")
+            .build()
+            .await
+            .unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        for (g, s) in [("g1", s1), ("g2", s2)] {
+            proj.anchor_group(g, s)
+                .build()
+                .await
+                .unwrap()
+                .anchor(&format!("{}_f", g), FeatureType::INT32)
+                .unwrap()
+                .transform("x")
+                .keys(&[&k1])
+                .build()
+                .await
+                .unwrap();
+        }
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        assert_eq!(conf["sources"]["h1"]["location"]["format"], "delta");
+        assert!(conf["sources"]["h1"]["location"].get("csvDelimiter").is_none());
+        assert_eq!(conf["sources"]["h2"]["location"]["format"], "csv");
+        assert_eq!(conf["sources"]["h2"]["location"]["csvDelimiter"], "|");
+        assert!(conf["sources"]["h1"].get("options").is_none());
+    }
+
+    #[tokio::test]
+    async fn source_schema() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "abfss://a@b.dfs.core.windows.net/c.csv")
+            .schema_column("c1", ValueType::INT32)
+            .schema_column("fare", ValueType::DOUBLE)
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(s.get_schema().len(), 2);
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        for (name, transform) in [
+            ("f1", Transformation::from("fare * 2")),
+            ("f2", Transformation::window_agg("cast(fare as int)", Aggregation::SUM, chrono::Duration::days(1)).unwrap()),
+        ] {
+            g1.anchor(name, FeatureType::DOUBLE)
+                .unwrap()
+                .transform(transform)
+                .keys(&[&k1])
+                .build()
+                .await
+                .unwrap();
+        }
+        proj.get_feature_config().await.unwrap();
+
+        g1.anchor("f3", FeatureType::DOUBLE)
+            .unwrap()
+            .transform("case when fare > 10 then tip else 0 end")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(
+            proj.get_feature_config().await,
+            Err(Error::UndeclaredColumn(f, c, s)) if f == "f3" && c == "tip" && s == "h1"
+        ));
+    }
+
+    #[tokio::test]
+    async fn source_schema_keys() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "abfss://a@b.dfs.core.windows.net/c.csv")
+            .schema_column("c1", ValueType::INT32)
+            .schema_column("Fare", ValueType::DOUBLE)
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        // Column names are case-insensitive, key expressions are checked too
+        g1.anchor("f1", FeatureType::DOUBLE)
+            .unwrap()
+            .transform("fare * 2")
+            .keys(&[&TypedKey::new("C1", ValueType::INT32)])
+            .build()
+            .await
+            .unwrap();
+        proj.get_feature_config().await.unwrap();
+
+        let s2 = proj
+            .hdfs_source("h2", "abfss://a@b.dfs.core.windows.net/d.csv")
+            .schema_column("c1", ValueType::INT32)
+            .schema_column("fare", ValueType::DOUBLE)
+            .build()
+            .await
+            .unwrap();
+        let g2 = proj.anchor_group("g2", s2).build().await.unwrap();
+        g2.anchor("f2", FeatureType::DOUBLE)
+            .unwrap()
+            .transform("fare")
+            .keys(&[&TypedKey::new("c1", ValueType::STRING)])
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(
+            proj.get_feature_config().await,
+            Err(Error::KeyColumnTypeMismatch(f, c, ValueType::STRING, ValueType::INT32, s))
+                if f == "f2" && c == "c1" && s == "h2"
+        ));
+
+        let proj = FeathrProject::new_detached("p2").await;
+        let s3 = proj
+            .hdfs_source("h3", "abfss://a@b.dfs.core.windows.net/e.csv")
+            .schema_column("fare", ValueType::DOUBLE)
+            .build()
+            .await
+            .unwrap();
+        let g3 = proj.anchor_group("g3", s3).build().await.unwrap();
+        g3.anchor("f3", FeatureType::DOUBLE)
+            .unwrap()
+            .transform("fare")
+            .keys(&[&TypedKey::new("concat(city, '_', zip)", ValueType::STRING)])
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(
+            proj.get_feature_config().await,
+            Err(Error::UndeclaredColumn(f, c, _)) if f == "f3" && c == "city"
+        ));
+
+        // Preprocessing may add columns, the schema is not checked
+        let proj = FeathrProject::new_detached("p3").await;
+        let s4 = proj
+            .hdfs_source("h4", "abfss://a@b.dfs.core.windows.net/f.csv")
+            .schema_column("fare", ValueType::DOUBLE)
+            .preprocessing("add_tip")
+            .build()
+            .await
+            .unwrap();
+        let g4 = proj.anchor_group("g4", s4).build().await.unwrap();
+        g4.anchor("f4", FeatureType::DOUBLE)
+            .unwrap()
+            .transform("fare + tip")
+            .keys(&[&TypedKey::new("c1", ValueType::INT32)])
+            .build()
+            .await
+            .unwrap();
+        proj.get_feature_config().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn preprocessing_file() {
+        let path = std::env::temp_dir().join(format!("feathr_pp_{}.py", uuid::Uuid::new_v4().as_simple()));
+        std::fs::write(&path, "import pyspark\n\ndef add_fare(df):\n    return df\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "abfss://a@b.dfs.core.windows.net/c.csv")
+            .preprocessing_file(&path)
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(s.get_preprocessing().as_deref(), Some("add_fare"));
+        assert!(matches!(
+            proj.hdfs_source("h2", "abfss://a@b.dfs.core.windows.net/c.csv")
+                .preprocessing("remove_fare")
+                .preprocessing_file(&path)
+                .build()
+                .await,
+            Err(Error::InvalidConfig(_))
+        ));
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        proj.anchor_group("g1", s)
+            .build()
+            .await
+            .unwrap()
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let ob = ObservationSettings::new("wasbs://a/b.csv", "ts", "epoch");
+        let request = proj
+            .feature_join_job(ob, &[&FeatureQuery::new(&["f1"], &[&k1])], "wasbs://a/out")
+            .await
+            .unwrap()
+            .output_path("wasbs://a/out")
+            .build();
+        assert_eq!(request.python_files, vec![path.clone()]);
+        assert!(request.main_python_script.unwrap().contains("add_fare"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn source_options() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s1 = proj
+            .hdfs_source("h1", "abfss://a@b.dfs.core.windows.net/c.json")
+            .option("multiLine", "true")
+            .build()
+            .await
+            .unwrap();
+        let s2 = proj
+            .jdbc_source("j1", "jdbc:sqlserver://a.database.windows.net:1433;database=b")
+            .dbtable("trips")
+            .option("fetchsize", "1000")
+            .build()
+            .await
+            .unwrap();
+        let s3 = proj
+            .snowflake_source("sf1", "a.snowflakecomputing.com")
+            .dbtable("trips")
+            .option("sfWarehouse", "w1")
+            .build()
+            .await
+            .unwrap();
+        let s4 = proj
+            .hdfs_source("h2", "abfss://a@b.dfs.core.windows.net/d")
+            .format(SourceFormat::Delta)
+            .options([("mergeSchema".to_string(), "true".to_string())].into())
+            .build()
+            .await
+            .unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        for (g, s) in [("g1", s1), ("g2", s2), ("g3", s3), ("g4", s4)] {
+            proj.anchor_group(g, s)
+                .build()
+                .await
+                .unwrap()
+                .anchor(&format!("{}_f", g), FeatureType::INT32)
+                .unwrap()
+                .transform("x")
+                .keys(&[&k1])
+                .build()
+                .await
+                .unwrap();
+        }
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        assert_eq!(conf["sources"]["h1"]["options"], serde_json::json!({"multiLine": "true"}));
+        assert_eq!(conf["sources"]["j1"]["options"], serde_json::json!({"fetchsize": "1000"}));
+        assert_eq!(conf["sources"]["sf1"]["options"], serde_json::json!({"sfWarehouse": "w1"}));
+        assert_eq!(conf["sources"]["h2"]["options"], serde_json::json!({"mergeSchema": "true"}));
+
+        // Options are kept in the registry
+        let def: crate::registry_client::api_models::SourceDef =
+            proj.get_source("h2").await.unwrap().inner.as_ref().to_owned().into();
+        assert_eq!(def.options["mergeSchema"], "true");
+    }
+
+    #[tokio::test]
+    async fn gen_job_secret_keys() {
+        use chrono::{TimeZone, Utc};
+
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj.mssql_source("sql1", "feathrtest", "trips").dbtable("t").build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        proj.anchor_group("g1", s)
+            .build()
+            .await
+            .unwrap()
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let end = Utc.with_ymd_and_hms(2022, 5, 3, 0, 0, 0).unwrap();
+        let requests = proj
+            .feature_gen_job(&["f1"], end - Duration::days(1), end, DateTimeResolution::Daily)
+            .await
+            .unwrap()
+            .sink(JdbcSink::new("sql1", "jdbc:sqlserver://feathrtest.database.windows.net:1433", "t").auth(JdbcSourceAuth::Userpass))
+            .sink(CosmosDbSink::new("cosmos1", "https://cosmos1.documents.azure.com:443/", "feathr", "features"))
+            .build()
+            .unwrap();
+        assert_eq!(requests[0].secret_key, vec!["sql1_PASSWORD", "sql1_USER"]);
+        assert_eq!(requests[0].cosmos_key_names, vec!["cosmos1_KEY"]);
+    }
+
+    #[tokio::test]
+    async fn mssql_source() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .mssql_source("sql1", "feathrtest", "trips")
+            .dbtable("green_tripdata")
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(s.get_secret_keys(), vec!["sql1_USER", "sql1_PASSWORD"]);
+        let s2 = proj
+            .mssql_source("sql2", "feathrtest.database.chinacloudapi.cn", "trips")
+            .query("select * from green_tripdata")
+            .build()
+            .await
+            .unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        for (g, s) in [("g1", s), ("g2", s2)] {
+            proj.anchor_group(g, s)
+                .build()
+                .await
+                .unwrap()
+                .anchor(&format!("{}_f", g), FeatureType::INT32)
+                .unwrap()
+                .transform("x")
+                .keys(&[&k1])
+                .build()
+                .await
+                .unwrap();
+        }
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        let location = &conf["sources"]["sql1"]["location"];
+        assert!(location["url"]
+            .as_str()
+            .unwrap()
+            .starts_with("jdbc:sqlserver://feathrtest.database.windows.net:1433;database=trips;"));
+        assert_eq!(location["driver"], "com.microsoft.sqlserver.jdbc.SQLServerDriver");
+        assert_eq!(location["user"], "${sql1_USER}");
+        assert_eq!(location["password"], "${sql1_PASSWORD}");
+        assert!(location["url"]
+            .as_str()
+            .unwrap()
+            .contains(";hostNameInCertificate=*.database.windows.net;"));
+        let url = conf["sources"]["sql2"]["location"]["url"].as_str().unwrap();
+        assert!(url.starts_with("jdbc:sqlserver://feathrtest.database.chinacloudapi.cn:1433;"));
+        assert!(!url.contains("hostNameInCertificate"));
+    }
+
+    #[tokio::test]
+    async fn jdbc_driver() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .jdbc_source("j1", "jdbc:postgresql://localhost:5432/db")
+            .dbtable("trips")
+            .driver("org.postgresql.Driver")
+            .connection_property("sslmode", "require")
+            .build()
+            .await
+            .unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        proj.anchor_group("g1", s)
+            .build()
+            .await
+            .unwrap()
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        assert_eq!(conf["sources"]["j1"]["location"]["driver"], "org.postgresql.Driver");
+        assert_eq!(
+            conf["sources"]["j1"]["location"]["connectionProperties"],
+            serde_json::json!({"sslmode": "require"})
+        );
+        let ob = ObservationSettings::new("wasbs://a/b.csv", "ts", "epoch");
+        let request = proj
+            .feature_join_job(ob, &[&FeatureQuery::new(&["f1"], &[&k1])], "wasbs://a/out")
+            .await
+            .unwrap()
+            .output_path("wasbs://a/out")
+            .build();
+        assert_eq!(request.jdbc_driver.as_deref(), Some("org.postgresql.Driver"));
+        assert_eq!(request.jdbc_properties["sslmode"], "require");
+
+        // The same settings can be shared by multiple sources
+        proj.jdbc_source("j2", "jdbc:postgresql://localhost:5432/db2")
+            .driver("org.postgresql.Driver")
+            .connection_property("sslmode", "require")
+            .build()
+            .await
+            .unwrap();
+        assert!(proj.get_jdbc_config().await.is_ok());
+
+        // Conflicting settings cannot be passed to the same job
+        proj.jdbc_source("j3", "jdbc:postgresql://localhost:5432/db3")
+            .connection_property("sslmode", "disable")
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(
+            proj.feature_gen_job(&["f1"], chrono::Utc::now() - chrono::Duration::days(1), chrono::Utc::now(), DateTimeResolution::Daily)
+                .await,
+            Err(Error::JdbcConfigConflict(_, b, _)) if b == "j3"
+        ));
+        proj.jdbc_source("j4", "jdbc:mysql://localhost:3306/db4")
+            .driver("com.mysql.cj.jdbc.Driver")
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(proj.get_jdbc_config().await, Err(Error::JdbcConfigConflict(..))));
+    }
+
+    #[tokio::test]
+    async fn time_partitioned_source() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "abfss://a@b.dfs.core.windows.net/trips/")
+            .time_partition_pattern("yyyy/MM/dd")
+            .postfix_path("hourly")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        proj.anchor_group("g1", s)
+            .build()
+            .await
+            .unwrap()
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&TypedKey::new("c1", ValueType::INT32)])
+            .build()
+            .await
+            .unwrap();
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        assert_eq!(conf["sources"]["h1"]["timePartitionPattern"], "yyyy/MM/dd");
+        assert_eq!(conf["sources"]["h1"]["postfixPath"], "hourly");
+
+        assert!(matches!(
+            proj.hdfs_source("h2", "abfss://a@b.dfs.core.windows.net/trips/")
+                .postfix_path("hourly")
+                .build()
+                .await,
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn generic_source() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .generic_source("cosmos1", "cosmos.oltp")
+            .option("spark.cosmos.accountEndpoint", "https://db1.documents.azure.com:443/")
+            .option("spark.cosmos.accountKey", "${cosmos1_KEY}")
+            .options([("spark.cosmos.container".to_string(), "trips".to_string())].into_iter().collect())
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(s.get_secret_keys(), vec!["cosmos1_KEY"]);
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        g1.anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        assert_eq!(
+            conf["sources"]["cosmos1"]["location"],
+            serde_json::json!({
+                "type": "generic",
+                "format": "cosmos.oltp",
+                "spark__cosmos__accountEndpoint": "https://db1.documents.azure.com:443/",
+                "spark__cosmos__accountKey": "${cosmos1_KEY}",
+                "spark__cosmos__container": "trips",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn elastic_source() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .elastic_source("es1", &["es-1:9200", "es-2:9200"], "trips")
+            .auth(ElasticSourceAuth::Userpass)
+            .option("es.nodes.wan.only", "true")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(s.get_secret_keys(), vec!["es1_PASSWORD", "es1_USER"]);
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        g1.anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        let location = &conf["sources"]["es1"]["location"];
+        assert_eq!(
+            location,
+            &serde_json::json!({
+                "type": "generic",
+                "format": "org.elasticsearch.spark.sql",
+                "es__nodes": "es-1:9200,es-2:9200",
+                "es__resource": "trips",
+                "es__nodes__wan__only": "true",
+                "es__net__http__auth__user": "${es1_USER}",
+                "es__net__http__auth__pass": "${es1_PASSWORD}",
+            })
+        );
+        assert!(matches!(
+            serde_json::from_value(location.to_owned()).unwrap(),
+            SourceLocation::Generic { .. }
+        ));
+        assert_eq!(proj.get_secret_keys().await.unwrap(), vec!["es1_PASSWORD", "es1_USER"]);
+    }
+
+    #[tokio::test]
+    async fn eventhub_source() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .eventhub_source("eh1")
+            .consumer_group("feathr")
+            .option("eventhubs.startingPosition", r#"{"offset": "-1", "isInclusive": true}"#)
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(s.get_secret_keys(), vec!["eh1_CONNECTION_STRING"]);
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        g1.anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&TypedKey::new("c1", ValueType::INT32)])
+            .build()
+            .await
+            .unwrap();
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        let location = &conf["sources"]["eh1"]["location"];
+        assert_eq!(location["format"], "eventhubs");
+        // Nothing but the reference to the secret is in the config
+        assert_eq!(location["eventhubs__connectionString"], "${eh1_CONNECTION_STRING}");
+        assert_eq!(location["eventhubs__consumerGroup"], "feathr");
+        assert_eq!(proj.get_secret_keys().await.unwrap(), vec!["eh1_CONNECTION_STRING"]);
+    }
+
+    #[tokio::test]
+    async fn snowflake_source() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let url = "snowflake://dqllago-ol19457.snowflakecomputing.com/?sfDatabase=DB&sfSchema=PUBLIC";
+        assert!(matches!(
+            proj.snowflake_source("s0", url).build().await,
+            Err(Error::SourceNoQuery(_))
+        ));
+        let s = proj
+            .snowflake_source("s1", url)
+            .dbtable("CALL_CENTER")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        g1.anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        let location = &conf["sources"]["s1"]["location"];
+        assert_eq!(
+            location,
+            &serde_json::json!({"type": "snowflake", "url": url, "dbtable": "CALL_CENTER"})
+        );
+        assert!(matches!(
+            serde_json::from_value(location.to_owned()).unwrap(),
+            SourceLocation::Snowflake { .. }
+        ));
+        let def: crate::registry_client::api_models::SourceDef =
+            proj.get_source("s1").await.unwrap().inner.as_ref().to_owned().into();
+        assert_eq!(def.source_type, "snowflake");
+        assert_eq!(def.dbtable.as_deref(), Some("CALL_CENTER"));
+    }
+
+    #[tokio::test]
+    async fn list_features() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "wasbs://public@azurefeathrstorage.blob.core.windows.net/a.csv")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let k2 = TypedKey::new("c2", ValueType::INT32).key_column_alias("a2");
+        let f1 = g1
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .add_tag("team", "t1")
+            .build()
+            .await
+            .unwrap();
+        let g2 = proj
+            .anchor_group("g2", proj.INPUT_CONTEXT().await)
+            .build()
+            .await
+            .unwrap();
+        g2.anchor("f2", FeatureType::FLOAT)
+            .unwrap()
+            .transform("y")
+            .keys(&[&k2])
+            .add_tag("team", "t2")
+            .build()
+            .await
+            .unwrap();
+        proj.derived_feature("d1", FeatureType::INT32)
+            .add_input(&f1)
+            .transform("f1 + 1")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+
+        let names = |fs: Vec<FeatureDescriptor>| -> Vec<String> {
+            fs.into_iter().map(|f| f.name).collect()
+        };
+        assert_eq!(
+            names(proj.list_features(&FeatureFilter::new()).await),
+            vec!["d1", "f1", "f2"]
+        );
+        assert_eq!(
+            names(proj.list_features(FeatureFilter::new().key_column("c1")).await),
+            vec!["d1", "f1"]
+        );
+        assert_eq!(
+            names(proj.list_features(FeatureFilter::new().key_column("a2")).await),
+            vec!["f2"]
+        );
+        assert_eq!(
+            names(
+                proj.list_features(FeatureFilter::new().feature_type(FeatureType::INT32))
+                    .await
+            ),
+            vec!["d1", "f1"]
+        );
+        let fs = proj
+            .list_features(FeatureFilter::new().tag("team", "t2"))
+            .await;
+        assert_eq!(names(fs.clone()), vec!["f2"]);
+        assert_eq!(fs[0].group.as_deref(), Some("g2"));
+        assert_eq!(
+            names(proj.list_features(FeatureFilter::new().has_tag("team")).await),
+            vec!["f1", "f2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_materialization_detached() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let end = chrono::Utc::now();
+        let sink = HdfsSink::new("abfss://a@b.dfs.core.windows.net/features").time_partitioned(DateTimeResolution::Daily);
+        assert!(matches!(
+            proj.resume_materialization(&["f1"], end - chrono::Duration::days(3), end, DateTimeResolution::Daily, &sink, false)
+                .await,
+            Err(Error::DetachedProject)
+        ));
+    }
+
+    #[tokio::test]
+    async fn project_file() {
+        let proj = FeathrProject::new_detached("p1").await;
+        proj.inner.write().await.registry_tags.insert("owner".to_string(), "o1".to_string());
+        let s = proj
+            .hdfs_source("h1", "abfss://a@b.dfs.core.windows.net/c.csv")
+            .schema_column("c1", ValueType::INT32)
+            .schema_column("x", ValueType::INT32)
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj
+            .anchor_group("g1", s)
+            .add_registry_tag("team", "t1")
+            .build()
+            .await
+            .unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let f1 = g1
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform(Transformation::window_agg("x", Aggregation::SUM, chrono::Duration::days(1)).unwrap())
+            .keys(&[&k1])
+            .add_tag("team", "t1")
+            .build()
+            .await
+            .unwrap();
+        let g2 = proj
+            .anchor_group("g2", Source::INPUT_CONTEXT())
+            .build()
+            .await
+            .unwrap();
+        g2.anchor("f2", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .build()
+            .await
+            .unwrap();
+        let d1 = proj
+            .derived_feature("d1", FeatureType::INT32)
+            .add_input(&f1)
+            .add_lagged_input(&f1, "f1_7d_ago", chrono::Duration::days(7))
+            .transform("f1 - f1_7d_ago")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        proj.derived_feature("d2", FeatureType::INT32)
+            .add_input(&d1)
+            .transform("d1 * 2")
+            .keys(&[&k1])
+            .add_tag("team", "t2")
+            .build()
+            .await
+            .unwrap();
+
+        for ext in ["json", "yaml"] {
+            let path = std::env::temp_dir().join(format!("feathr_project_{}.{}", uuid::Uuid::new_v4().as_simple(), ext));
+            proj.to_file(&path).await.unwrap();
+            let loaded = FeathrProject::from_file(&path).await.unwrap();
+
+            assert_eq!(loaded.get_name().await, "p1");
+            assert_eq!(loaded.get_registry_tags().await, proj.get_registry_tags().await);
+            let expected: serde_json::Value =
+                serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+            let actual: serde_json::Value =
+                serde_json::from_str(&loaded.get_feature_config().await.unwrap()).unwrap();
+            assert_eq!(expected, actual);
+            let without_id = |fs: Vec<FeatureDescriptor>| {
+                fs.into_iter()
+                    .map(|f| FeatureDescriptor { id: uuid::Uuid::nil(), ..f })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                without_id(loaded.list_features(&FeatureFilter::new()).await),
+                without_id(proj.list_features(&FeatureFilter::new()).await)
+            );
+            assert_eq!(loaded.get_source("h1").await.unwrap().get_schema().len(), 2);
+            assert_eq!(
+                loaded.get_derived_feature("d2").await.unwrap().inner.inputs["d1"].id,
+                loaded.get_derived_feature("d1").await.unwrap().get_id()
+            );
+
+            // Saving the loaded project gives the same file
+            let content = std::fs::read_to_string(&path).unwrap();
+            loaded.to_file(&path).await.unwrap();
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn import_feature_conf() {
+        let conf = r#"
+            anchors: {
+                request_features: {
+                    source: PASSTHROUGH
+                    key: [NOT_NEEDED]
+                    features: {
+                        f_trip_distance: "(float)trip_distance"
+                        f_is_long_trip_distance: {
+                            def: "cast_float(trip_distance)>30"
+                            type: BOOLEAN
+                        }
+                    }
+                }
+                aggregationFeatures: {
+                    source: nycTaxiBatchSource
+                    key: DOLocationID
+                    features: {
+                        f_location_avg_fare: {
+                            def: "cast_float(fare_amount)"
+                            aggregation: AVG
+                            window: 90d
+                            type: { type: TENSOR, tensorCategory: DENSE, dimensionType: [], valType: FLOAT }
+                        }
+                        f_location_max_fare.def.sqlExpr: "fare_amount"
+                        f_location_max_fare.type: DOUBLE
+                    }
+                }
+            }
+
+            derivations: {
+                f_trip_distance_rounded: {
+                    definition: "f_trip_distance * 10"
+                    type: NUMERIC
+                }
+                f_fare_ratio: {
+                    key: [location]
+                    inputs: {
+                        avg: { key: location, feature: f_location_avg_fare }
+                        max: { key: location, feature: f_location_max_fare }
+                    }
+                    definition.sqlExpr: "avg / max"
+                    type: FLOAT
+                }
+            }
+
+            sources: {
+                nycTaxiBatchSource: {
+                    location: { path: "wasbs://public@azurefeathrstorage.blob.core.windows.net/sample_data/green_tripdata_2020-04.csv" }
+                    timeWindowParameters: {
+                        timestampColumn: "lpep_dropoff_datetime"
+                        timestampColumnFormat: "yyyy-MM-dd HH:mm:ss"
+                    }
+                }
+            }
+        "#;
+        let proj = FeathrProject::from_feature_conf_str("nyc_taxi", conf).await.unwrap();
+        assert_eq!(proj.get_name().await, "nyc_taxi");
+        assert_eq!(proj.get_sources().await.len(), 2);
+        let f = proj
+            .get_anchor_feature("aggregationFeatures", "f_location_avg_fare")
+            .await
+            .unwrap();
+        assert_eq!(f.get_type(), FeatureType::FLOAT);
+        assert_eq!(f.get_key_alias(), vec!["DOLocationID"]);
+        assert!(matches!(
+            f.get_transformation(),
+            Transformation::WindowAgg { agg_func: Some(Aggregation::AVG), window: Some(w), .. } if w == chrono::Duration::days(90)
+        ));
+        assert_eq!(
+            proj.get_anchor_feature("request_features", "f_is_long_trip_distance")
+                .await
+                .unwrap()
+                .get_type(),
+            FeatureType::BOOLEAN
+        );
+
+        let rounded = proj.get_derived_feature("f_trip_distance_rounded").await.unwrap();
+        assert_eq!(rounded.get_type(), FeatureType::FLOAT);
+        assert_eq!(
+            rounded.inner.inputs["f_trip_distance"].id,
+            proj.get_anchor_feature("request_features", "f_trip_distance")
+                .await
+                .unwrap()
+                .get_id()
+        );
+        assert_eq!(
+            proj.dependency_graph().await["f_fare_ratio"],
+            vec!["f_location_avg_fare", "f_location_max_fare"]
+        );
+
+        let conf: serde_json::Value =
+            serde_json::from_str(&proj.get_feature_config().await.unwrap()).unwrap();
+        assert_eq!(conf["derivations"]["f_fare_ratio"]["inputs"]["avg"]["key"], serde_json::json!(["location"]));
+        assert_eq!(
+            conf["anchors"]["aggregationFeatures"]["features"]["f_location_max_fare"]["def"]["sqlExpr"],
+            "fare_amount"
+        );
+
+        assert!(matches!(
+            FeathrProject::from_feature_conf_str("p", "anchors: { a: { source: s1, features: { f: x } } }").await,
+            Err(Error::SourceGroupNotFound(s)) if s == "s1"
+        ));
+    }
+
+    #[tokio::test]
+    async fn lineage_graph() {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("h1", "abfss://a@b.dfs.core.windows.net/c.csv")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let f1 = g1
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let d1 = proj
+            .derived_feature("d1", FeatureType::INT32)
+            .add_input(&f1)
+            .transform("f1 + 1")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        proj.derived_feature("d2", FeatureType::INT32)
+            .add_input(&d1)
+            .transform("d1 * 2")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+
+        let graph = proj.lineage_graph().await;
+        assert_eq!(
+            graph
+                .nodes
+                .iter()
+                .map(|n| (n.qualified_name.as_str(), n.node_type))
+                .collect::<Vec<_>>(),
+            vec![
+                ("p1__h1", LineageNodeType::Source),
+                ("p1__g1", LineageNodeType::Anchor),
+                ("p1__g1__f1", LineageNodeType::AnchorFeature),
+                ("p1__d1", LineageNodeType::DerivedFeature),
+                ("p1__d2", LineageNodeType::DerivedFeature),
+            ]
+        );
+        let id = |i: usize| graph.nodes[i].id;
+        assert_eq!(
+            graph.edges,
+            vec![
+                LineageEdge { from: id(0), to: id(1) },
+                LineageEdge { from: id(1), to: id(2) },
+                LineageEdge { from: id(2), to: id(3) },
+                LineageEdge { from: id(3), to: id(4) },
+            ]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph \"p1\" {\n"));
+        assert!(dot.contains(&format!("\"{}\" [label=\"h1\", shape=cylinder];", id(0))));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\";", id(3), id(4))));
+        assert!(dot.ends_with("}\n"));
+    }
 }
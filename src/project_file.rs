@@ -0,0 +1,391 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    feature::{AnchorFeatureImpl, DerivedFeatureImpl, FeatureBase, InputFeature},
+    project::{AnchorGroupImpl, FeathrProjectImpl},
+    source::SourceImpl,
+    Error, Feature, FeatureType, Source, Transformation, TypedKey, ValueType,
+};
+
+/**
+ * File format of a project definition, decided by the file extension
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ProjectFileFormat {
+    Json,
+    Yaml,
+}
+
+impl ProjectFileFormat {
+    /**
+     * `.yaml` and `.yml` files are YAML, everything else is JSON
+     */
+    pub(crate) fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .as_deref()
+        {
+            Some("yaml") | Some("yml") => ProjectFileFormat::Yaml,
+            _ => ProjectFileFormat::Json,
+        }
+    }
+}
+
+/**
+ * Self contained project definition, entities are ordered by name so the file diffs cleanly
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectFile {
+    pub(crate) name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) tags: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) sources: Vec<SourceFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) anchor_groups: Vec<AnchorGroupFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) derived_features: Vec<DerivedFeatureFile>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SourceFile {
+    pub(crate) name: String,
+    #[serde(flatten)]
+    pub(crate) source: SourceImpl,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) schema: Vec<ColumnFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) preprocessing: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) preprocessing_file: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) tags: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ColumnFile {
+    pub(crate) name: String,
+    #[serde(rename = "type")]
+    pub(crate) value_type: ValueType,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AnchorGroupFile {
+    pub(crate) name: String,
+    pub(crate) source: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) tags: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) features: Vec<AnchorFeatureFile>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AnchorFeatureFile {
+    pub(crate) name: String,
+    #[serde(rename = "type")]
+    pub(crate) feature_type: FeatureType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) key: Vec<TypedKey>,
+    pub(crate) transformation: Transformation,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) tags: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DerivedFeatureFile {
+    pub(crate) name: String,
+    #[serde(rename = "type")]
+    pub(crate) feature_type: FeatureType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) key: Vec<TypedKey>,
+    pub(crate) transformation: Transformation,
+    pub(crate) inputs: Vec<DerivedInputFile>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) tags: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DerivedInputFile {
+    /**
+     * Name the derived feature refers the input by, same as `feature` unless the input is lagged
+     */
+    pub(crate) name: String,
+    pub(crate) feature: String,
+    pub(crate) key: Vec<TypedKey>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::hocon_duration::option"
+    )]
+    pub(crate) offset: Option<Duration>,
+}
+
+/**
+ * Features without key use the dummy key, which is implied in the file
+ */
+fn file_key(key: Vec<TypedKey>) -> Vec<TypedKey> {
+    if key == vec![TypedKey::DUMMY_KEY()] {
+        vec![]
+    } else {
+        key
+    }
+}
+
+fn feature_base(
+    name: String,
+    feature_type: FeatureType,
+    key: &[TypedKey],
+    tags: BTreeMap<String, String>,
+) -> FeatureBase {
+    FeatureBase {
+        id: Uuid::new_v4(),
+        feature_alias: name.clone(),
+        name,
+        feature_type,
+        key: if key.is_empty() {
+            vec![TypedKey::DUMMY_KEY()]
+        } else {
+            key.to_vec()
+        },
+        registry_tags: Default::default(),
+        owner: None,
+        team: None,
+        description: None,
+    }
+    .with_registry_tags(tags.into_iter().collect())
+}
+
+fn key_alias(key: &[TypedKey]) -> Vec<String> {
+    key.iter()
+        .map(|k| k.key_column_alias.clone().unwrap_or_else(|| k.key_column.clone()))
+        .collect()
+}
+
+fn sorted<T>(mut v: Vec<T>, name: impl Fn(&T) -> &str) -> Vec<T> {
+    v.sort_by(|a, b| name(a).cmp(name(b)));
+    v
+}
+
+impl ProjectFile {
+    pub(crate) fn parse(content: &str, format: ProjectFileFormat) -> Result<Self, Error> {
+        Ok(match format {
+            ProjectFileFormat::Json => serde_json::from_str(content)?,
+            ProjectFileFormat::Yaml => serde_yaml::from_str(content)?,
+        })
+    }
+
+    pub(crate) fn to_string(&self, format: ProjectFileFormat) -> Result<String, Error> {
+        // Going through `serde_json::Value` sorts map keys, so the output is stable between saves
+        let value = serde_json::to_value(self)?;
+        Ok(match format {
+            ProjectFileFormat::Json => serde_json::to_string_pretty(&value)?,
+            ProjectFileFormat::Yaml => serde_yaml::to_string(&value)?,
+        })
+    }
+}
+
+impl From<&FeathrProjectImpl> for ProjectFile {
+    fn from(p: &FeathrProjectImpl) -> Self {
+        let sources = p
+            .sources
+            .values()
+            .filter(|s| !s.is_input_context())
+            .map(|s| SourceFile {
+                name: s.name.clone(),
+                source: s.as_ref().to_owned(),
+                schema: s
+                    .schema
+                    .iter()
+                    .map(|(name, value_type)| ColumnFile {
+                        name: name.clone(),
+                        value_type: *value_type,
+                    })
+                    .collect(),
+                preprocessing: s.preprocessing.clone(),
+                preprocessing_file: s.preprocessing_file.clone(),
+                tags: s.registry_tags.clone().into_iter().collect(),
+            })
+            .collect();
+        let anchor_groups = p
+            .anchor_groups
+            .values()
+            .map(|g| AnchorGroupFile {
+                name: g.name.clone(),
+                source: g.source.get_name(),
+                tags: g.registry_tags.clone().into_iter().collect(),
+                features: sorted(
+                    p.anchor_map
+                        .get(&g.name)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|name| p.anchor_features.get(name))
+                        .map(|f| AnchorFeatureFile {
+                            name: f.get_name(),
+                            feature_type: f.get_type(),
+                            key: file_key(f.get_key()),
+                            transformation: f.get_transformation(),
+                            tags: f.get_registry_tags().into_iter().collect(),
+                        })
+                        .collect(),
+                    |f| &f.name,
+                ),
+            })
+            .collect();
+        let derived_features = p
+            .derivations
+            .values()
+            .map(|f| DerivedFeatureFile {
+                name: f.get_name(),
+                feature_type: f.get_type(),
+                key: file_key(f.get_key()),
+                transformation: f.get_transformation(),
+                inputs: sorted(
+                    f.inputs
+                        .iter()
+                        .map(|(name, i)| DerivedInputFile {
+                            name: name.clone(),
+                            feature: i.feature.clone(),
+                            key: i.key.clone(),
+                            offset: i.offset,
+                        })
+                        .collect(),
+                    |i| &i.name,
+                ),
+                tags: f.get_registry_tags().into_iter().collect(),
+            })
+            .collect();
+        Self {
+            name: p.name.clone(),
+            tags: p.registry_tags.clone().into_iter().collect(),
+            sources: sorted(sources, |s| &s.name),
+            anchor_groups: sorted(anchor_groups, |g| &g.name),
+            derived_features: sorted(derived_features, |f| &f.name),
+        }
+    }
+}
+
+impl TryFrom<ProjectFile> for FeathrProjectImpl {
+    type Error = Error;
+
+    /**
+     * The returned project is detached and not validated, it's meant to be imported into a real project
+     */
+    fn try_from(file: ProjectFile) -> Result<Self, Self::Error> {
+        let mut sources: HashMap<String, Arc<SourceImpl>> = HashMap::new();
+        let input_context = Arc::new(SourceImpl::INPUT_CONTEXT());
+        sources.insert(input_context.name.clone(), input_context);
+        for s in file.sources {
+            let mut source = s.source;
+            source.id = Uuid::new_v4();
+            source.name = s.name;
+            source.schema = s
+                .schema
+                .into_iter()
+                .map(|c| (c.name, c.value_type))
+                .collect();
+            source.preprocessing = s.preprocessing;
+            source.preprocessing_file = s.preprocessing_file;
+            source.registry_tags = s.tags.into_iter().collect();
+            if sources.contains_key(&source.name) {
+                return Err(Error::DuplicateSource(source.name));
+            }
+            sources.insert(source.name.clone(), Arc::new(source));
+        }
+
+        let mut anchor_groups = HashMap::new();
+        let mut anchor_map: HashMap<String, Vec<String>> = HashMap::new();
+        let mut anchor_features = HashMap::new();
+        for g in file.anchor_groups {
+            let source = sources
+                .get(&g.source)
+                .cloned()
+                .ok_or_else(|| Error::SourceGroupNotFound(g.source.clone()))?;
+            let features = anchor_map.entry(g.name.clone()).or_default();
+            for f in g.features {
+                let feature = AnchorFeatureImpl {
+                    key_alias: key_alias(&f.key),
+                    base: feature_base(f.name, f.feature_type, &f.key, f.tags),
+                    transform: f.transformation,
+                };
+                features.push(feature.get_name());
+                anchor_features.insert(feature.get_name(), Arc::new(feature));
+            }
+            anchor_groups.insert(
+                g.name.clone(),
+                Arc::new(AnchorGroupImpl {
+                    id: Uuid::new_v4(),
+                    name: g.name,
+                    source: Source { inner: source },
+                    registry_tags: g.tags.into_iter().collect(),
+                }),
+            );
+        }
+
+        let derivations = file
+            .derived_features
+            .into_iter()
+            .map(|f| {
+                let inputs = f
+                    .inputs
+                    .into_iter()
+                    .map(|i| {
+                        let input = InputFeature {
+                            key: i.key,
+                            id: Uuid::nil(),
+                            is_anchor_feature: anchor_features.contains_key(&i.feature),
+                            feature: i.feature,
+                            offset: i.offset,
+                        };
+                        (i.name, input)
+                    })
+                    .collect();
+                let mut aliases = key_alias(&f.key);
+                if aliases.is_empty() {
+                    aliases.push(TypedKey::DUMMY_KEY().key_column);
+                }
+                let feature = DerivedFeatureImpl {
+                    key_alias: aliases,
+                    base: feature_base(f.name, f.feature_type, &f.key, f.tags),
+                    transform: f.transformation.into(),
+                    inputs,
+                };
+                (feature.get_name(), Arc::new(feature))
+            })
+            .collect();
+
+        Ok(FeathrProjectImpl {
+            id: Uuid::new_v4(),
+            owner: None,
+            name: file.name,
+            anchor_groups,
+            derivations,
+            anchor_features,
+            anchor_map,
+            sources,
+            registry_tags: file.tags.into_iter().collect(),
+            version: None,
+            force: false,
+            overwrite: false,
+            quote_identifiers: true,
+            implicit_transforms: Default::default(),
+        })
+    }
+}
@@ -3,7 +3,11 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{feature::FeatureBase, SourceImpl, Transformation};
+use crate::{
+    feature::{parse_inputs_tag, FeatureBase, INPUTS_TAG},
+    source::SETTINGS_TAG,
+    SourceImpl, Transformation,
+};
 
 use super::{EntityRef, FeatureTransformation, FeatureType, TypedKey};
 
@@ -134,6 +138,11 @@ impl TryInto<crate::project::FeathrProjectImpl> for (Uuid, ProjectAttributes) {
             anchor_map: Default::default(),
             sources: Default::default(),
             registry_tags: self.1.tags,
+            version: None,
+            force: false,
+            overwrite: false,
+            quote_identifiers: true,
+            implicit_transforms: Default::default(),
         })
     }
 }
@@ -144,6 +153,14 @@ pub struct SourceAttributes {
     pub qualified_name: String,
     pub name: String,
     pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub dbtable: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub query: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub auth: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preprocessing: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -152,6 +169,8 @@ pub struct SourceAttributes {
     pub timestamp_format: Option<String>,
     #[serde(rename = "type")]
     pub type_: String,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub options: HashMap<String, String>,
     pub tags: HashMap<String, String>,
 }
 
@@ -165,23 +184,64 @@ impl TryInto<crate::source::SourceImpl> for (Uuid, SourceAttributes) {
                 name: self.1.name,
                 location: crate::SourceLocation::InputContext,
                 time_window_parameters: None,
+                options: Default::default(),
+                schema: Default::default(),
+                time_partition_pattern: None,
+                postfix_path: None,
                 preprocessing: None,
+                preprocessing_file: None,
                 registry_tags: Default::default(),
             }
         } else {
-            SourceImpl {
+            let mut tags = self.1.tags;
+            let settings = tags.remove(SETTINGS_TAG);
+            // Sources registered without the `settings` tag only have the registry attributes
+            let location = match (self.1.type_.to_lowercase().as_str(), self.1.url) {
+                ("jdbc", Some(url)) => crate::SourceLocation::Jdbc {
+                    url,
+                    dbtable: self.1.dbtable,
+                    query: self.1.query,
+                    driver: None,
+                    connection_properties: Default::default(),
+                    auth: match self.1.auth.as_deref().map(|a| a.to_uppercase()).as_deref() {
+                        Some("USERPASS") => crate::JdbcAuth::Userpass {
+                            user: format!("${{{}_USER}}", self.1.name),
+                            password: format!("${{{}_PASSWORD}}", self.1.name),
+                        },
+                        Some("TOKEN") => crate::JdbcAuth::Token {
+                            token: format!("${{{}_TOKEN}}", self.1.name),
+                        },
+                        _ => crate::JdbcAuth::Anonymous,
+                    },
+                },
+                _ => crate::SourceLocation::Hdfs {
+                    path: self.1.path,
+                    format: None,
+                    csv_delimiter: None,
+                },
+            };
+            let mut source = SourceImpl {
                 id: self.0,
                 name: self.1.name,
-                location: crate::SourceLocation::Hdfs { path: self.1.path },
+                location,
                 time_window_parameters: self.1.event_timestamp_column.map(|c| {
                     crate::TimeWindowParameters {
                         timestamp_column: c,
                         timestamp_column_format: self.1.timestamp_format.unwrap_or_default(),
                     }
                 }),
+                options: self.1.options,
+                schema: Default::default(),
+                time_partition_pattern: None,
+                postfix_path: None,
                 preprocessing: self.1.preprocessing,
-                registry_tags: self.1.tags,
+                preprocessing_file: None,
+                registry_tags: tags,
+            };
+            if let Some(settings) = settings {
+                source.apply_settings_tag(&settings)?;
             }
+            source
         })
     }
 }
@@ -244,8 +304,12 @@ impl TryInto<crate::feature::AnchorFeatureImpl> for (Uuid, AnchorFeatureAttribut
                 feature_type: self.1.type_.into(),
                 key,
                 feature_alias: self.1.name,
-                registry_tags: self.1.tags,
-            },
+                registry_tags: Default::default(),
+                owner: None,
+                team: None,
+                description: None,
+            }
+            .with_registry_tags(self.1.tags),
             key_alias,
             transform: self.1.transformation.try_into()?,
         })
@@ -281,6 +345,12 @@ impl TryInto<crate::feature::DerivedFeatureImpl> for (Uuid, DerivedFeatureAttrib
             })
             .collect();
         let t: Transformation = self.1.transformation.try_into()?;
+        let mut tags = self.1.tags;
+        // Inputs are resolved from the lineage, only the aliases and offsets are kept here
+        let inputs = match tags.remove(INPUTS_TAG) {
+            Some(tag) => parse_inputs_tag(&tag)?,
+            None => Default::default(),
+        };
         Ok(crate::feature::DerivedFeatureImpl {
             base: FeatureBase {
                 id: self.0,
@@ -288,15 +358,34 @@ impl TryInto<crate::feature::DerivedFeatureImpl> for (Uuid, DerivedFeatureAttrib
                 feature_type: self.1.type_.into(),
                 key,
                 feature_alias: self.1.name,
-                registry_tags: self.1.tags,
-            },
+                registry_tags: Default::default(),
+                owner: None,
+                team: None,
+                description: None,
+            }
+            .with_registry_tags(tags),
             key_alias,
             transform: t.into(),
-            inputs: Default::default(),
+            inputs,
         })
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetAttributes {
+    pub qualified_name: String,
+    pub name: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub schema: Option<String>,
+    pub job_name: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub job_id: Option<String>,
+    pub features: Vec<EntityRef>,
+    pub tags: HashMap<String, String>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "typeName", content = "attributes")]
 pub enum EntityAttributes {
@@ -310,4 +399,6 @@ pub enum EntityAttributes {
     AnchorFeature(AnchorFeatureAttributes),
     #[serde(rename = "feathr_derived_feature_v1")]
     DerivedFeature(DerivedFeatureAttributes),
+    #[serde(rename = "feathr_dataset_v1")]
+    Dataset(DatasetAttributes),
 }
@@ -22,6 +22,8 @@ pub enum EntityType {
     AnchorFeature,
     #[serde(rename = "feathr_derived_feature_v1")]
     DerivedFeature,
+    #[serde(rename = "feathr_dataset_v1")]
+    Dataset,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -49,6 +51,7 @@ impl Entity {
             EntityAttributes::Anchor(_) => EntityType::Anchor,
             EntityAttributes::AnchorFeature(_) => EntityType::AnchorFeature,
             EntityAttributes::DerivedFeature(_) => EntityType::DerivedFeature,
+            EntityAttributes::Dataset(_) => EntityType::Dataset,
         }
     }
 
@@ -132,6 +135,27 @@ pub struct EntityRef {
     unique_attributes: HashMap<String, String>,
 }
 
+impl EntityRef {
+    pub fn guid(&self) -> Uuid {
+        self.guid
+    }
+}
+
+impl From<&Entity> for EntityRef {
+    fn from(e: &Entity) -> Self {
+        Self {
+            guid: e.guid,
+            type_name: serde_json::to_value(e.get_entity_type())
+                .ok()
+                .and_then(|v| v.as_str().map(ToString::to_string))
+                .unwrap_or_default(),
+            unique_attributes: [("qualifiedName".to_string(), e.qualified_name.clone())]
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EntityLineage {
@@ -140,3 +164,42 @@ pub struct EntityLineage {
     pub relations: Vec<Relationship>,
 }
 
+impl EntityLineage {
+    /**
+     * Version of the project content, changes when any entity in the project is added or modified
+     */
+    pub fn version(&self) -> String {
+        lineage_version(
+            self.guid_entity_map
+                .values()
+                .map(|e| e.last_modified_ts.as_str()),
+        )
+    }
+}
+
+pub(crate) fn lineage_version<'a, T>(timestamps: T) -> String
+where
+    T: Iterator<Item = &'a str>,
+{
+    let (count, latest) = timestamps.fold((0usize, ""), |(count, latest), ts| {
+        (count + 1, latest.max(ts))
+    });
+    format!("{}-{}", count, latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lineage_version;
+
+    #[test]
+    fn version() {
+        let v1 = lineage_version(["1657072352", "1657072360"].into_iter());
+        assert_eq!(v1, "2-1657072360");
+        // New entity with an older timestamp still changes the version
+        assert_ne!(
+            lineage_version(["1657072352", "1657072360", "1657072300"].into_iter()),
+            v1
+        );
+    }
+}
+
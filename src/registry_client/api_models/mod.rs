@@ -4,8 +4,9 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    feature::{AnchorFeatureImpl, DerivedFeatureImpl},
+    feature::{inputs_tag, AnchorFeatureImpl, DerivedFeatureImpl, INPUTS_TAG},
     project::AnchorGroupImpl,
+    source::SETTINGS_TAG,
     utils::{dur_to_string, str_to_dur},
     Error, SourceImpl,
 };
@@ -26,7 +27,7 @@ pub struct ProjectDef {
     pub tags: HashMap<String, String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceDef {
     pub name: String,
@@ -48,14 +49,17 @@ pub struct SourceDef {
     pub timestamp_format: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub preprocessing: Option<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub options: HashMap<String, String>,
     #[serde(default)]
     pub tags: HashMap<String, String>,
 }
 
 impl From<SourceImpl> for SourceDef {
     fn from(s: SourceImpl) -> Self {
+        let settings = s.settings_tag();
         let (t, path, url, dbtable, query, auth) = match s.location {
-            crate::SourceLocation::Hdfs { path } => {
+            crate::SourceLocation::Hdfs { path, .. } => {
                 ("hdfs".to_string(), Some(path), None, None, None, None)
             }
             crate::SourceLocation::InputContext => (
@@ -66,11 +70,21 @@ impl From<SourceImpl> for SourceDef {
                 None,
                 None,
             ),
+            crate::SourceLocation::Snowflake {
+                url,
+                dbtable,
+                query,
+                ..
+            } => ("snowflake".to_string(), None, Some(url), dbtable, query, None),
+            crate::SourceLocation::Generic { format, .. } => {
+                (format, None, None, None, None, None)
+            }
             crate::SourceLocation::Jdbc {
                 url,
                 dbtable,
                 query,
                 auth,
+                ..
             } => (
                 "jdbc".to_string(),
                 None,
@@ -83,8 +97,14 @@ impl From<SourceImpl> for SourceDef {
                     crate::JdbcAuth::Anonymous => None,
                 },
             ),
-            crate::SourceLocation::Kafka { .. } => todo!(),
+            crate::SourceLocation::Kafka { .. } => {
+                ("kafka".to_string(), None, None, None, None, None)
+            }
         };
+        let mut tags = s.registry_tags;
+        if let Some(settings) = settings {
+            tags.insert(SETTINGS_TAG.to_string(), settings);
+        }
         Self {
             name: s.name,
             source_type: t,
@@ -96,7 +116,8 @@ impl From<SourceImpl> for SourceDef {
             event_timestamp_column: s.time_window_parameters.clone().map(|t| t.timestamp_column),
             timestamp_format: s.time_window_parameters.map(|t| t.timestamp_column_format),
             preprocessing: s.preprocessing,
-            tags: s.registry_tags,
+            options: s.options,
+            tags,
         }
     }
 }
@@ -347,12 +368,13 @@ pub struct AnchorFeatureDef {
 
 impl From<AnchorFeatureImpl> for AnchorFeatureDef {
     fn from(f: AnchorFeatureImpl) -> Self {
+        let tags = f.base.registry_tags();
         Self {
             name: f.base.name,
             feature_type: f.base.feature_type.into(),
             transformation: f.transform.into(),
             key: f.base.key.into_iter().map(Into::into).collect(),
-            tags: f.base.registry_tags,
+            tags,
         }
     }
 }
@@ -372,6 +394,10 @@ pub struct DerivedFeatureDef {
 
 impl From<DerivedFeatureImpl> for DerivedFeatureDef {
     fn from(f: DerivedFeatureImpl) -> Self {
+        let mut tags = f.base.registry_tags();
+        if let Some(inputs) = inputs_tag(&f.inputs) {
+            tags.insert(INPUTS_TAG.to_string(), inputs);
+        }
         Self {
             name: f.base.name,
             feature_type: f.base.feature_type.into(),
@@ -389,11 +415,41 @@ impl From<DerivedFeatureImpl> for DerivedFeatureDef {
                 .filter(|(_, f)| !f.is_anchor_feature)
                 .map(|(_, f)| f.id)
                 .collect(),
-            tags: f.base.registry_tags,
+            tags,
         }
     }
 }
 
+/**
+ * Dataset produced by a job, consumes the features it contains
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetDef {
+    pub name: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub schema: Option<String>,
+    pub job_name: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub job_id: Option<String>,
+    pub features: Vec<Uuid>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+}
+
+/**
+ * Definition of an entity created in a project, the anchor feature carries the id of its anchor
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntityDef {
+    Source(SourceDef),
+    Anchor(AnchorDef),
+    AnchorFeature(Uuid, AnchorFeatureDef),
+    DerivedFeature(DerivedFeatureDef),
+    Dataset(DatasetDef),
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct CreationResponse {
     pub guid: Uuid,
@@ -0,0 +1,964 @@
+use std::collections::HashMap;
+
+use log::debug;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    feature::{AnchorFeatureImpl, DerivedFeatureImpl},
+    project::FeathrProjectImpl,
+    Error, Feature,
+};
+
+use super::{
+    api_models::{
+        AnchorDef, AnchorFeatureDef, DerivedFeatureDef, EdgeType, Entity, EntityAttributes,
+        EntityDef, EntityLineage, FeatureTransformation, FeatureType, ProjectDef, SourceDef,
+        TypedKey,
+    },
+    FeatureRegistry,
+};
+
+/**
+ * Entities created or updated in the registry by `FeathrClient::save_project_delta`, everything else was already registered unchanged
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSaveSummary {
+    pub project_created: bool,
+    pub sources: Vec<String>,
+    pub anchor_groups: Vec<String>,
+    pub anchor_features: Vec<String>,
+    pub derived_features: Vec<String>,
+    /**
+     * Names of the entities above that were registered with another definition and updated in place
+     */
+    pub updated: Vec<String>,
+    /**
+     * Number of entities skipped as they're already registered with the same definition
+     */
+    pub unchanged: usize,
+}
+
+impl ProjectSaveSummary {
+    /**
+     * Number of entities created or updated
+     */
+    pub fn created(&self) -> usize {
+        self.sources.len()
+            + self.anchor_groups.len()
+            + self.anchor_features.len()
+            + self.derived_features.len()
+    }
+}
+
+/**
+ * Field of a feature whose registered value differs from the local one, values are in JSON
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FeatureDifference {
+    pub field: String,
+    pub registered: String,
+    pub local: String,
+}
+
+/**
+ * Feature registered with a definition different from the local one
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FeatureConflict {
+    pub qualified_name: String,
+    pub differences: Vec<FeatureDifference>,
+}
+
+impl std::fmt::Display for FeatureConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<&str> = self.differences.iter().map(|d| d.field.as_str()).collect();
+        write!(f, "{} ({})", self.qualified_name, fields.join(", "))
+    }
+}
+
+/**
+ * Registered entities of the project, keyed by name
+ */
+struct Registered<'a> {
+    lineage: &'a EntityLineage,
+    entities: HashMap<(&'static str, &'a str), &'a Entity>,
+}
+
+impl<'a> Registered<'a> {
+    fn new(lineage: &'a EntityLineage) -> Self {
+        let entities = lineage
+            .guid_entity_map
+            .values()
+            .filter_map(|e| {
+                let kind = match &e.attributes {
+                    EntityAttributes::Source(_) => "source",
+                    EntityAttributes::Anchor(_) => "anchor",
+                    EntityAttributes::AnchorFeature(_) | EntityAttributes::DerivedFeature(_) => {
+                        "feature"
+                    }
+                    _ => return None,
+                };
+                Some(((kind, e.name.as_str()), e))
+            })
+            .collect();
+        Self { lineage, entities }
+    }
+
+    fn get(&self, kind: &'static str, name: &str) -> Option<&'a Entity> {
+        self.entities.get(&(kind, name)).copied()
+    }
+
+    fn belongs_to(&self, id: Uuid, anchor_id: Uuid) -> bool {
+        self.lineage
+            .relations
+            .iter()
+            .any(|r| r.edge_type == EdgeType::BelongsTo && r.from == id && r.to == anchor_id)
+    }
+}
+
+fn difference<T: Serialize + PartialEq>(field: &str, registered: &T, local: &T) -> Option<FeatureDifference> {
+    (registered != local).then(|| FeatureDifference {
+        field: field.to_string(),
+        registered: serde_json::to_string(registered).unwrap_or_default(),
+        local: serde_json::to_string(local).unwrap_or_default(),
+    })
+}
+
+/**
+ * Fields of a feature that must not change once it's registered
+ */
+pub(crate) struct FeatureDef {
+    kind: &'static str,
+    feature_type: FeatureType,
+    transformation: FeatureTransformation,
+    key: Vec<TypedKey>,
+}
+
+impl FeatureDef {
+    fn from_entity(e: &Entity) -> Option<Self> {
+        let (kind, feature_type, transformation, key) = match &e.attributes {
+            EntityAttributes::AnchorFeature(attr) => ("anchor", &attr.type_, &attr.transformation, &attr.key),
+            EntityAttributes::DerivedFeature(attr) => ("derived", &attr.type_, &attr.transformation, &attr.key),
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            feature_type: feature_type.to_owned(),
+            transformation: transformation.to_owned(),
+            key: key.to_owned(),
+        })
+    }
+}
+
+impl From<&AnchorFeatureImpl> for FeatureDef {
+    fn from(f: &AnchorFeatureImpl) -> Self {
+        let def: AnchorFeatureDef = f.to_owned().into();
+        Self {
+            kind: "anchor",
+            feature_type: def.feature_type,
+            transformation: def.transformation,
+            key: def.key,
+        }
+    }
+}
+
+impl From<&DerivedFeatureImpl> for FeatureDef {
+    fn from(f: &DerivedFeatureImpl) -> Self {
+        let def: DerivedFeatureDef = f.to_owned().into();
+        Self {
+            kind: "derived",
+            feature_type: def.feature_type,
+            transformation: def.transformation,
+            key: def.key,
+        }
+    }
+}
+
+/**
+ * Differences in the kind, type, transformation and keys between the registered feature and the local one,
+ * `None` if they're the same
+ */
+pub(crate) fn feature_conflict(
+    qualified_name: &str,
+    registered: &FeatureDef,
+    local: &FeatureDef,
+) -> Option<FeatureConflict> {
+    let differences: Vec<FeatureDifference> = [
+        difference("kind", &registered.kind, &local.kind),
+        difference("type", &registered.feature_type, &local.feature_type),
+        difference("transformation", &registered.transformation, &local.transformation),
+        difference("key", &registered.key, &local.key),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    (!differences.is_empty()).then(|| FeatureConflict {
+        qualified_name: qualified_name.to_string(),
+        differences,
+    })
+}
+
+/**
+ * Registered features with the same name as a local feature but a different definition
+ */
+fn find_conflicts(registered: &Registered, project: &FeathrProjectImpl) -> Vec<FeatureConflict> {
+    let anchors = project
+        .anchor_features
+        .values()
+        .map(|f| (f.get_name(), FeatureDef::from(f.as_ref())));
+    let derived = project
+        .derivations
+        .values()
+        .map(|f| (f.get_name(), FeatureDef::from(f.as_ref())));
+    let mut conflicts: Vec<FeatureConflict> = anchors
+        .chain(derived)
+        .filter_map(|(name, local)| {
+            let e = registered.get("feature", &name)?;
+            feature_conflict(&e.qualified_name, &FeatureDef::from_entity(e)?, &local)
+        })
+        .collect();
+    conflicts.sort_by(|a, b| a.qualified_name.cmp(&b.qualified_name));
+    conflicts
+}
+
+fn same_source(e: &Entity, def: &SourceDef) -> bool {
+    match &e.attributes {
+        EntityAttributes::Source(attr) => {
+            attr.type_ == def.source_type
+                && Some(&attr.path) == def.path.as_ref().or(def.url.as_ref())
+                && attr.url == def.url
+                && attr.dbtable == def.dbtable
+                && attr.query == def.query
+                && attr.auth == def.auth
+                && attr.preprocessing == def.preprocessing
+                && attr.event_timestamp_column == def.event_timestamp_column
+                && attr.timestamp_format == def.timestamp_format
+                && attr.options == def.options
+                && attr.tags == def.tags
+        }
+        _ => false,
+    }
+}
+
+fn same_anchor(e: &Entity, def: &AnchorDef) -> bool {
+    match &e.attributes {
+        EntityAttributes::Anchor(attr) => {
+            attr.source.as_ref().map(|s| s.guid().to_string()) == Some(def.source_id.clone())
+                && attr.tags == def.tags
+        }
+        _ => false,
+    }
+}
+
+fn same_anchor_feature(e: &Entity, def: &AnchorFeatureDef) -> bool {
+    match &e.attributes {
+        EntityAttributes::AnchorFeature(attr) => {
+            attr.type_ == def.feature_type
+                && attr.transformation == def.transformation
+                && attr.key == def.key
+                && attr.tags == def.tags
+        }
+        _ => false,
+    }
+}
+
+fn same_derived_feature(e: &Entity, def: &DerivedFeatureDef) -> bool {
+    let sorted = |ids: Vec<Uuid>| {
+        let mut ids = ids;
+        ids.sort();
+        ids
+    };
+    match &e.attributes {
+        EntityAttributes::DerivedFeature(attr) => {
+            attr.type_ == def.feature_type
+                && attr.transformation == def.transformation
+                && attr.key == def.key
+                && attr.tags == def.tags
+                && sorted(attr.input_anchor_features.iter().map(|r| r.guid()).collect())
+                    == sorted(def.input_anchor_features.clone())
+                && sorted(attr.input_derived_features.iter().map(|r| r.guid()).collect())
+                    == sorted(def.input_derived_features.clone())
+        }
+        _ => false,
+    }
+}
+
+/**
+ * Update the registered entity `existing` in place, or register a new one if there is none or the registry doesn't support updates.
+ * Returns the id of the entity and whether it was updated
+ */
+async fn upsert(
+    registry: &dyn FeatureRegistry,
+    project: &FeathrProjectImpl,
+    project_id: Uuid,
+    existing: Option<Uuid>,
+    definition: EntityDef,
+) -> Result<(Uuid, bool), Error> {
+    if let Some(id) = existing {
+        match registry
+            .update_entity_if_version(&project.name, project_id, None, id, definition.clone())
+            .await
+        {
+            Ok(_) => return Ok((id, true)),
+            Err(Error::UpdateNotSupported(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let id = match definition {
+        EntityDef::Source(d) => registry.new_source(project_id, d).await?,
+        EntityDef::Anchor(d) => registry.new_anchor(project_id, d).await?,
+        EntityDef::AnchorFeature(anchor_id, d) => {
+            registry.new_anchor_feature(project_id, anchor_id, d).await?
+        }
+        EntityDef::DerivedFeature(d) => registry.new_derived_feature(project_id, d).await?,
+        EntityDef::Dataset(d) => registry.new_dataset(project_id, d).await?,
+    };
+    Ok((id, false))
+}
+
+/**
+ * Register the entities of the project that are new or different from the registered version of the project,
+ * changed entities are updated in place if the registry supports it, otherwise registered again.
+ * Fails with `Error::FeatureConflict` without writing anything if a registered feature has a different kind, type,
+ * transformation or keys than the local one, unless `overwrite` is set on the project
+ */
+pub(crate) async fn save_project_delta(
+    registry: &dyn FeatureRegistry,
+    project: &FeathrProjectImpl,
+) -> Result<ProjectSaveSummary, Error> {
+    let mut summary = ProjectSaveSummary::default();
+    let exists = registry.list_projects().await?.contains(&project.name);
+    let lineage = if exists {
+        registry.load_project(&project.name).await?
+    } else {
+        registry
+            .new_project(ProjectDef {
+                name: project.name.clone(),
+                tags: project.registry_tags.clone(),
+            })
+            .await?;
+        summary.project_created = true;
+        registry.load_project(&project.name).await?
+    };
+    let project_id = lineage
+        .guid_entity_map
+        .values()
+        .find(|e| matches!(e.attributes, EntityAttributes::Project(_)))
+        .map(|e| e.guid)
+        .ok_or_else(|| Error::ProjectNotFound(project.name.clone()))?;
+    let registered = Registered::new(&lineage);
+    if !project.overwrite {
+        let conflicts = find_conflicts(&registered, project);
+        if !conflicts.is_empty() {
+            return Err(Error::FeatureConflict(conflicts));
+        }
+    }
+
+    let mut source_ids: HashMap<String, Uuid> = HashMap::new();
+    let mut sources: Vec<_> = project.sources.values().collect();
+    sources.sort_by(|a, b| a.name.cmp(&b.name));
+    for s in sources {
+        let def: SourceDef = s.as_ref().to_owned().into();
+        let existing = registered.get("source", &s.name);
+        let id = match existing.filter(|e| same_source(e, &def)) {
+            Some(e) => {
+                summary.unchanged += 1;
+                e.guid
+            }
+            None => {
+                summary.sources.push(s.name.clone());
+                let existing = existing.map(|e| e.guid);
+                let (id, updated) = upsert(registry, project, project_id, existing, EntityDef::Source(def)).await?;
+                if updated {
+                    summary.updated.push(s.name.clone());
+                }
+                id
+            }
+        };
+        source_ids.insert(s.name.clone(), id);
+    }
+
+    let mut feature_ids: HashMap<String, Uuid> = HashMap::new();
+    let mut groups: Vec<_> = project.anchor_groups.values().collect();
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    for g in groups {
+        let mut def: AnchorDef = g.as_ref().to_owned().into();
+        def.source_id = source_ids
+            .get(&g.source.inner.name)
+            .ok_or_else(|| Error::SourceGroupNotFound(g.source.inner.name.clone()))?
+            .to_string();
+        let existing = registered.get("anchor", &g.name);
+        let anchor_id = match existing.filter(|e| same_anchor(e, &def)) {
+            Some(e) => {
+                summary.unchanged += 1;
+                e.guid
+            }
+            None => {
+                summary.anchor_groups.push(g.name.clone());
+                let existing = existing.map(|e| e.guid);
+                let (id, updated) = upsert(registry, project, project_id, existing, EntityDef::Anchor(def)).await?;
+                if updated {
+                    summary.updated.push(g.name.clone());
+                }
+                id
+            }
+        };
+        let mut names: Vec<&String> = project.anchor_map.get(&g.name).into_iter().flatten().collect();
+        names.sort();
+        for name in names {
+            let f = project
+                .anchor_features
+                .get(name)
+                .ok_or_else(|| Error::FeatureNotFound(name.to_owned()))?;
+            let def: AnchorFeatureDef = f.as_ref().to_owned().into();
+            // Features moved to another anchor are registered again, updates don't change the anchor
+            let existing = registered.get("feature", name).filter(|e| {
+                matches!(e.attributes, EntityAttributes::AnchorFeature(_))
+                    && registered.belongs_to(e.guid, anchor_id)
+            });
+            let id = match existing.filter(|e| same_anchor_feature(e, &def)) {
+                Some(e) => {
+                    summary.unchanged += 1;
+                    e.guid
+                }
+                None => {
+                    summary.anchor_features.push(name.clone());
+                    let existing = existing.map(|e| e.guid);
+                    let definition = EntityDef::AnchorFeature(anchor_id, def);
+                    let (id, updated) = upsert(registry, project, project_id, existing, definition).await?;
+                    if updated {
+                        summary.updated.push(name.clone());
+                    }
+                    id
+                }
+            };
+            feature_ids.insert(name.clone(), id);
+        }
+    }
+
+    // Derived features may depend on other derived features, save them after all their inputs
+    let mut pending: Vec<&DerivedFeatureImpl> =
+        project.derivations.values().map(AsRef::as_ref).collect();
+    pending.sort_by_key(|f| f.get_name());
+    while !pending.is_empty() {
+        let (ready, rest): (Vec<_>, Vec<_>) = pending
+            .into_iter()
+            .partition(|f| f.inputs.values().all(|i| feature_ids.contains_key(&i.feature)));
+        if ready.is_empty() {
+            let missing = rest
+                .iter()
+                .flat_map(|f| f.inputs.values().map(|i| &i.feature))
+                .find(|i| !feature_ids.contains_key(*i))
+                .cloned()
+                .unwrap_or_default();
+            return Err(Error::FeatureNotFound(missing));
+        }
+        for f in ready {
+            let name = f.get_name();
+            let mut def: DerivedFeatureDef = f.to_owned().into();
+            let inputs = |anchor: bool| -> Vec<Uuid> {
+                f.inputs
+                    .values()
+                    .filter(|i| i.is_anchor_feature == anchor)
+                    .map(|i| feature_ids[&i.feature])
+                    .collect()
+            };
+            def.input_anchor_features = inputs(true);
+            def.input_derived_features = inputs(false);
+            let existing = registered
+                .get("feature", &name)
+                .filter(|e| matches!(e.attributes, EntityAttributes::DerivedFeature(_)));
+            let id = match existing.filter(|e| same_derived_feature(e, &def)) {
+                Some(e) => {
+                    summary.unchanged += 1;
+                    e.guid
+                }
+                None => {
+                    summary.derived_features.push(name.clone());
+                    let existing = existing.map(|e| e.guid);
+                    let definition = EntityDef::DerivedFeature(def);
+                    let (id, updated) = upsert(registry, project, project_id, existing, definition).await?;
+                    if updated {
+                        summary.updated.push(name.clone());
+                    }
+                    id
+                }
+            };
+            feature_ids.insert(name, id);
+        }
+        pending = rest;
+    }
+
+    debug!(
+        "Project {} saved, {} entities created or updated, {} unchanged",
+        project.name,
+        summary.created(),
+        summary.unchanged
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::{project::FeathrProjectImpl, registry_client::LocalRegistry, *};
+
+    use super::{save_project_delta, AnchorFeatureDef};
+
+    #[tokio::test]
+    async fn save_delta() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("s1", "wasbs://a@b.blob.core.windows.net/c.csv")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let f1 = g1
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        proj.derived_feature("d1", FeatureType::INT32)
+            .add_input(&f1)
+            .transform("f1 + 1")
+            .build()
+            .await
+            .unwrap();
+
+        let summary = save_project_delta(&registry, &*proj.inner.read().await).await.unwrap();
+        assert!(summary.project_created);
+        assert_eq!(summary.sources, vec!["PASSTHROUGH".to_string(), "s1".to_string()]);
+        assert_eq!(summary.anchor_groups, vec!["g1".to_string()]);
+        assert_eq!(summary.anchor_features, vec!["f1".to_string()]);
+        assert_eq!(summary.derived_features, vec!["d1".to_string()]);
+        assert_eq!(summary.unchanged, 0);
+
+        // Nothing is registered again
+        let summary = save_project_delta(&registry, &*proj.inner.read().await).await.unwrap();
+        assert!(!summary.project_created);
+        assert_eq!(summary.created(), 0);
+        assert_eq!(summary.unchanged, 5);
+
+        // Only the new feature is registered
+        g1.anchor("f2", FeatureType::INT32)
+            .unwrap()
+            .transform("y")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let summary = save_project_delta(&registry, &*proj.inner.read().await).await.unwrap();
+        assert_eq!(summary.anchor_features, vec!["f2".to_string()]);
+        assert_eq!(summary.created(), 1);
+        assert_eq!(summary.unchanged, 5);
+        let loaded: FeathrProjectImpl = registry.load_project("p1").await.unwrap().try_into().unwrap();
+        assert_eq!(loaded.anchor_features.len(), 2);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    /**
+     * Save the project into a new local registry, returns the source as defined and as loaded back
+     */
+    async fn reload_source(proj: &FeathrProject, name: &str) -> (SourceImpl, SourceImpl) {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+        let project = proj.inner.read().await;
+        save_project_delta(&registry, &project).await.unwrap();
+        let loaded: FeathrProjectImpl = registry.load_project(&project.name).await.unwrap().try_into().unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        (
+            project.sources[name].as_ref().to_owned(),
+            loaded.sources[name].as_ref().to_owned(),
+        )
+    }
+
+    #[tokio::test]
+    async fn snowflake_source_round_trip() {
+        let proj = FeathrProject::new_detached("p1").await;
+        proj.snowflake_source("s1", "account.snowflakecomputing.com")
+            .dbtable("t1")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let (source, loaded) = reload_source(&proj, "s1").await;
+        assert!(matches!(loaded.location, SourceLocation::Snowflake { .. }));
+        assert_eq!(loaded.location, source.location);
+        assert_eq!(loaded.time_window_parameters, source.time_window_parameters);
+        // The settings tag is moved back into the source
+        assert!(loaded.registry_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn hdfs_format_round_trip() {
+        let proj = FeathrProject::new_detached("p1").await;
+        proj.hdfs_source("s1", "abfss://a@b.dfs.core.windows.net/table")
+            .format(SourceFormat::Delta)
+            .build()
+            .await
+            .unwrap();
+        proj.hdfs_source("s2", "abfss://a@b.dfs.core.windows.net/data.txt")
+            .format(SourceFormat::Csv)
+            .csv_delimiter("|")
+            .build()
+            .await
+            .unwrap();
+        for name in ["s1", "s2"] {
+            let (source, loaded) = reload_source(&proj, name).await;
+            assert_eq!(loaded.location, source.location);
+            assert!(loaded.registry_tags.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn time_partitioned_source_round_trip() {
+        let proj = FeathrProject::new_detached("p1").await;
+        proj.hdfs_source("s1", "abfss://a@b.dfs.core.windows.net/events")
+            .time_partition_pattern("yyyy/MM/dd")
+            .postfix_path("data")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let (source, loaded) = reload_source(&proj, "s1").await;
+        assert_eq!(loaded.time_partition_pattern.as_deref(), Some("yyyy/MM/dd"));
+        assert_eq!(loaded.postfix_path.as_deref(), Some("data"));
+        assert_eq!(loaded.location, source.location);
+        assert!(loaded.registry_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn source_schema_round_trip() {
+        let proj = FeathrProject::new_detached("p1").await;
+        proj.hdfs_source("s1", "abfss://a@b.dfs.core.windows.net/trips.parquet")
+            .schema_column("trip_id", ValueType::INT64)
+            .schema_column("fare", ValueType::DOUBLE)
+            .build()
+            .await
+            .unwrap();
+        let (source, loaded) = reload_source(&proj, "s1").await;
+        assert_eq!(
+            loaded.schema,
+            vec![("trip_id".to_string(), ValueType::INT64), ("fare".to_string(), ValueType::DOUBLE)]
+        );
+        assert_eq!(loaded.schema, source.schema);
+        assert!(loaded.registry_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn preprocessing_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("feathr_pp_{}.py", Uuid::new_v4().as_simple()));
+        tokio::fs::write(&path, "def add_fare(df):\n    return df\n").await.unwrap();
+        let path = path.to_string_lossy().to_string();
+        let proj = FeathrProject::new_detached("p1").await;
+        proj.hdfs_source("s1", "abfss://a@b.dfs.core.windows.net/trips.parquet")
+            .preprocessing_file(&path)
+            .build()
+            .await
+            .unwrap();
+        let (_, loaded) = reload_source(&proj, "s1").await;
+        assert_eq!(loaded.preprocessing.as_deref(), Some("add_fare"));
+        assert_eq!(loaded.preprocessing_file, Some(path.clone()));
+        assert!(loaded.registry_tags.is_empty());
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn jdbc_source_round_trip() {
+        let proj = FeathrProject::new_detached("p1").await;
+        proj.jdbc_source("s1", "jdbc:postgresql://localhost:5432/db")
+            .dbtable("trips")
+            .auth(JdbcSourceAuth::Userpass)
+            .driver("org.postgresql.Driver")
+            .connection_property("sslmode", "require")
+            .option("fetchsize", "1000")
+            .build()
+            .await
+            .unwrap();
+        let (source, loaded) = reload_source(&proj, "s1").await;
+        match &loaded.location {
+            SourceLocation::Jdbc {
+                driver,
+                connection_properties,
+                ..
+            } => {
+                assert_eq!(driver.as_deref(), Some("org.postgresql.Driver"));
+                assert_eq!(connection_properties["sslmode"], "require");
+            }
+            _ => panic!("unexpected location {:?}", loaded.location),
+        }
+        assert_eq!(loaded.location, source.location);
+        assert_eq!(loaded.options, source.options);
+        assert!(loaded.registry_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn elastic_source_round_trip() {
+        let proj = FeathrProject::new_detached("p1").await;
+        proj.elastic_source("s1", &["es1:9200", "es2:9200"], "index1")
+            .query("?q=status:active")
+            .auth(ElasticSourceAuth::ApiKey)
+            .option("es.nodes.wan.only", "true")
+            .build()
+            .await
+            .unwrap();
+        let (source, loaded) = reload_source(&proj, "s1").await;
+        assert!(matches!(loaded.location, SourceLocation::Generic { .. }));
+        assert_eq!(loaded.location, source.location);
+        assert!(loaded.registry_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn generic_source_round_trip() {
+        let proj = FeathrProject::new_detached("p1").await;
+        proj.generic_source("s1", "cosmos.oltp")
+            .option("spark.cosmos.accountEndpoint", "https://db1.documents.azure.com:443/")
+            .option("spark.cosmos.accountKey", "${s1_KEY}")
+            .option("spark.cosmos.container", "trips")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let (source, loaded) = reload_source(&proj, "s1").await;
+        match &loaded.location {
+            SourceLocation::Generic { format, options, .. } => {
+                assert_eq!(format, "cosmos.oltp");
+                assert_eq!(options["spark__cosmos__container"], "trips");
+            }
+            _ => panic!("unexpected location {:?}", loaded.location),
+        }
+        assert_eq!(loaded.location, source.location);
+        assert_eq!(loaded.get_secret_keys(), vec!["s1_KEY".to_string()]);
+        assert!(loaded.registry_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn lagged_input_round_trip() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("s1", "a.csv")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let f1 = g1
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        proj.derived_feature("d1", FeatureType::INT32)
+            .add_input(&f1)
+            .add_lagged_input(&f1, "f1_7d_ago", chrono::Duration::days(7))
+            .transform("f1 - f1_7d_ago")
+            .build()
+            .await
+            .unwrap();
+        save_project_delta(&registry, &*proj.inner.read().await).await.unwrap();
+
+        let loaded: FeathrProjectImpl = registry.load_project("p1").await.unwrap().try_into().unwrap();
+        let d1 = &loaded.derivations["d1"];
+        assert_eq!(d1.inputs.len(), 2);
+        assert_eq!(d1.inputs["f1"].offset, None);
+        assert_eq!(d1.inputs["f1_7d_ago"].feature, "f1");
+        assert_eq!(d1.inputs["f1_7d_ago"].offset, Some(chrono::Duration::days(7)));
+        assert_eq!(d1.inputs["f1_7d_ago"].id, loaded.anchor_features["f1"].base.id);
+        assert_eq!(d1.inputs["f1_7d_ago"].key, loaded.anchor_features["f1"].base.key);
+        // The tag is moved back into the inputs
+        assert!(d1.base.registry_tags.is_empty());
+
+        // Reloaded project is unchanged in the registry
+        let summary = save_project_delta(&registry, &loaded).await.unwrap();
+        assert_eq!(summary.created(), 0);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn conflict() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+
+        let build = |transform: &'static str| {
+            let k1 = k1.clone();
+            async move {
+                let proj = FeathrProject::new_detached("p1").await;
+                let s = proj.hdfs_source("s1", "a.csv").build().await.unwrap();
+                proj.anchor_group("g1", s)
+                    .build()
+                    .await
+                    .unwrap()
+                    .anchor("f1", FeatureType::INT32)
+                    .unwrap()
+                    .transform(transform)
+                    .keys(&[&k1])
+                    .build()
+                    .await
+                    .unwrap();
+                proj
+            }
+        };
+        let proj = build("x").await;
+        save_project_delta(&registry, &*proj.inner.read().await).await.unwrap();
+
+        let proj = build("y").await;
+        match save_project_delta(&registry, &*proj.inner.read().await).await {
+            Err(Error::FeatureConflict(conflicts)) => {
+                assert_eq!(conflicts.len(), 1);
+                assert_eq!(conflicts[0].qualified_name, "p1__g1__f1");
+                let fields: Vec<&str> = conflicts[0].differences.iter().map(|d| d.field.as_str()).collect();
+                assert_eq!(fields, vec!["transformation"]);
+            }
+            r => panic!("Unexpected result {:?}", r),
+        }
+
+        // Forcing the version check doesn't skip the conflict check
+        proj.set_force(true).await;
+        assert!(matches!(
+            save_project_delta(&registry, &*proj.inner.read().await).await,
+            Err(Error::FeatureConflict(_))
+        ));
+
+        // Overwriting replaces the registered definition
+        proj.set_overwrite(true).await;
+        save_project_delta(&registry, &*proj.inner.read().await).await.unwrap();
+        let loaded: FeathrProjectImpl = registry.load_project("p1").await.unwrap().try_into().unwrap();
+        let transformation = |p: &FeathrProjectImpl| {
+            AnchorFeatureDef::from(p.anchor_features["f1"].as_ref().to_owned()).transformation
+        };
+        assert_eq!(transformation(&loaded), transformation(&*proj.inner.read().await));
+        assert_ne!(transformation(&loaded), transformation(&*build("x").await.inner.read().await));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn update_changed_entities() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+
+        let build = |path: &'static str, transform: &'static str| {
+            let k1 = k1.clone();
+            async move {
+                let proj = FeathrProject::new_detached("p1").await;
+                let s = proj.hdfs_source("s1", path).build().await.unwrap();
+                proj.anchor_group("g1", s)
+                    .build()
+                    .await
+                    .unwrap()
+                    .anchor("f1", FeatureType::INT32)
+                    .unwrap()
+                    .transform(transform)
+                    .keys(&[&k1])
+                    .build()
+                    .await
+                    .unwrap();
+                proj
+            }
+        };
+        let proj = build("a.csv", "x").await;
+        save_project_delta(&registry, &*proj.inner.read().await).await.unwrap();
+        let before: FeathrProjectImpl = registry.load_project("p1").await.unwrap().try_into().unwrap();
+
+        let proj = build("b.csv", "y").await;
+        proj.set_overwrite(true).await;
+        let summary = save_project_delta(&registry, &*proj.inner.read().await).await.unwrap();
+        assert_eq!(summary.sources, vec!["s1".to_string()]);
+        assert_eq!(summary.anchor_features, vec!["f1".to_string()]);
+        assert_eq!(summary.updated, vec!["s1".to_string(), "f1".to_string()]);
+
+        // Entities keep their ids and are not duplicated
+        let lineage = registry.load_project("p1").await.unwrap();
+        assert_eq!(lineage.guid_entity_map.values().filter(|e| e.name == "f1").count(), 1);
+        let loaded: FeathrProjectImpl = lineage.try_into().unwrap();
+        assert_eq!(loaded.sources["s1"].id, before.sources["s1"].id);
+        assert_eq!(
+            loaded.sources["s1"].location,
+            SourceLocation::Hdfs {
+                path: "b.csv".to_string(),
+                format: None,
+                csv_delimiter: None
+            }
+        );
+        assert_eq!(loaded.anchor_features["f1"].base.id, before.anchor_features["f1"].base.id);
+        let transformation = |p: &FeathrProjectImpl| {
+            AnchorFeatureDef::from(p.anchor_features["f1"].as_ref().to_owned()).transformation
+        };
+        assert_eq!(transformation(&loaded), transformation(&*proj.inner.read().await));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn feature_metadata() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj.hdfs_source("s1", "a.csv").build().await.unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let f1 = g1
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .owner("alice")
+            .team("t1")
+            .description("Trip count")
+            .add_tag("tier", "gold")
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(f1.get_owner().as_deref(), Some("alice"));
+        assert_eq!(f1.get_registry_tags()["team"], "t1");
+        assert_eq!(f1.get_registry_tags()["tier"], "gold");
+        proj.derived_feature("d1", FeatureType::INT32)
+            .add_input(&f1)
+            .transform("f1 + 1")
+            .keys(&[&k1])
+            .team("t2")
+            .build()
+            .await
+            .unwrap();
+        save_project_delta(&registry, &*proj.inner.read().await).await.unwrap();
+
+        let loaded: FeathrProjectImpl = registry.load_project("p1").await.unwrap().try_into().unwrap();
+        let f1 = &loaded.anchor_features["f1"];
+        assert_eq!(f1.base.owner.as_deref(), Some("alice"));
+        assert_eq!(f1.base.description.as_deref(), Some("Trip count"));
+        assert_eq!(f1.base.registry_tags.len(), 1);
+        assert_eq!(loaded.derivations["d1"].base.team.as_deref(), Some("t2"));
+
+        let found = registry
+            .search_features("", &[("team".to_string(), "t1".to_string())].into_iter().collect())
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].feature.name, "f1");
+        assert_eq!(found[0].feature.owner.as_deref(), Some("alice"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
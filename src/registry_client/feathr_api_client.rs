@@ -1,17 +1,47 @@
-use std::sync::Arc;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use async_trait::async_trait;
+use azure_core::auth::TokenCredential;
+use azure_identity::{
+    AutoRefreshingTokenCredential, ClientSecretCredential, DefaultAzureCredential,
+    TokenCredentialOptions,
+};
+use futures::future::try_join_all;
 use log::debug;
+use reqwest::{
+    header::{ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    Method, RequestBuilder, StatusCode,
+};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{Error, FeatureRegistry, VarSource};
+use crate::{Error, FeatureRegistry, RegistryFeature, VarSource};
 
-use super::api_models::{self, CreationResponse};
+use super::{
+    api_models::{self, CreationResponse},
+    deletable_feature, project_of,
+};
+
+const DEFAULT_AAD_RESOURCE: &str = "https://management.azure.com";
 
 #[derive(Clone, Debug)]
 pub struct FeathrApiClient {
     registry_endpoint: String,
     client: reqwest::Client,
+    credential: Option<Arc<AutoRefreshingTokenCredential>>,
+    resource: String,
+    cache_dir: Option<PathBuf>,
+}
+
+/**
+ * Project lineage cached on disk with the validators returned by the registry
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedLineage {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    lineage: api_models::EntityLineage,
 }
 
 impl FeathrApiClient {
@@ -19,21 +49,213 @@ impl FeathrApiClient {
         Self {
             registry_endpoint: registry_url.to_string(),
             client: Default::default(),
+            credential: None,
+            resource: DEFAULT_AAD_RESOURCE.to_string(),
+            cache_dir: None,
         }
     }
+
+    /**
+     * Cache loaded projects in `dir`, cached projects are revalidated with `If-None-Match` and `If-Modified-Since`
+     * so unchanged projects are not downloaded again. Entries are kept per URL and caller identity, so callers with
+     * different credentials sharing the directory never see each other's responses.
+     * Only responses with an `ETag` or `Last-Modified` header are cached, the cache has no effect if the registry sends neither
+     */
+    pub fn with_cache<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /**
+     * Send AAD bearer tokens issued for `resource` with every request, tokens are refreshed before they expire
+     */
+    pub fn with_credential(mut self, credential: Arc<dyn TokenCredential>, resource: &str) -> Self {
+        self.credential = Some(Arc::new(AutoRefreshingTokenCredential::new(credential)));
+        self.resource = resource.to_string();
+        self
+    }
+
     /**
-     * Create Api Client from a VarSource
+     * Create Api Client from a VarSource.
+     * `feature_registry.auth.type` selects the authentication, `none` by default, `default` uses `DefaultAzureCredential`,
+     * and `client_credentials` uses the service principal in `feature_registry.auth.tenant_id`, `client_id` and `client_secret`.
+     * Tokens are requested for `feature_registry.auth.resource`, defaults to the Azure management endpoint.
+     * Loaded projects are cached in `feature_registry.cache.path` if set
      */
     pub async fn from_var_source(
         var_source: Arc<dyn VarSource + Send + Sync>,
     ) -> Result<Self, crate::Error> {
-        Ok(Self {
-            registry_endpoint: var_source
+        let mut client = Self::new(
+            &var_source
                 .get_environment_variable(&["feature_registry", "endpoint"])
                 .await?,
-            client: Default::default(),
+        );
+        if let Ok(dir) = var_source
+            .get_environment_variable(&["feature_registry", "cache", "path"])
+            .await
+        {
+            client = client.with_cache(dir);
+        }
+        let auth_type = var_source
+            .get_environment_variable(&["feature_registry", "auth", "type"])
+            .await
+            .unwrap_or_else(|_| "none".to_string())
+            .to_lowercase();
+        let resource = var_source
+            .get_environment_variable(&["feature_registry", "auth", "resource"])
+            .await
+            .unwrap_or_else(|_| DEFAULT_AAD_RESOURCE.to_string());
+        let credential: Arc<dyn TokenCredential> = match auth_type.as_str() {
+            "none" => return Ok(client),
+            "default" => Arc::new(DefaultAzureCredential::default()),
+            "client_credentials" => {
+                let get = |name: &'static str| {
+                    let var_source = var_source.clone();
+                    async move {
+                        var_source
+                            .get_environment_variable(&["feature_registry", "auth", name])
+                            .await
+                    }
+                };
+                Arc::new(ClientSecretCredential::new(
+                    get("tenant_id").await?,
+                    get("client_id").await?,
+                    get("client_secret").await?,
+                    TokenCredentialOptions::default(),
+                ))
+            }
+            _ => {
+                return Err(Error::InvalidConfig(format!(
+                    "Invalid registry auth type '{}'",
+                    auth_type
+                )))
+            }
+        };
+        Ok(client.with_credential(credential, &resource))
+    }
+
+    async fn request(&self, method: Method, url: String) -> Result<RequestBuilder, Error> {
+        let builder = self.client.request(method, url);
+        Ok(match &self.credential {
+            Some(c) => builder.bearer_auth(c.get_token(&self.resource).await?.token.secret()),
+            None => builder,
         })
     }
+
+    /**
+     * Post the definition of the entity, with `If-Match` set to the expected project version if there is one
+     */
+    async fn post_entity<T: Serialize>(
+        &self,
+        url: String,
+        definition: &T,
+        version: Option<&str>,
+    ) -> Result<Uuid, Error> {
+        debug!("URL: {}, definition: {}", url, serde_json::to_string(definition)?);
+        let mut request = self.request(Method::POST, url).await?.json(definition);
+        if let Some(version) = version {
+            request = request.header(IF_MATCH, format!("\"{}\"", version));
+        }
+        let r: CreationResponse = request.send().await.map(check_conflict)??.json().await?;
+        debug!("Entity created, id: {}", r.guid);
+        Ok(r.guid)
+    }
+
+    async fn post_entity_def(
+        &self,
+        project_id: Uuid,
+        definition: api_models::EntityDef,
+        version: Option<&str>,
+    ) -> Result<Uuid, Error> {
+        let url = format!("{}/projects/{}", self.registry_endpoint, project_id);
+        match definition {
+            api_models::EntityDef::Source(d) => {
+                self.post_entity(format!("{}/datasources", url), &d, version).await
+            }
+            api_models::EntityDef::Anchor(d) => {
+                self.post_entity(format!("{}/anchors", url), &d, version).await
+            }
+            api_models::EntityDef::AnchorFeature(anchor_id, d) => {
+                let url = format!("{}/anchors/{}/features", url, anchor_id);
+                self.post_entity(url, &d, version).await
+            }
+            api_models::EntityDef::DerivedFeature(d) => {
+                self.post_entity(format!("{}/derivedfeatures", url), &d, version).await
+            }
+            api_models::EntityDef::Dataset(d) => self.new_dataset(project_id, d).await,
+        }
+    }
+
+    /**
+     * Identity of the caller as seen by the registry, the tenant and object id in the AAD token,
+     * or the token itself if it has no such claims. Only a hash of it ends up in the cache paths
+     */
+    async fn identity(&self) -> Result<String, Error> {
+        #[derive(Deserialize)]
+        struct Claims {
+            tid: Option<String>,
+            oid: Option<String>,
+            sub: Option<String>,
+        }
+        let credential = match &self.credential {
+            Some(c) => c,
+            None => return Ok("anonymous".to_string()),
+        };
+        let token = credential.get_token(&self.resource).await?.token.secret().to_owned();
+        let claims = token
+            .split('.')
+            .nth(1)
+            .and_then(|payload| base64::decode_config(payload, base64::URL_SAFE_NO_PAD).ok())
+            .and_then(|payload| serde_json::from_slice::<Claims>(&payload).ok());
+        Ok(match claims {
+            Some(Claims { tid, oid, sub }) if oid.is_some() || sub.is_some() => format!(
+                "{}/{}/{}",
+                self.resource,
+                tid.unwrap_or_default(),
+                oid.or(sub).unwrap_or_default()
+            ),
+            _ => format!("{}/{}", self.resource, token),
+        })
+    }
+
+    fn cache_path(&self, url: &str, identity: &str) -> Option<PathBuf> {
+        let key = format!("{}\n{}", identity, url);
+        self.cache_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", Uuid::new_v5(&Uuid::NAMESPACE_URL, key.as_bytes()))))
+    }
+
+    /**
+     * Missing or unreadable cache entries are treated as cache misses
+     */
+    async fn read_cache(&self, url: &str, identity: &str) -> Option<CachedLineage> {
+        let path = self.cache_path(url, identity).filter(|p| p.is_file())?;
+        crate::runtime::read_to_string(&path)
+            .await
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    async fn write_cache(&self, url: &str, identity: &str, cached: &CachedLineage) -> Result<(), Error> {
+        if let (Some(dir), Some(path)) = (self.cache_dir.clone(), self.cache_path(url, identity)) {
+            crate::runtime::spawn_blocking(move || std::fs::create_dir_all(dir)).await??;
+            crate::runtime::write_file(path, serde_json::to_string(cached)?.as_bytes()).await?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Map conflict responses to `Error::RegistryConflict`, other errors are returned as is
+ */
+fn check_conflict(resp: reqwest::Response) -> Result<reqwest::Response, Error> {
+    match resp.status() {
+        StatusCode::CONFLICT | StatusCode::PRECONDITION_FAILED => Err(Error::RegistryConflict(
+            resp.url().path().to_string(),
+            resp.status().to_string(),
+        )),
+        _ => Ok(resp.error_for_status()?),
+    }
 }
 
 #[allow(unused_variables)]
@@ -42,18 +264,57 @@ impl FeatureRegistry for FeathrApiClient {
     async fn load_project(&self, name: &str) -> Result<api_models::EntityLineage, Error> {
         let url = format!("{}/projects/{}/lineage", self.registry_endpoint, name);
         debug!("URL: {}", url);
-        Ok(self.client.get(url).send().await?.json().await?)
+        let identity = match self.cache_dir {
+            Some(_) => self.identity().await?,
+            None => Default::default(),
+        };
+        let cached = self.read_cache(&url, &identity).await;
+        let mut request = self.request(Method::GET, url.clone()).await?;
+        if let Some(c) = &cached {
+            if let Some(etag) = &c.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &c.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let resp = request.send().await?;
+        if let Some(c) = cached.filter(|_| resp.status() == StatusCode::NOT_MODIFIED) {
+            debug!("Project {} not modified, using cached lineage", name);
+            return Ok(c.lineage);
+        }
+        let header = |name| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(ToString::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let lineage: api_models::EntityLineage = resp.json().await?;
+        if self.cache_dir.is_some() && (etag.is_some() || last_modified.is_some()) {
+            let cached = CachedLineage {
+                etag,
+                last_modified,
+                lineage,
+            };
+            if let Err(e) = self.write_cache(&url, &identity, &cached).await {
+                debug!("Failed to cache project {}: {}", name, e);
+            }
+            return Ok(cached.lineage);
+        }
+        Ok(lineage)
     }
     async fn new_project(&self, definition: api_models::ProjectDef) -> Result<Uuid, Error> {
         let url = format!("{}/projects", self.registry_endpoint);
         debug!("ProjectDef: {}", serde_json::to_string(&definition).unwrap());
         let r: CreationResponse = self
-            .client
-            .post(url)
+            .request(Method::POST, url)
+            .await?
             .json(&definition)
             .send()
-            .await?
-            .error_for_status()?
+            .await
+            .map(check_conflict)??
             .json()
             .await?;
         debug!("Entity created, id: {}", r.guid);
@@ -65,19 +326,8 @@ impl FeatureRegistry for FeathrApiClient {
         project_id: Uuid,
         definition: api_models::SourceDef,
     ) -> Result<Uuid, Error> {
-        let url = format!("{}/projects/{}/datasources", self.registry_endpoint, project_id);
-        debug!("SourceDef: {}", serde_json::to_string(&definition).unwrap());
-        let r: CreationResponse = self
-            .client
-            .post(url)
-            .json(&definition)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
-        debug!("Entity created, id: {}", r.guid);
-        Ok(r.guid)
+        let definition = api_models::EntityDef::Source(definition);
+        self.post_entity_def(project_id, definition, None).await
     }
 
     async fn new_anchor(
@@ -85,19 +335,8 @@ impl FeatureRegistry for FeathrApiClient {
         project_id: Uuid,
         definition: api_models::AnchorDef,
     ) -> Result<Uuid, Error> {
-        let url = format!("{}/projects/{}/anchors", self.registry_endpoint, project_id);
-        debug!("AnchorDef: {}", serde_json::to_string(&definition).unwrap());
-        let r: CreationResponse = self
-            .client
-            .post(url)
-            .json(&definition)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
-        debug!("Entity created, id: {}", r.guid);
-        Ok(r.guid)
+        let definition = api_models::EntityDef::Anchor(definition);
+        self.post_entity_def(project_id, definition, None).await
     }
 
     async fn new_anchor_feature(
@@ -106,19 +345,8 @@ impl FeatureRegistry for FeathrApiClient {
         anchor_id: Uuid,
         definition: api_models::AnchorFeatureDef,
     ) -> Result<Uuid, Error> {
-        let url = format!("{}/projects/{}/anchors/{}/features", self.registry_endpoint, project_id, anchor_id);
-        debug!("AnchorFeatureDef: {}", serde_json::to_string(&definition).unwrap());
-        let r: CreationResponse = self
-            .client
-            .post(url)
-            .json(&definition)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
-        debug!("Entity created, id: {}", r.guid);
-        Ok(r.guid)
+        let definition = api_models::EntityDef::AnchorFeature(anchor_id, definition);
+        self.post_entity_def(project_id, definition, None).await
     }
 
     async fn new_derived_feature(
@@ -126,18 +354,243 @@ impl FeatureRegistry for FeathrApiClient {
         project_id: Uuid,
         definition: api_models::DerivedFeatureDef,
     ) -> Result<Uuid, Error> {
-        let url = format!("{}/projects/{}/derivedfeatures", self.registry_endpoint, project_id);
-        debug!("DerivedFeatureDef: {}", serde_json::to_string(&definition).unwrap());
-        let r: CreationResponse = self
-            .client
-            .post(url)
-            .json(&definition)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
-        debug!("Entity created, id: {}", r.guid);
-        Ok(r.guid)
+        let definition = api_models::EntityDef::DerivedFeature(definition);
+        self.post_entity_def(project_id, definition, None).await
+    }
+
+    async fn list_projects(&self) -> Result<Vec<String>, Error> {
+        let url = format!("{}/projects", self.registry_endpoint);
+        debug!("URL: {}", url);
+        Ok(self.request(Method::GET, url).await?.send().await?.error_for_status()?.json().await?)
+    }
+
+    async fn search_features(
+        &self,
+        query: &str,
+        tags: &HashMap<String, String>,
+    ) -> Result<Vec<RegistryFeature>, Error> {
+        let projects = self.list_projects().await?;
+        // Projects are searched concurrently, the registry service has no cross-project search
+        let found = try_join_all(projects.iter().map(|project| async move {
+            let url = format!("{}/projects/{}/features", self.registry_endpoint, project);
+            debug!("URL: {}, keyword: {}", url, query);
+            let entities: Vec<api_models::Entity> = self
+                .request(Method::GET, url)
+                .await?
+                .query(&[("keyword", query)])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok::<_, Error>(
+                entities
+                    .into_iter()
+                    .filter_map(|e| RegistryFeature::from_entity(project, e))
+                    .filter(|f| f.has_tags(tags))
+                    .collect::<Vec<_>>(),
+            )
+        }))
+        .await?;
+        Ok(found.into_iter().flatten().collect())
+    }
+
+    async fn delete_feature(&self, qualified_name: &str, force: bool) -> Result<(), Error> {
+        let lineage = self.load_project(project_of(qualified_name)).await?;
+        let id = deletable_feature(&lineage, qualified_name, force)?;
+        let url = format!("{}/entity/{}", self.registry_endpoint, id);
+        debug!("Deleting feature {}, URL: {}", qualified_name, url);
+        self.request(Method::DELETE, url).await?.send().await.map(check_conflict)??;
+        Ok(())
+    }
+
+    /**
+     * The version is sent in `If-Match` so the registry service checks it with the write,
+     * a mismatch is reported as `412 Precondition Failed` and returned as `Error::RegistryConflict`
+     */
+    async fn new_entity_if_version(
+        &self,
+        project: &str,
+        project_id: Uuid,
+        version: Option<&str>,
+        definition: api_models::EntityDef,
+    ) -> Result<(Uuid, String), Error> {
+        let id = self.post_entity_def(project_id, definition, version).await?;
+        Ok((id, self.get_project_version(project).await?))
+    }
+
+    async fn delete_project(&self, name: &str) -> Result<(), Error> {
+        let url = format!("{}/projects/{}", self.registry_endpoint, name);
+        debug!("Deleting project {}, URL: {}", name, url);
+        let resp = self.request(Method::DELETE, url).await?.send().await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(Error::ProjectNotFound(name.to_string()));
+        }
+        resp.error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn auth_from_var_source() {
+        let conf = "feature_registry:\n  endpoint: 'http://localhost/api/v1'\n";
+        let c = FeathrApiClient::from_var_source(crate::new_var_source(conf)).await.unwrap();
+        assert!(c.credential.is_none());
+
+        let conf = "feature_registry:\n  endpoint: 'http://localhost/api/v1'\n  auth:\n    type: client_credentials\n    tenant_id: t\n    client_id: c\n    client_secret: s\n    resource: 'api://feathr'\n";
+        let c = FeathrApiClient::from_var_source(crate::new_var_source(conf)).await.unwrap();
+        assert!(c.credential.is_some());
+        assert_eq!(c.resource, "api://feathr");
+
+        let conf = "feature_registry:\n  endpoint: 'http://localhost/api/v1'\n  auth:\n    type: client_credentials\n";
+        assert!(FeathrApiClient::from_var_source(crate::new_var_source(conf)).await.is_err());
+        let conf = "feature_registry:\n  endpoint: 'http://localhost/api/v1'\n  auth:\n    type: foo\n";
+        assert!(matches!(
+            FeathrApiClient::from_var_source(crate::new_var_source(conf)).await,
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn cached_load_project() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Minimal registry returning the lineage with an ETag, and 304 when the ETag matches
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/api/v1", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut not_modified = 0;
+            for _ in 0..2 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut buf = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8(request).unwrap().to_lowercase();
+                let response = if request.contains("if-none-match: \"v1\"") {
+                    not_modified += 1;
+                    "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n".to_string()
+                } else {
+                    let body = r#"{"guidEntityMap": {}, "relations": []}"#;
+                    format!(
+                        "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+            not_modified
+        });
+
+        let dir = std::env::temp_dir().join(format!("feathr_registry_cache_{}", Uuid::new_v4().as_simple()));
+        let c = FeathrApiClient::new(&endpoint).with_cache(&dir);
+        assert!(c.load_project("p1").await.unwrap().guid_entity_map.is_empty());
+        assert!(c.load_project("p1").await.unwrap().relations.is_empty());
+        assert_eq!(server.await.unwrap(), 1);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_per_identity() {
+        #[derive(Debug)]
+        struct StaticToken(String);
+
+        #[async_trait]
+        impl TokenCredential for StaticToken {
+            async fn get_token(
+                &self,
+                _resource: &str,
+            ) -> Result<azure_core::auth::TokenResponse, azure_core::error::Error> {
+                Ok(azure_core::auth::TokenResponse::new(
+                    oauth2::AccessToken::new(self.0.clone()),
+                    chrono::Utc::now() + chrono::Duration::hours(1),
+                ))
+            }
+        }
+        let jwt = |oid: &str, nonce: &str| {
+            let claims = format!(r#"{{"tid":"t1","oid":"{}","nonce":"{}"}}"#, oid, nonce);
+            format!("h.{}.s", base64::encode_config(claims, base64::URL_SAFE_NO_PAD))
+        };
+        let client = |token: String| {
+            FeathrApiClient::new("http://localhost/api/v1")
+                .with_cache("cache")
+                .with_credential(Arc::new(StaticToken(token)), "api://feathr")
+        };
+        let url = "http://localhost/api/v1/projects/p1/lineage";
+        let path = |c: FeathrApiClient| async move { c.cache_path(url, &c.identity().await.unwrap()) };
+
+        // Refreshed tokens of the same principal share the entry, other principals and anonymous callers don't
+        let u1 = path(client(jwt("u1", "a"))).await;
+        assert_eq!(u1, path(client(jwt("u1", "b"))).await);
+        assert_ne!(u1, path(client(jwt("u2", "a"))).await);
+        assert_ne!(u1, path(FeathrApiClient::new("http://localhost/api/v1").with_cache("cache")).await);
+        assert_ne!(path(client("opaque1".to_string())).await, path(client("opaque2".to_string())).await);
+    }
+
+    #[tokio::test]
+    async fn dataset_not_supported() {
+        let c = FeathrApiClient::new("http://localhost/api/v1");
+        let definition = api_models::DatasetDef {
+            name: "training_set".to_string(),
+            path: "abfss://a/b".to_string(),
+            schema: None,
+            job_name: "j1".to_string(),
+            job_id: None,
+            features: vec![],
+            tags: Default::default(),
+        };
+        assert!(matches!(
+            c.new_dataset(Uuid::new_v4(), definition).await,
+            Err(Error::DatasetNotSupported(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn conditional_write() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Registry rejecting the write as the project version does not match
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/api/v1", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let mut buf = [0u8; 1024];
+            while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8(request).unwrap().to_lowercase();
+            socket
+                .write_all(b"HTTP/1.1 412 Precondition Failed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            socket.shutdown().await.unwrap();
+            request
+        });
+
+        let c = FeathrApiClient::new(&endpoint);
+        let definition = api_models::EntityDef::Source(api_models::SourceDef {
+            name: "s1".to_string(),
+            source_type: "hdfs".to_string(),
+            path: Some("a.csv".to_string()),
+            ..Default::default()
+        });
+        let r = c
+            .new_entity_if_version("p1", Uuid::new_v4(), Some("2-1657072360"), definition)
+            .await;
+        assert!(matches!(r, Err(Error::RegistryConflict(_, _))));
+        let request = server.await.unwrap();
+        assert!(request.contains("/datasources"));
+        assert!(request.contains("if-match: \"2-1657072360\""));
     }
 }
@@ -0,0 +1,189 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::Error;
+
+use super::api_models::{EdgeType, Entity, EntityLineage, EntityType};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum LineageNodeType {
+    Source,
+    Anchor,
+    AnchorFeature,
+    DerivedFeature,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineageNode {
+    pub id: Uuid,
+    pub name: String,
+    pub qualified_name: String,
+    pub node_type: LineageNodeType,
+}
+
+impl LineageNode {
+    fn new(entity: &Entity) -> Option<Self> {
+        let node_type = match entity.get_entity_type() {
+            EntityType::Source => LineageNodeType::Source,
+            EntityType::Anchor => LineageNodeType::Anchor,
+            EntityType::AnchorFeature => LineageNodeType::AnchorFeature,
+            EntityType::DerivedFeature => LineageNodeType::DerivedFeature,
+            _ => return None,
+        };
+        Some(Self {
+            id: entity.guid,
+            name: entity.name.clone(),
+            qualified_name: entity.qualified_name.clone(),
+            node_type,
+        })
+    }
+}
+
+/**
+ * Data flows from `from` to `to`, e.g. from a source to an anchor, or from an input feature to a derived feature
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct LineageEdge {
+    pub from: Uuid,
+    pub to: Uuid,
+}
+
+/**
+ * Where a feature comes from and which derived features are built on it
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureLineage {
+    pub feature: LineageNode,
+    /**
+     * Sources, anchors and input features the feature is computed from, nearest first
+     */
+    pub upstream: Vec<LineageNode>,
+    /**
+     * Derived features depending on the feature, directly or indirectly, nearest first
+     */
+    pub downstream: Vec<LineageNode>,
+    pub edges: Vec<LineageEdge>,
+}
+
+impl FeatureLineage {
+    pub(crate) fn new(lineage: &EntityLineage, feature_name: &str) -> Result<Self, Error> {
+        let feature = lineage
+            .guid_entity_map
+            .values()
+            .filter(|e| e.name == feature_name)
+            .find_map(|e| {
+                LineageNode::new(e).filter(|n| {
+                    matches!(
+                        n.node_type,
+                        LineageNodeType::AnchorFeature | LineageNodeType::DerivedFeature
+                    )
+                })
+            })
+            .ok_or_else(|| Error::FeatureNotFound(feature_name.to_string()))?;
+
+        // Upstream nodes of an entity, anchor features belong to anchors, anchors and derived features consume their inputs
+        let inputs = |id: Uuid| -> Vec<LineageNode> {
+            let edge_type = match lineage.guid_entity_map[&id].get_entity_type() {
+                EntityType::AnchorFeature => EdgeType::BelongsTo,
+                EntityType::Anchor | EntityType::DerivedFeature => EdgeType::Consumes,
+                _ => return vec![],
+            };
+            lineage
+                .relations
+                .iter()
+                .filter(|r| r.from == id && r.edge_type == edge_type)
+                .filter_map(|r| lineage.guid_entity_map.get(&r.to))
+                .filter_map(LineageNode::new)
+                .collect()
+        };
+        // Derived features consuming the feature
+        let outputs = |id: Uuid| -> Vec<LineageNode> {
+            lineage
+                .relations
+                .iter()
+                .filter(|r| r.to == id && r.edge_type == EdgeType::Consumes)
+                .filter_map(|r| lineage.guid_entity_map.get(&r.from))
+                .filter_map(LineageNode::new)
+                .filter(|n| n.node_type == LineageNodeType::DerivedFeature)
+                .collect()
+        };
+
+        let mut edges = vec![];
+        let mut upstream = vec![];
+        let mut visited: HashSet<Uuid> = HashSet::from([feature.id]);
+        let mut queue = VecDeque::from([feature.id]);
+        while let Some(id) = queue.pop_front() {
+            for node in inputs(id) {
+                edges.push(LineageEdge { from: node.id, to: id });
+                if visited.insert(node.id) {
+                    queue.push_back(node.id);
+                    upstream.push(node);
+                }
+            }
+        }
+
+        let mut downstream = vec![];
+        let mut visited: HashSet<Uuid> = HashSet::from([feature.id]);
+        let mut queue = VecDeque::from([feature.id]);
+        while let Some(id) = queue.pop_front() {
+            for node in outputs(id) {
+                edges.push(LineageEdge { from: id, to: node.id });
+                if visited.insert(node.id) {
+                    queue.push_back(node.id);
+                    downstream.push(node);
+                }
+            }
+        }
+
+        Ok(Self {
+            feature,
+            upstream,
+            downstream,
+            edges,
+        })
+    }
+}
+
+/**
+ * Dependency structure of a whole project, data flows from sources to anchors, anchor features and derived features
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineageGraph {
+    pub name: String,
+    pub nodes: Vec<LineageNode>,
+    pub edges: Vec<LineageEdge>,
+}
+
+impl LineageGraph {
+    /**
+     * Render the graph in Graphviz DOT format, e.g. `dot -Tsvg lineage.dot -o lineage.svg`
+     */
+    pub fn to_dot(&self) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut ret = format!("digraph \"{}\" {{\n    rankdir=LR;\n", escape(&self.name));
+        for node in &self.nodes {
+            let shape = match node.node_type {
+                LineageNodeType::Source => "cylinder",
+                LineageNodeType::Anchor => "folder",
+                LineageNodeType::AnchorFeature => "box",
+                LineageNodeType::DerivedFeature => "box, style=rounded",
+            };
+            ret.push_str(&format!(
+                "    \"{}\" [label=\"{}\", shape={}];\n",
+                node.id,
+                escape(&node.name),
+                shape
+            ));
+        }
+        for edge in &self.edges {
+            ret.push_str(&format!("    \"{}\" -> \"{}\";\n", edge.from, edge.to));
+        }
+        ret.push_str("}\n");
+        ret
+    }
+}
@@ -0,0 +1,1284 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use log::debug;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{Error, FeatureRegistry, RegistryFeature, VarSource};
+
+use super::{deletable_feature, project_of, version_conflict};
+
+use super::api_models::{
+    self, AnchorAttributes, AnchorFeatureAttributes, DatasetAttributes, DerivedFeatureAttributes,
+    EdgeType, Entity, EntityAttributes, EntityLineage, EntityRef, ProjectAttributes,
+    Relationship, SourceAttributes,
+};
+
+/**
+ * File format of the projects stored by `LocalRegistry`
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LocalRegistryFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl LocalRegistryFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            LocalRegistryFormat::Json => "json",
+            LocalRegistryFormat::Yaml => "yaml",
+        }
+    }
+}
+
+/**
+ * Feature registry storing each project as a file in a local directory, for offline development and testing.
+ * The file contains the same lineage returned by the registry service, so it can be shared with `FeathrClient::import_registry`
+ */
+#[derive(Clone, Debug)]
+pub struct LocalRegistry {
+    root: PathBuf,
+    format: LocalRegistryFormat,
+    // Project id to project name, also serializes all writes of this registry and its clones.
+    // The lock is in-process only, multiple processes must not write to the same directory
+    projects: Arc<Mutex<HashMap<Uuid, String>>>,
+}
+
+impl LocalRegistry {
+    /**
+     * Registry stores projects under `root` as JSON files, the directory is created on first write
+     */
+    pub fn new<T: AsRef<Path>>(root: T) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            format: Default::default(),
+            projects: Default::default(),
+        }
+    }
+
+    /**
+     * Create the registry from `feature_registry.local.path` and optional `feature_registry.local.format`, `json` or `yaml`
+     */
+    pub async fn from_var_source(
+        var_source: Arc<dyn VarSource + Send + Sync>,
+    ) -> Result<Self, Error> {
+        let root = var_source
+            .get_environment_variable(&["feature_registry", "local", "path"])
+            .await?;
+        let format = match var_source
+            .get_environment_variable(&["feature_registry", "local", "format"])
+            .await
+            .map(|f| f.to_lowercase())
+        {
+            Ok(f) if f == "yaml" || f == "yml" => LocalRegistryFormat::Yaml,
+            Ok(f) if f == "json" => LocalRegistryFormat::Json,
+            Ok(f) => {
+                return Err(Error::InvalidConfig(format!(
+                    "Invalid local registry format '{}'",
+                    f
+                )))
+            }
+            Err(_) => Default::default(),
+        };
+        Ok(Self::new(root).format(format))
+    }
+
+    /**
+     * Set the file format of the projects
+     */
+    pub fn format(mut self, format: LocalRegistryFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /**
+     * Path of the file storing the project
+     */
+    pub fn project_path(&self, name: &str) -> PathBuf {
+        self.root.join(format!("{}.{}", name, self.format.extension()))
+    }
+
+    /**
+     * Same as `project_path`, but fails if the name could refer to a file outside of the root directory
+     */
+    fn checked_project_path(&self, name: &str) -> Result<PathBuf, Error> {
+        if name.is_empty()
+            || name.starts_with('.')
+            || name.contains(|c: char| c == '/' || c == '\\' || c == ':' || c.is_control())
+        {
+            return Err(Error::InvalidConfig(format!(
+                "Invalid project name '{}' for local registry",
+                name
+            )));
+        }
+        Ok(self.project_path(name))
+    }
+
+    async fn read_project(&self, name: &str) -> Result<Option<EntityLineage>, Error> {
+        let path = self.checked_project_path(name)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content = crate::runtime::read_to_string(&path).await?;
+        Ok(Some(match self.format {
+            LocalRegistryFormat::Json => serde_json::from_str(&content)?,
+            LocalRegistryFormat::Yaml => serde_yaml::from_str(&content)?,
+        }))
+    }
+
+    async fn write_project(&self, name: &str, lineage: &EntityLineage) -> Result<(), Error> {
+        let root = self.root.clone();
+        crate::runtime::spawn_blocking(move || std::fs::create_dir_all(root)).await??;
+        let content = match self.format {
+            LocalRegistryFormat::Json => serde_json::to_string_pretty(lineage)?,
+            LocalRegistryFormat::Yaml => serde_yaml::to_string(lineage)?,
+        };
+        let path = self.checked_project_path(name)?;
+        debug!("Writing project {} to {:?}", name, path);
+        // Write to a temporary file and rename it, readers never see a partially written project
+        let temp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        crate::runtime::write_file(&temp, content.as_bytes()).await?;
+        crate::runtime::spawn_blocking(move || {
+            std::fs::rename(&temp, path).inspect_err(|_| {
+                let _ = std::fs::remove_file(&temp);
+            })
+        })
+        .await??;
+        Ok(())
+    }
+
+    /**
+     * Names of the projects stored in the directory, ordered by name
+     */
+    async fn project_names(&self) -> Result<Vec<String>, Error> {
+        let root = self.root.clone();
+        let ext = self.format.extension();
+        let mut names: Vec<String> = crate::runtime::spawn_blocking(move || {
+            std::fs::read_dir(root).map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().map(|e| e == ext).unwrap_or_default())
+                    .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+                    .collect()
+            })
+        })
+        .await?
+        .unwrap_or_default();
+        names.sort();
+        Ok(names)
+    }
+
+    /**
+     * Name of the project with the id, scans the directory if the project is not created or loaded by this registry
+     */
+    async fn project_name(
+        &self,
+        projects: &mut HashMap<Uuid, String>,
+        project_id: Uuid,
+    ) -> Result<String, Error> {
+        if let Some(name) = projects.get(&project_id) {
+            return Ok(name.to_owned());
+        }
+        for name in self.project_names().await? {
+            if let Some(lineage) = self.read_project(&name).await? {
+                if let Some(id) = project_id_of(&lineage) {
+                    projects.insert(id, name.clone());
+                    if id == project_id {
+                        return Ok(name);
+                    }
+                }
+            }
+        }
+        Err(Error::ProjectNotFound(project_id.to_string()))
+    }
+
+    /**
+     * Load the project, apply `f` and write it back, `f` returns the id of the added entity.
+     * Fails with `Error::RegistryConflict` if `version` is set and the project is at another version,
+     * the check and the write are done under the same lock. Returns the id and the new version of the project
+     */
+    async fn update_project<F>(
+        &self,
+        project_id: Uuid,
+        version: Option<&str>,
+        f: F,
+    ) -> Result<(Uuid, String), Error>
+    where
+        F: FnOnce(&mut EntityLineage, &Entity) -> Result<Uuid, Error> + Send,
+    {
+        let mut projects = self.projects.lock().await;
+        let name = self.project_name(&mut projects, project_id).await?;
+        let mut lineage = self
+            .read_project(&name)
+            .await?
+            .ok_or_else(|| Error::ProjectNotFound(name.clone()))?;
+        let project = lineage
+            .guid_entity_map
+            .get(&project_id)
+            .cloned()
+            .ok_or_else(|| Error::ProjectNotFound(name.clone()))?;
+        if let Some(version) = version {
+            let current = lineage.version();
+            if current != version {
+                return Err(version_conflict(&name, version, &current));
+            }
+        }
+        let id = f(&mut lineage, &project)?;
+        self.write_project(&name, &lineage).await?;
+        debug!("Entity created, id: {}", id);
+        Ok((id, lineage.version()))
+    }
+
+    async fn add_source(
+        &self,
+        project_id: Uuid,
+        version: Option<&str>,
+        definition: api_models::SourceDef,
+    ) -> Result<(Uuid, String), Error> {
+        self.update_project(project_id, version, move |lineage, project| {
+            let qualified_name = format!("{}__{}", project.qualified_name, definition.name);
+            let entity = new_entity(
+                &definition.name,
+                qualified_name.clone(),
+                EntityAttributes::Source(SourceAttributes {
+                    qualified_name,
+                    name: definition.name.clone(),
+                    path: definition.path.or_else(|| definition.url.clone()).unwrap_or_default(),
+                    url: definition.url,
+                    dbtable: definition.dbtable,
+                    query: definition.query,
+                    auth: definition.auth,
+                    preprocessing: definition.preprocessing,
+                    event_timestamp_column: definition.event_timestamp_column,
+                    timestamp_format: definition.timestamp_format,
+                    type_: definition.source_type,
+                    options: definition.options,
+                    tags: definition.tags,
+                }),
+            );
+            Ok(add_entity(lineage, project, entity, |p, r| p.sources.push(r)))
+        })
+        .await
+    }
+
+    async fn add_anchor(
+        &self,
+        project_id: Uuid,
+        version: Option<&str>,
+        definition: api_models::AnchorDef,
+    ) -> Result<(Uuid, String), Error> {
+        self.update_project(project_id, version, move |lineage, project| {
+            let source = definition
+                .source_id
+                .parse()
+                .ok()
+                .and_then(|id| {
+                    get_entity(lineage, id, |a| matches!(a, EntityAttributes::Source(_)))
+                })
+                .ok_or_else(|| Error::SourceGroupNotFound(definition.source_id.clone()))?;
+            let source_id = source.guid;
+            let qualified_name = format!("{}__{}", project.qualified_name, definition.name);
+            let entity = new_entity(
+                &definition.name,
+                qualified_name.clone(),
+                EntityAttributes::Anchor(AnchorAttributes {
+                    qualified_name,
+                    name: definition.name.clone(),
+                    features: Default::default(),
+                    source: Some(source.into()),
+                    tags: definition.tags,
+                }),
+            );
+            let new_id = entity.guid;
+            let id = add_entity(lineage, project, entity, |p, r| p.anchors.push(r));
+            if id == new_id {
+                relate(lineage, id, EdgeType::Consumes, source_id);
+            }
+            Ok(id)
+        })
+        .await
+    }
+
+    async fn add_anchor_feature(
+        &self,
+        project_id: Uuid,
+        version: Option<&str>,
+        anchor_id: Uuid,
+        definition: api_models::AnchorFeatureDef,
+    ) -> Result<(Uuid, String), Error> {
+        self.update_project(project_id, version, move |lineage, project| {
+            let anchor = get_entity(lineage, anchor_id, |a| {
+                matches!(a, EntityAttributes::Anchor(_))
+            })
+            .ok_or_else(|| Error::AnchorGroupNotFound(anchor_id.to_string()))?;
+            let qualified_name = format!("{}__{}", anchor.qualified_name, definition.name);
+            let entity = new_entity(
+                &definition.name,
+                qualified_name.clone(),
+                EntityAttributes::AnchorFeature(AnchorFeatureAttributes {
+                    qualified_name,
+                    name: definition.name.clone(),
+                    type_: definition.feature_type,
+                    transformation: definition.transformation,
+                    key: definition.key,
+                    tags: definition.tags,
+                }),
+            );
+            let new_id = entity.guid;
+            let r = EntityRef::from(&entity);
+            let id = add_entity(lineage, project, entity, |p, r| p.anchor_features.push(r));
+            if id == new_id {
+                if let Some(a) = lineage.guid_entity_map.get_mut(&anchor_id) {
+                    if let EntityAttributes::Anchor(attr) = &mut a.attributes {
+                        attr.features.push(r);
+                    }
+                }
+                relate(lineage, id, EdgeType::BelongsTo, anchor_id);
+            }
+            Ok(id)
+        })
+        .await
+    }
+
+    async fn add_derived_feature(
+        &self,
+        project_id: Uuid,
+        version: Option<&str>,
+        definition: api_models::DerivedFeatureDef,
+    ) -> Result<(Uuid, String), Error> {
+        self.update_project(project_id, version, move |lineage, project| {
+            let refs = |ids: &[Uuid], matches: fn(&EntityAttributes) -> bool| {
+                ids.iter()
+                    .map(|&id| {
+                        get_entity(lineage, id, matches)
+                            .map(EntityRef::from)
+                            .ok_or_else(|| Error::FeatureNotFound(id.to_string()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            let input_anchor_features = refs(&definition.input_anchor_features, |a| {
+                matches!(a, EntityAttributes::AnchorFeature(_))
+            })?;
+            let input_derived_features = refs(&definition.input_derived_features, |a| {
+                matches!(a, EntityAttributes::DerivedFeature(_))
+            })?;
+            let qualified_name = format!("{}__{}", project.qualified_name, definition.name);
+            let entity = new_entity(
+                &definition.name,
+                qualified_name.clone(),
+                EntityAttributes::DerivedFeature(DerivedFeatureAttributes {
+                    qualified_name,
+                    name: definition.name.clone(),
+                    type_: definition.feature_type,
+                    transformation: definition.transformation,
+                    key: definition.key,
+                    input_anchor_features,
+                    input_derived_features,
+                    tags: definition.tags,
+                }),
+            );
+            let new_id = entity.guid;
+            let id = add_entity(lineage, project, entity, |p, r| p.derived_features.push(r));
+            if id == new_id {
+                for input in definition
+                    .input_anchor_features
+                    .iter()
+                    .chain(definition.input_derived_features.iter())
+                {
+                    relate(lineage, id, EdgeType::Consumes, *input);
+                }
+            }
+            Ok(id)
+        })
+        .await
+    }
+
+    async fn update_entity(
+        &self,
+        project_id: Uuid,
+        version: Option<&str>,
+        id: Uuid,
+        definition: api_models::EntityDef,
+    ) -> Result<String, Error> {
+        self.update_project(project_id, version, move |lineage, project| {
+            let now = Utc::now().timestamp().to_string();
+            let refs = |lineage: &EntityLineage, ids: &[Uuid], matches: fn(&EntityAttributes) -> bool| {
+                ids.iter()
+                    .map(|&id| {
+                        get_entity(lineage, id, matches)
+                            .map(EntityRef::from)
+                            .ok_or_else(|| Error::FeatureNotFound(id.to_string()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            let not_found: fn(String) -> Error = match &definition {
+                api_models::EntityDef::Source(_) => Error::SourceGroupNotFound,
+                api_models::EntityDef::Anchor(_) => Error::AnchorGroupNotFound,
+                _ => Error::FeatureNotFound,
+            };
+            // Upstream entities the updated entity consumes
+            let mut inputs = vec![];
+            let attributes = match definition {
+                api_models::EntityDef::Source(d) => EntityAttributes::Source(SourceAttributes {
+                    qualified_name: Default::default(),
+                    name: Default::default(),
+                    path: d.path.or_else(|| d.url.clone()).unwrap_or_default(),
+                    url: d.url,
+                    dbtable: d.dbtable,
+                    query: d.query,
+                    auth: d.auth,
+                    preprocessing: d.preprocessing,
+                    event_timestamp_column: d.event_timestamp_column,
+                    timestamp_format: d.timestamp_format,
+                    type_: d.source_type,
+                    options: d.options,
+                    tags: d.tags,
+                }),
+                api_models::EntityDef::Anchor(d) => {
+                    let source = d
+                        .source_id
+                        .parse()
+                        .ok()
+                        .and_then(|id| {
+                            get_entity(lineage, id, |a| matches!(a, EntityAttributes::Source(_)))
+                        })
+                        .ok_or_else(|| Error::SourceGroupNotFound(d.source_id.clone()))?;
+                    inputs.push(source.guid);
+                    let features = match lineage.guid_entity_map.get(&id).map(|e| &e.attributes) {
+                        Some(EntityAttributes::Anchor(attr)) => attr.features.clone(),
+                        _ => Default::default(),
+                    };
+                    EntityAttributes::Anchor(AnchorAttributes {
+                        qualified_name: Default::default(),
+                        name: Default::default(),
+                        features,
+                        source: Some(source.into()),
+                        tags: d.tags,
+                    })
+                }
+                api_models::EntityDef::AnchorFeature(_, d) => {
+                    EntityAttributes::AnchorFeature(AnchorFeatureAttributes {
+                        qualified_name: Default::default(),
+                        name: Default::default(),
+                        type_: d.feature_type,
+                        transformation: d.transformation,
+                        key: d.key,
+                        tags: d.tags,
+                    })
+                }
+                api_models::EntityDef::DerivedFeature(d) => {
+                    let input_anchor_features = refs(lineage, &d.input_anchor_features, |a| {
+                        matches!(a, EntityAttributes::AnchorFeature(_))
+                    })?;
+                    let input_derived_features = refs(lineage, &d.input_derived_features, |a| {
+                        matches!(a, EntityAttributes::DerivedFeature(_))
+                    })?;
+                    inputs.extend(d.input_anchor_features.iter().chain(d.input_derived_features.iter()));
+                    EntityAttributes::DerivedFeature(DerivedFeatureAttributes {
+                        qualified_name: Default::default(),
+                        name: Default::default(),
+                        type_: d.feature_type,
+                        transformation: d.transformation,
+                        key: d.key,
+                        input_anchor_features,
+                        input_derived_features,
+                        tags: d.tags,
+                    })
+                }
+                api_models::EntityDef::Dataset(_) => {
+                    return Err(Error::UpdateNotSupported("datasets".to_string()))
+                }
+            };
+            let entity = lineage
+                .guid_entity_map
+                .get_mut(&id)
+                .filter(|e| {
+                    std::mem::discriminant(&e.attributes) == std::mem::discriminant(&attributes)
+                })
+                .ok_or_else(|| not_found(id.to_string()))?;
+            let mut attributes = attributes;
+            match &mut attributes {
+                EntityAttributes::Source(attr) => {
+                    attr.qualified_name = entity.qualified_name.clone();
+                    attr.name = entity.name.clone();
+                }
+                EntityAttributes::Anchor(attr) => {
+                    attr.qualified_name = entity.qualified_name.clone();
+                    attr.name = entity.name.clone();
+                }
+                EntityAttributes::AnchorFeature(attr) => {
+                    attr.qualified_name = entity.qualified_name.clone();
+                    attr.name = entity.name.clone();
+                }
+                EntityAttributes::DerivedFeature(attr) => {
+                    attr.qualified_name = entity.qualified_name.clone();
+                    attr.name = entity.name.clone();
+                }
+                _ => {}
+            }
+            entity.attributes = attributes;
+            entity.last_modified_ts = now.clone();
+            if let Some(p) = lineage.guid_entity_map.get_mut(&project.guid) {
+                p.last_modified_ts = now;
+            }
+            // Only the edges to the upstream entities are replaced, downstream entities still consume this one
+            lineage.relations.retain(|r| {
+                !((r.from == id && r.edge_type == EdgeType::Consumes)
+                    || (r.to == id && r.edge_type == EdgeType::Produces))
+            });
+            for input in inputs {
+                relate(lineage, id, EdgeType::Consumes, input);
+            }
+            debug!("Entity updated, id: {}", id);
+            Ok(id)
+        })
+        .await
+        .map(|(_, version)| version)
+    }
+
+    async fn add_dataset(
+        &self,
+        project_id: Uuid,
+        version: Option<&str>,
+        definition: api_models::DatasetDef,
+    ) -> Result<(Uuid, String), Error> {
+        self.update_project(project_id, version, move |lineage, project| {
+            let features = definition
+                .features
+                .iter()
+                .map(|&id| {
+                    get_entity(lineage, id, |a| {
+                        matches!(
+                            a,
+                            EntityAttributes::AnchorFeature(_) | EntityAttributes::DerivedFeature(_)
+                        )
+                    })
+                    .map(EntityRef::from)
+                    .ok_or_else(|| Error::FeatureNotFound(id.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let qualified_name = format!("{}__{}", project.qualified_name, definition.name);
+            let entity = new_entity(
+                &definition.name,
+                qualified_name.clone(),
+                EntityAttributes::Dataset(DatasetAttributes {
+                    qualified_name,
+                    name: definition.name.clone(),
+                    path: definition.path,
+                    schema: definition.schema,
+                    job_name: definition.job_name,
+                    job_id: definition.job_id,
+                    features,
+                    tags: definition.tags,
+                }),
+            );
+            let new_id = entity.guid;
+            let id = add_entity(lineage, project, entity, |_, _| {});
+            if id == new_id {
+                for feature in definition.features.iter() {
+                    relate(lineage, id, EdgeType::Consumes, *feature);
+                }
+            }
+            Ok(id)
+        })
+        .await
+    }
+}
+
+fn project_id_of(lineage: &EntityLineage) -> Option<Uuid> {
+    lineage
+        .guid_entity_map
+        .values()
+        .find(|e| matches!(e.attributes, EntityAttributes::Project(_)))
+        .map(|e| e.guid)
+}
+
+fn new_entity(name: &str, qualified_name: String, attributes: EntityAttributes) -> Entity {
+    Entity {
+        guid: Uuid::new_v4(),
+        name: name.to_string(),
+        qualified_name,
+        last_modified_ts: Utc::now().timestamp().to_string(),
+        status: "Active".to_string(),
+        display_text: name.to_string(),
+        labels: Default::default(),
+        attributes,
+    }
+}
+
+fn relate(lineage: &mut EntityLineage, from: Uuid, edge_type: EdgeType, to: Uuid) {
+    let reverse = match edge_type {
+        EdgeType::BelongsTo => EdgeType::Contains,
+        EdgeType::Contains => EdgeType::BelongsTo,
+        EdgeType::Consumes => EdgeType::Produces,
+        EdgeType::Produces => EdgeType::Consumes,
+    };
+    lineage.relations.push(Relationship {
+        edge_type,
+        from,
+        to,
+    });
+    lineage.relations.push(Relationship {
+        edge_type: reverse,
+        from: to,
+        to: from,
+    });
+}
+
+/**
+ * Add the entity into the project, returns the id of the existing entity with the same qualified name if there is one
+ */
+fn add_entity<F>(lineage: &mut EntityLineage, project: &Entity, entity: Entity, f: F) -> Uuid
+where
+    F: FnOnce(&mut ProjectAttributes, EntityRef),
+{
+    if let Some(existing) = lineage
+        .guid_entity_map
+        .values()
+        .find(|e| e.qualified_name == entity.qualified_name)
+    {
+        return existing.guid;
+    }
+    let id = entity.guid;
+    let r = EntityRef::from(&entity);
+    if let Some(p) = lineage.guid_entity_map.get_mut(&project.guid) {
+        if let EntityAttributes::Project(attr) = &mut p.attributes {
+            f(attr, r);
+        }
+        p.last_modified_ts = entity.last_modified_ts.clone();
+    }
+    lineage.guid_entity_map.insert(id, entity);
+    // Only `Contains` edges between the project and its entities, `BelongsTo` is reserved for features in anchor groups
+    lineage.relations.push(Relationship {
+        edge_type: EdgeType::Contains,
+        from: project.guid,
+        to: id,
+    });
+    id
+}
+
+fn get_entity(
+    lineage: &EntityLineage,
+    id: Uuid,
+    matches: fn(&EntityAttributes) -> bool,
+) -> Option<&Entity> {
+    lineage
+        .guid_entity_map
+        .get(&id)
+        .filter(|e| matches(&e.attributes))
+}
+
+#[async_trait]
+impl FeatureRegistry for LocalRegistry {
+    async fn load_project(&self, name: &str) -> Result<api_models::EntityLineage, Error> {
+        let lineage = self
+            .read_project(name)
+            .await?
+            .ok_or_else(|| Error::ProjectNotFound(name.to_string()))?;
+        if let Some(id) = project_id_of(&lineage) {
+            self.projects.lock().await.insert(id, name.to_string());
+        }
+        Ok(lineage)
+    }
+
+    async fn new_project(&self, definition: api_models::ProjectDef) -> Result<Uuid, Error> {
+        let mut projects = self.projects.lock().await;
+        if let Some(lineage) = self.read_project(&definition.name).await? {
+            if let Some(id) = project_id_of(&lineage) {
+                projects.insert(id, definition.name.clone());
+                return Ok(id);
+            }
+        }
+        let project = new_entity(
+            &definition.name,
+            definition.name.clone(),
+            EntityAttributes::Project(ProjectAttributes {
+                qualified_name: definition.name.clone(),
+                name: definition.name.clone(),
+                anchors: Default::default(),
+                sources: Default::default(),
+                anchor_features: Default::default(),
+                derived_features: Default::default(),
+                tags: definition.tags,
+            }),
+        );
+        let id = project.guid;
+        let lineage = EntityLineage {
+            guid_entity_map: [(id, project)].into_iter().collect(),
+            relations: Default::default(),
+        };
+        self.write_project(&definition.name, &lineage).await?;
+        projects.insert(id, definition.name);
+        debug!("Entity created, id: {}", id);
+        Ok(id)
+    }
+
+    async fn new_source(
+        &self,
+        project_id: Uuid,
+        definition: api_models::SourceDef,
+    ) -> Result<Uuid, Error> {
+        Ok(self.add_source(project_id, None, definition).await?.0)
+    }
+
+    async fn new_anchor(
+        &self,
+        project_id: Uuid,
+        definition: api_models::AnchorDef,
+    ) -> Result<Uuid, Error> {
+        Ok(self.add_anchor(project_id, None, definition).await?.0)
+    }
+
+    async fn new_anchor_feature(
+        &self,
+        project_id: Uuid,
+        anchor_id: Uuid,
+        definition: api_models::AnchorFeatureDef,
+    ) -> Result<Uuid, Error> {
+        Ok(self.add_anchor_feature(project_id, None, anchor_id, definition).await?.0)
+    }
+
+    async fn new_derived_feature(
+        &self,
+        project_id: Uuid,
+        definition: api_models::DerivedFeatureDef,
+    ) -> Result<Uuid, Error> {
+        Ok(self.add_derived_feature(project_id, None, definition).await?.0)
+    }
+
+    async fn new_dataset(
+        &self,
+        project_id: Uuid,
+        definition: api_models::DatasetDef,
+    ) -> Result<Uuid, Error> {
+        Ok(self.add_dataset(project_id, None, definition).await?.0)
+    }
+
+    /**
+     * The version is checked while holding the lock serializing all writes of this registry
+     */
+    async fn new_entity_if_version(
+        &self,
+        _project: &str,
+        project_id: Uuid,
+        version: Option<&str>,
+        definition: api_models::EntityDef,
+    ) -> Result<(Uuid, String), Error> {
+        match definition {
+            api_models::EntityDef::Source(d) => self.add_source(project_id, version, d).await,
+            api_models::EntityDef::Anchor(d) => self.add_anchor(project_id, version, d).await,
+            api_models::EntityDef::AnchorFeature(anchor_id, d) => {
+                self.add_anchor_feature(project_id, version, anchor_id, d).await
+            }
+            api_models::EntityDef::DerivedFeature(d) => {
+                self.add_derived_feature(project_id, version, d).await
+            }
+            api_models::EntityDef::Dataset(d) => self.add_dataset(project_id, version, d).await,
+        }
+    }
+
+    /**
+     * The version is checked while holding the lock serializing all writes of this registry
+     */
+    async fn update_entity_if_version(
+        &self,
+        _project: &str,
+        project_id: Uuid,
+        version: Option<&str>,
+        id: Uuid,
+        definition: api_models::EntityDef,
+    ) -> Result<String, Error> {
+        self.update_entity(project_id, version, id, definition).await
+    }
+
+    async fn list_projects(&self) -> Result<Vec<String>, Error> {
+        self.project_names().await
+    }
+
+    async fn search_features(
+        &self,
+        query: &str,
+        tags: &HashMap<String, String>,
+    ) -> Result<Vec<RegistryFeature>, Error> {
+        let query = query.to_lowercase();
+        let mut ret = vec![];
+        for project in self.project_names().await? {
+            if let Some(lineage) = self.read_project(&project).await? {
+                ret.extend(
+                    lineage
+                        .guid_entity_map
+                        .into_values()
+                        .filter(|e| e.name.to_lowercase().contains(&query))
+                        .filter_map(|e| RegistryFeature::from_entity(&project, e))
+                        .filter(|f| f.has_tags(tags)),
+                );
+            }
+        }
+        ret.sort_by(|a, b| a.qualified_name.cmp(&b.qualified_name));
+        Ok(ret)
+    }
+
+    async fn delete_feature(&self, qualified_name: &str, force: bool) -> Result<(), Error> {
+        let _guard = self.projects.lock().await;
+        let name = project_of(qualified_name);
+        let mut lineage = self
+            .read_project(name)
+            .await?
+            .ok_or_else(|| Error::ProjectNotFound(name.to_string()))?;
+        let id = deletable_feature(&lineage, qualified_name, force)?;
+        lineage.guid_entity_map.remove(&id);
+        lineage.relations.retain(|r| r.from != id && r.to != id);
+        let now = Utc::now().timestamp().to_string();
+        for e in lineage.guid_entity_map.values_mut() {
+            match &mut e.attributes {
+                EntityAttributes::Project(attr) => {
+                    attr.anchor_features.retain(|r| r.guid() != id);
+                    attr.derived_features.retain(|r| r.guid() != id);
+                    e.last_modified_ts = now.clone();
+                }
+                EntityAttributes::Anchor(attr) => attr.features.retain(|r| r.guid() != id),
+                EntityAttributes::DerivedFeature(attr) => {
+                    attr.input_anchor_features.retain(|r| r.guid() != id);
+                    attr.input_derived_features.retain(|r| r.guid() != id);
+                }
+                EntityAttributes::Dataset(attr) => attr.features.retain(|r| r.guid() != id),
+                _ => {}
+            }
+        }
+        debug!("Entity deleted, id: {}", id);
+        self.write_project(name, &lineage).await
+    }
+
+    async fn delete_project(&self, name: &str) -> Result<(), Error> {
+        let mut projects = self.projects.lock().await;
+        let path = self.checked_project_path(name)?;
+        if !path.is_file() {
+            return Err(Error::ProjectNotFound(name.to_string()));
+        }
+        debug!("Deleting project {} at {:?}", name, path);
+        crate::runtime::spawn_blocking(move || std::fs::remove_file(path)).await??;
+        projects.retain(|_, n| n != name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::result::Result;
+
+    use crate::{project::FeathrProjectImpl, *};
+
+    use super::*;
+
+    async fn build_project(registry: &LocalRegistry) -> Uuid {
+        build_project_with(registry).await
+    }
+
+    async fn build_project_with(registry: &dyn FeatureRegistry) -> Uuid {
+        let proj = FeathrProject::new_detached("p1").await;
+        let s = proj
+            .hdfs_source("s1", "wasbs://a@b.blob.core.windows.net/c.csv")
+            .time_window("ts", "epoch")
+            .build()
+            .await
+            .unwrap();
+        let g1 = proj.anchor_group("g1", s).build().await.unwrap();
+        let k1 = TypedKey::new("c1", ValueType::INT32);
+        let f1 = g1
+            .anchor("f1", FeatureType::INT32)
+            .unwrap()
+            .transform("x")
+            .keys(&[&k1])
+            .build()
+            .await
+            .unwrap();
+        let d1 = proj
+            .derived_feature("d1", FeatureType::INT32)
+            .add_input(&f1)
+            .transform("f1 + 1")
+            .build()
+            .await
+            .unwrap();
+
+        let p = proj.inner.read().await;
+        let project_id = registry
+            .new_project(api_models::ProjectDef {
+                name: "p1".to_string(),
+                tags: Default::default(),
+            })
+            .await
+            .unwrap();
+        let source_id = registry
+            .new_source(project_id, p.sources["s1"].as_ref().to_owned().into())
+            .await
+            .unwrap();
+        let mut anchor: api_models::AnchorDef = p.anchor_groups["g1"].as_ref().to_owned().into();
+        anchor.source_id = source_id.to_string();
+        let anchor_id = registry.new_anchor(project_id, anchor).await.unwrap();
+        let f1_id = registry
+            .new_anchor_feature(
+                project_id,
+                anchor_id,
+                p.anchor_features[&f1.get_name()].as_ref().to_owned().into(),
+            )
+            .await
+            .unwrap();
+        let mut derived: api_models::DerivedFeatureDef =
+            p.derivations[&d1.get_name()].as_ref().to_owned().into();
+        derived.input_anchor_features = vec![f1_id];
+        registry.new_derived_feature(project_id, derived).await.unwrap();
+        project_id
+    }
+
+    async fn round_trip(format: LocalRegistryFormat) {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root).format(format);
+        let project_id = build_project(&registry).await;
+        assert!(registry.project_path("p1").is_file());
+
+        // Same project is not created twice
+        let id = registry
+            .new_project(api_models::ProjectDef {
+                name: "p1".to_string(),
+                tags: Default::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(id, project_id);
+
+        // A new registry on the same directory finds the project by id
+        let reopened = LocalRegistry::new(&root).format(format);
+        let version = reopened.get_project_version("p1").await.unwrap();
+        let lineage = reopened.load_project("p1").await.unwrap();
+        let project: FeathrProjectImpl = lineage.try_into().unwrap();
+        assert_eq!(project.id, project_id);
+        assert_eq!(project.anchor_map["g1"], vec!["f1".to_string()]);
+        assert_eq!(project.derivations["d1"].inputs.len(), 1);
+        assert_eq!(project.version, Some(version));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn local_registry_json() {
+        round_trip(LocalRegistryFormat::Json).await;
+    }
+
+    #[tokio::test]
+    async fn local_registry_yaml() {
+        round_trip(LocalRegistryFormat::Yaml).await;
+    }
+
+    #[tokio::test]
+    async fn jdbc_source_attributes() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+        let project_id = build_project(&registry).await;
+        // Registered without the `settings` tag, the location is restored from the attributes
+        registry
+            .new_source(
+                project_id,
+                api_models::SourceDef {
+                    name: "j1".to_string(),
+                    source_type: "jdbc".to_string(),
+                    url: Some("jdbc:sqlserver://host:1433;database=db".to_string()),
+                    dbtable: Some("table1".to_string()),
+                    auth: Some("USERPASS".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        let project: FeathrProjectImpl = registry.load_project("p1").await.unwrap().try_into().unwrap();
+        assert_eq!(
+            project.sources["j1"].location,
+            SourceLocation::Jdbc {
+                url: "jdbc:sqlserver://host:1433;database=db".to_string(),
+                dbtable: Some("table1".to_string()),
+                query: None,
+                driver: None,
+                connection_properties: Default::default(),
+                auth: JdbcAuth::Userpass {
+                    user: "${j1_USER}".to_string(),
+                    password: "${j1_PASSWORD}".to_string(),
+                },
+            }
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalid_project_name() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(root.join("registry"));
+        for name in ["", "..", "../p1", "a/b", "a\\b", ".hidden"] {
+            let result = registry
+                .new_project(api_models::ProjectDef {
+                    name: name.to_string(),
+                    tags: Default::default(),
+                })
+                .await;
+            assert!(matches!(result, Err(Error::InvalidConfig(_))), "{}", name);
+            assert!(matches!(
+                registry.delete_project(name).await,
+                Err(Error::InvalidConfig(_))
+            ));
+        }
+        assert!(!root.exists());
+    }
+
+    #[tokio::test]
+    async fn conditional_write() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+        let project_id = build_project(&registry).await;
+        let source = |name: &str| {
+            api_models::EntityDef::Source(api_models::SourceDef {
+                name: name.to_string(),
+                source_type: "hdfs".to_string(),
+                path: Some("wasbs://a/b.csv".to_string()),
+                ..Default::default()
+            })
+        };
+        let v1 = registry.get_project_version("p1").await.unwrap();
+        let (_, v2) = registry
+            .new_entity_if_version("p1", project_id, Some(&v1), source("s2"))
+            .await
+            .unwrap();
+        assert_ne!(v1, v2);
+        assert_eq!(registry.get_project_version("p1").await.unwrap(), v2);
+        // Writes based on the stale version are rejected
+        assert!(matches!(
+            registry
+                .new_entity_if_version("p1", project_id, Some(&v1), source("s3"))
+                .await,
+            Err(Error::RegistryConflict(_, _))
+        ));
+        assert!(registry
+            .new_entity_if_version("p1", project_id, None, source("s3"))
+            .await
+            .is_ok());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn search_features() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+        build_project(&registry).await;
+        assert_eq!(registry.list_projects().await.unwrap(), vec!["p1".to_string()]);
+
+        let all = registry.search_features("", &Default::default()).await.unwrap();
+        assert_eq!(all.len(), 2);
+        let found = registry.search_features("F1", &Default::default()).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].project, "p1");
+        assert_eq!(found[0].qualified_name, "p1__g1__f1");
+        assert_eq!(found[0].feature.group, Some("g1".to_string()));
+        let tags = [("team".to_string(), "a".to_string())].into_iter().collect();
+        assert!(registry.search_features("", &tags).await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    /**
+     * Registry overriding only the required methods and `list_projects`, to exercise the default ones
+     */
+    #[derive(Debug)]
+    struct MinimalRegistry(LocalRegistry);
+
+    #[async_trait]
+    impl FeatureRegistry for MinimalRegistry {
+        async fn load_project(&self, name: &str) -> Result<api_models::EntityLineage, Error> {
+            self.0.load_project(name).await
+        }
+        async fn new_project(&self, definition: api_models::ProjectDef) -> Result<Uuid, Error> {
+            self.0.new_project(definition).await
+        }
+        async fn new_source(&self, project_id: Uuid, definition: api_models::SourceDef) -> Result<Uuid, Error> {
+            self.0.new_source(project_id, definition).await
+        }
+        async fn new_anchor(&self, project_id: Uuid, definition: api_models::AnchorDef) -> Result<Uuid, Error> {
+            self.0.new_anchor(project_id, definition).await
+        }
+        async fn new_anchor_feature(&self, project_id: Uuid, anchor_id: Uuid, definition: api_models::AnchorFeatureDef) -> Result<Uuid, Error> {
+            self.0.new_anchor_feature(project_id, anchor_id, definition).await
+        }
+        async fn new_derived_feature(&self, project_id: Uuid, definition: api_models::DerivedFeatureDef) -> Result<Uuid, Error> {
+            self.0.new_derived_feature(project_id, definition).await
+        }
+        async fn list_projects(&self) -> Result<Vec<String>, Error> {
+            self.0.list_projects().await
+        }
+    }
+
+    #[tokio::test]
+    async fn default_methods() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = MinimalRegistry(LocalRegistry::new(&root));
+        build_project_with(&registry).await;
+
+        let all = registry.search_features("", &Default::default()).await.unwrap();
+        assert_eq!(all, registry.0.search_features("", &Default::default()).await.unwrap());
+        let found = registry.search_features("F1", &Default::default()).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].qualified_name, "p1__g1__f1");
+        assert!(matches!(
+            registry.delete_feature("p1__g1__f1", false).await,
+            Err(Error::DeleteNotSupported(_))
+        ));
+        assert!(matches!(
+            registry.delete_project("p1").await,
+            Err(Error::DeleteNotSupported(_))
+        ));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn feature_lineage() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+        build_project(&registry).await;
+
+        let lineage = registry.get_lineage("p1", "d1").await.unwrap();
+        assert_eq!(lineage.feature.node_type, LineageNodeType::DerivedFeature);
+        let upstream: Vec<(&str, LineageNodeType)> = lineage
+            .upstream
+            .iter()
+            .map(|n| (n.name.as_str(), n.node_type))
+            .collect();
+        assert_eq!(
+            upstream,
+            vec![
+                ("f1", LineageNodeType::AnchorFeature),
+                ("g1", LineageNodeType::Anchor),
+                ("s1", LineageNodeType::Source),
+            ]
+        );
+        assert!(lineage.downstream.is_empty());
+        assert_eq!(lineage.edges.len(), 3);
+
+        let lineage = registry.get_lineage("p1", "f1").await.unwrap();
+        assert_eq!(lineage.downstream.len(), 1);
+        assert_eq!(lineage.downstream[0].name, "d1");
+        assert!(lineage.edges.contains(&LineageEdge {
+            from: lineage.feature.id,
+            to: lineage.downstream[0].id
+        }));
+        assert!(matches!(
+            registry.get_lineage("p1", "s1").await,
+            Err(Error::FeatureNotFound(_))
+        ));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn delete() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+        build_project(&registry).await;
+
+        // d1 depends on f1
+        assert!(matches!(
+            registry.delete_feature("p1__g1__f1", false).await,
+            Err(Error::FeatureInUse(_, dependents)) if dependents == vec!["d1".to_string()]
+        ));
+        assert!(matches!(
+            registry.delete_feature("p1__g1__f2", false).await,
+            Err(Error::FeatureNotFound(_))
+        ));
+        registry.delete_feature("p1__d1", false).await.unwrap();
+        registry.delete_feature("p1__g1__f1", false).await.unwrap();
+        let project: FeathrProjectImpl = registry.load_project("p1").await.unwrap().try_into().unwrap();
+        assert!(project.anchor_features.is_empty());
+        assert!(project.derivations.is_empty());
+        assert_eq!(project.anchor_map["g1"], Vec::<String>::new());
+
+        registry.delete_project("p1").await.unwrap();
+        assert!(registry.list_projects().await.unwrap().is_empty());
+        assert!(matches!(
+            registry.delete_project("p1").await,
+            Err(Error::ProjectNotFound(_))
+        ));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn from_var_source() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let conf = format!(
+            "feature_registry:\n  type: local\n  local:\n    path: '{}'\n    format: yaml\n",
+            root.to_string_lossy()
+        );
+        let registry = crate::registry_client::from_var_source(crate::new_var_source(&conf))
+            .await
+            .unwrap()
+            .unwrap();
+        build_project_with(registry.as_ref()).await;
+        assert!(root.join("p1.yaml").is_file());
+
+        let conf = "feature_registry:\n  type: none\n";
+        assert!(crate::registry_client::from_var_source(crate::new_var_source(conf))
+            .await
+            .unwrap()
+            .is_none());
+        let conf = "feature_registry:\n  type: foo\n";
+        assert!(matches!(
+            crate::registry_client::from_var_source(crate::new_var_source(conf)).await,
+            Err(Error::UnsupportedRegistry(t)) if t == "foo"
+        ));
+        // The registry service is used without the type, but only if the endpoint is set
+        assert!(crate::registry_client::from_var_source(crate::new_var_source("feature_registry: {}\n"))
+            .await
+            .unwrap()
+            .is_none());
+        let conf = "feature_registry:\n  endpoint: 'http://localhost'\n  auth:\n    type: foo\n";
+        assert!(matches!(
+            crate::registry_client::from_var_source(crate::new_var_source(conf)).await,
+            Err(Error::InvalidConfig(_))
+        ));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn project_not_found() {
+        let root = std::env::temp_dir().join(format!("feathr_registry_{}", Uuid::new_v4().as_simple()));
+        let registry = LocalRegistry::new(&root);
+        assert!(matches!(
+            registry.load_project("p1").await,
+            Err(Error::ProjectNotFound(_))
+        ));
+        assert!(matches!(
+            registry
+                .new_source(Uuid::new_v4(), api_models::SourceDef {
+                    name: "s1".to_string(),
+                    source_type: "hdfs".to_string(),
+                    path: Some("a.csv".to_string()),
+                    url: None,
+                    dbtable: None,
+                    query: None,
+                    auth: None,
+                    event_timestamp_column: None,
+                    timestamp_format: None,
+                    preprocessing: None,
+                    options: Default::default(),
+                    tags: Default::default(),
+                })
+                .await,
+            Err(Error::ProjectNotFound(_))
+        ));
+    }
+}
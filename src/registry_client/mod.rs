@@ -1,20 +1,278 @@
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
 use async_trait::async_trait;
+use futures::future::try_join_all;
+use serde::Serialize;
 use uuid::Uuid;
 
-use crate::Error;
+use crate::{
+    feature::{AnchorFeatureImpl, DerivedFeatureImpl},
+    Error, FeatureDescriptor, VarSource,
+};
 
+mod delta;
 mod feathr_api_client;
+mod lineage;
+mod local_registry;
 pub mod api_models;
 
+pub(crate) use delta::{feature_conflict, save_project_delta, FeatureDef};
+pub use delta::{FeatureConflict, FeatureDifference, ProjectSaveSummary};
 pub use feathr_api_client::FeathrApiClient;
+pub use lineage::{FeatureLineage, LineageEdge, LineageGraph, LineageNode, LineageNodeType};
+pub use local_registry::{LocalRegistry, LocalRegistryFormat};
 
 // TODO:
+/**
+ * Backend of the feature registry, `FeathrClient::get_registry_client` returns the configured one as `Arc<dyn FeatureRegistry>`.
+ * Implementations must be `Debug` as the client holding them is. Both are breaking changes since 0.3.0
+ */
 #[async_trait]
-pub trait FeatureRegistry: Send + Sync {
+pub trait FeatureRegistry: Debug + Send + Sync {
     async fn load_project(&self, name: &str) -> Result<api_models::EntityLineage, Error>;
     async fn new_project(&self, definition: api_models::ProjectDef) -> Result<Uuid, Error>;
     async fn new_source(&self, project_id: Uuid, definition: api_models::SourceDef) -> Result<Uuid, Error>;
     async fn new_anchor(&self, project_id: Uuid, definition: api_models::AnchorDef) -> Result<Uuid, Error>;
     async fn new_anchor_feature(&self, project_id: Uuid, anchor_id: Uuid, definition: api_models::AnchorFeatureDef) -> Result<Uuid, Error>;
     async fn new_derived_feature(&self, project_id: Uuid, definition: api_models::DerivedFeatureDef) -> Result<Uuid, Error>;
-}
\ No newline at end of file
+
+    /**
+     * Register the dataset produced by a job, the registry service has no dataset entity so only `LocalRegistry` supports it
+     */
+    async fn new_dataset(&self, _project_id: Uuid, _definition: api_models::DatasetDef) -> Result<Uuid, Error> {
+        Err(Error::DatasetNotSupported("the feature registry".to_string()))
+    }
+
+    /**
+     * Names of all projects in the registry
+     */
+    async fn list_projects(&self) -> Result<Vec<String>, Error> {
+        Err(Error::ListProjectsNotSupported("the feature registry".to_string()))
+    }
+
+    /**
+     * Anchor and derived features in all projects matching the keyword and having all the `tags`.
+     * The default implementation loads all listed projects concurrently and matches the names case-insensitively
+     */
+    async fn search_features(&self, query: &str, tags: &HashMap<String, String>) -> Result<Vec<RegistryFeature>, Error> {
+        let query = query.to_lowercase();
+        let projects = self.list_projects().await?;
+        let lineages = try_join_all(projects.iter().map(|p| self.load_project(p))).await?;
+        let mut ret: Vec<RegistryFeature> = projects
+            .iter()
+            .zip(lineages)
+            .flat_map(|(project, lineage)| {
+                lineage
+                    .guid_entity_map
+                    .into_values()
+                    .filter(|e| e.name.to_lowercase().contains(&query))
+                    .filter_map(|e| RegistryFeature::from_entity(project, e))
+                    .filter(|f| f.has_tags(tags))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        ret.sort_by(|a, b| a.qualified_name.cmp(&b.qualified_name));
+        Ok(ret)
+    }
+
+    /**
+     * Upstream sources, anchors and input features, and downstream derived features of the feature in the project
+     */
+    async fn get_lineage(&self, project: &str, feature_name: &str) -> Result<FeatureLineage, Error> {
+        FeatureLineage::new(&self.load_project(project).await?, feature_name)
+    }
+
+    /**
+     * Delete the anchor or derived feature, fails with `Error::FeatureInUse` if other derived features depend on it unless `force` is set
+     */
+    async fn delete_feature(&self, _qualified_name: &str, _force: bool) -> Result<(), Error> {
+        Err(Error::DeleteNotSupported("the feature registry".to_string()))
+    }
+
+    /**
+     * Delete the project and all entities in it
+     */
+    async fn delete_project(&self, _name: &str) -> Result<(), Error> {
+        Err(Error::DeleteNotSupported("the feature registry".to_string()))
+    }
+
+    /**
+     * Concurrency token of the project, changes whenever the project is modified in the registry
+     */
+    async fn get_project_version(&self, name: &str) -> Result<String, Error> {
+        Ok(self.load_project(name).await?.version())
+    }
+
+    /**
+     * Create the entity only if the project is still at `version`, fails with `Error::RegistryConflict` otherwise,
+     * returns the id of the entity and the new version of the project. No check is done if `version` is `None`.
+     * The default implementation compares the versions right before the write, which is not atomic,
+     * registries supporting conditional writes should override it
+     */
+    async fn new_entity_if_version(
+        &self,
+        project: &str,
+        project_id: Uuid,
+        version: Option<&str>,
+        definition: api_models::EntityDef,
+    ) -> Result<(Uuid, String), Error> {
+        if let Some(version) = version {
+            let current = self.get_project_version(project).await?;
+            if current != version {
+                return Err(version_conflict(project, version, &current));
+            }
+        }
+        let id = match definition {
+            api_models::EntityDef::Source(d) => self.new_source(project_id, d).await?,
+            api_models::EntityDef::Anchor(d) => self.new_anchor(project_id, d).await?,
+            api_models::EntityDef::AnchorFeature(anchor_id, d) => {
+                self.new_anchor_feature(project_id, anchor_id, d).await?
+            }
+            api_models::EntityDef::DerivedFeature(d) => self.new_derived_feature(project_id, d).await?,
+            api_models::EntityDef::Dataset(d) => self.new_dataset(project_id, d).await?,
+        };
+        Ok((id, self.get_project_version(project).await?))
+    }
+
+    /**
+     * Replace the definition of the registered entity `id`, the entity keeps its id and name.
+     * Same as `new_entity_if_version`, fails with `Error::RegistryConflict` if `version` is set and the project is at another version,
+     * returns the new version of the project. The registry service has no update API so only `LocalRegistry` supports it
+     */
+    async fn update_entity_if_version(
+        &self,
+        _project: &str,
+        _project_id: Uuid,
+        _version: Option<&str>,
+        _id: Uuid,
+        _definition: api_models::EntityDef,
+    ) -> Result<String, Error> {
+        Err(Error::UpdateNotSupported("the feature registry".to_string()))
+    }
+}
+
+pub(crate) fn version_conflict(project: &str, expected: &str, found: &str) -> Error {
+    Error::RegistryConflict(
+        format!("Project {}", project),
+        format!("expected version {}, found {}", expected, found),
+    )
+}
+
+/**
+ * Create the registry client selected by `feature_registry.type`, one of `api`, `purview`, `local` and `none`.
+ * `purview` connects to the registry service at `feature_registry.endpoint` backed by Purview, same as `api`.
+ * If the type is not set, the registry service is used when the endpoint is configured, otherwise the client is detached
+ */
+pub(crate) async fn from_var_source(
+    var_source: Arc<dyn VarSource + Send + Sync>,
+) -> Result<Option<Arc<dyn FeatureRegistry>>, Error> {
+    let registry_type = match var_source
+        .get_environment_variable(&["feature_registry", "type"])
+        .await
+    {
+        Ok(t) => t.to_lowercase(),
+        Err(_) => {
+            // Misconfigured registry settings are reported instead of silently detaching the client
+            if var_source
+                .get_environment_variable(&["feature_registry", "endpoint"])
+                .await
+                .is_err()
+            {
+                return Ok(None);
+            }
+            "api".to_string()
+        }
+    };
+    Ok(match registry_type.as_str() {
+        "api" | "purview" => Some(Arc::new(FeathrApiClient::from_var_source(var_source).await?)),
+        "local" => Some(Arc::new(LocalRegistry::from_var_source(var_source).await?)),
+        "none" => None,
+        _ => return Err(Error::UnsupportedRegistry(registry_type)),
+    })
+}
+
+/**
+ * Feature found in the registry
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryFeature {
+    pub project: String,
+    pub qualified_name: String,
+    #[serde(flatten)]
+    pub feature: FeatureDescriptor,
+}
+
+impl RegistryFeature {
+    /**
+     * `None` if the entity is not a feature
+     */
+    pub(crate) fn from_entity(project: &str, entity: api_models::Entity) -> Option<Self> {
+        let qualified_name = entity.qualified_name.clone();
+        let feature = match entity.get_entity_type() {
+            api_models::EntityType::AnchorFeature => {
+                // Qualified name of anchor features is `project__anchor__feature`
+                let group: Vec<&str> = qualified_name.split("__").collect();
+                let group = if group.len() == 3 { group[1] } else { "" };
+                let f: AnchorFeatureImpl = entity.try_into().ok()?;
+                FeatureDescriptor::new(&f, Some(group))
+            }
+            api_models::EntityType::DerivedFeature => {
+                let f: DerivedFeatureImpl = entity.try_into().ok()?;
+                FeatureDescriptor::new(&f, None)
+            }
+            _ => return None,
+        };
+        Some(Self {
+            project: project.to_string(),
+            qualified_name,
+            feature,
+        })
+    }
+
+    pub(crate) fn has_tags(&self, tags: &HashMap<String, String>) -> bool {
+        tags.iter()
+            .all(|(k, v)| self.feature.registry_tags.get(k) == Some(v))
+    }
+}
+
+/**
+ * Project of the entity, the qualified name is `project__name` or `project__anchor__name`
+ */
+pub(crate) fn project_of(qualified_name: &str) -> &str {
+    qualified_name.split("__").next().unwrap_or_default()
+}
+
+/**
+ * Id of the feature to be deleted, checks no derived feature depends on it unless `force` is set
+ */
+pub(crate) fn deletable_feature(
+    lineage: &api_models::EntityLineage,
+    qualified_name: &str,
+    force: bool,
+) -> Result<Uuid, Error> {
+    let feature = lineage
+        .guid_entity_map
+        .values()
+        .find(|e| {
+            e.qualified_name == qualified_name
+                && matches!(
+                    e.get_entity_type(),
+                    api_models::EntityType::AnchorFeature | api_models::EntityType::DerivedFeature
+                )
+        })
+        .ok_or_else(|| Error::FeatureNotFound(qualified_name.to_string()))?;
+    let mut dependents: Vec<String> = lineage
+        .relations
+        .iter()
+        .filter(|r| r.to == feature.guid && r.edge_type == api_models::EdgeType::Consumes)
+        .filter_map(|r| lineage.guid_entity_map.get(&r.from))
+        .filter(|e| e.get_entity_type() == api_models::EntityType::DerivedFeature)
+        .map(|e| e.name.clone())
+        .collect();
+    if !dependents.is_empty() && !force {
+        dependents.sort();
+        return Err(Error::FeatureInUse(qualified_name.to_string(), dependents));
+    }
+    Ok(feature.guid)
+}
@@ -0,0 +1,140 @@
+use std::{
+    fmt::Debug,
+    future::Future,
+    path::Path,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::{
+    channel::oneshot,
+    future::{select, Either},
+};
+
+use crate::Error;
+
+/**
+ * Hooks for the sleeps, blocking calls and local file IO of the client, e.g. to read files through
+ * a virtual file system or to run blocking calls on a dedicated thread pool.
+ * The default one is backed by tokio. Install another one with `set_runtime` before creating any client.
+ *
+ * This doesn't make the client executor agnostic, it still must run within a tokio runtime:
+ * HTTP and Redis connections, locks and task locals used by the client depend on tokio.
+ */
+#[async_trait]
+pub trait Runtime: Debug + Send + Sync {
+    async fn sleep(&self, duration: Duration);
+
+    /**
+     * Run the blocking function on a thread where blocking is acceptable
+     */
+    async fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send + 'static>) -> Result<(), Error>;
+
+    async fn read_file(&self, path: &Path) -> Result<Vec<u8>, Error>;
+
+    async fn write_file(&self, path: &Path, content: &[u8]) -> Result<(), Error>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TokioRuntime;
+
+#[async_trait]
+impl Runtime for TokioRuntime {
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+
+    async fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send + 'static>) -> Result<(), Error> {
+        tokio::task::spawn_blocking(f)
+            .await
+            .map_err(|e| Error::RuntimeError(e.to_string()))
+    }
+
+    async fn read_file(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        Ok(tokio::fs::read(path).await?)
+    }
+
+    async fn write_file(&self, path: &Path, content: &[u8]) -> Result<(), Error> {
+        Ok(tokio::fs::write(path, content).await?)
+    }
+}
+
+static RUNTIME: OnceLock<Arc<dyn Runtime>> = OnceLock::new();
+
+/**
+ * Install the hooks used by the client, fails if they have already been set or used
+ */
+pub fn set_runtime(runtime: Arc<dyn Runtime>) -> Result<(), Error> {
+    RUNTIME
+        .set(runtime)
+        .map_err(|_| Error::RuntimeError("Runtime has already been initialized".to_string()))
+}
+
+pub(crate) fn runtime() -> &'static Arc<dyn Runtime> {
+    RUNTIME.get_or_init(|| Arc::new(TokioRuntime))
+}
+
+pub(crate) async fn sleep(duration: Duration) {
+    runtime().sleep(duration).await
+}
+
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    runtime()
+        .spawn_blocking(Box::new(move || {
+            let _ = tx.send(f());
+        }))
+        .await?;
+    rx.await.map_err(|e| Error::RuntimeError(e.to_string()))
+}
+
+/**
+ * Returns `Error::Timeout` if the future doesn't complete in time, or `Error::DurationError` if the duration is negative
+ */
+pub(crate) async fn timeout<F: Future>(duration: chrono::Duration, f: F) -> Result<F::Output, Error> {
+    let duration = crate::utils::to_std_duration(duration)?;
+    match select(Box::pin(f), Box::pin(sleep(duration))).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(Error::Timeout),
+    }
+}
+
+pub(crate) async fn read_file<T: AsRef<Path>>(path: T) -> Result<Vec<u8>, Error> {
+    runtime().read_file(path.as_ref()).await
+}
+
+pub(crate) async fn read_to_string<T: AsRef<Path>>(path: T) -> Result<String, Error> {
+    String::from_utf8(read_file(path).await?).map_err(|e| Error::RuntimeError(e.to_string()))
+}
+
+pub(crate) async fn write_file<T: AsRef<Path>>(path: T, content: &[u8]) -> Result<(), Error> {
+    runtime().write_file(path.as_ref(), content).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tokio_runtime() {
+        assert_eq!(spawn_blocking(|| 42).await.unwrap(), 42);
+        let path = std::env::temp_dir().join(format!("feathr_runtime_{}", uuid::Uuid::new_v4()));
+        write_file(&path, b"abc").await.unwrap();
+        assert_eq!(read_to_string(&path).await.unwrap(), "abc");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(timeout(chrono::Duration::seconds(1), async { 42 }).await.unwrap(), 42);
+        assert!(matches!(
+            timeout(chrono::Duration::milliseconds(10), sleep(Duration::from_secs(10))).await,
+            Err(Error::Timeout)
+        ));
+        assert!(matches!(
+            timeout(chrono::Duration::seconds(-1), async { 42 }).await,
+            Err(Error::DurationError(_))
+        ));
+    }
+}
@@ -1,22 +1,66 @@
-use std::{sync::Arc, collections::HashMap};
+use std::{sync::Arc, collections::HashMap, str::FromStr};
 
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::{
     project::{FeathrProjectImpl, FeathrProjectModifier},
-    Error,
+    Error, ValueType,
 };
 
-#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
-#[serde(untagged)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum JdbcAuth {
     Userpass { user: String, password: String },
     Token { token: String },
     Anonymous,
 }
 
+impl<'de> Deserialize<'de> for JdbcAuth {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // Anonymous auth is serialized as `anonymous: true`, which is flattened into the location
+        #[derive(Deserialize)]
+        struct Fields {
+            user: Option<String>,
+            password: Option<String>,
+            token: Option<String>,
+        }
+        let fields = Option::<Fields>::deserialize(deserializer)?.unwrap_or(Fields {
+            user: None,
+            password: None,
+            token: None,
+        });
+        match fields {
+            Fields {
+                user: Some(user),
+                password: Some(password),
+                token: None,
+            } => Ok(JdbcAuth::Userpass { user, password }),
+            Fields {
+                user: None,
+                password: None,
+                token: Some(token),
+            } => Ok(JdbcAuth::Token { token }),
+            Fields {
+                user: None,
+                password: None,
+                token: None,
+            } => Ok(JdbcAuth::Anonymous),
+            Fields { user, password, token } => Err(serde::de::Error::custom(format!(
+                "JDBC auth needs both user and password, or a token, got user: {}, password: {}, token: {}",
+                user.is_some(),
+                password.is_some(),
+                token.is_some()
+            ))),
+        }
+    }
+}
+
 impl Serialize for JdbcAuth {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -55,12 +99,91 @@ pub struct KafkaSchema {
     avro_json: String,
 }
 
+/**
+ * The format Spark uses to read the HDFS source, guessed from the path extension if not set
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceFormat {
+    Delta,
+    Parquet,
+    Csv,
+    Avro,
+}
+
+impl SourceFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceFormat::Delta => "delta",
+            SourceFormat::Parquet => "parquet",
+            SourceFormat::Csv => "csv",
+            SourceFormat::Avro => "avro",
+        }
+    }
+}
+
+impl FromStr for SourceFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "delta" => SourceFormat::Delta,
+            "parquet" => SourceFormat::Parquet,
+            "csv" => SourceFormat::Csv,
+            "avro" => SourceFormat::Avro,
+            _ => return Err(Error::InvalidConfig(format!("Unsupported source format '{}'", s))),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum SnowflakeType {
+    #[serde(rename = "snowflake")]
+    Snowflake,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum GenericType {
+    #[serde(rename = "generic")]
+    Generic,
+}
+
+pub(crate) const ELASTICSEARCH_FORMAT: &str = "org.elasticsearch.spark.sql";
+pub(crate) const EVENTHUBS_FORMAT: &str = "eventhubs";
+
+// Secret referenced as `${KEY}` in an option value
+static SECRET_REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$\{([^}]+)\}").unwrap());
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
 #[serde(rename_all = "camelCase")]
 pub(crate) enum SourceLocation {
     Hdfs {
         path: String,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        format: Option<SourceFormat>,
+        #[serde(rename = "csvDelimiter", skip_serializing_if = "Option::is_none", default)]
+        csv_delimiter: Option<String>,
+    },
+    // Must come before `Jdbc` as they share the same fields except `type`
+    Snowflake {
+        #[serde(rename = "type")]
+        type_: SnowflakeType,
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        dbtable: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        query: Option<String>,
+    },
+    /**
+     * Spark data source read with `format` and `options`, dots in option names are replaced with `__`
+     */
+    Generic {
+        #[serde(rename = "type")]
+        type_: GenericType,
+        format: String,
+        #[serde(flatten)]
+        options: HashMap<String, String>,
     },
     Jdbc {
         url: String,
@@ -68,6 +191,10 @@ pub(crate) enum SourceLocation {
         dbtable: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         query: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        driver: Option<String>,
+        #[serde(rename = "connectionProperties", skip_serializing_if = "HashMap::is_empty", default)]
+        connection_properties: HashMap<String, String>,
         #[serde(flatten)]
         auth: JdbcAuth,
     },
@@ -79,6 +206,61 @@ pub(crate) enum SourceLocation {
     InputContext,
 }
 
+impl SourceLocation {
+    pub(crate) fn generic(format: &str, options: HashMap<String, String>) -> Self {
+        SourceLocation::Generic {
+            type_: GenericType::Generic,
+            format: format.to_string(),
+            options: options
+                .into_iter()
+                .map(|(k, v)| (k.replace('.', "__"), v))
+                .collect(),
+        }
+    }
+}
+
+/**
+ * Tag keeping the settings of the source the registry has no attributes for
+ */
+pub(crate) const SETTINGS_TAG: &str = "settings";
+
+/**
+ * Value of the `settings` tag
+ */
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SourceSettings {
+    /**
+     * Location of non-HDFS sources, the registry only keeps the path of HDFS sources
+     */
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    location: Option<SourceLocation>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    format: Option<SourceFormat>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    csv_delimiter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    time_partition_pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    postfix_path: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    schema: Vec<(String, ValueType)>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    preprocessing_file: Option<String>,
+}
+
+impl SourceSettings {
+    fn is_empty(&self) -> bool {
+        self.location.is_none()
+            && self.format.is_none()
+            && self.csv_delimiter.is_none()
+            && self.time_partition_pattern.is_none()
+            && self.postfix_path.is_none()
+            && self.schema.is_empty()
+            && self.preprocessing_file.is_none()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct TimeWindowParameters {
@@ -96,8 +278,33 @@ pub(crate) struct SourceImpl {
     pub(crate) location: SourceLocation,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) time_window_parameters: Option<TimeWindowParameters>,
+    /**
+     * Spark reader options, e.g. `header` or `multiLine`
+     */
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub(crate) options: HashMap<String, String>,
+    /**
+     * Declared columns of the source, empty if not declared
+     */
+    #[serde(skip)]
+    pub(crate) schema: Vec<(String, ValueType)>,
+    /**
+     * Data is in time partitioned sub folders of the path, e.g. `yyyy/MM/dd`
+     */
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub(crate) time_partition_pattern: Option<String>,
+    /**
+     * Path after the time partition, e.g. `data` for `path/yyyy/MM/dd/data`
+     */
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub(crate) postfix_path: Option<String>,
     #[serde(skip)]
     pub(crate) preprocessing: Option<String>,
+    /**
+     * Local Python file defining the preprocessing function
+     */
+    #[serde(skip)]
+    pub(crate) preprocessing_file: Option<String>,
     #[serde(skip)]
     pub(crate) registry_tags: HashMap<String, String>,
 }
@@ -116,11 +323,67 @@ impl SourceImpl {
             name: "PASSTHROUGH".to_string(),
             location: SourceLocation::InputContext,
             time_window_parameters: None,
+            options: Default::default(),
+            schema: Default::default(),
+            time_partition_pattern: None,
+            postfix_path: None,
             preprocessing: None,
+            preprocessing_file: None,
             registry_tags: Default::default(),
         }
     }
 
+    /**
+     * Value of the `settings` tag, `None` if the registry attributes describe the source completely
+     */
+    pub(crate) fn settings_tag(&self) -> Option<String> {
+        let mut settings = SourceSettings::default();
+        match &self.location {
+            SourceLocation::Hdfs {
+                format,
+                csv_delimiter,
+                ..
+            } => {
+                settings.format = *format;
+                settings.csv_delimiter = csv_delimiter.to_owned();
+            }
+            SourceLocation::InputContext => {}
+            location => settings.location = Some(location.to_owned()),
+        }
+        settings.time_partition_pattern = self.time_partition_pattern.to_owned();
+        settings.postfix_path = self.postfix_path.to_owned();
+        settings.schema = self.schema.to_owned();
+        settings.preprocessing_file = self.preprocessing_file.to_owned();
+        if settings.is_empty() {
+            return None;
+        }
+        serde_json::to_string(&settings).ok()
+    }
+
+    /**
+     * Restore the settings kept in the `settings` tag
+     */
+    pub(crate) fn apply_settings_tag(&mut self, tag: &str) -> Result<(), Error> {
+        let settings: SourceSettings = serde_json::from_str(tag)?;
+        if let Some(location) = settings.location {
+            self.location = location;
+        }
+        if let SourceLocation::Hdfs {
+            format,
+            csv_delimiter,
+            ..
+        } = &mut self.location
+        {
+            *format = settings.format;
+            *csv_delimiter = settings.csv_delimiter;
+        }
+        self.time_partition_pattern = settings.time_partition_pattern;
+        self.postfix_path = settings.postfix_path;
+        self.schema = settings.schema;
+        self.preprocessing_file = settings.preprocessing_file;
+        Ok(())
+    }
+
     pub(crate) fn is_input_context(&self) -> bool {
         matches!(self.location, SourceLocation::InputContext)
     }
@@ -135,6 +398,16 @@ impl SourceImpl {
                 JdbcAuth::Token { .. } => vec![format!("{}_TOKEN", self.name)],
                 _ => vec![],
             },
+            // Secrets are referred as `${KEY}` in the options, possibly embedded in a connection string
+            SourceLocation::Generic { options, .. } => {
+                let mut keys: Vec<String> = options
+                    .values()
+                    .flat_map(|v| SECRET_REFERENCE.captures_iter(v).map(|c| c[1].to_string()))
+                    .collect();
+                keys.sort();
+                keys.dedup();
+                keys
+            }
             _ => vec![],
         }
     }
@@ -170,6 +443,13 @@ impl Source {
         self.inner.preprocessing.clone()
     }
 
+    /**
+     * Declared columns of the source, empty if not declared
+     */
+    pub fn get_schema(&self) -> Vec<(String, ValueType)> {
+        self.inner.schema.clone()
+    }
+
     #[allow(non_snake_case)]
     pub fn INPUT_CONTEXT() -> Self {
         Self {
@@ -178,12 +458,62 @@ impl Source {
     }
 }
 
+/**
+ * Check the preprocessing file is readable and defines the preprocessing function,
+ * the function name is taken from the file if it's the only function in it
+ */
+async fn resolve_preprocessing(
+    source: &str,
+    preprocessing: &Option<String>,
+    preprocessing_file: &Option<String>,
+) -> Result<(Option<String>, Option<String>), Error> {
+    let path = match preprocessing_file {
+        Some(path) => path,
+        None => return Ok((preprocessing.to_owned(), None)),
+    };
+    let code = crate::runtime::read_to_string(path).await?;
+    let functions: Vec<&str> = code
+        .lines()
+        .filter_map(|l| l.strip_prefix("def "))
+        .filter_map(|l| l.split('(').next())
+        .map(str::trim)
+        .collect();
+    let function = match preprocessing {
+        Some(f) if functions.contains(&f.as_str()) => f.to_owned(),
+        Some(f) => {
+            return Err(Error::InvalidConfig(format!(
+                "Preprocessing function {} of source {} is not defined in {}",
+                f, source, path
+            )))
+        }
+        None => match functions.as_slice() {
+            [f] => f.to_string(),
+            _ => {
+                return Err(Error::InvalidConfig(format!(
+                    "Source {} needs the preprocessing function name, {} defines {} functions",
+                    source,
+                    path,
+                    functions.len()
+                )))
+            }
+        },
+    };
+    Ok((Some(function), Some(path.to_owned())))
+}
+
 pub struct HdfsSourceBuilder {
     owner: Arc<RwLock<FeathrProjectImpl>>,
     name: String,
     path: String,
+    format: Option<SourceFormat>,
+    csv_delimiter: Option<String>,
     time_window_parameters: Option<TimeWindowParameters>,
+    options: HashMap<String, String>,
+    schema: Vec<(String, ValueType)>,
+    time_partition_pattern: Option<String>,
+    postfix_path: Option<String>,
     preprocessing: Option<String>,
+    preprocessing_file: Option<String>,
 }
 
 impl HdfsSourceBuilder {
@@ -192,11 +522,31 @@ impl HdfsSourceBuilder {
             owner,
             name: name.to_string(),
             path: path.to_string(),
+            format: None,
+            csv_delimiter: None,
             time_window_parameters: None,
+            options: Default::default(),
+            schema: Default::default(),
+            time_partition_pattern: None,
+            postfix_path: None,
             preprocessing: None,
+            preprocessing_file: None,
         }
     }
 
+    pub fn format(&mut self, format: SourceFormat) -> &mut Self {
+        self.format = Some(format);
+        self
+    }
+
+    /**
+     * Field delimiter of the CSV file, only valid with `SourceFormat::Csv`
+     */
+    pub fn csv_delimiter(&mut self, delimiter: &str) -> &mut Self {
+        self.csv_delimiter = Some(delimiter.to_string());
+        self
+    }
+
     pub fn time_window(
         &mut self,
         timestamp_column: &str,
@@ -209,20 +559,91 @@ impl HdfsSourceBuilder {
         self
     }
 
+    /**
+     * Set a Spark reader option of the source, e.g. `header` or `multiLine`
+     */
+    pub fn option(&mut self, key: &str, value: &str) -> &mut Self {
+        self.options.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /**
+     * Set multiple Spark reader options of the source
+     */
+    pub fn options(&mut self, options: HashMap<String, String>) -> &mut Self {
+        self.options.extend(options);
+        self
+    }
+
+    /**
+     * Data is in time partitioned sub folders of the path, e.g. `yyyy/MM/dd` or `yyyy/MM/dd/HH`,
+     * only the partitions needed by the job are read
+     */
+    pub fn time_partition_pattern(&mut self, pattern: &str) -> &mut Self {
+        self.time_partition_pattern = Some(pattern.to_string());
+        self
+    }
+
+    /**
+     * Path after the time partition, only valid with time partition pattern
+     */
+    pub fn postfix_path(&mut self, postfix_path: &str) -> &mut Self {
+        self.postfix_path = Some(postfix_path.to_string());
+        self
+    }
+
     pub fn preprocessing(&mut self, preprocessing: &str) -> &mut Self {
         self.preprocessing = Some(preprocessing.to_string());
         self
     }
 
+    /**
+     * Local Python file defining the preprocessing function, it's uploaded with the jobs using this source.
+     * The function can be omitted if it's the only function in the file
+     */
+    pub fn preprocessing_file(&mut self, path: &str) -> &mut Self {
+        self.preprocessing_file = Some(path.to_string());
+        self
+    }
+
+    /**
+     * Declare a column of the source, columns referenced by anchor transformations are checked against the declared ones
+     */
+    pub fn schema_column(&mut self, name: &str, value_type: ValueType) -> &mut Self {
+        self.schema.push((name.to_string(), value_type));
+        self
+    }
+
     pub async fn build(&self) -> Result<Source, Error> {
+        let (preprocessing, preprocessing_file) =
+            resolve_preprocessing(&self.name, &self.preprocessing, &self.preprocessing_file).await?;
+        if self.postfix_path.is_some() && self.time_partition_pattern.is_none() {
+            return Err(Error::InvalidConfig(format!(
+                "Source {} has postfix path set but no time partition pattern",
+                self.name
+            )));
+        }
+        if self.csv_delimiter.is_some() && self.format != Some(SourceFormat::Csv) {
+            return Err(Error::InvalidConfig(format!(
+                "Source {} has CSV delimiter set but its format is not CSV",
+                self.name
+            )));
+        }
         let imp = SourceImpl {
             id: Uuid::new_v4(),
             name: self.name.to_string(),
             location: SourceLocation::Hdfs {
                 path: self.path.clone(),
+                format: self.format,
+                csv_delimiter: self.csv_delimiter.clone(),
             },
             time_window_parameters: self.time_window_parameters.clone(),
-            preprocessing: self.preprocessing.clone(),
+            options: self.options.clone(),
+            schema: self.schema.clone(),
+            time_partition_pattern: self.time_partition_pattern.clone(),
+            postfix_path: self.postfix_path.clone(),
+            preprocessing,
+            preprocessing_file,
             registry_tags: Default::default(),
         };
         self.owner.insert_source(imp).await
@@ -235,10 +656,17 @@ pub struct JdbcSourceBuilder {
     dbtable: Option<String>,
     query: Option<String>,
     auth: Option<JdbcAuth>,
+    driver: Option<String>,
+    connection_properties: HashMap<String, String>,
     time_window_parameters: Option<TimeWindowParameters>,
+    options: HashMap<String, String>,
+    schema: Vec<(String, ValueType)>,
     preprocessing: Option<String>,
+    preprocessing_file: Option<String>,
 }
 
+const MSSQL_DRIVER: &str = "com.microsoft.sqlserver.jdbc.SQLServerDriver";
+
 #[derive(Clone, Copy, Debug)]
 pub enum JdbcSourceAuth {
     Anonymous,
@@ -255,8 +683,13 @@ impl JdbcSourceBuilder {
             dbtable: None,
             query: None,
             auth: None,
+            driver: None,
+            connection_properties: Default::default(),
             time_window_parameters: None,
+            options: Default::default(),
+            schema: Default::default(),
             preprocessing: None,
+            preprocessing_file: None,
         }
     }
 
@@ -270,6 +703,32 @@ impl JdbcSourceBuilder {
         self
     }
 
+    pub(crate) fn new_mssql(
+        owner: Arc<RwLock<FeathrProjectImpl>>,
+        name: &str,
+        server: &str,
+        database: &str,
+    ) -> Self {
+        let host = if server.contains('.') {
+            server.to_string()
+        } else {
+            format!("{}.database.windows.net", server)
+        };
+        // The wildcard certificate only covers the public cloud, other hosts are validated against their own name
+        let cert_host = if host.ends_with(".database.windows.net") {
+            "hostNameInCertificate=*.database.windows.net;"
+        } else {
+            ""
+        };
+        let url = format!(
+            "jdbc:sqlserver://{}:1433;database={};encrypt=true;trustServerCertificate=false;{}loginTimeout=30;",
+            host, database, cert_host
+        );
+        let mut ret = Self::new(owner, name, &url);
+        ret.driver(MSSQL_DRIVER).auth(JdbcSourceAuth::Userpass);
+        ret
+    }
+
     pub fn auth(&mut self, auth: JdbcSourceAuth) -> &mut Self {
         match auth {
             JdbcSourceAuth::Anonymous => self.auth = Some(JdbcAuth::Anonymous),
@@ -288,6 +747,23 @@ impl JdbcSourceBuilder {
         self
     }
 
+    /**
+     * JDBC driver class, e.g. `org.postgresql.Driver`
+     */
+    pub fn driver(&mut self, class_name: &str) -> &mut Self {
+        self.driver = Some(class_name.to_string());
+        self
+    }
+
+    /**
+     * Set a JDBC connection property, e.g. `sslmode`
+     */
+    pub fn connection_property(&mut self, key: &str, value: &str) -> &mut Self {
+        self.connection_properties
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
     pub fn time_window(
         &mut self,
         timestamp_column: &str,
@@ -300,12 +776,48 @@ impl JdbcSourceBuilder {
         self
     }
 
+    /**
+     * Set an option of the Spark JDBC reader, e.g. `fetchsize` or `partitionColumn`.
+     * Properties passed to the JDBC driver itself are set with `connection_property`
+     */
+    pub fn option(&mut self, key: &str, value: &str) -> &mut Self {
+        self.options.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /**
+     * Set multiple options of the Spark JDBC reader
+     */
+    pub fn options(&mut self, options: HashMap<String, String>) -> &mut Self {
+        self.options.extend(options);
+        self
+    }
+
     pub fn preprocessing(&mut self, preprocessing: &str) -> &mut Self {
         self.preprocessing = Some(preprocessing.to_string());
         self
     }
 
+    /**
+     * Local Python file defining the preprocessing function, it's uploaded with the jobs using this source.
+     * The function can be omitted if it's the only function in the file
+     */
+    pub fn preprocessing_file(&mut self, path: &str) -> &mut Self {
+        self.preprocessing_file = Some(path.to_string());
+        self
+    }
+
+    /**
+     * Declare a column of the source, columns referenced by anchor transformations are checked against the declared ones
+     */
+    pub fn schema_column(&mut self, name: &str, value_type: ValueType) -> &mut Self {
+        self.schema.push((name.to_string(), value_type));
+        self
+    }
+
     pub async fn build(&self) -> Result<Source, Error> {
+        let (preprocessing, preprocessing_file) =
+            resolve_preprocessing(&self.name, &self.preprocessing, &self.preprocessing_file).await?;
         let imp = SourceImpl {
             id: Uuid::new_v4(),
             name: self.name.to_string(),
@@ -313,10 +825,450 @@ impl JdbcSourceBuilder {
                 url: self.url.clone(),
                 dbtable: self.dbtable.to_owned(),
                 query: self.query.to_owned(),
+                driver: self.driver.to_owned(),
+                connection_properties: self.connection_properties.to_owned(),
                 auth: self.auth.clone().unwrap_or(JdbcAuth::Anonymous),
             },
             time_window_parameters: self.time_window_parameters.clone(),
-            preprocessing: self.preprocessing.clone(),
+            options: self.options.clone(),
+            schema: self.schema.clone(),
+            time_partition_pattern: None,
+            postfix_path: None,
+            preprocessing,
+            preprocessing_file,
+            registry_tags: Default::default(),
+        };
+        self.owner.insert_source(imp).await
+    }
+}
+
+/**
+ * Snowflake credentials are not part of the source, they're passed to the Spark job via `--snowflake-config`
+ */
+pub struct SnowflakeSourceBuilder {
+    owner: Arc<RwLock<FeathrProjectImpl>>,
+    name: String,
+    url: String,
+    dbtable: Option<String>,
+    query: Option<String>,
+    options: HashMap<String, String>,
+    schema: Vec<(String, ValueType)>,
+    time_window_parameters: Option<TimeWindowParameters>,
+    preprocessing: Option<String>,
+    preprocessing_file: Option<String>,
+}
+
+impl SnowflakeSourceBuilder {
+    pub(crate) fn new(owner: Arc<RwLock<FeathrProjectImpl>>, name: &str, url: &str) -> Self {
+        Self {
+            owner,
+            name: name.to_string(),
+            url: url.to_string(),
+            dbtable: None,
+            query: None,
+            options: Default::default(),
+            schema: Default::default(),
+            time_window_parameters: None,
+            preprocessing: None,
+            preprocessing_file: None,
+        }
+    }
+
+    pub fn dbtable(&mut self, dbtable: &str) -> &mut Self {
+        self.dbtable = Some(dbtable.to_string());
+        self
+    }
+
+    pub fn query(&mut self, query: &str) -> &mut Self {
+        self.query = Some(query.to_string());
+        self
+    }
+
+    /**
+     * Set a Spark reader option of the source, e.g. `sfWarehouse`
+     */
+    pub fn option(&mut self, key: &str, value: &str) -> &mut Self {
+        self.options.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn time_window(
+        &mut self,
+        timestamp_column: &str,
+        timestamp_column_format: &str,
+    ) -> &mut Self {
+        self.time_window_parameters = Some(TimeWindowParameters {
+            timestamp_column: timestamp_column.to_string(),
+            timestamp_column_format: timestamp_column_format.to_string(),
+        });
+        self
+    }
+
+    pub fn preprocessing(&mut self, preprocessing: &str) -> &mut Self {
+        self.preprocessing = Some(preprocessing.to_string());
+        self
+    }
+
+    /**
+     * Local Python file defining the preprocessing function, it's uploaded with the jobs using this source.
+     * The function can be omitted if it's the only function in the file
+     */
+    pub fn preprocessing_file(&mut self, path: &str) -> &mut Self {
+        self.preprocessing_file = Some(path.to_string());
+        self
+    }
+
+    /**
+     * Declare a column of the source, columns referenced by anchor transformations are checked against the declared ones
+     */
+    pub fn schema_column(&mut self, name: &str, value_type: ValueType) -> &mut Self {
+        self.schema.push((name.to_string(), value_type));
+        self
+    }
+
+    pub async fn build(&self) -> Result<Source, Error> {
+        let (preprocessing, preprocessing_file) =
+            resolve_preprocessing(&self.name, &self.preprocessing, &self.preprocessing_file).await?;
+        if self.dbtable.is_none() && self.query.is_none() {
+            return Err(Error::SourceNoQuery(self.name.clone()));
+        }
+        let imp = SourceImpl {
+            id: Uuid::new_v4(),
+            name: self.name.to_string(),
+            location: SourceLocation::Snowflake {
+                type_: SnowflakeType::Snowflake,
+                url: self.url.clone(),
+                dbtable: self.dbtable.to_owned(),
+                query: self.query.to_owned(),
+            },
+            time_window_parameters: self.time_window_parameters.clone(),
+            options: self.options.clone(),
+            schema: self.schema.clone(),
+            time_partition_pattern: None,
+            postfix_path: None,
+            preprocessing,
+            preprocessing_file,
+            registry_tags: Default::default(),
+        };
+        self.owner.insert_source(imp).await
+    }
+}
+
+/**
+ * Source read by any Spark data source format, e.g. `cosmos.oltp`, with the reader options
+ */
+pub struct GenericSourceBuilder {
+    owner: Arc<RwLock<FeathrProjectImpl>>,
+    name: String,
+    format: String,
+    options: HashMap<String, String>,
+    schema: Vec<(String, ValueType)>,
+    time_window_parameters: Option<TimeWindowParameters>,
+    preprocessing: Option<String>,
+    preprocessing_file: Option<String>,
+}
+
+impl GenericSourceBuilder {
+    pub(crate) fn new(owner: Arc<RwLock<FeathrProjectImpl>>, name: &str, format: &str) -> Self {
+        Self {
+            owner,
+            name: name.to_string(),
+            format: format.to_string(),
+            options: Default::default(),
+            schema: Default::default(),
+            time_window_parameters: None,
+            preprocessing: None,
+            preprocessing_file: None,
+        }
+    }
+
+    /**
+     * Set a reader option, use `${KEY}` as the value to read it from the secret `KEY`
+     */
+    pub fn option(&mut self, key: &str, value: &str) -> &mut Self {
+        self.options.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn options(&mut self, options: HashMap<String, String>) -> &mut Self {
+        self.options.extend(options);
+        self
+    }
+
+    pub fn time_window(
+        &mut self,
+        timestamp_column: &str,
+        timestamp_column_format: &str,
+    ) -> &mut Self {
+        self.time_window_parameters = Some(TimeWindowParameters {
+            timestamp_column: timestamp_column.to_string(),
+            timestamp_column_format: timestamp_column_format.to_string(),
+        });
+        self
+    }
+
+    pub fn preprocessing(&mut self, preprocessing: &str) -> &mut Self {
+        self.preprocessing = Some(preprocessing.to_string());
+        self
+    }
+
+    /**
+     * Local Python file defining the preprocessing function, it's uploaded with the jobs using this source.
+     * The function can be omitted if it's the only function in the file
+     */
+    pub fn preprocessing_file(&mut self, path: &str) -> &mut Self {
+        self.preprocessing_file = Some(path.to_string());
+        self
+    }
+
+    /**
+     * Declare a column of the source, columns referenced by anchor transformations are checked against the declared ones
+     */
+    pub fn schema_column(&mut self, name: &str, value_type: ValueType) -> &mut Self {
+        self.schema.push((name.to_string(), value_type));
+        self
+    }
+
+    pub async fn build(&self) -> Result<Source, Error> {
+        let (preprocessing, preprocessing_file) =
+            resolve_preprocessing(&self.name, &self.preprocessing, &self.preprocessing_file).await?;
+        let imp = SourceImpl {
+            id: Uuid::new_v4(),
+            name: self.name.to_string(),
+            location: SourceLocation::generic(&self.format, self.options.clone()),
+            time_window_parameters: self.time_window_parameters.clone(),
+            options: Default::default(),
+            schema: self.schema.clone(),
+            time_partition_pattern: None,
+            postfix_path: None,
+            preprocessing,
+            preprocessing_file,
+            registry_tags: Default::default(),
+        };
+        self.owner.insert_source(imp).await
+    }
+}
+
+pub struct ElasticSourceBuilder {
+    owner: Arc<RwLock<FeathrProjectImpl>>,
+    name: String,
+    hosts: Vec<String>,
+    index: String,
+    query: Option<String>,
+    auth: ElasticSourceAuth,
+    options: HashMap<String, String>,
+    time_window_parameters: Option<TimeWindowParameters>,
+    preprocessing: Option<String>,
+    preprocessing_file: Option<String>,
+}
+
+/**
+ * Credentials are read from `<SOURCE_NAME>_USER` and `<SOURCE_NAME>_PASSWORD`, or `<SOURCE_NAME>_API_KEY`
+ */
+#[derive(Clone, Copy, Debug)]
+pub enum ElasticSourceAuth {
+    Anonymous,
+    Userpass,
+    ApiKey,
+}
+
+impl ElasticSourceBuilder {
+    pub(crate) fn new<T: ToString>(
+        owner: Arc<RwLock<FeathrProjectImpl>>,
+        name: &str,
+        hosts: &[T],
+        index: &str,
+    ) -> Self {
+        Self {
+            owner,
+            name: name.to_string(),
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            index: index.to_string(),
+            query: None,
+            auth: ElasticSourceAuth::Anonymous,
+            options: Default::default(),
+            time_window_parameters: None,
+            preprocessing: None,
+            preprocessing_file: None,
+        }
+    }
+
+    /**
+     * Elasticsearch query DSL or URI query, e.g. `?q=status:active`
+     */
+    pub fn query(&mut self, query: &str) -> &mut Self {
+        self.query = Some(query.to_string());
+        self
+    }
+
+    pub fn auth(&mut self, auth: ElasticSourceAuth) -> &mut Self {
+        self.auth = auth;
+        self
+    }
+
+    /**
+     * Set other options of the Elasticsearch connector, e.g. `es.nodes.wan.only`
+     */
+    pub fn option(&mut self, key: &str, value: &str) -> &mut Self {
+        self.options.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn time_window(
+        &mut self,
+        timestamp_column: &str,
+        timestamp_column_format: &str,
+    ) -> &mut Self {
+        self.time_window_parameters = Some(TimeWindowParameters {
+            timestamp_column: timestamp_column.to_string(),
+            timestamp_column_format: timestamp_column_format.to_string(),
+        });
+        self
+    }
+
+    pub fn preprocessing(&mut self, preprocessing: &str) -> &mut Self {
+        self.preprocessing = Some(preprocessing.to_string());
+        self
+    }
+
+    /**
+     * Local Python file defining the preprocessing function, it's uploaded with the jobs using this source.
+     * The function can be omitted if it's the only function in the file
+     */
+    pub fn preprocessing_file(&mut self, path: &str) -> &mut Self {
+        self.preprocessing_file = Some(path.to_string());
+        self
+    }
+
+    pub async fn build(&self) -> Result<Source, Error> {
+        let (preprocessing, preprocessing_file) =
+            resolve_preprocessing(&self.name, &self.preprocessing, &self.preprocessing_file).await?;
+        let mut options = self.options.clone();
+        options.insert("es.nodes".to_string(), self.hosts.join(","));
+        options.insert("es.resource".to_string(), self.index.clone());
+        if let Some(query) = &self.query {
+            options.insert("es.query".to_string(), query.clone());
+        }
+        match self.auth {
+            ElasticSourceAuth::Anonymous => {}
+            ElasticSourceAuth::Userpass => {
+                options.insert("es.net.http.auth.user".to_string(), format!("${{{}_USER}}", self.name));
+                options.insert("es.net.http.auth.pass".to_string(), format!("${{{}_PASSWORD}}", self.name));
+            }
+            ElasticSourceAuth::ApiKey => {
+                options.insert(
+                    "es.net.http.header.Authorization".to_string(),
+                    format!("${{{}_API_KEY}}", self.name),
+                );
+            }
+        }
+        let imp = SourceImpl {
+            id: Uuid::new_v4(),
+            name: self.name.to_string(),
+            location: SourceLocation::generic(ELASTICSEARCH_FORMAT, options),
+            time_window_parameters: self.time_window_parameters.clone(),
+            options: Default::default(),
+            schema: Default::default(),
+            time_partition_pattern: None,
+            postfix_path: None,
+            preprocessing,
+            preprocessing_file,
+            registry_tags: Default::default(),
+        };
+        self.owner.insert_source(imp).await
+    }
+}
+
+/**
+ * Event Hubs source read with the Azure Event Hubs Spark connector.
+ * The connection string of the hub, including `SharedAccessKey` and `EntityPath`, is a secret read from
+ * `<SOURCE_NAME>_CONNECTION_STRING`, the Spark job only resolves secrets referenced as a whole option value
+ */
+pub struct EventHubSourceBuilder {
+    owner: Arc<RwLock<FeathrProjectImpl>>,
+    name: String,
+    consumer_group: String,
+    options: HashMap<String, String>,
+    time_window_parameters: Option<TimeWindowParameters>,
+    preprocessing: Option<String>,
+    preprocessing_file: Option<String>,
+}
+
+impl EventHubSourceBuilder {
+    pub(crate) fn new(owner: Arc<RwLock<FeathrProjectImpl>>, name: &str) -> Self {
+        Self {
+            owner,
+            name: name.to_string(),
+            consumer_group: "$Default".to_string(),
+            options: Default::default(),
+            time_window_parameters: None,
+            preprocessing: None,
+            preprocessing_file: None,
+        }
+    }
+
+    /**
+     * Consumer group to read with, `$Default` if not set
+     */
+    pub fn consumer_group(&mut self, consumer_group: &str) -> &mut Self {
+        self.consumer_group = consumer_group.to_string();
+        self
+    }
+
+    /**
+     * Set other options of the Event Hubs connector, e.g. `eventhubs.startingPosition`
+     */
+    pub fn option(&mut self, key: &str, value: &str) -> &mut Self {
+        self.options.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn time_window(
+        &mut self,
+        timestamp_column: &str,
+        timestamp_column_format: &str,
+    ) -> &mut Self {
+        self.time_window_parameters = Some(TimeWindowParameters {
+            timestamp_column: timestamp_column.to_string(),
+            timestamp_column_format: timestamp_column_format.to_string(),
+        });
+        self
+    }
+
+    pub fn preprocessing(&mut self, preprocessing: &str) -> &mut Self {
+        self.preprocessing = Some(preprocessing.to_string());
+        self
+    }
+
+    /**
+     * Local Python file defining the preprocessing function, it's uploaded with the jobs using this source.
+     * The function can be omitted if it's the only function in the file
+     */
+    pub fn preprocessing_file(&mut self, path: &str) -> &mut Self {
+        self.preprocessing_file = Some(path.to_string());
+        self
+    }
+
+    pub async fn build(&self) -> Result<Source, Error> {
+        let (preprocessing, preprocessing_file) =
+            resolve_preprocessing(&self.name, &self.preprocessing, &self.preprocessing_file).await?;
+        let mut options = self.options.clone();
+        options.insert(
+            "eventhubs.connectionString".to_string(),
+            format!("${{{}_CONNECTION_STRING}}", self.name),
+        );
+        options.insert("eventhubs.consumerGroup".to_string(), self.consumer_group.clone());
+        let imp = SourceImpl {
+            id: Uuid::new_v4(),
+            name: self.name.to_string(),
+            location: SourceLocation::generic(EVENTHUBS_FORMAT, options),
+            time_window_parameters: self.time_window_parameters.clone(),
+            options: Default::default(),
+            schema: Default::default(),
+            time_partition_pattern: None,
+            postfix_path: None,
+            preprocessing,
+            preprocessing_file,
             registry_tags: Default::default(),
         };
         self.owner.insert_source(imp).await
@@ -404,7 +1356,12 @@ impl KafkaSourceBuilder {
                 },
             },
             time_window_parameters: None,
+            options: Default::default(),
+            schema: Default::default(),
+            time_partition_pattern: None,
+            postfix_path: None,
             preprocessing: None,
+            preprocessing_file: None,
             registry_tags: Default::default(),
         };
         self.owner.insert_source(imp).await
@@ -0,0 +1,8 @@
+/**
+ * Databricks DBFS client, public with the `dbfs` feature
+ */
+#[cfg(feature = "dbfs")]
+pub mod dbfs;
+#[cfg(not(feature = "dbfs"))]
+#[allow(dead_code)]
+pub(crate) mod dbfs;
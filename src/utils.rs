@@ -14,6 +14,10 @@ where
     fn from_str<T>(s: T) -> Result<Self, Error>
     where
         T: AsRef<str>;
+    /**
+     * HOCON duration string, e.g. `90s` or `1d`
+     */
+    fn to_hocon_string(&self) -> String;
 }
 
 impl ExtDuration for std::time::Duration {
@@ -36,6 +40,14 @@ impl ExtDuration for std::time::Duration {
         Ok(str_to_dur(s.as_ref())?.to_std()
             .map_err(|_| Error::DurationError(s.as_ref().to_owned()))?)
     }
+
+    fn to_hocon_string(&self) -> String {
+        match Duration::from_std(*self) {
+            Ok(d) if d.num_nanoseconds().is_some() => dur_to_string(d),
+            // Too long to be represented in nanoseconds
+            _ => format!("{}s", self.as_secs()),
+        }
+    }
 }
 
 impl ExtDuration for chrono::Duration {
@@ -57,6 +69,118 @@ impl ExtDuration for chrono::Duration {
     {
         str_to_dur(s.as_ref())
     }
+
+    fn to_hocon_string(&self) -> String {
+        dur_to_string(*self)
+    }
+}
+
+/**
+ * Durations are `chrono::Duration` in public APIs, APIs taking `IntoDuration` also accept
+ * `std::time::Duration` and HOCON duration strings, e.g. `"7d"`
+ */
+pub trait IntoDuration {
+    fn into_duration(self) -> Result<Duration, Error>;
+}
+
+impl IntoDuration for Duration {
+    fn into_duration(self) -> Result<Duration, Error> {
+        Ok(self)
+    }
+}
+
+impl IntoDuration for std::time::Duration {
+    fn into_duration(self) -> Result<Duration, Error> {
+        Duration::from_std(self).map_err(|_| Error::DurationError(format!("{:?}", self)))
+    }
+}
+
+impl IntoDuration for &str {
+    fn into_duration(self) -> Result<Duration, Error> {
+        str_to_dur(self)
+    }
+}
+
+impl IntoDuration for String {
+    fn into_duration(self) -> Result<Duration, Error> {
+        str_to_dur(&self)
+    }
+}
+
+impl IntoDuration for &String {
+    fn into_duration(self) -> Result<Duration, Error> {
+        str_to_dur(self)
+    }
+}
+
+/**
+ * Serde support of `chrono::Duration` as HOCON duration string, use with `#[serde(with = "feathr::hocon_duration")]`,
+ * numbers are taken as milliseconds when deserializing
+ */
+pub mod hocon_duration {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{dur_to_string, str_to_dur};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StrOrNum {
+        Str(String),
+        Num(i64),
+    }
+
+    impl StrOrNum {
+        fn into_duration<E: serde::de::Error>(self) -> Result<Duration, E> {
+            match self {
+                StrOrNum::Str(s) => str_to_dur(&s).map_err(|e| E::custom(e.to_string())),
+                StrOrNum::Num(ms) => Ok(Duration::milliseconds(ms)),
+            }
+        }
+    }
+
+    pub fn serialize<S>(d: &Duration, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.serialize_str(&dur_to_string(*d))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        StrOrNum::deserialize(deserializer)?.into_duration()
+    }
+
+    /**
+     * For `Option<chrono::Duration>`, use with `#[serde(with = "feathr::hocon_duration::option", default)]`
+     */
+    pub mod option {
+        use chrono::Duration;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        use super::{dur_to_string, StrOrNum};
+
+        pub fn serialize<S>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match d {
+                Some(d) => s.serialize_str(&dur_to_string(*d)),
+                None => s.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Option::<StrOrNum>::deserialize(deserializer)?
+                .map(|v| v.into_duration())
+                .transpose()
+        }
+    }
 }
 
 pub(crate) fn str_to_dur(s: &str) -> Result<Duration, Error> {
@@ -93,6 +217,23 @@ pub(crate) fn str_to_dur(s: &str) -> Result<Duration, Error> {
     }
 }
 
+/**
+ * Negative durations cannot be used as timeouts or waits, they're rejected instead of being taken as zero
+ */
+pub(crate) fn to_std_duration(d: Duration) -> Result<std::time::Duration, Error> {
+    d.to_std().map_err(|_| Error::DurationError(d.to_string()))
+}
+
+/**
+ * Redis expires keys in whole seconds, TTLs shorter than 1 second would expire the keys on write
+ */
+pub(crate) fn ttl_seconds(d: Duration) -> Result<i64, Error> {
+    match d.num_seconds() {
+        s if s >= 1 => Ok(s),
+        _ => Err(Error::InvalidTtl(d.to_string())),
+    }
+}
+
 pub(crate) fn dur_to_string(d: Duration) -> String {
     if (d.num_nanoseconds().unwrap() % 1000) != 0 {
         format!("{}ns", d.num_nanoseconds().unwrap())
@@ -115,7 +256,10 @@ pub(crate) fn dur_to_string(d: Duration) -> String {
 mod tests {
     use chrono::Duration;
 
+    use serde::{Deserialize, Serialize};
+
     use crate::utils::str_to_dur;
+    use crate::{ExtDuration, IntoDuration};
 
     use super::dur_to_string;
 
@@ -147,4 +291,36 @@ mod tests {
         assert_eq!(dur_to_string(Duration::seconds(986400)), "274h");
         assert_eq!(dur_to_string(Duration::seconds(86400)), "1d");
     }
+
+    #[test]
+    fn into_duration() {
+        assert_eq!("7d".into_duration().unwrap(), Duration::days(7));
+        assert_eq!(
+            std::time::Duration::from_secs(90).into_duration().unwrap(),
+            Duration::seconds(90)
+        );
+        assert!("7 days".to_string().into_duration().is_err());
+        assert_eq!(std::time::Duration::from_secs(3600).to_hocon_string(), "1h");
+    }
+
+    #[test]
+    fn hocon_duration_serde() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Settings {
+            #[serde(with = "crate::hocon_duration")]
+            window: Duration,
+            #[serde(with = "crate::hocon_duration::option", default)]
+            timeout: Option<Duration>,
+        }
+        let s: Settings = serde_json::from_str(r#"{"window": "3d", "timeout": 1500}"#).unwrap();
+        assert_eq!(s.window, Duration::days(3));
+        assert_eq!(s.timeout, Some(Duration::milliseconds(1500)));
+        assert_eq!(
+            serde_json::to_string(&s).unwrap(),
+            r#"{"window":"3d","timeout":"1500ms"}"#
+        );
+        let s: Settings = serde_json::from_str(r#"{"window": "1h"}"#).unwrap();
+        assert_eq!(s.timeout, None);
+        assert!(serde_json::from_str::<Settings>(r#"{"window": "1 hour"}"#).is_err());
+    }
 }
@@ -10,6 +10,8 @@ use log::{debug, warn};
 
 use crate::Logged;
 
+const PROFILE_ENV_VAR: &str = "FEATHR_PROFILE";
+
 #[async_trait]
 pub trait VarSource: Sync + Send + std::fmt::Debug {
     async fn get_environment_variable(&self, name: &[&str]) -> Result<String, crate::Error>;
@@ -29,6 +31,7 @@ impl VarSource for EnvVarSource {
 #[derive(Debug, Clone)]
 struct YamlSource {
     root: serde_yaml::Value,
+    profile: Option<String>,
     overlay: EnvVarSource,
     kv_overlay: Option<KeyVaultSource>,
 }
@@ -42,11 +45,44 @@ impl YamlSource {
         let root = serde_yaml::from_reader(f)?;
         Ok(Self {
             root,
+            profile: None,
             overlay: EnvVarSource,
             kv_overlay: KeyVaultSource::from_env().ok(),
         })
     }
 
+    /**
+     * Select the named profile under `profiles`, falls back to the `FEATHR_PROFILE` environment variable if `profile` is `None`
+     */
+    fn with_profile(mut self, profile: Option<&str>) -> Self {
+        self.profile = profile
+            .map(ToString::to_string)
+            .or_else(|| std::env::var(PROFILE_ENV_VAR).ok())
+            .filter(|p| !p.is_empty());
+        if let Some(p) = &self.profile {
+            if self.get_value_by_path(&self.root, &["profiles", p]).is_err() {
+                warn!("Profile `{}` is not defined in Feathr config, using default settings.", p);
+            }
+        }
+        self
+    }
+
+    /**
+     * Values in the selected profile override the ones at the top level
+     */
+    fn get_value(&self, name: &[&str]) -> Result<String, crate::Error> {
+        if let Some(p) = &self.profile {
+            let path: Vec<&str> = ["profiles", p.as_str()]
+                .into_iter()
+                .chain(name.iter().copied())
+                .collect();
+            if let Ok(v) = self.get_value_by_path(&self.root, &path) {
+                return Ok(v);
+            }
+        }
+        self.get_value_by_path(&self.root, name)
+    }
+
     fn get_value_by_path<T>(
         &self,
         node: &serde_yaml::Value,
@@ -87,9 +123,11 @@ impl FromStr for YamlSource {
         let root = serde_yaml::from_slice(s.as_bytes())?;
         Ok(Self {
             root,
+            profile: None,
             overlay: EnvVarSource,
             kv_overlay: KeyVaultSource::from_env().ok(),
-        })
+        }
+        .with_profile(None))
     }
 }
 
@@ -101,9 +139,9 @@ impl VarSource for YamlSource {
             Err(_) => match &self.kv_overlay {
                 Some(kv) => match kv.get_environment_variable(name).await {
                     Ok(v) => Ok(v),
-                    Err(_) => self.get_value_by_path(&self.root, name),
+                    Err(_) => self.get_value(name),
                 },
-                None => self.get_value_by_path(&self.root, name),
+                None => self.get_value(name),
             },
         }
     }
@@ -164,6 +202,19 @@ where
 }
 
 pub fn load_var_source<T>(conf_file: T) -> Arc<dyn VarSource + Send + Sync>
+where
+    T: AsRef<Path>,
+{
+    load_var_source_with_profile(conf_file, None)
+}
+
+/**
+ * Load config file with the named profile selected, `None` means the profile set by `FEATHR_PROFILE` environment variable, if any.
+ */
+pub fn load_var_source_with_profile<T>(
+    conf_file: T,
+    profile: Option<&str>,
+) -> Arc<dyn VarSource + Send + Sync>
 where
     T: AsRef<Path>,
 {
@@ -171,7 +222,7 @@ where
         "Loading Feathr config file `{}`",
         conf_file.as_ref().display()
     );
-    match YamlSource::load(conf_file.as_ref()) {
+    match YamlSource::load(conf_file.as_ref()).map(|s| s.with_profile(profile)) {
         Ok(src) => {
             debug!(
                 "Feathr config file `{}` loaded",
@@ -196,7 +247,7 @@ pub fn default_var_source() -> Arc<dyn VarSource> {
         .into();
     debug!("Loading Feathr config file `{}`", conf_file.display());
 
-    match YamlSource::load(&conf_file) {
+    match YamlSource::load(&conf_file).map(|s| s.with_profile(None)) {
         Ok(src) => {
             debug!("Feathr config file `{}` loaded", conf_file.display());
             Arc::new(src)
@@ -226,4 +277,32 @@ mod tests {
             "project_feathr_integration_test"
         );
     }
+
+    #[tokio::test]
+    async fn profile_override() {
+        let content = r#"
+spark_config:
+  spark_cluster: 'databricks'
+  spark_result_output_parts: '1'
+profiles:
+  prod:
+    spark_config:
+      spark_cluster: 'azure_synapse'
+"#;
+        let y = YamlSource::from_str(content)
+            .unwrap()
+            .with_profile(Some("prod"));
+        assert_eq!(
+            y.get_environment_variable(&["spark_config", "spark_cluster"])
+                .await
+                .unwrap(),
+            "azure_synapse"
+        );
+        assert_eq!(
+            y.get_environment_variable(&["spark_config", "spark_result_output_parts"])
+                .await
+                .unwrap(),
+            "1"
+        );
+    }
 }